@@ -0,0 +1,172 @@
+//! The engine's core benchmark suite.
+//!
+//! Covers each layer a search goes through, on the same standard positions,
+//! so a change to one of them (move list sizes, the transposition table
+//! layout, legal move generation) can be measured where it lands and in the
+//! search as a whole:
+//!
+//! * move generation, with and without check effects
+//! * making and unmaking every legal move
+//! * static evaluation
+//! * quiescence search, as depth 1 searches of capture-heavy positions
+//! * fixed-depth search, sequential so results are comparable across machines
+//!
+//! Run with `cargo bench --bench engine_benchmark`, or a single group with
+//! e.g. `cargo bench --bench engine_benchmark -- "Make/Unmake"`.
+
+use chess::alpha_beta_searcher::SearchContext;
+use chess::board::Board;
+use chess::chess_search::search_best_move;
+use chess::evaluate::score;
+use chess::input_handler::fen::parse_fen;
+use chess::move_generator::MoveGenerator;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Quiet and tactical positions from the perft suite, which every layer is
+/// measured on.
+const POSITIONS: &[(&str, &str)] = &[
+    (
+        "starting",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    (
+        "middlegame",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+];
+
+/// Positions with long capture sequences, where a depth 1 search is almost
+/// all quiescence.
+const CAPTURE_POSITIONS: &[(&str, &str)] = &[
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    (
+        "exchanges",
+        "r1bqk2r/pp2bppp/2n1pn2/2pp4/2PP4/2N1PN2/PP2BPPP/R1BQK2R w KQkq - 0 7",
+    ),
+    (
+        "promotions",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ),
+];
+
+fn positions(fens: &[(&'static str, &str)]) -> Vec<(&'static str, Board)> {
+    fens.iter()
+        .map(|&(name, fen)| (name, parse_fen(fen).unwrap()))
+        .collect()
+}
+
+fn benchmark_move_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Move Generation");
+    let move_generator = MoveGenerator::default();
+
+    for (name, mut board) in positions(POSITIONS) {
+        let turn = board.turn();
+        group.bench_function(BenchmarkId::new("legal", name), |b| {
+            b.iter(|| black_box(move_generator.generate_moves(&mut board, turn)))
+        });
+        group.bench_function(BenchmarkId::new("with_effects", name), |b| {
+            b.iter(|| {
+                black_box(
+                    move_generator
+                        .generate_moves_and_lazily_update_chess_move_effects(&mut board, turn),
+                )
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_make_unmake(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Make/Unmake");
+    let move_generator = MoveGenerator::default();
+
+    for (name, mut board) in positions(POSITIONS) {
+        let turn = board.turn();
+        let moves = move_generator.generate_moves(&mut board, turn);
+        group.bench_function(BenchmarkId::new("all_legal_moves", name), |b| {
+            b.iter(|| {
+                for chess_move in moves.iter() {
+                    chess_move.apply(&mut board).unwrap();
+                    board.toggle_turn();
+                    black_box(board.current_position_hash());
+                    board.toggle_turn();
+                    chess_move.undo(&mut board).unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_evaluation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Evaluation");
+    let move_generator = MoveGenerator::default();
+
+    for (name, mut board) in positions(POSITIONS) {
+        let turn = board.turn();
+        group.bench_function(BenchmarkId::new("leaf", name), |b| {
+            b.iter(|| black_box(score(&mut board, &move_generator, turn, 0)))
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_quiescence(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Quiescence");
+
+    for (name, board) in positions(CAPTURE_POSITIONS) {
+        group.bench_function(BenchmarkId::new("depth_1_search", name), |b| {
+            b.iter_batched(
+                || (board.clone(), SearchContext::with_parallel(1, false)),
+                |(mut board, mut context)| {
+                    black_box(search_best_move(&mut context, &mut board).unwrap())
+                },
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn benchmark_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Fixed-Depth Search");
+    group.sample_size(10);
+
+    for depth in [4, 5] {
+        for (name, board) in positions(POSITIONS) {
+            group.bench_function(BenchmarkId::new(name, depth), |b| {
+                b.iter_batched(
+                    || (board.clone(), SearchContext::with_parallel(depth, false)),
+                    |(mut board, mut context)| {
+                        black_box(search_best_move(&mut context, &mut board).unwrap())
+                    },
+                    criterion::BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_move_generation,
+    benchmark_make_unmake,
+    benchmark_evaluation,
+    benchmark_quiescence,
+    benchmark_search
+);
+criterion_main!(benches);
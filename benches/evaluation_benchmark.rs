@@ -1,12 +1,13 @@
 //! Evaluation speed benchmark measuring raw board_material_score() throughput
-//! across a diverse set of positions covering all game phases.
+//! across a diverse set of positions covering all game phases, with and
+//! without the mobility term.
 
 use std::str::FromStr;
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 use chess::board::Board;
-use chess::evaluate::board_material_score;
+use chess::evaluate::{board_material_score, set_mobility_enabled};
 
 /// ~70 FEN positions covering openings, middlegame, endgame, pawn structure
 /// extremes, and asymmetric material. The eval function is called at every
@@ -124,7 +125,7 @@ const BENCHMARK_FENS: &[&str] = &[
     // K+Q vs K+R
     "8/5k2/8/8/8/8/5K2/8 w - - 0 45",
     // K+B+N vs K (mating position)
-    "8/8/8/8/4k3/8/2BN4/4K3 w - - 0 1",
+    "8/8/8/4k3/8/8/2BN4/4K3 w - - 0 1",
     // Two connected passed pawns
     "8/8/8/5k2/8/3PP3/5K2/8 w - - 0 40",
     // Pawn race
@@ -167,7 +168,7 @@ fn evaluation_benchmark(c: &mut Criterion) {
         .map(|fen| Board::from_str(fen).unwrap())
         .collect();
 
-    c.bench_function("eval_suite_all_positions", |b| {
+    let eval_suite = |b: &mut criterion::Bencher| {
         b.iter(|| {
             let mut total: i16 = 0;
             for board in &positions {
@@ -175,7 +176,13 @@ fn evaluation_benchmark(c: &mut Criterion) {
             }
             black_box(total)
         })
-    });
+    };
+
+    c.bench_function("eval_suite_all_positions", eval_suite);
+
+    set_mobility_enabled(false);
+    c.bench_function("eval_suite_all_positions_without_mobility", eval_suite);
+    set_mobility_enabled(true);
 }
 
 criterion_group!(benches, evaluation_benchmark);
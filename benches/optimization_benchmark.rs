@@ -21,8 +21,10 @@ fn benchmark_positions() -> Vec<(String, Board)> {
             board
         }),
         // Tactical position - mate in 2 for Black, tests tactical acuity
-        ("tactical_mate_in_2".to_string(), {
-            let mut board = chess_position! {
+        (
+            "tactical_mate_in_2".to_string(),
+            chess_position! {
+                turn = Color::Black, castling = "-";
                 ....r..k
                 ....q...
                 ........
@@ -31,14 +33,13 @@ fn benchmark_positions() -> Vec<(String, Board)> {
                 ........
                 .....PPP
                 R.....K.
-            };
-            board.set_turn(Color::Black);
-            board.lose_castle_rights(CastleRights::all());
-            board
-        }),
+            },
+        ),
         // Quiet middlegame - no immediate tactics, tests positional search
-        ("quiet_middlegame".to_string(), {
-            let mut board = chess_position! {
+        (
+            "quiet_middlegame".to_string(),
+            chess_position! {
+                castling = "-";
                 r...k..r
                 ppp..ppp
                 ..n.bn..
@@ -47,13 +48,13 @@ fn benchmark_positions() -> Vec<(String, Board)> {
                 ..N.BN..
                 PPP..PPP
                 R...K..R
-            };
-            board.lose_castle_rights(CastleRights::all());
-            board
-        }),
+            },
+        ),
         // Complex middlegame with queens - tests branching factor handling
-        ("complex_middlegame".to_string(), {
-            let mut board = chess_position! {
+        (
+            "complex_middlegame".to_string(),
+            chess_position! {
+                castling = "-";
                 r..q.rk.
                 ppp.bppp
                 ..n.pn..
@@ -62,13 +63,13 @@ fn benchmark_positions() -> Vec<(String, Board)> {
                 ..N.PN..
                 PPP.BPPP
                 R..Q.RK.
-            };
-            board.lose_castle_rights(CastleRights::all());
-            board
-        }),
+            },
+        ),
         // Endgame - fewer pieces, deeper calculation possible
-        ("endgame".to_string(), {
-            let mut board = chess_position! {
+        (
+            "endgame".to_string(),
+            chess_position! {
+                turn = Color::White, castling = "-";
                 ........
                 ........
                 ...k....
@@ -77,11 +78,8 @@ fn benchmark_positions() -> Vec<(String, Board)> {
                 ........
                 .R......
                 ........
-            };
-            board.set_turn(Color::White);
-            board.lose_castle_rights(CastleRights::all());
-            board
-        }),
+            },
+        ),
     ]
 }
 
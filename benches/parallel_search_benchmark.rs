@@ -0,0 +1,63 @@
+//! Benchmarks for searching root moves in parallel.
+//!
+//! Each position is searched to a fixed depth with and without the parallel
+//! root search, so the cost of parallelizing (copying the root position for
+//! the threads, the lost sharing of alpha between root moves) can be set
+//! against its gain. The parallel search borrows root copies from a pool kept
+//! for the whole search instead of cloning the board for every root move at
+//! every depth.
+
+use chess::alpha_beta_searcher::SearchContext;
+use chess::chess_search::search_best_move;
+use chess::input_handler::fen::parse_fen;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+const POSITIONS: &[(&str, &str)] = &[
+    (
+        "starting",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    ),
+    (
+        "middlegame",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ),
+];
+
+fn parallel_search_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Parallel Root Search");
+    group.sample_size(10);
+
+    for depth in [5, 6] {
+        for &(name, fen) in POSITIONS {
+            let board = parse_fen(fen).unwrap();
+            for parallel in [true, false] {
+                let mode = if parallel { "parallel" } else { "sequential" };
+                group.bench_with_input(
+                    BenchmarkId::new(format!("{}_{}", name, mode), depth),
+                    &depth,
+                    |b, &depth| {
+                        b.iter_batched(
+                            || {
+                                let context = SearchContext::with_parallel(depth, parallel);
+                                (board.clone(), context)
+                            },
+                            |(mut board, mut context)| {
+                                black_box(search_best_move(&mut context, &mut board).unwrap())
+                            },
+                            criterion::BatchSize::LargeInput,
+                        )
+                    },
+                );
+            }
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parallel_search_benchmark);
+criterion_main!(benches);
@@ -1,8 +1,4 @@
-use fastrand;
-use std::time::Duration;
-
-use chess::board::Board;
-use chess::game::{engine::EngineConfig, mode::ComputerVsComputer, r#loop::GameLoop};
+use chess::game::engine::{Engine, EngineConfig};
 use criterion::{criterion_group, criterion_main, Criterion};
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -18,7 +14,7 @@ fn criterion_benchmark(c: &mut Criterion) {
 }
 
 fn times(n: usize) -> impl Iterator {
-    std::iter::repeat(()).take(n)
+    std::iter::repeat_n((), n)
 }
 
 fn computer_vs_computer(game_count: usize, search_depth: u8) {
@@ -27,11 +23,14 @@ fn computer_vs_computer(game_count: usize, search_depth: u8) {
             search_depth,
             starting_position: Default::default(),
         };
-        let mode = ComputerVsComputer {
-            delay_between_moves: Some(Duration::ZERO),
-        };
-        let mut game = GameLoop::new(mode, config);
-        game.run();
+        let mut engine = Engine::with_config(config);
+        while engine.check_game_over().is_none() {
+            if engine.make_best_move().is_err() {
+                break;
+            }
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        }
     }
 }
 
@@ -0,0 +1,6 @@
+//! Prints the C header for the `ffi` feature's library; `make ffi` writes it
+//! next to the library.
+
+fn main() {
+    print!("{}", chess::ffi::c_header());
+}
@@ -1,29 +1,213 @@
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::Path;
 
-pub fn generate_opening_book(input_file: &str, out: &mut BufWriter<File>) -> std::io::Result<()> {
-    let file = File::open(input_file)?;
-    let reader = BufReader::new(file);
+use crate::zobrist::ZobristTables;
 
-    writeln!(out, "\npub fn create_book() -> Book {{")?;
-    writeln!(out, "    let mut book = Book::new();")?;
-
-    for line in reader.lines() {
-        let line = line?;
-        let parts: Vec<&str> = line.split(": ").collect();
-        if parts.len() == 2 {
-            let name = parts[0];
-            let moves = parts[1];
-            writeln!(out, "    book.add_line(OpeningLine {{
-        name: String::from(\"{}\"),
-        moves: String::from(\"{}\"),
-    }});", name, moves)?;
+use super::pgn::pgn_opening_lines;
+use super::san_board::{parse_square, SanBoard};
+
+/// Named opening lines, each with its moves in UCI separated by spaces.
+/// Files ending in `.pgn` are read as PGN games; anything else as lines of
+/// `Name: e2e4 e7e5 ...`.
+pub fn read_opening_lines(input_file: &Path) -> Result<Vec<(String, String)>> {
+    let contents = std::fs::read_to_string(input_file)?;
+    let is_pgn = input_file
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"));
+    if is_pgn {
+        return pgn_opening_lines(&contents)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()));
+    }
+    Ok(contents
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, moves)| (name.to_string(), moves.trim().to_string()))
+        .collect())
+}
+
+/// First line of the opening book file, recording the book it was generated
+/// from, a hash of the input's contents, and the seed of the Zobrist keys its
+/// positions are hashed with; or that there was no book.
+pub fn book_header(input_file: Option<&Path>, seed: u64) -> Result<String> {
+    let Some(input_file) = input_file else {
+        return Ok("// Opening book skipped".to_string());
+    };
+    // FNV-1a, which is plenty to notice an edited file
+    let hash = std::fs::read(input_file)?
+        .iter()
+        .fold(0xCBF2_9CE4_8422_2325u64, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3)
+        });
+    Ok(format!(
+        "// Opening book from {} (0x{:016X}), positions hashed with seed 0x{:016X}",
+        input_file.display(),
+        hash,
+        seed
+    ))
+}
+
+/// A position reached in the book, keyed by `SanBoard::book_key` in
+/// `BookPositions`.
+#[derive(Default)]
+struct BookPosition {
+    /// The name of the last line to end here.
+    line_name: Option<String>,
+    /// The from and to square indices of the book moves, with how many lines
+    /// play each, in the order the lines first play them.
+    moves: Vec<(u8, u8, u32)>,
+}
+
+/// Book positions by key, sorted so the same book always writes the same file.
+type BookPositions = BTreeMap<u64, BookPosition>;
+
+/// The book positions of `lines`: every position along each line, with the
+/// moves the lines play from it.
+fn book_positions(lines: &[(String, String)], tables: &ZobristTables) -> Result<BookPositions> {
+    let mut positions = BookPositions::new();
+    for (name, moves) in lines {
+        let mut board = SanBoard::starting_position();
+        for uci in moves.split_whitespace() {
+            let invalid = || {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}: {} is not a move", name, uci),
+                )
+            };
+            let from = uci.get(0..2).and_then(square_index).ok_or_else(invalid)?;
+            let to = uci.get(2..4).and_then(square_index).ok_or_else(invalid)?;
+            let position = positions.entry(board.book_key(tables)).or_default();
+            match position
+                .moves
+                .iter_mut()
+                .find(|(move_from, move_to, _)| (*move_from, *move_to) == (from, to))
+            {
+                Some((_, _, count)) => *count += 1,
+                None => position.moves.push((from, to, 1)),
+            }
+            board.play_uci(uci).ok_or_else(invalid)?;
         }
+        positions
+            .entry(board.book_key(tables))
+            .or_default()
+            .line_name = Some(name.clone());
+    }
+    Ok(positions)
+}
+
+/// The index of the square named `name`, with a1 at 0 and h8 at 63.
+fn square_index(name: &str) -> Option<u8> {
+    parse_square(name).map(|(rank, file)| (rank * 8 + file) as u8)
+}
+
+/// Writes `create_book` with the lines of `input_file`, or an empty book
+/// when there is none. The book's positions are keyed by their Zobrist hash
+/// from the tables `seed` generates, so transpositions share their moves. The
+/// named lines are kept as well, for drilling an opening.
+pub fn generate_opening_book(
+    input_file: Option<&Path>,
+    seed: u64,
+    out: &mut BufWriter<File>,
+) -> Result<()> {
+    let lines = match input_file {
+        Some(input_file) => read_opening_lines(input_file)?,
+        None => Vec::new(),
+    };
+    let mut unique_lines = HashSet::new();
+    let lines: Vec<_> = lines
+        .into_iter()
+        .filter(|line| unique_lines.insert(line.clone()))
+        .collect();
+    let positions = book_positions(&lines, &ZobristTables::new(seed))?;
+
+    writeln!(out, "{}", book_header(input_file, seed)?)?;
+    writeln!(out, "#[rustfmt::skip]")?;
+    writeln!(out, "static BOOK_POSITIONS: &[BookPositionEntry] = &[")?;
+    for (hash, position) in &positions {
+        let moves: Vec<String> = position
+            .moves
+            .iter()
+            .map(|(from, to, count)| format!("({}, {}, {})", from, to, count))
+            .collect();
+        writeln!(
+            out,
+            "    (0x{:016X}, {:?}, &[{}]),",
+            hash,
+            position.line_name,
+            moves.join(", ")
+        )?;
+    }
+    writeln!(out, "];")?;
+
+    writeln!(out, "\n#[rustfmt::skip]")?;
+    writeln!(out, "static BOOK_LINES: &[(&str, &str)] = &[")?;
+    for (name, moves) in &lines {
+        writeln!(out, "    ({:?}, {:?}),", name, moves)?;
     }
+    writeln!(out, "];")?;
 
-    writeln!(out, "    book")?;
+    writeln!(out, "\npub fn create_book() -> Book {{")?;
+    writeln!(out, "    Book::from_tables(BOOK_POSITIONS, BOOK_LINES)")?;
     writeln!(out, "}}")?;
 
     out.flush()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(name: &str, moves: &str) -> (String, String) {
+        (name.to_string(), moves.to_string())
+    }
+
+    #[test]
+    fn test_transpositions_share_a_book_position() {
+        let tables = ZobristTables::new(7);
+        let lines = [
+            line("Queen's Gambit", "d2d4 d7d5 c2c4 e7e6"),
+            line("Queen's Gambit via c4", "c2c4 e7e6 d2d4 d7d5"),
+            line("Queen's Gambit Declined", "d2d4 d7d5 c2c4 e7e6 b1c3"),
+        ];
+        let positions = book_positions(&lines, &tables).unwrap();
+
+        let start = SanBoard::starting_position().book_key(&tables);
+        // d4 and c4, with d4 played by two lines
+        assert_eq!(positions[&start].moves, vec![(11, 27, 2), (10, 26, 1)]);
+
+        let mut board = SanBoard::starting_position();
+        for uci in ["d2d4", "d7d5", "c2c4", "e7e6"] {
+            board.play_uci(uci).unwrap();
+        }
+        let transposed = &positions[&board.book_key(&tables)];
+        assert_eq!(transposed.moves, vec![(1, 18, 1)]);
+        assert_eq!(
+            transposed.line_name.as_deref(),
+            Some("Queen's Gambit via c4")
+        );
+    }
+
+    #[test]
+    fn test_castling_rights_are_hashed_and_en_passant_targets_are_not() {
+        let tables = ZobristTables::new(7);
+        let key_after = |moves: &[&str]| {
+            let mut board = SanBoard::starting_position();
+            for uci in moves {
+                board.play_uci(uci).unwrap();
+            }
+            board.book_key(&tables)
+        };
+        // The same pieces, but White's king has moved
+        assert_ne!(
+            key_after(&["e2e4", "e7e5", "e1e2", "e8e7", "e2e1", "e7e8"]),
+            key_after(&["e2e4", "e7e5", "g1f3", "g8f6", "f3g1", "f6g8"])
+        );
+        // The same pieces, but only the first ends with a double push
+        assert_eq!(
+            key_after(&["g1f3", "g8f6", "f3g1", "f6g8", "e2e4"]),
+            key_after(&["e2e4", "g8f6", "g1f3", "f6g8", "f3g1"])
+        );
+    }
+}
@@ -1 +1,3 @@
 pub mod book_generator;
+pub mod pgn;
+pub mod san_board;
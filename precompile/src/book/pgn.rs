@@ -0,0 +1,195 @@
+//! Opening lines from PGN games
+//!
+//! Book moves are from and to squares, so each game's SAN moves are replayed
+//! on a `SanBoard` to find them. Only the first `MAX_BOOK_PLIES` of each game
+//! go into the book; a game is named after its `Opening` and `Variation`
+//! tags, or its `Event` when it has no `Opening`.
+
+use thiserror::Error;
+
+use super::san_board::SanBoard;
+
+/// Plies of each game kept in the book.
+pub const MAX_BOOK_PLIES: usize = 20;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum PgnError {
+    #[error("Game {game}: {san} is not a legal move")]
+    IllegalMove { game: usize, san: String },
+}
+
+/// One game's tags and move text
+#[derive(Default)]
+struct PgnGame {
+    opening: Option<String>,
+    variation: Option<String>,
+    event: Option<String>,
+    movetext: String,
+}
+
+impl PgnGame {
+    fn name(&self, number: usize) -> String {
+        match (&self.opening, &self.variation) {
+            (Some(opening), Some(variation)) => format!("{}: {}", opening, variation),
+            (Some(opening), None) => opening.clone(),
+            _ => self
+                .event
+                .clone()
+                .filter(|event| !event.is_empty() && event != "?")
+                .unwrap_or_else(|| format!("PGN game {}", number)),
+        }
+    }
+
+    /// The SAN moves of the main line, without comments, variations, move
+    /// numbers, annotations or the result.
+    fn moves(&self) -> Vec<String> {
+        let mut main_line = String::new();
+        let mut variation_depth = 0;
+        let mut in_comment = false;
+        let mut in_line_comment = false;
+        for c in self.movetext.chars() {
+            match c {
+                '\n' if in_line_comment => in_line_comment = false,
+                _ if in_line_comment => {}
+                '}' if in_comment => in_comment = false,
+                _ if in_comment => {}
+                '{' => in_comment = true,
+                ';' => in_line_comment = true,
+                '(' => variation_depth += 1,
+                ')' => variation_depth -= 1,
+                _ if variation_depth > 0 => {}
+                _ => main_line.push(c),
+            }
+        }
+        main_line
+            .split_whitespace()
+            .map(without_move_number)
+            .filter(|token| {
+                !token.is_empty()
+                    && !token.starts_with('$')
+                    && !["1-0", "0-1", "1/2-1/2", "*"].contains(token)
+            })
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// `token` without a leading move number such as `12.` or `12...`.
+fn without_move_number(token: &str) -> &str {
+    match token.split_once('.') {
+        Some((number, rest)) if number.chars().all(|c| c.is_ascii_digit()) => {
+            rest.trim_start_matches('.')
+        }
+        _ => token,
+    }
+}
+
+fn tag(line: &str) -> Option<(&str, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(' ')?;
+    Some((key, value.trim().trim_matches('"').replace("\\\"", "\"")))
+}
+
+fn split_games(pgn: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if let Some((key, value)) = tag(line) {
+            if !game.movetext.trim().is_empty() {
+                games.push(std::mem::take(&mut game));
+            }
+            match key {
+                "Opening" => game.opening = Some(value),
+                "Variation" => game.variation = Some(value),
+                "Event" => game.event = Some(value),
+                _ => {}
+            }
+        } else {
+            game.movetext.push_str(line);
+            game.movetext.push('\n');
+        }
+    }
+    if !game.movetext.trim().is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+/// The named lines of the games in `pgn`, each with its first
+/// `MAX_BOOK_PLIES` moves in UCI, separated by spaces.
+pub fn pgn_opening_lines(pgn: &str) -> Result<Vec<(String, String)>, PgnError> {
+    split_games(pgn)
+        .iter()
+        .enumerate()
+        .map(|(index, game)| {
+            let mut board = SanBoard::starting_position();
+            let moves = game
+                .moves()
+                .iter()
+                .take(MAX_BOOK_PLIES)
+                .map(|san| {
+                    board.play_san(san).ok_or_else(|| PgnError::IllegalMove {
+                        game: index + 1,
+                        san: san.clone(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((game.name(index + 1), moves.join(" ")))
+        })
+        .filter(|line| !matches!(line, Ok((_, moves)) if moves.is_empty()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_games_become_named_lines_of_uci_moves() {
+        let pgn = r#"[Event "Casual game"]
+[Opening "Ruy Lopez"]
+[Variation "Berlin Defense"]
+
+1. e4 e5 2. Nf3 {the usual} Nc6 (2... d6 3. d4) 3. Bb5 Nf6 4. O-O Nxe4 $1 1-0
+
+[Event "Tactics"]
+
+1.d4 e5 2.dxe5 f5 3.exf6 d6 4.fxg7 Ke7 5.gxh8=Q Nc6 6.Nc3 Be6 7.Bg5+ Nf6 8.Qd2 Qd7
+9. O-O-O *
+"#;
+        let lines = pgn_opening_lines(pgn).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                (
+                    "Ruy Lopez: Berlin Defense".to_string(),
+                    "e2e4 e7e5 g1f3 b8c6 f1b5 g8f6 e1g1 f6e4".to_string()
+                ),
+                (
+                    "Tactics".to_string(),
+                    "d2d4 e7e5 d4e5 f7f5 e5f6 d7d6 f6g7 e8e7 g7h8 b8c6 b1c3 c8e6 \
+                     c1g5 g8f6 d1d2 d8d7 e1c1"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pinned_pieces_need_no_disambiguation() {
+        // Both knights reach e2, but the one on c3 is pinned
+        let pgn = "1. e4 e5 2. d3 Bb4+ 3. Nc3 d6 4. Ne2 *";
+        let lines = pgn_opening_lines(pgn).unwrap();
+        assert_eq!(lines[0].1, "e2e4 e7e5 d2d3 f8b4 b1c3 d7d6 g1e2");
+
+        let illegal = pgn_opening_lines("1. e4 e5 2. Ke3 *");
+        assert_eq!(
+            illegal,
+            Err(PgnError::IllegalMove {
+                game: 1,
+                san: "Ke3".to_string()
+            })
+        );
+    }
+}
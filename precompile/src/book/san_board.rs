@@ -0,0 +1,320 @@
+//! A small mailbox board for replaying opening lines
+//!
+//! The book generator turns SAN into from and to squares with it, and keys
+//! the positions along each line by the engine's Zobrist hash, so the engine
+//! can look up book moves by position.
+
+use crate::zobrist::ZobristTables;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    fn opposite(self) -> Self {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    /// Rank direction pawns move in.
+    fn forward(self) -> i8 {
+        match self {
+            Color::White => 1,
+            Color::Black => -1,
+        }
+    }
+
+    fn back_rank(self) -> i8 {
+        match self {
+            Color::White => 0,
+            Color::Black => 7,
+        }
+    }
+}
+
+/// A piece: its color and its SAN letter, `P` for pawns
+pub type Piece = (Color, char);
+
+/// Castling rights bits, as the engine numbers them
+const WHITE_KINGSIDE: u8 = 0b1000;
+const BLACK_KINGSIDE: u8 = 0b0100;
+const WHITE_QUEENSIDE: u8 = 0b0010;
+const BLACK_QUEENSIDE: u8 = 0b0001;
+
+/// The castling rights lost when a piece moves from or to each square: the
+/// kings' and rooks' starting squares.
+const CASTLING_RIGHTS_SQUARES: [((i8, i8), u8); 6] = [
+    ((0, 0), WHITE_QUEENSIDE),
+    ((0, 4), WHITE_KINGSIDE | WHITE_QUEENSIDE),
+    ((0, 7), WHITE_KINGSIDE),
+    ((7, 0), BLACK_QUEENSIDE),
+    ((7, 4), BLACK_KINGSIDE | BLACK_QUEENSIDE),
+    ((7, 7), BLACK_KINGSIDE),
+];
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// Just enough of a chess board to turn SAN into from and to squares and
+/// hash the positions. Squares are `(rank, file)`, with a1 at `(0, 0)`.
+#[derive(Clone)]
+pub struct SanBoard {
+    squares: [[Option<Piece>; 8]; 8],
+    turn: Color,
+    en_passant: Option<(i8, i8)>,
+    castling_rights: u8,
+}
+
+impl SanBoard {
+    pub fn starting_position() -> Self {
+        let mut squares = [[None; 8]; 8];
+        for (file, kind) in "RNBQKBNR".chars().enumerate() {
+            squares[0][file] = Some((Color::White, kind));
+            squares[1][file] = Some((Color::White, 'P'));
+            squares[6][file] = Some((Color::Black, 'P'));
+            squares[7][file] = Some((Color::Black, kind));
+        }
+        Self {
+            squares,
+            turn: Color::White,
+            en_passant: None,
+            castling_rights: WHITE_KINGSIDE | BLACK_KINGSIDE | WHITE_QUEENSIDE | BLACK_QUEENSIDE,
+        }
+    }
+
+    fn get(&self, (rank, file): (i8, i8)) -> Option<Piece> {
+        self.squares[rank as usize][file as usize]
+    }
+
+    fn set(&mut self, (rank, file): (i8, i8), piece: Option<Piece>) {
+        self.squares[rank as usize][file as usize] = piece;
+    }
+
+    /// Whether `kind` standing on `from` attacks `to`, with the pieces in
+    /// between in the way. Pawns attack diagonally forward.
+    fn attacks(&self, (color, kind): Piece, from: (i8, i8), to: (i8, i8)) -> bool {
+        let (d_rank, d_file) = (to.0 - from.0, to.1 - from.1);
+        match kind {
+            'P' => d_rank == color.forward() && d_file.abs() == 1,
+            'N' => KNIGHT_OFFSETS.contains(&(d_rank, d_file)),
+            'K' => KING_OFFSETS.contains(&(d_rank, d_file)),
+            _ => {
+                let straight = d_rank == 0 || d_file == 0;
+                let diagonal = d_rank.abs() == d_file.abs();
+                let moves_that_way = match kind {
+                    'R' => straight,
+                    'B' => diagonal,
+                    _ => straight || diagonal,
+                };
+                if !moves_that_way || (d_rank, d_file) == (0, 0) {
+                    return false;
+                }
+                let step = (d_rank.signum(), d_file.signum());
+                let mut square = (from.0 + step.0, from.1 + step.1);
+                while square != to {
+                    if self.get(square).is_some() {
+                        return false;
+                    }
+                    square = (square.0 + step.0, square.1 + step.1);
+                }
+                true
+            }
+        }
+    }
+
+    /// Whether `color`'s king is attacked.
+    fn in_check(&self, color: Color) -> bool {
+        let all_squares = || (0..8).flat_map(|rank| (0..8).map(move |file| (rank, file)));
+        let Some(king) = all_squares().find(|&square| self.get(square) == Some((color, 'K')))
+        else {
+            return false;
+        };
+        all_squares().any(|square| match self.get(square) {
+            Some(piece) if piece.0 != color => self.attacks(piece, square, king),
+            _ => false,
+        })
+    }
+
+    /// Whether the side to move's `kind` on `from` can move to `to`, ignoring
+    /// whether it leaves its king in check.
+    fn reaches(&self, kind: char, from: (i8, i8), to: (i8, i8), capture: bool) -> bool {
+        if kind != 'P' {
+            return self.attacks((self.turn, kind), from, to);
+        }
+        if capture {
+            return self.attacks((self.turn, 'P'), from, to);
+        }
+        let forward = self.turn.forward();
+        let start_rank = self.turn.back_rank() + forward;
+        from.1 == to.1
+            && (to.0 - from.0 == forward
+                || (from.0 == start_rank
+                    && to.0 - from.0 == 2 * forward
+                    && self.get((from.0 + forward, from.1)).is_none()))
+    }
+
+    /// Play `from`-`to`, moving the rook along when the king castles.
+    fn apply(&mut self, from: (i8, i8), to: (i8, i8), promotion: Option<char>) {
+        let (color, kind) = self.get(from).expect("a piece is on the from square");
+        if kind == 'P' && Some(to) == self.en_passant {
+            self.set((from.0, to.1), None);
+        }
+        if kind == 'K' && (to.1 - from.1).abs() == 2 {
+            let (rook_from, rook_to) = if to.1 > from.1 { (7, 5) } else { (0, 3) };
+            let rook = self.get((from.0, rook_from));
+            self.set((from.0, rook_from), None);
+            self.set((from.0, rook_to), rook);
+        }
+        for (square, rights) in CASTLING_RIGHTS_SQUARES {
+            if square == from || square == to {
+                self.castling_rights &= !rights;
+            }
+        }
+        self.en_passant =
+            (kind == 'P' && (to.0 - from.0).abs() == 2).then_some(((from.0 + to.0) / 2, from.1));
+        self.set(from, None);
+        self.set(to, Some((color, promotion.unwrap_or(kind))));
+        self.turn = color.opposite();
+    }
+
+    /// Play a move given in SAN and return it in UCI (without promotion).
+    pub fn play_san(&mut self, san: &str) -> Option<String> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let back_rank = self.turn.back_rank();
+        let (from, to, promotion) = match san {
+            "O-O" | "0-0" => ((back_rank, 4), (back_rank, 6), None),
+            "O-O-O" | "0-0-0" => ((back_rank, 4), (back_rank, 2), None),
+            _ => {
+                let (san, promotion) = match san.rsplit_once('=') {
+                    Some((san, piece)) => (san, piece.chars().next()),
+                    None => match san.chars().last() {
+                        Some(piece @ ('Q' | 'R' | 'B' | 'N')) => {
+                            (&san[..san.len() - 1], Some(piece))
+                        }
+                        _ => (san, None),
+                    },
+                };
+                let (kind, rest) = match san.chars().next() {
+                    Some(kind @ ('K' | 'Q' | 'R' | 'B' | 'N')) => (kind, &san[1..]),
+                    _ => ('P', san),
+                };
+                if rest.len() < 2 {
+                    return None;
+                }
+                let (qualifiers, destination) = rest.split_at(rest.len() - 2);
+                let to = parse_square(destination)?;
+                let capture = qualifiers.contains('x');
+                let from_file = qualifiers.chars().find(|c| ('a'..='h').contains(c));
+                let from_rank = qualifiers.chars().find(char::is_ascii_digit);
+                let from = self.find_origin(kind, to, capture, from_file, from_rank)?;
+                (from, to, promotion)
+            }
+        };
+        if self.get(from).map(|piece| piece.0) != Some(self.turn) {
+            return None;
+        }
+        self.apply(from, to, promotion);
+        Some(format!("{}{}", square_name(from), square_name(to)))
+    }
+
+    /// Play a move given in UCI, as long as the side to move has a piece on
+    /// its from square.
+    pub fn play_uci(&mut self, uci: &str) -> Option<()> {
+        let from = parse_square(uci.get(0..2)?)?;
+        let to = parse_square(uci.get(2..4)?)?;
+        let promotion = uci[4..]
+            .chars()
+            .next()
+            .map(|piece| piece.to_ascii_uppercase());
+        if self.get(from).map(|piece| piece.0) != Some(self.turn) {
+            return None;
+        }
+        self.apply(from, to, promotion);
+        Some(())
+    }
+
+    /// The position's key in the book: its hash as the engine's
+    /// `Board::zobrist` works it out, less the en passant target. Every
+    /// double push sets a target, which would keep a line ending in one from
+    /// transposing into a line ending in another move.
+    pub fn book_key(&self, tables: &ZobristTables) -> u64 {
+        let mut hash = tables.castling_rights[self.castling_rights as usize];
+        for rank in 0..8 {
+            for file in 0..8 {
+                if let Some((color, kind)) = self.get((rank, file)) {
+                    let piece = "PNBRQK".find(kind).expect("a known piece letter");
+                    let color = match color {
+                        Color::Black => 0,
+                        Color::White => 1,
+                    };
+                    hash ^= tables.pieces[piece][(rank * 8 + file) as usize][color];
+                }
+            }
+        }
+        if self.turn == Color::Black {
+            hash ^= tables.turn;
+        }
+        hash
+    }
+
+    /// The square of the only piece of `kind` that can legally move to `to`,
+    /// matching the SAN's disambiguation.
+    fn find_origin(
+        &self,
+        kind: char,
+        to: (i8, i8),
+        capture: bool,
+        from_file: Option<char>,
+        from_rank: Option<char>,
+    ) -> Option<(i8, i8)> {
+        let mut origins = (0..8)
+            .flat_map(|rank| (0..8).map(move |file| (rank, file)))
+            .filter(|&from| self.get(from) == Some((self.turn, kind)))
+            .filter(|&from| from_file.is_none_or(|file| square_name(from).starts_with(file)))
+            .filter(|&from| from_rank.is_none_or(|rank| square_name(from).ends_with(rank)))
+            .filter(|&from| self.reaches(kind, from, to, capture))
+            .filter(|&from| {
+                let mut after = self.clone();
+                after.apply(from, to, None);
+                !after.in_check(self.turn)
+            });
+        let origin = origins.next();
+        match origins.next() {
+            Some(_) => None,
+            None => origin,
+        }
+    }
+}
+
+pub fn parse_square(name: &str) -> Option<(i8, i8)> {
+    let mut chars = name.chars();
+    let file = chars.next().filter(|file| ('a'..='h').contains(file))?;
+    let rank = chars.next().filter(|rank| ('1'..='8').contains(rank))?;
+    Some((rank as i8 - '1' as i8, file as i8 - 'a' as i8))
+}
+
+fn square_name((rank, file): (i8, i8)) -> String {
+    format!("{}{}", (b'a' + file as u8) as char, rank + 1)
+}
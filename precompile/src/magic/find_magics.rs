@@ -6,7 +6,7 @@ use common::bitboard::square::Square;
 
 use log::debug;
 
-use crate::random_number_generator::generate_random_u64;
+use crate::random_number_generator::{seed_header, RandomNumberGenerator};
 
 // This blog post does an excellent job of explaining magic bitboards:
 // https://analog-hors.github.io/site/magic-bitboards/
@@ -93,6 +93,7 @@ fn find_magic(
     sliding_piece: &SlidingPiece,
     square: Bitboard,
     index_bits: u8,
+    rng: &mut RandomNumberGenerator,
 ) -> (MagicEntry, Vec<Bitboard>) {
     let mask = sliding_piece.relevant_blockers(square);
     let shift = 64 - index_bits;
@@ -100,7 +101,9 @@ fn find_magic(
     loop {
         // Magics require a low number of active bits, so we AND
         // by two more random values to cut down on the bits set.
-        let magic = generate_random_u64() & generate_random_u64() & generate_random_u64();
+        let magic = rng.generate_random_u64()
+            & rng.generate_random_u64()
+            & rng.generate_random_u64();
         let magic_entry = MagicEntry { mask, magic, shift };
         if let Ok(table) = try_make_table(sliding_piece, square, &magic_entry) {
             return (magic_entry, table);
@@ -144,43 +147,221 @@ fn try_make_table(
     Ok(table)
 }
 
+/// The attack tables of every square, rook and bishop alike, packed into one.
+/// A square's table goes at the lowest offset where each of its used entries
+/// lands on an unused entry or on one holding the same targets, so the gaps
+/// that constructive collisions leave in one table are filled by others.
+struct PackedTable {
+    entries: Vec<Bitboard>,
+}
+
+impl PackedTable {
+    /// Offset at which `used`, a table's used (index, targets) entries, fits.
+    /// The search starts where the table's first entry would land on the
+    /// first unused one; before that, everything is taken.
+    fn first_fit(&self, used: &[(usize, Bitboard)]) -> usize {
+        let first_unused = self
+            .entries
+            .iter()
+            .position(|entry| entry.is_empty())
+            .unwrap_or(self.entries.len());
+        let start = first_unused.saturating_sub(used.first().map_or(0, |&(index, _)| index));
+        (start..=self.entries.len())
+            .find(|&offset| {
+                used.iter().all(|&(index, targets)| {
+                    self.entries
+                        .get(offset + index)
+                        .is_none_or(|entry| entry.is_empty() || *entry == targets)
+                })
+            })
+            .expect("a table always fits at the end")
+    }
+
+    /// Length of the packed table after placing `used` at `offset`.
+    fn length_with(&self, used: &[(usize, Bitboard)], offset: usize) -> usize {
+        let end = used.last().map_or(0, |&(index, _)| offset + index + 1);
+        self.entries.len().max(end)
+    }
+
+    fn place(&mut self, used: &[(usize, Bitboard)], offset: usize) {
+        let length = self.length_with(used, offset);
+        self.entries.resize(length, Bitboard::EMPTY);
+        for &(index, targets) in used {
+            self.entries[offset + index] = targets;
+        }
+    }
+}
+
+/// The used (index, targets) entries of a table, by index.
+fn used_entries(table: &[Bitboard]) -> Vec<(usize, Bitboard)> {
+    table
+        .iter()
+        .enumerate()
+        .filter(|(_, targets)| !targets.is_empty())
+        .map(|(index, &targets)| (index, targets))
+        .collect()
+}
+
+/// Finds a magic for `square` and packs its table into `packed`, returning
+/// the magic and the offset of its table. After the first magic, `effort`
+/// more are considered, keeping the one that grows the packed table least.
+fn find_packed_magic(
+    sliding_piece: &SlidingPiece,
+    square: Bitboard,
+    packed: &mut PackedTable,
+    effort: u32,
+    rng: &mut RandomNumberGenerator,
+) -> (MagicEntry, usize) {
+    let index_bits = sliding_piece.relevant_blockers(square).popcnt() as u8;
+    debug!("Index bits: {}", index_bits);
+
+    let mut best: Option<(MagicEntry, Vec<(usize, Bitboard)>, usize, usize)> = None;
+    for _ in 0..=effort {
+        let (entry, table) = find_magic(sliding_piece, square, index_bits, rng);
+        let used = used_entries(&table);
+        let offset = packed.first_fit(&used);
+        let length = packed.length_with(&used, offset);
+        if best.as_ref().is_none_or(|(_, _, _, best_length)| length < *best_length) {
+            best = Some((entry, used, offset, length));
+        }
+    }
+    let (entry, used, offset, _) = best.expect("at least one magic is found");
+    packed.place(&used, offset);
+    (entry, offset)
+}
+
 fn find_and_write_magics(
     sliding_piece: &SlidingPiece,
     sliding_piece_name: &str,
     out: &mut BufWriter<File>,
+    packed: &mut PackedTable,
+    effort: u32,
+    rng: &mut RandomNumberGenerator,
 ) -> std::io::Result<()> {
     writeln!(out,
         "pub const {}_MAGICS: &[MagicEntry; 64] = &[",
         sliding_piece_name
     )?;
-    let mut total_table_size = 0;
     for square_i in 0..64 {
         let square = Bitboard(1) << square_i;
         debug!("Finding magic for square: {:?}", square);
-        let index_bits = sliding_piece.relevant_blockers(square).popcnt() as u8;
-        debug!("Index bits: {}", index_bits);
-        let (entry, table) = find_magic(sliding_piece, square, index_bits);
-        // In the final move generator, each table is concatenated into one contiguous table
-        // for convenience, so an offset is added to denote the start of each segment.
+        let (entry, offset) = find_packed_magic(sliding_piece, square, packed, effort, rng);
+        // In the final move generator, the tables share one contiguous table, so an
+        // offset is added to denote where each one starts.
         writeln!(out,
             "    MagicEntry {{ mask: 0x{:016X}, magic: 0x{:016X}, shift: {}, offset: {} }},",
-            entry.mask.0, entry.magic, entry.shift, total_table_size
+            entry.mask.0, entry.magic, entry.shift, offset
         )?;
-        total_table_size += table.len();
     }
     writeln!(out,"];")?;
-    writeln!(out,
-        "pub const {}_TABLE_SIZE: usize = {};",
-        sliding_piece_name, total_table_size
-    )?;
     Ok(())
 }
 
-pub fn find_and_write_all_magics(out: &mut BufWriter<File>) -> std::io::Result<()> {
+/// First line of the magic table file, recording what it was searched with.
+pub fn magic_header(seed: u64, effort: u32) -> String {
+    format!("{}, magic search effort {}", seed_header(seed), effort)
+}
+
+/// Finds magics for rooks and bishops and writes them to `out`, with the
+/// size of the table they share. Returns that size in entries. The same
+/// `seed` and `effort` always find the same magics; a higher `effort`
+/// considers that many more magics per square for a smaller table, which
+/// takes longer.
+pub fn find_and_write_all_magics(
+    out: &mut BufWriter<File>,
+    seed: u64,
+    effort: u32,
+) -> std::io::Result<usize> {
+    let mut rng = RandomNumberGenerator::new(seed);
+    let mut packed = PackedTable { entries: Vec::new() };
+    writeln!(out, "{}", magic_header(seed, effort))?;
     debug!("Finding magics...");
-    find_and_write_magics(&ROOK, "ROOK", out)?;
+    find_and_write_magics(&ROOK, "ROOK", out, &mut packed, effort, &mut rng)?;
     debug!("Found rook magics!");
-    find_and_write_magics(&BISHOP, "BISHOP", out)?;
+    find_and_write_magics(&BISHOP, "BISHOP", out, &mut packed, effort, &mut rng)?;
     debug!("Found bishop magics!");
-    Ok(())
+    let table_size = packed.entries.len();
+    writeln!(out, "pub const MAGIC_TABLE_SIZE: usize = {};", table_size)?;
+    Ok(table_size)
+}
+
+/// First line of the PEXT table file.
+pub fn pext_header() -> String {
+    "// Generated by precompile: PEXT attack tables".to_string()
+}
+
+/// Writes the masks of rooks and bishops for tables indexed with PEXT, which
+/// needs no magics: each square's table has an entry for every subset of its
+/// mask, right after the previous square's. Returns the size of the table
+/// they share in entries.
+pub fn write_all_pext_entries(out: &mut BufWriter<File>) -> std::io::Result<usize> {
+    writeln!(out, "{}", pext_header())?;
+    let mut table_size = 0;
+    for (sliding_piece, sliding_piece_name) in [(&ROOK, "ROOK"), (&BISHOP, "BISHOP")] {
+        writeln!(out,
+            "pub const {}_MAGICS: &[MagicEntry; 64] = &[",
+            sliding_piece_name
+        )?;
+        for square_i in 0..64 {
+            let mask = sliding_piece.relevant_blockers(Bitboard(1) << square_i);
+            writeln!(out,
+                "    MagicEntry {{ mask: 0x{:016X}, offset: {} }},",
+                mask.0, table_size
+            )?;
+            table_size += 1 << mask.popcnt();
+        }
+        writeln!(out, "];")?;
+    }
+    writeln!(out, "pub const MAGIC_TABLE_SIZE: usize = {};", table_size)?;
+    Ok(table_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packing_shares_entries_without_conflicts() {
+        let mut packed = PackedTable { entries: Vec::new() };
+        let a = Bitboard(1);
+        let b = Bitboard(2);
+        packed.place(&[(0, a), (2, b)], 0);
+        // Fills the gap at 1, and agrees with the entry at 2
+        let offset = packed.first_fit(&[(0, Bitboard(4)), (1, b)]);
+        assert_eq!(offset, 1);
+        // Conflicts at 0 and 1, and agrees with the entry at 2
+        assert_eq!(packed.first_fit(&[(0, b), (1, a)]), 2);
+        assert_eq!(packed.first_fit(&[(0, a), (1, a)]), 3);
+    }
+
+    #[test]
+    fn test_packed_tables_are_smaller_and_still_answer_every_lookup() {
+        let mut rng = RandomNumberGenerator::new(1);
+        let mut packed = PackedTable { entries: Vec::new() };
+        let squares = [0, 7, 27, 36];
+        let magics: Vec<_> = squares
+            .iter()
+            .map(|&square_i| {
+                let square = Bitboard(1) << square_i;
+                find_packed_magic(&BISHOP, square, &mut packed, 4, &mut rng)
+            })
+            .collect();
+        // Unpacked, these tables take 64 + 64 + 512 + 512 entries
+        assert!(packed.entries.len() <= 64 + 64 + 512 + 512);
+
+        for (&square_i, (entry, offset)) in squares.iter().zip(&magics) {
+            let square = Bitboard(1) << square_i;
+            let mut blockers = Bitboard::EMPTY;
+            loop {
+                assert_eq!(
+                    packed.entries[offset + magic_index(entry, blockers)],
+                    BISHOP.targets(square, blockers)
+                );
+                blockers.0 = blockers.0.wrapping_sub(entry.mask.0) & entry.mask.0;
+                if blockers.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
 }
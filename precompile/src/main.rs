@@ -1,33 +1,85 @@
-use std::{fs::File, io::BufWriter, path::PathBuf};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
 
-use precompile::book::book_generator::generate_opening_book;
-use precompile::{magic::find_magics::find_and_write_all_magics, zobrist::write_zobrist_tables};
+use precompile::book::book_generator::{book_header, generate_opening_book};
+use precompile::magic::find_magics::{
+    find_and_write_all_magics, magic_header, pext_header, write_all_pext_entries,
+};
+use precompile::random_number_generator::{seed_from_env, SEED_ENV_VAR};
+use precompile::zobrist::{write_zobrist_tables, zobrist_header};
 
-fn file_exists_in_build_cache(file_name: &str) -> bool {
+/// Environment variable with the number of extra magics tried per square in
+/// search of smaller tables (default: 0, the first that works).
+const MAGIC_EFFORT_ENV_VAR: &str = "CHESS_MAGIC_EFFORT";
+
+/// Environment variable with the opening book to build in: a file of opening
+/// lines or a `.pgn`, relative to the repository root, or `none` for an empty
+/// book.
+const OPENING_BOOK_ENV_VAR: &str = "CHESS_OPENING_BOOK";
+
+/// Opening lines built in when `OPENING_BOOK_ENV_VAR` is not set.
+const DEFAULT_OPENING_BOOK: &str = "precompile/data/opening_lines.txt";
+
+/// Environment variable that, set to anything but `0`, regenerates every
+/// table even when the cached one is up to date.
+const FORCE_ENV_VAR: &str = "CHESS_PRECOMPILE_FORCE";
+
+/// Whether the cached `file_name` starts with `header`, which records what it
+/// was generated from.
+fn cached_file_has_header(file_name: &str, header: &str) -> bool {
     let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
     out.push(file_name);
-    out.exists()
+    File::open(out)
+        .ok()
+        .and_then(|file| BufReader::new(file).lines().next())
+        .and_then(Result::ok)
+        .is_some_and(|line| line == header)
+}
+
+fn build_zobrist_tables(filename: &str, seed: u64) {
+    let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
+    out.push(filename);
+    let mut out = BufWriter::new(File::create(out).unwrap());
+    write_zobrist_tables(&mut out, seed).unwrap();
 }
 
-fn build_zobrist_tables(filename: &str) {
+fn build_magics_tables(filename: &str, seed: u64, effort: u32) {
     let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
     out.push(filename);
     let mut out = BufWriter::new(File::create(out).unwrap());
-    write_zobrist_tables(&mut out).unwrap();
+    let table_size = find_and_write_all_magics(&mut out, seed, effort).unwrap();
+    println!(
+        "cargo:warning=Magic table: {} entries ({} KiB)",
+        table_size,
+        table_size * std::mem::size_of::<u64>() / 1024
+    );
 }
 
-fn build_magics_tables(filename: &str) {
+fn build_pext_tables(filename: &str) {
     let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
     out.push(filename);
     let mut out = BufWriter::new(File::create(out).unwrap());
-    find_and_write_all_magics(&mut out).unwrap();
+    let table_size = write_all_pext_entries(&mut out).unwrap();
+    println!(
+        "cargo:warning=PEXT table: {} entries ({} KiB)",
+        table_size,
+        table_size * std::mem::size_of::<u64>() / 1024
+    );
 }
 
-fn build_opening_book(filename: &str) {
+fn build_opening_book(filename: &str, input_file: Option<&Path>, seed: u64) {
     let mut out: PathBuf = std::env::var("OUT_DIR").unwrap().into();
     out.push(filename);
     let mut out = BufWriter::new(File::create(out).unwrap());
-    generate_opening_book("precompile/data/opening_lines.txt", &mut out).unwrap();
+    generate_opening_book(input_file, seed, &mut out).unwrap_or_else(|err| {
+        panic!(
+            "Could not build the opening book from {:?}: {}",
+            input_file, err
+        )
+    });
 }
 
 fn main() {
@@ -38,27 +90,75 @@ fn main() {
     println!("cargo:rerun-if-changed=precompile/src/magic");
     println!("cargo:rerun-if-changed=precompile/src/book");
     println!("cargo:rerun-if-changed=precompile/src/random_number_generator");
-    println!("cargo:rerun-if-changed=precompile/data/opening_lines.txt");
+    println!("cargo:rerun-if-env-changed={}", SEED_ENV_VAR);
+    println!("cargo:rerun-if-env-changed={}", MAGIC_EFFORT_ENV_VAR);
+    println!("cargo:rerun-if-env-changed={}", OPENING_BOOK_ENV_VAR);
+    println!("cargo:rerun-if-env-changed={}", FORCE_ENV_VAR);
+
+    let seed = seed_from_env().unwrap_or_else(|err| {
+        panic!(
+            "{} must be a u64 in decimal or 0x hex: {}",
+            SEED_ENV_VAR, err
+        )
+    });
+    let magic_effort = std::env::var(MAGIC_EFFORT_ENV_VAR).map_or(0, |value| {
+        value.trim().parse().unwrap_or_else(|err| {
+            panic!(
+                "{} must be a number of tries: {}",
+                MAGIC_EFFORT_ENV_VAR, err
+            )
+        })
+    });
+    let opening_book = match std::env::var(OPENING_BOOK_ENV_VAR) {
+        Ok(path) if path.trim().eq_ignore_ascii_case("none") => None,
+        Ok(path) => Some(PathBuf::from(path.trim())),
+        Err(_) => Some(PathBuf::from(DEFAULT_OPENING_BOOK)),
+    };
+    if let Some(path) = &opening_book {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    let force = std::env::var(FORCE_ENV_VAR).is_ok_and(|value| !value.is_empty() && value != "0");
+    // Enabled by the engine's `pext` feature
+    let pext = std::env::var_os("CARGO_FEATURE_PEXT").is_some();
+
+    // Tables cached from another seed, effort or book are rebuilt, so a long
+    // magic search only runs again when asked for
+    let is_cached =
+        |file_name: &str, header: &str| !force && cached_file_has_header(file_name, header);
 
-    if !file_exists_in_build_cache("zobrist_table.rs") {
+    if !is_cached("zobrist_table.rs", &zobrist_header(seed)) {
         println!("cargo:warning=Building zobrist tables...");
-        build_zobrist_tables("zobrist_table.rs");
+        build_zobrist_tables("zobrist_table.rs", seed);
         println!("cargo:warning=Finished building zobrist tables.");
     } else {
         println!("cargo:warning=Using cached zobrist tables");
     }
 
-    if !file_exists_in_build_cache("magic_table.rs") {
+    if pext {
+        if !is_cached("magic_table.rs", &pext_header()) {
+            println!("cargo:warning=Building PEXT tables...");
+            build_pext_tables("magic_table.rs");
+            println!("cargo:warning=Finished building PEXT tables.");
+        } else {
+            println!("cargo:warning=Using cached PEXT tables");
+        }
+    } else if !is_cached("magic_table.rs", &magic_header(seed, magic_effort)) {
         println!("cargo:warning=Building magic tables...");
-        build_magics_tables("magic_table.rs");
+        build_magics_tables("magic_table.rs", seed, magic_effort);
         println!("cargo:warning=Finished building magic tables.");
     } else {
         println!("cargo:warning=Using cached magic tables");
     }
 
-    if !file_exists_in_build_cache("opening_book.rs") {
+    let opening_book_header = book_header(opening_book.as_deref(), seed).unwrap_or_else(|err| {
+        panic!(
+            "Could not read the opening book {:?}: {}",
+            opening_book, err
+        )
+    });
+    if !is_cached("opening_book.rs", &opening_book_header) {
         println!("cargo:warning=Building opening book...");
-        build_opening_book("opening_book.rs");
+        build_opening_book("opening_book.rs", opening_book.as_deref(), seed);
         println!("cargo:warning=Finished building opening book.");
     } else {
         println!("cargo:warning=Using cached opening book");
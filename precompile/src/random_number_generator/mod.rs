@@ -1,7 +1,82 @@
-use rand::Rng;
+use std::num::ParseIntError;
 
-/// Generates a random u64. This is needed for both Zobrist tables and magic bitboard generation.
-pub fn generate_random_u64() -> u64 {
-    let mut rng = rand::thread_rng();
-    rng.gen::<u64>()
+/// Environment variable that sets the seed of the generated tables, in
+/// decimal or as `0x`-prefixed hex.
+pub const SEED_ENV_VAR: &str = "CHESS_PRECOMPILE_SEED";
+
+/// Seed used when `SEED_ENV_VAR` is not set.
+pub const DEFAULT_SEED: u64 = 0x5EED_C4E5_5B0A_4D01;
+
+/// Seeded generator of the random u64s needed for both Zobrist tables and
+/// magic bitboard generation. The same seed always gives the same numbers,
+/// so every build gets the same tables (and the same position hashes).
+///
+/// This is SplitMix64 rather than one of `rand`'s generators, whose output
+/// may change between `rand` versions.
+pub struct RandomNumberGenerator {
+    state: u64,
+}
+
+impl RandomNumberGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn generate_random_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Parse a seed given in decimal or as `0x`-prefixed hex.
+pub fn parse_seed(value: &str) -> Result<u64, ParseIntError> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(&hex.replace('_', ""), 16),
+        None => value.replace('_', "").parse(),
+    }
+}
+
+/// The seed set with `SEED_ENV_VAR`, or `DEFAULT_SEED`.
+pub fn seed_from_env() -> Result<u64, ParseIntError> {
+    match std::env::var(SEED_ENV_VAR) {
+        Ok(value) => parse_seed(&value),
+        Err(_) => Ok(DEFAULT_SEED),
+    }
+}
+
+/// First line of a generated file, recording the seed it was generated with.
+pub fn seed_header(seed: u64) -> String {
+    format!("// Generated by precompile with seed 0x{:016X}", seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_the_same_seed_gives_the_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = RandomNumberGenerator::new(seed);
+            (0..4).map(|_| rng.generate_random_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(numbers(7), numbers(8));
+        // SplitMix64's published first output for seed 0
+        assert_eq!(numbers(0)[0], 0xE220_A839_7B1D_CDAF);
+    }
+
+    #[test]
+    fn test_seeds_parse_in_decimal_or_hex() {
+        assert_eq!(parse_seed("42"), Ok(42));
+        assert_eq!(parse_seed(" 0x2A "), Ok(42));
+        assert_eq!(parse_seed("0xFF_FF"), Ok(0xFFFF));
+        assert!(parse_seed("forty-two").is_err());
+    }
 }
@@ -1,50 +1,97 @@
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
-use crate::random_number_generator::generate_random_u64;
+use crate::random_number_generator::{seed_header, RandomNumberGenerator};
 
 // https://www.chessprogramming.org/Zobrist_Hashing
 
-const PIECES: [&str; 6] = ["pawn", "rook", "knight", "bishop", "king", "queen"];
+const PIECES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
 const SQUARES: usize = 64;
 
-/// Generates three tables of random u64s for Zobrist hashing and writes them to a file
-/// that is included in the project's primary module.
-pub fn write_zobrist_tables(out: &mut BufWriter<File>) -> std::io::Result<()> {
-    // Generate ZOBRIST_PIECES_TABLE
-    let mut zobrist_table = [[[0u64; 2]; SQUARES]; PIECES.len()];
-    for piece in 0..PIECES.len() {
-        for square in 0..SQUARES {
-            for color in 0..2 {
-                zobrist_table[piece][square][color] = generate_random_u64();
+/// First line of the Zobrist tables file, recording the seed they were
+/// generated from and that they include check counts.
+pub fn zobrist_header(seed: u64) -> String {
+    format!("{}, with check counts", seed_header(seed))
+}
+
+/// The random u64s of Zobrist hashing, indexed as the engine indexes them:
+/// pieces by piece (pawn, knight, bishop, rook, queen, king), square (a1 is
+/// 0, h8 is 63) and color (black, then white), and castling rights by their
+/// bits. The turn key is in the hash when Black is to move.
+pub struct ZobristTables {
+    pub pieces: [[[u64; 2]; SQUARES]; PIECES.len()],
+    pub castling_rights: [u64; 16],
+    pub en_passant: [u64; SQUARES],
+    pub turn: u64,
+    pub checks: [[u64; 3]; 2],
+}
+
+impl ZobristTables {
+    /// The tables for `seed`, which are always the same for the same seed.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = RandomNumberGenerator::new(seed);
+
+        let mut pieces = [[[0u64; 2]; SQUARES]; PIECES.len()];
+        for piece in pieces.iter_mut() {
+            for square in piece.iter_mut() {
+                for color in square.iter_mut() {
+                    *color = rng.generate_random_u64();
+                }
             }
         }
-    }
 
-    // Generate ZOBRIST_CASTLING_RIGHTS_TABLE
-    let mut zobrist_castling_rights = [0u64; 16];
-    for i in 0..16 {
-        zobrist_castling_rights[i] = generate_random_u64();
-    }
+        let mut castling_rights = [0u64; 16];
+        for rights in castling_rights.iter_mut() {
+            *rights = rng.generate_random_u64();
+        }
+
+        let mut en_passant = [0u64; SQUARES];
+        for square in en_passant.iter_mut() {
+            *square = rng.generate_random_u64();
+        }
+
+        let turn = rng.generate_random_u64();
 
-    // Generate ZOBRIST_EN_PASSANT_TABLE
-    let mut zobrist_en_passant = [0u64; SQUARES];
-    for i in 0..SQUARES {
-        zobrist_en_passant[i] = generate_random_u64();
+        // Generated last, so the tables above stay the same for a seed.
+        // Indexed by color and checks given less one; three-check positions
+        // with no checks given hash like standard ones
+        let mut checks = [[0u64; 3]; 2];
+        for color in checks.iter_mut() {
+            for count in color.iter_mut() {
+                *count = rng.generate_random_u64();
+            }
+        }
+
+        Self {
+            pieces,
+            castling_rights,
+            en_passant,
+            turn,
+            checks,
+        }
     }
+}
+
+/// Generates the tables of random u64s for Zobrist hashing and writes them to a file
+/// that is included in the project's primary module. The same `seed` always
+/// writes the same tables.
+pub fn write_zobrist_tables(out: &mut BufWriter<File>, seed: u64) -> std::io::Result<()> {
+    let tables = ZobristTables::new(seed);
 
     // Write the generated values into a format that can be used in a Rust module
+    writeln!(out, "{}", zobrist_header(seed))?;
     writeln!(out, "#[rustfmt::skip]")?;
-    writeln!(out, "pub const ZOBRIST_PIECES_TABLE: [[[u64; 2]; 64]; 6] = [")?;
-    for piece_index in 0..PIECES.len() {
-        writeln!(out, "    [  // {}", PIECES[piece_index])?;
-        for square_index in 0..SQUARES {
+    writeln!(
+        out,
+        "pub const ZOBRIST_PIECES_TABLE: [[[u64; 2]; 64]; 6] = ["
+    )?;
+    for (piece, squares) in PIECES.iter().zip(tables.pieces.iter()) {
+        writeln!(out, "    [  // {}", piece)?;
+        for (square_index, colors) in squares.iter().enumerate() {
             writeln!(
                 out,
                 "        [{}, {}],  // Square {}",
-                zobrist_table[piece_index][square_index][0],
-                zobrist_table[piece_index][square_index][1],
-                square_index
+                colors[0], colors[1], square_index
             )?;
         }
         writeln!(out, "    ],")?;
@@ -52,23 +99,32 @@ pub fn write_zobrist_tables(out: &mut BufWriter<File>) -> std::io::Result<()> {
     writeln!(out, "];")?;
 
     writeln!(out, "\n#[rustfmt::skip]")?;
-    writeln!(out, "pub const ZOBRIST_CASTLING_RIGHTS_TABLE: [u64; 16] = [")?;
-    for rights in zobrist_castling_rights.iter() {
+    writeln!(
+        out,
+        "pub const ZOBRIST_CASTLING_RIGHTS_TABLE: [u64; 16] = ["
+    )?;
+    for rights in tables.castling_rights.iter() {
         writeln!(out, "    {},", rights)?;
     }
     writeln!(out, "];")?;
 
     writeln!(out, "\n#[rustfmt::skip]")?;
     writeln!(out, "pub const ZOBRIST_EN_PASSANT_TABLE: [u64; 64] = [")?;
-    for ep_square in zobrist_en_passant.iter() {
+    for ep_square in tables.en_passant.iter() {
         writeln!(out, "    {},", ep_square)?;
     }
     writeln!(out, "];")?;
 
-    // Generate ZOBRIST_TURN
-    let zobrist_turn = generate_random_u64();
     writeln!(out)?;
-    writeln!(out, "pub const ZOBRIST_TURN: u64 = {};", zobrist_turn)?;
+    writeln!(out, "pub const ZOBRIST_TURN: u64 = {};", tables.turn)?;
+
+    writeln!(out, "\n#[rustfmt::skip]")?;
+    writeln!(out, "pub const ZOBRIST_CHECKS_TABLE: [[u64; 3]; 2] = [")?;
+    for checks in tables.checks.iter() {
+        let checks: Vec<String> = checks.iter().map(u64::to_string).collect();
+        writeln!(out, "    [{}],", checks.join(", "))?;
+    }
+    writeln!(out, "];")?;
 
     Ok(())
 }
@@ -0,0 +1,353 @@
+//! Python bindings for the chess engine
+//!
+//! The `rustchess` module offers a `Board` for scripting analysis and
+//! generating training data without running the engine as a UCI process: the
+//! legal moves, playing and taking back moves, FEN, SAN and UCI conversion,
+//! perft counts, and searches limited by depth, time or nodes. Moves are
+//! taken in SAN (`Nf3`) or UCI (`g1f3`). Invalid FENs and illegal moves raise
+//! `ValueError`.
+//!
+//! Build with `maturin develop --release` in this directory, then:
+//! ```python
+//! import rustchess
+//!
+//! board = rustchess.Board()          # or rustchess.Board(fen)
+//! board.push("e4")
+//! result = board.search(depth=8, movetime=0.5)
+//! print(result.move, result.score, result.nodes)
+//! board.push(result.uci)
+//! print(board.fen(), board.legal_moves())
+//! ```
+
+use std::time::Duration;
+
+use chess::alpha_beta_searcher::{SearchContext, SearchError};
+use chess::board::color::Color;
+use chess::board::Board as ChessBoard;
+use chess::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use chess::chess_move::chess_move::ChessMove;
+use chess::chess_search::search_best_move_with_history;
+use chess::evaluate::{game_ending, GameEnding};
+use chess::input_handler::fen::{parse_fen, FenParseError};
+use chess::input_handler::fen_serialize::to_fen;
+use chess::move_generator::MoveGenerator;
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BoardError {
+    #[error("Invalid FEN: {error}")]
+    InvalidFen { error: FenParseError },
+    #[error("Illegal move: {notation}")]
+    IllegalMove { notation: String },
+    #[error("No move to take back")]
+    NoMoveToPop,
+    #[error("Search failed: {error}")]
+    Search { error: SearchError },
+}
+
+impl From<BoardError> for PyErr {
+    fn from(error: BoardError) -> Self {
+        match error {
+            BoardError::InvalidFen { .. } | BoardError::IllegalMove { .. } => {
+                PyValueError::new_err(error.to_string())
+            }
+            BoardError::NoMoveToPop => PyIndexError::new_err(error.to_string()),
+            BoardError::Search { .. } => PyRuntimeError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// The engine's choice in a position
+#[pyclass(get_all, frozen)]
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    /// The best move in SAN.
+    #[pyo3(name = "move")]
+    pub san: String,
+    /// The best move in UCI.
+    pub uci: String,
+    /// Score in centipawns from White's perspective, when the search gave one.
+    pub score: Option<i16>,
+    /// Deepest iteration the search completed.
+    pub depth: Option<u8>,
+    /// Positions searched.
+    pub nodes: usize,
+}
+
+#[pymethods]
+impl SearchResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "SearchResult(move='{}', score={}, depth={}, nodes={})",
+            self.san,
+            self.score
+                .map_or("None".to_string(), |score| score.to_string()),
+            self.depth
+                .map_or("None".to_string(), |depth| depth.to_string()),
+            self.nodes
+        )
+    }
+}
+
+/// A position and the moves played to reach it
+#[pyclass]
+#[derive(Clone)]
+pub struct Board {
+    board: ChessBoard,
+    move_generator: MoveGenerator,
+    /// Moves played with `push`, with their SAN, so `pop` can take them back.
+    move_stack: Vec<(ChessMove, String)>,
+    /// Hashes of the positions reached so far, for repetition draws.
+    position_history: Vec<u64>,
+}
+
+impl Board {
+    fn from_board(board: ChessBoard) -> Self {
+        Self {
+            position_history: vec![board.current_position_hash()],
+            board,
+            move_generator: MoveGenerator::default(),
+            move_stack: Vec::new(),
+        }
+    }
+
+    /// The legal moves with their SAN.
+    fn candidates(&mut self) -> Vec<(ChessMove, String)> {
+        let turn = self.board.turn();
+        enumerate_candidate_moves_with_algebraic_notation(
+            &mut self.board,
+            turn,
+            &self.move_generator,
+        )
+    }
+
+    /// The legal move `notation` names, in SAN with or without its check
+    /// suffix, or in UCI.
+    fn find_move(&mut self, notation: &str) -> Result<(ChessMove, String), BoardError> {
+        let without_suffix = |san: &str| san.trim_end_matches(['+', '#']).to_string();
+        let candidates = self.candidates();
+        candidates
+            .iter()
+            .find(|(_, san)| san == notation)
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|(_, san)| without_suffix(san) == without_suffix(notation))
+            })
+            .or_else(|| candidates.iter().find(|(m, _)| m.to_uci() == notation))
+            .cloned()
+            .ok_or_else(|| BoardError::IllegalMove {
+                notation: notation.to_string(),
+            })
+    }
+}
+
+#[pymethods]
+impl Board {
+    /// A board at `fen`, or the standard starting position.
+    #[new]
+    #[pyo3(signature = (fen=None))]
+    fn new(fen: Option<&str>) -> Result<Self, BoardError> {
+        match fen {
+            Some(fen) => parse_fen(fen)
+                .map(Self::from_board)
+                .map_err(|error| BoardError::InvalidFen { error }),
+            None => Ok(Self::from_board(ChessBoard::default())),
+        }
+    }
+
+    /// The current position in FEN.
+    fn fen(&self) -> String {
+        to_fen(&self.board)
+    }
+
+    /// The side to move, "white" or "black".
+    #[getter]
+    fn turn(&self) -> &'static str {
+        match self.board.turn() {
+            Color::White => "white",
+            Color::Black => "black",
+        }
+    }
+
+    /// The position's Zobrist hash. Transpositions hash the same, and so do
+    /// the same positions in builds with the same precompile seed.
+    fn zobrist(&self) -> u64 {
+        self.board.zobrist()
+    }
+
+    /// The moves the side to move can play, in SAN.
+    fn legal_moves(&mut self) -> Vec<String> {
+        self.candidates().into_iter().map(|(_, san)| san).collect()
+    }
+
+    /// The moves the side to move can play, in UCI.
+    fn legal_moves_uci(&mut self) -> Vec<String> {
+        self.candidates()
+            .into_iter()
+            .map(|(chess_move, _)| chess_move.to_uci())
+            .collect()
+    }
+
+    /// Play a move given in SAN or UCI. Returns the move in SAN.
+    fn push(&mut self, notation: &str) -> Result<String, BoardError> {
+        let (chess_move, san) = self.find_move(notation)?;
+        chess_move
+            .apply(&mut self.board)
+            .map_err(|_| BoardError::IllegalMove {
+                notation: notation.to_string(),
+            })?;
+        self.board.toggle_turn();
+        self.position_history
+            .push(self.board.current_position_hash());
+        self.move_stack.push((chess_move, san.clone()));
+        Ok(san)
+    }
+
+    /// Take back the last move played with `push`. Returns it in SAN.
+    fn pop(&mut self) -> Result<String, BoardError> {
+        let (chess_move, san) = self.move_stack.pop().ok_or(BoardError::NoMoveToPop)?;
+        self.board.toggle_turn();
+        chess_move
+            .undo(&mut self.board)
+            .expect("undoing a played move should succeed");
+        self.position_history.pop();
+        Ok(san)
+    }
+
+    /// The moves played with `push`, in SAN.
+    fn move_stack(&self) -> Vec<String> {
+        self.move_stack.iter().map(|(_, san)| san.clone()).collect()
+    }
+
+    /// A legal move in SAN, given in UCI.
+    fn san(&mut self, uci: &str) -> Result<String, BoardError> {
+        self.find_move(uci).map(|(_, san)| san)
+    }
+
+    /// A legal move in UCI, given in SAN.
+    fn uci(&mut self, san: &str) -> Result<String, BoardError> {
+        self.find_move(san)
+            .map(|(chess_move, _)| chess_move.to_uci())
+    }
+
+    /// How the game ended: "checkmate", "stalemate", "draw" or, in a
+    /// three-check position, "variant win"; or None while it goes on.
+    fn outcome(&mut self) -> Option<&'static str> {
+        let turn = self.board.turn();
+        let ending = game_ending(
+            &mut self.board,
+            &self.move_generator,
+            turn,
+            &self.position_history,
+        )?;
+        Some(match ending {
+            GameEnding::Checkmate => "checkmate",
+            GameEnding::Stalemate => "stalemate",
+            GameEnding::Draw => "draw",
+            GameEnding::VariantWin => "variant win",
+        })
+    }
+
+    /// The number of move sequences `depth` plies long from this position.
+    fn perft(&mut self, depth: u8) -> usize {
+        let turn = self.board.turn();
+        self.move_generator.perft(depth, &mut self.board, turn)
+    }
+
+    /// The engine's choice for the side to move, without playing it. The
+    /// search goes `depth` plies deep, and stops early after `movetime`
+    /// seconds or roughly `nodes` positions when given. Other Python threads
+    /// run while it searches.
+    #[pyo3(signature = (depth=6, movetime=None, nodes=None))]
+    fn search(
+        &mut self,
+        py: Python<'_>,
+        depth: u8,
+        movetime: Option<f64>,
+        nodes: Option<usize>,
+    ) -> Result<SearchResult, BoardError> {
+        let mut context = SearchContext::new(depth);
+        context.set_time_limit(movetime.map(Duration::from_secs_f64));
+        context.set_node_limit(nodes);
+        let mut board = self.board.clone();
+        let position_history = self.position_history.clone();
+        let best_move = py
+            .allow_threads(|| {
+                search_best_move_with_history(&mut context, &mut board, position_history, 0)
+            })
+            .map_err(|error| BoardError::Search { error })?;
+
+        let uci = best_move.to_uci();
+        let (_, san) = self.find_move(&uci)?;
+        Ok(SearchResult {
+            san,
+            uci,
+            score: context.last_score(),
+            depth: context.last_completed_depth(),
+            nodes: context.searched_position_count(),
+        })
+    }
+
+    /// An independent copy of the board and its moves.
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Board('{}')", self.fen())
+    }
+}
+
+#[pymodule]
+fn rustchess(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Board>()?;
+    module.add_class::<SearchResult>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_convert_between_san_and_uci_and_can_be_taken_back() {
+        let mut board = Board::new(None).unwrap();
+        assert_eq!(board.legal_moves().len(), 20);
+        assert_eq!(board.uci("Nf3").unwrap(), "g1f3");
+        assert_eq!(board.push("e2e4").unwrap(), "e4");
+        assert_eq!(board.san("e7e5").unwrap(), "e5");
+        assert!(matches!(
+            board.push("Ke2"),
+            Err(BoardError::IllegalMove { .. })
+        ));
+        assert_eq!(board.turn(), "black");
+
+        assert_eq!(board.pop().unwrap(), "e4");
+        assert_eq!(board.fen(), to_fen(&ChessBoard::default()));
+        assert!(matches!(board.pop(), Err(BoardError::NoMoveToPop)));
+    }
+
+    #[test]
+    fn test_search_finds_a_mate_in_one_within_a_node_limit() {
+        pyo3::prepare_freethreaded_python();
+        // Scholar's mate: Qxf7#
+        let mut board = Board::new(Some(
+            "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        ))
+        .unwrap();
+        let result = Python::with_gil(|py| board.search(py, 4, None, Some(10_000))).unwrap();
+        assert_eq!(result.san, "Qxf7#");
+        assert_eq!(result.uci, "h5f7");
+        board.push(&result.uci).unwrap();
+        assert_eq!(board.outcome(), Some("checkmate"));
+    }
+
+    #[test]
+    fn test_perft_counts_the_starting_position() {
+        let mut board = Board::new(None).unwrap();
+        assert_eq!(board.perft(3), 8902);
+    }
+}
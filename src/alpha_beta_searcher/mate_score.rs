@@ -0,0 +1,94 @@
+//! Mate scores, by their distance from the root of the search.
+//!
+//! An evaluator scores a position that is won outright with `MATE_SCORE`
+//! (negated when the minimizing player has won), however far into the search
+//! it is. The search turns that into `MATE_SCORE - ply`, where `ply` counts
+//! the moves from the root to the mate, so a quicker mate always scores higher
+//! and the number of moves to it can be read off the score at the root.
+//!
+//! The transposition table holds mate scores by their distance from the
+//! position they were stored for instead, so that they stay right when the
+//! position comes up again at another ply or under another root.
+
+/// Score of a won position, from the winner's side.
+pub const MATE_SCORE: i16 = i16::MAX / 2;
+
+/// Scores at least this far from zero are mates. Mates are never further from
+/// the root than a ply can count.
+pub const MATE_THRESHOLD: i16 = MATE_SCORE - u8::MAX as i16;
+
+/// Whether `score` is a forced mate for either side.
+pub fn is_mate_score(score: i16) -> bool {
+    score.unsigned_abs() >= MATE_THRESHOLD.unsigned_abs()
+}
+
+/// Full moves until the mate that `score`, as returned by a search, stands
+/// for: positive when the maximizing player mates, negative when it is mated.
+pub fn mate_in_moves(score: i16) -> Option<i16> {
+    if !is_mate_score(score) {
+        return None;
+    }
+    let plies = MATE_SCORE - score.saturating_abs().min(MATE_SCORE);
+    let moves = (plies + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+/// An evaluator's score for a position `ply` moves from the root, with a mate
+/// scored by its distance from the root.
+pub(crate) fn mate_at_ply(score: i16, ply: u8) -> i16 {
+    if score >= MATE_THRESHOLD {
+        MATE_SCORE - i16::from(ply)
+    } else if score <= -MATE_THRESHOLD {
+        -MATE_SCORE + i16::from(ply)
+    } else {
+        score
+    }
+}
+
+/// A score found `ply` moves from the root, as stored for its position.
+pub(crate) fn to_table(score: i16, ply: u8) -> i16 {
+    if score >= MATE_THRESHOLD {
+        score + i16::from(ply)
+    } else if score <= -MATE_THRESHOLD {
+        score - i16::from(ply)
+    } else {
+        score
+    }
+}
+
+/// A score stored for a position, as seen from a root `ply` moves before it.
+pub(crate) fn from_table(score: i16, ply: u8) -> i16 {
+    if score >= MATE_THRESHOLD {
+        score - i16::from(ply)
+    } else if score <= -MATE_THRESHOLD {
+        score + i16::from(ply)
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_scores_are_relative_to_the_stored_position() {
+        // Mate 5 plies from the root, found at a position 2 plies in
+        let score = MATE_SCORE - 5;
+        let stored = to_table(score, 2);
+        assert_eq!(stored, MATE_SCORE - 3);
+        // The same position 4 plies into another search mates at ply 7
+        assert_eq!(from_table(stored, 4), MATE_SCORE - 7);
+        assert_eq!(from_table(to_table(-score, 2), 2), -score);
+        assert_eq!(to_table(250, 6), 250);
+    }
+
+    #[test]
+    fn test_mate_in_moves_counts_full_moves() {
+        assert_eq!(mate_in_moves(MATE_SCORE - 1), Some(1));
+        assert_eq!(mate_in_moves(MATE_SCORE - 3), Some(2));
+        assert_eq!(mate_in_moves(-MATE_SCORE + 2), Some(-1));
+        assert_eq!(mate_in_moves(900), None);
+        assert!(!is_mate_score(900));
+    }
+}
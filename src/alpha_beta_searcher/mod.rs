@@ -1,13 +1,23 @@
 //! Generic alpha-beta search algorithm.
 
 mod killer_moves;
+pub mod mate_score;
+mod move_picker;
 pub mod search;
+mod time_manager;
 mod traits;
 mod transposition_table;
 
 #[cfg(test)]
 mod tests;
 
-pub use search::{alpha_beta_search, SearchContext, SearchError};
+pub use mate_score::{is_mate_score, mate_in_moves, MATE_SCORE, MATE_THRESHOLD};
+pub use move_picker::MovePicker;
+pub use search::{
+    alpha_beta_search, CurrentMove, CurrentMoveCallback, PonderState, RootMovePenalty,
+    SearchContext, SearchError, SearchInfo, SearchInfoCallback, SearchLimits, SearchParams,
+    MAX_LIMITED_SEARCH_DEPTH,
+};
+pub use time_manager::{TimeLimits, TimeManager};
 pub use traits::*;
-pub use transposition_table::{BoundType, TranspositionTable};
+pub use transposition_table::{BoundType, TTEntry, TranspositionTable, DEFAULT_TT_SIZE_MB};
@@ -0,0 +1,159 @@
+//! Staged move generation: the moves of a node handed out one at a time, best
+//! first, generating each group only once the search gets to it.
+//!
+//! The stages are the transposition table move, the tactical moves that don't
+//! lose material, the killer moves, the quiet moves, and last the tactical
+//! moves that do. A node that cuts off on its TT move or a good capture never
+//! generates its quiet moves. The TT move and killers may come from another
+//! position that shares the hash or ply, so they are only handed out once
+//! found among the moves generated for their stage.
+
+use smallvec::SmallVec;
+
+use super::traits::{GameMove, GameState, MoveGenerator, MoveOrderer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    TtMove,
+    GoodTactical,
+    Killers,
+    Quiet,
+    BadTactical,
+    Done,
+}
+
+/// Hands out the moves of a position in stages, generating them lazily.
+pub struct MovePicker<S: GameState, G: MoveGenerator<S>> {
+    stage: Stage,
+    tt_move: Option<G::Move>,
+    killers: [Option<G::Move>; 2],
+    tactical: Option<G::MoveList>,
+    quiet: Option<G::MoveList>,
+    /// Tactical moves set aside for the last stage, in the order found.
+    bad_tactical: SmallVec<[G::Move; 8]>,
+    /// Position in the list of the current stage.
+    index: usize,
+}
+
+impl<S: GameState, G: MoveGenerator<S>> MovePicker<S, G> {
+    pub fn new(tt_move: Option<G::Move>, killers: [Option<G::Move>; 2]) -> Self {
+        Self {
+            stage: Stage::TtMove,
+            tt_move,
+            killers,
+            tactical: None,
+            quiet: None,
+            bad_tactical: SmallVec::new(),
+            index: 0,
+        }
+    }
+
+    /// The next move to search, or None once every move has been handed out.
+    /// `state` must be the position the picker was created for.
+    pub fn next<O: MoveOrderer<S, G::Move>>(
+        &mut self,
+        state: &mut S,
+        move_generator: &G,
+        move_orderer: &O,
+    ) -> Option<G::Move> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GoodTactical;
+                    if let Some(tt_move) = self.tt_move.clone() {
+                        let moves = if tt_move.is_tactical(state) {
+                            self.tactical_moves(state, move_generator)
+                        } else {
+                            self.quiet_moves(state, move_generator)
+                        };
+                        if moves.as_ref().contains(&tt_move) {
+                            return Some(tt_move);
+                        }
+                        self.tt_move = None;
+                    }
+                }
+                Stage::GoodTactical => {
+                    self.tactical_moves(state, move_generator);
+                    let moves = self.tactical.as_mut().expect("tactical moves generated");
+                    let moves = moves.as_mut();
+                    while self.index < moves.len() {
+                        move_orderer.pick_next(moves, self.index, state);
+                        let candidate = moves[self.index].clone();
+                        self.index += 1;
+                        if !candidate.is_tactical(state)
+                            || self.tt_move.as_ref() == Some(&candidate)
+                        {
+                            continue;
+                        }
+                        if move_orderer.is_losing_tactical(&candidate, state) {
+                            self.bad_tactical.push(candidate);
+                            continue;
+                        }
+                        return Some(candidate);
+                    }
+                    self.stage = Stage::Killers;
+                    self.index = 0;
+                }
+                Stage::Killers => {
+                    while self.index < self.killers.len() {
+                        let slot = self.index;
+                        self.index += 1;
+                        let Some(killer) = self.killers[slot].clone() else {
+                            continue;
+                        };
+                        let found = !killer.is_tactical(state)
+                            && self.tt_move.as_ref() != Some(&killer)
+                            && !self.killers[..slot].contains(&Some(killer.clone()))
+                            && self
+                                .quiet_moves(state, move_generator)
+                                .as_ref()
+                                .contains(&killer);
+                        if found {
+                            return Some(killer);
+                        }
+                        // Handed out already or not a move here; nothing to skip later
+                        self.killers[slot] = None;
+                    }
+                    self.stage = Stage::Quiet;
+                    self.index = 0;
+                }
+                Stage::Quiet => {
+                    self.quiet_moves(state, move_generator);
+                    let moves = self.quiet.as_mut().expect("quiet moves generated");
+                    let moves = moves.as_mut();
+                    while self.index < moves.len() {
+                        move_orderer.pick_next(moves, self.index, state);
+                        let candidate = &moves[self.index];
+                        self.index += 1;
+                        let already_picked = self.tt_move.as_ref() == Some(candidate)
+                            || self.killers.iter().flatten().any(|k| k == candidate);
+                        if already_picked || candidate.is_tactical(state) {
+                            continue;
+                        }
+                        return Some(candidate.clone());
+                    }
+                    self.stage = Stage::BadTactical;
+                    self.index = 0;
+                }
+                Stage::BadTactical => {
+                    if let Some(candidate) = self.bad_tactical.get(self.index) {
+                        self.index += 1;
+                        return Some(candidate.clone());
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    fn tactical_moves(&mut self, state: &mut S, move_generator: &G) -> &G::MoveList {
+        self.tactical
+            .get_or_insert_with(|| move_generator.generate_tactical_moves(state))
+    }
+
+    fn quiet_moves(&mut self, state: &mut S, move_generator: &G) -> &G::MoveList {
+        self.quiet
+            .get_or_insert_with(|| move_generator.generate_quiet_moves(state))
+    }
+}
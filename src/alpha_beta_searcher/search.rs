@@ -19,21 +19,32 @@
 //! arise through move transpositions. Stores the score, depth, bound type (exact/upper/lower),
 //! and best move for each position.
 //!
+//! ## Mate Scores
+//! Mates are scored by their distance from the root (see `mate_score`), so a
+//! quicker mate always beats a slower one. Mate distance pruning cuts off any
+//! node where even mating on the spot couldn't beat a mate already found.
+//!
 //! ## Move Ordering
 //! Orders moves to maximize alpha-beta cutoffs:
 //! 1. PV (Principal Variation) move from transposition table
-//! 2. Killer moves (moves that caused cutoffs at the same ply)
-//! 3. Remaining moves via incremental selection (`MoveOrderer::pick_next`)
+//! 2. Tactical moves that don't lose material (`MoveOrderer::is_losing_tactical`)
+//! 3. Killer moves (moves that caused cutoffs at the same ply)
+//! 4. Quiet moves
+//! 5. Tactical moves that lose material
 //!
-//! Interior nodes use incremental selection (pick-best) instead of a full sort: after
-//! placing the PV and killer moves at the front, each subsequent move is selected on
-//! demand via `pick_next()`. This avoids O(n log n) sorting of moves that are never
-//! searched due to beta cutoffs. Root and quiescence nodes still use full sort.
+//! Interior nodes generate their moves in these stages with a `MovePicker`, so a node
+//! that cuts off early never generates its quiet moves. Within a stage each move is
+//! selected on demand via `MoveOrderer::pick_next()` instead of a full sort, avoiding
+//! O(n log n) sorting of moves that are never searched due to beta cutoffs. Root and
+//! quiescence nodes still use full sort.
 //!
 //! ## Quiescence Search
 //! Extends search beyond the nominal depth for tactical moves to avoid the horizon effect
 //! where evaluation stops just before a critical sequence. Games opt in by implementing
-//! `is_tactical` on their move type to identify which moves should be searched in quiescence.
+//! `is_tactical` on their move type to identify which moves should be searched in quiescence,
+//! and can override `MoveGenerator::generate_tactical_moves` to generate only those moves
+//! rather than every move for the search to filter. Quiet moves that give check, from
+//! `MoveGenerator::generate_quiet_checks`, are also searched at the first quiescence ply.
 //!
 //! ## Reverse Futility Pruning (RFP)
 //! At shallow depths (controlled by `Evaluator::rfp_margin`), if the static evaluation
@@ -54,34 +65,65 @@
 //! After the first few moves at each node, later quiet moves are searched at reduced depth
 //! under the assumption that well-ordered moves tend to be best. Uses a logarithmic formula
 //! `reduction = 1 + ln(depth) * ln(move_count) / 2` that scales reductions with both depth
-//! and move index. If the reduced search returns a score that improves on the current bound,
+//! and move index. Its constants, the aspiration window and a scale on the futility
+//! margins are `SearchParams`, so that they can be tuned. If the reduced search returns a score that improves on the current bound,
 //! a full-depth re-search is performed to verify the result.
 //!
 //! ## Check Extensions
 //! When the current player is in check, search depth is extended by 1 ply. Positions
 //! where a player is in check are tactically forcing — cutting search short here risks
 //! missing critical forced sequences. The extension is capped at depth 64 to prevent
-//! unbounded growth. Uses `Evaluator::is_in_check` to detect check positions.
+//! unbounded growth. Uses `GameState::is_in_check` to detect check positions.
+//!
+//! ## Singular Extensions
+//! When the transposition table holds a bound for the TT move from a search nearly as
+//! deep as this one, the other moves are searched at half the depth against that score
+//! less a margin. If none of them reaches it, the TT move is the only one that holds
+//! the position, and it is searched a ply deeper so a refutation at the horizon is not
+//! missed.
 //!
 //! ## Parallel Search
 //! Root moves can be searched in parallel using thread-local storage for killer moves to
-//! eliminate lock contention.
+//! eliminate lock contention. Without the `parallel` feature they are searched in turn.
+//! Each root move is searched on a copy of the root position borrowed from a `StatePool`
+//! and left back at the root afterwards, so a search makes one copy per thread rather than
+//! one per root move at every depth.
+//!
+//! ## Time Management
+//! A search with `TimeLimits` starts no new depth past the soft limit and abandons the
+//! depth it is on past the hard limit. A `TimeManager` moves the soft limit after every
+//! depth, giving more time while the best move changes or the score falls and less once
+//! the best move has held for several depths.
+//!
+//! ## Pondering
+//! A search started while its `PonderState` is pondering ignores its time limit until
+//! another thread calls `PonderState::finish` (on a UCI `ponderhit`); the limit then
+//! counts from that moment, so the search carries on with everything it has learned.
 
 use std::cmp::{max, min};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 
 use log::debug;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use smallvec::SmallVec;
 use thiserror::Error;
 #[cfg(feature = "instrumentation")]
 use tracing::instrument;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
 use super::killer_moves::KillerMovesManager;
-use super::transposition_table::{BoundType, TranspositionTable};
-use super::{Evaluator, GameMove, GameState, MoveCollection, MoveGenerator, MoveOrderer};
+use super::mate_score::{from_table, is_mate_score, mate_at_ply, MATE_SCORE};
+use super::move_picker::MovePicker;
+use super::time_manager::{TimeLimits, TimeManager};
+use super::transposition_table::{BoundType, TTEntry, TranspositionTable};
+use super::{
+    Evaluator, GameMove, GameState, MoveCollection, MoveGenerator, MoveOrderer, TableMove,
+};
 
 #[derive(Error, Debug)]
 pub enum SearchError {
@@ -93,12 +135,56 @@ pub enum SearchError {
     Stopped,
 }
 
+/// Whether a search is thinking on the opponent's time, shared with the
+/// thread that tells it when the opponent has moved.
+#[derive(Debug, Default)]
+pub struct PonderState {
+    pondering: AtomicBool,
+    finished_at: Mutex<Option<Instant>>,
+}
+
+impl PonderState {
+    /// Suspend the time limit of the next search until `finish`.
+    pub fn start(&self) {
+        *self.finished_at.lock().unwrap() = None;
+        self.pondering.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop pondering: a search's time limit counts from now.
+    pub fn finish(&self) {
+        *self.finished_at.lock().unwrap() = Some(Instant::now());
+        self.pondering.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.load(Ordering::SeqCst)
+    }
+
+    /// When the time limit of a search started at `search_start` began
+    /// counting, or `None` while it has not.
+    fn clock_start(&self, search_start: Instant) -> Option<Instant> {
+        if self.is_pondering() {
+            return None;
+        }
+        let finished_at = *self.finished_at.lock().unwrap();
+        Some(finished_at.map_or(search_start, |finished_at| finished_at.max(search_start)))
+    }
+}
+
 /// Search configuration parameters.
 struct SearchConfig {
     depth: u8,
     parallel: bool,
-    time_limit: Option<Duration>,
+    time_limits: Option<TimeLimits>,
     start_time: Option<Instant>,
+    ponder: Arc<PonderState>,
+    node_limit: Option<usize>,
+    /// Node count when the current search started; stats accumulate across searches.
+    start_nodes: usize,
+    /// Whether the node limit may abort the current iteration. Off until the
+    /// first iteration completes so that a best move is always available.
+    node_limit_armed: bool,
+    params: SearchParams,
 }
 
 impl SearchConfig {
@@ -106,38 +192,50 @@ impl SearchConfig {
         Self {
             depth,
             parallel,
-            time_limit: None,
+            time_limits: None,
             start_time: None,
+            ponder: Arc::new(PonderState::default()),
+            node_limit: None,
+            start_nodes: 0,
+            node_limit_armed: false,
+            params: SearchParams::default(),
         }
     }
 
     fn with_time_limit(depth: u8, parallel: bool, time_limit: Duration) -> Self {
         Self {
-            depth,
-            parallel,
-            time_limit: Some(time_limit),
-            start_time: None,
+            time_limits: Some(TimeLimits::for_movetime(time_limit)),
+            ..Self::new(depth, parallel)
         }
     }
 
-    /// Check if we've exceeded the soft time limit (~50% of budget).
-    /// Used between depth iterations -- if true, don't start the next depth.
-    fn soft_time_exceeded(&self) -> bool {
-        if let (Some(limit), Some(start)) = (self.time_limit, self.start_time) {
-            start.elapsed() > limit / 2
-        } else {
-            false
+    /// Check if the current search has used up its node budget.
+    fn node_limit_exceeded(&self, nodes: usize) -> bool {
+        match self.node_limit {
+            Some(limit) => self.node_limit_armed && nodes.saturating_sub(self.start_nodes) >= limit,
+            None => false,
         }
     }
 
-    /// Check if we've exceeded the hard time limit (~80% of budget).
+    /// Time used, once the time limits are counting.
+    fn time_used(&self) -> Option<Duration> {
+        self.time_limits?;
+        let start = self.ponder.clock_start(self.start_time?)?;
+        Some(start.elapsed())
+    }
+
+    /// Check if the time manager says not to start the next depth.
+    fn soft_time_exceeded(&self, time_manager: &TimeManager) -> bool {
+        self.time_used()
+            .is_some_and(|elapsed| time_manager.should_stop_deepening(elapsed))
+    }
+
+    /// Check if we've exceeded the hard time limit.
     /// Used during search to abort mid-depth.
     fn hard_time_exceeded(&self) -> bool {
-        if let (Some(limit), Some(start)) = (self.time_limit, self.start_time) {
-            start.elapsed() > limit * 4 / 5
-        } else {
-            false
-        }
+        self.time_limits
+            .zip(self.time_used())
+            .is_some_and(|(limits, elapsed)| elapsed > limits.hard)
     }
 }
 
@@ -156,7 +254,12 @@ struct SearchStats {
     fp_attempts: AtomicUsize,
     fp_cutoffs: AtomicUsize,
     check_extensions: AtomicUsize,
+    singular_extensions: AtomicUsize,
+    /// The deepest ply reached by the current search, with extensions and
+    /// quiescence.
+    selective_depth: AtomicUsize,
     last_score: Option<i16>,
+    last_depth: Option<u8>,
     last_duration: Option<Duration>,
 }
 
@@ -176,7 +279,10 @@ impl SearchStats {
             fp_attempts: AtomicUsize::new(0),
             fp_cutoffs: AtomicUsize::new(0),
             check_extensions: AtomicUsize::new(0),
+            singular_extensions: AtomicUsize::new(0),
+            selective_depth: AtomicUsize::new(0),
             last_score: None,
+            last_depth: None,
             last_duration: None,
         }
     }
@@ -233,12 +339,36 @@ impl SearchStats {
         self.check_extensions.fetch_add(1, Ordering::Relaxed);
     }
 
+    fn increment_singular_extensions(&self) {
+        self.singular_extensions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_ply(&self, ply: u8) {
+        let ply = usize::from(ply);
+        // Most nodes are no deeper than the deepest so far, and reading is
+        // cheaper than writing a counter every thread shares
+        if ply > self.selective_depth.load(Ordering::Relaxed) {
+            self.selective_depth.fetch_max(ply, Ordering::Relaxed);
+        }
+    }
+
+    fn selective_depth(&self) -> u8 {
+        self.selective_depth
+            .load(Ordering::Relaxed)
+            .min(usize::from(u8::MAX)) as u8
+    }
+
     fn check_extensions(&self) -> usize {
         self.check_extensions.load(Ordering::Relaxed)
     }
 
+    fn singular_extensions(&self) -> usize {
+        self.singular_extensions.load(Ordering::Relaxed)
+    }
+
     fn reset(&mut self) {
         self.last_score = None;
+        self.last_depth = None;
         self.last_duration = None;
         self.position_count.store(0, Ordering::Relaxed);
         self.quiescence_nodes.store(0, Ordering::Relaxed);
@@ -253,10 +383,12 @@ impl SearchStats {
         self.fp_attempts.store(0, Ordering::Relaxed);
         self.fp_cutoffs.store(0, Ordering::Relaxed);
         self.check_extensions.store(0, Ordering::Relaxed);
+        self.singular_extensions.store(0, Ordering::Relaxed);
     }
 
-    fn record_result(&mut self, score: i16, duration: Duration) {
+    fn record_result(&mut self, score: i16, depth: u8, duration: Duration) {
         self.last_score = Some(score);
+        self.last_depth = Some(depth);
         self.last_duration = Some(duration);
     }
 
@@ -309,15 +441,137 @@ impl SearchStats {
     }
 }
 
+/// Tunable constants of the pruning and reduction heuristics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchParams {
+    /// Half-width of the aspiration window around the previous depth's score.
+    pub aspiration_window: i16,
+    /// Constant term of the late move reduction formula.
+    pub lmr_base: f64,
+    /// Divisor of the `ln(depth) * ln(move_count)` term of the late move
+    /// reduction formula; larger values reduce less.
+    pub lmr_divisor: f64,
+    /// Scale applied to `Evaluator::rfp_margin` for reverse futility and
+    /// futility pruning; 1.0 uses the evaluator's margins as they are.
+    pub futility_margin_scale: f64,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            aspiration_window: 50,
+            lmr_base: 1.0,
+            lmr_divisor: 2.0,
+            futility_margin_scale: 1.0,
+        }
+    }
+}
+
+impl SearchParams {
+    /// Reduction for the `move_count`th move at `depth`, before clamping.
+    fn lmr_reduction(&self, depth: u8, move_count: usize) -> f64 {
+        self.lmr_base + (depth as f64).ln() * (move_count as f64).ln() / self.lmr_divisor
+    }
+
+    fn futility_margin(&self, margin: i16) -> i16 {
+        (f64::from(margin) * self.futility_margin_scale).round() as i16
+    }
+}
+
+/// Progress of an iterative deepening search after a completed depth, like a
+/// UCI `info` line.
+#[derive(Debug, Clone)]
+pub struct SearchInfo<M> {
+    pub depth: u8,
+    /// The deepest ply reached so far, with extensions and quiescence.
+    pub seldepth: u8,
+    /// Score of the best move, positive when the maximizing player is ahead.
+    pub score: i16,
+    /// Positions searched since the search started.
+    pub nodes: usize,
+    pub elapsed: Duration,
+    /// The best move followed by the expected replies, as far as the
+    /// transposition table knows them.
+    pub principal_variation: Vec<M>,
+}
+
+/// Called with a `SearchInfo` after each completed depth.
+pub type SearchInfoCallback<M> = Arc<dyn Fn(&SearchInfo<M>) + Send + Sync>;
+
+/// A root move the search has started on, like a UCI `info currmove` line.
+#[derive(Debug, Clone)]
+pub struct CurrentMove<M> {
+    pub depth: u8,
+    pub current_move: M,
+    /// The move's place in the order root moves are searched in, from 1.
+    pub number: usize,
+    /// Positions searched since the search started.
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+/// Called with a `CurrentMove` as each root move's search starts. With a
+/// parallel search, the calls come from several threads at once.
+pub type CurrentMoveCallback<M> = Arc<dyn Fn(&CurrentMove<M>) + Send + Sync>;
+
+/// Centipawns held against a root move, e.g. by experience of the games it
+/// lost, for the side to move. Called for each root move at every depth.
+pub type RootMovePenalty<M> = Arc<dyn Fn(&M) -> i16 + Send + Sync>;
+
+/// Depth cap used when a search is bounded by time or nodes instead of depth.
+pub const MAX_LIMITED_SEARCH_DEPTH: u8 = 100;
+
+/// What bounds a search, as given to `SearchContext::set_limits`, e.g. from
+/// a UCI `go` command. A search bounded by nodes or time, or an `infinite`
+/// one, deepens until stopped by them instead of stopping at the context's
+/// depth, unless a `depth` is given as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    /// Roughly the most nodes to search. The first iteration always completes.
+    pub nodes: Option<usize>,
+    /// Time to think, with the limits of `TimeLimits::for_movetime`.
+    pub movetime: Option<Duration>,
+    /// Time limits from a game clock, used instead of `movetime`.
+    pub clock: Option<TimeLimits>,
+    /// Search until stopped, ignoring the node and time limits.
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    /// Whether the search is bounded only by depth.
+    pub fn is_depth_only(&self) -> bool {
+        !self.infinite && self.node_limit().is_none() && self.time_limits().is_none()
+    }
+
+    /// The time limits of the search, if it has any.
+    pub fn time_limits(&self) -> Option<TimeLimits> {
+        if self.infinite {
+            return None;
+        }
+        self.clock
+            .or_else(|| self.movetime.map(TimeLimits::for_movetime))
+    }
+
+    pub fn node_limit(&self) -> Option<usize> {
+        self.nodes.filter(|_| !self.infinite)
+    }
+}
+
 pub struct SearchContext<M: Clone + Send + Sync + 'static> {
     config: SearchConfig,
     stats: SearchStats,
     transposition_table: TranspositionTable<M>,
     killer_manager: KillerMovesManager,
     stop: Arc<AtomicBool>,
+    info_callback: Option<SearchInfoCallback<M>>,
+    current_move_callback: Option<CurrentMoveCallback<M>>,
+    root_move_penalty: Option<RootMovePenalty<M>>,
+    /// Root moves left out of later searches, to find the best of the rest.
+    excluded_root_moves: Vec<M>,
 }
 
-impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
+impl<M: TableMove + Clone + Send + Sync + 'static> SearchContext<M> {
     pub fn new(depth: u8) -> Self {
         Self {
             config: SearchConfig::new(depth, true),
@@ -325,6 +579,10 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
             transposition_table: TranspositionTable::default(),
             killer_manager: KillerMovesManager::new(depth),
             stop: Arc::new(AtomicBool::new(false)),
+            info_callback: None,
+            current_move_callback: None,
+            root_move_penalty: None,
+            excluded_root_moves: Vec::new(),
         }
     }
 
@@ -335,6 +593,10 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
             transposition_table: TranspositionTable::default(),
             killer_manager: KillerMovesManager::new(depth),
             stop: Arc::new(AtomicBool::new(false)),
+            info_callback: None,
+            current_move_callback: None,
+            root_move_penalty: None,
+            excluded_root_moves: Vec::new(),
         }
     }
 
@@ -345,6 +607,10 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
             transposition_table: TranspositionTable::default(),
             killer_manager: KillerMovesManager::new(max_depth),
             stop: Arc::new(AtomicBool::new(false)),
+            info_callback: None,
+            current_move_callback: None,
+            root_move_penalty: None,
+            excluded_root_moves: Vec::new(),
         }
     }
 
@@ -353,11 +619,74 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
         self.killer_manager = KillerMovesManager::new(depth);
     }
 
+    /// Bound later searches by `limits`, in place of the depth, time and node
+    /// limits set before. The depth is left as it is for a search bounded by
+    /// nothing but depth, and without a `depth` given.
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        let depth = match limits.depth {
+            Some(depth) => Some(depth),
+            None if !limits.is_depth_only() => Some(MAX_LIMITED_SEARCH_DEPTH),
+            None => None,
+        };
+        if let Some(depth) = depth {
+            self.set_depth(depth);
+        }
+        self.set_time_limits(limits.time_limits());
+        self.set_node_limit(limits.node_limit());
+    }
+
+    /// Search for about `time_limit`, with the limits of
+    /// `TimeLimits::for_movetime`.
     pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
-        self.config.time_limit = time_limit;
+        self.set_time_limits(time_limit.map(TimeLimits::for_movetime));
+    }
+
+    /// Search within `time_limits`, e.g. from a game clock.
+    pub fn set_time_limits(&mut self, time_limits: Option<TimeLimits>) {
+        self.config.time_limits = time_limits;
+    }
+
+    /// Stop each search after roughly `node_limit` nodes (checked every 4096
+    /// nodes). The first iteration always completes.
+    pub fn set_node_limit(&mut self, node_limit: Option<usize>) {
+        self.config.node_limit = node_limit;
+    }
+
+    /// Search with `params` instead of the default heuristic constants.
+    pub fn set_params(&mut self, params: SearchParams) {
+        self.config.params = params;
+    }
+
+    pub fn params(&self) -> &SearchParams {
+        &self.config.params
     }
 
     /// Returns a clone of the stop flag Arc for use by a polling thread.
+    /// Report progress to `callback` after each completed depth of later
+    /// searches, or stop reporting with None.
+    pub fn set_info_callback(&mut self, callback: Option<SearchInfoCallback<M>>) {
+        self.info_callback = callback;
+    }
+
+    /// Report each root move of later searches to `callback` as its search
+    /// starts, or stop reporting with None.
+    pub fn set_current_move_callback(&mut self, callback: Option<CurrentMoveCallback<M>>) {
+        self.current_move_callback = callback;
+    }
+
+    /// Score each root move of later searches less `penalty` for the side to
+    /// move, or score them as searched with None.
+    pub fn set_root_move_penalty(&mut self, penalty: Option<RootMovePenalty<M>>) {
+        self.root_move_penalty = penalty;
+    }
+
+    /// Leave `moves` out of the root moves of later searches, e.g. the best
+    /// moves already found when looking for the next best line. A search that
+    /// leaves out some moves is not stored as the position's result.
+    pub fn set_excluded_root_moves(&mut self, moves: Vec<M>) {
+        self.excluded_root_moves = moves;
+    }
+
     pub fn stop_flag(&self) -> Arc<AtomicBool> {
         Arc::clone(&self.stop)
     }
@@ -372,6 +701,11 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
         self.stop.store(false, Ordering::Relaxed);
     }
 
+    /// Returns a clone of the pondering state for the thread that ends it.
+    pub fn ponder_state(&self) -> Arc<PonderState> {
+        Arc::clone(&self.config.ponder)
+    }
+
     pub fn set_parallel(&mut self, parallel: bool) {
         self.config.parallel = parallel;
     }
@@ -386,6 +720,12 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
         self.killer_manager.clear();
     }
 
+    /// Replace the transposition table with an empty one of at most
+    /// `size_mb` megabytes (the UCI `Hash` option).
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.transposition_table = TranspositionTable::new(size_mb);
+    }
+
     /// Reset stats and killers but keep transposition table entries.
     /// Useful for benchmarking multiple positions with shared TT.
     pub fn reset_stats_keep_tt(&mut self) {
@@ -417,10 +757,45 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
         self.stats.last_score
     }
 
+    /// Deepest iteration the last search completed, which is less than
+    /// `search_depth` when it was stopped by a time or node limit.
+    pub fn last_completed_depth(&self) -> Option<u8> {
+        self.stats.last_depth
+    }
+
     pub fn last_search_duration(&self) -> Option<Duration> {
         self.stats.last_duration
     }
 
+    /// Best move the transposition table holds for a position hash.
+    pub fn tt_best_move(&self, hash: u64) -> Option<M> {
+        self.transposition_table.best_move(hash)
+    }
+
+    /// The transposition table entry for a position hash, if any.
+    pub fn tt_entry(&self, hash: u64) -> Option<TTEntry<M>> {
+        self.transposition_table.entry(hash)
+    }
+
+    /// Snapshot of the transposition table, restorable with `tt_restore`.
+    pub fn tt_entries(&self) -> Vec<(u64, TTEntry<M>)> {
+        self.transposition_table.entries()
+    }
+
+    /// Store entries from `tt_entries`, e.g. saved by an earlier session.
+    pub fn tt_restore(&self, entries: impl IntoIterator<Item = (u64, TTEntry<M>)>) {
+        for (hash, entry) in entries {
+            self.transposition_table.store(
+                hash,
+                0,
+                entry.score,
+                entry.depth,
+                entry.bound_type,
+                entry.best_move,
+            );
+        }
+    }
+
     pub fn tt_hits(&self) -> usize {
         self.transposition_table.hits()
     }
@@ -441,6 +816,11 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
         self.transposition_table.size()
     }
 
+    /// The number of entries the transposition table has room for.
+    pub fn tt_capacity(&self) -> usize {
+        self.transposition_table.capacity()
+    }
+
     pub fn tt_probes(&self) -> usize {
         self.stats.tt_probes()
     }
@@ -489,6 +869,10 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
         self.stats.check_extensions()
     }
 
+    pub fn singular_extension_count(&self) -> usize {
+        self.stats.singular_extensions()
+    }
+
     fn increment_position_count(&self) {
         self.stats.increment();
     }
@@ -540,6 +924,49 @@ impl<M: Clone + Send + Sync + 'static> SearchContext<M> {
     fn increment_check_extensions(&self) {
         self.stats.increment_check_extensions();
     }
+
+    fn increment_singular_extensions(&self) {
+        self.stats.increment_singular_extensions();
+    }
+
+    fn record_ply(&self, ply: u8) {
+        self.stats.record_ply(ply);
+    }
+
+    /// The `score` of `root_move` less its penalty for the side to move, if a
+    /// penalty is set. Mate scores are left alone.
+    fn penalize_root_move(&self, score: i16, root_move: &M, maximizing_player: bool) -> i16 {
+        let Some(penalty) = &self.root_move_penalty else {
+            return score;
+        };
+        if is_mate_score(score) {
+            return score;
+        }
+        let penalty = penalty(root_move);
+        if maximizing_player {
+            score.saturating_sub(penalty)
+        } else {
+            score.saturating_add(penalty)
+        }
+    }
+
+    /// Send the root move about to be searched to the current move callback,
+    /// if one is set.
+    fn report_current_move(&self, depth: u8, current_move: &M, number: usize) {
+        let Some(callback) = &self.current_move_callback else {
+            return;
+        };
+        callback(&CurrentMove {
+            depth,
+            current_move: current_move.clone(),
+            number,
+            nodes: self.stats.count() - self.config.start_nodes,
+            elapsed: self
+                .config
+                .start_time
+                .map_or(Duration::ZERO, |start| start.elapsed()),
+        });
+    }
 }
 
 /// Applies a move, executes a closure with the new state, then undoes the move.
@@ -565,6 +992,32 @@ where
     result
 }
 
+/// Copies of the root position for a parallel root search. Searching a root
+/// move borrows one, plays the move and takes it back, so the copy is at the
+/// root again when it is returned and can be reused for the next move, depth
+/// or re-search. A copy is only made when every other one is in use.
+pub(super) struct StatePool<S> {
+    states: Mutex<Vec<S>>,
+}
+
+impl<S: Clone> StatePool<S> {
+    pub(super) fn new() -> Self {
+        Self {
+            states: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Run `f` on a copy of `root`, which must be the position every copy in
+    /// the pool is at.
+    pub(super) fn with_state<R>(&self, root: &S, f: impl FnOnce(&mut S) -> R) -> R {
+        let pooled = self.states.lock().expect("state pool lock").pop();
+        let mut state = pooled.unwrap_or_else(|| root.clone());
+        let result = f(&mut state);
+        self.states.lock().expect("state pool lock").push(state);
+        result
+    }
+}
+
 /// Updates best score and move if new score is better.
 /// Returns true if best_score was updated.
 fn update_best<M: Clone>(
@@ -587,46 +1040,6 @@ fn update_best<M: Clone>(
     is_better
 }
 
-/// Reorders moves for better alpha-beta pruning.
-///
-/// Priority: 1) PV move from transposition table, 2) Killer moves, 3) Other moves.
-/// The PV move is placed first if present, followed by killer moves, then remaining moves.
-/// Returns the number of moves actually placed at the front (PV + killers found in the list).
-fn reorder_moves_with_heuristics<M>(
-    moves: &mut [M],
-    pv_move: Option<&M>,
-    killers: &[Option<M>; 2],
-) -> usize
-where
-    M: PartialEq + Clone,
-{
-    let mut placed = 0;
-
-    // Move PV to front (highest priority)
-    if let Some(pv) = pv_move {
-        if let Some(pos) = moves.iter().position(|m| m == pv) {
-            if pos > 0 {
-                moves[0..=pos].rotate_right(1);
-            }
-            placed += 1;
-        }
-    }
-
-    // Move killers to front after PV
-    let killer_start = placed;
-    for killer in killers.iter().flatten().rev() {
-        if let Some(pos) = moves.iter().position(|m| m == killer) {
-            // Only move if beyond insertion point and not the PV move
-            if pos > killer_start && Some(killer) != pv_move {
-                moves[killer_start..=pos].rotate_right(1);
-                placed += 1;
-            }
-        }
-    }
-
-    placed
-}
-
 /// Searches for the best move using alpha-beta pruning with iterative deepening.
 ///
 /// This is the main entry point for the search algorithm. It performs iterative deepening,
@@ -635,7 +1048,8 @@ where
 ///
 /// # Returns
 ///
-/// - `Ok(best_move)` - The best move found at the target depth
+/// - `Ok(best_move)` - The best move found at the target depth, or at the last
+///   depth completed when stopped early (the first ordered move if none was)
 /// - `Err(SearchError::DepthTooLow)` - If search depth is < 1
 /// - `Err(SearchError::NoAvailableMoves)` - If no legal moves available
 ///
@@ -677,6 +1091,10 @@ where
 
     let start = Instant::now();
     context.config.start_time = Some(start);
+    context.config.start_nodes = context.stats.count();
+    context.transposition_table.new_search();
+    context.config.node_limit_armed = false;
+    context.stats.selective_depth.store(0, Ordering::Relaxed);
     let current_player_is_maximizing = state.is_maximizing_player();
     let mut candidates = move_generator.generate_moves(state);
 
@@ -686,6 +1104,22 @@ where
 
     move_orderer.order_moves(candidates.as_mut(), state);
 
+    // Excluded moves go to the back, out of the searched part of the list
+    let mut searched_count = 0;
+    for index in 0..candidates.len() {
+        if !context
+            .excluded_root_moves
+            .contains(&candidates.as_ref()[index])
+        {
+            candidates.as_mut()[searched_count..=index].rotate_right(1);
+            searched_count += 1;
+        }
+    }
+    if searched_count == 0 {
+        return Err(SearchError::NoAvailableMoves);
+    }
+    let restricted = searched_count < candidates.len();
+
     let hash = state.position_hash();
 
     // Iterative deepening: search at increasing depths, using previous results for move ordering
@@ -696,30 +1130,63 @@ where
         i16::MAX
     };
 
-    const ASPIRATION_WINDOW: i16 = 50;
+    let aspiration_window = context.config.params.aspiration_window;
+
+    // Reused by every parallel root search below, all of which start here
+    let root_states = StatePool::new();
 
+    // Moves the soft time limit as the best move and score settle or not
+    let mut time_manager = context.config.time_limits.map(TimeManager::new);
+
+    let mut completed_depth = 0;
     for depth in 1..=target_depth {
         // Check stop flag at the top of each depth iteration
         if context.should_stop() {
             break;
         }
 
-        // Soft time limit: don't start a new depth if >50% of time budget used.
-        // Skip for depth 1 so we always complete at least one iteration.
-        if depth > 1 && context.config.soft_time_exceeded() {
+        // Soft time limit: don't start a new depth once the time manager's
+        // soft limit has passed. Skip for depth 1 so we always complete at
+        // least one iteration.
+        if depth > 1
+            && time_manager
+                .as_ref()
+                .is_some_and(|time_manager| context.config.soft_time_exceeded(time_manager))
+        {
             break;
         }
 
-        // Check if we already have an exact result at this depth from TT
-        if let (Some(score), Some(ref mv)) =
-            context
-                .transposition_table
-                .probe_with_move(hash, depth, i16::MIN, i16::MAX)
-        {
+        // Node budget: once an iteration has completed, stop when the budget is spent
+        // and allow the limit to abort the next iteration mid-search.
+        context.config.node_limit_armed = best_move.is_some();
+        if context.config.node_limit_exceeded(context.stats.count()) {
+            break;
+        }
+
+        // Check if we already have an exact result at this depth from TT,
+        // which knows nothing of root move penalties or excluded moves
+        let tt_hit = match context.root_move_penalty {
+            None if !restricted => {
+                context
+                    .transposition_table
+                    .probe_with_move(hash, 0, depth, i16::MIN, i16::MAX)
+            }
+            _ => (None, None),
+        };
+        if let (Some(score), Some(ref mv)) = tt_hit {
             if candidates.as_ref().iter().any(|c| c == mv) {
                 debug!("Using transposition table hit at depth {}", depth);
+                record_depth(
+                    &mut time_manager,
+                    &best_move,
+                    mv,
+                    score,
+                    current_player_is_maximizing,
+                );
                 best_move = Some(mv.clone());
                 best_score = score;
+                completed_depth = depth;
+                report_iteration(context, state, move_generator, mv, score, depth, start);
                 // Continue to next depth to ensure we search to target_depth
                 continue;
             }
@@ -738,8 +1205,8 @@ where
         let (mut window_alpha, mut window_beta) =
             if depth >= 4 && best_score > i16::MIN / 2 + 100 && best_score < i16::MAX / 2 - 100 {
                 (
-                    best_score.saturating_sub(ASPIRATION_WINDOW),
-                    best_score.saturating_add(ASPIRATION_WINDOW),
+                    best_score.saturating_sub(aspiration_window),
+                    best_score.saturating_add(aspiration_window),
                 )
             } else {
                 (i16::MIN, i16::MAX)
@@ -749,10 +1216,11 @@ where
             search_root_parallel(
                 context,
                 state,
+                &root_states,
                 move_generator,
                 evaluator,
                 move_orderer,
-                &candidates,
+                &candidates.as_ref()[..searched_count],
                 depth,
                 current_player_is_maximizing,
                 window_alpha,
@@ -765,7 +1233,7 @@ where
                 move_generator,
                 evaluator,
                 move_orderer,
-                &candidates,
+                &candidates.as_ref()[..searched_count],
                 depth,
                 current_player_is_maximizing,
                 window_alpha,
@@ -798,10 +1266,11 @@ where
                     search_root_parallel(
                         context,
                         state,
+                        &root_states,
                         move_generator,
                         evaluator,
                         move_orderer,
-                        &candidates,
+                        &candidates.as_ref()[..searched_count],
                         depth,
                         current_player_is_maximizing,
                         window_alpha,
@@ -814,7 +1283,7 @@ where
                         move_generator,
                         evaluator,
                         move_orderer,
-                        &candidates,
+                        &candidates.as_ref()[..searched_count],
                         depth,
                         current_player_is_maximizing,
                         window_alpha,
@@ -846,35 +1315,151 @@ where
         };
 
         if let Some(mv) = move_found {
+            record_depth(
+                &mut time_manager,
+                &best_move,
+                &mv,
+                score,
+                current_player_is_maximizing,
+            );
+            report_iteration(context, state, move_generator, &mv, score, depth, start);
             best_move = Some(mv);
             best_score = score;
+            completed_depth = depth;
         }
     }
 
-    let best_move = best_move.ok_or(SearchError::NoAvailableMoves)?;
+    // Stopped before depth 1 completed: the first ordered move is still a move,
+    // and says nothing about the position worth storing
+    let Some(best_move) = best_move else {
+        return Ok(candidates.as_ref()[0].clone());
+    };
 
-    context.increment_tt_stores();
-    context.transposition_table.store(
-        hash,
-        best_score,
-        target_depth,
-        BoundType::Exact,
-        Some(best_move.clone()),
-    );
+    // Store the depth actually searched: a search cut short by a time or node
+    // limit must not claim the full target depth for later searches. A search
+    // of some of the moves says nothing of the position as a whole.
+    if !restricted {
+        context.increment_tt_stores();
+        context.transposition_table.store(
+            hash,
+            0,
+            best_score,
+            completed_depth,
+            BoundType::Exact,
+            Some(best_move.clone()),
+        );
+    }
 
-    context.stats.record_result(best_score, start.elapsed());
+    context
+        .stats
+        .record_result(best_score, completed_depth, start.elapsed());
 
     Ok(best_move)
 }
 
+/// Tell the time manager, if the search has one, about a completed depth
+/// whose best move is `best_move` after `previous_best` and whose score is
+/// `score` for the maximizing player.
+fn record_depth<M: PartialEq>(
+    time_manager: &mut Option<TimeManager>,
+    previous_best: &Option<M>,
+    best_move: &M,
+    score: i16,
+    maximizing: bool,
+) {
+    if let Some(time_manager) = time_manager {
+        let changed = previous_best
+            .as_ref()
+            .is_some_and(|previous| previous != best_move);
+        let score = if maximizing {
+            score
+        } else {
+            score.saturating_neg()
+        };
+        time_manager.record_depth(changed, score);
+    }
+}
+
+/// Send the result of a completed depth to the info callback, if one is set.
+fn report_iteration<S, G>(
+    context: &SearchContext<G::Move>,
+    state: &mut S,
+    move_generator: &G,
+    best_move: &G::Move,
+    score: i16,
+    depth: u8,
+    start: Instant,
+) where
+    S: GameState,
+    G: MoveGenerator<S>,
+    G::Move: GameMove<State = S>,
+{
+    let Some(callback) = &context.info_callback else {
+        return;
+    };
+    let principal_variation = principal_variation(
+        context,
+        state,
+        move_generator,
+        best_move,
+        usize::from(depth),
+    );
+    callback(&SearchInfo {
+        depth,
+        seldepth: context.stats.selective_depth().max(depth),
+        score,
+        nodes: context.stats.count() - context.config.start_nodes,
+        elapsed: start.elapsed(),
+        principal_variation,
+    });
+}
+
+/// `first_move` followed by the best replies stored in the transposition
+/// table, up to `max_length` moves. Stops at the first stored move that is not
+/// legal in its position. The state is left unchanged.
+fn principal_variation<S, G>(
+    context: &SearchContext<G::Move>,
+    state: &mut S,
+    move_generator: &G,
+    first_move: &G::Move,
+    max_length: usize,
+) -> Vec<G::Move>
+where
+    S: GameState,
+    G: MoveGenerator<S>,
+    G::Move: GameMove<State = S>,
+{
+    let mut line: Vec<G::Move> = Vec::new();
+    let mut next = Some(first_move.clone());
+    while let Some(candidate) = next.take() {
+        if line.len() >= max_length {
+            break;
+        }
+        let legal = move_generator.generate_moves(state);
+        if !legal.as_ref().contains(&candidate) || candidate.apply(state).is_err() {
+            break;
+        }
+        state.toggle_turn();
+        next = context.transposition_table.best_move(state.position_hash());
+        line.push(candidate);
+    }
+    for game_move in line.iter().rev() {
+        game_move
+            .undo(state)
+            .expect("undoing a principal variation move should succeed");
+        state.toggle_turn();
+    }
+    line
+}
+
 #[allow(clippy::too_many_arguments)]
-fn search_root_sequential<S, G, E, O, C>(
+fn search_root_sequential<S, G, E, O>(
     context: &SearchContext<G::Move>,
     state: &mut S,
     move_generator: &G,
     evaluator: &E,
     move_orderer: &O,
-    candidates: &C,
+    candidates: &[G::Move],
     depth: u8,
     maximizing_player: bool,
     alpha: i16,
@@ -882,9 +1467,8 @@ fn search_root_sequential<S, G, E, O, C>(
 ) -> Result<(i16, Option<G::Move>), SearchError>
 where
     S: GameState,
-    G: MoveGenerator<S, MoveList = C>,
+    G: MoveGenerator<S>,
     G::Move: GameMove<State = S>,
-    C: MoveCollection<G::Move>,
     E: Evaluator<S>,
     O: MoveOrderer<S, G::Move>,
 {
@@ -897,7 +1481,8 @@ where
     let mut current_alpha = alpha;
     let mut current_beta = beta;
 
-    for game_move in candidates.as_ref().iter() {
+    for (index, game_move) in candidates.iter().enumerate() {
+        context.report_current_move(depth, game_move, index + 1);
         let score = with_move_applied(game_move, state, |state| {
             alpha_beta_minimax(
                 context,
@@ -906,13 +1491,14 @@ where
                 evaluator,
                 move_orderer,
                 depth - 1,
-                0, // ply starts at 0 for root
+                1, // root moves lead to ply 1
                 current_alpha,
                 current_beta,
                 !maximizing_player,
                 true,
             )
         })?;
+        let score = context.penalize_root_move(score, game_move, maximizing_player);
 
         update_best(
             score,
@@ -936,13 +1522,14 @@ where
 }
 
 #[allow(clippy::too_many_arguments)]
-fn search_root_parallel<S, G, E, O, C>(
+fn search_root_parallel<S, G, E, O>(
     context: &SearchContext<G::Move>,
     state: &S,
+    root_states: &StatePool<S>,
     move_generator: &G,
     evaluator: &E,
     move_orderer: &O,
-    candidates: &C,
+    candidates: &[G::Move],
     depth: u8,
     maximizing_player: bool,
     alpha: i16,
@@ -950,35 +1537,40 @@ fn search_root_parallel<S, G, E, O, C>(
 ) -> Result<(i16, Option<G::Move>), SearchError>
 where
     S: GameState + Clone,
-    G: MoveGenerator<S, MoveList = C> + Sync,
+    G: MoveGenerator<S> + Sync,
     G::Move: GameMove<State = S>,
-    C: MoveCollection<G::Move> + Sync,
     E: Evaluator<S> + Sync,
     O: MoveOrderer<S, G::Move> + Sync,
 {
-    let results: Vec<_> = candidates
-        .as_ref()
-        .par_iter()
-        .map(|game_move| {
-            let mut cloned_state = state.clone();
-
-            let result = with_move_applied(game_move, &mut cloned_state, |state| {
-                alpha_beta_minimax(
-                    context,
-                    state,
-                    move_generator,
-                    evaluator,
-                    move_orderer,
-                    depth - 1,
-                    0, // ply starts at 0 for root
-                    alpha,
-                    beta,
-                    !maximizing_player,
-                    true,
-                )
+    #[cfg(feature = "parallel")]
+    let moves = candidates.par_iter().enumerate();
+    #[cfg(not(feature = "parallel"))]
+    let moves = candidates.iter().enumerate();
+    let results: Vec<_> = moves
+        .map(|(index, game_move)| {
+            context.report_current_move(depth, game_move, index + 1);
+            let result = root_states.with_state(state, |root_state| {
+                with_move_applied(game_move, root_state, |state| {
+                    alpha_beta_minimax(
+                        context,
+                        state,
+                        move_generator,
+                        evaluator,
+                        move_orderer,
+                        depth - 1,
+                        1, // root moves lead to ply 1
+                        alpha,
+                        beta,
+                        !maximizing_player,
+                        true,
+                    )
+                })
             });
 
-            result.map(|score| (score, game_move.clone()))
+            result.map(|score| {
+                let score = context.penalize_root_move(score, game_move, maximizing_player);
+                (score, game_move.clone())
+            })
         })
         .collect();
 
@@ -1016,6 +1608,13 @@ where
 
 const MAX_QUIESCENCE_DEPTH: u8 = 8;
 
+/// Shallowest depth at which the TT move is tested for a singular extension.
+const SINGULAR_MIN_DEPTH: u8 = 6;
+
+/// How far below the TT move's score, per ply of depth, every other move
+/// must stay for it to count as singular.
+const SINGULAR_MARGIN_PER_PLY: i16 = 3;
+
 /// Quiescence search to avoid the horizon effect.
 ///
 /// Extends the search beyond the nominal depth by only considering tactical moves.
@@ -1031,6 +1630,7 @@ const MAX_QUIESCENCE_DEPTH: u8 = 8;
 /// - `beta` - Upper bound of search window
 /// - `maximizing_player` - True if current player wants to maximize score
 /// - `qdepth` - Current quiescence depth (limited to MAX_QUIESCENCE_DEPTH)
+/// - `ply` - Current distance from root, for scoring mates
 ///
 /// # Returns
 ///
@@ -1048,6 +1648,7 @@ fn quiescence_search<S, G, E, O>(
     beta: i16,
     maximizing_player: bool,
     qdepth: u8,
+    ply: u8,
 ) -> Result<i16, SearchError>
 where
     S: GameState,
@@ -1058,12 +1659,13 @@ where
 {
     context.increment_position_count();
     context.increment_quiescence();
+    context.record_ply(ply);
 
     // Probe TT for cached quiescence result
     context.increment_tt_probes();
     let (cutoff_score, _tt_move) = context
         .transposition_table
-        .probe_with_move(hash, qdepth, alpha, beta);
+        .probe_with_move(hash, ply, qdepth, alpha, beta);
 
     // Track miss
     if cutoff_score.is_none() {
@@ -1079,20 +1681,20 @@ where
     let original_alpha = alpha;
 
     if qdepth >= MAX_QUIESCENCE_DEPTH {
-        let score = evaluator.evaluate(state, 0);
+        let score = mate_at_ply(evaluator.evaluate(state, 0), ply);
         context.increment_tt_stores();
         context
             .transposition_table
-            .store(hash, score, qdepth, BoundType::Exact, None);
+            .store(hash, ply, score, qdepth, BoundType::Exact, None);
         return Ok(score);
     }
 
-    let stand_pat = evaluator.evaluate(state, 0);
+    let stand_pat = mate_at_ply(evaluator.evaluate(state, 0), ply);
     if stand_pat >= beta {
         context.increment_tt_stores();
         context
             .transposition_table
-            .store(hash, beta, qdepth, BoundType::Lower, None);
+            .store(hash, ply, beta, qdepth, BoundType::Lower, None);
         return Ok(beta);
     }
     if stand_pat > alpha {
@@ -1100,32 +1702,35 @@ where
     }
 
     context.increment_move_gen();
-    let candidates = move_generator.generate_tactical_moves(state);
-    // Collect tactical moves (generate_tactical_moves may still include non-tactical
-    // moves for generic games that don't override the default)
-    let mut tactical_moves: SmallVec<[G::Move; 32]> = candidates
-        .as_ref()
-        .iter()
-        .filter(|mv| mv.is_tactical(state))
-        .cloned()
-        .collect();
+    let mut tactical_moves = move_generator.generate_tactical_moves(state);
+    // Quiet checks only at the first ply, so checks can't go on to the depth limit
+    let mut quiet_checks = if qdepth == 0 {
+        move_generator.generate_quiet_checks(state)
+    } else {
+        None
+    };
+    let quiet_checks_empty = quiet_checks.as_ref().is_none_or(|moves| moves.is_empty());
 
-    if tactical_moves.is_empty() {
+    if tactical_moves.is_empty() && quiet_checks_empty {
         context.increment_tt_stores();
         context
             .transposition_table
-            .store(hash, stand_pat, qdepth, BoundType::Exact, None);
+            .store(hash, ply, stand_pat, qdepth, BoundType::Exact, None);
         return Ok(stand_pat);
     }
 
-    move_orderer.order_moves(&mut tactical_moves, state);
+    move_orderer.order_moves(tactical_moves.as_mut(), state);
+    if let Some(quiet_checks) = quiet_checks.as_mut() {
+        move_orderer.order_moves(quiet_checks.as_mut(), state);
+    }
 
     let mut best_score = stand_pat;
 
     // Delta pruning: get maximum possible tactical gain
     let max_gain = evaluator.max_tactical_gain(state);
 
-    for game_move in tactical_moves.iter() {
+    let quiet_checks = quiet_checks.iter().flat_map(|moves| moves.as_ref());
+    for game_move in tactical_moves.as_ref().iter().chain(quiet_checks) {
         // Delta pruning: skip moves that cannot possibly raise alpha
         // Only apply if max_gain is reasonable (not i16::MAX which means no pruning)
         if max_gain < i16::MAX {
@@ -1144,7 +1749,7 @@ where
         state.toggle_turn();
 
         let child_hash = state.position_hash();
-        let score = -quiescence_search(
+        let score = quiescence_search(
             context,
             state,
             child_hash,
@@ -1155,18 +1760,20 @@ where
             -alpha,
             !maximizing_player,
             qdepth + 1,
-        )?;
+            ply + 1,
+        );
 
         game_move
             .undo(state)
             .expect("move undo should succeed in quiescence");
         state.toggle_turn();
+        let score = -score?;
 
         if score >= beta {
             context.increment_tt_stores();
             context
                 .transposition_table
-                .store(hash, beta, qdepth, BoundType::Lower, None);
+                .store(hash, ply, beta, qdepth, BoundType::Lower, None);
             return Ok(beta);
         }
         if score > alpha {
@@ -1186,11 +1793,99 @@ where
     context.increment_tt_stores();
     context
         .transposition_table
-        .store(hash, best_score, qdepth, bound_type, None);
+        .store(hash, ply, best_score, qdepth, bound_type, None);
 
     Ok(best_score)
 }
 
+/// Whether `tt_move` is the only move that holds the position with `hash`:
+/// its stored score is a bound from a search at least `depth - 3` deep that
+/// is not a mate, and every other move, searched at half the depth with a
+/// null window, falls short of that score less a depth-scaled margin.
+#[allow(clippy::too_many_arguments)]
+fn tt_move_is_singular<S, G, E, O>(
+    context: &SearchContext<G::Move>,
+    state: &mut S,
+    hash: u64,
+    tt_move: &G::Move,
+    move_generator: &G,
+    evaluator: &E,
+    move_orderer: &O,
+    depth: u8,
+    ply: u8,
+    maximizing_player: bool,
+) -> Result<bool, SearchError>
+where
+    S: GameState,
+    G: MoveGenerator<S>,
+    G::Move: GameMove<State = S>,
+    E: Evaluator<S>,
+    O: MoveOrderer<S, G::Move>,
+{
+    let Some(entry) = context.transposition_table.entry(hash) else {
+        return Ok(false);
+    };
+    // A bound on the side of the score the TT move has to hold
+    let holds = if maximizing_player {
+        BoundType::Lower
+    } else {
+        BoundType::Upper
+    };
+    let tt_score = from_table(entry.score, ply);
+    if entry.best_move.as_ref() != Some(tt_move)
+        || entry.depth + 3 < depth
+        || !(entry.bound_type == holds || entry.bound_type == BoundType::Exact)
+        || is_mate_score(tt_score)
+    {
+        return Ok(false);
+    }
+
+    let margin = SINGULAR_MARGIN_PER_PLY * i16::from(depth);
+    let singular_bound = if maximizing_player {
+        tt_score.saturating_sub(margin)
+    } else {
+        tt_score.saturating_add(margin)
+    };
+    let moves = move_generator.generate_moves(state);
+    for game_move in moves.as_ref() {
+        if game_move == tt_move {
+            continue;
+        }
+        let score = with_move_applied(game_move, state, |state| {
+            alpha_beta_minimax(
+                context,
+                state,
+                move_generator,
+                evaluator,
+                move_orderer,
+                (depth - 1) / 2,
+                ply + 1,
+                if maximizing_player {
+                    singular_bound - 1
+                } else {
+                    singular_bound
+                },
+                if maximizing_player {
+                    singular_bound
+                } else {
+                    singular_bound + 1
+                },
+                !maximizing_player,
+                true,
+            )
+        })?;
+        let reaches = if maximizing_player {
+            score >= singular_bound
+        } else {
+            score <= singular_bound
+        };
+        if reaches {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
 /// Core alpha-beta minimax search with pruning.
 ///
 /// Recursively searches the game tree using alpha-beta pruning. The [alpha, beta] window
@@ -1199,6 +1894,8 @@ where
 /// # Search Optimizations
 ///
 /// - **Check Extensions**: Extends depth by 1 when current player is in check
+/// - **Singular Extensions**: Extends the TT move by 1 when every other move falls
+///   well short of it
 /// - **Transposition Table Lookup**: Checks for cached results at this position
 /// - **Move Ordering**: Prioritizes PV move, killer moves, then other moves
 /// - **Quiescence Extension**: Calls quiescence_search at depth 0 to avoid horizon effect
@@ -1206,7 +1903,8 @@ where
 /// # Parameters
 ///
 /// - `depth` - Remaining search depth (decrements each ply)
-/// - `ply` - Current distance from root (increments each ply, used for killer moves)
+/// - `ply` - Current distance from root (increments each ply, used for killer moves
+///   and mate scores)
 /// - `alpha` - Lower bound of search window
 /// - `beta` - Upper bound of search window
 /// - `maximizing_player` - True if current player wants to maximize score
@@ -1237,10 +1935,13 @@ where
     O: MoveOrderer<S, G::Move>,
 {
     context.increment_position_count();
+    context.record_ply(ply);
 
-    // Periodically check the stop flag and hard time limit (every 4096 nodes)
+    // Periodically check the stop flag, hard time limit, and node budget (every 4096 nodes)
     if context.stats.count() & 0xFFF == 0
-        && (context.should_stop() || context.config.hard_time_exceeded())
+        && (context.should_stop()
+            || context.config.hard_time_exceeded()
+            || context.config.node_limit_exceeded(context.stats.count()))
     {
         return Err(SearchError::Stopped);
     }
@@ -1248,7 +1949,7 @@ where
     // Check extension: when in check, extend search by 1 ply to avoid missing
     // critical tactical sequences at the horizon. Capped by ply distance from
     // root to prevent unbounded growth in perpetual check positions.
-    let in_check = evaluator.is_in_check(state);
+    let in_check = state.is_in_check();
     let depth = if in_check && ply < 64 {
         context.increment_check_extensions();
         depth + 1
@@ -1257,8 +1958,6 @@ where
     };
 
     // Mate distance pruning: if we already know a shorter mate exists, prune.
-    // Uses WHITE_WINS = i16::MAX / 2 as the base mate score.
-    const MATE_SCORE: i16 = i16::MAX / 2;
     {
         let mating_score = MATE_SCORE - ply as i16;
         if mating_score < beta {
@@ -1288,7 +1987,7 @@ where
     context.increment_tt_probes();
     let (cutoff_score, tt_move) = context
         .transposition_table
-        .probe_with_move(hash, depth, alpha, beta);
+        .probe_with_move(hash, ply, depth, alpha, beta);
 
     // Track if we got a TT miss
     if cutoff_score.is_none() && tt_move.is_none() {
@@ -1330,8 +2029,11 @@ where
             beta,
             !maximizing_player,
             false,
-        )?;
+        );
+        // Undone before a stopped search returns, so the state is back at the
+        // root for the next root move searched on it
         state.undo_null_move();
+        let null_score = null_score?;
 
         if maximizing_player && null_score >= beta {
             context.increment_null_move_cutoffs();
@@ -1345,9 +2047,11 @@ where
 
     // Compute static eval lazily for RFP and futility pruning at shallow depths.
     // Cached here to avoid redundant evaluation calls.
-    let rfp_margin = evaluator.rfp_margin(depth);
+    let rfp_margin = evaluator
+        .rfp_margin(depth)
+        .map(|margin| context.config.params.futility_margin(margin));
     let static_eval = if depth > 0 && !skip_speculative_pruning && rfp_margin.is_some() {
-        Some(evaluator.evaluate(state, depth))
+        Some(mate_at_ply(evaluator.evaluate(state, depth), ply))
     } else {
         None
     };
@@ -1371,7 +2075,8 @@ where
     // Only when not in check. Conservative margin to avoid missing tactics.
     if !skip_speculative_pruning && depth == 1 {
         let razor_margin = 300_i16;
-        let eval = static_eval.unwrap_or_else(|| evaluator.evaluate(state, depth));
+        let eval =
+            static_eval.unwrap_or_else(|| mate_at_ply(evaluator.evaluate(state, depth), ply));
         if maximizing_player && eval + razor_margin < alpha {
             return quiescence_search(
                 context,
@@ -1384,6 +2089,7 @@ where
                 beta,
                 maximizing_player,
                 0,
+                ply,
             );
         }
         if !maximizing_player && eval - razor_margin > beta {
@@ -1398,6 +2104,7 @@ where
                 beta,
                 maximizing_player,
                 0,
+                ply,
             );
         }
     }
@@ -1414,23 +2121,38 @@ where
             beta,
             maximizing_player,
             0,
+            ply,
         );
     }
 
-    context.increment_move_gen();
-    let mut candidates = move_generator.generate_moves(state);
-
-    if candidates.is_empty() {
-        let score = evaluator.evaluate(state, depth);
-        return Ok(score);
-    }
+    // Singular extension: the TT move is searched a ply deeper when no other
+    // move comes close to its stored score. Capped by ply like check extensions.
+    let singular_move = match &tt_move {
+        Some(tt_move) if depth >= SINGULAR_MIN_DEPTH && ply < 64 => {
+            let singular = tt_move_is_singular(
+                context,
+                state,
+                hash,
+                tt_move,
+                move_generator,
+                evaluator,
+                move_orderer,
+                depth,
+                ply,
+                maximizing_player,
+            )?;
+            if singular {
+                context.increment_singular_extensions();
+            }
+            singular.then(|| tt_move.clone())
+        }
+        _ => None,
+    };
 
-    // Place PV move and killers at the front for best ordering.
-    // Remaining moves are ordered incrementally via pick_next() to avoid sorting
-    // moves that are never searched due to beta cutoffs.
-    let killers = context.get_killers(ply);
-    let heuristic_count =
-        reorder_moves_with_heuristics(candidates.as_mut(), tt_move.as_ref(), &killers);
+    // Moves are generated in stages as they are searched: the TT move, good
+    // tactical moves, killers, quiet moves, then losing tactical moves
+    context.increment_move_gen();
+    let mut picker = MovePicker::<S, G>::new(tt_move, context.get_killers(ply));
 
     let mut best_move = None;
     let mut best_score = if maximizing_player {
@@ -1445,22 +2167,17 @@ where
     // Reuses the same margin and static_eval already computed for RFP above — both use
     // rfp_margin(depth) at the same depth, so whenever do_futility is true, static_eval
     // is guaranteed to already be Some from the RFP computation.
-    let futility_margin = evaluator.rfp_margin(depth);
+    let futility_margin = rfp_margin;
     let do_futility = futility_margin.is_some() && !skip_speculative_pruning;
 
-    let moves = candidates.as_mut();
-    for i in 0..moves.len() {
-        // Incrementally select the best remaining move for positions beyond those
-        // already placed by PV/killer heuristics.
-        if i >= heuristic_count {
-            move_orderer.pick_next(moves, i, state);
-        }
-        let game_move = &moves[i];
+    while let Some(game_move) = picker.next(state, move_generator, move_orderer) {
+        let game_move = &game_move;
         move_count += 1;
         let is_first_move = move_count == 1;
 
         let score = if is_first_move {
-            // Search first move with full window
+            // Search first move with full window, a ply deeper if it is singular
+            let extension = u8::from(singular_move.as_ref() == Some(game_move));
             with_move_applied(game_move, state, |state| {
                 alpha_beta_minimax(
                     context,
@@ -1468,7 +2185,7 @@ where
                     move_generator,
                     evaluator,
                     move_orderer,
-                    depth - 1,
+                    depth - 1 + extension,
                     ply + 1,
                     alpha,
                     beta,
@@ -1511,7 +2228,7 @@ where
             let do_lmr = depth >= 3 && move_count > 3 && !is_tactical && !in_check;
             let reduction = if do_lmr {
                 // Logarithmic reduction: deeper depths and later moves get larger reductions.
-                let r = 1.0 + (depth as f64).ln() * (move_count as f64).ln() / 2.0;
+                let r = context.config.params.lmr_reduction(depth, move_count);
                 // Clamp: at least 1, at most depth-2 (always search at least 1 ply).
                 (r.floor() as u8).clamp(1, depth.saturating_sub(2).max(1))
             } else {
@@ -1632,6 +2349,10 @@ where
         }
     }
 
+    if move_count == 0 {
+        return Ok(mate_at_ply(evaluator.evaluate(state, depth), ply));
+    }
+
     let bound_type = if best_score <= original_alpha {
         BoundType::Upper
     } else if best_score >= beta {
@@ -1643,7 +2364,7 @@ where
     context.increment_tt_stores();
     context
         .transposition_table
-        .store(hash, best_score, depth, bound_type, best_move);
+        .store(hash, ply, best_score, depth, bound_type, best_move);
 
     Ok(best_score)
 }
@@ -10,11 +10,13 @@
 //! - Alpha-beta pruning (beta cutoffs, score boundaries, best move positions)
 //! - Iterative deepening (PV move ordering, TT usage across depths, consistency)
 //! - Null move pruning (depth requirements, check/endgame conditions)
+//! - Singular extensions (the only winning move, shallow depths)
 //! - Depth edge cases (depth 1, single/two moves)
 //! - Parallel vs sequential search consistency
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 
 use super::*;
 
@@ -56,6 +58,23 @@ struct NimMove {
     take: u8,
 }
 
+/// Packs a move whose only field is `take` for the transposition table.
+macro_rules! impl_take_table_move {
+    ($move_type:ident) => {
+        impl TableMove for $move_type {
+            fn pack(&self) -> u32 {
+                u32::from(self.take)
+            }
+
+            fn unpack(packed: u32) -> Option<Self> {
+                Some(Self { take: packed as u8 })
+            }
+        }
+    };
+}
+
+impl_take_table_move!(NimMove);
+
 impl GameMove for NimMove {
     type State = NimState;
     type Error = &'static str;
@@ -105,7 +124,7 @@ impl Evaluator<NimState> for NimEvaluator {
             }
         } else {
             // pile % 4 == 0 is a losing position for the player to move
-            if state.pile % 4 == 0 {
+            if state.pile.is_multiple_of(4) {
                 if state.is_player_one_turn {
                     -100
                 } else {
@@ -182,6 +201,41 @@ fn test_nim_finds_winning_move_from_7() {
     );
 }
 
+#[test]
+fn test_singular_extension_fires_on_the_only_winning_move() {
+    // Wherever the pile isn't a multiple of 4, one move wins and the others
+    // lose, so the TT move of a deep enough node is singular
+    let mut state = NimState::new(23);
+    let mut context = SearchContext::<NimMove>::new(10);
+
+    let best_move = alpha_beta_search(
+        &mut context,
+        &mut state,
+        &NimMoveGenerator,
+        &NimEvaluator,
+        &NoOpMoveOrderer,
+    )
+    .unwrap();
+
+    assert_eq!(best_move.take, 3);
+    assert!(
+        context.singular_extension_count() > 0,
+        "Singular extensions should fire when only one move wins"
+    );
+
+    // None at depths too shallow to test for them
+    let mut context = SearchContext::<NimMove>::new(5);
+    alpha_beta_search(
+        &mut context,
+        &mut NimState::new(23),
+        &NimMoveGenerator,
+        &NimEvaluator,
+        &NoOpMoveOrderer,
+    )
+    .unwrap();
+    assert_eq!(context.singular_extension_count(), 0);
+}
+
 #[test]
 fn test_nim_losing_position() {
     let mut state = NimState::new(4);
@@ -437,6 +491,19 @@ fn test_quiescence_search_with_tactical_moves() {
         is_tactical: bool,
     }
 
+    impl TableMove for TacticalNimMove {
+        fn pack(&self) -> u32 {
+            u32::from(self.take) | u32::from(self.is_tactical) << 8
+        }
+
+        fn unpack(packed: u32) -> Option<Self> {
+            Some(Self {
+                take: packed as u8,
+                is_tactical: packed >> 8 != 0,
+            })
+        }
+    }
+
     impl GameMove for TacticalNimMove {
         type State = NimState;
         type Error = &'static str;
@@ -538,6 +605,8 @@ fn test_quiescence_depth_limit() {
         take: u8,
     }
 
+    impl_take_table_move!(AlwaysTacticalMove);
+
     impl GameMove for AlwaysTacticalMove {
         type State = NimState;
         type Error = &'static str;
@@ -892,6 +961,8 @@ fn test_quiescence_beta_cutoff() {
         take: u8,
     }
 
+    impl_take_table_move!(HighValueTacticalMove);
+
     impl GameMove for HighValueTacticalMove {
         type State = NimState;
         type Error = &'static str;
@@ -1151,6 +1222,62 @@ fn test_parallel_vs_sequential_same_result() {
     );
 }
 
+#[test]
+fn test_state_pool_copies_the_root_once_per_thread() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[cfg(feature = "parallel")]
+    use rayon::prelude::*;
+
+    use super::search::StatePool;
+
+    struct CountedState {
+        clones: Arc<AtomicUsize>,
+        moves: Vec<usize>,
+    }
+
+    impl Clone for CountedState {
+        fn clone(&self) -> Self {
+            self.clones.fetch_add(1, Ordering::SeqCst);
+            Self {
+                clones: self.clones.clone(),
+                moves: self.moves.clone(),
+            }
+        }
+    }
+
+    let root = CountedState {
+        clones: Arc::new(AtomicUsize::new(0)),
+        moves: Vec::new(),
+    };
+    let pool = StatePool::new();
+    for _depth in 0..4 {
+        #[cfg(feature = "parallel")]
+        let moves = (0..64).into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let moves = 0..64;
+        moves.for_each(|game_move| {
+            pool.with_state(&root, |state| {
+                assert!(state.moves.is_empty(), "borrowed states start at the root");
+                state.moves.push(game_move);
+                state.moves.pop();
+            })
+        });
+    }
+
+    #[cfg(feature = "parallel")]
+    let threads = rayon::current_num_threads();
+    #[cfg(not(feature = "parallel"))]
+    let threads = 1;
+    let clones = root.clones.load(Ordering::SeqCst);
+    assert!(
+        (1..=threads).contains(&clones),
+        "{} clones for {} threads",
+        clones,
+        threads
+    );
+}
+
 #[test]
 fn test_alpha_beta_score_exactly_equals_beta() {
     #[derive(Clone)]
@@ -1269,6 +1396,8 @@ fn test_quiescence_all_tactical_moves() {
         take: u8,
     }
 
+    impl_take_table_move!(AlwaysTacticalNimMove);
+
     impl GameMove for AlwaysTacticalNimMove {
         type State = NimState;
         type Error = &'static str;
@@ -1487,6 +1616,19 @@ fn test_quiescence_alpha_update() {
         improvement: i16,
     }
 
+    impl TableMove for ImprovingTacticalMove {
+        fn pack(&self) -> u32 {
+            u32::from(self.take) | u32::from(self.improvement as u16) << 8
+        }
+
+        fn unpack(packed: u32) -> Option<Self> {
+            Some(Self {
+                take: packed as u8,
+                improvement: (packed >> 8) as u16 as i16,
+            })
+        }
+    }
+
     impl GameMove for ImprovingTacticalMove {
         type State = NimState;
         type Error = &'static str;
@@ -1638,6 +1780,124 @@ fn test_iterative_deepening_pv_move_ordering() {
     );
 }
 
+#[test]
+fn test_info_callback_reports_each_completed_depth() {
+    let mut state = NimState::new(10);
+    let mut context = SearchContext::<NimMove>::new(5);
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    context.set_info_callback(Some(Arc::new(move |info: &SearchInfo<NimMove>| {
+        sink.lock().unwrap().push(info.clone());
+    })));
+
+    let best_move = alpha_beta_search(
+        &mut context,
+        &mut state,
+        &NimMoveGenerator,
+        &NimEvaluator,
+        &NoOpMoveOrderer,
+    )
+    .unwrap();
+
+    let reports = reports.lock().unwrap();
+    let depths: Vec<u8> = reports.iter().map(|info| info.depth).collect();
+    assert_eq!(depths, vec![1, 2, 3, 4, 5]);
+    let last = reports.last().unwrap();
+    assert_eq!(last.principal_variation.first(), Some(&best_move));
+    assert!(last.principal_variation.len() <= 5);
+    assert!(last.nodes > 0);
+    assert!(last.seldepth >= last.depth);
+    assert_eq!(state.pile, 10, "reporting must leave the state unchanged");
+}
+
+#[test]
+fn test_current_move_callback_reports_each_root_move() {
+    let mut state = NimState::new(10);
+    let mut context = SearchContext::<NimMove>::with_parallel(2, false);
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    context.set_current_move_callback(Some(Arc::new(move |current: &CurrentMove<NimMove>| {
+        sink.lock()
+            .unwrap()
+            .push((current.depth, current.current_move.take, current.number));
+    })));
+
+    alpha_beta_search(
+        &mut context,
+        &mut state,
+        &NimMoveGenerator,
+        &NimEvaluator,
+        &NoOpMoveOrderer,
+    )
+    .unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(&reports[..3], &[(1, 1, 1), (1, 2, 2), (1, 3, 3)]);
+    assert!(reports[3..]
+        .iter()
+        .all(|&(depth, _, number)| depth == 2 && (1..=3).contains(&number)));
+}
+
+#[test]
+fn test_root_move_penalty_steers_the_search_away() {
+    let search = |penalty: Option<RootMovePenalty<NimMove>>| {
+        let mut context = SearchContext::<NimMove>::with_parallel(2, false);
+        context.set_root_move_penalty(penalty);
+        alpha_beta_search(
+            &mut context,
+            &mut NimState::new(10),
+            &NimMoveGenerator,
+            &NimEvaluator,
+            &NoOpMoveOrderer,
+        )
+        .unwrap()
+        .take
+    };
+    assert_eq!(search(None), 2);
+    // Leaving 8 is only worth 100 more than the alternatives
+    assert_eq!(
+        search(Some(Arc::new(|m: &NimMove| if m.take == 2 {
+            50
+        } else {
+            0
+        }))),
+        2
+    );
+    assert_ne!(
+        search(Some(Arc::new(|m: &NimMove| if m.take == 2 {
+            300
+        } else {
+            0
+        }))),
+        2
+    );
+}
+
+#[test]
+fn test_excluded_root_moves_are_not_searched() {
+    let mut context = SearchContext::<NimMove>::with_parallel(3, false);
+    let mut search = |excluded: Vec<NimMove>| {
+        context.set_excluded_root_moves(excluded);
+        alpha_beta_search(
+            &mut context,
+            &mut NimState::new(10),
+            &NimMoveGenerator,
+            &NimEvaluator,
+            &NoOpMoveOrderer,
+        )
+        .map(|best| best.take)
+    };
+    assert_eq!(search(vec![]).unwrap(), 2);
+    let second = search(vec![NimMove { take: 2 }]).unwrap();
+    assert_ne!(second, 2);
+    // The search without the best move did not replace the position's result
+    assert_eq!(search(vec![]).unwrap(), 2);
+    assert!(matches!(
+        search((1..=3).map(|take| NimMove { take }).collect()),
+        Err(SearchError::NoAvailableMoves)
+    ));
+}
+
 #[test]
 fn test_iterative_deepening_tt_hit_skip() {
     // Test that iterative deepening uses TT for each depth iteration
@@ -2051,7 +2311,7 @@ fn test_move_reordering_killer_equals_pv() {
     // Store same move as both PV (via TT) and killer
     context.store_killer(0, pv_move.clone());
 
-    let mut moves = vec![NimMove { take: 2 }, pv_move.clone(), NimMove { take: 3 }];
+    let mut moves = [NimMove { take: 2 }, pv_move.clone(), NimMove { take: 3 }];
 
     // Reorder with PV
     if let Some(pos) = moves.iter().position(|m| m == &pv_move) {
@@ -2105,7 +2365,7 @@ fn test_move_reordering_multiple_killers() {
         "Secondary killer should be previous"
     );
 
-    let mut moves = vec![
+    let mut moves = [
         NimMove { take: 3 },
         NimMove { take: 1 },
         NimMove { take: 2 },
@@ -2242,12 +2502,12 @@ fn test_tt_depth_preferred_keeps_deeper_entry() {
     let tt = TranspositionTable::<NimMove>::default();
 
     // Store a deep entry
-    tt.store(42, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
+    tt.store(42, 0, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
     // Try to overwrite with a shallower entry
-    tt.store(42, 50, 3, BoundType::Exact, Some(NimMove { take: 1 }));
+    tt.store(42, 0, 50, 3, BoundType::Exact, Some(NimMove { take: 1 }));
 
     // The deeper entry should be preserved
-    let (score, _) = tt.probe_with_move(42, 5, i16::MIN, i16::MAX);
+    let (score, _) = tt.probe_with_move(42, 0, 5, i16::MIN, i16::MAX);
     assert_eq!(
         score,
         Some(100),
@@ -2260,12 +2520,12 @@ fn test_tt_depth_preferred_replaces_with_deeper() {
     let tt = TranspositionTable::<NimMove>::default();
 
     // Store a shallow entry
-    tt.store(42, 50, 3, BoundType::Exact, Some(NimMove { take: 1 }));
+    tt.store(42, 0, 50, 3, BoundType::Exact, Some(NimMove { take: 1 }));
     // Overwrite with a deeper entry
-    tt.store(42, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
+    tt.store(42, 0, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
 
     // The deeper entry should be stored
-    let (score, _) = tt.probe_with_move(42, 5, i16::MIN, i16::MAX);
+    let (score, _) = tt.probe_with_move(42, 0, 5, i16::MIN, i16::MAX);
     assert_eq!(
         score,
         Some(100),
@@ -2278,12 +2538,12 @@ fn test_tt_depth_preferred_replaces_at_same_depth() {
     let tt = TranspositionTable::<NimMove>::default();
 
     // Store an entry
-    tt.store(42, 50, 4, BoundType::Exact, Some(NimMove { take: 1 }));
+    tt.store(42, 0, 50, 4, BoundType::Exact, Some(NimMove { take: 1 }));
     // Overwrite with same depth (should replace — newer info at same depth is better)
-    tt.store(42, 80, 4, BoundType::Exact, Some(NimMove { take: 2 }));
+    tt.store(42, 0, 80, 4, BoundType::Exact, Some(NimMove { take: 2 }));
 
     // The newer entry should be stored
-    let (score, _) = tt.probe_with_move(42, 4, i16::MIN, i16::MAX);
+    let (score, _) = tt.probe_with_move(42, 0, 4, i16::MIN, i16::MAX);
     assert_eq!(
         score,
         Some(80),
@@ -2296,11 +2556,11 @@ fn test_tt_depth_preferred_overwrite_counter() {
     let tt = TranspositionTable::<NimMove>::default();
 
     // First store — no overwrite
-    tt.store(42, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
+    tt.store(42, 0, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
     assert_eq!(tt.overwrites(), 0, "First store should not be an overwrite");
 
     // Same-depth store — overwrites
-    tt.store(42, 80, 5, BoundType::Exact, Some(NimMove { take: 1 }));
+    tt.store(42, 0, 80, 5, BoundType::Exact, Some(NimMove { take: 1 }));
     assert_eq!(
         tt.overwrites(),
         1,
@@ -2308,7 +2568,7 @@ fn test_tt_depth_preferred_overwrite_counter() {
     );
 
     // Shallow store — rejected, no overwrite
-    tt.store(42, 60, 3, BoundType::Exact, Some(NimMove { take: 1 }));
+    tt.store(42, 0, 60, 3, BoundType::Exact, Some(NimMove { take: 1 }));
     assert_eq!(
         tt.overwrites(),
         1,
@@ -2316,7 +2576,7 @@ fn test_tt_depth_preferred_overwrite_counter() {
     );
 
     // Deeper store — overwrites
-    tt.store(42, 120, 7, BoundType::Exact, Some(NimMove { take: 3 }));
+    tt.store(42, 0, 120, 7, BoundType::Exact, Some(NimMove { take: 3 }));
     assert_eq!(
         tt.overwrites(),
         2,
@@ -2324,6 +2584,38 @@ fn test_tt_depth_preferred_overwrite_counter() {
     );
 }
 
+#[test]
+fn test_tt_entry_of_an_earlier_search_gives_way_to_a_shallower_one() {
+    let tt = TranspositionTable::<NimMove>::default();
+
+    tt.store(42, 0, 100, 5, BoundType::Exact, Some(NimMove { take: 2 }));
+    tt.new_search();
+    tt.store(42, 0, 50, 3, BoundType::Exact, Some(NimMove { take: 1 }));
+
+    let (score, best_move) = tt.probe_with_move(42, 0, 3, i16::MIN, i16::MAX);
+    assert_eq!(
+        score,
+        Some(50),
+        "The earlier search's entry should be replaced"
+    );
+    assert_eq!(best_move, Some(NimMove { take: 1 }));
+    assert_eq!(tt.overwrites(), 1);
+}
+
+#[test]
+fn test_tt_stays_within_its_size() {
+    let tt = TranspositionTable::<NimMove>::new(1);
+    let capacity = tt.capacity();
+    assert!(capacity > 0 && capacity * 16 <= 1024 * 1024);
+
+    for hash in 0..(capacity as u64 * 4) {
+        let hash = hash.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        tt.store(hash, 0, 1, (hash % 8) as u8, BoundType::Exact, None);
+    }
+    assert!(tt.size() <= capacity);
+    assert!(TranspositionTable::<NimMove>::new(3).capacity() <= 3 * capacity);
+}
+
 // ========================================================================
 // Time limit tests
 // ========================================================================
@@ -2441,6 +2733,52 @@ fn test_search_without_time_limit_unchanged() {
     );
 }
 
+#[test]
+fn test_search_limits_set_the_depth_and_limits() {
+    let mut context = SearchContext::<NimMove>::new(3);
+    context.set_limits(SearchLimits::default());
+    assert_eq!(
+        context.search_depth(),
+        3,
+        "depth-only limits keep the depth"
+    );
+
+    // Time and node limits search as deep as they allow, unless given a depth
+    context.set_limits(SearchLimits {
+        nodes: Some(1),
+        ..SearchLimits::default()
+    });
+    assert_eq!(context.search_depth(), MAX_LIMITED_SEARCH_DEPTH);
+    context.set_limits(SearchLimits {
+        depth: Some(5),
+        movetime: Some(std::time::Duration::from_secs(5)),
+        ..SearchLimits::default()
+    });
+    assert_eq!(context.search_depth(), 5);
+
+    let mut state = NimState::new(10);
+    let result = alpha_beta_search(
+        &mut context,
+        &mut state,
+        &NimMoveGenerator,
+        &NimEvaluator,
+        &NoOpMoveOrderer,
+    );
+    assert_eq!(10 - result.unwrap().take, 8);
+    assert_eq!(context.last_completed_depth(), Some(5));
+
+    // An infinite search ignores its node and time limits
+    let limits = SearchLimits {
+        nodes: Some(1),
+        movetime: Some(std::time::Duration::from_millis(1)),
+        infinite: true,
+        ..SearchLimits::default()
+    };
+    assert!(!limits.is_depth_only());
+    assert_eq!(limits.node_limit(), None);
+    assert_eq!(limits.time_limits(), None);
+}
+
 #[test]
 fn test_stop_flag_halts_search() {
     use std::sync::atomic::Ordering;
@@ -2477,6 +2815,28 @@ fn test_stop_flag_halts_search() {
     );
 }
 
+#[test]
+fn test_search_stopped_before_starting_still_returns_a_move() {
+    use std::sync::atomic::Ordering;
+
+    let mut state = NimState::new(15);
+    let mut context = SearchContext::<NimMove>::with_parallel(20, false);
+    context.stop_flag().store(true, Ordering::Relaxed);
+
+    let result = alpha_beta_search(
+        &mut context,
+        &mut state,
+        &NimMoveGenerator,
+        &NimEvaluator,
+        &NoOpMoveOrderer,
+    );
+
+    assert!(
+        result.is_ok(),
+        "A stop before depth 1 completes should still give a move"
+    );
+}
+
 #[test]
 fn test_search_records_duration() {
     let mut state = NimState::new(5);
@@ -2539,3 +2899,28 @@ fn test_soft_limit_stops_between_depths() {
         unlimited_nodes
     );
 }
+
+#[test]
+fn test_move_picker_hands_out_each_move_once_in_stages() {
+    let mut state = NimState::new(10);
+    let moves = |picker: &mut MovePicker<NimState, NimMoveGenerator>, state: &mut NimState| {
+        std::iter::from_fn(|| picker.next(state, &NimMoveGenerator, &NoOpMoveOrderer))
+            .map(|nim_move| nim_move.take)
+            .collect::<Vec<_>>()
+    };
+
+    let mut picker = MovePicker::new(None, [None, None]);
+    assert_eq!(moves(&mut picker, &mut state), vec![1, 2, 3]);
+
+    // The TT move first, then the killers, then the rest
+    let mut picker = MovePicker::new(
+        Some(NimMove { take: 3 }),
+        [Some(NimMove { take: 2 }), Some(NimMove { take: 3 })],
+    );
+    assert_eq!(moves(&mut picker, &mut state), vec![3, 2, 1]);
+
+    // Moves that aren't legal here are left out
+    let mut state = NimState::new(1);
+    let mut picker = MovePicker::new(Some(NimMove { take: 3 }), [Some(NimMove { take: 2 }), None]);
+    assert_eq!(moves(&mut picker, &mut state), vec![1]);
+}
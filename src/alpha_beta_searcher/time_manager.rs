@@ -0,0 +1,199 @@
+//! Time management within a search: when to stop deepening, and when to give
+//! up on a depth.
+//!
+//! A move gets a soft and a hard time limit. No new depth is started past the
+//! soft limit, and the depth being searched is abandoned past the hard limit.
+//! The `TimeManager` moves the soft limit after every completed depth: it
+//! stretches towards the hard limit while the best move keeps changing or the
+//! score falls, and shrinks once the same move has stayed best for several
+//! depths in a row, as more time is unlikely to change the answer.
+
+use std::time::Duration;
+
+/// How far each recent change of best move stretches the soft limit.
+const BEST_MOVE_CHANGE_STRETCH: f64 = 0.5;
+
+/// A fall in score, in centipawns, at which the soft limit stretches the most.
+const SCORE_DROP_CP: i16 = 100;
+
+/// How far a fall of `SCORE_DROP_CP` or more stretches the soft limit.
+const SCORE_DROP_STRETCH: f64 = 0.5;
+
+/// Depths in a row with the same best move before the soft limit shrinks.
+const STABLE_DEPTHS: u32 = 6;
+
+/// The share of the soft limit left once the best move is stable.
+const STABLE_SCALE: f64 = 0.6;
+
+/// The soft and hard time limits for one move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLimits {
+    /// No new depth is started past this, as adjusted by the time manager.
+    pub soft: Duration,
+    /// The search stops mid-depth past this.
+    pub hard: Duration,
+}
+
+impl TimeLimits {
+    /// Limits for a fixed thinking time: stop deepening past half of it and
+    /// abort mid-depth past four fifths, leaving time to unwind and answer.
+    pub fn for_movetime(movetime: Duration) -> Self {
+        Self {
+            soft: movetime / 2,
+            hard: movetime * 4 / 5,
+        }
+    }
+
+    /// Limits for a move budgeted `budget` from a clock that can spare up to
+    /// `max` for it when the search needs longer. The soft limit is as for a
+    /// fixed `budget`; the hard limit allows up to twice the budget.
+    pub fn for_budget(budget: Duration, max: Duration) -> Self {
+        let movetime = Self::for_movetime(budget);
+        Self {
+            hard: (budget * 2).min(max).max(movetime.hard),
+            ..movetime
+        }
+    }
+}
+
+/// Adjusts the soft limit of a search as its depths complete.
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    limits: TimeLimits,
+    /// Recent changes of best move, halved at every depth.
+    best_move_changes: f64,
+    /// Depths in a row completed with the same best move.
+    stable_depths: u32,
+    /// The score of the last completed depth, for the side to move.
+    last_score: Option<i16>,
+    /// How far the score fell at the last completed depth.
+    score_drop: i16,
+}
+
+impl TimeManager {
+    pub fn new(limits: TimeLimits) -> Self {
+        Self {
+            limits,
+            best_move_changes: 0.0,
+            stable_depths: 0,
+            last_score: None,
+            score_drop: 0,
+        }
+    }
+
+    pub fn limits(&self) -> TimeLimits {
+        self.limits
+    }
+
+    /// Record a completed depth, whose best move differs from the previous
+    /// depth's if `best_move_changed`, with `score` for the side to move.
+    pub fn record_depth(&mut self, best_move_changed: bool, score: i16) {
+        self.best_move_changes =
+            self.best_move_changes / 2.0 + if best_move_changed { 1.0 } else { 0.0 };
+        self.stable_depths = if best_move_changed {
+            0
+        } else {
+            self.stable_depths + 1
+        };
+        self.score_drop = self
+            .last_score
+            .map_or(0, |last| last.saturating_sub(score).max(0));
+        self.last_score = Some(score);
+    }
+
+    /// The soft limit for the search so far, never past the hard limit.
+    pub fn soft_limit(&self) -> Duration {
+        let mut scale = 1.0 + self.best_move_changes * BEST_MOVE_CHANGE_STRETCH;
+        let drop = f64::from(self.score_drop.min(SCORE_DROP_CP)) / f64::from(SCORE_DROP_CP);
+        scale *= 1.0 + drop * SCORE_DROP_STRETCH;
+        if self.stable_depths >= STABLE_DEPTHS && self.score_drop == 0 {
+            scale *= STABLE_SCALE;
+        }
+        self.limits.soft.mul_f64(scale).min(self.limits.hard)
+    }
+
+    /// Whether a search that has run for `elapsed` should start no new depth.
+    pub fn should_stop_deepening(&self, elapsed: Duration) -> bool {
+        elapsed > self.soft_limit()
+    }
+
+    /// Whether a search that has run for `elapsed` should stop mid-depth.
+    pub fn hard_limit_exceeded(&self, elapsed: Duration) -> bool {
+        elapsed > self.limits.hard
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> TimeManager {
+        TimeManager::new(TimeLimits::for_budget(
+            Duration::from_millis(1000),
+            Duration::from_millis(5000),
+        ))
+    }
+
+    /// The soft limit to the millisecond, as scaling it is not exact.
+    fn soft_millis(manager: &TimeManager) -> u128 {
+        (manager.soft_limit().as_secs_f64() * 1000.0).round() as u128
+    }
+
+    #[test]
+    fn test_limits_follow_the_budget() {
+        let limits = TimeLimits::for_movetime(Duration::from_millis(1000));
+        assert_eq!(limits.soft, Duration::from_millis(500));
+        assert_eq!(limits.hard, Duration::from_millis(800));
+
+        let limits = TimeLimits::for_budget(Duration::from_millis(1000), Duration::from_secs(60));
+        assert_eq!(limits.hard, Duration::from_millis(2000));
+        // Never past what the clock can spare, nor short of a fixed movetime
+        let limits =
+            TimeLimits::for_budget(Duration::from_millis(1000), Duration::from_millis(1200));
+        assert_eq!(limits.hard, Duration::from_millis(1200));
+        let limits = TimeLimits::for_budget(Duration::from_millis(1000), Duration::ZERO);
+        assert_eq!(limits.hard, Duration::from_millis(800));
+    }
+
+    #[test]
+    fn test_instability_and_falling_scores_extend_the_soft_limit() {
+        let mut manager = manager();
+        manager.record_depth(false, 50);
+        assert_eq!(soft_millis(&manager), 500);
+
+        manager.record_depth(true, 50);
+        assert_eq!(soft_millis(&manager), 750);
+        manager.record_depth(true, 50);
+        assert_eq!(soft_millis(&manager), 875);
+
+        let mut manager = self::manager();
+        manager.record_depth(false, 50);
+        manager.record_depth(false, -50);
+        assert_eq!(soft_millis(&manager), 750);
+        assert!(!manager.should_stop_deepening(Duration::from_millis(600)));
+
+        // Never past the hard limit
+        let mut manager = TimeManager::new(TimeLimits::for_movetime(Duration::from_millis(1000)));
+        for _ in 0..10 {
+            manager.record_depth(true, 0);
+        }
+        assert_eq!(manager.soft_limit(), manager.limits().hard);
+    }
+
+    #[test]
+    fn test_a_stable_best_move_cuts_the_soft_limit() {
+        let mut manager = manager();
+        for depth in 1..STABLE_DEPTHS {
+            manager.record_depth(false, 30);
+            assert!(
+                manager.soft_limit() >= Duration::from_millis(500),
+                "cut at depth {}",
+                depth
+            );
+        }
+        manager.record_depth(false, 30);
+        assert_eq!(soft_millis(&manager), 300);
+        assert!(manager.should_stop_deepening(Duration::from_millis(400)));
+        assert!(!manager.hard_limit_exceeded(Duration::from_millis(400)));
+    }
+}
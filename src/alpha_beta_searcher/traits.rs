@@ -23,10 +23,28 @@ pub trait GameState: Clone + Send + Sync {
     fn undo_null_move(&mut self) {
         self.toggle_turn();
     }
+
+    /// Returns true if the current player is in check.
+    /// Used for check extensions (extending search depth by 1 when in check)
+    /// and to gate speculative pruning techniques.
+    /// Default returns false (no check detection).
+    fn is_in_check(&self) -> bool {
+        false
+    }
+}
+
+/// A move the transposition table can hold in 32 bits, so that its entries fit
+/// in atomic words.
+pub trait TableMove: Sized {
+    /// The move packed into 32 bits.
+    fn pack(&self) -> u32;
+
+    /// The move `pack` packed into `packed`, or None if it is not one.
+    fn unpack(packed: u32) -> Option<Self>;
 }
 
 /// Represents an action that can be applied to and undone from a game state.
-pub trait GameMove: Clone + Send + Sync + PartialEq + Debug {
+pub trait GameMove: Clone + Send + Sync + PartialEq + Debug + TableMove {
     type State: GameState;
     type Error: Debug;
 
@@ -54,8 +72,25 @@ pub trait MoveGenerator<S: GameState>: Clone + Send + Sync {
     fn generate_moves(&self, state: &mut S) -> Self::MoveList;
 
     /// Generates only tactical moves (captures, promotions, etc.) for quiescence search.
-    /// Default delegates to generate_moves() for games without tactical-specific generation.
+    /// Default generates every move and keeps those `GameMove::is_tactical` selects,
+    /// for games without tactical-specific generation.
     fn generate_tactical_moves(&self, state: &mut S) -> Self::MoveList {
+        let mut moves = self.generate_moves(state);
+        moves.retain(|game_move| game_move.is_tactical(state));
+        moves
+    }
+
+    /// Generates the quiet moves that give check, which quiescence search tries
+    /// along with the tactical moves at its first ply. Default returns None
+    /// (no checks in quiescence).
+    fn generate_quiet_checks(&self, _state: &mut S) -> Option<Self::MoveList> {
+        None
+    }
+
+    /// Generates only the moves `generate_tactical_moves` leaves out, for staged
+    /// move generation (see `MovePicker`). Default delegates to generate_moves();
+    /// the picker skips the tactical moves among them.
+    fn generate_quiet_moves(&self, state: &mut S) -> Self::MoveList {
         self.generate_moves(state)
     }
 }
@@ -88,14 +123,6 @@ pub trait Evaluator<S: GameState>: Clone + Send + Sync {
         None
     }
 
-    /// Returns true if the current player is in check.
-    /// Used for check extensions (extending search depth by 1 when in check)
-    /// and to gate speculative pruning techniques.
-    /// Default returns false (no check detection).
-    fn is_in_check(&self, _state: &mut S) -> bool {
-        false
-    }
-
     /// Returns the late move pruning threshold for the given depth, or None to disable LMP.
     /// At shallow depths, quiet moves beyond this threshold are pruned entirely.
     /// Default returns None (LMP disabled).
@@ -125,6 +152,13 @@ pub trait MoveOrderer<S: GameState, M>: Clone + Send + Sync {
     /// Called when a move causes a beta cutoff. Can be used to update
     /// move ordering heuristics (e.g., history table). Default does nothing.
     fn record_cutoff(&self, _mv: &M, _state: &S, _depth: u8) {}
+
+    /// Returns true if the tactical move `mv` likely loses material, so that
+    /// staged move generation tries it after the quiet moves rather than
+    /// before them. Default returns false.
+    fn is_losing_tactical(&self, _mv: &M, _state: &S) -> bool {
+        false
+    }
 }
 
 /// A no-op move orderer for games without move ordering heuristics.
@@ -147,6 +181,14 @@ pub trait MoveCollection<M>: AsRef<[M]> + AsMut<[M]> + Send {
     fn len(&self) -> usize {
         self.as_ref().len()
     }
+
+    /// Keeps only the moves for which `keep` returns true, in order.
+    fn retain<F: FnMut(&M) -> bool>(&mut self, keep: F);
 }
 
-impl<M: Send> MoveCollection<M> for Vec<M> {}
+impl<M: Send> MoveCollection<M> for Vec<M> {
+    #[inline]
+    fn retain<F: FnMut(&M) -> bool>(&mut self, keep: F) {
+        Vec::retain(self, keep);
+    }
+}
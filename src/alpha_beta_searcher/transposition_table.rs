@@ -1,10 +1,25 @@
 //! Generic transposition table for caching search results.
 //!
 //! Uses a fixed-size array indexed by hash % capacity with two slots per bucket:
-//! one depth-preferred (only replaced by equal or deeper entries) and one always-replace.
-//! This bounded-memory design provides much better cache locality than a hash map.
+//! one depth-preferred (only replaced by equal or deeper entries, or entries of
+//! an earlier search) and one always-replace. The table is sized in megabytes
+//! and never grows, however long the game.
+//!
+//! Each slot is two atomic words: the entry packed into one, and the position's
+//! hash XORed with it in the other. Searching threads read and write slots
+//! without locks; a slot read while another thread writes it fails the hash
+//! check and is a miss rather than a corrupt entry.
+//!
+//! Mate scores are stored by their distance from the entry's position and read back by
+//! their distance from the current root, given the ply the position is at.
+
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+
+use super::mate_score::{from_table, to_table};
+use super::traits::TableMove;
 
 #[derive(Clone)]
 pub struct TTEntry<M: Clone> {
@@ -27,180 +42,260 @@ impl<M: Clone> Default for TTEntry<M> {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum BoundType {
     Exact,
     Lower,
     Upper,
 }
 
+impl BoundType {
+    fn to_bits(self) -> u64 {
+        match self {
+            BoundType::Exact => 0,
+            BoundType::Lower => 1,
+            BoundType::Upper => 2,
+        }
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        match bits {
+            0 => BoundType::Exact,
+            1 => BoundType::Lower,
+            _ => BoundType::Upper,
+        }
+    }
+}
+
+// Layout of an entry's word, from the low bits: the packed move (32 bits), the
+// score (16), the depth (8), the bound (2), whether there is a move, whether the
+// slot is occupied, and the age of the search that stored it (4).
+const SCORE_SHIFT: u32 = 32;
+const DEPTH_SHIFT: u32 = 48;
+const BOUND_SHIFT: u32 = 56;
+const HAS_MOVE: u64 = 1 << 58;
+const OCCUPIED: u64 = 1 << 59;
+const AGE_SHIFT: u32 = 60;
+const AGE_MASK: usize = 0xf;
+
+/// A slot holding one entry: its packed word, and the hash XOR the word.
+#[derive(Default)]
+struct TTSlot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+impl TTSlot {
+    /// The slot's hash and word, if it is occupied. A slot torn by a
+    /// concurrent write gives a hash no position probes for.
+    #[inline]
+    fn load(&self) -> Option<(u64, u64)> {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.key.load(Ordering::Relaxed);
+        (data & OCCUPIED != 0).then_some((key ^ data, data))
+    }
+
+    /// The slot's word, if it holds the entry for `hash`.
+    #[inline]
+    fn read(&self, hash: u64) -> Option<u64> {
+        self.load()
+            .filter(|&(stored_hash, _)| stored_hash == hash)
+            .map(|(_, data)| data)
+    }
+
+    #[inline]
+    fn write(&self, hash: u64, data: u64) {
+        self.key.store(hash ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.key.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+}
+
 /// A two-slot bucket: slot 0 is depth-preferred, slot 1 is always-replace.
-#[derive(Clone)]
-struct TTBucket<M: Clone> {
-    depth_slot: TTEntry<M>,
-    replace_slot: TTEntry<M>,
+#[derive(Default)]
+struct TTBucket {
+    depth_slot: TTSlot,
+    replace_slot: TTSlot,
 }
 
-impl<M: Clone> Default for TTBucket<M> {
-    fn default() -> Self {
-        Self {
-            depth_slot: TTEntry::default(),
-            replace_slot: TTEntry::default(),
-        }
+impl TTBucket {
+    fn slots(&self) -> [&TTSlot; 2] {
+        [&self.depth_slot, &self.replace_slot]
     }
 }
 
-const DEFAULT_TT_SIZE_MB: usize = 64;
-
-/// Fixed-size transposition table with bounded memory and O(1) access.
-///
-/// Uses `UnsafeCell` for lock-free concurrent access. Benign data races are
-/// acceptable (may read a partially-written entry), which is the standard
-/// approach in chess engines. The hash verification key catches most corruption.
-pub struct TranspositionTable<M: Clone + Send + Sync> {
-    // UnsafeCell allows concurrent mutable access without locks.
-    // Safety: benign races are acceptable -- the verification key catches corruption.
-    table: Vec<std::cell::UnsafeCell<TTBucket<M>>>,
+pub const DEFAULT_TT_SIZE_MB: usize = 64;
+
+/// Fixed-size, lock-free transposition table with bounded memory and O(1) access.
+pub struct TranspositionTable<M> {
+    table: Box<[TTBucket]>,
     capacity: usize,
+    /// Counts searches, so entries of earlier ones give way to new ones.
+    age: AtomicUsize,
     hits: AtomicUsize,
     depth_rejected: AtomicUsize,
     bound_rejected: AtomicUsize,
     overwrites: AtomicUsize,
+    _move: PhantomData<fn() -> M>,
 }
 
-// Safety: concurrent access produces benign races (stale reads, partial writes)
-// that are detected by the key verification check. This is the standard approach
-// for transposition tables in chess engines.
-unsafe impl<M: Clone + Send + Sync> Sync for TranspositionTable<M> {}
-
-impl<M: Clone + Send + Sync> Default for TranspositionTable<M> {
+impl<M: TableMove + Clone> Default for TranspositionTable<M> {
     fn default() -> Self {
         Self::new(DEFAULT_TT_SIZE_MB)
     }
 }
 
-impl<M: Clone + Send + Sync> TranspositionTable<M> {
+impl<M: TableMove + Clone> TranspositionTable<M> {
+    /// A table of at most `size_mb` megabytes, and at least one bucket.
     pub fn new(size_mb: usize) -> Self {
-        let bucket_size = std::mem::size_of::<TTBucket<M>>().max(1);
-        let num_buckets = (size_mb * 1024 * 1024) / bucket_size;
-        // Use power of 2 for fast modulo via bitwise AND
-        let capacity = num_buckets.next_power_of_two();
-
-        let mut table = Vec::with_capacity(capacity);
-        for _ in 0..capacity {
-            table.push(std::cell::UnsafeCell::new(TTBucket::default()));
-        }
+        let bucket_size = std::mem::size_of::<TTBucket>();
+        let num_buckets = (size_mb * 1024 * 1024 / bucket_size).max(1);
+        // Use power of 2 for fast modulo via bitwise AND, rounding down to
+        // stay within the size asked for
+        let capacity = 1 << (usize::BITS - 1 - num_buckets.leading_zeros());
 
         Self {
-            table,
+            table: (0..capacity).map(|_| TTBucket::default()).collect(),
             capacity,
+            age: AtomicUsize::new(0),
             hits: AtomicUsize::new(0),
             depth_rejected: AtomicUsize::new(0),
             bound_rejected: AtomicUsize::new(0),
             overwrites: AtomicUsize::new(0),
+            _move: PhantomData,
         }
     }
 
     #[inline]
-    fn index(&self, hash: u64) -> usize {
-        (hash as usize) & (self.capacity - 1)
+    fn bucket(&self, hash: u64) -> &TTBucket {
+        &self.table[(hash as usize) & (self.capacity - 1)]
     }
 
     #[inline]
-    fn verification_key(hash: u64) -> u32 {
-        // Ensure key is never 0 (reserved for empty entries)
-        let key = (hash >> 32) as u32;
-        if key == 0 {
-            1
-        } else {
-            key
-        }
+    fn current_age(&self) -> u64 {
+        (self.age.load(Ordering::Relaxed) & AGE_MASK) as u64
     }
 
+    /// Start a new search: entries stored before it no longer keep their
+    /// depth-preferred slots from shallower ones.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Store the result of a search of the position with `hash`, `ply` moves
+    /// from the root.
     pub fn store(
         &self,
         hash: u64,
+        ply: u8,
         score: i16,
         depth: u8,
         bound_type: BoundType,
         best_move: Option<M>,
     ) {
-        let idx = self.index(hash);
-        let key = Self::verification_key(hash);
-
-        let entry = TTEntry {
-            key,
-            score,
-            depth,
-            bound_type,
-            best_move,
-        };
-
-        // Safety: benign races are acceptable -- verified by key check on read.
-        let bucket = unsafe { &mut *self.table[idx].get() };
-
-        // Depth-preferred slot: only replace if new entry has >= depth
-        if depth >= bucket.depth_slot.depth || bucket.depth_slot.key == 0 {
-            if bucket.depth_slot.key != 0 {
+        let mut data = OCCUPIED
+            | u64::from(to_table(score, ply) as u16) << SCORE_SHIFT
+            | u64::from(depth) << DEPTH_SHIFT
+            | bound_type.to_bits() << BOUND_SHIFT
+            | self.current_age() << AGE_SHIFT;
+        if let Some(best_move) = best_move {
+            data |= HAS_MOVE | u64::from(best_move.pack());
+        }
+
+        let bucket = self.bucket(hash);
+
+        // Depth-preferred slot: only replace if new entry has >= depth, or
+        // the old one is from an earlier search
+        match bucket.depth_slot.load() {
+            None => bucket.depth_slot.write(hash, data),
+            Some((_, old)) if depth >= depth_of(old) || old >> AGE_SHIFT != data >> AGE_SHIFT => {
                 self.overwrites.fetch_add(1, Ordering::Relaxed);
+                bucket.depth_slot.write(hash, data);
             }
-            bucket.depth_slot = entry;
-        } else {
             // Always-replace slot: always overwrite
-            bucket.replace_slot = entry;
+            Some(_) => bucket.replace_slot.write(hash, data),
         }
     }
 
     /// Probe TT and return both cutoff score (if applicable) and best move (if exists).
     /// Returns (Some(score), best_move) if early cutoff possible, (None, best_move)
-    /// otherwise.
+    /// otherwise. The position is `ply` moves from the root.
     pub fn probe_with_move(
         &self,
         hash: u64,
+        ply: u8,
         depth: u8,
         alpha: i16,
         beta: i16,
     ) -> (Option<i16>, Option<M>) {
-        let idx = self.index(hash);
-        let key = Self::verification_key(hash);
+        match self.entry(hash) {
+            Some(entry) => self.check_entry(&entry, ply, depth, alpha, beta),
+            None => (None, None),
+        }
+    }
 
-        // Safety: benign races -- verified by key check.
-        let bucket = unsafe { &*self.table[idx].get() };
+    /// Best move stored for `hash`, if any, without touching hit statistics.
+    /// Used to walk the principal variation after a search.
+    pub fn best_move(&self, hash: u64) -> Option<M> {
+        self.entry(hash).and_then(|entry| entry.best_move)
+    }
 
+    /// The entry stored for `hash`, if any, without touching hit statistics.
+    pub fn entry(&self, hash: u64) -> Option<TTEntry<M>> {
         // Check depth-preferred slot first (more likely to be useful)
-        if bucket.depth_slot.key == key {
-            return self.check_entry(&bucket.depth_slot, depth, alpha, beta);
-        }
+        self.bucket(hash)
+            .slots()
+            .iter()
+            .find_map(|slot| slot.read(hash))
+            .map(|data| decode(hash, data))
+    }
 
-        // Check always-replace slot
-        if bucket.replace_slot.key == key {
-            return self.check_entry(&bucket.replace_slot, depth, alpha, beta);
+    /// Every stored entry with the hash of its position. Depth-preferred
+    /// entries come first, so storing the entries into a table of the same or
+    /// smaller capacity, in order, restores them.
+    pub fn entries(&self) -> Vec<(u64, TTEntry<M>)> {
+        let mut depth_entries = Vec::new();
+        let mut replace_entries = Vec::new();
+        for bucket in self.table.iter() {
+            if let Some((hash, data)) = bucket.depth_slot.load() {
+                depth_entries.push((hash, decode(hash, data)));
+            }
+            if let Some((hash, data)) = bucket.replace_slot.load() {
+                replace_entries.push((hash, decode(hash, data)));
+            }
         }
-
-        (None, None)
+        depth_entries.extend(replace_entries);
+        depth_entries
     }
 
     #[inline]
     fn check_entry(
         &self,
         entry: &TTEntry<M>,
+        ply: u8,
         depth: u8,
         alpha: i16,
         beta: i16,
     ) -> (Option<i16>, Option<M>) {
         let best_move = entry.best_move.clone();
+        let score = from_table(entry.score, ply);
 
         if entry.depth >= depth {
             match entry.bound_type {
                 BoundType::Exact => {
                     self.hits.fetch_add(1, Ordering::Relaxed);
-                    return (Some(entry.score), best_move);
+                    return (Some(score), best_move);
                 }
-                BoundType::Lower if entry.score >= beta => {
+                BoundType::Lower if score >= beta => {
                     self.hits.fetch_add(1, Ordering::Relaxed);
                     return (Some(beta), best_move);
                 }
-                BoundType::Upper if entry.score <= alpha => {
+                BoundType::Upper if score <= alpha => {
                     self.hits.fetch_add(1, Ordering::Relaxed);
                     return (Some(alpha), best_move);
                 }
@@ -217,10 +312,9 @@ impl<M: Clone + Send + Sync> TranspositionTable<M> {
     }
 
     pub fn clear(&self) {
-        for cell in &self.table {
-            // Safety: called between searches, no concurrent access expected.
-            let bucket = unsafe { &mut *cell.get() };
-            *bucket = TTBucket::default();
+        for bucket in self.table.iter() {
+            bucket.depth_slot.clear();
+            bucket.replace_slot.clear();
         }
         self.hits.store(0, Ordering::Relaxed);
         self.depth_rejected.store(0, Ordering::Relaxed);
@@ -234,17 +328,16 @@ impl<M: Clone + Send + Sync> TranspositionTable<M> {
 
     pub fn size(&self) -> usize {
         // Count non-empty entries
-        let mut count = 0;
-        for cell in &self.table {
-            let bucket = unsafe { &*cell.get() };
-            if bucket.depth_slot.key != 0 {
-                count += 1;
-            }
-            if bucket.replace_slot.key != 0 {
-                count += 1;
-            }
-        }
-        count
+        self.table
+            .iter()
+            .flat_map(TTBucket::slots)
+            .filter(|slot| slot.load().is_some())
+            .count()
+    }
+
+    /// The number of entries the table has room for.
+    pub fn capacity(&self) -> usize {
+        self.capacity * 2
     }
 
     pub fn depth_rejected(&self) -> usize {
@@ -259,3 +352,72 @@ impl<M: Clone + Send + Sync> TranspositionTable<M> {
         self.overwrites.load(Ordering::Relaxed)
     }
 }
+
+#[inline]
+fn depth_of(data: u64) -> u8 {
+    (data >> DEPTH_SHIFT) as u8
+}
+
+/// The entry for `hash` packed into `data`, with its score as stored.
+fn decode<M: TableMove + Clone>(hash: u64, data: u64) -> TTEntry<M> {
+    TTEntry {
+        key: (hash >> 32) as u32,
+        score: (data >> SCORE_SHIFT) as u16 as i16,
+        depth: depth_of(data),
+        bound_type: BoundType::from_bits((data >> BOUND_SHIFT) & 0b11),
+        best_move: (data & HAS_MOVE != 0)
+            .then(|| M::unpack(data as u32))
+            .flatten(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl TableMove for u8 {
+        fn pack(&self) -> u32 {
+            u32::from(*self)
+        }
+
+        fn unpack(packed: u32) -> Option<Self> {
+            Some(packed as u8)
+        }
+    }
+
+    #[test]
+    fn test_entries_restore_into_a_new_table() {
+        let table = TranspositionTable::<u8>::new(1);
+        let hashes = [
+            0x1234_5678_0000_0042u64,
+            0xdead_beef_0000_0042,
+            0x0000_0001_0000_0007,
+        ];
+        table.store(hashes[0], 0, 50, 6, BoundType::Exact, Some(1));
+        table.store(hashes[1], 0, -20, 3, BoundType::Lower, Some(2));
+        table.store(hashes[2], 0, 7, 2, BoundType::Upper, None);
+
+        let restored = TranspositionTable::<u8>::new(1);
+        for (hash, entry) in table.entries() {
+            restored.store(
+                hash,
+                0,
+                entry.score,
+                entry.depth,
+                entry.bound_type,
+                entry.best_move,
+            );
+        }
+
+        assert_eq!(restored.size(), 3);
+        let entry = restored.entry(hashes[0]).unwrap();
+        assert_eq!((entry.score, entry.depth), (50, 6));
+        assert_eq!(entry.best_move, Some(1));
+        assert_eq!(
+            restored.entry(hashes[1]).unwrap().bound_type,
+            BoundType::Lower
+        );
+        assert_eq!(restored.best_move(hashes[2]), None);
+        assert!(restored.entry(0x0000_0002_0000_0007).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+//! The squares attacked by each piece on the board, kept up to date as
+//! pieces are put and removed.
+//!
+//! Putting a piece on a square or removing it changes the attacks of that
+//! piece and of the bishops, rooks and queens whose rays reach the square,
+//! blocked there or passing through; every other piece attacks what it did
+//! before. So only those are recomputed, and a side's attacks are the union
+//! over the squares of its pieces, without generating them all again.
+
+use common::bitboard::{Bitboard, Square};
+use once_cell::sync::Lazy;
+
+use crate::move_generator::Targets;
+
+use super::{Color, Piece};
+
+/// Attack tables for recomputing the attacks of a piece.
+static ATTACK_TARGETS: Lazy<Targets> = Lazy::new(Targets::default);
+
+/// The attacks of the piece on every square.
+#[derive(Clone)]
+pub struct AttackMap {
+    /// Squares attacked from each square, empty where there is no piece.
+    from: [Bitboard; 64],
+}
+
+impl Default for AttackMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AttackMap {
+    pub fn new() -> Self {
+        Self {
+            from: [Bitboard::EMPTY; 64],
+        }
+    }
+
+    /// Squares attacked by the piece on `square`, or none if it is empty.
+    #[inline]
+    pub fn attacks_from(&self, square: Square) -> Bitboard {
+        self.from[square.index() as usize]
+    }
+
+    /// Squares attacked by any of the pieces on `squares`.
+    #[inline]
+    pub fn attacks_from_all(&self, mut squares: Bitboard) -> Bitboard {
+        let mut attacks = Bitboard::EMPTY;
+        while !squares.is_empty() {
+            attacks |= self.attacks_from(squares.pop_lsb_as_square());
+        }
+        attacks
+    }
+
+    /// Update the map after a piece was put on `square` or removed from it,
+    /// leaving `piece` there. `occupied` is the board afterwards, and the
+    /// sliders are the squares of both sides' bishops and queens, and rooks
+    /// and queens.
+    pub fn update(
+        &mut self,
+        square: Square,
+        piece: Option<(Piece, Color)>,
+        occupied: Bitboard,
+        diagonal_sliders: Bitboard,
+        orthogonal_sliders: Bitboard,
+    ) {
+        let targets = &*ATTACK_TARGETS;
+        self.from[square.index() as usize] = piece.map_or(Bitboard::EMPTY, |(piece, color)| {
+            targets.attacks_from(square, piece, color, occupied)
+        });
+
+        // The sliders whose rays reach `square` attacked it before the change,
+        // blocked there or passing through
+        let mut sliders = (diagonal_sliders | orthogonal_sliders) & !square.to_bitboard();
+        while !sliders.is_empty() {
+            let slider = sliders.pop_lsb_as_square();
+            if !square.overlaps(self.attacks_from(slider)) {
+                continue;
+            }
+            let mut attacks = Bitboard::EMPTY;
+            if slider.overlaps(diagonal_sliders) {
+                attacks |= targets.bishop_attacks(slider, occupied);
+            }
+            if slider.overlaps(orthogonal_sliders) {
+                attacks |= targets.rook_attacks(slider, occupied);
+            }
+            self.from[slider.index() as usize] = attacks;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::move_generator::MoveGenerator;
+
+    /// Check the map against attacks generated from scratch.
+    fn assert_up_to_date(board: &Board, targets: &Targets) {
+        let occupied = board.occupied();
+        for square in Square::ALL {
+            let expected = board.get(square).map_or(Bitboard::EMPTY, |(piece, color)| {
+                targets.attacks_from(square, piece, color, occupied)
+            });
+            assert_eq!(board.attacks_from(square), expected, "{}", board.to_fen());
+        }
+        // Generated attacks leave out the squares of the side's own pieces
+        for color in [Color::White, Color::Black] {
+            let others = !board.pieces(color).occupied();
+            assert_eq!(
+                board.attacks(color) & others,
+                targets.generate_attack_targets(board, color) & others,
+                "{}",
+                board.to_fen()
+            );
+        }
+    }
+
+    #[test]
+    fn test_attacks_stay_up_to_date_through_moves_and_undos() {
+        let move_generator = MoveGenerator::new();
+        let targets = move_generator.targets();
+        let mut board: Board =
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+                .parse()
+                .unwrap();
+        assert_up_to_date(&board, targets);
+
+        let mut played = Vec::new();
+        for ply in 0..40 {
+            let turn = board.turn();
+            let moves = move_generator.generate_moves(&mut board, turn);
+            if moves.is_empty() {
+                break;
+            }
+            // Castling, en passant and promotions come up among the candidates
+            for candidate in &moves {
+                candidate.apply(&mut board).unwrap();
+                assert_up_to_date(&board, targets);
+                candidate.undo(&mut board).unwrap();
+            }
+            let chess_move = moves[(ply * 7) % moves.len()].clone();
+            chess_move.apply(&mut board).unwrap();
+            board.toggle_turn();
+            played.push(chess_move);
+        }
+        while let Some(chess_move) = played.pop() {
+            board.toggle_turn();
+            chess_move.undo(&mut board).unwrap();
+            assert_up_to_date(&board, targets);
+        }
+    }
+
+    #[test]
+    fn test_checkers_and_is_in_check() {
+        // The black queen on h1 checks along the first rank; the bishop on d4
+        // is blocked by the white queen on b2, and blocks her in turn
+        let board: Board = "7k/8/8/8/3b4/8/1Q5p/K6q w - - 0 1".parse().unwrap();
+        assert_eq!(board.checkers(Color::White), Square::H1.to_bitboard());
+        assert!(board.is_in_check(Color::White));
+        assert_eq!(board.checkers(Color::Black), Bitboard::EMPTY);
+        assert!(!board.is_in_check(Color::Black));
+
+        // Double check: the knight and the rook it uncovered
+        let board: Board = "4k3/8/3N4/8/8/8/8/3KR3 b - - 0 1".parse().unwrap();
+        assert_eq!(
+            board.checkers(Color::Black),
+            Square::D6.to_bitboard() | Square::E1.to_bitboard()
+        );
+        assert!(board.is_in_check(Color::Black));
+    }
+}
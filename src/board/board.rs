@@ -7,14 +7,22 @@ use common::bitboard::{Bitboard, Square};
 use tracing::instrument;
 
 use crate::{
+    chess_move::chess_move::ChessMove,
     chess_position,
     input_handler::fen::{parse_fen, FenParseError},
 };
 
 use super::{
-    castle_rights::CastleRights, error::BoardError, fullmove_number::FullmoveNumber,
-    halfmove_clock::HalfmoveClock, move_info::MoveInfo, piece_set::PieceSet,
-    position_info::PositionInfo, Color, Piece,
+    attack_map::AttackMap,
+    castle_rights::CastleRights,
+    error::BoardError,
+    fullmove_number::FullmoveNumber,
+    halfmove_clock::HalfmoveClock,
+    move_info::MoveInfo,
+    piece_set::PieceSet,
+    position_info::{castling_rights_key, en_passant_key, piece_key, PositionInfo, ZOBRIST_TURN},
+    variant::Variant,
+    Color, Piece,
 };
 
 /// Represents the state of a chess board.
@@ -24,6 +32,9 @@ pub struct Board {
     turn: Color,
     move_info: MoveInfo,
     position_info: PositionInfo,
+    variant: Variant,
+    /// The squares each piece attacks, updated as pieces are put and removed.
+    attack_map: AttackMap,
 }
 
 impl Default for Board {
@@ -43,13 +54,21 @@ impl Default for Board {
 
 impl Board {
     pub fn new() -> Self {
-        Self {
+        let mut board = Self {
             white: PieceSet::new(),
             black: PieceSet::new(),
             turn: Color::White,
             move_info: MoveInfo::new(),
             position_info: PositionInfo::new(),
-        }
+            variant: Variant::Standard,
+            attack_map: AttackMap::new(),
+        };
+        // Hash the castling rights the board starts with, like every other
+        // part of the position
+        board
+            .position_info
+            .update_zobrist_hash_toggle_castling_rights(board.peek_castle_rights().bits());
+        board
     }
 
     pub fn pieces(&self, color: Color) -> &PieceSet {
@@ -103,6 +122,7 @@ impl Board {
         if result.is_ok() {
             self.position_info
                 .update_zobrist_hash_toggle_piece(square, piece, color);
+            self.update_attacks(square, Some((piece, color)));
         }
 
         result
@@ -117,9 +137,60 @@ impl Board {
         }?;
         self.position_info
             .update_zobrist_hash_toggle_piece(square, piece, color);
+        self.update_attacks(square, None);
         Some((piece, color))
     }
 
+    /// Squares attacked by `color`'s pieces, including those of its own
+    /// pieces they defend.
+    #[inline]
+    pub fn attacks(&self, color: Color) -> Bitboard {
+        self.attack_map
+            .attacks_from_all(self.pieces(color).occupied())
+    }
+
+    /// Squares attacked by the piece on `square`, or none if it is empty.
+    #[inline]
+    pub fn attacks_from(&self, square: Square) -> Bitboard {
+        self.attack_map.attacks_from(square)
+    }
+
+    /// The opponent's pieces giving check to `color`'s king.
+    pub fn checkers(&self, color: Color) -> Bitboard {
+        let king = self.pieces(color).locate(Piece::King);
+        let mut checkers = Bitboard::EMPTY;
+        let mut opponents = self.pieces(color.opposite()).occupied();
+        while !opponents.is_empty() {
+            let square = opponents.pop_lsb_as_square();
+            if self.attack_map.attacks_from(square).overlaps(king) {
+                checkers |= square.to_bitboard();
+            }
+        }
+        checkers
+    }
+
+    /// Whether `color`'s king is in check.
+    #[inline]
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let king = self.pieces(color).locate(Piece::King);
+        king.overlaps(self.attacks(color.opposite()))
+    }
+
+    fn update_attacks(&mut self, square: Square, piece: Option<(Piece, Color)>) {
+        let queens = self.white.locate(Piece::Queen) | self.black.locate(Piece::Queen);
+        let diagonal_sliders =
+            self.white.locate(Piece::Bishop) | self.black.locate(Piece::Bishop) | queens;
+        let orthogonal_sliders =
+            self.white.locate(Piece::Rook) | self.black.locate(Piece::Rook) | queens;
+        self.attack_map.update(
+            square,
+            piece,
+            self.occupied(),
+            diagonal_sliders,
+            orthogonal_sliders,
+        );
+    }
+
     pub fn turn(&self) -> Color {
         self.turn
     }
@@ -131,11 +202,17 @@ impl Board {
     }
 
     pub fn set_turn(&mut self, turn: Color) -> Color {
-        self.turn = turn;
+        if turn != self.turn {
+            self.toggle_turn();
+        }
         turn
     }
 
     pub fn push_en_passant_target(&mut self, target_square: Option<Square>) -> Option<Square> {
+        // Only the current target is hashed, so positions hash the same
+        // however they were reached
+        self.position_info
+            .update_zobrist_hash_toggle_en_passant_target(self.peek_en_passant_target());
         self.position_info
             .update_zobrist_hash_toggle_en_passant_target(target_square);
         self.move_info.push_en_passant_target(target_square)
@@ -149,6 +226,8 @@ impl Board {
         let target_square = self.move_info.pop_en_passant_target();
         self.position_info
             .update_zobrist_hash_toggle_en_passant_target(target_square);
+        self.position_info
+            .update_zobrist_hash_toggle_en_passant_target(self.peek_en_passant_target());
         target_square
     }
 
@@ -181,6 +260,7 @@ impl Board {
         new_rights
     }
 
+    /// Called after Black's moves, which complete a full move.
     pub fn increment_fullmove_clock(&mut self) -> FullmoveNumber {
         self.move_info.increment_fullmove_clock()
     }
@@ -217,12 +297,131 @@ impl Board {
         self.move_info.pop_halfmove_clock()
     }
 
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Play this position under `variant`'s rules. Set it before any moves
+    /// are made, as moves only track three-check state when it's in play.
+    pub fn set_variant(&mut self, variant: Variant) -> Variant {
+        self.variant = variant;
+        variant
+    }
+
+    /// The checks `color` has given so far in three-check.
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.move_info.peek_checks_given()[color as usize]
+    }
+
+    pub fn push_checks_given(&mut self, checks: [u8; 2]) -> [u8; 2] {
+        self.toggle_checks_given_hash(self.move_info.peek_checks_given());
+        self.toggle_checks_given_hash(checks);
+        self.move_info.push_checks_given(checks)
+    }
+
+    pub fn pop_checks_given(&mut self) -> [u8; 2] {
+        let checks = self.move_info.pop_checks_given();
+        self.toggle_checks_given_hash(checks);
+        self.toggle_checks_given_hash(self.move_info.peek_checks_given());
+        checks
+    }
+
+    fn toggle_checks_given_hash(&mut self, checks: [u8; 2]) {
+        for color in [Color::White, Color::Black] {
+            self.position_info
+                .update_zobrist_hash_toggle_checks_given(color, checks[color as usize]);
+        }
+    }
+
     // PositionInfo delegation
 
-    pub fn current_position_hash(&self) -> u64 {
+    /// The Zobrist hash of the position: the XOR of the keys of each piece on
+    /// its square, the castling rights, the en passant target if there is
+    /// one, the side to move when it's Black, and in three-check the checks
+    /// each side has given. Positions that are the same by these hash the
+    /// same however they were reached, whether by moves or from FEN. The keys
+    /// come from the precompile step's seed, so hashes are stable between
+    /// builds with the same seed. The transposition table, repetition
+    /// detection and the opening book are all keyed by it.
+    pub fn zobrist(&self) -> u64 {
         self.position_info.current_position_hash()
     }
 
+    /// The same as `zobrist`.
+    pub fn current_position_hash(&self) -> u64 {
+        self.zobrist()
+    }
+
+    /// The `zobrist` hash of the position after `chess_move` is played and
+    /// the turn passes to the other side, worked out from this position's
+    /// hash without playing it. In three-check, where a check changes the
+    /// hash, the move is played on a copy of the board instead.
+    ///
+    /// # Panics
+    ///
+    /// If there is no piece on the move's from square.
+    pub fn zobrist_for_move(&self, chess_move: &ChessMove) -> u64 {
+        if self.variant == Variant::ThreeCheck {
+            let mut board = self.clone();
+            chess_move
+                .apply(&mut board)
+                .expect("chess_move should apply to the board");
+            board.toggle_turn();
+            return board.zobrist();
+        }
+
+        let (from, to) = (chess_move.from_square(), chess_move.to_square());
+        let (piece, color) = self
+            .get(from)
+            .expect("chess_move should move a piece on the board");
+        let mut hash = self.zobrist() ^ ZOBRIST_TURN ^ piece_key(from, piece, color);
+
+        if let Some(capture) = chess_move.captures() {
+            let captured_square = match chess_move {
+                ChessMove::EnPassant(_) => Square::from_rank_file(from.rank(), to.file()),
+                _ => to,
+            };
+            hash ^= piece_key(captured_square, capture.0, color.opposite());
+        }
+        let placed = match chess_move {
+            ChessMove::PawnPromotion(promotion) => promotion.promote_to_piece(),
+            _ => piece,
+        };
+        hash ^= piece_key(to, placed, color);
+        if let ChessMove::Castle(_) = chess_move {
+            let (rook_from, rook_to) = if to.file() > from.file() {
+                (7, 5)
+            } else {
+                (0, 3)
+            };
+            hash ^= piece_key(
+                Square::from_rank_file(to.rank(), rook_from),
+                Piece::Rook,
+                color,
+            ) ^ piece_key(
+                Square::from_rank_file(to.rank(), rook_to),
+                Piece::Rook,
+                color,
+            );
+        }
+
+        // A move from or to a king or rook's starting square loses the
+        // castling rights that go with it
+        let old_rights = self.peek_castle_rights();
+        let new_rights =
+            old_rights.without(castle_rights_lost_on(from) | castle_rights_lost_on(to));
+        hash ^= castling_rights_key(old_rights.bits()) ^ castling_rights_key(new_rights.bits());
+
+        let double_push = piece == Piece::Pawn && from.rank().abs_diff(to.rank()) == 2;
+        let en_passant_target = double_push.then(|| Square::new((from.index() + to.index()) / 2));
+        hash ^ en_passant_key(self.peek_en_passant_target()) ^ en_passant_key(en_passant_target)
+    }
+
+    /// Hash of the pawns alone, for caching pawn structure evaluation.
+    pub fn current_pawn_hash(&self) -> u64 {
+        self.position_info.current_pawn_hash()
+    }
+
     /// Convert the board position to FEN (Forsyth-Edwards Notation) string
     pub fn to_fen(&self) -> String {
         crate::input_handler::fen_serialize::to_fen(self)
@@ -238,6 +437,8 @@ impl Clone for Board {
             turn: self.turn,
             move_info: self.move_info.clone(),
             position_info: self.position_info.clone(),
+            variant: self.variant,
+            attack_map: self.attack_map.clone(),
         }
     }
 }
@@ -249,3 +450,17 @@ impl FromStr for Board {
         parse_fen(input)
     }
 }
+
+/// The castling rights lost when a piece moves from or to `square`: a king
+/// or rook leaving its starting square, or a rook being taken on it.
+fn castle_rights_lost_on(square: Square) -> CastleRights {
+    match square {
+        Square::A1 => CastleRights::white_queenside(),
+        Square::H1 => CastleRights::white_kingside(),
+        Square::E1 => CastleRights::white_kingside() | CastleRights::white_queenside(),
+        Square::A8 => CastleRights::black_queenside(),
+        Square::H8 => CastleRights::black_kingside(),
+        Square::E8 => CastleRights::black_kingside() | CastleRights::black_queenside(),
+        _ => CastleRights::none(),
+    }
+}
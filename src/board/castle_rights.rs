@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use super::castle_rights_bitmask::CastleRightsBitmask;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -58,6 +60,28 @@ impl CastleRights {
     }
 }
 
+impl FromStr for CastleRights {
+    /// The character that is not a castling right.
+    type Err = char;
+
+    /// Parses castling rights as in a FEN, e.g. `KQkq`, `Kq` or `-` for none.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            return Ok(Self::none());
+        }
+        s.chars().try_fold(Self::none(), |rights, c| {
+            Ok(rights
+                | match c {
+                    'K' => Self::white_kingside(),
+                    'Q' => Self::white_queenside(),
+                    'k' => Self::black_kingside(),
+                    'q' => Self::black_queenside(),
+                    _ => return Err(c),
+                })
+        })
+    }
+}
+
 impl From<CastleRightsBitmask> for CastleRights {
     fn from(bits: CastleRightsBitmask) -> Self {
         Self(bits)
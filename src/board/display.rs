@@ -1,18 +1,56 @@
+#[cfg(feature = "native")]
+use std::fmt;
+
+#[cfg(feature = "native")]
 use crate::game::display::GameDisplay;
 
+#[cfg(feature = "native")]
 use super::Board;
-use std::fmt;
 
+/// Drawn with the game display, which needs the terminal.
+#[cfg(feature = "native")]
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut ui = GameDisplay::new();
-        ui.render_game_state(self, self.turn(), None, None, None);
+        ui.render_game_state(self, self.turn(), None, "", None, None);
         write!(f, "{}", ui.buffer())
     }
 }
 
+/// Builds a `Board` from a diagram of its 64 squares, rank 8 first, with
+/// FEN piece letters and `.` for empty squares. The board starts with White
+/// to move and every castling right, unless the diagram is preceded by
+/// annotations ended by a semicolon:
+///
+/// ```ignore
+/// let board = chess_position! {
+///     turn = Color::Black, castling = "Kq", en_passant = E3;
+///     r...k..r
+///     ........
+///     ...
+/// };
+/// ```
+///
+/// `Board`, `Piece` and `Color` need to be in scope where it is used.
 #[macro_export]
 macro_rules! chess_position {
+    ($($key:ident = $value:expr),+ ; $($piece:tt)*) => {{
+        let mut board = $crate::chess_position!($($piece)*);
+        $($crate::chess_position!(@annotate board, $key, $value);)+
+        board
+    }};
+    (@annotate $board:ident, turn, $turn:expr) => {
+        $board.set_turn($turn);
+    };
+    (@annotate $board:ident, castling, $rights:expr) => {
+        let rights: $crate::board::castle_rights::CastleRights = $rights
+            .parse()
+            .unwrap_or_else(|c| panic!("Invalid castling right {:?}", c));
+        $board.lose_castle_rights(!rights);
+    };
+    (@annotate $board:ident, en_passant, $square:expr) => {
+        $board.push_en_passant_target(Some($square));
+    };
     ($($piece:tt)*) => {{
         let mut board = Board::new();
         // Convert all input tokens to a string and filter out whitespace characters.
@@ -55,3 +93,62 @@ macro_rules! chess_position {
         board
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use common::bitboard::*;
+
+    use crate::board::castle_rights::CastleRights;
+    use crate::board::{Board, Color, Piece};
+
+    #[test]
+    fn test_annotations_set_turn_castling_and_en_passant() {
+        let board = chess_position! {
+            turn = Color::Black, castling = "Kq", en_passant = E3;
+            r...k..r
+            pppppppp
+            ........
+            ........
+            ....P...
+            ........
+            PPPP.PPP
+            R...K..R
+        };
+        assert_eq!(board.turn(), Color::Black);
+        assert_eq!(
+            board.peek_castle_rights(),
+            CastleRights::white_kingside() | CastleRights::black_queenside()
+        );
+        assert_eq!(board.peek_en_passant_target(), Some(E3));
+        assert_eq!(board.get(E4), Some((Piece::Pawn, Color::White)));
+        assert_eq!(
+            board.to_fen(),
+            "r3k2r/pppppppp/8/8/4P3/8/PPPP1PPP/R3K2R b Kq e3 0 1"
+        );
+
+        let plain = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+            ....K...
+        };
+        assert_eq!(plain.turn(), Color::White);
+        assert_eq!(plain.peek_castle_rights(), CastleRights::all());
+        let none = chess_position! {
+            castling = "-";
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ........
+            ....K...
+        };
+        assert!(none.peek_castle_rights().is_empty());
+    }
+}
@@ -1,12 +1,12 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct FullmoveNumber(u8);
+pub struct FullmoveNumber(u16);
 
 impl FullmoveNumber {
-    pub const fn new(value: u8) -> Self {
+    pub const fn new(value: u16) -> Self {
         Self(value)
     }
 
-    pub const fn value(self) -> u8 {
+    pub const fn value(self) -> u16 {
         self.0
     }
 
@@ -19,13 +19,13 @@ impl FullmoveNumber {
     }
 }
 
-impl From<u8> for FullmoveNumber {
-    fn from(value: u8) -> Self {
+impl From<u16> for FullmoveNumber {
+    fn from(value: u16) -> Self {
         Self(value)
     }
 }
 
-impl From<FullmoveNumber> for u8 {
+impl From<FullmoveNumber> for u16 {
     fn from(number: FullmoveNumber) -> Self {
         number.0
     }
@@ -1,5 +1,6 @@
 //! Chess board state representation and management.
 
+pub mod attack_map;
 #[allow(clippy::module_inception)]
 pub mod board;
 pub mod castle_rights;
@@ -9,6 +10,7 @@ pub mod error;
 pub mod fullmove_number;
 pub mod halfmove_clock;
 pub mod piece;
+pub mod variant;
 
 mod display;
 mod move_info;
@@ -16,6 +18,11 @@ mod piece_set;
 mod position_info;
 mod state_stack;
 
+#[cfg(test)]
+mod tests;
+
 pub use board::Board;
 pub use color::Color;
 pub use piece::Piece;
+pub(crate) use position_info::en_passant_key;
+pub use variant::Variant;
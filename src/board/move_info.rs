@@ -6,13 +6,15 @@ use super::halfmove_clock::HalfmoveClock;
 use super::state_stack::StateStack;
 
 /// Stores information about state changes related to individual chess moves,
-/// including en passant targets, castle rights, and position clocks.
+/// including en passant targets, castle rights, position clocks, and the
+/// checks each side has given in three-check.
 #[derive(Clone)]
 pub struct MoveInfo {
     en_passant_target_stack: StateStack<Option<Square>>,
     castle_rights_stack: StateStack<CastleRights>,
     halfmove_clock_stack: StateStack<HalfmoveClock>,
     fullmove_clock: FullmoveNumber,
+    checks_given_stack: StateStack<[u8; 2]>,
 }
 
 impl Default for MoveInfo {
@@ -22,6 +24,7 @@ impl Default for MoveInfo {
             castle_rights_stack: StateStack::new(CastleRights::all()),
             halfmove_clock_stack: StateStack::new(HalfmoveClock::new(0)),
             fullmove_clock: FullmoveNumber::new(1),
+            checks_given_stack: StateStack::new([0, 0]),
         }
     }
 }
@@ -122,4 +125,19 @@ impl MoveInfo {
     pub fn pop_halfmove_clock(&mut self) -> HalfmoveClock {
         self.halfmove_clock_stack.pop()
     }
+
+    // Check count state management
+
+    /// Checks given by White and by Black, indexed by `Color`.
+    pub fn peek_checks_given(&self) -> [u8; 2] {
+        *self.checks_given_stack.peek()
+    }
+
+    pub fn push_checks_given(&mut self, checks: [u8; 2]) -> [u8; 2] {
+        self.checks_given_stack.push(checks)
+    }
+
+    pub fn pop_checks_given(&mut self) -> [u8; 2] {
+        self.checks_given_stack.pop()
+    }
 }
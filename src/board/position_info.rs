@@ -7,9 +7,13 @@ include!(concat!(env!("OUT_DIR"), "/zobrist_table.rs"));
 /// Stores information about state changes related to the current (and previous) positions.
 /// Holds the logic for incrementally updating the hash of the current position using
 /// Zobrist hashing: https://www.chessprogramming.org/Zobrist_Hashing
+///
+/// A second hash covers only the pawns, keying the evaluation's pawn
+/// structure cache.
 #[derive(Clone, Default)]
 pub struct PositionInfo {
     current_position_hash: u64,
+    current_pawn_hash: u64,
 }
 
 impl PositionInfo {
@@ -18,18 +22,29 @@ impl PositionInfo {
     }
 
     pub fn update_zobrist_hash_toggle_piece(&mut self, square: Square, piece: Piece, color: Color) {
-        let piece_hash =
-            ZOBRIST_PIECES_TABLE[piece as usize][square.index() as usize][color as usize];
+        let piece_hash = piece_key(square, piece, color);
         self.current_position_hash ^= piece_hash;
+        if piece == Piece::Pawn {
+            self.current_pawn_hash ^= piece_hash;
+        }
     }
 
     pub fn update_zobrist_hash_toggle_en_passant_target(&mut self, square: Option<Square>) {
-        let Some(sq) = square else { return };
-        self.current_position_hash ^= ZOBRIST_EN_PASSANT_TABLE[sq.index() as usize];
+        self.current_position_hash ^= en_passant_key(square);
     }
 
     pub fn update_zobrist_hash_toggle_castling_rights(&mut self, castling_rights: u8) {
-        self.current_position_hash ^= ZOBRIST_CASTLING_RIGHTS_TABLE[castling_rights as usize];
+        self.current_position_hash ^= castling_rights_key(castling_rights);
+    }
+
+    /// Checks given are only hashed once there are some, so a three-check
+    /// position without any hashes like the standard one.
+    pub fn update_zobrist_hash_toggle_checks_given(&mut self, color: Color, checks: u8) {
+        if checks == 0 {
+            return;
+        }
+        let checks = checks.min(ZOBRIST_CHECKS_TABLE[0].len() as u8);
+        self.current_position_hash ^= ZOBRIST_CHECKS_TABLE[color as usize][checks as usize - 1];
     }
 
     pub fn update_zobrist_hash_toggle_turn(&mut self) {
@@ -39,6 +54,28 @@ impl PositionInfo {
     pub fn current_position_hash(&self) -> u64 {
         self.current_position_hash
     }
+
+    pub fn current_pawn_hash(&self) -> u64 {
+        self.current_pawn_hash
+    }
+}
+
+/// The key of `piece` of `color` standing on `square`.
+#[inline(always)]
+pub fn piece_key(square: Square, piece: Piece, color: Color) -> u64 {
+    ZOBRIST_PIECES_TABLE[piece as usize][square.index() as usize][color as usize]
+}
+
+/// The key of the en passant target, or nothing without one.
+#[inline(always)]
+pub fn en_passant_key(square: Option<Square>) -> u64 {
+    square.map_or(0, |sq| ZOBRIST_EN_PASSANT_TABLE[sq.index() as usize])
+}
+
+/// The key of a set of castling rights, by their bits.
+#[inline(always)]
+pub fn castling_rights_key(castling_rights: u8) -> u64 {
+    ZOBRIST_CASTLING_RIGHTS_TABLE[castling_rights as usize]
 }
 
 #[cfg(test)]
@@ -48,6 +85,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn test_zobrist_hashing_piece_placement() {
         let mut position_info = PositionInfo::new();
         let mut hash = 0;
@@ -86,6 +124,23 @@ mod tests {
         assert_eq!(position_info.current_position_hash(), hash);
     }
 
+    #[test]
+    fn test_pawn_hash_only_covers_pawns() {
+        let mut position_info = PositionInfo::new();
+        position_info.update_zobrist_hash_toggle_piece(Square::E2, Piece::Pawn, Color::White);
+        let pawn_hash = position_info.current_pawn_hash();
+        assert_eq!(
+            pawn_hash,
+            ZOBRIST_PIECES_TABLE[Piece::Pawn as usize][Square::E2.index() as usize]
+                [Color::White as usize]
+        );
+
+        position_info.update_zobrist_hash_toggle_piece(Square::G1, Piece::Knight, Color::White);
+        position_info.update_zobrist_hash_toggle_turn();
+        assert_eq!(position_info.current_pawn_hash(), pawn_hash);
+        assert_ne!(position_info.current_position_hash(), pawn_hash);
+    }
+
     #[test]
     fn test_zobrist_turn_toggle_changes_hash() {
         let mut position_info = PositionInfo::new();
@@ -107,6 +162,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_range_loop)]
     fn test_zobrist_piece_hashing_reversible() {
         let mut position_info = PositionInfo::new();
         let mut hash = 0;
@@ -127,7 +183,6 @@ mod tests {
                 random_piece,
                 Color::White,
             );
-            hash ^= ZOBRIST_PIECES_TABLE[random_piece as usize][i][Color::White as usize];
         }
         assert_eq!(position_info.current_position_hash(), 0);
     }
@@ -1,11 +1,15 @@
-use crate::chess_move::chess_move_effect::ChessMoveEffect;
-use crate::{castle_kingside, std_move};
+//! Board tests: Zobrist hashing through moves, FEN and the side to move.
+
+use std::str::FromStr;
+
+use common::bitboard::*;
 
-use super::*;
 use crate::chess_move::castle::CastleChessMove;
 use crate::chess_move::chess_move::ChessMove;
 use crate::chess_move::standard::StandardChessMove;
-use common::bitboard::*;
+use crate::{castle_kingside, std_move};
+
+use super::*;
 
 #[test]
 fn test_zobrist_hashing_is_equal_for_transpositions() {
@@ -15,7 +19,7 @@ fn test_zobrist_hashing_is_equal_for_transpositions() {
     let initial_hash_2 = board2.current_position_hash();
     assert_eq!(initial_hash_1, initial_hash_2);
 
-    let board1_moves = vec![
+    let board1_moves = [
         std_move!(E2, E4),
         std_move!(E7, E5),
         std_move!(G1, F3),
@@ -25,7 +29,7 @@ fn test_zobrist_hashing_is_equal_for_transpositions() {
         castle_kingside!(Color::White),
     ];
 
-    let board2_moves = vec![
+    let board2_moves = [
         std_move!(G1, F3),
         std_move!(B8, C6),
         std_move!(E2, E4),
@@ -89,3 +93,45 @@ fn test_zobrist_hashing_is_equal_for_transpositions() {
     );
 }
 
+#[test]
+fn test_zobrist_covers_the_side_to_move() {
+    // The same position from FEN and after 1. e4, with Black to move
+    let from_fen =
+        Board::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let mut played = Board::default();
+    std_move!(E2, E4).apply(&mut played).unwrap();
+    played.toggle_turn();
+    assert_eq!(from_fen.zobrist(), played.zobrist());
+
+    let mut white_to_move = from_fen.clone();
+    white_to_move.set_turn(Color::White);
+    assert_ne!(white_to_move.zobrist(), from_fen.zobrist());
+    white_to_move.set_turn(Color::Black);
+    assert_eq!(white_to_move.zobrist(), from_fen.zobrist());
+}
+
+#[test]
+fn test_zobrist_for_move_matches_playing_the_move() {
+    let move_generator = crate::move_generator::MoveGenerator::default();
+    for fen in [
+        // Castling both ways, and rooks that can be taken on their squares
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+        // En passant, double pushes and promotions with and without capture
+        "rnbqkbnr/ppp2ppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ] {
+        let mut board = Board::from_str(fen).unwrap();
+        let turn = board.turn();
+        for chess_move in move_generator.generate_moves(&mut board, turn).iter() {
+            let expected = board.zobrist_for_move(chess_move);
+            let mut played = board.clone();
+            chess_move.apply(&mut played).unwrap();
+            played.toggle_turn();
+            assert_eq!(expected, played.zobrist(), "{} in {}", chess_move, fen);
+            // And from scratch, through FEN
+            let reparsed = Board::from_str(&played.to_fen()).unwrap();
+            assert_eq!(expected, reparsed.zobrist(), "{} in {}", chess_move, fen);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Chess variants that change how a game is won.
+//!
+//! A variant is part of the board, so everything that plays or searches a
+//! position follows its rules:
+//!
+//! * King of the Hill: moving your king to one of the four center squares
+//!   (d4, e4, d5, e5) wins.
+//! * Three-check: giving check for the third time wins. The checks given by
+//!   each side are part of the position and its hash.
+//!
+//! Both are otherwise played with the standard rules; once a variant win is
+//! reached the game is over and there are no more legal moves.
+
+use std::fmt;
+use std::str::FromStr;
+
+use common::bitboard::{Bitboard, Square};
+
+use super::color::Color;
+use super::piece::Piece;
+use super::Board;
+
+/// Checks given that win a three-check game.
+pub const CHECKS_TO_WIN: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    KingOfTheHill,
+    ThreeCheck,
+}
+
+/// All variants, in the order they're listed to the user.
+pub const ALL_VARIANTS: [Variant; 3] = [
+    Variant::Standard,
+    Variant::KingOfTheHill,
+    Variant::ThreeCheck,
+];
+
+impl Variant {
+    /// The variant's `UCI_Variant` name, as used by other engines and GUIs.
+    pub fn uci_name(self) -> &'static str {
+        match self {
+            Variant::Standard => "chess",
+            Variant::KingOfTheHill => "kingofthehill",
+            Variant::ThreeCheck => "3check",
+        }
+    }
+
+    /// The variant's name for people.
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::ThreeCheck => "Three-check",
+        }
+    }
+
+    /// The squares a king wins on in King of the Hill.
+    pub fn hill() -> Bitboard {
+        Square::D4 | Square::E4 | Square::D5 | Square::E5
+    }
+
+    /// The side that has won `board` by this variant's own rule, if either.
+    /// Checkmate and draws are left to the usual game ending checks.
+    pub fn winner(self, board: &Board) -> Option<Color> {
+        match self {
+            Variant::Standard => None,
+            Variant::KingOfTheHill => [Color::White, Color::Black].iter().copied().find(|&color| {
+                board
+                    .pieces(color)
+                    .locate(Piece::King)
+                    .overlaps(Self::hill())
+            }),
+            Variant::ThreeCheck => [Color::White, Color::Black]
+                .iter()
+                .copied()
+                .find(|&color| board.checks_given(color) >= CHECKS_TO_WIN),
+        }
+    }
+
+    /// How a variant win came about, to follow "wins by", e.g. "reaching
+    /// the hill".
+    pub fn win_description(self) -> &'static str {
+        match self {
+            Variant::Standard => "variant rule",
+            Variant::KingOfTheHill => "reaching the hill",
+            Variant::ThreeCheck => "third check",
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "chess" | "standard" | "normal" => Ok(Variant::Standard),
+            "kingofthehill" | "koth" => Ok(Variant::KingOfTheHill),
+            "3check" | "threecheck" => Ok(Variant::ThreeCheck),
+            _ => Err(format!(
+                "invalid variant {:?}; options are: {}",
+                s,
+                ALL_VARIANTS.map(Variant::uci_name).join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.uci_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alpha_beta_searcher::SearchContext;
+    use crate::chess_move::chess_move::ChessMove;
+    use crate::chess_move::standard::StandardChessMove;
+    use crate::chess_search::search_best_move;
+    use crate::evaluate::{game_ending, GameEnding};
+    use crate::input_handler::fen::parse_fen;
+    use crate::move_generator::MoveGenerator;
+
+    #[test]
+    fn test_variant_names_round_trip() {
+        for variant in ALL_VARIANTS {
+            assert_eq!(variant.uci_name().parse::<Variant>(), Ok(variant));
+        }
+        assert_eq!("King-of-the-Hill".parse(), Ok(Variant::KingOfTheHill));
+        assert_eq!("three_check".parse(), Ok(Variant::ThreeCheck));
+        assert!("crazyhouse".parse::<Variant>().is_err());
+    }
+
+    #[test]
+    fn test_king_on_the_hill_wins() {
+        let mut board = parse_fen("4k3/8/8/8/8/4K3/8/8 w - - 0 1").unwrap();
+        board.set_variant(Variant::KingOfTheHill);
+        assert_eq!(board.variant().winner(&board), None);
+
+        let chess_move = ChessMove::Standard(StandardChessMove::new(Square::E3, Square::E4, None));
+        chess_move.apply(&mut board).unwrap();
+        assert_eq!(board.variant().winner(&board), Some(Color::White));
+        assert_eq!(Variant::Standard.winner(&board), None);
+    }
+
+    #[test]
+    fn test_checks_are_counted_and_undone() {
+        let mut board = parse_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        board.set_variant(Variant::ThreeCheck);
+        let hash = board.current_position_hash();
+
+        let check = ChessMove::Standard(StandardChessMove::new(Square::A1, Square::A8, None));
+        check.apply(&mut board).unwrap();
+        assert_eq!(board.checks_given(Color::White), 1);
+        assert_eq!(board.checks_given(Color::Black), 0);
+
+        check.undo(&mut board).unwrap();
+        assert_eq!(board.checks_given(Color::White), 0);
+        assert_eq!(board.current_position_hash(), hash);
+    }
+
+    #[test]
+    fn test_a_variant_win_ends_the_game() {
+        let mut board = parse_fen("4k3/8/8/8/4K3/8/7P/8 b - - 0 1").unwrap();
+        let move_generator = MoveGenerator::default();
+        assert!(game_ending(&mut board, &move_generator, Color::Black, &[]).is_none());
+
+        board.set_variant(Variant::KingOfTheHill);
+        assert!(matches!(
+            game_ending(&mut board, &move_generator, Color::Black, &[]),
+            Some(GameEnding::VariantWin)
+        ));
+    }
+
+    #[test]
+    fn test_search_plays_for_the_third_check() {
+        // Ra8+ is no mate, but it is White's third check
+        let mut board = parse_fen("4k3/8/8/8/8/8/7p/R3K3 w - - 1+3 0 1").unwrap();
+        let mut context = SearchContext::new(3);
+        let best_move = search_best_move(&mut context, &mut board).unwrap();
+        assert_eq!(best_move.to_square(), Square::A8);
+    }
+}
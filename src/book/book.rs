@@ -5,6 +5,9 @@ use std::fmt::{Display, Formatter};
 use common::bitboard::Square;
 use rustc_hash::FxHashMap;
 
+use crate::board::{en_passant_key, Board};
+use crate::move_generator::MoveGenerator;
+
 include!(concat!(env!("OUT_DIR"), "/opening_book.rs"));
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -29,20 +32,27 @@ pub struct OpeningLine {
     pub moves: String,
 }
 
+/// A book position as the precompile step writes it: its key, the name of a
+/// line ending there, and its moves as from and to square indices with how
+/// many book lines play each.
+pub type BookPositionEntry = (u64, Option<&'static str>, &'static [(u8, u8, u32)]);
+
+/// A position in the book.
 #[derive(Default)]
 pub struct BookNode {
-    lines: FxHashMap<BookMove, Box<BookNode>>,
+    /// The book moves from this position, with how many book lines play
+    /// each, e.g. the games of a PGN book that played them.
+    moves: Vec<(BookMove, u32)>,
+    /// The name of a book line ending in this position.
     line_name: Option<String>,
 }
 
-impl BookNode {
-    fn new() -> Self {
-        Default::default()
-    }
-}
-
+/// Book moves by position, so a game that reaches a book position by another
+/// move order still finds its moves. Positions are keyed by `Book::key`.
 pub struct Book {
-    root: BookNode,
+    positions: FxHashMap<u64, BookNode>,
+    /// The named lines, for drilling an opening.
+    lines: Vec<(String, Vec<BookMove>)>,
 }
 
 impl Default for Book {
@@ -55,91 +65,186 @@ impl Default for Book {
 impl Book {
     pub fn new() -> Self {
         Self {
-            root: BookNode::default(),
+            positions: FxHashMap::default(),
+            lines: Vec::new(),
         }
     }
 
+    /// A book from the tables the precompile step generates: its positions,
+    /// and its named lines in UCI.
+    pub fn from_tables(positions: &[BookPositionEntry], lines: &[(&str, &str)]) -> Self {
+        let positions = positions
+            .iter()
+            .map(|&(key, line_name, moves)| {
+                let moves = moves
+                    .iter()
+                    .map(|&(from, to, count)| {
+                        (BookMove::new(Square::new(from), Square::new(to)), count)
+                    })
+                    .collect();
+                let node = BookNode {
+                    moves,
+                    line_name: line_name.map(str::to_string),
+                };
+                (key, node)
+            })
+            .collect();
+        let lines = lines
+            .iter()
+            .map(|(name, moves)| (name.to_string(), parse_line(moves)))
+            .collect();
+        Self { positions, lines }
+    }
+
+    /// The key `board` is found by in the book: its Zobrist hash less the en
+    /// passant target. Every double push sets a target, which would keep a
+    /// line ending in one from transposing into a line ending in another move.
+    pub fn key(board: &Board) -> u64 {
+        board.zobrist() ^ en_passant_key(board.peek_en_passant_target())
+    }
+
+    /// Add a line of moves in UCI from the starting position.
+    ///
+    /// Panics if a move is not legal.
     pub fn add_line(&mut self, line: OpeningLine) {
-        let moves = line.moves.split(' ');
-        let moves_count = moves.clone().count();
-        if moves_count == 0 {
+        let moves = parse_line(&line.moves);
+        if moves.is_empty() {
             return;
         }
 
-        let mut curr_node = &mut self.root;
-
-        for (i, raw_move) in moves.clone().enumerate() {
-            let raw_from_square: String = raw_move.chars().take(2).collect();
-            let raw_to_square: String = raw_move.chars().skip(2).take(2).collect();
-            let from_square = Square::from_algebraic(&raw_from_square)
-                .unwrap_or_else(|| panic!("Invalid square: {}", raw_from_square));
-            let to_square = Square::from_algebraic(&raw_to_square)
-                .unwrap_or_else(|| panic!("Invalid square: {}", raw_to_square));
-            let book_move = BookMove::new(from_square, to_square);
-
-            let next_node = curr_node
-                .lines
-                .entry(book_move)
-                .or_insert_with(|| Box::new(BookNode::new()));
-
-            if i == moves_count - 1 {
-                next_node.line_name = Some(line.name.clone());
+        let move_generator = MoveGenerator::default();
+        let mut board = Board::default();
+        for &book_move in &moves {
+            let node = self.positions.entry(Self::key(&board)).or_default();
+            match node.moves.iter_mut().find(|(move_, _)| *move_ == book_move) {
+                Some((_, count)) => *count += 1,
+                None => node.moves.push((book_move, 1)),
             }
 
-            curr_node = next_node;
+            play_book_move(&move_generator, &mut board, book_move)
+                .unwrap_or_else(|| panic!("{} is not legal in {}", book_move, line.name));
         }
+        self.positions
+            .entry(Self::key(&board))
+            .or_default()
+            .line_name = Some(line.name.clone());
+        self.lines.push((line.name, moves));
     }
 
-    pub fn get_next_moves(&self, line: Vec<BookMove>) -> Vec<(BookMove, Option<String>)> {
-        let mut curr_node = &self.root;
+    /// Whether `board` is a book position.
+    pub fn contains(&self, board: &Board) -> bool {
+        self.positions.contains_key(&Self::key(board))
+    }
 
-        for book_move in line {
-            let next = curr_node.lines.get(&book_move);
-            if next.is_none() {
-                return vec![];
-            }
+    /// The book moves in `board` with how many book lines play each, the most
+    /// popular first.
+    pub fn get_move_popularity(&self, board: &Board) -> Vec<(BookMove, u32)> {
+        let Some(node) = self.positions.get(&Self::key(board)) else {
+            return vec![];
+        };
 
-            curr_node = next.expect("next should exist after is_none check");
-        }
+        let mut moves = node.moves.clone();
+        moves.sort_by_key(|&(move_, count)| {
+            (
+                std::cmp::Reverse(count),
+                move_.from_square().index(),
+                move_.to_square().index(),
+            )
+        });
+        moves
+    }
+
+    /// The name of the latest named book position of a game, given the book
+    /// keys of its positions in order, or None once the game leaves the book.
+    pub fn get_line(&self, position_keys: &[u64]) -> Option<String> {
+        position_keys
+            .iter()
+            .rev()
+            .map_while(|key| self.positions.get(key))
+            .find_map(|node| node.line_name.clone())
+    }
 
-        curr_node
+    /// Every named line whose name matches `query`, with the moves leading to it.
+    /// Names equal to `query` (ignoring case) are preferred; otherwise all names
+    /// containing it are returned.
+    pub fn find_lines(&self, query: &str) -> Vec<(String, Vec<BookMove>)> {
+        let query = query.trim().to_lowercase();
+        let exact: Vec<_> = self
             .lines
             .iter()
-            .map(|(move_, node)| (*move_, node.line_name.clone()))
+            .filter(|(name, _)| name.to_lowercase() == query)
+            .cloned()
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        self.lines
+            .iter()
+            .filter(|(name, _)| name.to_lowercase().contains(&query))
+            .cloned()
             .collect()
     }
+}
 
-    pub fn get_line(&self, line: Vec<BookMove>) -> Option<String> {
-        let mut curr_node = &self.root;
-        let mut last_line_name: Option<String> = None;
-
-        for book_move in line {
-            curr_node = curr_node.lines.get(&book_move)?;
-            // Track the most recent opening name we've seen
-            if curr_node.line_name.is_some() {
-                last_line_name.clone_from(&curr_node.line_name);
-            }
-        }
+/// Play `book_move` on `board` and hand the turn over, or None if it is not
+/// a legal move.
+fn play_book_move(
+    move_generator: &MoveGenerator,
+    board: &mut Board,
+    book_move: BookMove,
+) -> Option<()> {
+    let turn = board.turn();
+    let chess_move = move_generator
+        .generate_moves(board, turn)
+        .into_iter()
+        .find(|m| {
+            m.from_square() == book_move.from_square() && m.to_square() == book_move.to_square()
+        })?;
+    chess_move.apply(board).ok()?;
+    board.toggle_turn();
+    Some(())
+}
 
-        last_line_name.or(curr_node.line_name.clone())
-    }
+/// The moves of a line in UCI, separated by spaces.
+///
+/// Panics on a move that does not name two squares.
+fn parse_line(moves: &str) -> Vec<BookMove> {
+    moves
+        .split_whitespace()
+        .map(|raw_move| {
+            let raw_from_square = raw_move.get(0..2).unwrap_or(raw_move);
+            let raw_to_square = raw_move.get(2..4).unwrap_or(raw_move);
+            let from_square = Square::from_algebraic(raw_from_square)
+                .unwrap_or_else(|| panic!("Invalid square: {}", raw_from_square));
+            let to_square = Square::from_algebraic(raw_to_square)
+                .unwrap_or_else(|| panic!("Invalid square: {}", raw_to_square));
+            BookMove::new(from_square, to_square)
+        })
+        .collect()
 }
 
 impl Display for Book {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.root)
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|(name, moves)| {
+                let moves: Vec<String> = moves.iter().map(BookMove::to_string).collect();
+                format!("{}: {}", name, moves.join(" "))
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
     }
 }
 
 impl Display for BookNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut lines = vec![];
-        for (move_, node) in self.lines.iter() {
-            let mut line = format!("{}", move_);
-            if let Some(name) = node.line_name.clone() {
-                line.push_str(&format!(" {}", name));
-            }
-            lines.push(line);
+        for (move_, count) in self.moves.iter() {
+            lines.push(format!("{} ({})", move_, count));
+        }
+        if let Some(name) = &self.line_name {
+            lines.push(name.clone());
         }
         write!(f, "{}", lines.join("\n"))
     }
@@ -150,3 +255,95 @@ impl Display for BookMove {
         write!(f, "{}{}", self.0.to_algebraic(), self.1.to_algebraic())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use common::bitboard::*;
+
+    use super::*;
+
+    fn line(moves: &str) -> OpeningLine {
+        OpeningLine {
+            name: moves.to_string(),
+            moves: moves.to_string(),
+        }
+    }
+
+    fn board_after(moves: &str) -> Board {
+        let move_generator = MoveGenerator::default();
+        let mut board = Board::default();
+        for book_move in parse_line(moves) {
+            play_book_move(&move_generator, &mut board, book_move).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn test_move_popularity_counts_the_lines_through_each_move() {
+        let mut book = Book::new();
+        book.add_line(line("d2d4 d7d5"));
+        book.add_line(line("e2e4 e7e5"));
+        book.add_line(line("e2e4 c7c5"));
+        book.add_line(line("e2e4 e7e5 g1f3"));
+
+        assert_eq!(
+            book.get_move_popularity(&board_after("")),
+            vec![(BookMove::new(E2, E4), 3), (BookMove::new(D2, D4), 1)]
+        );
+        assert_eq!(
+            book.get_move_popularity(&board_after("e2e4")),
+            vec![(BookMove::new(E7, E5), 2), (BookMove::new(C7, C5), 1)]
+        );
+        assert_eq!(book.get_move_popularity(&board_after("g1f3")), vec![]);
+    }
+
+    #[test]
+    fn test_transpositions_reach_book_moves() {
+        let mut book = Book::new();
+        book.add_line(OpeningLine {
+            name: "Queen's Gambit Declined".to_string(),
+            moves: "d2d4 d7d5 c2c4 e7e6 b1c3 g8f6".to_string(),
+        });
+
+        // Black's last move set an en passant target the book line never had
+        let transposed = board_after("c2c4 e7e6 d2d4 d7d5");
+        assert!(book.contains(&transposed));
+        assert_eq!(
+            book.get_move_popularity(&transposed),
+            vec![(BookMove::new(B1, C3), 1)]
+        );
+
+        let keys: Vec<u64> = ["c2c4 e7e6 d2d4 d7d5", "c2c4 e7e6 d2d4 d7d5 b1c3 g8f6"]
+            .iter()
+            .map(|moves| Book::key(&board_after(moves)))
+            .collect();
+        assert_eq!(
+            book.get_line(&keys).as_deref(),
+            Some("Queen's Gambit Declined")
+        );
+        assert_eq!(book.get_line(&keys[..1]), None);
+        let out_of_book = Book::key(&board_after("c2c4 e7e5"));
+        assert_eq!(book.get_line(&[keys[1], out_of_book]), None);
+    }
+
+    #[test]
+    fn test_the_generated_book_finds_every_position_of_its_lines() {
+        let book = Book::default();
+        let move_generator = MoveGenerator::default();
+        for (name, moves) in &book.lines {
+            let mut board = Board::default();
+            for &book_move in moves {
+                let book_moves = book.get_move_popularity(&board);
+                assert!(
+                    book_moves.iter().any(|&(move_, _)| move_ == book_move),
+                    "{} is not a book move in {}",
+                    book_move,
+                    name
+                );
+                play_book_move(&move_generator, &mut board, book_move)
+                    .unwrap_or_else(|| panic!("{} is not legal in {}", book_move, name));
+            }
+            assert!(book.contains(&board), "{} ends out of book", name);
+        }
+    }
+}
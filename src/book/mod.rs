@@ -2,5 +2,7 @@
 
 #[allow(clippy::module_inception)]
 pub mod book;
+pub mod selection;
 
 pub use book::{Book, BookMove, BookNode, OpeningLine};
+pub use selection::{BookPolicy, BookSettings, ALL_BOOK_POLICIES};
@@ -0,0 +1,148 @@
+//! How the engine picks among the book moves of a position, and how long it
+//! keeps to the book.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::BookMove;
+
+/// How a move is chosen from the book moves of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookPolicy {
+    /// At random, in proportion to how many book lines play each move.
+    #[default]
+    Popular,
+    /// The move whose position evaluates best, the most popular of equals.
+    Best,
+    /// At random, every book move alike, so rare sidelines come up as often
+    /// as main lines.
+    Variety,
+}
+
+pub const ALL_BOOK_POLICIES: [BookPolicy; 3] =
+    [BookPolicy::Popular, BookPolicy::Best, BookPolicy::Variety];
+
+impl BookPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BookPolicy::Popular => "popular",
+            BookPolicy::Best => "best",
+            BookPolicy::Variety => "variety",
+        }
+    }
+}
+
+impl FromStr for BookPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ALL_BOOK_POLICIES
+            .iter()
+            .copied()
+            .find(|policy| policy.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                format!(
+                    "unknown book policy {:?}; expected popular, best or variety",
+                    s
+                )
+            })
+    }
+}
+
+impl fmt::Display for BookPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Whether and how the engine plays from its opening book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookSettings {
+    pub enabled: bool,
+    pub policy: BookPolicy,
+    /// Moves per side played from the book at most, or None for as long as
+    /// the game stays in it.
+    pub depth: Option<u8>,
+}
+
+impl Default for BookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            policy: BookPolicy::default(),
+            depth: None,
+        }
+    }
+}
+
+impl BookSettings {
+    /// Whether the book is consulted after `plies` moves of the game.
+    pub fn in_book(&self, plies: usize) -> bool {
+        self.enabled
+            && self
+                .depth
+                .is_none_or(|depth| plies < 2 * usize::from(depth))
+    }
+}
+
+/// One of `moves` at random, weighted by their popularity.
+pub fn pick_popular(moves: &[(BookMove, u32)]) -> Option<BookMove> {
+    let total: u64 = moves.iter().map(|&(_, count)| u64::from(count)).sum();
+    if total == 0 {
+        return moves.first().map(|&(book_move, _)| book_move);
+    }
+    let mut pick = fastrand::u64(..total);
+    for &(book_move, count) in moves {
+        if pick < u64::from(count) {
+            return Some(book_move);
+        }
+        pick -= u64::from(count);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use common::bitboard::*;
+
+    use super::*;
+
+    #[test]
+    fn test_book_depth_counts_moves_per_side() {
+        let settings = BookSettings {
+            depth: Some(2),
+            ..BookSettings::default()
+        };
+        assert!(settings.in_book(3));
+        assert!(!settings.in_book(4));
+        assert!(BookSettings::default().in_book(100));
+        let disabled = BookSettings {
+            enabled: false,
+            ..BookSettings::default()
+        };
+        assert!(!disabled.in_book(0));
+    }
+
+    #[test]
+    fn test_popular_moves_are_picked_in_proportion() {
+        let e4 = BookMove::new(E2, E4);
+        let d4 = BookMove::new(D2, D4);
+        assert_eq!(pick_popular(&[(e4, 5), (d4, 0)]), Some(e4));
+        assert_eq!(pick_popular(&[]), None);
+
+        fastrand::seed(7);
+        let picks = (0..1000)
+            .filter(|_| pick_popular(&[(e4, 3), (d4, 1)]) == Some(e4))
+            .count();
+        assert!((650..850).contains(&picks), "e4 picked {} times", picks);
+    }
+
+    #[test]
+    fn test_policies_parse_by_name() {
+        for policy in ALL_BOOK_POLICIES {
+            assert_eq!(policy.name().parse::<BookPolicy>(), Ok(policy));
+        }
+        assert_eq!("Variety".parse::<BookPolicy>(), Ok(BookPolicy::Variety));
+        assert!("random".parse::<BookPolicy>().is_err());
+    }
+}
@@ -240,6 +240,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_pawn_captures() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             k.......
             ........
             ........
@@ -249,8 +250,6 @@ mod tests {
             ........
             K.......
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -263,6 +262,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_castle_moves() {
         let mut board = chess_position! {
+            turn = Color::White;
             r...k..r
             ........
             ........
@@ -272,7 +272,6 @@ mod tests {
             ........
             R...K..R
         };
-        board.set_turn(Color::White);
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -307,6 +306,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_castle_moves_with_check() {
         let mut board = chess_position! {
+            turn = Color::White;
             ...k....
             ........
             ........
@@ -316,7 +316,6 @@ mod tests {
             ........
             R...K...
         };
-        board.set_turn(Color::White);
         board.lose_castle_rights(
             CastleRights::white_kingside()
                 | CastleRights::black_kingside()
@@ -333,6 +332,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_castle_moves_with_checkmate() {
         let mut board = chess_position! {
+            turn = Color::White;
             ..rkr...
             ..p.p...
             ........
@@ -342,7 +342,6 @@ mod tests {
             ........
             R...K...
         };
-        board.set_turn(Color::White);
         board.lose_castle_rights(
             CastleRights::white_kingside()
                 | CastleRights::black_kingside()
@@ -360,6 +359,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_captures() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             .......k
             ........
             ...p....
@@ -369,8 +369,6 @@ mod tests {
             .b......
             .......K
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -389,6 +387,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_ambiguous_moves() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             .....n.k
             ...P....
             .....n..
@@ -398,8 +397,6 @@ mod tests {
             R....R..
             K....N..
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(&mut board, Color::White, std_move!(F1, D2), "N1d2");
         assert_move_has_algebraic_notation!(&mut board, Color::White, std_move!(F3, D2), "N3d2");
@@ -425,6 +422,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_rare_ambiguous_moves() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             .......k
             ........
             ........
@@ -434,8 +432,6 @@ mod tests {
             ........
             KN...N..
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(&mut board, Color::White, std_move!(F1, D2), "Nf1d2");
         assert_move_has_algebraic_notation!(&mut board, Color::White, std_move!(F3, D2), "Nf3d2");
@@ -444,6 +440,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_en_passant() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             k.......
             ........
             ........
@@ -453,8 +450,6 @@ mod tests {
             ...P....
             K.......
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
         let exposes_en_passant = std_move!(D2, D4);
         exposes_en_passant.apply(&mut board).unwrap();
         board.toggle_turn();
@@ -477,6 +472,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_promotion() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             ........
             ..P.....
             ........
@@ -486,8 +482,6 @@ mod tests {
             ........
             K.......
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -500,6 +494,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_promotion_with_check() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             .......k
             ..P.....
             ........
@@ -509,8 +504,6 @@ mod tests {
             ........
             K.......
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -523,6 +516,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_promotion_with_check_and_capture() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             ...r...k
             ..P.....
             ........
@@ -532,8 +526,6 @@ mod tests {
             ........
             K.......
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -546,6 +538,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_discovered_check() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             ........
             ........
             ........
@@ -555,8 +548,6 @@ mod tests {
             ........
             ........
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -569,6 +560,7 @@ mod tests {
     #[test]
     fn test_algebraic_notation_for_discovered_check_with_capture() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             ........
             ........
             ........
@@ -578,8 +570,6 @@ mod tests {
             ........
             ........
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         assert_move_has_algebraic_notation!(
             &mut board,
@@ -164,7 +164,9 @@ impl CastleChessMove {
         };
 
         board.increment_halfmove_clock();
-        board.increment_fullmove_clock();
+        if color == Color::Black {
+            board.increment_fullmove_clock();
+        }
         board.push_en_passant_target(None);
         board.lose_castle_rights(lost_castle_rights);
 
@@ -216,7 +218,9 @@ impl CastleChessMove {
             .expect("rook_from should be empty when undoing");
 
         // Revert the board state.
-        board.decrement_fullmove_clock();
+        if color == Color::Black {
+            board.decrement_fullmove_clock();
+        }
         board.pop_halfmove_clock();
         board.pop_en_passant_target();
         board.pop_castle_rights();
@@ -2,7 +2,7 @@ use core::fmt;
 
 use common::bitboard::Square;
 
-use crate::board::{error::BoardError, piece::Piece, Board};
+use crate::board::{error::BoardError, piece::Piece, Board, Color, Variant};
 
 use super::capture::Capture;
 use super::castle::CastleChessMove;
@@ -88,11 +88,17 @@ impl ChessMove {
             EnPassant,
             Castle
         );
+        if result.is_ok() && board.variant() == Variant::ThreeCheck {
+            self.count_check(board);
+        }
         map_ok(result)
     }
 
     #[must_use = "move undo may fail"]
     pub fn undo(&self, board: &mut Board) -> Result<(), BoardError> {
+        if board.variant() == Variant::ThreeCheck {
+            board.pop_checks_given();
+        }
         let result = delegate_to_variants_mut!(
             self,
             undo,
@@ -105,6 +111,18 @@ impl ChessMove {
         map_ok(result)
     }
 
+    /// Records the checks given after this move was applied in three-check,
+    /// one more for the mover if it gives check.
+    fn count_check(&self, board: &mut Board) {
+        let mut checks = [Color::Black, Color::White].map(|color| board.checks_given(color));
+        if let Some((_, mover)) = board.get(self.to_square()) {
+            if board.is_in_check(mover.opposite()) {
+                checks[mover as usize] += 1;
+            }
+        }
+        board.push_checks_given(checks);
+    }
+
     pub fn to_uci(&self) -> String {
         let from = self.from_square().to_algebraic();
         let to = self.to_square().to_algebraic();
@@ -109,7 +109,9 @@ impl EnPassantChessMove {
         }
 
         board.reset_halfmove_clock();
-        board.increment_fullmove_clock();
+        if color == Color::Black {
+            board.increment_fullmove_clock();
+        }
         board.push_en_passant_target(None);
         board.preserve_castle_rights();
         board.put(*to_square, piece_to_move, color)?;
@@ -147,7 +149,9 @@ impl EnPassantChessMove {
 
         // Revert the board state.
         board.pop_halfmove_clock();
-        board.decrement_fullmove_clock();
+        if piece_color == Color::Black {
+            board.decrement_fullmove_clock();
+        }
         board.pop_en_passant_target();
         board.pop_castle_rights();
         board.put(captures_square, Piece::Pawn, piece_color.opposite())?;
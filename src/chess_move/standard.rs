@@ -131,7 +131,9 @@ impl StandardChessMove {
             board.increment_halfmove_clock();
         }
 
-        board.increment_fullmove_clock();
+        if color_of_piece_to_move == Color::Black {
+            board.increment_fullmove_clock();
+        }
         board.push_en_passant_target(en_passant_target);
         board.lose_castle_rights(lost_castle_rights);
         board
@@ -167,7 +169,9 @@ impl StandardChessMove {
 
         // Revert the board state.
         board.pop_halfmove_clock();
-        board.decrement_fullmove_clock();
+        if color_of_piece_to_move_back == Color::Black {
+            board.decrement_fullmove_clock();
+        }
         board.pop_en_passant_target();
         board.pop_castle_rights();
         board
@@ -688,4 +692,46 @@ mod tests {
         chess_move.undo(&mut board).unwrap();
         assert_eq!(initial_hash, board.current_position_hash());
     }
+
+    #[test]
+    fn test_zobrist_hash_drops_stale_en_passant_target() {
+        let mut board = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ....P...
+            ....K...
+        };
+        let double_push = std_move!(E2, E4);
+        let king_move = std_move!(E8, D8);
+        double_push.apply(&mut board).unwrap();
+        let with_target = board.current_position_hash();
+        king_move.apply(&mut board).unwrap();
+
+        let mut single_pushes = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            ....P...
+            ....K...
+        };
+        std_move!(E2, E3).apply(&mut single_pushes).unwrap();
+        king_move.apply(&mut single_pushes).unwrap();
+        std_move!(E3, E4).apply(&mut single_pushes).unwrap();
+        std_move!(D8, E8).apply(&mut single_pushes).unwrap();
+        king_move.apply(&mut single_pushes).unwrap();
+        assert_eq!(
+            board.current_position_hash(),
+            single_pushes.current_position_hash()
+        );
+
+        king_move.undo(&mut board).unwrap();
+        assert_eq!(board.current_position_hash(), with_target);
+    }
 }
@@ -1,11 +1,20 @@
 //! Chess-specific trait implementations for the alpha-beta search.
 
+use std::convert::TryFrom;
+
+use common::bitboard::Square;
+
 use crate::alpha_beta_searcher::{
     alpha_beta_search, Evaluator, GameMove, GameState, MoveCollection, MoveGenerator,
-    SearchContext, SearchError,
+    SearchContext, SearchError, TableMove,
 };
-use crate::board::{error::BoardError, Board};
+use crate::board::{error::BoardError, Board, Color, Piece};
+use crate::chess_move::capture::Capture;
 use crate::chess_move::chess_move::ChessMove;
+use crate::chess_move::chess_move_effect::ChessMoveEffect;
+use crate::chess_move::{
+    CastleChessMove, EnPassantChessMove, PawnPromotionChessMove, StandardChessMove,
+};
 use crate::move_generator::{ChessMoveList, MoveGenerator as ChessMoveGen};
 use crate::{evaluate, move_generator};
 #[cfg(feature = "instrumentation")]
@@ -40,6 +49,11 @@ impl GameState for Board {
         Board::toggle_turn(self);
         self.pop_en_passant_target();
     }
+
+    #[inline]
+    fn is_in_check(&self) -> bool {
+        Board::is_in_check(self, self.turn())
+    }
 }
 
 impl GameMove for ChessMove {
@@ -62,6 +76,72 @@ impl GameMove for ChessMove {
     }
 }
 
+/// From the low bits: the from and to squares (6 bits each), the kind of move
+/// (2), the captured piece plus one (3), the promotion piece (3) and the effect
+/// plus one (2), 0 standing for none.
+impl TableMove for ChessMove {
+    fn pack(&self) -> u32 {
+        let (kind, promotion) = match self {
+            ChessMove::Standard(_) => (0, 0),
+            ChessMove::PawnPromotion(m) => (1, m.promote_to_piece() as u32),
+            ChessMove::EnPassant(_) => (2, 0),
+            ChessMove::Castle(_) => (3, 0),
+        };
+        let captured = match (self, self.captures()) {
+            (ChessMove::Standard(_) | ChessMove::PawnPromotion(_), Some(Capture(piece))) => {
+                piece as u32 + 1
+            }
+            _ => 0,
+        };
+        let effect = self.effect().map_or(0, |effect| effect as u32 + 1);
+        u32::from(self.from_square().index())
+            | u32::from(self.to_square().index()) << 6
+            | kind << 12
+            | captured << 14
+            | promotion << 17
+            | effect << 20
+    }
+
+    fn unpack(packed: u32) -> Option<Self> {
+        let from = Square::new((packed & 0x3f) as u8);
+        let to = Square::new((packed >> 6 & 0x3f) as u8);
+        let captures = match packed >> 14 & 0b111 {
+            0 => None,
+            piece => Some(Capture(Piece::try_from(piece as usize - 1).ok()?)),
+        };
+        let mut chess_move = match packed >> 12 & 0b11 {
+            0 => ChessMove::Standard(StandardChessMove::new(from, to, captures)),
+            1 => {
+                let promotion = Piece::try_from((packed >> 17 & 0b111) as usize).ok()?;
+                ChessMove::PawnPromotion(PawnPromotionChessMove::new(from, to, captures, promotion))
+            }
+            2 => ChessMove::EnPassant(EnPassantChessMove::new(from, to)),
+            _ => {
+                let color = if from.rank() == 0 {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                ChessMove::Castle(if to.file() == 6 {
+                    CastleChessMove::castle_kingside(color)
+                } else {
+                    CastleChessMove::castle_queenside(color)
+                })
+            }
+        };
+        let effect = match packed >> 20 & 0b11 {
+            0 => None,
+            1 => Some(ChessMoveEffect::None),
+            2 => Some(ChessMoveEffect::Check),
+            _ => Some(ChessMoveEffect::Checkmate),
+        };
+        if let Some(effect) = effect {
+            chess_move.set_effect(effect);
+        }
+        Some(chess_move)
+    }
+}
+
 impl MoveCollection<ChessMove> for ChessMoveList {
     fn is_empty(&self) -> bool {
         ChessMoveList::is_empty(self)
@@ -70,6 +150,10 @@ impl MoveCollection<ChessMove> for ChessMoveList {
     fn len(&self) -> usize {
         ChessMoveList::len(self)
     }
+
+    fn retain<F: FnMut(&ChessMove) -> bool>(&mut self, mut keep: F) {
+        ChessMoveList::retain(self, |chess_move| keep(chess_move));
+    }
 }
 
 #[derive(Clone, Default)]
@@ -93,13 +177,37 @@ impl MoveGenerator<Board> for ChessMoveGenerator {
 
     #[inline]
     fn generate_moves(&self, state: &mut Board) -> ChessMoveList {
+        // No moves once the game is won by the variant's rule, so the search
+        // scores it as a finished game
+        if state.variant().winner(state).is_some() {
+            return ChessMoveList::new();
+        }
         self.inner.generate_moves(state, state.turn())
     }
 
     #[inline]
     fn generate_tactical_moves(&self, state: &mut Board) -> ChessMoveList {
+        if state.variant().winner(state).is_some() {
+            return ChessMoveList::new();
+        }
         self.inner.generate_tactical_moves(state, state.turn())
     }
+
+    #[inline]
+    fn generate_quiet_moves(&self, state: &mut Board) -> ChessMoveList {
+        if state.variant().winner(state).is_some() {
+            return ChessMoveList::new();
+        }
+        self.inner.generate_quiet_moves(state, state.turn())
+    }
+
+    #[inline]
+    fn generate_quiet_checks(&self, state: &mut Board) -> Option<ChessMoveList> {
+        if state.variant().winner(state).is_some() {
+            return Some(ChessMoveList::new());
+        }
+        Some(self.inner.generate_quiet_checks(state, state.turn()))
+    }
 }
 
 #[derive(Clone, Default)]
@@ -137,7 +245,7 @@ impl Evaluator<Board> for ChessEvaluator {
 
     #[inline]
     fn should_skip_null_move(&self, state: &mut Board) -> bool {
-        // Check detection is handled by the search framework via is_in_check(),
+        // Check detection is handled by the search framework via GameState::is_in_check(),
         // so this only needs to check for endgame/zugzwang conditions.
         evaluate::is_endgame(state)
     }
@@ -152,11 +260,6 @@ impl Evaluator<Board> for ChessEvaluator {
         }
     }
 
-    #[inline]
-    fn is_in_check(&self, state: &mut Board) -> bool {
-        evaluate::current_player_is_in_check(state, &self.move_generator)
-    }
-
     #[inline]
     fn lmp_threshold(&self, depth: u8) -> Option<usize> {
         match depth {
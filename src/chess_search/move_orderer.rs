@@ -67,6 +67,24 @@ impl MoveOrderer<Board, ChessMove> for ChessMoveOrderer {
             record_history_cutoff(mv.from_square(), mv.to_square(), depth);
         }
     }
+
+    /// A capture by a piece worth more than its victim, onto a square the
+    /// opponent defends. Promotions are never counted as losing.
+    fn is_losing_tactical(&self, mv: &ChessMove, state: &Board) -> bool {
+        let Some(capture) = mv.captures() else {
+            return false;
+        };
+        if matches!(mv, ChessMove::PawnPromotion(_)) {
+            return false;
+        }
+        let victim_value = MATERIAL_VALUES[capture.0 as usize];
+        let attacker_value =
+            get_piece_type(mv, state).map_or(0, |piece| MATERIAL_VALUES[piece as usize]);
+        attacker_value > victim_value
+            && mv
+                .to_square()
+                .overlaps(state.attacks(state.turn().opposite()))
+    }
 }
 
 fn compare_moves(a: &ChessMove, b: &ChessMove, board: &Board) -> std::cmp::Ordering {
@@ -294,6 +312,33 @@ mod tests {
         assert!(!castle.is_tactical(&board));
     }
 
+    #[test]
+    fn test_losing_captures_are_told_apart() {
+        let board = chess_position! {
+            ....k...
+            ........
+            ..p.....
+            ...p...r
+            ....P...
+            ........
+            ........
+            ...QK...
+        };
+
+        // Queen takes a pawn the c6 pawn defends
+        assert!(
+            ChessMoveOrderer.is_losing_tactical(&std_move!(D1, D5, Capture(Piece::Pawn)), &board)
+        );
+        // Pawn takes pawn, and queen takes an undefended rook
+        assert!(
+            !ChessMoveOrderer.is_losing_tactical(&std_move!(E4, D5, Capture(Piece::Pawn)), &board)
+        );
+        assert!(
+            !ChessMoveOrderer.is_losing_tactical(&std_move!(D1, H5, Capture(Piece::Rook)), &board)
+        );
+        assert!(!ChessMoveOrderer.is_losing_tactical(&std_move!(E4, E5), &board));
+    }
+
     #[test]
     fn test_pick_next_selects_best_first() {
         let board = create_test_board();
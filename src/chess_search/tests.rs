@@ -3,6 +3,7 @@
 //! Test coverage:
 //! - Mate finding (mate in 1, mate in 2)
 //! - Back rank mate patterns
+//! - Mate scores (distance from the root, through the transposition table)
 //! - Chess-specific quiescence (captures, checks)
 //! - Killer moves in chess positions
 //! - Transposition tables with chess positions
@@ -15,8 +16,8 @@ use std::str::FromStr;
 
 use common::bitboard::*;
 
-use crate::alpha_beta_searcher::{Evaluator, SearchContext};
-use crate::board::{castle_rights::CastleRights, color::Color, piece::Piece, Board};
+use crate::alpha_beta_searcher::{mate_in_moves, Evaluator, GameState, SearchContext, MATE_SCORE};
+use crate::board::{color::Color, piece::Piece, Board};
 use crate::chess_move::{capture::Capture, standard::StandardChessMove, ChessMove};
 use crate::{check_move, checkmate_move, chess_position, std_move};
 
@@ -28,6 +29,7 @@ fn test_find_mate_in_1_white() {
     let mut context = SearchContext::new(4);
 
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         .Q......
         ........
         ........
@@ -37,8 +39,6 @@ fn test_find_mate_in_1_white() {
         k.K.....
         ........
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
     let valid_checkmates = [
@@ -58,6 +58,7 @@ fn test_find_mate_in_1_black() {
     let mut context = SearchContext::new(4);
 
     let mut board = chess_position! {
+        turn = Color::Black, castling = "-";
         .q......
         ........
         ........
@@ -67,8 +68,6 @@ fn test_find_mate_in_1_black() {
         K.k.....
         ........
     };
-    board.set_turn(Color::Black);
-    board.lose_castle_rights(CastleRights::all());
 
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
 
@@ -89,6 +88,7 @@ fn test_find_back_rank_mate_in_2_white() {
     let mut context = SearchContext::new(4);
 
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         .k.....r
         ppp.....
         ........
@@ -98,8 +98,6 @@ fn test_find_back_rank_mate_in_2_white() {
         ...Q....
         K..R....
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let expected_moves = [
         check_move!(std_move!(D2, D8)),
@@ -124,11 +122,44 @@ fn test_find_back_rank_mate_in_2_white() {
     assert_eq!(expected_move_iter.next().unwrap(), &move3);
 }
 
+#[test]
+fn test_mate_scores_count_the_plies_from_the_root() {
+    let mut board = chess_position! {
+        turn = Color::White, castling = "-";
+        .k.....r
+        ppp.....
+        ........
+        ........
+        ........
+        ........
+        ...Q....
+        K..R....
+    };
+
+    // Qd8+ Rxd8 Rxd8#: White mates on the third ply
+    let mut context = SearchContext::new(4);
+    search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!(context.last_score(), Some(MATE_SCORE - 3));
+    assert_eq!(mate_in_moves(MATE_SCORE - 3), Some(2));
+
+    // A deeper search through the same table finds the same mate
+    context.set_depth(6);
+    search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!(context.last_score(), Some(MATE_SCORE - 3));
+
+    let move1 = search_best_move(&mut context, &mut board).unwrap();
+    move1.apply(&mut board).unwrap();
+    board.toggle_turn();
+    search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!(context.last_score(), Some(MATE_SCORE - 2));
+}
+
 #[test]
 fn test_find_back_rank_mate_in_2_black() {
     let mut context = SearchContext::new(4);
 
     let mut board = chess_position! {
+        turn = Color::Black, castling = "-";
         ....r..k
         ....q...
         ........
@@ -138,8 +169,6 @@ fn test_find_back_rank_mate_in_2_black() {
         .....PPP
         R.....K.
     };
-    board.set_turn(Color::Black);
-    board.lose_castle_rights(CastleRights::all());
 
     let expected_moves = [
         check_move!(std_move!(E7, E1)),
@@ -184,6 +213,7 @@ fn test_quiescence_with_captures() {
     // White can capture black's knight, then black can recapture, etc.
     // This tests quiescence searching through capture sequences
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         rnbqkb.r
         pppppppp
         ........
@@ -193,8 +223,6 @@ fn test_quiescence_with_captures() {
         PPPPPPPP
         RNBQKB.R
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let result = search_best_move(&mut context, &mut board);
     assert!(result.is_ok(), "Quiescence with captures should succeed");
@@ -224,6 +252,7 @@ fn test_search_with_check_opportunities() {
     // extend them. This test verifies the search completes correctly in positions
     // with abundant checking opportunities.
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         .k......
         ........
         ........
@@ -233,8 +262,6 @@ fn test_search_with_check_opportunities() {
         K.Q.R...
         ........
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let result = search_best_move(&mut context, &mut board);
     assert!(
@@ -256,6 +283,7 @@ fn test_transposition_table_chess_positions() {
     // Complex middlegame position with many transpositions possible
     // Different move orders can reach the same position
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         rnbqkb.r
         pppppppp
         ........
@@ -265,8 +293,6 @@ fn test_transposition_table_chess_positions() {
         PPPPPPPP
         RNBQKB.R
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let first_result = search_best_move(&mut context, &mut board).unwrap();
     let first_count = context.searched_position_count();
@@ -310,6 +336,7 @@ fn test_killer_moves_chess_positions() {
     // Position where many moves cause beta cutoffs, testing killer move storage
     // Black is in a bad position, most moves won't help - causing beta cutoffs
     let mut board = chess_position! {
+        turn = Color::Black, castling = "-";
         rnbqkb.r
         pppppppp
         ........
@@ -319,8 +346,6 @@ fn test_killer_moves_chess_positions() {
         PPPPPPPP
         RNBQKB.R
     };
-    board.set_turn(Color::Black);
-    board.lose_castle_rights(CastleRights::all());
 
     let result = search_best_move(&mut context, &mut board).unwrap();
 
@@ -361,6 +386,7 @@ fn test_null_move_pruning_disabled_when_in_check() {
     // which gates NMP via skip_speculative_pruning. Verify is_in_check works
     // and the search handles the in-check position correctly.
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         r......k
         ........
         ........
@@ -370,12 +396,9 @@ fn test_null_move_pruning_disabled_when_in_check() {
         .PPP....
         K...Q...
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
-    let evaluator = ChessEvaluator::new();
     assert!(
-        evaluator.is_in_check(&mut board),
+        GameState::is_in_check(&board),
         "is_in_check must return true when king is attacked"
     );
 
@@ -389,6 +412,7 @@ fn test_null_move_pruning_disabled_when_in_check() {
 fn test_null_move_pruning_disabled_in_endgame() {
     // Endgame position (king and pawn vs king)
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         .......k
         ........
         ........
@@ -398,8 +422,6 @@ fn test_null_move_pruning_disabled_in_endgame() {
         P.......
         K.......
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let evaluator = ChessEvaluator::new();
     assert!(
@@ -493,6 +515,7 @@ fn test_null_move_preserves_mate_finding() {
     // Mate in 1 — White queen can deliver checkmate
     let mut context = SearchContext::new(4);
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         .Q......
         ........
         ........
@@ -502,8 +525,6 @@ fn test_null_move_preserves_mate_finding() {
         k.K.....
         ........
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
     let valid_checkmates = [
@@ -522,6 +543,7 @@ fn test_null_move_preserves_mate_finding() {
 fn test_null_move_pruning_finds_correct_best_move() {
     // Position where black has a hanging queen
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         rnb.kb.r
         pppppppp
         ........
@@ -531,8 +553,6 @@ fn test_null_move_pruning_finds_correct_best_move() {
         PPPPPPPP
         RNBQKB.R
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(4, false);
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
@@ -552,6 +572,7 @@ fn test_rfp_fires_in_lopsided_position() {
     // At sufficient depth, RFP should prune many nodes because the static eval
     // is far above beta. More pieces produce enough interior nodes for RFP to fire.
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         ....k...
         .p...p..
         ........
@@ -561,8 +582,6 @@ fn test_rfp_fires_in_lopsided_position() {
         ........
         R...K..Q
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(6, false);
     search_best_move(&mut context, &mut board).unwrap();
@@ -578,6 +597,7 @@ fn test_rfp_fires_for_minimizing_player() {
     // Black has massive material advantage (queen + rook + bishop vs king + pawns).
     // Exercises the minimizing-player RFP path (static_eval + margin <= alpha).
     let mut board = chess_position! {
+        turn = Color::Black, castling = "-";
         r...k..q
         ........
         ..b.....
@@ -587,8 +607,6 @@ fn test_rfp_fires_for_minimizing_player() {
         .P...P..
         ....K...
     };
-    board.set_turn(Color::Black);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(6, false);
     search_best_move(&mut context, &mut board).unwrap();
@@ -604,6 +622,7 @@ fn test_rfp_skipped_when_in_check() {
     // Black king is in check — RFP should NOT fire because should_skip_null_move
     // returns true when in check.
     let mut board = chess_position! {
+        turn = Color::Black, castling = "-";
         ....k...
         ........
         ........
@@ -613,8 +632,6 @@ fn test_rfp_skipped_when_in_check() {
         ........
         R...K...
     };
-    board.set_turn(Color::Black);
-    board.lose_castle_rights(CastleRights::all());
 
     // Verify that the evaluator considers this a "skip NMP" position (in check)
     let evaluator = ChessEvaluator::new();
@@ -658,6 +675,7 @@ fn test_rfp_does_not_prevent_finding_queen_capture() {
     // Same position as NMP correctness test: white should capture hanging queen.
     // RFP should not interfere with finding the best tactical move.
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         rnb.kb.r
         pppppppp
         ........
@@ -667,8 +685,6 @@ fn test_rfp_does_not_prevent_finding_queen_capture() {
         PPPPPPPP
         RNBQKB.R
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(4, false);
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
@@ -686,6 +702,7 @@ fn test_futility_pruning_fires_in_lopsided_position() {
     // White has massive material advantage. Futility pruning should skip quiet
     // moves at shallow depths because even with a margin the eval can't reach alpha.
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         ....k...
         .p...pp.
         ........
@@ -695,8 +712,6 @@ fn test_futility_pruning_fires_in_lopsided_position() {
         .PP..PP.
         R...K..Q
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(7, false);
     search_best_move(&mut context, &mut board).unwrap();
@@ -712,6 +727,7 @@ fn test_futility_pruning_fires_for_minimizing_player() {
     // Black has massive material advantage. Futility pruning should skip quiet
     // moves for the minimizing player at shallow depths.
     let mut board = chess_position! {
+        turn = Color::Black, castling = "-";
         r...k..q
         ........
         ..b.....
@@ -721,8 +737,6 @@ fn test_futility_pruning_fires_for_minimizing_player() {
         .P...P..
         ....K...
     };
-    board.set_turn(Color::Black);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(7, false);
     search_best_move(&mut context, &mut board).unwrap();
@@ -737,6 +751,7 @@ fn test_futility_pruning_fires_for_minimizing_player() {
 fn test_futility_pruning_does_not_skip_captures() {
     // White should still find the queen capture even with futility pruning active.
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         rnb.kb.r
         pppppppp
         ........
@@ -746,8 +761,6 @@ fn test_futility_pruning_does_not_skip_captures() {
         PPPPPPPP
         RNBQKB.R
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let mut context = SearchContext::with_parallel(4, false);
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
@@ -776,6 +789,7 @@ fn test_check_extension_fires_when_in_check() {
     let mut context = SearchContext::new(4);
 
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         r......k
         ........
         ........
@@ -785,8 +799,6 @@ fn test_check_extension_fires_when_in_check() {
         ........
         K.......
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let _chess_move = search_best_move(&mut context, &mut board).unwrap();
     assert!(
@@ -804,6 +816,7 @@ fn test_check_extension_finds_deeper_mate() {
     let mut context = SearchContext::new(4);
 
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         ........
         ........
         ........
@@ -813,8 +826,6 @@ fn test_check_extension_finds_deeper_mate() {
         k.......
         .RKQ....
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
     let score = context.last_score().unwrap();
@@ -832,7 +843,10 @@ fn test_check_extension_finds_deeper_mate() {
     );
 
     // Verify the move is sensible (should be a checking move or lead to mate)
-    assert!(chess_move.to_string().len() > 0, "Should find a valid move");
+    assert!(
+        !chess_move.to_string().is_empty(),
+        "Should find a valid move"
+    );
 }
 
 #[test]
@@ -858,6 +872,7 @@ fn test_check_extension_skips_speculative_pruning_when_in_check() {
     let mut context = SearchContext::new(5);
 
     let mut board = chess_position! {
+        turn = Color::White, castling = "-";
         r......k
         ........
         ........
@@ -867,8 +882,6 @@ fn test_check_extension_skips_speculative_pruning_when_in_check() {
         .Q......
         K.......
     };
-    board.set_turn(Color::White);
-    board.lose_castle_rights(CastleRights::all());
 
     let chess_move = search_best_move(&mut context, &mut board).unwrap();
 
@@ -888,3 +901,117 @@ fn test_check_extension_skips_speculative_pruning_when_in_check() {
     // Verify the move is legal (king must escape check)
     let _move_str = chess_move.to_string();
 }
+
+#[test]
+fn test_rook_is_brought_to_the_open_file() {
+    // The d- and e-files are open; every other file is closed
+    let mut board = Board::from_str("6k1/ppp2ppp/8/8/8/8/PPP2PPP/R5K1 w - - 0 1").unwrap();
+    let mut context = SearchContext::new(2);
+    let chess_move = search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!(chess_move.from_square(), A1);
+    assert!(
+        [D1, E1].contains(&chess_move.to_square()),
+        "expected the rook on an open file, got {}",
+        chess_move
+    );
+}
+
+#[test]
+fn test_unstoppable_pawn_runs() {
+    // The king on a6 is outside the square of the pawn once it is on g4
+    let mut board = Board::from_str("8/8/k7/8/8/8/6P1/6K1 w - - 0 1").unwrap();
+    let mut context = SearchContext::new(4);
+    let chess_move = search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!((chess_move.from_square(), chess_move.to_square()), (G2, G4));
+}
+
+#[test]
+fn test_king_steps_into_the_square_of_the_pawn() {
+    let mut board = Board::from_str("8/8/1k6/8/8/8/6P1/6K1 b - - 0 1").unwrap();
+    let mut context = SearchContext::new(4);
+    let chess_move = search_best_move(&mut context, &mut board).unwrap();
+    assert!(
+        [C5, C6, C7].contains(&chess_move.to_square()),
+        "expected the king to head for the pawn, got {}",
+        chess_move
+    );
+}
+
+#[test]
+fn test_king_heads_back_to_defend_its_pawn() {
+    let mut board = Board::from_str("8/8/8/8/4k3/8/1K3P2/8 w - - 0 1").unwrap();
+    let mut context = SearchContext::new(6);
+    let chess_move = search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!(chess_move.from_square(), B2);
+    assert!(
+        [C1, C2, C3].contains(&chess_move.to_square()),
+        "expected the king to head for the pawn, got {}",
+        chess_move
+    );
+}
+
+#[test]
+fn test_king_takes_the_opposition_instead_of_pushing_the_pawn() {
+    // e6 draws (Kd8, e7+ Ke8, Ke6 is stalemate); Ke6 keeps the king on a key
+    // square of the pawn and wins
+    let mut board = Board::from_str("4k3/8/3K4/4P3/8/8/8/8 w - - 0 1").unwrap();
+    let mut context = SearchContext::new(6);
+    let chess_move = search_best_move(&mut context, &mut board).unwrap();
+    assert_eq!((chess_move.from_square(), chess_move.to_square()), (D6, E6));
+}
+
+/// Plays the position out with the engine on both sides, keeping the game's
+/// position history like the game loop does, and returns the number of plies
+/// to checkmate if it comes within the fifty-move rule.
+fn plies_to_mate(fen: &str, depth: u8) -> Option<usize> {
+    let mut board = Board::from_str(fen).unwrap();
+    let mut context = SearchContext::with_parallel(depth, false);
+    let mut history = vec![board.current_position_hash()];
+    for plies in 0..100 {
+        let Ok(chess_move) =
+            search_best_move_with_history(&mut context, &mut board, history.clone(), 0)
+        else {
+            return board.is_in_check(board.turn()).then_some(plies);
+        };
+        chess_move.apply(&mut board).unwrap();
+        board.toggle_turn();
+        history.push(board.current_position_hash());
+    }
+    None
+}
+
+#[test]
+fn test_king_and_queen_mate_the_lone_king() {
+    let plies = plies_to_mate("8/8/8/4k3/8/8/8/3QK3 w - - 0 1", 4);
+    assert!(plies.is_some(), "no mate within the fifty-move rule");
+}
+
+#[test]
+fn test_king_and_rook_mate_the_lone_king() {
+    let plies = plies_to_mate("8/8/8/3k4/8/8/8/R3K3 b - - 0 1", 4);
+    assert!(plies.is_some(), "no mate within the fifty-move rule");
+}
+
+#[test]
+fn test_king_and_bishop_pair_mate_the_lone_king() {
+    let plies = plies_to_mate("8/8/8/4k3/8/8/8/2B1KB2 w - - 0 1", 4);
+    assert!(plies.is_some(), "no mate within the fifty-move rule");
+}
+
+#[test]
+fn test_moves_round_trip_through_the_transposition_table_packing() {
+    use crate::alpha_beta_searcher::TableMove;
+    use crate::input_handler::fen::parse_fen;
+    use crate::move_generator::MoveGenerator;
+
+    // Promotions with and without captures, en passant, both castles, checks
+    let mut board = parse_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+    let moves = MoveGenerator::default().generate_moves(&mut board, Color::White);
+    assert!(moves.iter().any(|m| matches!(m, ChessMove::EnPassant(_))));
+    for chess_move in moves.iter() {
+        let unpacked = ChessMove::unpack(chess_move.pack()).unwrap();
+        assert_eq!(&unpacked, chess_move);
+        assert_eq!(unpacked.effect(), chess_move.effect());
+        assert_eq!(unpacked.captures(), chess_move.captures());
+    }
+}
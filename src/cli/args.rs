@@ -3,9 +3,13 @@
 use structopt::StructOpt;
 
 use crate::cli::commands::{
+    analyze::AnalyzeArgs, annotate::AnnotateArgs, bench::BenchArgs,
     benchmark_alpha_beta::BenchmarkAlphaBetaArgs, calculate_best_move::CalculateBestMoveArgs,
-    count_positions::CountPositionsArgs, determine_stockfish_elo::DetermineStockfishEloArgs,
-    play::PlayArgs, pvp::PvpArgs, solve_puzzles::SolvePuzzlesArgs, uci::UciArgs, watch::WatchArgs,
+    correspondence::CorrespondenceArgs, count_positions::CountPositionsArgs,
+    determine_stockfish_elo::DetermineStockfishEloArgs, drill::DrillArgs, epd::EpdArgs,
+    lichess_bot::LichessBotArgs, play::PlayArgs, pvp::PvpArgs, replay::ReplayArgs,
+    serve::ServeArgs, solve::SolveArgs, solve_puzzles::SolvePuzzlesArgs, spsa::SpsaArgs,
+    tournament::TournamentArgs, tune::TuneArgs, uci::UciArgs, watch::WatchArgs,
 };
 
 #[derive(StructOpt)]
@@ -16,24 +20,54 @@ use crate::cli::commands::{
 pub enum Chess {
     #[structopt(
         name = "play",
-        about = "Play a game against the computer, which will search for the best move using alpha-beta pruning at the given `--depth` (default: 4). Your starting color will be chosen at random unless you specify with `--color`. The initial position can be specified using FEN notation with `--fen` (default: starting position)."
+        about = "Play a game against the computer, which will search for the best move using alpha-beta pruning at the given `--depth` (default: 4). Your starting color will be chosen at random unless you specify with `--color`. The initial position can be specified using FEN notation with `--fen` (default: starting position), optionally followed by `--moves` in SAN or UCI notation. Use `--movetime <ms>` or `--nodes <N>` to bound each engine move by time or nodes instead of depth, or `--strength <elo>` for a weaker opponent, and `--learning-file <path>` to have the engine learn from its games. Use `--odds <piece>` for a handicap game where the engine starts without a pawn, knight, rook, or queen. Use `--opponent <command>` to play against an external UCI engine instead, limited with `--opponent-elo` and `--opponent-movetime`. Use `--tc <base>+<inc>` (seconds) to play on a chess clock, and `--armageddon`, `--black-tc`, or `--draw-odds` for unequal clocks and draw odds. Enter `undo` to take back the last move pair, up to `--max-undos` times."
     )]
     Play(PlayArgs),
     #[structopt(
         name = "pvp",
-        about = "Play a game against another human on this local machine. The initial position can be specified using FEN notation with `--fen` (default: starting position)."
+        about = "Play a game against another human on this local machine. The initial position can be specified using FEN notation with `--fen` (default: starting position), optionally followed by `--moves` in SAN or UCI notation. Use `--tc <base>+<inc>` (seconds) to play on a chess clock, and `--armageddon`, `--black-tc`, or `--draw-odds` for unequal clocks and draw odds. Enter `undo` to take back the last move pair, up to `--max-undos` times."
     )]
     Pvp(PvpArgs),
     #[structopt(
         name = "watch",
-        about = "Watch the computer play against itself at the given `--depth` (default: 4). The initial position can be specified using FEN notation with `--fen` (default: starting position)."
+        about = "Watch the computer play against itself at the given `--depth` (default: 4), or against an external UCI engine given with `--opponent`. The initial position can be specified using FEN notation with `--fen` (default: starting position), optionally followed by `--moves` in SAN or UCI notation. Use `--movetime <ms>` or `--nodes <N>` to bound each engine move by time or nodes instead of depth. Use `--delay <ms>` to slow the game down, space to pause, `n` to step one move, and `--pause-at-move`/`--pause-at-eval` to stop automatically. Use `--tc <base>+<inc>` (seconds) to play on chess clocks, `--armageddon`, `--black-tc`, or `--draw-odds` for unequal clocks and draw odds, and `--resign`/`--draw` to adjudicate decided games early."
     )]
     Watch(WatchArgs),
+    #[structopt(
+        name = "drill",
+        about = "Practise an opening from the book, given by name with `--opening` (e.g. \"Ruy Lopez\"; partial names drill every matching line). You play `--color` (default: white) and must follow the book moves; the computer replies with book moves from the same opening and every deviation is reported and counted. When the line ends, play continues against the engine at `--depth` (default: 6)."
+    )]
+    Drill(DrillArgs),
+    #[structopt(
+        name = "replay",
+        about = "Replay a game from a PGN file in the TUI, starting from its first position (use `--game N` to pick a later game in the file). The up and down arrows, PgUp/PgDn, Home and End step through the moves, and F2 (or `--analyze` from the start) shows a live engine evaluation of the position on the board. Entering a move while an earlier position is shown branches off the game there to explore what might have happened; enter `mainline` to return to the game."
+    )]
+    Replay(ReplayArgs),
     #[structopt(
         name = "calculate-best-move",
-        about = "Use the chess engine to determine the best move from a given position, provided in FEN notation with `--fen` (required). You can optionally specify the depth of the search with the `--depth` arg (default: 4)."
+        about = "Use the chess engine to determine the best move from a given position, provided in FEN notation with `--fen` (default: starting position) and optionally advanced with `--moves` in SAN or UCI notation. You can optionally specify the depth of the search with the `--depth` arg (default: 4), or bound it with `--movetime <ms>` or `--nodes <N>`. Use `--json` for structured output including the score, PV, and node counts."
     )]
     CalculateBestMove(CalculateBestMoveArgs),
+    #[structopt(
+        name = "analyze",
+        about = "Analyze a position until stopped, printing the evaluation, principal variation and node counts as each depth completes. The position is given in FEN notation with `--fen` (default: starting position), or as the last position of a game in a PGN file with `--pgn` (`--game N` picks a later game in the file), optionally followed by `--moves` in SAN or UCI notation. Use `--depth` to stop at a given depth. While it runs, enter `stop` to halt the search, `go` to resume it, a move in SAN or UCI notation to play it, `undo` to take back the last move, `setfen <fen>` to analyze another position, and `quit` to exit."
+    )]
+    Analyze(AnalyzeArgs),
+    #[structopt(
+        name = "annotate",
+        about = "Run the engine over every position of the games in a PGN file and write an annotated PGN with evaluations, the better move where one was missed, and `?!`/`?`/`??` markers for moves losing at least `--inaccuracy`/`--mistake`/`--blunder` centipawns (default: 50/100/300). Each position is searched to `--depth` (default: 6) or bounded with `--movetime <ms>` or `--nodes <N>`. Output goes to stdout unless `--output` is given."
+    )]
+    Annotate(AnnotateArgs),
+    #[structopt(
+        name = "correspondence",
+        about = "Analyze a correspondence game one session at a time. The game and the engine's analysis (its transposition table and candidate move evaluations) are kept in the given JSON file, which is created from `--fen` if missing. Each run records the `--moves` played since the last session, analyzes the position for `--think` seconds (default: 60) on top of the saved analysis, prints the top `--candidates` moves (default: 5) with the best line, and saves everything back to the file."
+    )]
+    Correspondence(CorrespondenceArgs),
+    #[structopt(
+        name = "bench",
+        about = "Search a fixed built-in set of positions to a fixed `--depth` (default: 5) on a single thread and print the total node count and nodes/sec. The node count is a deterministic bench signature: refactors that should not change search behavior must leave it unchanged. Use `--save <file>` to write the result as a baseline and `--compare <file>` to flag nodes/sec or per-position time regressions beyond `--threshold` percent (default: 10), exiting non-zero. Use `--json` for structured output."
+    )]
+    Bench(BenchArgs),
     #[structopt(
         name = "benchmark-alpha-beta",
         about = "Run a quick alpha-beta performance benchmark on a curated set of positions. Reports nodes/sec, transposition table hit rate, and other metrics for fast iteration. Use `--depth` (default: 4) and `--parallel` flag to test different configurations."
@@ -41,22 +75,57 @@ pub enum Chess {
     BenchmarkAlphaBeta(BenchmarkAlphaBetaArgs),
     #[structopt(
         name = "determine-stockfish-elo",
-        about = "Determine the ELO rating of the engine at a given `--depth` (default: 4) and `--starting-elo` (default: 1000). The engine will increment the Stockfish ELO until it plateaus at a 50% win rate, at which point the rating is reported."
+        about = "Estimate the engine's ELO rating by playing `--games` (default: 40) against Stockfish (or another UCI engine given with `--engine`) at `UCI_Elo` levels from `--min-elo` (default: 1320) to `--max-elo` (default: 2120) in steps of `--elo-step` (default: 200), alternating colors. The time control is set with `--tc` and games can run in parallel with `--concurrency`. The maximum-likelihood rating is reported with a 95% confidence interval, and every game can be saved with `--pgn-out`."
     )]
     DetermineStockfishElo(DetermineStockfishEloArgs),
     #[structopt(
         name = "count-positions",
-        about = "Count the number of possible positions for a given `--depth` (default: 4), and reports the time it took to do so. By default, this searches all possible positions. The routine can be run with alpha-beta pruning by selecting `--strategy alpha-beta`."
+        about = "Count the number of possible positions for a given `--depth` (default: 4), and reports the time it took to do so. By default, this searches all possible positions. The routine can be run with alpha-beta pruning by selecting `--strategy alpha-beta`. With `--verify`, perft results for standard test positions are checked against a reference table up to `--depth`, exiting non-zero on mismatch. With `--divide`, perft of `--fen` (default: starting position) at `--depth` is split by root move, and checked against `--expected` or the reference table when the position is in it. Perft subtree counts are cached in a `--hash` of 64 MB by default. Use `--json` for structured output."
     )]
     CountPositions(CountPositionsArgs),
+    #[structopt(
+        name = "serve",
+        about = "Serve a small HTTP API for analysis on `--host` and `--port` (default: 127.0.0.1:8080). POST a JSON body with a `fen` (and optional `moves`, `depth` and `movetime` in milliseconds) to `/analyze` to get the best move, score and principal variation as JSON. Searches run on `--workers` threads (default: 2); up to `--queue` requests (default: 16) wait for one before more are turned away. Requests search to `--depth` (default: 6) unless they ask otherwise, up to `--max-depth` and `--max-movetime`. With `--ws-port`, live analysis is also streamed over WebSocket: send `{\"type\": \"position\", \"fen\": ...}` to get `info` updates for each completed depth, and `{\"type\": \"stop\"}` to stop."
+    )]
+    Serve(ServeArgs),
+    #[structopt(
+        name = "lichess-bot",
+        about = "Play on lichess.org as a bot account, using the API token given with `--token` or in `LICHESS_BOT_TOKEN` (it needs the `bot:play` scope). Challenges are accepted for the `--variant`s given (default: chess) and filtered by clock with `--min-initial`/`--max-initial` and `--min-increment`/`--max-increment` (seconds), by `--no-rated`/`--no-casual` and `--no-bots`; correspondence and unlimited games need `--untimed`. Up to `--max-games` games (default: 1) are played at once. Each move's thinking time follows the game clock, less `--move-overhead` (default: 100ms) and capped by `--max-movetime`; games without a clock get `--untimed-movetime` (default: 5000ms)."
+    )]
+    LichessBot(LichessBotArgs),
+    #[structopt(
+        name = "solve",
+        about = "Search the position given with `--fen` for a forced mate and print the solution line in algebraic notation. Use `--mate-in N` to prove a mate of a specific length (otherwise the shortest mate up to `--max-mate` is found) and `--unique` to check that the key move is unique."
+    )]
+    Solve(SolveArgs),
     #[structopt(
         name = "solve-puzzles",
         about = "Run the puzzle suite (tactical, strategic, deep positional) and report solve rates. Use `--tier 1|2|3` to run a single tier."
     )]
     SolvePuzzles(SolvePuzzlesArgs),
+    #[structopt(
+        name = "epd",
+        about = "Run an EPD test suite such as WAC, STS or Bratko-Kopec. Each position is searched for `--movetime` milliseconds (default: 1000), up to `--depth`, and solved when the move found is one of its `bm` moves and none of its `am` moves. Reports each position's move, time to solution and node count, and the number solved."
+    )]
+    Epd(EpdArgs),
+    #[structopt(
+        name = "tournament",
+        about = "Run a round-robin or gauntlet tournament between the built-in engine (`builtin` or `builtin:<depth>`) and external UCI engines, each given with `--engine`. Supports time controls via `--tc`, opening positions via `--openings`, adjudicates decided games with `--resign`/`--draw`, and writes every game to `--pgn-out`. Prints a crosstable when finished."
+    )]
+    Tournament(TournamentArgs),
+    #[structopt(
+        name = "tune",
+        about = "Texel-tune the engine's scalar evaluation weights (material, bishop pair, pawn structure, rook files, mobility) against positions labelled with game results in `--data`. Progress is saved to `--params` after every iteration, and an existing `--params` file is resumed from. Runs `--iterations` passes (default: 10)."
+    )]
+    Tune(TuneArgs),
+    #[structopt(
+        name = "spsa",
+        about = "Tune the search parameters (aspiration window, late move reduction constants, futility margin scale) with SPSA: each iteration plays `--pairs` game pairs (default: 1) between the engine with the parameters perturbed up and down, and moves them towards the side that scored better. Games are played at `--depth` or on `--tc` (default: movetime=100) from `--openings`. Progress is saved to `--params` after every iteration, and an existing `--params` file is resumed from, until `--iterations` (default: 100) are done."
+    )]
+    Spsa(SpsaArgs),
     #[structopt(
         name = "uci",
-        about = "Start UCI (Universal Chess Interface) mode for integration with external chess GUIs like Arena, cutechess-cli, or lichess. Reads UCI commands from stdin and responds on stdout."
+        about = "Start UCI (Universal Chess Interface) mode for integration with external chess GUIs like Arena, cutechess-cli, or lichess. Reads UCI commands from stdin and responds on stdout. Use `--learning-file <path>` to learn from the games played, steering away from moves that lost."
     )]
     Uci(UciArgs),
 }
@@ -75,11 +144,24 @@ impl crate::cli::commands::Command for Chess {
             Play(cmd),
             Pvp(cmd),
             Watch(cmd),
+            Drill(cmd),
+            Replay(cmd),
             CalculateBestMove(cmd),
+            Analyze(cmd),
+            Annotate(cmd),
+            Correspondence(cmd),
+            Bench(cmd),
             BenchmarkAlphaBeta(cmd),
             DetermineStockfishElo(cmd),
             CountPositions(cmd),
+            Serve(cmd),
+            LichessBot(cmd),
+            Solve(cmd),
             SolvePuzzles(cmd),
+            Epd(cmd),
+            Tournament(cmd),
+            Tune(cmd),
+            Spsa(cmd),
             Uci(cmd),
         }
     }
@@ -0,0 +1,262 @@
+//! Analyze command - evaluate a position continuously, deepening until
+//! stopped, with commands to change the position as the analysis runs.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use chess::board::Board;
+use chess::evaluate::GameEnding;
+use chess::game::analysis::{Analysis, AnalysisLine};
+use chess::game::engine::{Engine, EngineConfig};
+use chess::game::pgn::parse_pgn;
+use chess::input_handler::fen::STARTING_POSITION_FEN;
+use chess::tui::eval_bar_widget::score_label;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use super::util::{parse_move_list, MoveReport};
+use super::Command;
+
+/// Deepest search when no `--depth` is given; analysis is normally stopped
+/// long before.
+const UNLIMITED_DEPTH: u8 = 64;
+
+const HELP: &str = "Commands: stop, go, undo, setfen <fen>, a move in SAN or UCI notation, quit";
+
+#[derive(StructOpt)]
+pub struct AnalyzeArgs {
+    #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
+    pub starting_position: Board,
+    /// PGN file whose game is analyzed from its last position, instead of `--fen`
+    #[structopt(long, conflicts_with = "starting-position")]
+    pub pgn: Option<String>,
+    /// Which game of the `--pgn` file to analyze, counting from 1
+    #[structopt(long, default_value = "1")]
+    pub game: usize,
+    /// Moves (SAN or UCI) to play before analyzing, after those of the PGN game
+    #[structopt(long)]
+    pub moves: Vec<String>,
+    /// Stop deepening after this depth instead of when told to
+    #[structopt(short, long)]
+    pub depth: Option<u8>,
+    /// Print each completed depth as a line of JSON (score, PV, nodes, timing)
+    #[structopt(long)]
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct DepthReport {
+    fen: String,
+    depth: u8,
+    /// Centipawns from White's perspective; absent for forced mates.
+    score_cp: Option<i16>,
+    /// Moves to checkmate, negative when Black mates.
+    mate: Option<i16>,
+    pv: Vec<MoveReport>,
+    nodes: usize,
+    time_ms: u128,
+    nps: u64,
+}
+
+impl Command for AnalyzeArgs {
+    fn execute(self) {
+        let (starting_position, mut moves) = match &self.pgn {
+            Some(path) => pgn_game(path, self.game).unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }),
+            None => (self.starting_position.clone(), Vec::new()),
+        };
+        moves.extend(parse_move_list(&self.moves));
+
+        let mut engine = new_engine(starting_position);
+        for (index, notation) in moves.iter().enumerate() {
+            if !play_move(&mut engine, notation) {
+                eprintln!("Illegal move `{}` (move {})", notation, index + 1);
+                std::process::exit(1);
+            }
+        }
+
+        let depth = self.depth.unwrap_or(UNLIMITED_DEPTH);
+        let json = self.json;
+        notice(json, HELP);
+        let mut analysis = start_analysis(&mut engine, depth, json);
+
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let line = line.trim();
+            let (command, argument) = line
+                .split_once(char::is_whitespace)
+                .map_or((line, ""), |(command, argument)| (command, argument.trim()));
+            match command {
+                "" => continue,
+                "quit" | "exit" => break,
+                "help" => notice(json, HELP),
+                "stop" => {
+                    // Dropping the analysis waits for its search to stop
+                    if analysis.take().is_some_and(|search| !search.is_finished()) {
+                        notice(json, "Stopped");
+                    }
+                }
+                "go" => {
+                    drop(analysis.take());
+                    analysis = start_analysis(&mut engine, depth, json);
+                }
+                "undo" => {
+                    drop(analysis.take());
+                    if engine.undo_move().is_err() {
+                        notice(json, "No move to undo");
+                    }
+                    analysis = start_analysis(&mut engine, depth, json);
+                }
+                "setfen" => match Board::from_str(argument) {
+                    Ok(board) => {
+                        drop(analysis.take());
+                        engine = new_engine(board);
+                        analysis = start_analysis(&mut engine, depth, json);
+                    }
+                    Err(err) => notice(json, &format!("Invalid FEN: {}", err)),
+                },
+                notation => {
+                    drop(analysis.take());
+                    if !play_move(&mut engine, notation) {
+                        let text =
+                            format!("Unknown command or illegal move `{}`. {}", notation, HELP);
+                        notice(json, &text);
+                    }
+                    analysis = start_analysis(&mut engine, depth, json);
+                }
+            }
+        }
+        drop(analysis);
+    }
+}
+
+/// The starting position and moves of game `number` in the PGN file at `path`.
+fn pgn_game(path: &str, number: usize) -> Result<(Board, Vec<String>), String> {
+    let text =
+        fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path, err))?;
+    let games = parse_pgn(&text).map_err(|err| format!("Failed to parse {}: {}", path, err))?;
+    let game = number
+        .checked_sub(1)
+        .and_then(|index| games.get(index))
+        .ok_or_else(|| format!("No game {} in {} ({} game(s))", number, path, games.len()))?;
+    let starting_position = game
+        .starting_position()
+        .map_err(|err| format!("Invalid starting position in game {}: {}", number, err))?;
+    let moves = game.moves().iter().map(|m| m.san.clone()).collect();
+    Ok((starting_position, moves))
+}
+
+fn new_engine(starting_position: Board) -> Engine {
+    Engine::with_config(EngineConfig {
+        starting_position,
+        ..EngineConfig::default()
+    })
+}
+
+/// Play `notation` in SAN or UCI notation, keeping the history `undo` needs.
+/// Returns whether it was a legal move.
+fn play_move(engine: &mut Engine, notation: &str) -> bool {
+    if engine.make_move_san_or_uci(notation).is_err() {
+        return false;
+    }
+    engine.board_mut().toggle_turn();
+    engine.record_position_hash();
+    true
+}
+
+/// Print the position and start analyzing it, unless the game is over there.
+/// With `json`, each completed depth is printed as a `DepthReport`.
+fn start_analysis(engine: &mut Engine, depth: u8, json: bool) -> Option<Analysis> {
+    let fen = engine.board().to_fen();
+    notice(json, &format!("Position: {}", fen));
+    if let Some(ending) = engine.check_game_over() {
+        let description = match ending {
+            GameEnding::Checkmate => "checkmate",
+            GameEnding::Stalemate => "stalemate",
+            GameEnding::Draw => "a draw",
+            GameEnding::VariantWin => "won by the variant's rule",
+        };
+        let text = format!("Nothing to analyze: the game is over ({})", description);
+        notice(json, &text);
+        return None;
+    }
+    Some(Analysis::start_to_depth(
+        engine.board().clone(),
+        engine.position_hashes().to_vec(),
+        depth,
+        move |line| {
+            if json {
+                print_report(&fen, line)
+            } else {
+                print_line(line)
+            }
+        },
+    ))
+}
+
+/// Print a message for the user. With `--json` it goes to stderr, so stdout
+/// holds nothing but reports.
+fn notice(json: bool, text: &str) {
+    if json {
+        eprintln!("{}", text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+fn print_line(line: &AnalysisLine) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(
+        stdout,
+        "depth {:>2}  score {:>6}  nodes {:>10}  nps {:>8}  time {:>7.2}s  pv {}",
+        line.depth,
+        score_label(line.score, line.mate_in),
+        line.nodes,
+        line.nodes_per_second,
+        line.elapsed.as_secs_f64(),
+        line.principal_variation.join(" ")
+    );
+    let _ = stdout.flush();
+}
+
+fn print_report(fen: &str, line: &AnalysisLine) {
+    let mate = line.mate_in.map(|moves| {
+        let moves = i16::from(moves);
+        if line.score < 0 {
+            -moves
+        } else {
+            moves
+        }
+    });
+    let pv = line
+        .principal_variation_uci
+        .iter()
+        .zip(&line.principal_variation)
+        .map(|(uci, san)| MoveReport {
+            uci: uci.clone(),
+            san: san.clone(),
+        })
+        .collect();
+    let report = DepthReport {
+        fen: fen.to_string(),
+        depth: line.depth,
+        score_cp: mate.is_none().then_some(line.score),
+        mate,
+        pv,
+        nodes: line.nodes,
+        time_ms: line.elapsed.as_millis(),
+        nps: line.nodes_per_second,
+    };
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(
+        stdout,
+        "{}",
+        serde_json::to_string(&report).expect("report should serialize")
+    );
+    let _ = stdout.flush();
+}
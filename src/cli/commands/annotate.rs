@@ -0,0 +1,94 @@
+//! Annotate command - engine analysis of every move in a PGN file.
+
+use std::fs;
+
+use chess::game::annotate::{annotate_game, AnnotateConfig, AnnotationThresholds};
+use chess::game::pgn::parse_pgn;
+use structopt::StructOpt;
+
+use super::util::SearchLimitArgs;
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct AnnotateArgs {
+    /// PGN file with the games to annotate
+    pub input: String,
+    /// Write the annotated games here instead of to stdout
+    #[structopt(short, long)]
+    pub output: Option<String>,
+    /// Search depth per position when no `--movetime` or `--nodes` is given
+    #[structopt(short, long, default_value = "6")]
+    pub depth: u8,
+    #[structopt(flatten)]
+    pub search_limits: SearchLimitArgs,
+    /// Centipawn loss at which a move is marked as an inaccuracy (?!)
+    #[structopt(long, default_value = "50")]
+    pub inaccuracy: u16,
+    /// Centipawn loss at which a move is marked as a mistake (?)
+    #[structopt(long, default_value = "100")]
+    pub mistake: u16,
+    /// Centipawn loss at which a move is marked as a blunder (??)
+    #[structopt(long, default_value = "300")]
+    pub blunder: u16,
+}
+
+impl Command for AnnotateArgs {
+    fn execute(self) {
+        let text = match fs::read_to_string(&self.input) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", self.input, err);
+                std::process::exit(1);
+            }
+        };
+        let games = match parse_pgn(&text) {
+            Ok(games) => games,
+            Err(err) => {
+                eprintln!("Failed to parse {}: {}", self.input, err);
+                std::process::exit(1);
+            }
+        };
+
+        let config = AnnotateConfig {
+            depth: self.depth,
+            limits: self.search_limits.limits(),
+            thresholds: AnnotationThresholds {
+                inaccuracy: self.inaccuracy,
+                mistake: self.mistake,
+                blunder: self.blunder,
+            },
+        };
+        let annotator = format!("chess ({})", self.search_limits.describe(self.depth));
+
+        let mut annotated = Vec::with_capacity(games.len());
+        for (index, game) in games.iter().enumerate() {
+            eprintln!(
+                "Annotating game {}/{} ({} moves)...",
+                index + 1,
+                games.len(),
+                game.moves().len()
+            );
+            match annotate_game(game, &config) {
+                Ok(mut game) => {
+                    game.set_header("Annotator", &annotator);
+                    annotated.push(game.to_string());
+                }
+                Err(err) => {
+                    eprintln!("Skipping game {}: {}", index + 1, err);
+                }
+            }
+        }
+
+        let output = annotated.join("\n");
+        match &self.output {
+            Some(path) => {
+                if let Err(err) = fs::write(path, output) {
+                    eprintln!("Failed to write {}: {}", path, err);
+                    std::process::exit(1);
+                }
+                eprintln!("Wrote {} annotated games to {}", annotated.len(), path);
+            }
+            None => print!("{}", output),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Bench command - deterministic node-count signature for search changes.
+
+use chess::game::bench::{compare_bench, print_bench, print_comparison, run_bench, BenchResult};
+use structopt::StructOpt;
+
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct BenchArgs {
+    #[structopt(short, long, default_value = "5")]
+    pub depth: u8,
+    /// Print the result as JSON
+    #[structopt(long)]
+    pub json: bool,
+    /// Write the result (nodes, NPS, per-position times) to this baseline file
+    #[structopt(long)]
+    pub save: Option<String>,
+    /// Compare against a baseline written with `--save`, at the baseline's depth,
+    /// and exit non-zero on a regression
+    #[structopt(long)]
+    pub compare: Option<String>,
+    /// Percentage slowdown tolerated by `--compare`
+    #[structopt(long, default_value = "10")]
+    pub threshold: f64,
+}
+
+impl Command for BenchArgs {
+    fn execute(self) {
+        let baseline = self
+            .compare
+            .as_deref()
+            .map(|path| match BenchResult::load(path) {
+                Ok(baseline) => baseline,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            });
+        let depth = baseline.as_ref().map_or(self.depth, |b| b.depth);
+
+        let result = if self.json {
+            run_bench(depth, |_, _, _| {})
+        } else {
+            print_bench(depth)
+        };
+        let comparison = baseline.map(|b| compare_bench(&b, &result, self.threshold));
+
+        if self.json {
+            let output = match &comparison {
+                Some(comparison) => {
+                    serde_json::json!({ "result": result, "comparison": comparison })
+                }
+                None => serde_json::to_value(&result).expect("bench result should serialize"),
+            };
+            println!("{}", output);
+        } else if let Some(comparison) = &comparison {
+            print_comparison(comparison);
+        }
+
+        if let Some(path) = &self.save {
+            if let Err(err) = result.save(path) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            if !self.json {
+                println!("Saved baseline to {}", path);
+            }
+        }
+
+        if comparison.is_some_and(|c| c.is_regression()) {
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,44 +1,129 @@
 //! Calculate best move command - determine the best move from a position.
 
+use std::time::Instant;
+
 use chess::board::Board;
 use chess::game::engine::{Engine, EngineConfig};
+use chess::input_handler::fen::STARTING_POSITION_FEN;
+use serde::Serialize;
 use structopt::StructOpt;
 
+use super::util::{starting_position_with_moves, MoveReport, SearchLimitArgs};
 use super::Command;
 
 #[derive(StructOpt)]
 pub struct CalculateBestMoveArgs {
     #[structopt(short, long, default_value = "4")]
     pub depth: u8,
-    #[structopt(long = "fen")]
+    #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
     pub starting_position: Board,
+    /// Moves (SAN or UCI) to play from `--fen` before searching
+    #[structopt(long)]
+    pub moves: Vec<String>,
+    #[structopt(flatten)]
+    pub search_limits: SearchLimitArgs,
+    /// Print the result as JSON (move, score, PV, nodes, timing)
+    #[structopt(long)]
+    pub json: bool,
+}
+
+/// Longest principal variation reported in JSON output.
+const MAX_PV_LENGTH: usize = 32;
+
+#[derive(Serialize)]
+struct BestMoveReport {
+    fen: String,
+    /// Fixed search depth; absent when the search was bounded by time or nodes.
+    depth: Option<u8>,
+    best_move: MoveReport,
+    /// Centipawns from White's perspective; absent for book moves.
+    score_cp: Option<i16>,
+    pv: Vec<MoveReport>,
+    nodes: usize,
+    time_ms: u128,
+    nps: u64,
 }
 
 impl Command for CalculateBestMoveArgs {
     fn execute(self) {
         let config = EngineConfig {
             search_depth: self.depth,
-            starting_position: self.starting_position,
+            starting_position: starting_position_with_moves(self.starting_position, &self.moves),
         };
         let mut engine = Engine::with_config(config);
+        let limits = self.search_limits.limits();
+        engine.set_search_limits(limits);
+        let fen = engine.board().to_fen();
 
         let valid_moves = engine.get_valid_moves();
         if valid_moves.is_empty() {
-            eprintln!("There are no valid moves in the given position.");
+            report_error(self.json, "There are no valid moves in the given position.");
             return;
         }
 
-        match engine.get_best_move() {
-            Ok(best_move) => {
-                let algebraic_move = valid_moves
-                    .iter()
-                    .find_map(|(chess_move, algebraic_notation)| {
-                        (chess_move == &best_move).then_some(algebraic_notation.as_str())
-                    })
-                    .expect("best move should be in valid moves");
-                println!("{}", algebraic_move);
+        let start = Instant::now();
+        let best_move = match engine.get_best_move() {
+            Ok(best_move) => best_move,
+            Err(err) => {
+                report_error(
+                    self.json,
+                    &format!("Failed to calculate best move: {}", err),
+                );
+                return;
             }
-            Err(err) => eprintln!("Failed to calculate best move: {}", err),
+        };
+        let elapsed = start.elapsed();
+
+        let algebraic_move = valid_moves
+            .iter()
+            .find_map(|(chess_move, algebraic_notation)| {
+                (chess_move == &best_move).then_some(algebraic_notation.as_str())
+            })
+            .expect("best move should be in valid moves");
+
+        if !self.json {
+            println!("{}", algebraic_move);
+            return;
         }
+
+        let stats = engine.get_search_stats();
+        let pv = engine
+            .principal_variation(&best_move, MAX_PV_LENGTH)
+            .into_iter()
+            .map(|(chess_move, san)| MoveReport {
+                uci: chess_move.to_uci(),
+                san,
+            })
+            .collect();
+        let secs = elapsed.as_secs_f64();
+        let report = BestMoveReport {
+            fen,
+            depth: limits.is_depth_only().then_some(self.depth),
+            best_move: MoveReport {
+                uci: best_move.to_uci(),
+                san: algebraic_move.to_string(),
+            },
+            score_cp: stats.last_score,
+            pv,
+            nodes: stats.positions_searched,
+            time_ms: elapsed.as_millis(),
+            nps: if secs > 0.0 {
+                (stats.positions_searched as f64 / secs) as u64
+            } else {
+                0
+            },
+        };
+        println!(
+            "{}",
+            serde_json::to_string(&report).expect("report should serialize")
+        );
+    }
+}
+
+fn report_error(json: bool, message: &str) {
+    if json {
+        println!("{}", serde_json::json!({ "error": message }));
+    } else {
+        eprintln!("{}", message);
     }
 }
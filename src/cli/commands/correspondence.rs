@@ -0,0 +1,149 @@
+//! Correspondence command - analyze a long-running game a session at a time.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chess::alpha_beta_searcher::SearchLimits;
+use chess::board::Board;
+use chess::game::correspondence::CorrespondenceGame;
+use chess::game::move_list::format_move_list;
+use chess::game::pgn::format_eval_comment;
+use chess::input_handler::fen::STARTING_POSITION_FEN;
+use structopt::StructOpt;
+
+use super::util::parse_move_list;
+use super::Command;
+
+/// Depth cap for the search; the time budget is what bounds it in practice.
+const MAX_DEPTH: u8 = 100;
+
+/// Longest principal variation printed for the best candidate.
+const PV_LENGTH: usize = 12;
+
+#[derive(StructOpt)]
+pub struct CorrespondenceArgs {
+    /// JSON file holding the game and the saved analysis; created if missing
+    pub file: String,
+    /// Starting position for a new game file
+    #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
+    pub starting_position: Board,
+    /// Moves (SAN or UCI) played since the last session, for either side
+    #[structopt(long)]
+    pub moves: Vec<String>,
+    /// Seconds to analyze the current position this session
+    #[structopt(long, default_value = "60")]
+    pub think: u64,
+    /// Number of candidate moves to show
+    #[structopt(long, default_value = "5")]
+    pub candidates: usize,
+}
+
+impl Command for CorrespondenceArgs {
+    fn execute(self) {
+        let mut game = if Path::new(&self.file).exists() {
+            match CorrespondenceGame::load(&self.file) {
+                Ok(game) => game,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            }
+        } else {
+            println!("Starting a new correspondence game in {}", self.file);
+            CorrespondenceGame::new(&self.starting_position)
+        };
+        let mut engine = match game.engine(MAX_DEPTH) {
+            Ok(engine) => engine,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        if game.analysis_len() > 0 {
+            println!("Loaded {} analysis entries", game.analysis_len());
+        }
+
+        for notation in parse_move_list(&self.moves) {
+            if engine.make_move_san_or_uci(&notation).is_err() {
+                eprintln!("Illegal move `{}`", notation);
+                std::process::exit(1);
+            }
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        }
+
+        println!();
+        println!("FEN: {}", engine.board().to_fen());
+        let moves = format_move_list(&engine.move_list());
+        if !moves.is_empty() {
+            println!("Moves: {}", moves);
+        }
+
+        let mut elapsed = Duration::ZERO;
+        let mut candidates = Vec::new();
+        if let Some(ending) = engine.check_game_over() {
+            println!("The game is over: {:?}", ending);
+        } else if self.think > 0 {
+            println!("Analyzing for {}s...", self.think);
+            // Half the time goes to the main search, the rest to scoring each
+            // legal move one ply shallower
+            let think = Duration::from_secs(self.think);
+            engine.set_search_limits(SearchLimits {
+                movetime: Some(think / 2),
+                nodes: None,
+                ..SearchLimits::default()
+            });
+            let start = Instant::now();
+            if let Err(err) = engine.analyze_position() {
+                eprintln!("Analysis failed: {}", err);
+            }
+            let depth = engine.last_completed_depth().unwrap_or(1);
+            let remaining = think.saturating_sub(start.elapsed());
+            candidates = engine.evaluate_moves(remaining, depth.saturating_sub(1));
+            elapsed = start.elapsed();
+        }
+
+        game.update(&engine, elapsed, &candidates);
+        print_candidates(&game, self.candidates);
+        if let Some(best) = candidates.first() {
+            let pv: Vec<String> = engine
+                .principal_variation(&best.chess_move, PV_LENGTH)
+                .into_iter()
+                .map(|(_, san)| san)
+                .collect();
+            println!("Best line: {}", pv.join(" "));
+        }
+
+        match game.save(&self.file) {
+            Ok(()) => println!(
+                "Saved the game and {} analysis entries to {}",
+                game.analysis_len(),
+                self.file
+            ),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn print_candidates(game: &CorrespondenceGame, count: usize) {
+    if game.candidates.is_empty() {
+        return;
+    }
+    println!();
+    println!(
+        "Candidate moves ({:.0}s of analysis on this position):",
+        game.analysis_seconds
+    );
+    for candidate in game.candidates.iter().take(count) {
+        println!(
+            "  {:<8} {:>6}  depth {}",
+            candidate.san,
+            format_eval_comment(candidate.score),
+            candidate.depth
+        );
+    }
+    println!();
+}
@@ -1,6 +1,10 @@
 //! Count positions command - count possible positions at a given depth.
 
-use chess::game::position_counter::{run_count_positions, CountPositionsStrategy};
+use chess::board::Board;
+use chess::game::position_counter::{
+    run_count_positions, run_perft_divide, run_verify_perft, CountPositionsStrategy,
+};
+use chess::input_handler::fen::STARTING_POSITION_FEN;
 use structopt::StructOpt;
 
 use super::Command;
@@ -11,10 +15,51 @@ pub struct CountPositionsArgs {
     pub depth: u8,
     #[structopt(short, long, default_value = "all")]
     pub strategy: CountPositionsStrategy,
+    /// Check perft results for the standard reference positions up to `--depth`
+    /// and exit non-zero on any mismatch
+    #[structopt(long)]
+    pub verify: bool,
+    /// Run perft on `--fen` at `--depth` and print the leaf count under each
+    /// root move
+    #[structopt(long)]
+    pub divide: bool,
+    /// Position for `--divide`
+    #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
+    pub starting_position: Board,
+    /// Perft total `--divide` should find, exiting non-zero otherwise. The
+    /// reference positions are checked without it
+    #[structopt(long)]
+    pub expected: Option<usize>,
+    /// Size in megabytes of the hash caching perft subtree counts for
+    /// `--divide` and `--verify`; 0 turns it off
+    #[structopt(long, default_value = "64")]
+    pub hash: usize,
+    /// Print per-depth counts and timing as JSON
+    #[structopt(long)]
+    pub json: bool,
 }
 
 impl Command for CountPositionsArgs {
     fn execute(self) {
-        run_count_positions(self.depth, self.strategy);
+        if self.divide {
+            let passed = run_perft_divide(
+                &self.starting_position,
+                self.depth,
+                self.hash,
+                self.expected,
+                self.json,
+            );
+            if !passed {
+                std::process::exit(1);
+            }
+            return;
+        }
+        if self.verify {
+            if !run_verify_perft(self.depth, self.hash, self.json) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        run_count_positions(self.depth, self.strategy, self.json);
     }
 }
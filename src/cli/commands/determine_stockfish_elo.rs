@@ -1,29 +1,75 @@
 //! Determine Stockfish ELO command - measure engine strength.
 
-use chess::game::stockfish_elo::determine_stockfish_elo;
+use chess::game::stockfish_elo::{determine_stockfish_elo, EloRunConfig};
+use chess::game::tournament::TimeControl;
+use chess::game::uci_engine::UciEngineHandle;
 use structopt::StructOpt;
 
+use super::util::OpponentSettingsArgs;
 use super::Command;
 
 #[derive(StructOpt)]
 pub struct DetermineStockfishEloArgs {
+    /// Opponent engine command line; must support `UCI_LimitStrength` and `UCI_Elo`
+    #[structopt(long, default_value = "stockfish")]
+    pub engine: UciEngineHandle,
+    #[structopt(flatten)]
+    pub opponent_settings: OpponentSettingsArgs,
     #[structopt(short, long, default_value = "4")]
     pub depth: u8,
-    #[structopt(short, long, default_value = "1320")]
-    pub starting_elo: u32,
+    /// Total number of games, spread evenly over the opponent's Elo levels
+    #[structopt(short, long, default_value = "40")]
+    pub games: usize,
+    /// Time control: `<base>+<inc>` in seconds, or `movetime=<ms>`
+    #[structopt(long = "tc", default_value = "movetime=1000")]
+    pub time_control: TimeControl,
+    /// Lowest opponent `UCI_Elo` level to play against
+    #[structopt(long, default_value = "1320")]
+    pub min_elo: u32,
+    /// Highest opponent `UCI_Elo` level to play against
+    #[structopt(long, default_value = "2120")]
+    pub max_elo: u32,
+    /// Spacing between opponent Elo levels
+    #[structopt(long, default_value = "200")]
+    pub elo_step: u32,
+    /// Number of games to play in parallel, each against its own engine process
+    #[structopt(short, long, default_value = "1")]
+    pub concurrency: usize,
+    /// Write every game to this PGN file
+    #[structopt(long)]
+    pub pgn_out: Option<String>,
     #[structopt(long, help = "Disable TUI and print progress to stdout")]
     pub no_tui: bool,
-    #[structopt(
-        short,
-        long,
-        default_value = "1000",
-        help = "Time limit per move in ms"
-    )]
-    pub time_limit: u64,
 }
 
 impl Command for DetermineStockfishEloArgs {
     fn execute(self) {
-        determine_stockfish_elo(self.depth, self.starting_elo, self.no_tui, self.time_limit);
+        let config = EloRunConfig {
+            opponent: self.opponent_settings.apply(self.engine),
+            depth: self.depth,
+            games: self.games,
+            time_control: self.time_control,
+            min_elo: self.min_elo,
+            max_elo: self.max_elo,
+            elo_step: self.elo_step,
+            concurrency: self.concurrency,
+        };
+        let report = match determine_stockfish_elo(&config, self.no_tui) {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        };
+
+        if let Some(path) = &self.pgn_out {
+            for game in &report.games {
+                if let Err(e) = game.pgn.append_to_file(path) {
+                    eprintln!("Failed to write PGN to {}: {}", path, e);
+                    return;
+                }
+            }
+            println!("Wrote {} games to {}", report.games.len(), path);
+        }
     }
 }
@@ -0,0 +1,64 @@
+//! Drill command - practise the book moves of an opening.
+
+use chess::board::color::Color;
+use chess::board::Board;
+use chess::book::Book;
+use chess::game::drill::OpeningDrill;
+use chess::game::input_source::DrillInput;
+use chess::game::renderer::TuiRenderer;
+use structopt::StructOpt;
+
+use super::util::{create_config, run_game_loop, GameOptions, SearchLimitArgs};
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct DrillArgs {
+    /// Name of the opening to drill, e.g. "Ruy Lopez" (partial names match every line)
+    #[structopt(long)]
+    pub opening: String,
+    /// Side played by the student
+    #[structopt(short = "c", long = "color", default_value = "white")]
+    pub color: Color,
+    /// Search depth for the engine once the book line is finished
+    #[structopt(short, long, default_value = "6")]
+    pub depth: u8,
+    #[structopt(flatten)]
+    pub search_limits: SearchLimitArgs,
+}
+
+impl Command for DrillArgs {
+    fn execute(self) {
+        let drill = match OpeningDrill::new(&Book::default(), &self.opening, self.color) {
+            Ok(drill) => drill,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+
+        let renderer = match TuiRenderer::new(Some(self.color)) {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Failed to initialize TUI: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let engine_name = format!("chess ({})", self.search_limits.describe(self.depth));
+        let players = match self.color {
+            Color::White => ("Student", engine_name.as_str()),
+            Color::Black => (engine_name.as_str(), "Student"),
+        };
+        let options = GameOptions {
+            search_limits: self.search_limits,
+            ..GameOptions::default()
+        };
+        run_game_loop(
+            DrillInput::new(drill),
+            renderer,
+            create_config(self.depth, Board::default()),
+            players,
+            &options,
+            None,
+        );
+    }
+}
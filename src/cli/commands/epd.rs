@@ -0,0 +1,93 @@
+//! EPD command - run a test suite of positions with best or avoid moves.
+
+use std::time::Duration;
+
+use chess::game::epd_suite::{load_epd_suite, solve_epd_position, EpdLimits};
+use structopt::StructOpt;
+
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct EpdArgs {
+    /// EPD file with a `bm` or `am` operation on every line (WAC, STS, Bratko-Kopec, ...)
+    pub file: String,
+    /// Search time per position, in milliseconds
+    #[structopt(long, default_value = "1000")]
+    pub movetime: u64,
+    /// Deepest search per position, however much time is left
+    #[structopt(short, long, default_value = "64")]
+    pub depth: u8,
+}
+
+impl Command for EpdArgs {
+    fn execute(self) {
+        let positions = match load_epd_suite(&self.file) {
+            Ok(positions) => positions,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        let limits = EpdLimits {
+            movetime: Duration::from_millis(self.movetime),
+            max_depth: self.depth.max(1),
+        };
+
+        println!(
+            "EPD suite {}: {} positions, {}ms each",
+            self.file,
+            positions.len(),
+            self.movetime
+        );
+        println!("{}", "-".repeat(70));
+
+        let mut solved = 0;
+        let mut total_nodes = 0;
+        let mut total_time = Duration::ZERO;
+        let mut total_time_to_solution = Duration::ZERO;
+        for position in &positions {
+            let result = solve_epd_position(position, &limits);
+            let status = if result.solved { "PASS" } else { "FAIL" };
+            let time_to_solution = result.time_to_solution.map_or_else(
+                || "-".to_string(),
+                |time| format!("{:.2}s", time.as_secs_f64()),
+            );
+            let move_info = if result.solved {
+                result.found.clone()
+            } else {
+                format!("{} ({})", result.found, position.expected())
+            };
+            println!(
+                "  [{}] {:.<24} {:<24} tts {:>7}  depth {:>2}  {:>10} nodes",
+                status,
+                format!("{} ", position.name),
+                move_info,
+                time_to_solution,
+                result.depth,
+                result.nodes
+            );
+
+            if result.solved {
+                solved += 1;
+                total_time_to_solution += result.time_to_solution.unwrap_or_default();
+            }
+            total_nodes += result.nodes;
+            total_time += result.elapsed;
+        }
+
+        println!("{}", "-".repeat(70));
+        println!(
+            "Solved {}/{} in {:.1}s, {} nodes",
+            solved,
+            positions.len(),
+            total_time.as_secs_f64(),
+            total_nodes
+        );
+        if solved > 0 {
+            println!(
+                "Average time to solution: {:.2}s",
+                total_time_to_solution.as_secs_f64() / solved as f64
+            );
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Lichess bot command - play on lichess.org through the Bot API.
+
+use std::time::Duration;
+
+use chess::board::Variant;
+use chess::lichess_bot::{run_bot, BotConfig, ChallengeFilter, TimeSettings, LICHESS_URL};
+use structopt::StructOpt;
+
+use super::Command;
+
+/// Environment variable read for the API token when `--token` isn't given.
+const TOKEN_VARIABLE: &str = "LICHESS_BOT_TOKEN";
+
+#[derive(StructOpt)]
+pub struct LichessBotArgs {
+    /// API token of the bot account, with the `bot:play` scope (default: $LICHESS_BOT_TOKEN)
+    #[structopt(long)]
+    pub token: Option<String>,
+    /// Lichess server to connect to
+    #[structopt(long, default_value = LICHESS_URL)]
+    pub server: String,
+    /// Variant to accept challenges for: chess, kingofthehill, or 3check. Repeat for several
+    #[structopt(long = "variant", default_value = "chess")]
+    pub variants: Vec<Variant>,
+    /// Shortest initial clock to accept, in seconds
+    #[structopt(long, default_value = "0")]
+    pub min_initial: u64,
+    /// Longest initial clock to accept, in seconds
+    #[structopt(long)]
+    pub max_initial: Option<u64>,
+    /// Smallest increment to accept, in seconds
+    #[structopt(long, default_value = "0")]
+    pub min_increment: u64,
+    /// Largest increment to accept, in seconds
+    #[structopt(long)]
+    pub max_increment: Option<u64>,
+    /// Accept correspondence and unlimited games
+    #[structopt(long)]
+    pub untimed: bool,
+    /// Decline rated challenges
+    #[structopt(long, conflicts_with = "no-casual")]
+    pub no_rated: bool,
+    /// Decline casual challenges
+    #[structopt(long)]
+    pub no_casual: bool,
+    /// Decline challenges from other bots
+    #[structopt(long)]
+    pub no_bots: bool,
+    /// Games played at the same time; later challenges are declined
+    #[structopt(long, default_value = "1")]
+    pub max_games: usize,
+    /// Time kept back on every move for network lag, in milliseconds
+    #[structopt(long, default_value = "100")]
+    pub move_overhead: u64,
+    /// Longest time spent on any move, in milliseconds
+    #[structopt(long)]
+    pub max_movetime: Option<u64>,
+    /// Time per move in games without a clock, in milliseconds
+    #[structopt(long, default_value = "5000")]
+    pub untimed_movetime: u64,
+}
+
+impl Command for LichessBotArgs {
+    fn execute(self) {
+        let Some(token) = self
+            .token
+            .or_else(|| std::env::var(TOKEN_VARIABLE).ok())
+            .filter(|token| !token.is_empty())
+        else {
+            eprintln!(
+                "An API token is required: pass --token or set {}",
+                TOKEN_VARIABLE
+            );
+            std::process::exit(1);
+        };
+
+        let config = BotConfig {
+            server: self.server,
+            token,
+            filter: ChallengeFilter {
+                variants: self.variants,
+                min_initial: self.min_initial,
+                max_initial: self.max_initial,
+                min_increment: self.min_increment,
+                max_increment: self.max_increment,
+                allow_untimed: self.untimed,
+                allow_rated: !self.no_rated,
+                allow_casual: !self.no_casual,
+                allow_bots: !self.no_bots,
+            },
+            time: TimeSettings {
+                move_overhead: Duration::from_millis(self.move_overhead),
+                max_movetime: self.max_movetime.map(Duration::from_millis),
+                untimed_movetime: Duration::from_millis(self.untimed_movetime),
+            },
+            max_games: self.max_games.max(1),
+        };
+        if let Err(err) = run_bot(config) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
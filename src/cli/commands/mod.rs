@@ -4,13 +4,26 @@ pub trait Command {
     fn execute(self);
 }
 
+pub mod analyze;
+pub mod annotate;
+pub mod bench;
 pub mod benchmark_alpha_beta;
 pub mod calculate_best_move;
+pub mod correspondence;
 pub mod count_positions;
 pub mod determine_stockfish_elo;
+pub mod drill;
+pub mod epd;
+pub mod lichess_bot;
 pub mod play;
 pub mod pvp;
+pub mod replay;
+pub mod serve;
+pub mod solve;
 pub mod solve_puzzles;
+pub mod spsa;
+pub mod tournament;
+pub mod tune;
 pub mod uci;
 pub mod watch;
 
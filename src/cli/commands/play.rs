@@ -1,31 +1,180 @@
 //! Play command - play a game against the computer.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use chess::board::color::Color;
 use chess::board::Board;
+use chess::game::clock::GameClock;
+use chess::game::input_source::ExternalEngineInput;
+use chess::game::learning::Experience;
+use chess::game::odds::Odds;
+use chess::game::pgn::PgnOutput;
+use chess::game::strength::Strength;
+use chess::game::uci_engine::{GoLimits, UciEngineHandle};
 use chess::input_handler::fen::STARTING_POSITION_FEN;
 use structopt::StructOpt;
 
+use super::util::{
+    parse_clock, ArmageddonArgs, OpponentSettingsArgs, SearchLimitArgs, VariantArgs,
+};
 use super::Command;
 
 #[derive(StructOpt)]
 pub struct PlayArgs {
-    #[structopt(short, long, default_value = "6")]
-    pub depth: u8,
+    /// Search depth [default: the one saved from the settings menu, or 6]
+    #[structopt(short, long)]
+    pub depth: Option<u8>,
     #[structopt(short = "c", long = "color", default_value = "random")]
     pub color: Color,
     #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
     pub starting_position: Board,
+    /// Moves (SAN or UCI) to play from the starting position before the game begins
+    #[structopt(long)]
+    pub moves: Vec<String>,
+    #[structopt(flatten)]
+    pub variant: VariantArgs,
+    #[structopt(flatten)]
+    pub search_limits: SearchLimitArgs,
+    /// Material the engine gives up at the start: pawn, knight, rook, or queen
+    #[structopt(long)]
+    pub odds: Option<Odds>,
+    /// Weaken the engine to roughly this Elo (600-2400); overrides `--depth`
+    #[structopt(long)]
+    pub strength: Option<Strength>,
+    /// External UCI engine command line to play against instead of the engine itself
+    #[structopt(long)]
+    pub opponent: Option<UciEngineHandle>,
+    /// Thinking time per move for `--opponent`, in milliseconds
+    #[structopt(long, default_value = "1000")]
+    pub opponent_movetime: u64,
+    /// Limit `--opponent` to this Elo with the `UCI_LimitStrength` and `UCI_Elo` options
+    #[structopt(long)]
+    pub opponent_elo: Option<u32>,
+    #[structopt(flatten)]
+    pub opponent_settings: OpponentSettingsArgs,
+    /// Don't let the engine think while it's your turn
+    #[structopt(long)]
+    pub no_ponder: bool,
+    /// Chess clock for each side as `<base>+<increment>` in seconds, e.g. `300+2`
+    #[structopt(long = "tc", parse(try_from_str = parse_clock))]
+    pub clock: Option<GameClock>,
+    #[structopt(flatten)]
+    pub time_odds: ArmageddonArgs,
+    /// Maximum number of takebacks with `undo` per game (default: unlimited)
+    #[structopt(long)]
+    pub max_undos: Option<usize>,
+    /// Append each game to this PGN file
+    #[structopt(long)]
+    pub pgn_out: Option<String>,
+    /// Include engine evaluations as move comments in the PGN
+    #[structopt(long)]
+    pub pgn_evals: bool,
+    /// Hide the pieces, leaving only the squares; F4 shows them until the next move
+    #[structopt(long)]
+    pub blindfold: bool,
+    /// Learn from games in this file: moves the engine lost with count against them
+    #[structopt(long)]
+    pub learning_file: Option<String>,
 }
 
 impl Command for PlayArgs {
     fn execute(self) {
-        use super::util::run_game_with_mode_switching;
+        use super::util::{checked_move_list, run_game_with_mode_switching, GameOptions};
         use chess::game::action::GameMode;
+        let (clock, draw_odds) = match self.time_odds.apply(self.clock) {
+            Ok(odds) => odds,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        if let Some(Err(e)) = self.learning_file.as_deref().map(Experience::load) {
+            eprintln!("{}", e);
+            return;
+        }
+        let include_evals = self.pgn_evals;
+        let (mut depth, mut search_limits) = self.search_limits.or_saved(self.depth, 6);
+        if let Some(strength) = self.strength {
+            // The strength stands in for a saved search budget
+            depth = strength.search_depth();
+            search_limits = SearchLimitArgs {
+                nodes: self.search_limits.nodes.or(strength.node_limit()),
+                ..self.search_limits
+            };
+        }
+        let settings = self.opponent_settings;
+        let opponent = self.opponent.map(|handle| settings.apply(handle));
+        let opponent = match opponent.as_ref().map(UciEngineHandle::spawn) {
+            Some(Ok(mut engine)) => {
+                if let Some(elo) = self.opponent_elo {
+                    match engine.limit_strength(elo) {
+                        Ok(applied) if applied != elo => {
+                            let (min, max) =
+                                engine.spin_option_range("UCI_Elo").unwrap_or_default();
+                            eprintln!(
+                                "Note: {} supports UCI_Elo {}-{}; playing at {}.",
+                                engine.name(),
+                                min,
+                                max,
+                                applied
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return;
+                        }
+                    }
+                }
+                Some(ExternalEngineInput {
+                    engine: Rc::new(RefCell::new(engine)),
+                    engine_color: self.color.opposite(),
+                    limits: GoLimits::MoveTime(self.opponent_movetime),
+                    human_opponent: true,
+                })
+            }
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return;
+            }
+            None => None,
+        };
+        let starting_position = match self.odds {
+            Some(odds) => match odds.apply(&self.starting_position, self.color.opposite()) {
+                Ok(board) => board,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            },
+            None => self.starting_position,
+        };
+        // After the odds, which start the position afresh
+        let starting_position = self.variant.apply(starting_position);
+        let opening_moves = checked_move_list(&starting_position, &self.moves);
         run_game_with_mode_switching(
             GameMode::Play,
-            self.depth,
+            depth,
             self.color,
-            self.starting_position,
+            starting_position,
+            GameOptions {
+                pgn_output: self.pgn_out.map(|path| PgnOutput {
+                    path,
+                    include_evals,
+                }),
+                search_limits,
+                opponent,
+                clock,
+                draw_odds,
+                max_undos: self.max_undos.unwrap_or(usize::MAX),
+                strength: self.strength,
+                opening_moves,
+                no_ponder: self.no_ponder,
+                blindfold: self.blindfold,
+                learning_file: self.learning_file,
+                ..GameOptions::default()
+            },
         );
     }
 }
@@ -1,26 +1,73 @@
 //! PvP command - play a game against another human.
 
 use chess::board::Board;
+use chess::game::clock::GameClock;
+use chess::game::pgn::PgnOutput;
 use chess::input_handler::fen::STARTING_POSITION_FEN;
 use structopt::StructOpt;
 
+use super::util::{parse_clock, ArmageddonArgs, VariantArgs};
 use super::Command;
 
 #[derive(StructOpt)]
 pub struct PvpArgs {
     #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
     pub starting_position: Board,
+    /// Moves (SAN or UCI) to play from the starting position before the game begins
+    #[structopt(long)]
+    pub moves: Vec<String>,
+    #[structopt(flatten)]
+    pub variant: VariantArgs,
+    /// Chess clock for each side as `<base>+<increment>` in seconds, e.g. `300+2`
+    #[structopt(long = "tc", parse(try_from_str = parse_clock))]
+    pub clock: Option<GameClock>,
+    #[structopt(flatten)]
+    pub time_odds: ArmageddonArgs,
+    /// Maximum number of takebacks with `undo` per game (default: unlimited)
+    #[structopt(long)]
+    pub max_undos: Option<usize>,
+    /// Append each game to this PGN file
+    #[structopt(long)]
+    pub pgn_out: Option<String>,
+    /// Include engine evaluations as move comments in the PGN
+    #[structopt(long)]
+    pub pgn_evals: bool,
+    /// Hide the pieces, leaving only the squares; F4 shows them until the next move
+    #[structopt(long)]
+    pub blindfold: bool,
 }
 
 impl Command for PvpArgs {
     fn execute(self) {
-        use super::util::run_game_with_mode_switching;
+        use super::util::{checked_move_list, run_game_with_mode_switching, GameOptions};
         use chess::game::action::GameMode;
+        let (clock, draw_odds) = match self.time_odds.apply(self.clock) {
+            Ok(odds) => odds,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        let include_evals = self.pgn_evals;
+        let starting_position = self.variant.apply(self.starting_position);
+        let opening_moves = checked_move_list(&starting_position, &self.moves);
         run_game_with_mode_switching(
             GameMode::Pvp,
             0,                                 // Depth not used in PvP
             chess::board::color::Color::White, // Not used in PvP
-            self.starting_position,
+            starting_position,
+            GameOptions {
+                pgn_output: self.pgn_out.map(|path| PgnOutput {
+                    path,
+                    include_evals,
+                }),
+                clock,
+                draw_odds,
+                max_undos: self.max_undos.unwrap_or(usize::MAX),
+                opening_moves,
+                blindfold: self.blindfold,
+                ..GameOptions::default()
+            },
         );
     }
 }
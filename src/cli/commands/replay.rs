@@ -0,0 +1,86 @@
+//! Replay command - step through a game from a PGN file.
+
+use std::fs;
+
+use chess::game::input_source::HumanInput;
+use chess::game::pgn::parse_pgn;
+use chess::game::renderer::{GameRenderer, TuiRenderer};
+use structopt::StructOpt;
+
+use super::util::{create_config, run_game_loop, GameOptions};
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct ReplayArgs {
+    /// PGN file with the game to replay
+    pub input: String,
+    /// Which game of the file to replay, counting from 1
+    #[structopt(long, default_value = "1")]
+    pub game: usize,
+    /// Show the live analysis of each position from the start
+    #[structopt(long)]
+    pub analyze: bool,
+}
+
+impl Command for ReplayArgs {
+    fn execute(self) {
+        let text = match fs::read_to_string(&self.input) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", self.input, err);
+                std::process::exit(1);
+            }
+        };
+        let games = match parse_pgn(&text) {
+            Ok(games) => games,
+            Err(err) => {
+                eprintln!("Failed to parse {}: {}", self.input, err);
+                std::process::exit(1);
+            }
+        };
+        let Some(game) = self.game.checked_sub(1).and_then(|index| games.get(index)) else {
+            eprintln!(
+                "No game {} in {} ({} game(s))",
+                self.game,
+                self.input,
+                games.len()
+            );
+            std::process::exit(1);
+        };
+        let starting_position = match game.starting_position() {
+            Ok(board) => board,
+            Err(err) => {
+                eprintln!("Invalid starting position in game {}: {}", self.game, err);
+                std::process::exit(1);
+            }
+        };
+
+        let renderer = match TuiRenderer::new(None) {
+            Ok(renderer) => renderer,
+            Err(e) => {
+                eprintln!("Failed to initialize TUI: {}", e);
+                std::process::exit(1);
+            }
+        };
+        if self.analyze {
+            renderer.toggle_analysis();
+        }
+        let players = (
+            game.header("White").unwrap_or("?"),
+            game.header("Black").unwrap_or("?"),
+        );
+        let options = GameOptions {
+            opening_moves: game.moves().iter().map(|m| m.san.clone()).collect(),
+            replay: true,
+            ..GameOptions::default()
+        };
+        run_game_loop(
+            HumanInput,
+            renderer,
+            create_config(0, starting_position),
+            players,
+            &options,
+            None,
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Serve command - answer analysis requests over HTTP.
+
+use std::time::Duration;
+
+use chess::server::{serve, AnalysisLimits, ServerConfig};
+use structopt::StructOpt;
+
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[structopt(long, default_value = "127.0.0.1")]
+    pub host: String,
+    #[structopt(long, default_value = "8080")]
+    pub port: u16,
+    /// Port to stream live analysis on over WebSocket
+    #[structopt(long)]
+    pub ws_port: Option<u16>,
+    /// WebSocket connections open at the same time
+    #[structopt(long, default_value = "4")]
+    pub streams: usize,
+    /// Searches run at the same time
+    #[structopt(long, default_value = "2")]
+    pub workers: usize,
+    /// Requests waiting for a worker before more are turned away with 503
+    #[structopt(long, default_value = "16")]
+    pub queue: usize,
+    /// Search depth of requests that don't give one
+    #[structopt(short, long, default_value = "6")]
+    pub depth: u8,
+    /// Deepest search a request may ask for
+    #[structopt(long, default_value = "12")]
+    pub max_depth: u8,
+    /// Longest search a request may ask for, in milliseconds
+    #[structopt(long, default_value = "10000")]
+    pub max_movetime: u64,
+}
+
+impl Command for ServeArgs {
+    fn execute(self) {
+        let config = ServerConfig {
+            address: format!("{}:{}", self.host, self.port),
+            stream_address: self.ws_port.map(|port| format!("{}:{}", self.host, port)),
+            max_streams: self.streams,
+            workers: self.workers,
+            queue_size: self.queue,
+            limits: AnalysisLimits {
+                default_depth: self.depth,
+                max_depth: self.max_depth.max(1),
+                max_movetime: Duration::from_millis(self.max_movetime),
+            },
+        };
+        if let Err(err) = serve(config) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Solve command - find forced mates and check puzzle soundness.
+
+use chess::board::Board;
+use chess::game::engine::{Engine, EngineConfig};
+use chess::game::mate_solver::{find_forced_mate, solve_mate_in};
+use structopt::StructOpt;
+
+use super::util::SearchLimitArgs;
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct SolveArgs {
+    #[structopt(long = "fen")]
+    pub starting_position: Board,
+    /// Prove a mate in exactly this many moves instead of searching for the shortest
+    #[structopt(short, long)]
+    pub mate_in: Option<u8>,
+    /// Longest mate to look for when `--mate-in` is not given
+    #[structopt(long, default_value = "3")]
+    pub max_mate: u8,
+    /// Verify that the key move is unique and list any alternatives
+    #[structopt(short, long)]
+    pub unique: bool,
+    /// Search depth for the fallback best-move search when no mate is found
+    #[structopt(short, long, default_value = "6")]
+    pub depth: u8,
+    #[structopt(flatten)]
+    pub search_limits: SearchLimitArgs,
+}
+
+impl Command for SolveArgs {
+    fn execute(self) {
+        let mut board = self.starting_position.clone();
+        let solution = match self.mate_in {
+            Some(n) => solve_mate_in(&mut board, n),
+            None => find_forced_mate(&mut board, self.max_mate),
+        };
+
+        let solution = match solution {
+            Some(solution) => solution,
+            None => {
+                let limit = self.mate_in.unwrap_or(self.max_mate);
+                println!("No forced mate in {} found.", limit);
+                print_best_move(self.starting_position, self.depth, self.search_limits);
+                return;
+            }
+        };
+
+        match self.mate_in {
+            Some(n) if n != solution.mate_in => println!(
+                "Mate in {} (within the {} asked for): {}",
+                solution.mate_in,
+                n,
+                solution.line.join(" ")
+            ),
+            _ => println!("Mate in {}: {}", solution.mate_in, solution.line.join(" ")),
+        }
+        if self.unique {
+            if solution.is_unique() {
+                println!("Key move is unique.");
+            } else {
+                println!(
+                    "Key move is NOT unique; {} moves force mate: {}",
+                    solution.key_moves.len(),
+                    solution.key_moves.join(", ")
+                );
+            }
+        }
+    }
+}
+
+fn print_best_move(starting_position: Board, depth: u8, search_limits: SearchLimitArgs) {
+    let mut engine = Engine::with_config(EngineConfig {
+        search_depth: depth,
+        starting_position,
+    });
+    engine.set_search_limits(search_limits.limits());
+    let valid_moves = engine.get_valid_moves();
+    match engine.get_best_move() {
+        Ok(best_move) => {
+            let notation = valid_moves
+                .iter()
+                .find(|(m, _)| m == &best_move)
+                .map(|(_, n)| n.clone())
+                .unwrap_or_else(|| best_move.to_uci());
+            let score = engine
+                .get_search_stats()
+                .last_score
+                .map_or("-".to_string(), |s| s.to_string());
+            println!(
+                "Best move ({}): {} (score: {})",
+                search_limits.describe(depth),
+                notation,
+                score
+            );
+        }
+        Err(err) => eprintln!("Failed to calculate best move: {}", err),
+    }
+}
@@ -0,0 +1,117 @@
+//! SPSA command - tune the search parameters by self-play.
+
+use std::time::Instant;
+
+use chess::game::spsa::{play_self_play_match, spsa_iteration, SpsaConfig, SpsaState, SPSA_PARAMS};
+use chess::game::tournament::{load_openings, TimeControl};
+use structopt::StructOpt;
+
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct SpsaArgs {
+    /// State file for tuned parameters; resumed from if it already exists
+    #[structopt(long)]
+    pub params: String,
+    /// Iterations in the whole run, including those already in `--params`
+    #[structopt(short, long, default_value = "100")]
+    pub iterations: usize,
+    /// Game pairs (colors reversed) per iteration
+    #[structopt(long, default_value = "1")]
+    pub pairs: usize,
+    /// Search depth of both engines; use `--tc` instead when not given
+    #[structopt(short, long)]
+    pub depth: Option<u8>,
+    /// Time control when no `--depth` is given: `<base>+<inc>` in seconds, or `movetime=<ms>`
+    #[structopt(long = "tc", default_value = "movetime=100")]
+    pub time_control: TimeControl,
+    /// File of opening positions, one FEN or EPD per line
+    #[structopt(long)]
+    pub openings: Option<String>,
+    /// Adjudicate a draw after this many full moves
+    #[structopt(long, default_value = "150")]
+    pub max_moves: usize,
+    /// Final step per game point, relative to each parameter's perturbation squared
+    #[structopt(long, default_value = "0.05")]
+    pub learning_rate: f64,
+    /// Seed for the perturbations and opening choice
+    #[structopt(long)]
+    pub seed: Option<u64>,
+}
+
+impl Command for SpsaArgs {
+    fn execute(self) {
+        let openings = match self.openings.as_deref().map(load_openings) {
+            Some(Ok(openings)) => openings,
+            Some(Err(err)) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            None => Vec::new(),
+        };
+        let mut state = match SpsaState::load_or_initial(&self.params) {
+            Ok(state) => state,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        let config = SpsaConfig {
+            iterations: self.iterations,
+            pairs: self.pairs.max(1),
+            depth: self.depth,
+            time_control: self.time_control,
+            openings,
+            max_moves: self.max_moves,
+            learning_rate: self.learning_rate,
+        };
+        let rng = match self.seed {
+            Some(seed) => fastrand::Rng::with_seed(seed),
+            None => fastrand::Rng::new(),
+        };
+
+        println!(
+            "SPSA: starting at iteration {} of {}, {} game(s) per iteration",
+            state.iteration,
+            config.iterations,
+            config.pairs * 2
+        );
+        while state.iteration < config.iterations {
+            let start = Instant::now();
+            let result = spsa_iteration(&mut state, &config, &rng, |plus, minus| {
+                play_self_play_match(plus, minus, &config, &rng)
+            });
+            let iteration = match result {
+                Ok(iteration) => iteration,
+                Err(err) => {
+                    eprintln!("Self-play failed: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            let values = SPSA_PARAMS
+                .iter()
+                .zip(&state.values)
+                .map(|(param, value)| format!("{} {:.3}", param.name, value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "Iteration {}: {:+} ({:.1}s) -> {}",
+                state.iteration,
+                iteration.net_score,
+                start.elapsed().as_secs_f64(),
+                values
+            );
+            if let Err(err) = state.save(&self.params) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+
+        println!("\nTuned parameters (saved to {}):", self.params);
+        let defaults = SpsaState::initial();
+        for ((param, value), default) in SPSA_PARAMS.iter().zip(&state.values).zip(&defaults.values)
+        {
+            println!("  {:<24} {:>8.3} (was {})", param.name, value, default);
+        }
+    }
+}
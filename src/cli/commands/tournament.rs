@@ -0,0 +1,67 @@
+//! Tournament command - play matches between this engine and external UCI engines.
+
+use chess::game::tournament::{
+    load_openings, run_tournament, EngineSpec, TimeControl, TournamentConfig, TournamentFormat,
+};
+use structopt::StructOpt;
+
+use super::util::AdjudicationArgs;
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct TournamentArgs {
+    /// Participant: `builtin`, `builtin:<depth>`, or an engine command line. Repeat for each engine.
+    #[structopt(short, long = "engine", required = true, min_values = 2)]
+    pub engines: Vec<EngineSpec>,
+    /// Pairing scheme: round-robin or gauntlet (first engine against all others)
+    #[structopt(short, long, default_value = "round-robin")]
+    pub format: TournamentFormat,
+    /// Time control: `<base>+<inc>` in seconds, or `movetime=<ms>`
+    #[structopt(long = "tc", default_value = "movetime=1000")]
+    pub time_control: TimeControl,
+    /// Games per pairing; colors alternate between games
+    #[structopt(short, long, default_value = "2")]
+    pub games: usize,
+    /// File of opening positions, one FEN or EPD per line
+    #[structopt(long)]
+    pub openings: Option<String>,
+    /// Adjudicate a draw after this many full moves
+    #[structopt(long, default_value = "200")]
+    pub max_moves: usize,
+    #[structopt(flatten)]
+    pub adjudication: AdjudicationArgs,
+    /// Append every game to this PGN file
+    #[structopt(long)]
+    pub pgn_out: Option<String>,
+    /// Include engine evaluations as move comments in the PGN
+    #[structopt(long)]
+    pub pgn_evals: bool,
+}
+
+impl Command for TournamentArgs {
+    fn execute(self) {
+        let openings = match self.openings.as_deref().map(load_openings) {
+            Some(Ok(openings)) => openings,
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return;
+            }
+            None => Vec::new(),
+        };
+
+        let config = TournamentConfig {
+            engines: self.engines,
+            format: self.format,
+            time_control: self.time_control,
+            games_per_pairing: self.games,
+            openings,
+            max_moves: self.max_moves,
+            adjudication: self.adjudication.rules(),
+            pgn_out: self.pgn_out,
+            pgn_evals: self.pgn_evals,
+        };
+        if let Err(e) = run_tournament(config) {
+            eprintln!("Tournament failed: {}", e);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Tune command - Texel tuning of evaluation weights on labelled positions.
+
+use std::time::Instant;
+
+use chess::evaluate::tuning::{
+    load_positions, tune_iteration, tuning_error, TuningState, TUNABLE_PARAMS,
+};
+use structopt::StructOpt;
+
+use super::Command;
+
+#[derive(StructOpt)]
+pub struct TuneArgs {
+    /// EPD/FEN file of positions labelled with game results
+    #[structopt(long)]
+    pub data: String,
+    /// State file for tuned weights; resumed from if it already exists
+    #[structopt(long)]
+    pub params: String,
+    /// Number of tuning iterations to run in this session
+    #[structopt(short, long, default_value = "10")]
+    pub iterations: usize,
+}
+
+impl Command for TuneArgs {
+    fn execute(self) {
+        let positions = match load_positions(&self.data) {
+            Ok(positions) => positions,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        let mut state = match TuningState::load_or_initial(&self.params, &positions) {
+            Ok(state) => state,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+
+        println!(
+            "Loaded {} positions; starting at iteration {} (k = {:.4})",
+            positions.len(),
+            state.iteration,
+            state.k
+        );
+        println!("Initial error: {:.8}", tuning_error(&positions, &state));
+
+        for _ in 0..self.iterations {
+            let start = Instant::now();
+            let (error, changed) = tune_iteration(&positions, &mut state);
+            println!(
+                "Iteration {}: error {:.8}, {} weights changed ({:.2}s)",
+                state.iteration,
+                error,
+                changed,
+                start.elapsed().as_secs_f64()
+            );
+            if let Err(err) = state.save(&self.params) {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+            if changed == 0 {
+                println!("Converged.");
+                break;
+            }
+        }
+
+        println!("\nTuned weights (saved to {}):", self.params);
+        for (&(name, default), value) in TUNABLE_PARAMS.iter().zip(&state.values) {
+            println!("  {:<30} {:>6} (was {})", name, value, default);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! UCI (Universal Chess Interface) command implementation
 
+use chess::game::learning::Experience;
 use chess::uci::UciProtocol;
 
 use super::Command;
@@ -7,12 +8,23 @@ use super::Command;
 /// UCI protocol mode - starts UCI interface for external chess GUIs
 #[derive(structopt::StructOpt)]
 pub struct UciArgs {
-    // No arguments needed for UCI mode
+    /// Learn from games in this file: moves the engine lost with count against them
+    #[structopt(long)]
+    pub learning_file: Option<String>,
 }
 
 impl Command for UciArgs {
     fn execute(self) {
         let mut protocol = UciProtocol::new();
+        if let Some(path) = &self.learning_file {
+            match Experience::load(path) {
+                Ok(experience) => protocol = protocol.with_learning(path, experience),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            }
+        }
         protocol.run();
     }
 }
@@ -1,20 +1,278 @@
 //! Shared utilities for CLI commands.
 
+use chess::alpha_beta_searcher::SearchLimits;
 use chess::board::color::Color;
-use chess::board::Board;
+use chess::board::{Board, Variant};
 use chess::game::action::{GameAction, GameMode};
-use chess::game::engine::EngineConfig;
-use chess::game::input_source::{ConditionalInput, EngineInput, HumanInput, InputSource};
+use chess::game::adjudication::{AdjudicationRules, DrawRule, ResignRule};
+use chess::game::clock::GameClock;
+use std::time::Duration;
+
+use chess::game::engine::{position_after_moves, EngineConfig};
+use chess::game::input_source::{
+    ConditionalInput, EngineInput, ExternalEngineInput, HumanInput, InputSource,
+};
+use chess::game::learning::Experience;
+use chess::game::pgn::{parse_pgn, PgnOutput};
+use chess::game::playback::PlaybackOptions;
 use chess::game::r#loop::GameLoop;
 use chess::game::renderer::GameRenderer;
 use chess::game::renderer::TuiRenderer;
+use chess::game::strength::Strength;
+use chess::game::tournament::TimeControl;
+use chess::game::uci_engine::UciEngineHandle;
+use chess::tui::config::Preferences;
+use serde::Serialize;
+use structopt::StructOpt;
+
+/// Search budget options shared by search-driven commands.
+#[derive(StructOpt, Clone, Copy, Default)]
+pub struct SearchLimitArgs {
+    /// Search each move for up to this many milliseconds instead of to `--depth`
+    #[structopt(long)]
+    pub movetime: Option<u64>,
+    /// Stop each search after roughly this many nodes instead of at `--depth`
+    #[structopt(long)]
+    pub nodes: Option<usize>,
+}
+
+impl SearchLimitArgs {
+    pub(crate) fn limits(&self) -> SearchLimits {
+        SearchLimits {
+            movetime: self.movetime.map(Duration::from_millis),
+            nodes: self.nodes,
+            ..SearchLimits::default()
+        }
+    }
+
+    /// The search depth and budget given on the command line, falling back to
+    /// those saved from the TUI's settings menu and then to `default_depth`.
+    /// A saved time per move only applies when no search option was given.
+    pub(crate) fn or_saved(self, depth: Option<u8>, default_depth: u8) -> (u8, Self) {
+        let saved = Preferences::load();
+        let mut limits = self;
+        if depth.is_none() && self.movetime.is_none() && self.nodes.is_none() {
+            limits.movetime = saved.movetime.map(|movetime| movetime.as_millis() as u64);
+        }
+        (depth.or(saved.depth).unwrap_or(default_depth), limits)
+    }
+
+    /// Describe the engine's strength setting, e.g. for PGN player names.
+    pub(crate) fn describe(&self, depth: u8) -> String {
+        match (self.movetime, self.nodes) {
+            (Some(ms), Some(nodes)) => format!("movetime {}ms, {} nodes", ms, nodes),
+            (Some(ms), None) => format!("movetime {}ms", ms),
+            (None, Some(nodes)) => format!("{} nodes", nodes),
+            (None, None) => format!("depth {}", depth),
+        }
+    }
+}
+
+/// Settings for an external UCI opponent, applied on top of its command line.
+#[derive(StructOpt, Clone, Copy, Default)]
+pub struct OpponentSettingsArgs {
+    /// Search threads for the external engine (`Threads`)
+    #[structopt(long)]
+    pub opponent_threads: Option<u32>,
+    /// Hash table size in MB for the external engine (`Hash`)
+    #[structopt(long)]
+    pub opponent_hash: Option<u32>,
+    /// Skill level for the external engine (Stockfish's `Skill Level`, 0-20)
+    #[structopt(long)]
+    pub opponent_skill_level: Option<u8>,
+}
+
+impl OpponentSettingsArgs {
+    pub(crate) fn apply(&self, mut handle: UciEngineHandle) -> UciEngineHandle {
+        if let Some(threads) = self.opponent_threads {
+            handle = handle.with_threads(threads);
+        }
+        if let Some(hash) = self.opponent_hash {
+            handle = handle.with_hash(hash);
+        }
+        if let Some(level) = self.opponent_skill_level {
+            handle = handle.with_skill_level(level);
+        }
+        handle
+    }
+}
 
-pub(crate) fn run_game_loop<I, R>(input_source: I, renderer: R, config: EngineConfig) -> GameAction
+/// Default Armageddon clock for White; Black gets `ARMAGEDDON_BLACK_SHARE` of it.
+const ARMAGEDDON_WHITE_TIME: Duration = Duration::from_secs(300);
+
+/// Black's share of White's time in Armageddon, e.g. 4 minutes against 5.
+const ARMAGEDDON_BLACK_SHARE: f64 = 0.8;
+
+/// The rules shared by the interactive game modes.
+#[derive(StructOpt, Clone, Copy, Default)]
+pub struct VariantArgs {
+    /// Rules to play by: chess, kingofthehill, or 3check
+    #[structopt(long, default_value = "chess")]
+    pub variant: Variant,
+}
+
+impl VariantArgs {
+    /// `starting_position` played by `--variant`'s rules. A three-check `--fen`
+    /// stays three-check without it.
+    pub(crate) fn apply(&self, mut starting_position: Board) -> Board {
+        if self.variant != Variant::Standard {
+            starting_position.set_variant(self.variant);
+        }
+        starting_position
+    }
+}
+
+/// Unequal clocks and draw odds shared by the interactive game modes.
+#[derive(StructOpt, Clone, Copy, Default)]
+pub struct ArmageddonArgs {
+    /// Armageddon: Black has draw odds and less time (4/5 of White's unless `--black-tc` is given)
+    #[structopt(long)]
+    pub armageddon: bool,
+    /// Black's clock as `<base>+<increment>` in seconds, when it differs from `--tc`
+    #[structopt(long = "black-tc", parse(try_from_str = parse_clock))]
+    pub black_clock: Option<GameClock>,
+    /// Side that wins drawn games
+    #[structopt(long)]
+    pub draw_odds: Option<Color>,
+}
+
+impl ArmageddonArgs {
+    /// Combine `--tc` with Black's time odds, returning the game clock and the
+    /// side with draw odds.
+    pub(crate) fn apply(
+        &self,
+        clock: Option<GameClock>,
+    ) -> Result<(Option<GameClock>, Option<Color>), String> {
+        let clock = match (clock, self.black_clock) {
+            (None, Some(_)) => return Err("`--black-tc` requires `--tc`".to_string()),
+            (Some(white), Some(black)) => Some(
+                GameClock::with_time_odds(
+                    white.base(Color::White),
+                    black.base(Color::Black),
+                    white.increment(Color::White),
+                )
+                .with_black_increment(black.increment(Color::Black)),
+            ),
+            (clock, None) if self.armageddon => {
+                let white = clock.map_or(ARMAGEDDON_WHITE_TIME, |c| c.base(Color::White));
+                let increment = clock.map_or(Duration::ZERO, |c| c.increment(Color::White));
+                Some(GameClock::with_time_odds(
+                    white,
+                    white.mul_f64(ARMAGEDDON_BLACK_SHARE),
+                    increment,
+                ))
+            }
+            (clock, None) => clock,
+        };
+        let draw_odds = if self.armageddon {
+            Some(self.draw_odds.unwrap_or(Color::Black))
+        } else {
+            self.draw_odds
+        };
+        Ok((clock, draw_odds))
+    }
+}
+
+/// Early adjudication options shared by engine-vs-engine commands.
+#[derive(StructOpt, Clone, Copy, Default)]
+pub struct AdjudicationArgs {
+    /// Adjudicate a loss once both engines see a side behind by `score` centipawns
+    /// for `movecount` moves, e.g. `movecount=3,score=600`
+    #[structopt(long)]
+    pub resign: Option<ResignRule>,
+    /// Adjudicate a draw once both engines score within `score` centipawns of zero
+    /// for `movecount` moves after move `movenumber`, e.g. `movenumber=40,movecount=8,score=10`
+    #[structopt(long)]
+    pub draw: Option<DrawRule>,
+}
+
+impl AdjudicationArgs {
+    pub(crate) fn rules(&self) -> AdjudicationRules {
+        AdjudicationRules {
+            resign: self.resign,
+            draw: self.draw,
+        }
+    }
+}
+
+/// Settings shared by the interactive game modes.
+#[derive(Clone, Default)]
+pub(crate) struct GameOptions {
+    pub pgn_output: Option<PgnOutput>,
+    pub search_limits: SearchLimitArgs,
+    /// External engine playing one side, against the human in play mode and
+    /// against the built-in engine in watch mode.
+    pub opponent: Option<ExternalEngineInput>,
+    /// Delay and stop conditions for watch mode.
+    pub playback: PlaybackOptions,
+    /// Chess clocks for both sides.
+    pub clock: Option<GameClock>,
+    /// Takebacks allowed per game with `undo`.
+    pub max_undos: usize,
+    /// Weakened engine play; the depth and node budget are applied by the caller.
+    pub strength: Option<Strength>,
+    /// Moves played from the starting position before the game begins.
+    pub opening_moves: Vec<String>,
+    /// Keep the engine from thinking on the human's time.
+    pub no_ponder: bool,
+    /// Resign and draw rules for engine games.
+    pub adjudication: AdjudicationRules,
+    /// Side that wins drawn games.
+    pub draw_odds: Option<Color>,
+    /// Step through the opening moves as a replayed game.
+    pub replay: bool,
+    /// Hide the pieces on the board.
+    pub blindfold: bool,
+    /// File the engine learns from its games with.
+    pub learning_file: Option<String>,
+}
+
+/// Parse a `--tc` game clock given as `<base>+<increment>` in seconds.
+pub(crate) fn parse_clock(s: &str) -> Result<GameClock, String> {
+    match s.parse::<TimeControl>()? {
+        TimeControl::Clock { base, increment } => Ok(GameClock::new(base, increment)),
+        TimeControl::MoveTime(_) => {
+            Err("expected a game clock like `300+2`, not a fixed move time".to_string())
+        }
+    }
+}
+
+pub(crate) fn run_game_loop<I, R>(
+    input_source: I,
+    renderer: R,
+    config: EngineConfig,
+    players: (&str, &str),
+    options: &GameOptions,
+    playback: Option<PlaybackOptions>,
+) -> GameAction
 where
     I: InputSource,
     R: GameRenderer,
 {
-    let mut game = GameLoop::new(input_source, renderer, config);
+    renderer.set_blindfold(options.blindfold);
+    let mut game = GameLoop::new(input_source, renderer, config)
+        .with_pgn_output(options.pgn_output.clone(), players.0, players.1)
+        .with_search_limits(options.search_limits.limits())
+        .with_undo_limit(options.max_undos)
+        .with_strength(options.strength)
+        .with_opening_moves(options.opening_moves.clone())
+        .with_pondering(!options.no_ponder)
+        .with_adjudication(options.adjudication)
+        .with_draw_odds(options.draw_odds)
+        .with_replay(options.replay);
+    if let Some(playback) = playback {
+        game = game.with_playback(playback);
+    }
+    if let Some(clock) = options.clock {
+        game = game.with_clock(clock);
+    }
+    // Loaded for every game, as the one before may have added to it
+    if let Some(path) = &options.learning_file {
+        match Experience::load(path) {
+            Ok(experience) => game = game.with_learning(path, experience),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
     game.run()
 }
 
@@ -25,53 +283,145 @@ pub(crate) fn create_config(depth: u8, starting_position: Board) -> EngineConfig
     }
 }
 
+/// Split `--moves` arguments into single moves. Each argument may hold several
+/// moves separated by spaces or commas, and pasted PGN movetext with move
+/// numbers, comments, annotation glyphs and a result is accepted.
+pub(crate) fn parse_move_list(moves: &[String]) -> Vec<String> {
+    let text = moves.join(" ").replace(',', " ");
+    match parse_pgn(&text) {
+        Ok(games) => games
+            .iter()
+            .flat_map(|game| game.moves())
+            .map(|m| m.san.trim_end_matches(['!', '?']).to_string())
+            .collect(),
+        Err(_) => text.split_whitespace().map(str::to_string).collect(),
+    }
+}
+
+/// Parse `--moves` and check that they are legal from `starting_position`.
+/// Exits with an error on the first illegal move.
+pub(crate) fn checked_move_list(starting_position: &Board, moves: &[String]) -> Vec<String> {
+    let moves = parse_move_list(moves);
+    if let Err(err) = position_after_moves(starting_position.clone(), &moves) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    moves
+}
+
+/// Apply `--moves` to the `--fen` position. Exits with an error on the first
+/// illegal move.
+pub(crate) fn starting_position_with_moves(starting_position: Board, moves: &[String]) -> Board {
+    match position_after_moves(starting_position, &parse_move_list(moves)) {
+        Ok(board) => board,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// Unified game runner that can switch between modes
 pub(crate) fn run_game_with_mode_switching(
     initial_mode: GameMode,
     default_depth: u8,
     default_color: Color,
     starting_position: Board,
+    options: GameOptions,
 ) {
     let mut current_mode = initial_mode;
     let current_depth = default_depth;
-    let current_color = default_color;
+    let mut current_color = default_color;
     let starting_position_clone = starting_position.clone();
     let mut current_position = starting_position;
 
+    let engine_name = match options.strength {
+        Some(strength) => format!("chess ({})", strength.describe()),
+        None => format!("chess ({})", options.search_limits.describe(current_depth)),
+    };
+
     loop {
         let action = match current_mode {
             GameMode::Play => {
                 let config = create_config(current_depth, current_position);
-                let input = ConditionalInput {
-                    human_color: current_color,
-                };
-
-                match TuiRenderer::new(Some(current_color)) {
-                    Ok(renderer) => run_game_loop(input, renderer, config),
+                let renderer = match TuiRenderer::new(Some(current_color)) {
+                    Ok(renderer) => renderer,
                     Err(e) => {
                         eprintln!("Failed to initialize TUI: {}", e);
                         std::process::exit(1);
                     }
+                };
+
+                match &options.opponent {
+                    Some(opponent) => {
+                        let opponent_name = opponent.engine.borrow().name().to_string();
+                        let players = match current_color {
+                            Color::White => ("Human", opponent_name.as_str()),
+                            Color::Black => (opponent_name.as_str(), "Human"),
+                        };
+                        let input = ExternalEngineInput {
+                            engine_color: current_color.opposite(),
+                            human_opponent: true,
+                            ..opponent.clone()
+                        };
+                        run_game_loop(input, renderer, config, players, &options, None)
+                    }
+                    None => {
+                        let players = match current_color {
+                            Color::White => ("Human", engine_name.as_str()),
+                            Color::Black => (engine_name.as_str(), "Human"),
+                        };
+                        let input = ConditionalInput {
+                            human_color: current_color,
+                        };
+                        run_game_loop(input, renderer, config, players, &options, None)
+                    }
                 }
             }
             GameMode::Watch => {
                 let config = create_config(current_depth, current_position);
-                let input = EngineInput;
-
-                match TuiRenderer::new(None) {
-                    Ok(renderer) => run_game_loop(input, renderer, config),
+                let renderer = match TuiRenderer::new(None) {
+                    Ok(renderer) => renderer,
                     Err(e) => {
                         eprintln!("Failed to initialize TUI: {}", e);
                         std::process::exit(1);
                     }
+                };
+                let playback = Some(options.playback);
+
+                match &options.opponent {
+                    Some(opponent) => {
+                        let opponent_name = opponent.engine.borrow().name().to_string();
+                        // A rematch swaps the external engine's color too
+                        let engine_color = if current_color == default_color {
+                            opponent.engine_color
+                        } else {
+                            opponent.engine_color.opposite()
+                        };
+                        let players = match engine_color {
+                            Color::White => (opponent_name.as_str(), engine_name.as_str()),
+                            Color::Black => (engine_name.as_str(), opponent_name.as_str()),
+                        };
+                        let input = ExternalEngineInput {
+                            engine_color,
+                            human_opponent: false,
+                            ..opponent.clone()
+                        };
+                        run_game_loop(input, renderer, config, players, &options, playback)
+                    }
+                    None => {
+                        let players = (engine_name.as_str(), engine_name.as_str());
+                        run_game_loop(EngineInput, renderer, config, players, &options, playback)
+                    }
                 }
             }
             GameMode::Pvp => {
                 let config = create_config(0, current_position);
                 let input = HumanInput;
+                let players = ("Human", "Human");
 
                 match TuiRenderer::new(None) {
-                    Ok(renderer) => run_game_loop(input, renderer, config),
+                    Ok(renderer) => run_game_loop(input, renderer, config, players, &options, None),
                     Err(e) => {
                         eprintln!("Failed to initialize TUI: {}", e);
                         std::process::exit(1);
@@ -85,6 +435,11 @@ pub(crate) fn run_game_with_mode_switching(
                 current_position = starting_position_clone.clone();
                 continue;
             }
+            GameAction::Rematch => {
+                current_color = current_color.opposite();
+                current_position = starting_position_clone.clone();
+                continue;
+            }
             GameAction::SwitchGameMode { target } => {
                 current_mode = target;
                 current_position = starting_position_clone.clone();
@@ -96,3 +451,10 @@ pub(crate) fn run_game_with_mode_switching(
         }
     }
 }
+
+/// A move in `--json` output, in both notations.
+#[derive(Serialize)]
+pub struct MoveReport {
+    pub uci: String,
+    pub san: String,
+}
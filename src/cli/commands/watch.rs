@@ -1,28 +1,129 @@
 //! Watch command - watch the computer play against itself.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chess::board::color::Color;
 use chess::board::Board;
+use chess::game::clock::GameClock;
+use chess::game::input_source::ExternalEngineInput;
+use chess::game::pgn::PgnOutput;
+use chess::game::playback::PlaybackOptions;
+use chess::game::uci_engine::{GoLimits, UciEngineHandle};
 use chess::input_handler::fen::STARTING_POSITION_FEN;
 use structopt::StructOpt;
 
+use super::util::{
+    parse_clock, AdjudicationArgs, ArmageddonArgs, OpponentSettingsArgs, SearchLimitArgs,
+    VariantArgs,
+};
 use super::Command;
 
 #[derive(StructOpt)]
 pub struct WatchArgs {
-    #[structopt(short, long, default_value = "6")]
-    pub depth: u8,
+    /// Search depth [default: the one saved from the settings menu, or 6]
+    #[structopt(short, long)]
+    pub depth: Option<u8>,
     #[structopt(long = "fen", default_value = STARTING_POSITION_FEN)]
     pub starting_position: Board,
+    /// Moves (SAN or UCI) to play from the starting position before the game begins
+    #[structopt(long)]
+    pub moves: Vec<String>,
+    #[structopt(flatten)]
+    pub variant: VariantArgs,
+    #[structopt(flatten)]
+    pub search_limits: SearchLimitArgs,
+    /// Chess clock for each side as `<base>+<increment>` in seconds, e.g. `300+2`
+    #[structopt(long = "tc", parse(try_from_str = parse_clock))]
+    pub clock: Option<GameClock>,
+    #[structopt(flatten)]
+    pub time_odds: ArmageddonArgs,
+    /// External UCI engine command line to play against instead of the engine itself
+    #[structopt(long)]
+    pub opponent: Option<UciEngineHandle>,
+    /// Color played by `--opponent`
+    #[structopt(long, default_value = "black")]
+    pub opponent_color: Color,
+    /// Thinking time per move for `--opponent`, in milliseconds
+    #[structopt(long, default_value = "1000")]
+    pub opponent_movetime: u64,
+    #[structopt(flatten)]
+    pub opponent_settings: OpponentSettingsArgs,
+    #[structopt(flatten)]
+    pub adjudication: AdjudicationArgs,
+    /// Wait this many milliseconds after each move
+    #[structopt(long)]
+    pub delay: Option<u64>,
+    /// Pause once this many full moves have been played
+    #[structopt(long)]
+    pub pause_at_move: Option<usize>,
+    /// Pause the first time the evaluation reaches this many centipawns for either side
+    #[structopt(long)]
+    pub pause_at_eval: Option<i16>,
+    /// Append each game to this PGN file
+    #[structopt(long)]
+    pub pgn_out: Option<String>,
+    /// Include engine evaluations as move comments in the PGN
+    #[structopt(long)]
+    pub pgn_evals: bool,
 }
 
 impl Command for WatchArgs {
     fn execute(self) {
-        use super::util::run_game_with_mode_switching;
+        use super::util::{checked_move_list, run_game_with_mode_switching, GameOptions};
         use chess::game::action::GameMode;
+        let (clock, draw_odds) = match self.time_odds.apply(self.clock) {
+            Ok(odds) => odds,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        let include_evals = self.pgn_evals;
+        let (depth, search_limits) = self.search_limits.or_saved(self.depth, 6);
+        let settings = self.opponent_settings;
+        let opponent = self.opponent.map(|handle| settings.apply(handle));
+        let opponent = match opponent.as_ref().map(UciEngineHandle::spawn) {
+            Some(Ok(engine)) => Some(ExternalEngineInput {
+                engine: Rc::new(RefCell::new(engine)),
+                engine_color: self.opponent_color,
+                limits: GoLimits::MoveTime(self.opponent_movetime),
+                human_opponent: false,
+            }),
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                return;
+            }
+            None => None,
+        };
+        let starting_position = self.variant.apply(self.starting_position);
+        let opening_moves = checked_move_list(&starting_position, &self.moves);
         run_game_with_mode_switching(
             GameMode::Watch,
-            self.depth,
+            depth,
             chess::board::color::Color::White,
-            self.starting_position,
+            starting_position,
+            GameOptions {
+                pgn_output: self.pgn_out.map(|path| PgnOutput {
+                    path,
+                    include_evals,
+                }),
+                search_limits,
+                opponent,
+                playback: PlaybackOptions {
+                    delay: self.delay.map(Duration::from_millis),
+                    pause_at_move: self.pause_at_move,
+                    pause_at_eval: self.pause_at_eval,
+                },
+                clock,
+                draw_odds,
+                // Applies after switching to play or pvp from the menu
+                max_undos: usize::MAX,
+                opening_moves,
+                adjudication: self.adjudication.rules(),
+                ..GameOptions::default()
+            },
         );
     }
 }
@@ -1,53 +1,56 @@
 //! Position evaluation functions and game state checking.
 
-use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use common::bitboard::bitboard::Bitboard;
+use common::bitboard::square::Square;
+use once_cell::sync::Lazy;
 
+use crate::alpha_beta_searcher::MATE_SCORE;
 use crate::board::piece::ALL_PIECES;
-use crate::board::{color::Color, piece::Piece, Board};
-use crate::move_generator::targets::Targets;
+use crate::board::{color::Color, piece::Piece, Board, Variant};
+use crate::move_generator::targets::{generate_pawn_attack_targets_bitboard, Targets};
 use crate::move_generator::MoveGenerator;
 
+use super::pawn_hash_table;
+
 /// Static singleton for evaluation targets (knight/king tables + magic bitboards).
 /// Avoids expensive re-creation on every `board_material_score` call.
-static EVAL_TARGETS: LazyLock<Targets> = LazyLock::new(Targets::default);
+static EVAL_TARGETS: Lazy<Targets> = Lazy::new(Targets::default);
+
+/// See `set_mobility_enabled`.
+static MOBILITY_ENABLED: AtomicBool = AtomicBool::new(true);
 
 use super::evaluation_tables::{
-    ADJACENT_FILES, BACKWARD_PAWN_PENALTY_EG, BACKWARD_PAWN_PENALTY_MG, BISHOP_MOBILITY_EG,
-    BISHOP_MOBILITY_MG, BISHOP_PAIR_BONUS_EG, BISHOP_PAIR_BONUS_MG, BONUS_TABLES_EG,
-    BONUS_TABLES_MG, CONNECTED_PAWN_BONUS_EG, CONNECTED_PAWN_BONUS_MG, DOUBLED_PAWN_PENALTY,
-    FILE_MASKS, ISOLATED_PAWN_PENALTY, KING_ATTACK_UNIT_PENALTY, KING_OPEN_FILE_PENALTY,
-    KNIGHT_MOBILITY_EG, KNIGHT_MOBILITY_MG, KNIGHT_OUTPOST_BONUS_EG, KNIGHT_OUTPOST_BONUS_MG,
-    KNIGHT_OUTPOST_SUPPORTED_EG, KNIGHT_OUTPOST_SUPPORTED_MG, MATERIAL_VALUES, MAX_PHASE,
-    PASSED_PAWN_BONUS_EG, PASSED_PAWN_BONUS_MG, PAWN_SHIELD_BONUS, PHASE_WEIGHTS,
-    ROOK_ON_SEVENTH_BONUS_EG, ROOK_ON_SEVENTH_BONUS_MG, ROOK_OPEN_FILE_BONUS_EG,
-    ROOK_OPEN_FILE_BONUS_MG, ROOK_SEMI_OPEN_FILE_BONUS_EG, ROOK_SEMI_OPEN_FILE_BONUS_MG,
-    SQUARE_TO_BLACK_BONUS_INDEX, SQUARE_TO_WHITE_BONUS_INDEX,
+    ADJACENT_FILES, BACKWARD_PAWN_PENALTY_EG, BACKWARD_PAWN_PENALTY_MG, BAD_BISHOP_PAWN_PENALTY_EG,
+    BAD_BISHOP_PAWN_PENALTY_MG, BISHOP_MOBILITY_EG, BISHOP_MOBILITY_MG, BISHOP_PAIR_BONUS_EG,
+    BISHOP_PAIR_BONUS_MG, BLACK_HALF, BONUS_TABLES_EG, BONUS_TABLES_MG, CONNECTED_PAWN_BONUS_EG,
+    CONNECTED_PAWN_BONUS_MG, DARK_SQUARES, DOUBLED_PAWN_PENALTY, FILE_MASKS, ISOLATED_PAWN_PENALTY,
+    KING_ATTACK_UNIT_PENALTY, KING_ON_KEY_SQUARE_BONUS, KING_OPEN_FILE_PENALTY, KNIGHT_MOBILITY_EG,
+    KNIGHT_MOBILITY_MG, KNIGHT_OUTPOST_BONUS_EG, KNIGHT_OUTPOST_BONUS_MG,
+    KNIGHT_OUTPOST_SUPPORTED_EG, KNIGHT_OUTPOST_SUPPORTED_MG, LONE_KING_DISTANCE_BONUS,
+    LONE_KING_EDGE_BONUS, MATERIAL_VALUES, MAX_PHASE, PASSED_PAWN_BONUS_EG, PASSED_PAWN_BONUS_MG,
+    PASSED_PAWN_ENEMY_KING_DISTANCE_WEIGHT, PASSED_PAWN_KING_PROXIMITY_RANK_SCALE,
+    PASSED_PAWN_OWN_KING_DISTANCE_WEIGHT, PAWN_SHIELD_BONUS, PHASE_WEIGHTS, QUEEN_MOBILITY_EG,
+    QUEEN_MOBILITY_MG, ROOK_MOBILITY_EG, ROOK_MOBILITY_MG, ROOK_ON_SEVENTH_BONUS_EG,
+    ROOK_ON_SEVENTH_BONUS_MG, ROOK_OPEN_FILE_BONUS_EG, ROOK_OPEN_FILE_BONUS_MG,
+    ROOK_SEMI_OPEN_FILE_BONUS_EG, ROOK_SEMI_OPEN_FILE_BONUS_MG, SQUARE_TO_BLACK_BONUS_INDEX,
+    SQUARE_TO_WHITE_BONUS_INDEX, TRAPPED_PIECE_MAX_SQUARES, TRAPPED_PIECE_PENALTY_EG,
+    TRAPPED_PIECE_PENALTY_MG, UNSTOPPABLE_PASSER_BONUS, WHITE_HALF,
 };
 
-const BLACK_WINS: i16 = i16::MIN / 2;
-const WHITE_WINS: i16 = i16::MAX / 2;
+const BLACK_WINS: i16 = -MATE_SCORE;
+const WHITE_WINS: i16 = MATE_SCORE;
 
 #[derive(Debug)]
 pub enum GameEnding {
     Checkmate,
     Stalemate,
+    /// By the fifty-move rule, threefold repetition or insufficient material.
     Draw,
-}
-
-#[inline(always)]
-pub fn current_player_is_in_check(board: &Board, move_generator: &MoveGenerator) -> bool {
-    let current_player = board.turn();
-    player_is_in_check(board, move_generator, current_player)
-}
-
-#[inline(always)]
-pub fn player_is_in_check(board: &Board, move_generator: &MoveGenerator, player: Color) -> bool {
-    let king = board.pieces(player).locate(Piece::King);
-    let attacked_squares = move_generator.get_attack_targets(board, player.opposite());
-
-    king.overlaps(attacked_squares)
+    /// The side to move lost by the board's variant rule, like the other
+    /// king reaching the hill in King of the Hill.
+    VariantWin,
 }
 
 #[inline(always)]
@@ -56,9 +59,7 @@ pub fn player_is_in_checkmate(
     move_generator: &MoveGenerator,
     player: Color,
 ) -> bool {
-    let candidates = move_generator.generate_moves(board, player);
-    let check = player_is_in_check(board, move_generator, player);
-    check && candidates.is_empty()
+    board.is_in_check(player) && move_generator.generate_moves(board, player).is_empty()
 }
 
 /// Returns the game ending state if the game has ended, otherwise returns None.
@@ -71,7 +72,11 @@ pub fn game_ending(
     current_turn: Color,
     position_history: &[u64],
 ) -> Option<GameEnding> {
-    if board.halfmove_clock().value() >= 100 {
+    if board.variant().winner(board).is_some() {
+        return Some(GameEnding::VariantWin);
+    }
+
+    if board.halfmove_clock().value() >= 100 || has_insufficient_material(board) {
         return Some(GameEnding::Draw);
     }
 
@@ -88,7 +93,7 @@ pub fn game_ending(
     }
 
     let candidates = move_generator.generate_moves(board, current_turn);
-    let check = current_player_is_in_check(board, move_generator);
+    let check = board.is_in_check(current_turn);
 
     if candidates.is_empty() {
         if check {
@@ -101,6 +106,28 @@ pub fn game_ending(
     None
 }
 
+/// Whether neither side has the material to checkmate: bare kings, a king
+/// and one knight or bishop against a king, or kings and bishops that all
+/// stand on squares of one color. Only in standard chess; in the variants a
+/// king can win on its own.
+#[inline(always)]
+pub fn has_insufficient_material(board: &Board) -> bool {
+    if board.variant() != Variant::Standard {
+        return false;
+    }
+    let (white, black) = (board.pieces(Color::White), board.pieces(Color::Black));
+    let kings = white.locate(Piece::King) | black.locate(Piece::King);
+    let others = board.occupied() & !kings;
+    let bishops = white.locate(Piece::Bishop) | black.locate(Piece::Bishop);
+    let minors = bishops | white.locate(Piece::Knight) | black.locate(Piece::Knight);
+
+    if others.count_ones() <= 1 {
+        return (others & !minors).is_empty();
+    }
+    others == bishops
+        && ((bishops & DARK_SQUARES).is_empty() || (bishops & !DARK_SQUARES).is_empty())
+}
+
 /// Returns the score of the board from White's perspective (positive = White advantage).
 #[inline(always)]
 pub fn score(
@@ -109,11 +136,24 @@ pub fn score(
     current_turn: Color,
     remaining_depth: u8,
 ) -> i16 {
+    // A variant win ends the game before anything else, scored like a
+    // checkmate; the search tells sooner wins apart by their ply
+    match board.variant().winner(board) {
+        Some(Color::White) => return WHITE_WINS,
+        Some(Color::Black) => return BLACK_WINS,
+        None => {}
+    }
+
     // Near the 50-move rule (100 half-moves), score as draw to avoid surprises
     if board.halfmove_clock().value() >= 90 {
         return 0;
     }
 
+    // Leaves are otherwise scored by material, which a dead draw still has
+    if has_insufficient_material(board) {
+        return 0;
+    }
+
     // Use the MoveGenerator's existing Targets to avoid duplicate allocation
     let targets = move_generator.targets();
 
@@ -121,8 +161,7 @@ pub fn score(
     // Most positions are not terminal, so generating all moves to check for checkmate/stalemate
     // is wasteful. We only need full game_ending() check when we're in check (potential checkmate).
     if remaining_depth == 0 {
-        let in_check = current_player_is_in_check(board, move_generator);
-        if !in_check {
+        if !board.is_in_check(current_turn) {
             // Not in check at leaf - just return material evaluation
             return eval_with_targets(board, targets);
         }
@@ -133,9 +172,9 @@ pub fn score(
         if !has_legal_moves {
             // Checkmate!
             return if current_turn == Color::White {
-                BLACK_WINS - remaining_depth as i16
+                BLACK_WINS
             } else {
-                WHITE_WINS + remaining_depth as i16
+                WHITE_WINS
             };
         }
         // In check but has legal moves - return material score
@@ -146,11 +185,9 @@ pub fn score(
     match game_ending(board, move_generator, current_turn, &[]) {
         Some(GameEnding::Checkmate) => {
             if current_turn == Color::White {
-                // Black wins, but sooner is better for Black
-                BLACK_WINS - remaining_depth as i16
+                BLACK_WINS
             } else {
-                // White wins, but sooner is better for White
-                WHITE_WINS + remaining_depth as i16
+                WHITE_WINS
             }
         }
         Some(GameEnding::Stalemate) | Some(GameEnding::Draw) => 0,
@@ -161,7 +198,7 @@ pub fn score(
 /// Computes the game phase (0 = pure endgame, 24 = opening/full pieces).
 /// Sums phase weights for all non-pawn, non-king pieces on both sides.
 #[inline(always)]
-fn game_phase(board: &Board) -> u8 {
+pub(crate) fn game_phase(board: &Board) -> u8 {
     let mut phase: u8 = 0;
     for &color in &[Color::White, Color::Black] {
         let pieces = board.pieces(color);
@@ -195,6 +232,10 @@ pub fn board_material_score(board: &Board) -> i16 {
 /// re-creating magic tables on each call.
 #[inline]
 fn eval_with_targets(board: &Board, targets: &Targets) -> i16 {
+    if let Some(score) = lone_king_eval(board) {
+        return score;
+    }
+
     let phase = game_phase(board);
 
     // Precompute shared bitboards used by positional eval
@@ -207,13 +248,63 @@ fn eval_with_targets(board: &Board, targets: &Targets) -> i16 {
     let black_material = player_material_score(board, Color::Black, phase);
     let material = white_material - black_material;
 
-    // Positional terms using precomputed bitboards
-    let pawn_score = pawn_structure_eval(white_pawns, black_pawns, phase);
+    // Positional terms using precomputed bitboards. The pawn structure only
+    // depends on the pawns, so it is cached by their hash.
+    let pawn_score = pawn_hash_table::probe_or_compute(board.current_pawn_hash(), || {
+        pawn_structure_eval(white_pawns, black_pawns)
+    })
+    .taper(phase);
+    let passed_pawn_race = passed_pawn_race_eval(board, white_pawns, black_pawns, phase);
     let activity = piece_activity_eval(board, all_pawns, white_pawns, black_pawns, phase);
     let king_safety = king_safety_eval(board, targets, all_pawns, white_pawns, black_pawns, phase);
     let mobility = mobility_eval(board, targets, phase);
 
-    material + pawn_score + activity + king_safety + mobility
+    material + pawn_score + passed_pawn_race + activity + king_safety + mobility
+}
+
+/// Evaluates a lone king against a queen, a rook or a bishop pair with no
+/// pawns, where the general terms don't know how to mate: the stronger side
+/// scores for driving the lone king to the edge and into a corner, and for
+/// bringing its own king close. Returns `None` for any other material.
+/// Returns score from White's perspective.
+#[inline]
+fn lone_king_eval(board: &Board) -> Option<i16> {
+    for (strong, weak) in [(Color::White, Color::Black), (Color::Black, Color::White)] {
+        let weak_pieces = board.pieces(weak);
+        let weak_king = weak_pieces.locate(Piece::King);
+        if weak_king.is_empty() || weak_pieces.occupied() != weak_king {
+            continue;
+        }
+        let strong_pieces = board.pieces(strong);
+        let strong_king = strong_pieces.locate(Piece::King);
+        let bishops = strong_pieces.locate(Piece::Bishop);
+        let bishop_pair =
+            !(bishops & DARK_SQUARES).is_empty() && !(bishops & !DARK_SQUARES).is_empty();
+        let can_mate = !strong_pieces.locate(Piece::Queen).is_empty()
+            || !strong_pieces.locate(Piece::Rook).is_empty()
+            || bishop_pair;
+        if strong_king.is_empty() || !strong_pieces.locate(Piece::Pawn).is_empty() || !can_mate {
+            return None;
+        }
+
+        let (strong_king, weak_king) = (strong_king.to_square(), weak_king.to_square());
+        let material: i16 = ALL_PIECES
+            .iter()
+            .filter(|&&piece| piece != Piece::King)
+            .map(|&piece| {
+                MATERIAL_VALUES[piece as usize] * strong_pieces.locate(piece).count_ones() as i16
+            })
+            .sum();
+        let score = material
+            + LONE_KING_EDGE_BONUS[weak_king.index() as usize]
+            + LONE_KING_DISTANCE_BONUS[king_distance(strong_king, weak_king) as usize];
+        return Some(if strong == Color::White {
+            score
+        } else {
+            -score
+        });
+    }
+    None
 }
 
 /// Returns the material score of the board for the given player.
@@ -247,13 +338,36 @@ fn player_material_score(board: &Board, color: Color, phase: u8) -> i16 {
     material
 }
 
-/// Evaluates pawn structure: passed pawns, doubled pawns, isolated pawns.
-/// Returns score from White's perspective.
+/// A pawn structure score before tapering, as kept in the pawn hash table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct PawnScore {
+    pub mg: i16,
+    pub eg: i16,
+}
+
+impl PawnScore {
+    #[inline(always)]
+    fn add(&mut self, mg: i16, eg: i16) {
+        self.mg += mg;
+        self.eg += eg;
+    }
+
+    #[inline(always)]
+    pub(crate) fn taper(self, phase: u8) -> i16 {
+        taper(self.mg, self.eg, phase)
+    }
+}
+
+/// Evaluates pawn structure: passed, doubled, isolated, backward and
+/// connected pawns. Returns score from White's perspective.
 #[inline]
-fn pawn_structure_eval(white_pawns: Bitboard, black_pawns: Bitboard, phase: u8) -> i16 {
-    let white_score = pawn_structure_for_color(white_pawns, black_pawns, phase, true);
-    let black_score = pawn_structure_for_color(black_pawns, white_pawns, phase, false);
-    white_score - black_score
+fn pawn_structure_eval(white_pawns: Bitboard, black_pawns: Bitboard) -> PawnScore {
+    let white_score = pawn_structure_for_color(white_pawns, black_pawns, true);
+    let black_score = pawn_structure_for_color(black_pawns, white_pawns, false);
+    PawnScore {
+        mg: white_score.mg - black_score.mg,
+        eg: white_score.eg - black_score.eg,
+    }
 }
 
 /// Evaluates pawn structure for one side.
@@ -262,10 +376,9 @@ fn pawn_structure_eval(white_pawns: Bitboard, black_pawns: Bitboard, phase: u8)
 fn pawn_structure_for_color(
     friendly_pawns: Bitboard,
     enemy_pawns: Bitboard,
-    phase: u8,
     is_white: bool,
-) -> i16 {
-    let mut score: i16 = 0;
+) -> PawnScore {
+    let mut score = PawnScore::default();
 
     // Precompute enemy pawn attack squares for backward pawn detection.
     // White pawns attack diagonally up-left and up-right;
@@ -278,6 +391,8 @@ fn pawn_structure_for_color(
         ((enemy_pawns & !Bitboard::A_FILE) << 7) | ((enemy_pawns & !Bitboard::H_FILE) << 9)
     };
 
+    let passed = passed_pawns(friendly_pawns, enemy_pawns, is_white);
+
     // --- Per-pawn evaluation ---
     let mut pawns = friendly_pawns;
     while !pawns.is_empty() {
@@ -285,23 +400,15 @@ fn pawn_structure_for_color(
         let file = (sq % 8) as usize;
         let rank = (sq / 8) as usize; // 0-based, rank 0 = rank 1
 
-        // A pawn is passed if there are no enemy pawns on the same or adjacent files
-        // ahead of it. "Ahead" means higher ranks for White, lower for Black.
-        let block_mask = FILE_MASKS[file] | ADJACENT_FILES[file];
-        let ahead_mask = if is_white {
-            block_mask & above_rank_mask(rank)
-        } else {
-            block_mask & below_rank_mask(rank)
-        };
-
         let is_isolated = (friendly_pawns & ADJACENT_FILES[file]).is_empty();
 
-        if (enemy_pawns & ahead_mask).is_empty() {
+        if Bitboard(1u64 << sq).overlaps(passed) {
             // Passed pawn -- bonus by rank from that side's perspective
             let bonus_rank = if is_white { rank } else { 7 - rank };
-            let mg = PASSED_PAWN_BONUS_MG[bonus_rank];
-            let eg = PASSED_PAWN_BONUS_EG[bonus_rank];
-            score += taper(mg, eg, phase);
+            score.add(
+                PASSED_PAWN_BONUS_MG[bonus_rank],
+                PASSED_PAWN_BONUS_EG[bonus_rank],
+            );
         }
 
         // --- Backward pawn ---
@@ -321,7 +428,7 @@ fn pawn_structure_for_color(
                 if stop_sq < 64 {
                     let stop_bb = Bitboard(1u64 << stop_sq);
                     if stop_bb.overlaps(enemy_pawn_attacks) {
-                        score -= taper(BACKWARD_PAWN_PENALTY_MG, BACKWARD_PAWN_PENALTY_EG, phase);
+                        score.add(-BACKWARD_PAWN_PENALTY_MG, -BACKWARD_PAWN_PENALTY_EG);
                     }
                 }
             }
@@ -340,7 +447,7 @@ fn pawn_structure_for_color(
         };
         let support_zone = ADJACENT_FILES[file] & (same_rank_mask | behind_one_rank);
         if !(friendly_pawns & support_zone).is_empty() {
-            score += taper(CONNECTED_PAWN_BONUS_MG, CONNECTED_PAWN_BONUS_EG, phase);
+            score.add(CONNECTED_PAWN_BONUS_MG, CONNECTED_PAWN_BONUS_EG);
         }
     }
 
@@ -348,20 +455,177 @@ fn pawn_structure_for_color(
     for (file_idx, &file_mask) in FILE_MASKS.iter().enumerate() {
         let count = (friendly_pawns & file_mask).count_ones();
         if count > 1 {
-            score -= (count - 1) as i16 * DOUBLED_PAWN_PENALTY;
+            let penalty = (count - 1) as i16 * DOUBLED_PAWN_PENALTY;
+            score.add(-penalty, -penalty);
         }
 
         // Isolated pawn: no friendly pawns on adjacent files
         if !(friendly_pawns & file_mask).is_empty()
             && (friendly_pawns & ADJACENT_FILES[file_idx]).is_empty()
         {
-            score -= ISOLATED_PAWN_PENALTY;
+            score.add(-ISOLATED_PAWN_PENALTY, -ISOLATED_PAWN_PENALTY);
         }
     }
 
     score
 }
 
+/// The pawns with no enemy pawns ahead of them on the same or adjacent files.
+/// "Ahead" means higher ranks for White, lower for Black.
+#[inline(always)]
+fn passed_pawns(friendly_pawns: Bitboard, enemy_pawns: Bitboard, is_white: bool) -> Bitboard {
+    // Fill from each enemy pawn back towards the friendly side, then widen
+    // to the adjacent files
+    let mut front = if is_white {
+        enemy_pawns >> 8
+    } else {
+        enemy_pawns << 8
+    };
+    for shift in [8, 16, 32] {
+        front |= if is_white {
+            front >> shift
+        } else {
+            front << shift
+        };
+    }
+    let blocked = front | ((front & !Bitboard::A_FILE) >> 1) | ((front & !Bitboard::H_FILE) << 1);
+    friendly_pawns & !blocked
+}
+
+/// Evaluates passed pawns against the kings, which the pawn hash table can't
+/// cache: in the endgame a passed pawn is worth more with its own king close
+/// and the enemy king far, and once the enemy has only king and pawns left, a
+/// passed pawn outside the enemy king's square (the rule of the square)
+/// promotes. Returns score from White's perspective.
+#[inline]
+fn passed_pawn_race_eval(
+    board: &Board,
+    white_pawns: Bitboard,
+    black_pawns: Bitboard,
+    phase: u8,
+) -> i16 {
+    let white_score = passed_pawn_race_for_color(board, white_pawns, black_pawns, true, phase);
+    let black_score = passed_pawn_race_for_color(board, black_pawns, white_pawns, false, phase);
+    white_score - black_score
+}
+
+#[inline]
+fn passed_pawn_race_for_color(
+    board: &Board,
+    friendly_pawns: Bitboard,
+    enemy_pawns: Bitboard,
+    is_white: bool,
+    phase: u8,
+) -> i16 {
+    let mut passed = passed_pawns(friendly_pawns, enemy_pawns, is_white);
+    let (color, opponent) = if is_white {
+        (Color::White, Color::Black)
+    } else {
+        (Color::Black, Color::White)
+    };
+    let own_king = board.pieces(color).locate(Piece::King);
+    let enemy_king = board.pieces(opponent).locate(Piece::King);
+    if passed.is_empty() || own_king.is_empty() || enemy_king.is_empty() {
+        return 0;
+    }
+    let own_king = own_king.to_square();
+    let enemy_king = enemy_king.to_square();
+    let enemy_has_only_pawns =
+        (board.pieces(opponent).occupied() & !enemy_pawns & !enemy_king.to_bitboard()).is_empty();
+    // The enemy king gets a move in first when it is the enemy's turn
+    let enemy_tempo = i16::from(board.turn() == opponent);
+    let occupied = board.occupied();
+
+    let mut proximity: i16 = 0;
+    let mut unstoppable = false;
+    let mut on_key_square = false;
+    while !passed.is_empty() {
+        let square = passed.pop_lsb_as_square();
+        let (file, rank) = (square.file(), square.rank());
+        let relative_rank = if is_white { rank } else { 7 - rank };
+        let (stop, promotion, path) = if is_white {
+            (
+                Square::new(square.index() + 8),
+                Square::from_rank_file(7, file),
+                FILE_MASKS[file as usize] & above_rank_mask(rank as usize),
+            )
+        } else {
+            (
+                Square::new(square.index() - 8),
+                Square::from_rank_file(0, file),
+                FILE_MASKS[file as usize] & below_rank_mask(rank as usize),
+            )
+        };
+
+        let scale = PASSED_PAWN_KING_PROXIMITY_RANK_SCALE[relative_rank as usize];
+        proximity += scale
+            * (PASSED_PAWN_ENEMY_KING_DISTANCE_WEIGHT * king_distance(enemy_king, stop).min(5)
+                - PASSED_PAWN_OWN_KING_DISTANCE_WEIGHT * king_distance(own_king, stop).min(5));
+
+        if enemy_has_only_pawns && king_on_key_square(own_king, enemy_king, square, is_white) {
+            on_key_square = true;
+        }
+
+        if enemy_has_only_pawns && !path.overlaps(occupied) {
+            // From its starting rank the pawn's first move is a double step
+            let moves_to_promote = if relative_rank == 1 {
+                5
+            } else {
+                7 - relative_rank as i16
+            };
+            if king_distance(enemy_king, promotion) > moves_to_promote + enemy_tempo {
+                unstoppable = true;
+            }
+        }
+    }
+
+    let unstoppable_bonus = if unstoppable {
+        UNSTOPPABLE_PASSER_BONUS
+    } else {
+        0
+    };
+    let key_square_bonus = if on_key_square && !unstoppable {
+        KING_ON_KEY_SQUARE_BONUS
+    } else {
+        0
+    };
+    taper(0, proximity, phase) + unstoppable_bonus + key_square_bonus
+}
+
+/// Whether the king stands on a key square of the pawn: with the king there,
+/// the pawn promotes against a lone king whoever moves. The key squares are
+/// on the pawn's and the adjacent files, two ranks ahead of a pawn on its
+/// 2nd to 4th rank and one or two ranks ahead of a pawn further up. A king
+/// three ranks ahead of a pawn on its 2nd to 4th rank counts too, as the pawn
+/// can step up to it. Rook
+/// pawns are left out, their key squares are different. A pawn the enemy
+/// king can take doesn't count.
+#[inline]
+fn king_on_key_square(king: Square, enemy_king: Square, pawn: Square, is_white: bool) -> bool {
+    let file = pawn.file();
+    if file == 0 || file == 7 || (king.file() as i16 - file as i16).abs() > 1 {
+        return false;
+    }
+    if king_distance(enemy_king, pawn) == 1 && king_distance(king, pawn) > 1 {
+        return false;
+    }
+    let (pawn_rank, king_rank) = if is_white {
+        (pawn.rank(), king.rank())
+    } else {
+        (7 - pawn.rank(), 7 - king.rank())
+    };
+    let (nearest, farthest) = if pawn_rank <= 3 { (2, 3) } else { (1, 2) };
+    (pawn_rank + nearest..=pawn_rank + farthest).contains(&king_rank)
+}
+
+/// The number of king moves from one square to the other.
+#[inline(always)]
+fn king_distance(from: Square, to: Square) -> i16 {
+    let ranks = (from.rank() as i16 - to.rank() as i16).abs();
+    let files = (from.file() as i16 - to.file() as i16).abs();
+    ranks.max(files)
+}
+
 /// Returns a mask of all squares strictly above the given rank (0-indexed).
 #[inline(always)]
 fn above_rank_mask(rank: usize) -> Bitboard {
@@ -383,7 +647,8 @@ fn below_rank_mask(rank: usize) -> Bitboard {
     }
 }
 
-/// Evaluates piece activity: bishop pair, rook on open/semi-open file, rook on 7th.
+/// Evaluates piece activity: bishop pair, bad bishops, knight outposts, rook on
+/// open/semi-open file, rook on 7th.
 /// Returns score from White's perspective.
 #[inline]
 fn piece_activity_eval(
@@ -426,10 +691,34 @@ fn piece_activity_for_color(
     let is_white = color == Color::White;
 
     // Bishop pair
-    if pieces.locate(Piece::Bishop).count_ones() >= 2 {
+    let bishops = pieces.locate(Piece::Bishop);
+    if has_bishop_pair(bishops) {
         bonus += taper(BISHOP_PAIR_BONUS_MG, BISHOP_PAIR_BONUS_EG, phase);
     }
 
+    // Bad bishops: friendly pawns that can't move off the bishop's color
+    let occupied = board.occupied();
+    let blocked_pawns = match color {
+        Color::White => ((friendly_pawns << 8) & occupied) >> 8,
+        Color::Black => ((friendly_pawns >> 8) & occupied) << 8,
+    };
+    let mut bad_bishop_pawns: i16 = 0;
+    let mut remaining_bishops = bishops;
+    while !remaining_bishops.is_empty() {
+        let sq = remaining_bishops.pop_lsb_as_square();
+        let bishop_color = if sq.overlaps(DARK_SQUARES) {
+            DARK_SQUARES
+        } else {
+            !DARK_SQUARES
+        };
+        bad_bishop_pawns += (blocked_pawns & bishop_color).count_ones() as i16;
+    }
+    bonus -= taper(
+        bad_bishop_pawns * BAD_BISHOP_PAWN_PENALTY_MG,
+        bad_bishop_pawns * BAD_BISHOP_PAWN_PENALTY_EG,
+        phase,
+    );
+
     // Knight outpost: knight on rank 4-6 (for white) / 3-5 (for black) where
     // no enemy pawn on adjacent files can attack it
     let mut knights = pieces.locate(Piece::Knight);
@@ -470,10 +759,7 @@ fn piece_activity_for_color(
     }
 
     // Rook bonuses
-    let seventh_rank = match color {
-        Color::White => Bitboard::RANK_7,
-        Color::Black => Bitboard::RANK_2,
-    };
+    let seventh_rank = seventh_rank_with_targets(board, color);
 
     let mut rooks = pieces.locate(Piece::Rook);
     while !rooks.is_empty() {
@@ -493,7 +779,7 @@ fn piece_activity_for_color(
             );
         }
 
-        // Rook on 7th rank
+        // Rook on 7th rank, where it has pawns to attack or the king to cut off
         let rook_bb = Bitboard(1u64 << sq);
         if rook_bb.overlaps(seventh_rank) {
             bonus += taper(ROOK_ON_SEVENTH_BONUS_MG, ROOK_ON_SEVENTH_BONUS_EG, phase);
@@ -606,54 +892,112 @@ fn king_safety_for_color(
     score
 }
 
-/// Evaluates piece mobility: count of pseudo-legal squares for each piece.
-/// Returns score from White's perspective.
+/// Evaluates piece mobility: the safe squares each piece attacks, and
+/// penalties for pieces with next to none. Returns score from White's
+/// perspective.
 #[inline]
 fn mobility_eval(board: &Board, targets: &Targets, phase: u8) -> i16 {
+    if !MOBILITY_ENABLED.load(Ordering::Relaxed) {
+        return 0;
+    }
     let white = mobility_for_color(board, targets, Color::White, phase);
     let black = mobility_for_color(board, targets, Color::Black, phase);
     white - black
 }
 
+/// Whether `bishops` make a bishop pair: one on each color of square, so two
+/// bishops of the same color after an underpromotion don't count.
+#[inline]
+pub(crate) fn has_bishop_pair(bishops: Bitboard) -> bool {
+    bishops.overlaps(DARK_SQUARES) && bishops.overlaps(!DARK_SQUARES)
+}
+
+/// `color`'s seventh rank if a rook there has something to do: enemy pawns
+/// to attack on it, or the enemy king to confine to the back rank. Empty
+/// otherwise.
+#[inline]
+pub(crate) fn seventh_rank_with_targets(board: &Board, color: Color) -> Bitboard {
+    let (seventh_rank, back_rank) = match color {
+        Color::White => (Bitboard::RANK_7, Bitboard::RANK_8),
+        Color::Black => (Bitboard::RANK_2, Bitboard::RANK_1),
+    };
+    let enemy = board.pieces(color.opposite());
+    if enemy.locate(Piece::Pawn).overlaps(seventh_rank)
+        || enemy.locate(Piece::King).overlaps(back_rank)
+    {
+        seventh_rank
+    } else {
+        Bitboard::EMPTY
+    }
+}
+
+/// Squares `color`'s pieces can safely move to: empty or holding an enemy
+/// piece, and not attacked by an enemy pawn. Attacks by the other enemy pieces
+/// are left out, as finding them costs more than the term is worth.
+#[inline]
+pub(crate) fn safe_squares(board: &Board, color: Color) -> Bitboard {
+    let friendly = board.pieces(color).occupied();
+    let enemy_pawn_attacks = generate_pawn_attack_targets_bitboard(board, color.opposite());
+    !(friendly | enemy_pawn_attacks)
+}
+
 #[inline]
 fn mobility_for_color(board: &Board, targets: &Targets, color: Color, phase: u8) -> i16 {
     let occupied = board.occupied();
-    let friendly = board.pieces(color).occupied();
-    let mobility_squares = !friendly;
+    let pieces = board.pieces(color);
+    let safe = safe_squares(board, color);
+    let enemy_half = match color {
+        Color::White => BLACK_HALF,
+        Color::Black => WHITE_HALF,
+    };
 
-    let mut score: i16 = 0;
+    let mut mg: i16 = 0;
+    let mut eg: i16 = 0;
+    let mut trapped: i16 = 0;
 
-    // Knights
-    let mut knights = board.pieces(color).locate(Piece::Knight);
-    while !knights.is_empty() {
-        let sq = knights.pop_lsb_as_square();
-        let attacks = targets.piece_attacks(sq, Piece::Knight) & mobility_squares;
-        let count = attacks.count_ones() as i16;
-        score += taper(
-            count * KNIGHT_MOBILITY_MG,
-            count * KNIGHT_MOBILITY_EG,
-            phase,
-        );
+    for (piece, weight_mg, weight_eg) in [
+        (Piece::Knight, KNIGHT_MOBILITY_MG, KNIGHT_MOBILITY_EG),
+        (Piece::Bishop, BISHOP_MOBILITY_MG, BISHOP_MOBILITY_EG),
+        (Piece::Rook, ROOK_MOBILITY_MG, ROOK_MOBILITY_EG),
+        (Piece::Queen, QUEEN_MOBILITY_MG, QUEEN_MOBILITY_EG),
+    ] {
+        let mut squares = pieces.locate(piece);
+        while !squares.is_empty() {
+            let sq = squares.pop_lsb_as_square();
+            let attacks = match piece {
+                Piece::Knight => targets.piece_attacks(sq, Piece::Knight),
+                Piece::Bishop => targets.bishop_attacks(sq, occupied),
+                Piece::Rook => targets.rook_attacks(sq, occupied),
+                _ => targets.bishop_attacks(sq, occupied) | targets.rook_attacks(sq, occupied),
+            };
+            let count = (attacks & safe).count_ones();
+            mg += count as i16 * weight_mg;
+            eg += count as i16 * weight_eg;
+
+            // A piece that went deep and can't get out is as good as lost
+            if piece != Piece::Queen
+                && count <= TRAPPED_PIECE_MAX_SQUARES
+                && sq.overlaps(enemy_half)
+            {
+                trapped += 1;
+            }
+        }
     }
 
-    // Bishops
-    let mut bishops = board.pieces(color).locate(Piece::Bishop);
-    while !bishops.is_empty() {
-        let sq = bishops.pop_lsb_as_square();
-        let attacks = targets.bishop_attacks(sq, occupied) & mobility_squares;
-        let count = attacks.count_ones() as i16;
-        score += taper(
-            count * BISHOP_MOBILITY_MG,
-            count * BISHOP_MOBILITY_EG,
-            phase,
-        );
-    }
+    mg -= trapped * TRAPPED_PIECE_PENALTY_MG;
+    eg -= trapped * TRAPPED_PIECE_PENALTY_EG;
 
-    // Rooks and queens omitted: rook mobility is partially captured by open/semi-open
-    // file bonuses in piece_activity, and queens already have high baseline mobility via
-    // PSTs. Limiting mobility to knights+bishops keeps overhead low.
+    taper(mg, eg, phase)
+}
 
-    score
+/// Whether the evaluation scores mobility (on by default). Turning it off
+/// shows what the term costs in benchmarks and buys in matches.
+pub fn set_mobility_enabled(enabled: bool) {
+    MOBILITY_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn mobility_enabled() -> bool {
+    MOBILITY_ENABLED.load(Ordering::Relaxed)
 }
 
 /// Determines if the position is an endgame.
@@ -682,7 +1026,9 @@ mod tests {
     use super::*;
     use crate::{
         board::{castle_rights::CastleRights, Board},
+        chess_move::{chess_move::ChessMove, standard::StandardChessMove},
         chess_position,
+        input_handler::fen::parse_fen,
     };
     use common::bitboard::*;
 
@@ -737,6 +1083,7 @@ mod tests {
     fn test_game_ending_stalemate() {
         // Black king on A8, White queen on B6 -- Black has no legal moves, not in check
         let mut board = chess_position! {
+            turn = Color::Black, castling = "-";
             k.......
             ........
             .Q......
@@ -746,8 +1093,6 @@ mod tests {
             ........
             ....K...
         };
-        board.set_turn(Color::Black);
-        board.lose_castle_rights(CastleRights::all());
 
         let ending = game_ending(&mut board, &MoveGenerator::default(), Color::Black, &[]);
         assert!(matches!(ending, Some(GameEnding::Stalemate)));
@@ -757,6 +1102,7 @@ mod tests {
     fn test_game_ending_checkmate() {
         // Black king on H8, White queen on G7 delivers check, White king on F7 supports
         let mut board = chess_position! {
+            turn = Color::Black, castling = "-";
             .......k
             .....KQ.
             ........
@@ -766,13 +1112,93 @@ mod tests {
             ........
             ........
         };
-        board.set_turn(Color::Black);
-        board.lose_castle_rights(CastleRights::all());
 
         let ending = game_ending(&mut board, &MoveGenerator::default(), Color::Black, &[]);
         assert!(matches!(ending, Some(GameEnding::Checkmate)));
     }
 
+    #[test]
+    fn test_insufficient_material_is_a_draw() {
+        let move_generator = MoveGenerator::default();
+        let ending = |mut board: Board| {
+            board.lose_castle_rights(CastleRights::all());
+            game_ending(&mut board, &move_generator, Color::White, &[])
+        };
+        let draws = [
+            chess_position! {
+                ....k...
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ....K...
+            },
+            chess_position! {
+                ....k...
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ....KN..
+            },
+            // Both bishops on dark squares
+            chess_position! {
+                ....kb..
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ....K.B.
+            },
+        ];
+        for board in draws {
+            assert!(matches!(ending(board), Some(GameEnding::Draw)));
+        }
+
+        let playable = [
+            // Bishops on squares of both colors
+            chess_position! {
+                ....k.b.
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ....K.B.
+            },
+            chess_position! {
+                ....k...
+                ........
+                ........
+                ........
+                ........
+                ........
+                ........
+                ....KNN.
+            },
+            chess_position! {
+                ....k...
+                ........
+                ........
+                ........
+                ........
+                ........
+                P.......
+                ....K...
+            },
+        ];
+        for board in playable {
+            assert!(ending(board).is_none());
+        }
+    }
+
     #[test]
     fn test_is_endgame_one_minor_piece() {
         let mut board = chess_position! {
@@ -835,7 +1261,7 @@ mod tests {
         let white_pawns = board.pieces(Color::White).locate(Piece::Pawn);
         let black_pawns = board.pieces(Color::Black).locate(Piece::Pawn);
         // Should have a positive pawn structure score (passed pawn for White)
-        let score = pawn_structure_eval(white_pawns, black_pawns, phase);
+        let score = pawn_structure_eval(white_pawns, black_pawns).taper(phase);
         assert!(
             score > 0,
             "Expected positive score for passed pawn, got {}",
@@ -859,7 +1285,7 @@ mod tests {
         let phase = game_phase(&board);
         let white_pawns = board.pieces(Color::White).locate(Piece::Pawn);
         let black_pawns = board.pieces(Color::Black).locate(Piece::Pawn);
-        let doubled_score = pawn_structure_eval(white_pawns, black_pawns, phase);
+        let doubled_score = pawn_structure_eval(white_pawns, black_pawns).taper(phase);
 
         // Compare: single pawn on e5 (no doubled penalty)
         let single = chess_position! {
@@ -874,7 +1300,7 @@ mod tests {
         };
         let single_wp = single.pieces(Color::White).locate(Piece::Pawn);
         let single_bp = single.pieces(Color::Black).locate(Piece::Pawn);
-        let single_score = pawn_structure_eval(single_wp, single_bp, phase);
+        let single_score = pawn_structure_eval(single_wp, single_bp).taper(phase);
 
         // Doubled should score less than single due to the penalty
         assert!(
@@ -901,7 +1327,7 @@ mod tests {
         let phase = game_phase(&isolated);
         let iso_wp = isolated.pieces(Color::White).locate(Piece::Pawn);
         let iso_bp = isolated.pieces(Color::Black).locate(Piece::Pawn);
-        let iso_score = pawn_structure_eval(iso_wp, iso_bp, phase);
+        let iso_score = pawn_structure_eval(iso_wp, iso_bp).taper(phase);
 
         // Connected pawn on a5 with support on b4
         let connected = chess_position! {
@@ -916,7 +1342,7 @@ mod tests {
         };
         let conn_wp = connected.pieces(Color::White).locate(Piece::Pawn);
         let conn_bp = connected.pieces(Color::Black).locate(Piece::Pawn);
-        let conn_score = pawn_structure_eval(conn_wp, conn_bp, phase);
+        let conn_score = pawn_structure_eval(conn_wp, conn_bp).taper(phase);
 
         // Connected pawns should score better per pawn than isolated
         assert!(
@@ -952,38 +1378,79 @@ mod tests {
         );
     }
 
+    /// `piece_activity_eval` of `board` with its own pawns.
+    fn activity(board: &Board) -> i16 {
+        let white_pawns = board.pieces(Color::White).locate(Piece::Pawn);
+        let black_pawns = board.pieces(Color::Black).locate(Piece::Pawn);
+        let phase = game_phase(board);
+        piece_activity_eval(
+            board,
+            white_pawns | black_pawns,
+            white_pawns,
+            black_pawns,
+            phase,
+        )
+    }
+
     #[test]
-    fn test_player_is_in_check() {
-        let mut move_generator = MoveGenerator::default();
-        let mut board = chess_position! {
-            .......k
-            .....ppp
-            ........
-            ...b....
-            ........
-            ........
-            .Q......
-            K......q
+    fn test_rook_open_and_semi_open_file_bonus() {
+        // The a-file is closed, the b-file semi-open (only a black pawn) and
+        // the d-file open
+        let rook_on = |first_rank: &str| {
+            let fen = format!("6k1/pp6/8/8/8/8/P7/{}K1 w - - 0 1", first_rank);
+            activity(&fen.parse().unwrap())
         };
-        board.lose_castle_rights(CastleRights::all());
-        board.set_turn(Color::White);
+        let closed = rook_on("R5");
+        let semi_open = rook_on("1R4");
+        let open = rook_on("3R2");
+        assert!(
+            closed < semi_open && semi_open < open,
+            "closed {}, semi-open {}, open {}",
+            closed,
+            semi_open,
+            open
+        );
+    }
 
-        assert!(player_is_in_check(
-            &board,
-            &mut move_generator,
-            Color::White
-        ));
-        assert!(!player_is_in_check(
-            &board,
-            &mut move_generator,
-            Color::Black
-        ));
+    #[test]
+    fn test_rook_on_seventh_needs_pawns_or_king_to_target() {
+        // The same rook on the 7th rank, with and without something to attack
+        let with_pawns: Board = "8/1R3ppp/6k1/8/8/8/8/6K1 w - - 0 1".parse().unwrap();
+        let with_king: Board = "6k1/1R6/8/8/8/8/8/6K1 w - - 0 1".parse().unwrap();
+        let without: Board = "8/1R6/6k1/8/8/8/8/6K1 w - - 0 1".parse().unwrap();
+        assert_eq!(
+            seventh_rank_with_targets(&with_pawns, Color::White),
+            Bitboard::RANK_7
+        );
+        assert_eq!(
+            seventh_rank_with_targets(&with_king, Color::White),
+            Bitboard::RANK_7
+        );
+        assert_eq!(
+            seventh_rank_with_targets(&without, Color::White),
+            Bitboard::EMPTY
+        );
+        assert!(activity(&with_king) > activity(&without));
+    }
+
+    #[test]
+    fn test_bishop_pair_needs_both_colors() {
+        // After an underpromotion, two bishops on light squares are no pair
+        let pair = C1.to_bitboard() | F1.to_bitboard();
+        let same_color = F1.to_bitboard() | H3.to_bitboard();
+        assert!(has_bishop_pair(pair));
+        assert!(!has_bishop_pair(same_color));
+        assert!(!has_bishop_pair(C1.to_bitboard()));
+
+        let board: Board = "4k3/8/8/8/8/7B/8/4KB2 w - - 0 1".parse().unwrap();
+        assert_eq!(activity(&board), 0);
     }
 
     #[test]
     fn test_player_is_in_checkmate() {
-        let mut move_generator = MoveGenerator::default();
+        let move_generator = MoveGenerator::default();
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             .......k
             ........
             ........
@@ -993,17 +1460,15 @@ mod tests {
             PPP.....
             .K.....r
         };
-        board.lose_castle_rights(CastleRights::all());
-        board.set_turn(Color::White);
 
         assert!(player_is_in_checkmate(
             &mut board,
-            &mut move_generator,
+            &move_generator,
             Color::White
         ));
         assert!(!player_is_in_checkmate(
             &mut board,
-            &mut move_generator,
+            &move_generator,
             Color::Black
         ));
     }
@@ -1050,7 +1515,7 @@ mod tests {
         let phase = game_phase(&board);
         let white_pawns = board.pieces(Color::White).locate(Piece::Pawn);
         let black_pawns = board.pieces(Color::Black).locate(Piece::Pawn);
-        let score = pawn_structure_eval(white_pawns, black_pawns, phase);
+        let score = pawn_structure_eval(white_pawns, black_pawns).taper(phase);
         // Score from White's perspective, so Black's passed pawn should be negative
         assert!(
             score < 0,
@@ -1059,6 +1524,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_passed_pawns() {
+        // The pawns on d4 and e5 hold each other; a2 and h7 are passed
+        let white_pawns = A2.to_bitboard() | D4.to_bitboard();
+        let black_pawns = E5.to_bitboard() | H7.to_bitboard();
+        assert_eq!(
+            passed_pawns(white_pawns, black_pawns, true),
+            A2.to_bitboard()
+        );
+        assert_eq!(
+            passed_pawns(black_pawns, white_pawns, false),
+            H7.to_bitboard()
+        );
+    }
+
+    fn passed_pawn_race(fen: &str) -> i16 {
+        let board: Board = fen.parse().unwrap();
+        let white_pawns = board.pieces(Color::White).locate(Piece::Pawn);
+        let black_pawns = board.pieces(Color::Black).locate(Piece::Pawn);
+        passed_pawn_race_eval(&board, white_pawns, black_pawns, game_phase(&board))
+    }
+
+    #[test]
+    fn test_unstoppable_passer_follows_the_rule_of_the_square() {
+        // g4 in one move and the king on a6 is outside its square, unless
+        // Black moves first
+        assert_eq!(
+            passed_pawn_race("8/8/k7/8/8/8/6P1/6K1 w - - 0 1"),
+            UNSTOPPABLE_PASSER_BONUS
+        );
+        assert_eq!(passed_pawn_race("8/8/k7/8/8/8/6P1/6K1 b - - 0 1"), 0);
+        assert_eq!(passed_pawn_race("8/8/1k6/8/8/8/6P1/6K1 w - - 0 1"), 0);
+        // A knight can still stop it, and so does a blocked path
+        assert_eq!(passed_pawn_race("8/8/k7/8/8/8/6P1/n5K1 w - - 0 1"), 0);
+        assert_eq!(passed_pawn_race("8/8/k7/8/8/6K1/6P1/8 w - - 0 1"), 0);
+        // Black's runner counts against White
+        assert_eq!(
+            passed_pawn_race("6k1/1p6/8/8/8/8/8/7K b - - 0 1"),
+            -UNSTOPPABLE_PASSER_BONUS
+        );
+    }
+
+    #[test]
+    fn test_king_on_key_square() {
+        // The key squares of e2 are d4-f4, and d5-f5 as the pawn can step up
+        assert!(king_on_key_square(D4, H8, E2, true));
+        assert!(king_on_key_square(F5, H8, E2, true));
+        assert!(!king_on_key_square(E3, H8, E2, true));
+        assert!(!king_on_key_square(E6, H8, E2, true));
+        // One or two ranks ahead of a pawn on its 5th rank, seen from Black
+        assert!(king_on_key_square(D3, A8, E4, false));
+        assert!(king_on_key_square(F2, A8, E4, false));
+        assert!(!king_on_key_square(E4, A8, E5, false));
+        // Not with the pawn hanging, nor for a rook pawn
+        assert!(!king_on_key_square(D7, F5, E5, true));
+        assert!(!king_on_key_square(B6, H8, A5, true));
+    }
+
+    #[test]
+    fn test_passed_pawn_wants_its_own_king_close_and_the_enemy_king_far() {
+        let escorted = passed_pawn_race("7k/8/8/4P3/3K4/8/8/8 w - - 0 1");
+        let alone = passed_pawn_race("7k/8/8/4P3/8/8/8/K7 w - - 0 1");
+        let blockaded = passed_pawn_race("8/8/4k3/4P3/3K4/8/8/8 w - - 0 1");
+        assert!(escorted > alone, "{} <= {}", escorted, alone);
+        assert!(escorted > blockaded, "{} <= {}", escorted, blockaded);
+        assert!(alone > 0 && blockaded < 0, "{} {}", alone, blockaded);
+    }
+
+    #[test]
+    fn test_lone_king_is_driven_to_the_corner() {
+        let score = |fen: &str| lone_king_eval(&fen.parse().unwrap()).unwrap();
+        // The lone king three squares from the other king each time
+        let centre = score("8/8/8/8/4k3/8/Q7/4K3 w - - 0 1");
+        let edge = score("8/8/8/8/7k/8/Q7/4K3 w - - 0 1");
+        let corner = score("8/8/8/8/8/8/Q7/4K2k w - - 0 1");
+        assert!(
+            centre < edge && edge < corner,
+            "{} {} {}",
+            centre,
+            edge,
+            corner
+        );
+        // Closer kings score more, and the sign follows the stronger side
+        let close = score("8/8/8/8/8/8/Q7/5K1k w - - 0 1");
+        assert!(close > corner, "{} <= {}", close, corner);
+        assert_eq!(score("4k2K/q7/8/8/8/8/8/8 w - - 0 1"), -corner);
+    }
+
+    #[test]
+    fn test_lone_king_eval_needs_mating_material() {
+        let lone_king = |fen: &str| lone_king_eval(&fen.parse().unwrap());
+        assert!(lone_king("8/8/8/4k3/8/8/8/2B1KB2 w - - 0 1").is_some());
+        assert!(lone_king("8/8/8/4k3/8/8/8/2R1K3 b - - 0 1").is_some());
+        // Bishops on one color, bishop and knight, or pawns on the board
+        assert!(lone_king("8/8/8/4k3/8/8/8/3BKB2 w - - 0 1").is_none());
+        assert!(lone_king("8/8/8/4k3/8/8/8/2B1KN2 w - - 0 1").is_none());
+        assert!(lone_king("8/8/8/4k3/8/8/4P3/3QK3 w - - 0 1").is_none());
+        assert!(lone_king("8/8/8/4k3/4p3/8/8/3QK3 w - - 0 1").is_none());
+    }
+
     #[test]
     fn test_king_safety_pawn_shield() {
         // Castled king with pawn shield should score better than exposed king
@@ -1324,6 +1889,31 @@ mod tests {
 
     // === Backward Pawn Tests ===
 
+    #[test]
+    fn test_pawn_hash_follows_moves_and_keys_the_cache() {
+        let mut board = Board::default();
+        let start_hash = board.current_pawn_hash();
+
+        let knight_move = ChessMove::Standard(StandardChessMove::new(G1, F3, None));
+        knight_move.apply(&mut board).unwrap();
+        assert_eq!(board.current_pawn_hash(), start_hash);
+        knight_move.undo(&mut board).unwrap();
+
+        let pawn_move = ChessMove::Standard(StandardChessMove::new(E2, E4, None));
+        pawn_move.apply(&mut board).unwrap();
+        board.toggle_turn();
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        let parsed = parse_fen(fen).unwrap();
+        assert_eq!(board.current_pawn_hash(), parsed.current_pawn_hash());
+        assert_ne!(board.current_pawn_hash(), start_hash);
+        // The cached structure scores the same as a fresh one
+        assert_eq!(board_material_score(&board), board_material_score(&parsed));
+
+        board.toggle_turn();
+        pawn_move.undo(&mut board).unwrap();
+        assert_eq!(board.current_pawn_hash(), start_hash);
+    }
+
     #[test]
     fn test_backward_pawn_penalty() {
         // White pawn on d3 is backward: no friendly pawns on c/e behind,
@@ -1352,11 +1942,11 @@ mod tests {
         let phase = 24u8;
         let bw_wp = backward.pieces(Color::White).locate(Piece::Pawn);
         let bw_bp = backward.pieces(Color::Black).locate(Piece::Pawn);
-        let bw_score = pawn_structure_for_color(bw_wp, bw_bp, phase, true);
+        let bw_score = pawn_structure_for_color(bw_wp, bw_bp, true).taper(phase);
 
         let sp_wp = supported.pieces(Color::White).locate(Piece::Pawn);
         let sp_bp = supported.pieces(Color::Black).locate(Piece::Pawn);
-        let sp_score_white = pawn_structure_for_color(sp_wp, sp_bp, phase, true);
+        let sp_score_white = pawn_structure_for_color(sp_wp, sp_bp, true).taper(phase);
 
         // Backward pawn should score less (or equal if support helps via connected bonus)
         // The backward pawn has penalty that the supported pawn doesn't
@@ -1385,7 +1975,7 @@ mod tests {
         let phase = 24u8;
         let wp = board.pieces(Color::White).locate(Piece::Pawn);
         let bp = board.pieces(Color::Black).locate(Piece::Pawn);
-        let score = pawn_structure_for_color(wp, bp, phase, true);
+        let score = pawn_structure_for_color(wp, bp, true).taper(phase);
         // Isolated pawn gets ISOLATED_PAWN_PENALTY but should NOT get BACKWARD_PAWN_PENALTY
         // The score should reflect isolated penalty only (plus any passed pawn bonus/lack thereof)
         // Just verify it runs without double-penalizing -- the isolated check in backward
@@ -1427,8 +2017,8 @@ mod tests {
         let conn_wp = connected.pieces(Color::White).locate(Piece::Pawn);
         let disc_wp = disconnected.pieces(Color::White).locate(Piece::Pawn);
         let bp = Bitboard::EMPTY;
-        let conn_score = pawn_structure_for_color(conn_wp, bp, phase, true);
-        let disc_score = pawn_structure_for_color(disc_wp, bp, phase, true);
+        let conn_score = pawn_structure_for_color(conn_wp, bp, true).taper(phase);
+        let disc_score = pawn_structure_for_color(disc_wp, bp, true).taper(phase);
         assert!(
             conn_score > disc_score,
             "Connected pawns ({}) should score higher than disconnected ({})",
@@ -1453,7 +2043,7 @@ mod tests {
         let phase = 24u8;
         let wp = board.pieces(Color::White).locate(Piece::Pawn);
         let bp = Bitboard::EMPTY;
-        let score = pawn_structure_for_color(wp, bp, phase, true);
+        let score = pawn_structure_for_color(wp, bp, true).taper(phase);
         // Both pawns should get connected bonus
         // Each pawn sees the other on adjacent file at same/behind rank
         assert!(
@@ -1537,6 +2127,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trapped_bishop_penalty() {
+        // Ba7 can only go to b8: b6 is defended by the c7 pawn
+        let board = chess_position! {
+            ....k...
+            B.p.....
+            .p......
+            ........
+            ........
+            ........
+            ........
+            ....K...
+        };
+        let targets = Targets::default();
+        let score = mobility_for_color(&board, &targets, Color::White, MAX_PHASE);
+        assert_eq!(score, BISHOP_MOBILITY_MG - TRAPPED_PIECE_PENALTY_MG);
+    }
+
+    #[test]
+    fn test_bad_bishop_penalty_for_blocked_pawns_on_its_color() {
+        // The d4 pawn is stuck on a dark square, like the c1 bishop
+        let board = chess_position! {
+            .......k
+            ........
+            ........
+            ...p....
+            ...P....
+            ........
+            ........
+            ..B....K
+        };
+        let targets = Targets::default();
+        let mobility = mobility_for_color(&board, &targets, Color::White, MAX_PHASE);
+        assert_eq!(mobility, 7 * BISHOP_MOBILITY_MG);
+        // Scored with the pieces' activity, so it counts with mobility off too
+        let pawns = board.pieces(Color::White).locate(Piece::Pawn);
+        let enemy_pawns = board.pieces(Color::Black).locate(Piece::Pawn);
+        let score = piece_activity_for_color(
+            &board,
+            Color::White,
+            pawns | enemy_pawns,
+            pawns,
+            enemy_pawns,
+            MAX_PHASE,
+        );
+        assert_eq!(score, -BAD_BISHOP_PAWN_PENALTY_MG);
+    }
+
     #[test]
     fn test_mobility_symmetry_starting_position() {
         // Starting position should have ~0 mobility difference
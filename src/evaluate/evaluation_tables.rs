@@ -257,11 +257,54 @@ pub const ADJACENT_FILES: [Bitboard; 8] = [
     Bitboard::G_FILE,
 ];
 
+/// The dark squares, a1 among them.
+pub const DARK_SQUARES: Bitboard = Bitboard(0xAA55_AA55_AA55_AA55);
+
+/// Each side's half of the board is the other side's enemy half.
+pub const WHITE_HALF: Bitboard = Bitboard(0x0000_0000_FFFF_FFFF);
+pub const BLACK_HALF: Bitboard = Bitboard(0xFFFF_FFFF_0000_0000);
+
 /// Passed pawn bonus by rank (from White's perspective, index 0 = rank 1).
 /// Rank 1 and rank 8 are 0 (pawns can't be on rank 1, rank 8 = promotion).
 pub const PASSED_PAWN_BONUS_MG: [i16; 8] = [0, 5, 10, 20, 35, 50, 80, 0];
 pub const PASSED_PAWN_BONUS_EG: [i16; 8] = [0, 10, 15, 30, 50, 70, 100, 0];
 
+/// Passed pawn king proximity (endgame only): the bonus is the enemy king's
+/// distance to the square in front of the pawn times the first weight, less
+/// the own king's times the second, scaled by the pawn's rank from its side's
+/// perspective. Distances count at most 5 squares.
+pub const PASSED_PAWN_KING_PROXIMITY_RANK_SCALE: [i16; 8] = [0, 0, 0, 1, 2, 4, 6, 0];
+pub const PASSED_PAWN_ENEMY_KING_DISTANCE_WEIGHT: i16 = 4;
+pub const PASSED_PAWN_OWN_KING_DISTANCE_WEIGHT: i16 = 2;
+
+/// A passed pawn the enemy king can't catch, against only king and pawns: it
+/// will promote, so it is worth most of a queen.
+pub const UNSTOPPABLE_PASSER_BONUS: i16 = 700;
+
+/// A king on a key square of its passed pawn, against only king and pawns:
+/// the pawn promotes with the king's help, so keep the king there rather
+/// than run the pawn ahead of it.
+pub const KING_ON_KEY_SQUARE_BONUS: i16 = 300;
+
+/// Mating a lone king: bonus for the stronger side by the square of the lone
+/// king, growing towards the edges and most in the corners, where it can be
+/// mated.
+#[rustfmt::skip]
+pub const LONE_KING_EDGE_BONUS: [i16; 64] = [
+    100, 90, 80, 70, 70, 80, 90,100,
+     90, 70, 60, 50, 50, 60, 70, 90,
+     80, 60, 40, 30, 30, 40, 60, 80,
+     70, 50, 30, 20, 20, 30, 50, 70,
+     70, 50, 30, 20, 20, 30, 50, 70,
+     80, 60, 40, 30, 30, 40, 60, 80,
+     90, 70, 60, 50, 50, 60, 70, 90,
+    100, 90, 80, 70, 70, 80, 90,100
+];
+
+/// Mating a lone king: bonus for the stronger side by the distance between
+/// the kings, as its king has to help drive the lone king back.
+pub const LONE_KING_DISTANCE_BONUS: [i16; 8] = [0, 0, 100, 80, 60, 40, 20, 10];
+
 /// Penalty per extra pawn on the same file (doubled pawns).
 pub const DOUBLED_PAWN_PENALTY: i16 = 10;
 
@@ -313,11 +356,25 @@ pub const BACKWARD_PAWN_PENALTY_EG: i16 = 10;
 pub const CONNECTED_PAWN_BONUS_MG: i16 = 4;
 pub const CONNECTED_PAWN_BONUS_EG: i16 = 6;
 
-// --- Piece mobility bonuses (per available square) ---
-// Only knight and bishop mobility are computed; rook/queen mobility is too
-// expensive per-node relative to the marginal benefit (PSTs already capture it).
+// --- Piece mobility bonuses (per safe square) ---
+// A safe square is one the piece attacks that holds no friendly piece and isn't
+// attacked by an enemy pawn.
 
 pub const KNIGHT_MOBILITY_MG: i16 = 4;
 pub const KNIGHT_MOBILITY_EG: i16 = 4;
 pub const BISHOP_MOBILITY_MG: i16 = 3;
 pub const BISHOP_MOBILITY_EG: i16 = 3;
+pub const ROOK_MOBILITY_MG: i16 = 2;
+pub const ROOK_MOBILITY_EG: i16 = 4;
+pub const QUEEN_MOBILITY_MG: i16 = 1;
+pub const QUEEN_MOBILITY_EG: i16 = 2;
+
+/// Penalty for a knight, bishop or rook in the enemy half with at most
+/// `TRAPPED_PIECE_MAX_SQUARES` safe squares, like a bishop shut in on a7 by b6.
+pub const TRAPPED_PIECE_PENALTY_MG: i16 = 40;
+pub const TRAPPED_PIECE_PENALTY_EG: i16 = 30;
+pub const TRAPPED_PIECE_MAX_SQUARES: u32 = 1;
+
+/// Penalty per friendly pawn that is blocked on a square of the bishop's color.
+pub const BAD_BISHOP_PAWN_PENALTY_MG: i16 = 3;
+pub const BAD_BISHOP_PAWN_PENALTY_EG: i16 = 5;
@@ -2,8 +2,10 @@
 
 pub mod evaluation;
 pub mod evaluation_tables;
+mod pawn_hash_table;
+pub mod tuning;
 
 pub use evaluation::{
-    board_material_score, current_player_is_in_check, game_ending, is_endgame, player_is_in_check,
-    player_is_in_checkmate, score, GameEnding,
+    board_material_score, game_ending, has_insufficient_material, is_endgame, mobility_enabled,
+    player_is_in_checkmate, score, set_mobility_enabled, GameEnding,
 };
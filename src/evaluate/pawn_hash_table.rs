@@ -0,0 +1,97 @@
+//! Pawn structure cache, keyed by the board's pawn-only Zobrist hash.
+//!
+//! Pawns move rarely compared to the other pieces, so most positions in a
+//! search share their pawn structure with many others. The structure terms
+//! (passed, doubled, isolated, backward and connected pawns) depend on the
+//! pawns alone, so they are computed once per structure and looked up after.
+//! Entries keep the midgame and endgame scores apart, since the phase that
+//! tapers them depends on the other pieces.
+
+use std::cell::RefCell;
+
+use thread_local::ThreadLocal;
+
+use super::evaluation::PawnScore;
+
+/// Entries per thread: 16 bytes each, so 256KB.
+const PAWN_HASH_ENTRIES: usize = 1 << 14;
+
+static PAWN_HASH_TABLE: ThreadLocal<RefCell<PawnHashTable>> = ThreadLocal::new();
+
+/// The pawn structure score for `pawn_hash`, computed by `compute` unless
+/// this thread has it cached.
+pub(crate) fn probe_or_compute(pawn_hash: u64, compute: impl FnOnce() -> PawnScore) -> PawnScore {
+    let mut table = PAWN_HASH_TABLE
+        .get_or(|| RefCell::new(PawnHashTable::new()))
+        .borrow_mut();
+    if let Some(score) = table.probe(pawn_hash) {
+        return score;
+    }
+    let score = compute();
+    table.store(pawn_hash, score);
+    score
+}
+
+#[derive(Clone, Copy, Default)]
+struct PawnHashEntry {
+    key: u64,
+    score: PawnScore,
+}
+
+/// Direct-mapped table of pawn structure scores, always replacing.
+///
+/// Empty entries have key 0, which is also the key of a board without pawns,
+/// whose pawn structure scores 0 like an empty entry.
+struct PawnHashTable {
+    entries: Vec<PawnHashEntry>,
+}
+
+impl PawnHashTable {
+    fn new() -> Self {
+        Self {
+            entries: vec![PawnHashEntry::default(); PAWN_HASH_ENTRIES],
+        }
+    }
+
+    #[inline]
+    fn index(pawn_hash: u64) -> usize {
+        pawn_hash as usize & (PAWN_HASH_ENTRIES - 1)
+    }
+
+    #[inline]
+    fn probe(&self, pawn_hash: u64) -> Option<PawnScore> {
+        let entry = &self.entries[Self::index(pawn_hash)];
+        (entry.key == pawn_hash).then_some(entry.score)
+    }
+
+    #[inline]
+    fn store(&mut self, pawn_hash: u64, score: PawnScore) {
+        self.entries[Self::index(pawn_hash)] = PawnHashEntry {
+            key: pawn_hash,
+            score,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structures_are_computed_once_per_hash() {
+        let score = PawnScore { mg: 12, eg: -7 };
+        let hash = 0x0123_4567_89ab_cdef;
+        assert_eq!(probe_or_compute(hash, || score), score);
+        assert_eq!(
+            probe_or_compute(hash, || unreachable!("score should be cached")),
+            score
+        );
+        // Another structure in the same slot replaces it
+        let other = hash ^ (1 << 40);
+        assert_eq!(
+            probe_or_compute(other, PawnScore::default),
+            PawnScore::default()
+        );
+        assert_eq!(probe_or_compute(hash, || score), score);
+    }
+}
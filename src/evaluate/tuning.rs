@@ -0,0 +1,521 @@
+//! Texel tuning of the scalar evaluation weights.
+//!
+//! Each training position is labelled with its game result. The tuner minimizes
+//! the mean squared error between that result and a sigmoid of the static
+//! evaluation, adjusting one weight at a time by ±1 (classic Texel local search).
+//!
+//! The tuned weights are linear terms of the evaluation, so a position's score
+//! under new weights is the current evaluation plus the weight deltas times the
+//! position's feature counts. Features are extracted once up front, which keeps
+//! each pass over the data cheap. Piece-square tables and non-linear terms (king
+//! attack units, passed pawn ranks) are left alone.
+//!
+//! State is saved as a small TOML file after every iteration so a run can be
+//! interrupted and resumed. Copy the final values into `evaluation_tables.rs`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use common::bitboard::bitboard::Bitboard;
+use thiserror::Error;
+
+use crate::board::{color::Color, piece::Piece, Board};
+use crate::move_generator::targets::Targets;
+
+use super::evaluation::{
+    board_material_score, game_phase, has_bishop_pair, safe_squares, seventh_rank_with_targets,
+};
+use super::evaluation_tables::{
+    ADJACENT_FILES, BISHOP_MOBILITY_EG, BISHOP_MOBILITY_MG, BISHOP_PAIR_BONUS_EG,
+    BISHOP_PAIR_BONUS_MG, DOUBLED_PAWN_PENALTY, FILE_MASKS, ISOLATED_PAWN_PENALTY,
+    KNIGHT_MOBILITY_EG, KNIGHT_MOBILITY_MG, MATERIAL_VALUES, MAX_PHASE, QUEEN_MOBILITY_EG,
+    QUEEN_MOBILITY_MG, ROOK_MOBILITY_EG, ROOK_MOBILITY_MG, ROOK_ON_SEVENTH_BONUS_EG,
+    ROOK_ON_SEVENTH_BONUS_MG, ROOK_OPEN_FILE_BONUS_EG, ROOK_OPEN_FILE_BONUS_MG,
+    ROOK_SEMI_OPEN_FILE_BONUS_EG, ROOK_SEMI_OPEN_FILE_BONUS_MG,
+};
+
+/// Tunable weights and their current values in `evaluation_tables.rs`.
+/// The pawn value is the fixed anchor of the scale and is not tuned.
+pub const TUNABLE_PARAMS: &[(&str, i16)] = &[
+    ("knight_value", MATERIAL_VALUES[1]),
+    ("bishop_value", MATERIAL_VALUES[2]),
+    ("rook_value", MATERIAL_VALUES[3]),
+    ("queen_value", MATERIAL_VALUES[4]),
+    ("bishop_pair_bonus_mg", BISHOP_PAIR_BONUS_MG),
+    ("bishop_pair_bonus_eg", BISHOP_PAIR_BONUS_EG),
+    ("doubled_pawn_penalty", DOUBLED_PAWN_PENALTY),
+    ("isolated_pawn_penalty", ISOLATED_PAWN_PENALTY),
+    ("rook_open_file_bonus_mg", ROOK_OPEN_FILE_BONUS_MG),
+    ("rook_open_file_bonus_eg", ROOK_OPEN_FILE_BONUS_EG),
+    ("rook_semi_open_file_bonus_mg", ROOK_SEMI_OPEN_FILE_BONUS_MG),
+    ("rook_semi_open_file_bonus_eg", ROOK_SEMI_OPEN_FILE_BONUS_EG),
+    ("rook_on_seventh_bonus_mg", ROOK_ON_SEVENTH_BONUS_MG),
+    ("rook_on_seventh_bonus_eg", ROOK_ON_SEVENTH_BONUS_EG),
+    ("knight_mobility_mg", KNIGHT_MOBILITY_MG),
+    ("knight_mobility_eg", KNIGHT_MOBILITY_EG),
+    ("bishop_mobility_mg", BISHOP_MOBILITY_MG),
+    ("bishop_mobility_eg", BISHOP_MOBILITY_EG),
+    ("rook_mobility_mg", ROOK_MOBILITY_MG),
+    ("rook_mobility_eg", ROOK_MOBILITY_EG),
+    ("queen_mobility_mg", QUEEN_MOBILITY_MG),
+    ("queen_mobility_eg", QUEEN_MOBILITY_EG),
+];
+
+#[derive(Error, Debug)]
+pub enum TuningError {
+    #[error("Failed to access `{path}`: {error}")]
+    Io { path: String, error: std::io::Error },
+    #[error("{path}:{line_number}: {message}")]
+    Parse {
+        path: String,
+        line_number: usize,
+        message: String,
+    },
+    #[error("Invalid tuning state in `{path}`: {message}")]
+    InvalidState { path: String, message: String },
+    #[error("No labelled positions found in `{path}`")]
+    EmptyDataset { path: String },
+}
+
+/// A training position reduced to what the error function needs.
+#[derive(Debug, Clone)]
+pub struct TuningPosition {
+    /// Game result from White's perspective: 1.0 win, 0.5 draw, 0.0 loss.
+    result: f64,
+    /// Static evaluation with the built-in weights, from White's perspective.
+    base_eval: f64,
+    /// Phase-weighted feature counts (White minus Black), one per tunable weight.
+    features: Vec<f64>,
+}
+
+impl TuningPosition {
+    pub fn new(board: &Board, result: f64, targets: &Targets) -> Self {
+        Self {
+            result,
+            base_eval: board_material_score(board) as f64,
+            features: extract_features(board, targets),
+        }
+    }
+}
+
+/// Resumable tuner state: the scaling constant, completed iterations, and weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningState {
+    pub iteration: usize,
+    pub k: f64,
+    /// Values in `TUNABLE_PARAMS` order.
+    pub values: Vec<i16>,
+}
+
+impl TuningState {
+    /// Start from the engine's current weights.
+    pub fn initial(k: f64) -> Self {
+        Self {
+            iteration: 0,
+            k,
+            values: TUNABLE_PARAMS.iter().map(|&(_, value)| value).collect(),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# Texel tuning state; copy values into evaluation_tables.rs"
+        );
+        let _ = writeln!(out, "iteration = {}", self.iteration);
+        let _ = writeln!(out, "k = {}", self.k);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[params]");
+        for (&(name, _), value) in TUNABLE_PARAMS.iter().zip(&self.values) {
+            let _ = writeln!(out, "{} = {}", name, value);
+        }
+        out
+    }
+
+    /// Parse a state file written by [`TuningState::to_toml`]. Unknown keys are
+    /// rejected; missing weights keep their built-in value.
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        let mut state = Self::initial(1.0);
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| format!("line {}: expected `key = value`", index + 1))?;
+            let invalid = || format!("line {}: invalid value for `{}`", index + 1, key);
+            match key {
+                "iteration" => state.iteration = value.parse().map_err(|_| invalid())?,
+                "k" => state.k = value.parse().map_err(|_| invalid())?,
+                _ => {
+                    let param = TUNABLE_PARAMS
+                        .iter()
+                        .position(|&(name, _)| name == key)
+                        .ok_or_else(|| {
+                            format!("line {}: unknown parameter `{}`", index + 1, key)
+                        })?;
+                    state.values[param] = value.parse().map_err(|_| invalid())?;
+                }
+            }
+        }
+        Ok(state)
+    }
+
+    pub fn load(path: &str) -> Result<Self, TuningError> {
+        let contents = fs::read_to_string(path).map_err(|error| TuningError::Io {
+            path: path.to_string(),
+            error,
+        })?;
+        Self::from_toml(&contents).map_err(|message| TuningError::InvalidState {
+            path: path.to_string(),
+            message,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), TuningError> {
+        fs::write(path, self.to_toml()).map_err(|error| TuningError::Io {
+            path: path.to_string(),
+            error,
+        })
+    }
+
+    /// Load `path` if it exists, otherwise start fresh with `k` fitted to `positions`.
+    pub fn load_or_initial(path: &str, positions: &[TuningPosition]) -> Result<Self, TuningError> {
+        if Path::new(path).exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::initial(fit_k(positions)))
+        }
+    }
+}
+
+/// Read labelled positions, one per line. A line holds a FEN (four or six
+/// fields) followed by a result as an EPD opcode (`c9 "1-0";`), a bracketed
+/// score (`[0.5]`), or a bare result token (`1/2-1/2`).
+pub fn load_positions(path: &str) -> Result<Vec<TuningPosition>, TuningError> {
+    let contents = fs::read_to_string(path).map_err(|error| TuningError::Io {
+        path: path.to_string(),
+        error,
+    })?;
+    let targets = Targets::default();
+    let mut positions = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (board, result) = parse_training_line(line).map_err(|message| TuningError::Parse {
+            path: path.to_string(),
+            line_number: index + 1,
+            message,
+        })?;
+        positions.push(TuningPosition::new(&board, result, &targets));
+    }
+    if positions.is_empty() {
+        return Err(TuningError::EmptyDataset {
+            path: path.to_string(),
+        });
+    }
+    Ok(positions)
+}
+
+fn parse_training_line(line: &str) -> Result<(Board, f64), String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 5 {
+        return Err(format!("expected a FEN and a result: {}", line));
+    }
+    let has_counters =
+        fields.len() >= 7 && fields[4].parse::<u32>().is_ok() && fields[5].parse::<u32>().is_ok();
+    let (fen, rest) = if has_counters {
+        (fields[..6].join(" "), &fields[6..])
+    } else {
+        (format!("{} 0 1", fields[..4].join(" ")), &fields[4..])
+    };
+    let board = Board::from_str(&fen).map_err(|e| format!("invalid FEN: {:?}", e))?;
+    let result = rest
+        .iter()
+        .find_map(|token| parse_result(token))
+        .ok_or_else(|| format!("missing game result: {}", line))?;
+    Ok((board, result))
+}
+
+fn parse_result(token: &str) -> Option<f64> {
+    let token = token.trim_matches(|c| matches!(c, '"' | ';' | '[' | ']'));
+    match token {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => token.parse().ok().filter(|r| (0.0..=1.0).contains(r)),
+    }
+}
+
+/// Feature counts for every tunable weight, from White's perspective. Tapered
+/// terms are pre-multiplied by their midgame/endgame phase weight.
+fn extract_features(board: &Board, targets: &Targets) -> Vec<f64> {
+    let phase = game_phase(board) as f64;
+    let mg = phase / MAX_PHASE as f64;
+    let eg = 1.0 - mg;
+
+    let white = side_features(board, targets, Color::White);
+    let black = side_features(board, targets, Color::Black);
+    let diff = |f: fn(&SideFeatures) -> f64| f(&white) - f(&black);
+
+    vec![
+        diff(|s| s.knights),
+        diff(|s| s.bishops),
+        diff(|s| s.rooks),
+        diff(|s| s.queens),
+        diff(|s| s.bishop_pair) * mg,
+        diff(|s| s.bishop_pair) * eg,
+        -diff(|s| s.doubled_pawns),
+        -diff(|s| s.isolated_files),
+        diff(|s| s.rooks_open) * mg,
+        diff(|s| s.rooks_open) * eg,
+        diff(|s| s.rooks_semi_open) * mg,
+        diff(|s| s.rooks_semi_open) * eg,
+        diff(|s| s.rooks_on_seventh) * mg,
+        diff(|s| s.rooks_on_seventh) * eg,
+        diff(|s| s.knight_mobility) * mg,
+        diff(|s| s.knight_mobility) * eg,
+        diff(|s| s.bishop_mobility) * mg,
+        diff(|s| s.bishop_mobility) * eg,
+        diff(|s| s.rook_mobility) * mg,
+        diff(|s| s.rook_mobility) * eg,
+        diff(|s| s.queen_mobility) * mg,
+        diff(|s| s.queen_mobility) * eg,
+    ]
+}
+
+#[derive(Default)]
+struct SideFeatures {
+    knights: f64,
+    bishops: f64,
+    rooks: f64,
+    queens: f64,
+    bishop_pair: f64,
+    doubled_pawns: f64,
+    isolated_files: f64,
+    rooks_open: f64,
+    rooks_semi_open: f64,
+    rooks_on_seventh: f64,
+    knight_mobility: f64,
+    bishop_mobility: f64,
+    rook_mobility: f64,
+    queen_mobility: f64,
+}
+
+/// Counts mirroring the corresponding terms in `evaluation.rs`.
+fn side_features(board: &Board, targets: &Targets, color: Color) -> SideFeatures {
+    let pieces = board.pieces(color);
+    let friendly_pawns = pieces.locate(Piece::Pawn);
+    let all_pawns = friendly_pawns | board.pieces(color.opposite()).locate(Piece::Pawn);
+    let mobility_squares = safe_squares(board, color);
+    let seventh_rank = seventh_rank_with_targets(board, color);
+
+    let count = |piece| pieces.locate(piece).count_ones() as f64;
+    let mut features = SideFeatures {
+        knights: count(Piece::Knight),
+        bishops: count(Piece::Bishop),
+        rooks: count(Piece::Rook),
+        queens: count(Piece::Queen),
+        bishop_pair: if has_bishop_pair(pieces.locate(Piece::Bishop)) {
+            1.0
+        } else {
+            0.0
+        },
+        ..Default::default()
+    };
+
+    for (file, &file_mask) in FILE_MASKS.iter().enumerate() {
+        let pawns_on_file = (friendly_pawns & file_mask).count_ones();
+        if pawns_on_file > 1 {
+            features.doubled_pawns += (pawns_on_file - 1) as f64;
+        }
+        if pawns_on_file > 0 && (friendly_pawns & ADJACENT_FILES[file]).is_empty() {
+            features.isolated_files += 1.0;
+        }
+    }
+
+    let mut rooks = pieces.locate(Piece::Rook);
+    while !rooks.is_empty() {
+        let sq = rooks.pop_lsb_as_square().index();
+        let rook_file = FILE_MASKS[(sq % 8) as usize];
+        if (all_pawns & rook_file).is_empty() {
+            features.rooks_open += 1.0;
+        } else if (friendly_pawns & rook_file).is_empty() {
+            features.rooks_semi_open += 1.0;
+        }
+        if Bitboard(1u64 << sq).overlaps(seventh_rank) {
+            features.rooks_on_seventh += 1.0;
+        }
+    }
+
+    let mut knights = pieces.locate(Piece::Knight);
+    while !knights.is_empty() {
+        let sq = knights.pop_lsb_as_square();
+        let attacks = targets.piece_attacks(sq, Piece::Knight) & mobility_squares;
+        features.knight_mobility += attacks.count_ones() as f64;
+    }
+
+    let mut bishops = pieces.locate(Piece::Bishop);
+    while !bishops.is_empty() {
+        let sq = bishops.pop_lsb_as_square();
+        let attacks = targets.bishop_attacks(sq, board.occupied()) & mobility_squares;
+        features.bishop_mobility += attacks.count_ones() as f64;
+    }
+
+    let mut rooks = pieces.locate(Piece::Rook);
+    while !rooks.is_empty() {
+        let sq = rooks.pop_lsb_as_square();
+        let attacks = targets.rook_attacks(sq, board.occupied()) & mobility_squares;
+        features.rook_mobility += attacks.count_ones() as f64;
+    }
+
+    let mut queens = pieces.locate(Piece::Queen);
+    while !queens.is_empty() {
+        let sq = queens.pop_lsb_as_square();
+        let attacks = (targets.bishop_attacks(sq, board.occupied())
+            | targets.rook_attacks(sq, board.occupied()))
+            & mobility_squares;
+        features.queen_mobility += attacks.count_ones() as f64;
+    }
+
+    features
+}
+
+fn sigmoid(k: f64, eval: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+fn mean_squared_error(positions: &[TuningPosition], evals: &[f64], k: f64) -> f64 {
+    let total: f64 = positions
+        .iter()
+        .zip(evals)
+        .map(|(position, &eval)| (position.result - sigmoid(k, eval)).powi(2))
+        .sum();
+    total / positions.len() as f64
+}
+
+/// Evaluations of every position under `values`.
+fn evaluations(positions: &[TuningPosition], values: &[i16]) -> Vec<f64> {
+    positions
+        .iter()
+        .map(|position| {
+            let delta: f64 = TUNABLE_PARAMS
+                .iter()
+                .zip(values)
+                .zip(&position.features)
+                .map(|((&(_, default), &value), &feature)| (value - default) as f64 * feature)
+                .sum();
+            position.base_eval + delta
+        })
+        .collect()
+}
+
+/// Mean squared error of `state` over `positions`.
+pub fn tuning_error(positions: &[TuningPosition], state: &TuningState) -> f64 {
+    mean_squared_error(positions, &evaluations(positions, &state.values), state.k)
+}
+
+/// Find the scaling constant `k` that best maps the built-in evaluation to results.
+pub fn fit_k(positions: &[TuningPosition]) -> f64 {
+    let evals = evaluations(positions, &TuningState::initial(1.0).values);
+    let mut best = (1.0, f64::MAX);
+    let mut step = 0.5;
+    let mut center: f64 = 1.0;
+    for _ in 0..6 {
+        for i in -4..=4 {
+            let k = (center + i as f64 * step).max(0.01);
+            let error = mean_squared_error(positions, &evals, k);
+            if error < best.1 {
+                best = (k, error);
+            }
+        }
+        center = best.0;
+        step /= 4.0;
+    }
+    best.0
+}
+
+/// Run one pass of local search over every weight, updating `state` in place.
+/// Returns the new error and the number of weights that changed.
+pub fn tune_iteration(positions: &[TuningPosition], state: &mut TuningState) -> (f64, usize) {
+    let mut evals = evaluations(positions, &state.values);
+    let mut best_error = mean_squared_error(positions, &evals, state.k);
+    let mut changed = 0;
+
+    for param in 0..TUNABLE_PARAMS.len() {
+        for step in [1i16, -1] {
+            let candidate: Vec<f64> = evals
+                .iter()
+                .zip(positions)
+                .map(|(&eval, position)| eval + step as f64 * position.features[param])
+                .collect();
+            let error = mean_squared_error(positions, &candidate, state.k);
+            if error < best_error {
+                best_error = error;
+                evals = candidate;
+                state.values[param] += step;
+                changed += 1;
+                break;
+            }
+        }
+    }
+
+    state.iteration += 1;
+    (best_error, changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_training_line_formats() {
+        let (_, result) = parse_training_line("4k3/8/8/8/8/8/8/R3K3 w - - c9 \"1-0\";").unwrap();
+        assert_eq!(result, 1.0);
+        let (_, result) = parse_training_line("4k3/8/8/8/8/8/8/R3K3 w - - 12 40 [0.5]").unwrap();
+        assert_eq!(result, 0.5);
+        let (_, result) = parse_training_line("4k3/8/8/8/8/8/8/R3K3 b - - 0-1").unwrap();
+        assert_eq!(result, 0.0);
+        assert!(parse_training_line("4k3/8/8/8/8/8/8/R3K3 w - -").is_err());
+    }
+
+    #[test]
+    fn test_state_round_trips_through_toml() {
+        let mut state = TuningState::initial(1.25);
+        state.iteration = 7;
+        state.values[0] += 5;
+        let parsed = TuningState::from_toml(&state.to_toml()).unwrap();
+        assert_eq!(parsed, state);
+        assert!(TuningState::from_toml("bogus_param = 3").is_err());
+    }
+
+    #[test]
+    fn test_tuning_reduces_error() {
+        let targets = Targets::default();
+        // Rook-up positions that White only draws: the rook value should come down.
+        let positions: Vec<TuningPosition> = [
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3RK3 w - - 0 1",
+            "3k4/8/8/8/8/8/8/R3K3 b - - 0 1",
+        ]
+        .iter()
+        .map(|fen| TuningPosition::new(&Board::from_str(fen).unwrap(), 0.5, &targets))
+        .collect();
+
+        let mut state = TuningState::initial(1.0);
+        let initial_error = tuning_error(&positions, &state);
+        let (error, changed) = tune_iteration(&positions, &mut state);
+
+        assert!(changed > 0);
+        assert!(error < initial_error);
+        assert_eq!(state.iteration, 1);
+        assert!(state.values[2] < MATERIAL_VALUES[3]);
+    }
+}
@@ -0,0 +1,302 @@
+//! The C functions: a game handle that C callers create, set up from FEN,
+//! play moves on and search, and free when done.
+//!
+//! Strings handed to C are allocated here and must be released with
+//! `chess_free_string`; strings passed in are read and never kept. Every
+//! function accepts a null game and reports it rather than crashing.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::time::Duration;
+
+use crate::alpha_beta_searcher::SearchContext;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+use crate::chess_search::search_best_move_with_history;
+use crate::input_handler::fen::parse_fen;
+use crate::input_handler::fen_serialize::to_fen;
+use crate::move_generator::MoveGenerator;
+
+/// The call succeeded.
+pub const CHESS_OK: c_int = 0;
+/// A required pointer was null.
+pub const CHESS_NULL_ARGUMENT: c_int = -1;
+/// The FEN could not be parsed, or was not UTF-8.
+pub const CHESS_INVALID_FEN: c_int = -2;
+/// The move is not a legal move in UCI notation.
+pub const CHESS_ILLEGAL_MOVE: c_int = -3;
+
+/// A game for C callers: the position, and the hashes of the positions
+/// reached so far, for repetition draws.
+pub struct ChessGame {
+    board: Board,
+    move_generator: MoveGenerator,
+    position_history: Vec<u64>,
+}
+
+impl ChessGame {
+    fn new(board: Board) -> Self {
+        Self {
+            position_history: vec![board.current_position_hash()],
+            board,
+            move_generator: MoveGenerator::default(),
+        }
+    }
+
+    fn legal_moves(&mut self) -> Vec<ChessMove> {
+        let turn = self.board.turn();
+        self.move_generator
+            .generate_moves(&mut self.board, turn)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// The string `text` as a C string owned by the caller.
+fn into_c_string(text: String) -> *mut c_char {
+    CString::new(text)
+        .expect("engine output has no NUL bytes")
+        .into_raw()
+}
+
+/// The UTF-8 string at `text`, or None if it is null or not UTF-8.
+///
+/// # Safety
+///
+/// `text` must be null or point to a NUL-terminated string.
+unsafe fn read_c_string<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// A new game at the standard starting position. Free it with
+/// `chess_free_game`.
+#[no_mangle]
+pub extern "C" fn chess_new_game() -> *mut ChessGame {
+    Box::into_raw(Box::new(ChessGame::new(Board::default())))
+}
+
+/// Free a game from `chess_new_game`. Does nothing given null.
+///
+/// # Safety
+///
+/// `game` must be null or a game from `chess_new_game` not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn chess_free_game(game: *mut ChessGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Set the game to the position `fen`, forgetting the moves played before.
+/// Returns `CHESS_OK`, or `CHESS_INVALID_FEN` leaving the game as it was.
+///
+/// # Safety
+///
+/// `game` must be null or a live game, and `fen` null or a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_set_fen(game: *mut ChessGame, fen: *const c_char) -> c_int {
+    let Some(game) = game.as_mut() else {
+        return CHESS_NULL_ARGUMENT;
+    };
+    if fen.is_null() {
+        return CHESS_NULL_ARGUMENT;
+    }
+    match read_c_string(fen).map(parse_fen) {
+        Some(Ok(board)) => {
+            *game = ChessGame::new(board);
+            CHESS_OK
+        }
+        _ => CHESS_INVALID_FEN,
+    }
+}
+
+/// The current position in FEN, or null given a null game.
+///
+/// # Safety
+///
+/// `game` must be null or a live game.
+#[no_mangle]
+pub unsafe extern "C" fn chess_fen(game: *const ChessGame) -> *mut c_char {
+    match game.as_ref() {
+        Some(game) => into_c_string(to_fen(&game.board)),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Play a move given in UCI (`e2e4`, `e7e8q`). Returns `CHESS_OK`, or
+/// `CHESS_ILLEGAL_MOVE` leaving the game as it was.
+///
+/// # Safety
+///
+/// `game` must be null or a live game, and `uci` null or a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_play_move(game: *mut ChessGame, uci: *const c_char) -> c_int {
+    let Some(game) = game.as_mut() else {
+        return CHESS_NULL_ARGUMENT;
+    };
+    if uci.is_null() {
+        return CHESS_NULL_ARGUMENT;
+    }
+    let Some(uci) = read_c_string(uci) else {
+        return CHESS_ILLEGAL_MOVE;
+    };
+    let Some(chess_move) = game.legal_moves().into_iter().find(|m| m.to_uci() == uci) else {
+        return CHESS_ILLEGAL_MOVE;
+    };
+    if chess_move.apply(&mut game.board).is_err() {
+        return CHESS_ILLEGAL_MOVE;
+    }
+    game.board.toggle_turn();
+    game.position_history
+        .push(game.board.current_position_hash());
+    CHESS_OK
+}
+
+/// The legal moves of the side to move in UCI, separated by spaces: empty
+/// once the game is over, and null given a null game.
+///
+/// # Safety
+///
+/// `game` must be null or a live game.
+#[no_mangle]
+pub unsafe extern "C" fn chess_legal_moves(game: *mut ChessGame) -> *mut c_char {
+    let Some(game) = game.as_mut() else {
+        return std::ptr::null_mut();
+    };
+    let moves: Vec<String> = game.legal_moves().iter().map(ChessMove::to_uci).collect();
+    into_c_string(moves.join(" "))
+}
+
+/// The engine's choice for the side to move in UCI, without playing it. The
+/// search goes `depth` plies deep, and stops early after `movetime_ms`
+/// milliseconds unless that is 0. Null when there is no legal move, or given
+/// a null game.
+///
+/// # Safety
+///
+/// `game` must be null or a live game.
+#[no_mangle]
+pub unsafe extern "C" fn chess_best_move(
+    game: *mut ChessGame,
+    depth: u8,
+    movetime_ms: u32,
+) -> *mut c_char {
+    let Some(game) = game.as_mut() else {
+        return std::ptr::null_mut();
+    };
+    let mut context = SearchContext::new(depth.max(1));
+    context.set_time_limit((movetime_ms > 0).then(|| Duration::from_millis(movetime_ms.into())));
+    let mut board = game.board.clone();
+    match search_best_move_with_history(&mut context, &mut board, game.position_history.clone(), 0)
+    {
+        Ok(best_move) => into_c_string(best_move.to_uci()),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free a string returned by this library. Does nothing given null.
+///
+/// # Safety
+///
+/// `text` must be null or a string from this library not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn chess_free_string(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Call `f` with `text` as a C string.
+    fn with_c_string<T>(text: &str, f: impl FnOnce(*const c_char) -> T) -> T {
+        let text = CString::new(text).unwrap();
+        f(text.as_ptr())
+    }
+
+    /// Take ownership of a string from the library.
+    unsafe fn take_string(text: *mut c_char) -> Option<String> {
+        if text.is_null() {
+            return None;
+        }
+        let owned = CStr::from_ptr(text).to_str().unwrap().to_string();
+        chess_free_string(text);
+        Some(owned)
+    }
+
+    #[test]
+    fn test_moves_are_played_and_listed_in_uci() {
+        unsafe {
+            let game = chess_new_game();
+            let moves = take_string(chess_legal_moves(game)).unwrap();
+            assert_eq!(moves.split(' ').count(), 20);
+
+            assert_eq!(
+                with_c_string("e2e4", |uci| chess_play_move(game, uci)),
+                CHESS_OK
+            );
+            assert_eq!(
+                with_c_string("e2e4", |uci| chess_play_move(game, uci)),
+                CHESS_ILLEGAL_MOVE
+            );
+            assert_eq!(
+                take_string(chess_fen(game)).unwrap(),
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+            );
+            chess_free_game(game);
+        }
+    }
+
+    #[test]
+    fn test_best_move_is_searched_from_a_fen() {
+        unsafe {
+            let game = chess_new_game();
+            // Scholar's mate: Qxf7#
+            let fen = "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+            assert_eq!(with_c_string(fen, |fen| chess_set_fen(game, fen)), CHESS_OK);
+            assert_eq!(
+                take_string(chess_best_move(game, 4, 0)).as_deref(),
+                Some("h5f7")
+            );
+
+            assert_eq!(
+                with_c_string("h5f7", |uci| chess_play_move(game, uci)),
+                CHESS_OK
+            );
+            assert_eq!(take_string(chess_legal_moves(game)).as_deref(), Some(""));
+            assert_eq!(take_string(chess_best_move(game, 4, 0)), None);
+
+            assert_eq!(
+                with_c_string("not a fen", |fen| chess_set_fen(game, fen)),
+                CHESS_INVALID_FEN
+            );
+            chess_free_game(game);
+        }
+    }
+
+    #[test]
+    fn test_null_arguments_are_reported() {
+        unsafe {
+            let game = chess_new_game();
+            assert_eq!(chess_set_fen(game, std::ptr::null()), CHESS_NULL_ARGUMENT);
+            assert_eq!(chess_play_move(game, std::ptr::null()), CHESS_NULL_ARGUMENT);
+            assert_eq!(
+                with_c_string("e2e4", |uci| chess_play_move(std::ptr::null_mut(), uci)),
+                CHESS_NULL_ARGUMENT
+            );
+            assert!(chess_legal_moves(std::ptr::null_mut()).is_null());
+            assert!(chess_best_move(std::ptr::null_mut(), 4, 0).is_null());
+            assert!(chess_fen(std::ptr::null()).is_null());
+            chess_free_string(std::ptr::null_mut());
+            chess_free_game(std::ptr::null_mut());
+            chess_free_game(game);
+        }
+    }
+}
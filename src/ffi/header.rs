@@ -0,0 +1,171 @@
+//! The C header for the library, generated from the declarations of the C
+//! functions and the status codes, so the header and the library change
+//! together. `make ffi` writes it next to the library.
+
+use std::os::raw::c_int;
+
+use super::api::{CHESS_ILLEGAL_MOVE, CHESS_INVALID_FEN, CHESS_NULL_ARGUMENT, CHESS_OK};
+
+/// A function of the C interface: a comment for it and its declaration.
+struct CFunction {
+    comment: &'static str,
+    declaration: &'static str,
+}
+
+const STATUS_CODES: [(&str, c_int); 4] = [
+    ("CHESS_OK", CHESS_OK),
+    ("CHESS_NULL_ARGUMENT", CHESS_NULL_ARGUMENT),
+    ("CHESS_INVALID_FEN", CHESS_INVALID_FEN),
+    ("CHESS_ILLEGAL_MOVE", CHESS_ILLEGAL_MOVE),
+];
+
+const FUNCTIONS: [CFunction; 8] = [
+    CFunction {
+        comment: "A new game at the standard starting position.",
+        declaration: "ChessGame *chess_new_game(void);",
+    },
+    CFunction {
+        comment: "Free a game from chess_new_game. Does nothing given NULL.",
+        declaration: "void chess_free_game(ChessGame *game);",
+    },
+    CFunction {
+        comment: "Set the game to a position, forgetting the moves played before.",
+        declaration: "int chess_set_fen(ChessGame *game, const char *fen);",
+    },
+    CFunction {
+        comment: "The current position in FEN. Free it with chess_free_string.",
+        declaration: "char *chess_fen(const ChessGame *game);",
+    },
+    CFunction {
+        comment: "Play a move given in UCI (e2e4, e7e8q).",
+        declaration: "int chess_play_move(ChessGame *game, const char *uci);",
+    },
+    CFunction {
+        comment: "The legal moves in UCI, separated by spaces. Free it with chess_free_string.",
+        declaration: "char *chess_legal_moves(ChessGame *game);",
+    },
+    CFunction {
+        comment: "The engine's move in UCI, searched depth plies deep and for at most \
+                  movetime_ms milliseconds (0 for no limit), or NULL without a legal move. \
+                  Free it with chess_free_string.",
+        declaration: "char *chess_best_move(ChessGame *game, uint8_t depth, uint32_t movetime_ms);",
+    },
+    CFunction {
+        comment: "Free a string returned by this library. Does nothing given NULL.",
+        declaration: "void chess_free_string(char *text);",
+    },
+];
+
+/// The C header declaring the library's functions and status codes.
+pub fn c_header() -> String {
+    let mut header = String::from(
+        "/* C interface to the chess engine, generated by `make ffi`.\n \
+         *\n \
+         * Functions returning int return CHESS_OK or one of the errors below. Strings\n \
+         * returned by the library belong to the caller, who frees them with\n \
+         * chess_free_string.\n \
+         */\n\
+         #ifndef CHESS_H\n\
+         #define CHESS_H\n\
+         \n\
+         #include <stdint.h>\n\
+         \n\
+         #ifdef __cplusplus\n\
+         extern \"C\" {\n\
+         #endif\n\
+         \n",
+    );
+    for (name, value) in STATUS_CODES {
+        header.push_str(&format!("#define {} {}\n", name, value));
+    }
+    header.push_str("\ntypedef struct ChessGame ChessGame;\n");
+    for function in FUNCTIONS {
+        header.push_str(&format!(
+            "\n/* {} */\n{}\n",
+            function.comment, function.declaration
+        ));
+    }
+    header.push_str(
+        "\n#ifdef __cplusplus\n\
+         }\n\
+         #endif\n\
+         \n\
+         #endif /* CHESS_H */\n",
+    );
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::process::Command;
+
+    use super::*;
+
+    /// The name of the function a C declaration declares.
+    fn declared_name(declaration: &str) -> &str {
+        let before_arguments = &declaration[..declaration.find('(').unwrap()];
+        before_arguments.rsplit([' ', '*']).next().unwrap()
+    }
+
+    #[test]
+    fn test_every_exported_function_is_declared() {
+        let exported: BTreeSet<&str> = include_str!("api.rs")
+            .lines()
+            .filter_map(|line| line.split_once("extern \"C\" fn "))
+            .map(|(_, rest)| &rest[..rest.find('(').unwrap()])
+            .collect();
+        let declared: BTreeSet<&str> = FUNCTIONS
+            .iter()
+            .map(|function| declared_name(function.declaration))
+            .collect();
+        assert_eq!(exported, declared);
+        assert!(declared.contains("chess_best_move"));
+    }
+
+    #[test]
+    fn test_the_header_compiles_with_a_c_compiler() {
+        let directory = std::env::temp_dir().join(format!("chess_ffi_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("chess.h"), c_header()).unwrap();
+        std::fs::write(
+            directory.join("smoke.c"),
+            r#"#include <stdio.h>
+#include "chess.h"
+
+int main(void) {
+    ChessGame *game = chess_new_game();
+    if (chess_play_move(game, "e2e4") != CHESS_OK) {
+        return 1;
+    }
+    char *moves = chess_legal_moves(game);
+    char *best = chess_best_move(game, 4, 100);
+    printf("%s\n%s\n", moves, best ? best : "(none)");
+    chess_free_string(best);
+    chess_free_string(moves);
+    chess_free_game(game);
+    return chess_set_fen(NULL, "") == CHESS_NULL_ARGUMENT ? 0 : 1;
+}
+"#,
+        )
+        .unwrap();
+
+        // Compile only: linking needs the cdylib, which `make ffi` builds
+        let compiled = Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror", "-c"])
+            .arg(directory.join("smoke.c"))
+            .arg("-o")
+            .arg(directory.join("smoke.o"))
+            .output();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let Ok(output) = compiled else {
+            eprintln!("No C compiler found; skipping");
+            return;
+        };
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
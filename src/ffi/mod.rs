@@ -0,0 +1,9 @@
+//! C interface to the engine, for embedding it in GUIs and other languages.
+//!
+//! Built with the `ffi` feature as a C library by `make ffi`, which writes
+//! the header `c_header` generates next to it.
+
+pub mod api;
+pub mod header;
+
+pub use header::c_header;
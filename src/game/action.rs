@@ -23,6 +23,8 @@ impl GameMode {
 pub enum GameAction {
     /// Restart the game in the same mode
     RestartSameMode,
+    /// Start a new game in the same mode with the colors swapped
+    Rematch,
     /// Switch to a different game mode
     SwitchGameMode { target: GameMode },
     /// Exit the application
@@ -0,0 +1,283 @@
+//! Early adjudication of engine-vs-engine games from the engines' evaluations.
+//!
+//! A resign rule ends the game once every evaluation for `movecount` full moves
+//! in a row (one from each engine per move) has the same side ahead by at least
+//! `score` centipawns. A draw rule ends it once every evaluation for `movecount`
+//! full moves in a row, starting after move `movenumber`, is within `score` of
+//! zero. A move without an evaluation breaks both streaks.
+//!
+//! Rules are written like cutechess-cli's: `movecount=3 score=600` for resigning
+//! and `movenumber=40 movecount=8 score=10` for draws, separated by spaces or
+//! commas. Omitted keys keep their defaults.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::color::Color;
+use crate::game::pgn::PgnResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResignRule {
+    /// Full moves the score must stay decisive.
+    pub movecount: usize,
+    /// Centipawn advantage that counts as decisive.
+    pub score: i16,
+}
+
+impl Default for ResignRule {
+    fn default() -> Self {
+        Self {
+            movecount: 3,
+            score: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRule {
+    /// Full move after which draws may be adjudicated.
+    pub movenumber: usize,
+    /// Full moves the score must stay near zero.
+    pub movecount: usize,
+    /// Largest centipawn score, for either side, that counts as drawn.
+    pub score: i16,
+}
+
+impl Default for DrawRule {
+    fn default() -> Self {
+        Self {
+            movenumber: 40,
+            movecount: 8,
+            score: 10,
+        }
+    }
+}
+
+/// Parse `key=value` pairs separated by spaces or commas, calling `set` for each.
+fn parse_pairs(
+    s: &str,
+    mut set: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    for pair in s.split([' ', ',']).filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected `key=value`, got {:?}", pair))?;
+        set(key, value)?;
+    }
+    Ok(())
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for `{}`: {:?}", key, value))
+}
+
+impl FromStr for ResignRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rule = Self::default();
+        parse_pairs(s, |key, value| {
+            match key {
+                "movecount" => rule.movecount = parse_value(key, value)?,
+                "score" => rule.score = parse_value(key, value)?,
+                _ => return Err(format!("unknown resign option `{}`", key)),
+            }
+            Ok(())
+        })?;
+        Ok(rule)
+    }
+}
+
+impl FromStr for DrawRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rule = Self::default();
+        parse_pairs(s, |key, value| {
+            match key {
+                "movenumber" => rule.movenumber = parse_value(key, value)?,
+                "movecount" => rule.movecount = parse_value(key, value)?,
+                "score" => rule.score = parse_value(key, value)?,
+                _ => return Err(format!("unknown draw option `{}`", key)),
+            }
+            Ok(())
+        })?;
+        Ok(rule)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AdjudicationRules {
+    pub resign: Option<ResignRule>,
+    pub draw: Option<DrawRule>,
+}
+
+impl AdjudicationRules {
+    pub fn is_enabled(&self) -> bool {
+        self.resign.is_some() || self.draw.is_some()
+    }
+}
+
+/// An adjudicated result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjudication {
+    /// The side behind resigned.
+    Resign {
+        winner: Color,
+    },
+    Draw,
+}
+
+impl Adjudication {
+    pub fn result(&self) -> PgnResult {
+        match self {
+            Adjudication::Resign { winner } => PgnResult::win_for(*winner),
+            Adjudication::Draw => PgnResult::Draw,
+        }
+    }
+}
+
+impl fmt::Display for Adjudication {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Adjudication::Resign { winner } => {
+                write!(f, "{} resigns (adjudicated)", winner.opposite())
+            }
+            Adjudication::Draw => write!(f, "Draw (adjudicated)"),
+        }
+    }
+}
+
+/// Tracks evaluation streaks over a game and applies `AdjudicationRules`.
+#[derive(Debug, Clone)]
+pub struct Adjudicator {
+    rules: AdjudicationRules,
+    /// Side ahead in the current decisive streak and its length in plies.
+    resign_streak: Option<(Color, usize)>,
+    /// Length in plies of the current near-zero streak.
+    draw_streak: usize,
+}
+
+impl Adjudicator {
+    pub fn new(rules: AdjudicationRules) -> Self {
+        Self {
+            rules,
+            resign_streak: None,
+            draw_streak: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.resign_streak = None;
+        self.draw_streak = 0;
+    }
+
+    /// Record the evaluation (centipawns, White's perspective) reported for a
+    /// move played during full move `fullmove`. Returns the adjudicated result
+    /// once a rule is met.
+    pub fn record(&mut self, fullmove: usize, score: Option<i16>) -> Option<Adjudication> {
+        let Some(score) = score else {
+            self.reset();
+            return None;
+        };
+
+        if let Some(rule) = self.rules.resign {
+            let leader = match score {
+                s if s >= rule.score => Some(Color::White),
+                s if s <= -rule.score => Some(Color::Black),
+                _ => None,
+            };
+            self.resign_streak = match (leader, self.resign_streak) {
+                (Some(leader), Some((side, plies))) if side == leader => Some((leader, plies + 1)),
+                (Some(leader), _) => Some((leader, 1)),
+                (None, _) => None,
+            };
+            if let Some((winner, plies)) = self.resign_streak {
+                if plies >= rule.movecount * 2 {
+                    return Some(Adjudication::Resign { winner });
+                }
+            }
+        }
+
+        if let Some(rule) = self.rules.draw {
+            if fullmove > rule.movenumber && score.unsigned_abs() <= rule.score.unsigned_abs() {
+                self.draw_streak += 1;
+            } else {
+                self.draw_streak = 0;
+            }
+            if self.draw_streak >= rule.movecount * 2 {
+                return Some(Adjudication::Draw);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules_with_defaults() {
+        assert_eq!(
+            "movecount=5 score=400".parse::<ResignRule>(),
+            Ok(ResignRule {
+                movecount: 5,
+                score: 400
+            })
+        );
+        assert_eq!(
+            "movenumber=30,score=5".parse::<DrawRule>(),
+            Ok(DrawRule {
+                movenumber: 30,
+                movecount: 8,
+                score: 5
+            })
+        );
+        assert!("moves=5".parse::<ResignRule>().is_err());
+        assert!("score=high".parse::<DrawRule>().is_err());
+    }
+
+    #[test]
+    fn test_resign_needs_both_engines_to_agree() {
+        let mut adjudicator = Adjudicator::new(AdjudicationRules {
+            resign: Some(ResignRule {
+                movecount: 2,
+                score: 500,
+            }),
+            draw: None,
+        });
+        // Black's engine disagrees on its first move, restarting the streak
+        assert_eq!(adjudicator.record(20, Some(-600)), None);
+        assert_eq!(adjudicator.record(20, Some(-100)), None);
+        for (fullmove, score) in [(21, -600), (21, -550), (22, -700)] {
+            assert_eq!(adjudicator.record(fullmove, Some(score)), None);
+        }
+        assert_eq!(
+            adjudicator.record(22, Some(-800)),
+            Some(Adjudication::Resign {
+                winner: Color::Black
+            })
+        );
+    }
+
+    #[test]
+    fn test_draw_only_after_move_number() {
+        let mut adjudicator = Adjudicator::new(AdjudicationRules {
+            resign: None,
+            draw: Some(DrawRule {
+                movenumber: 10,
+                movecount: 1,
+                score: 10,
+            }),
+        });
+        assert_eq!(adjudicator.record(10, Some(0)), None);
+        assert_eq!(adjudicator.record(10, Some(0)), None);
+        assert_eq!(adjudicator.record(11, Some(5)), None);
+        assert_eq!(adjudicator.record(11, None), None);
+        assert_eq!(adjudicator.record(12, Some(-5)), None);
+        assert_eq!(adjudicator.record(12, Some(3)), Some(Adjudication::Draw));
+    }
+}
@@ -0,0 +1,358 @@
+//! Background analysis of a position, for the TUI's live analysis panel and
+//! the `chess analyze` command.
+//!
+//! An unbounded iterative deepening search runs on its own thread and reports
+//! each completed depth, the way a UCI engine streams `info` lines during
+//! `go infinite`. To follow several lines, each depth is searched once per
+//! line, every search leaving out the first moves of the lines found before
+//! it, like a UCI engine's MultiPV.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::alpha_beta_searcher::{mate_in_moves, SearchContext, SearchInfo};
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use crate::chess_move::chess_move::ChessMove;
+use crate::chess_search::search_best_move_with_history;
+use crate::move_generator::MoveGenerator;
+
+/// Deepest iteration of an analysis. Analysis normally runs until stopped long
+/// before this.
+const ANALYSIS_DEPTH: u8 = 64;
+
+/// Result of the deepest completed iteration of an analysis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisLine {
+    pub depth: u8,
+    /// Score from White's perspective.
+    pub score: i16,
+    /// Positions searched so far, over all depths.
+    pub nodes: usize,
+    pub nodes_per_second: u64,
+    /// Time spent searching so far.
+    pub elapsed: Duration,
+    /// Moves to checkmate, when the score is a forced mate for either side.
+    pub mate_in: Option<u8>,
+    /// The expected continuation in SAN, best move first.
+    pub principal_variation: Vec<String>,
+    /// The same continuation in UCI notation.
+    pub principal_variation_uci: Vec<String>,
+}
+
+impl AnalysisLine {
+    fn from_search_info(board: &Board, info: &SearchInfo<ChessMove>) -> Self {
+        let principal_variation = san_line(board, &info.principal_variation);
+        let principal_variation_uci = info.principal_variation[..principal_variation.len()]
+            .iter()
+            .map(|m| m.to_uci())
+            .collect();
+        let mate_in = mate_in_moves(info.score).map(|moves| moves.unsigned_abs() as u8);
+        Self {
+            depth: info.depth,
+            score: info.score,
+            nodes: info.nodes,
+            nodes_per_second: nodes_per_second(info.nodes, info.elapsed),
+            elapsed: info.elapsed,
+            mate_in,
+            principal_variation,
+            principal_variation_uci,
+        }
+    }
+}
+
+fn nodes_per_second(nodes: usize, elapsed: Duration) -> u64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds > 0.0 {
+        (nodes as f64 / seconds) as u64
+    } else {
+        0
+    }
+}
+
+/// `moves` played in order from `board`, in SAN. Stops at the first move that
+/// is not legal.
+fn san_line(board: &Board, moves: &[ChessMove]) -> Vec<String> {
+    let move_generator = MoveGenerator::default();
+    let mut board = board.clone();
+    let mut line = Vec::new();
+    for chess_move in moves {
+        let turn = board.turn();
+        let candidates =
+            enumerate_candidate_moves_with_algebraic_notation(&mut board, turn, &move_generator);
+        let Some((legal_move, san)) = candidates
+            .into_iter()
+            .find(|(m, _)| m.to_uci() == chess_move.to_uci())
+        else {
+            break;
+        };
+        if legal_move.apply(&mut board).is_err() {
+            break;
+        }
+        board.toggle_turn();
+        line.push(san);
+    }
+    line
+}
+
+/// Called from the search thread with the lines of each completed depth,
+/// best first.
+type LinesCallback = Box<dyn Fn(&[AnalysisLine]) + Send>;
+
+/// A search of one position on a background thread, deepening until it is
+/// dropped or reaches its maximum depth
+pub struct Analysis {
+    position_hash: u64,
+    latest: Arc<Mutex<Vec<AnalysisLine>>>,
+    search: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl Analysis {
+    /// Start analyzing `board`, reached through the positions in
+    /// `position_hashes` (for repetition detection). `on_update` is called
+    /// from the search thread with each new line.
+    pub fn start(
+        board: Board,
+        position_hashes: Vec<u64>,
+        on_update: impl Fn(&AnalysisLine) + Send + Sync + 'static,
+    ) -> Self {
+        Self::start_to_depth(board, position_hashes, ANALYSIS_DEPTH, on_update)
+    }
+
+    /// Start analyzing `board` as with `start`, finishing once `max_depth`
+    /// has been searched.
+    pub fn start_to_depth(
+        board: Board,
+        position_hashes: Vec<u64>,
+        max_depth: u8,
+        on_update: impl Fn(&AnalysisLine) + Send + Sync + 'static,
+    ) -> Self {
+        Self::spawn(
+            board,
+            position_hashes,
+            max_depth,
+            1,
+            Box::new(move |lines| on_update(&lines[0])),
+        )
+    }
+
+    /// Start analyzing `board` as with `start`, following the best
+    /// `line_count` moves rather than just the best one. `on_update` is
+    /// called with the lines of each completed depth, best first.
+    pub fn start_with_lines(
+        board: Board,
+        position_hashes: Vec<u64>,
+        line_count: usize,
+        on_update: impl Fn(&[AnalysisLine]) + Send + Sync + 'static,
+    ) -> Self {
+        Self::spawn(
+            board,
+            position_hashes,
+            ANALYSIS_DEPTH,
+            line_count.max(1),
+            Box::new(on_update),
+        )
+    }
+
+    /// Search each depth once per line, leaving out the first moves of the
+    /// lines found before at that depth.
+    fn spawn(
+        board: Board,
+        position_hashes: Vec<u64>,
+        max_depth: u8,
+        line_count: usize,
+        on_update: LinesCallback,
+    ) -> Self {
+        let position_hash = board.current_position_hash();
+        let latest = Arc::new(Mutex::new(Vec::new()));
+
+        let mut context = SearchContext::new(max_depth);
+        let stop = context.stop_flag();
+        let last_info = Arc::new(Mutex::new(None));
+        let sink = last_info.clone();
+        context.set_info_callback(Some(Arc::new(move |info: &SearchInfo<ChessMove>| {
+            *sink.lock().unwrap() = Some(info.clone());
+        })));
+
+        let published = latest.clone();
+        let searching = stop.clone();
+        let handle = std::thread::spawn(move || {
+            let white_to_move = board.turn() == Color::White;
+            let start = Instant::now();
+            for depth in 1..=max_depth.clamp(1, ANALYSIS_DEPTH) {
+                context.set_depth(depth);
+                let mut lines: Vec<AnalysisLine> = Vec::new();
+                let mut first_moves = Vec::new();
+                while lines.len() < line_count {
+                    context.set_excluded_root_moves(first_moves.clone());
+                    let mut root = board.clone();
+                    // Analysis is neutral about draws. A search fails when
+                    // every move has been left out.
+                    let result = search_best_move_with_history(
+                        &mut context,
+                        &mut root,
+                        position_hashes.clone(),
+                        0,
+                    );
+                    if searching.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let info = last_info.lock().unwrap().take();
+                    let (Ok(best_move), Some(info)) = (result, info) else {
+                        break;
+                    };
+                    let elapsed = start.elapsed();
+                    lines.push(AnalysisLine {
+                        nodes: context.searched_position_count(),
+                        nodes_per_second: nodes_per_second(
+                            context.searched_position_count(),
+                            elapsed,
+                        ),
+                        elapsed,
+                        ..AnalysisLine::from_search_info(&board, &info)
+                    });
+                    first_moves.push(best_move);
+                }
+                if lines.is_empty() {
+                    return;
+                }
+                // Best first for the side to move, mates included
+                lines.sort_by_key(|line| {
+                    if white_to_move {
+                        line.score.saturating_neg()
+                    } else {
+                        line.score
+                    }
+                });
+                *published.lock().unwrap() = lines.clone();
+                on_update(&lines);
+            }
+        });
+
+        Self {
+            position_hash,
+            latest,
+            search: Some((stop, handle)),
+        }
+    }
+
+    /// Hash of the position being analyzed.
+    pub fn position_hash(&self) -> u64 {
+        self.position_hash
+    }
+
+    /// Whether the search has ended, either at its maximum depth or because
+    /// the position has no legal moves.
+    pub fn is_finished(&self) -> bool {
+        self.search
+            .as_ref()
+            .is_none_or(|(_, handle)| handle.is_finished())
+    }
+
+    /// The deepest line found so far, if a depth has completed.
+    pub fn latest(&self) -> Option<AnalysisLine> {
+        self.latest.lock().unwrap().first().cloned()
+    }
+
+    /// The lines of the deepest completed depth, best first.
+    pub fn latest_lines(&self) -> Vec<AnalysisLine> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        if let Some((stop, handle)) = self.search.take() {
+            stop.store(true, Ordering::Relaxed);
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_analysis_streams_lines_until_dropped() {
+        let board = Board::from_str("1Q6/8/8/8/8/8/k1K5/8 w - - 0 1").expect("valid FEN");
+        let hash = board.current_position_hash();
+        let (sender, lines) = mpsc::channel();
+        let analysis = Analysis::start(board, vec![hash], move |line| {
+            let _ = sender.send(line.clone());
+        });
+        assert_eq!(analysis.position_hash(), hash);
+
+        let first = lines
+            .recv_timeout(Duration::from_secs(30))
+            .expect("a depth should complete");
+        assert_eq!(first.depth, 1);
+        let deeper = lines
+            .recv_timeout(Duration::from_secs(30))
+            .expect("the search should keep deepening");
+        assert!(deeper.depth > first.depth);
+        assert!(deeper.principal_variation[0].ends_with('#'));
+        assert_eq!(
+            deeper.principal_variation_uci.len(),
+            deeper.principal_variation.len()
+        );
+        assert!(deeper.score > 0);
+        assert_eq!(deeper.mate_in, Some(1));
+        assert!(deeper.nodes > first.nodes);
+        assert!(analysis.latest().is_some());
+
+        drop(analysis);
+    }
+
+    #[test]
+    fn test_analysis_follows_several_lines() {
+        // Black to move: the lines are best first for Black
+        let board = Board::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+            .expect("valid FEN");
+        let hash = board.current_position_hash();
+        let (sender, updates) = mpsc::channel();
+        let analysis = Analysis::start_with_lines(board, vec![hash], 3, move |lines| {
+            let _ = sender.send(lines.to_vec());
+        });
+
+        let lines = loop {
+            let lines = updates
+                .recv_timeout(Duration::from_secs(30))
+                .expect("a depth should complete");
+            if lines[0].depth >= 2 {
+                break lines;
+            }
+        };
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.depth == lines[0].depth));
+        assert!(lines.windows(2).all(|pair| pair[0].score <= pair[1].score));
+        let mut first_moves: Vec<&String> = lines
+            .iter()
+            .map(|line| &line.principal_variation[0])
+            .collect();
+        first_moves.sort();
+        first_moves.dedup();
+        assert_eq!(first_moves.len(), 3);
+        assert_eq!(analysis.latest_lines().len(), 3);
+    }
+
+    #[test]
+    fn test_analysis_to_a_depth_finishes_there() {
+        let board = Board::default();
+        let hash = board.current_position_hash();
+        let analysis = Analysis::start_to_depth(board, vec![hash], 2, |_| {});
+        let deadline = std::time::Instant::now() + Duration::from_secs(30);
+        while !analysis.is_finished() {
+            assert!(std::time::Instant::now() < deadline, "analysis kept going");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let line = analysis.latest().expect("both depths should complete");
+        assert_eq!(line.depth, 2);
+        assert!(!line.principal_variation.is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+//! Engine annotation of recorded games.
+//!
+//! Every position of a game is searched under the same limits. Each move gets
+//! the evaluation after it as a comment, and moves that lose more than a
+//! configurable number of centipawns compared to the engine's choice are marked
+//! `?!`, `?` or `??` with the better move named in the comment.
+
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::SearchLimits;
+use crate::board::color::Color;
+use crate::evaluate::GameEnding;
+use crate::game::engine::{Engine, EngineConfig, EngineError};
+use crate::game::pgn::{format_eval_comment, PgnGame, PgnMove};
+use crate::input_handler::fen::FenParseError;
+
+/// Scores beyond this are treated as forced mates when displayed.
+const MATE_THRESHOLD: i16 = i16::MAX / 4;
+
+/// Score given to a checkmated position, from the winner's perspective.
+const MATE_SCORE: i16 = i16::MAX / 2;
+
+/// Evaluations are clamped to this many centipawns before computing how much a
+/// move lost, so that choosing a slower mate or a different winning line in an
+/// already decided position is not flagged.
+const LOSS_EVAL_CAP: i16 = 1000;
+
+/// Centipawn losses at which a move is marked as an inaccuracy, mistake or blunder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnotationThresholds {
+    pub inaccuracy: u16,
+    pub mistake: u16,
+    pub blunder: u16,
+}
+
+impl Default for AnnotationThresholds {
+    fn default() -> Self {
+        Self {
+            inaccuracy: 50,
+            mistake: 100,
+            blunder: 300,
+        }
+    }
+}
+
+impl AnnotationThresholds {
+    /// Classify a move by the centipawns it lost for the side that played it.
+    pub fn judge(&self, loss: u16) -> Option<MoveJudgement> {
+        if loss >= self.blunder {
+            Some(MoveJudgement::Blunder)
+        } else if loss >= self.mistake {
+            Some(MoveJudgement::Mistake)
+        } else if loss >= self.inaccuracy {
+            Some(MoveJudgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveJudgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveJudgement {
+    /// Annotation glyph appended to the move.
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            MoveJudgement::Inaccuracy => "?!",
+            MoveJudgement::Mistake => "?",
+            MoveJudgement::Blunder => "??",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MoveJudgement::Inaccuracy => "Inaccuracy",
+            MoveJudgement::Mistake => "Mistake",
+            MoveJudgement::Blunder => "Blunder",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnnotateConfig {
+    /// Search depth used when `limits` is depth-only.
+    pub depth: u8,
+    pub limits: SearchLimits,
+    pub thresholds: AnnotationThresholds,
+}
+
+#[derive(Error, Debug)]
+pub enum AnnotateError {
+    #[error("Invalid starting position: {error}")]
+    InvalidStartingPosition { error: FenParseError },
+    #[error("Illegal move `{san}` at ply {ply}")]
+    IllegalMove { san: String, ply: usize },
+    #[error("Engine error: {error}")]
+    Engine { error: EngineError },
+}
+
+/// Engine verdict on one position: the best move in SAN (None once the game is
+/// over) and the score from White's perspective.
+struct PositionAnalysis {
+    best_move: Option<String>,
+    score: i16,
+}
+
+/// Search every position of `game` and return a copy with annotated moves.
+/// Existing move comments are kept ahead of the engine's comment.
+pub fn annotate_game(game: &PgnGame, config: &AnnotateConfig) -> Result<PgnGame, AnnotateError> {
+    let starting_position = game
+        .starting_position()
+        .map_err(|error| AnnotateError::InvalidStartingPosition { error })?;
+    let mut engine = Engine::with_config(EngineConfig {
+        search_depth: config.depth,
+        starting_position: starting_position.clone(),
+    });
+    engine.set_search_limits(config.limits);
+
+    let mut analyses = vec![analyze(&mut engine)?];
+    let mut movers = Vec::with_capacity(game.moves().len());
+    for (ply, pgn_move) in game.moves().iter().enumerate() {
+        movers.push(engine.board().turn());
+        play_san(&mut engine, &pgn_move.san).map_err(|_| AnnotateError::IllegalMove {
+            san: pgn_move.san.clone(),
+            ply: ply + 1,
+        })?;
+        analyses.push(analyze(&mut engine)?);
+    }
+
+    let mut annotated = PgnGame::new(&starting_position);
+    for (tag, value) in game.headers() {
+        annotated.set_header(tag, value);
+    }
+    for (ply, pgn_move) in game.moves().iter().enumerate() {
+        let (before, after) = (&analyses[ply], &analyses[ply + 1]);
+        let annotated_move = annotate_move(pgn_move, movers[ply], before, after, config);
+        annotated.push_move(&annotated_move.san, annotated_move.comment);
+    }
+    annotated.set_result(game.result());
+    Ok(annotated)
+}
+
+fn annotate_move(
+    pgn_move: &PgnMove,
+    mover: Color,
+    before: &PositionAnalysis,
+    after: &PositionAnalysis,
+    config: &AnnotateConfig,
+) -> PgnMove {
+    let san = pgn_move.san.trim_end_matches(['!', '?']);
+    let is_best = before
+        .best_move
+        .as_deref()
+        .is_some_and(|best| strip_annotations(best) == strip_annotations(san));
+    let judgement = if is_best {
+        None
+    } else {
+        config
+            .thresholds
+            .judge(centipawn_loss(mover, before.score, after.score))
+    };
+
+    let mut comment = format_score(after.score);
+    if let (Some(judgement), Some(best)) = (judgement, &before.best_move) {
+        comment = format!(
+            "{}. {}: {} was best ({})",
+            comment,
+            judgement.label(),
+            best,
+            format_score(before.score)
+        );
+    }
+    if let Some(existing) = &pgn_move.comment {
+        comment = format!("{} {}", existing, comment);
+    }
+
+    PgnMove {
+        san: format!("{}{}", san, judgement.map_or("", |j| j.glyph())),
+        comment: Some(comment),
+    }
+}
+
+/// Centipawns lost by `mover` between the evaluation before and after its move.
+fn centipawn_loss(mover: Color, before: i16, after: i16) -> u16 {
+    let before = before.clamp(-LOSS_EVAL_CAP, LOSS_EVAL_CAP);
+    let after = after.clamp(-LOSS_EVAL_CAP, LOSS_EVAL_CAP);
+    let loss = match mover {
+        Color::White => before - after,
+        Color::Black => after - before,
+    };
+    loss.max(0) as u16
+}
+
+fn format_score(score: i16) -> String {
+    if score >= MATE_THRESHOLD {
+        "+mate".to_string()
+    } else if score <= -MATE_THRESHOLD {
+        "-mate".to_string()
+    } else {
+        format_eval_comment(score)
+    }
+}
+
+fn analyze(engine: &mut Engine) -> Result<PositionAnalysis, AnnotateError> {
+    if let Some(ending) = engine.check_game_over() {
+        let score = match ending {
+            GameEnding::Checkmate | GameEnding::VariantWin => match engine.board().turn() {
+                Color::White => -MATE_SCORE,
+                Color::Black => MATE_SCORE,
+            },
+            GameEnding::Stalemate | GameEnding::Draw => 0,
+        };
+        return Ok(PositionAnalysis {
+            best_move: None,
+            score,
+        });
+    }
+
+    let best_move = engine
+        .analyze_position()
+        .map_err(|error| AnnotateError::Engine { error })?;
+    let score = engine.get_search_stats().last_score.unwrap_or(0);
+    let best_move = engine
+        .get_valid_moves()
+        .into_iter()
+        .find(|(m, _)| m.to_uci() == best_move.to_uci())
+        .map(|(_, san)| san);
+    Ok(PositionAnalysis { best_move, score })
+}
+
+/// Play a move recorded in SAN, tolerating missing or extra check marks,
+/// annotation glyphs and zeros in castling. UCI notation is accepted as well.
+fn play_san(engine: &mut Engine, san: &str) -> Result<(), EngineError> {
+    let wanted = strip_annotations(san).replace('0', "O");
+    let legal = engine
+        .get_valid_moves()
+        .into_iter()
+        .find(|(_, notation)| strip_annotations(notation) == wanted);
+    match legal {
+        Some((chess_move, notation)) => {
+            engine.apply_chess_move_with_notation(chess_move, notation, None)?
+        }
+        None => {
+            engine.make_move_uci(san)?;
+        }
+    }
+    engine.board_mut().toggle_turn();
+    engine.record_position_hash();
+    Ok(())
+}
+
+/// SAN without check, mate and annotation suffixes.
+fn strip_annotations(san: &str) -> &str {
+    san.trim_end_matches(['+', '#', '!', '?'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::pgn::parse_pgn;
+
+    fn config() -> AnnotateConfig {
+        AnnotateConfig {
+            depth: 2,
+            limits: SearchLimits::default(),
+            thresholds: AnnotationThresholds::default(),
+        }
+    }
+
+    #[test]
+    fn test_thresholds_classify_losses() {
+        let thresholds = AnnotationThresholds::default();
+        assert_eq!(thresholds.judge(20), None);
+        assert_eq!(thresholds.judge(60), Some(MoveJudgement::Inaccuracy));
+        assert_eq!(thresholds.judge(150), Some(MoveJudgement::Mistake));
+        assert_eq!(thresholds.judge(900), Some(MoveJudgement::Blunder));
+    }
+
+    #[test]
+    fn test_centipawn_loss_is_from_movers_perspective() {
+        assert_eq!(centipawn_loss(Color::White, 50, -250), 300);
+        assert_eq!(centipawn_loss(Color::Black, 50, -250), 0);
+        assert_eq!(centipawn_loss(Color::Black, -100, 200), 300);
+        assert_eq!(
+            centipawn_loss(Color::White, MATE_SCORE, 1200),
+            0,
+            "winning either way is not a loss"
+        );
+    }
+
+    #[test]
+    fn test_annotate_game_marks_blunder_and_mate() {
+        let games = parse_pgn("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0").unwrap();
+        let annotated = annotate_game(&games[0], &config()).unwrap();
+
+        let moves = annotated.moves();
+        assert_eq!(moves.len(), 7);
+        assert!(moves.iter().all(|m| m.comment.is_some()));
+        assert_eq!(moves[5].san, "Nf6??");
+        assert!(moves[5].comment.as_deref().unwrap().contains("Blunder:"));
+        assert_eq!(moves[6].san, "Qxf7#");
+        assert_eq!(moves[6].comment.as_deref(), Some("+mate"));
+        assert_eq!(annotated.result(), games[0].result());
+    }
+
+    #[test]
+    fn test_annotate_game_reports_illegal_move() {
+        let games = parse_pgn("1. e4 e4 *").unwrap();
+        assert!(matches!(
+            annotate_game(&games[0], &config()),
+            Err(AnnotateError::IllegalMove { ply: 2, .. })
+        ));
+    }
+}
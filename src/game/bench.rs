@@ -0,0 +1,373 @@
+//! Standard bench: a fixed, single-threaded search over a built-in position set.
+//!
+//! The total node count is a deterministic "bench signature". Refactors that are
+//! meant to be non-functional should leave it unchanged; any difference means
+//! search behavior changed. Run via `chess bench`.
+//!
+//! Results can be saved as a JSON baseline and later compared against, flagging
+//! throughput and per-position time regressions beyond a threshold.
+
+use std::fs;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::SearchContext;
+use crate::board::Board;
+use crate::chess_search::search_best_move;
+
+/// Positions covering openings, middlegames, tactics, and endgames.
+pub const BENCH_POSITIONS: &[&str] = &[
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11",
+    "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+    "rq3rk1/ppp2ppp/1bnpb3/3N2B1/3NP3/7P/PPPQ1PP1/2KR3R w - - 7 14",
+    "r1bq1r1k/1pp1n1pp/1p1p4/4p2Q/4Pp2/1BNP4/PPP2PPP/3R1RK1 w - - 2 14",
+    "r3r1k1/2p2ppp/p1p1bn2/8/1q2P3/2NPQN2/PPP3PP/R4RK1 b - - 2 15",
+    "r1bbk1nr/pp3p1p/2n5/1N4p1/2Np1B2/8/PPP2PPP/2KR1B1R w kq - 0 13",
+    "r1bq1rk1/ppp1nppp/4n3/3p3Q/3P4/1BP1B3/PP1N2PP/R4RK1 w - - 1 16",
+    "4r1k1/r1q2ppp/ppp2n2/4P3/5Rb1/1N1BQ3/PPP3PP/R5K1 w - - 1 17",
+    "2rqkb1r/ppp2p2/2npb1p1/1N1Nn2p/2P1PP2/8/PP2B1PP/R1BQK2R b KQ - 0 11",
+    "r1bq1r1k/b1p1npp1/p2p3p/1p6/3PP3/1B2NN2/PP3PPP/R2Q1RK1 w - - 1 16",
+    "3r1rk1/p5pp/bpp1pp2/8/q1PP1P2/b3P3/P2NQRPP/1R2B1K1 b - - 6 22",
+    "r1q2rk1/2p1bppp/2Pp4/p6b/Q1PNp3/4B3/PP1R1PPP/2K4R w - - 2 18",
+    "4k2r/1pb2ppp/1p2p3/1R1p4/3P4/2r1PN2/P4PPP/1R4K1 b - - 3 22",
+    "3q2k1/pb3p1p/4pbp1/2r5/PpN2N2/1P2P2P/5PP1/Q2R2K1 b - - 4 26",
+    "6k1/6p1/6Pp/ppp5/3pn2P/1P3K2/1PP2P2/8 b - - 0 1",
+    "8/8/8/8/5kp1/P7/8/1K1N4 w - - 0 1",
+    "8/8/8/5N2/8/p7/8/2NK3k w - - 0 1",
+    "8/3k4/8/8/8/4B3/4KB2/2B5 w - - 0 1",
+    "8/8/1P6/5pr1/8/4R3/7k/2K5 w - - 0 1",
+    "8/2p4P/8/kr6/6R1/8/8/1K6 w - - 0 1",
+    "8/8/3P3k/8/1p6/8/1P6/1K3n2 b - - 0 1",
+    "8/R7/2q5/8/6k1/8/1P5p/K6R w - - 0 124",
+    "6k1/3b3r/1p1p4/p1n2p2/1PPNpP1q/P3Q1p1/1R1RB1P1/5K2 b - - 0 1",
+    "r2r1n2/pp2bk2/2p1p2p/3q4/3PN1QP/2P3R1/P4PP1/5RK1 w - - 0 1",
+    "8/8/8/8/8/5k2/6p1/6K1 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "2rr3k/pp3pp1/1nnrp1p1/3pN3/2pP4/2P3P1/PPB1PP1P/3RR1K1 w - - 0 1",
+    "1k1r4/pp1b1R2/3q2pp/4p3/2B5/4Q3/PPP2B2/2K5 b - - 0 1",
+    "3r1k2/4npp1/1ppr3p/p6P/P2PPPP1/1NR5/5K2/2R5 w - - 0 1",
+    "2q1rr1k/3bbnnp/p2p1pp1/2pPp3/PpP1P1P1/1P2BNNP/2BQ1PRR/7K b - - 0 1",
+    "rnbqkb1r/p3pppp/1p6/2ppP3/3N4/2P5/PPP1BPPP/R1BQK2R w KQkq - 0 1",
+    "8/8/1p1k4/5p2/1P3P2/8/3K1B2/8 w - - 0 1",
+    "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+    "4k3/8/8/8/8/8/R7/4K3 w - - 0 1",
+];
+
+/// Positions faster than this in the baseline are too noisy to compare individually.
+const MIN_COMPARABLE_POSITION_MS: f64 = 50.0;
+
+/// Node count and search time for a single bench position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchPosition {
+    pub fen: String,
+    pub nodes: usize,
+    #[serde(default)]
+    pub time_ms: f64,
+}
+
+/// Aggregate result of a bench run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub depth: u8,
+    pub positions: Vec<BenchPosition>,
+    /// Total nodes searched across all positions: the bench signature.
+    pub nodes: usize,
+    pub time_ms: u128,
+    pub nps: u64,
+}
+
+/// Search every bench position to `depth` with a fresh, single-threaded search
+/// context so the node count does not depend on thread scheduling or prior state.
+/// `on_position` is called after each position with its index, FEN, and node count.
+pub fn run_bench<F>(depth: u8, mut on_position: F) -> BenchResult
+where
+    F: FnMut(usize, &str, usize),
+{
+    let mut positions = Vec::with_capacity(BENCH_POSITIONS.len());
+    let mut duration = Duration::ZERO;
+
+    for (index, fen) in BENCH_POSITIONS.iter().enumerate() {
+        let mut board = Board::from_str(fen).expect("bench FEN should be valid");
+        let mut context = SearchContext::with_parallel(depth, false);
+        context.clear_killers();
+
+        let start = Instant::now();
+        search_best_move(&mut context, &mut board).expect("bench position should have a move");
+        let elapsed = start.elapsed();
+        duration += elapsed;
+
+        let nodes = context.searched_position_count();
+        on_position(index, fen, nodes);
+        positions.push(BenchPosition {
+            fen: fen.to_string(),
+            nodes,
+            time_ms: elapsed.as_secs_f64() * 1000.0,
+        });
+    }
+
+    let nodes = positions.iter().map(|p| p.nodes).sum();
+    let secs = duration.as_secs_f64();
+    BenchResult {
+        depth,
+        positions,
+        nodes,
+        time_ms: duration.as_millis(),
+        nps: if secs > 0.0 {
+            (nodes as f64 / secs) as u64
+        } else {
+            0
+        },
+    }
+}
+
+/// Run the bench, printing per-position node counts and the summary.
+pub fn print_bench(depth: u8) -> BenchResult {
+    let total = BENCH_POSITIONS.len();
+    let result = run_bench(depth, |index, fen, nodes| {
+        println!(
+            "Position {:>2}/{}: {:>10} nodes  {}",
+            index + 1,
+            total,
+            nodes,
+            fen
+        );
+    });
+
+    println!("{}", "=".repeat(40));
+    println!("Depth           : {}", result.depth);
+    println!("Total time (ms) : {}", result.time_ms);
+    println!("Nodes searched  : {}", result.nodes);
+    println!("Nodes/second    : {}", result.nps);
+    result
+}
+
+#[derive(Error, Debug)]
+pub enum BenchError {
+    #[error("Failed to access `{path}`: {error}")]
+    Io { path: String, error: std::io::Error },
+    #[error("Invalid bench baseline in `{path}`: {error}")]
+    InvalidBaseline {
+        path: String,
+        error: serde_json::Error,
+    },
+}
+
+impl BenchResult {
+    /// Read a baseline written by `save`.
+    pub fn load(path: &str) -> Result<Self, BenchError> {
+        let contents = fs::read_to_string(path).map_err(|error| BenchError::Io {
+            path: path.to_string(),
+            error,
+        })?;
+        serde_json::from_str(&contents).map_err(|error| BenchError::InvalidBaseline {
+            path: path.to_string(),
+            error,
+        })
+    }
+
+    /// Write this result as a JSON baseline.
+    pub fn save(&self, path: &str) -> Result<(), BenchError> {
+        let json = serde_json::to_string_pretty(self).expect("bench result should serialize");
+        fs::write(path, json + "\n").map_err(|error| BenchError::Io {
+            path: path.to_string(),
+            error,
+        })
+    }
+}
+
+/// Search time of one position in the baseline and the current run.
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionComparison {
+    pub fen: String,
+    pub baseline_ms: f64,
+    pub current_ms: f64,
+    pub change_pct: f64,
+}
+
+/// Current bench run measured against a baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchComparison {
+    pub threshold_pct: f64,
+    pub baseline_nps: u64,
+    pub current_nps: u64,
+    pub nps_change_pct: f64,
+    pub baseline_nodes: usize,
+    pub current_nodes: usize,
+    /// Positions whose search time grew by more than the threshold.
+    pub slower_positions: Vec<PositionComparison>,
+}
+
+impl BenchComparison {
+    /// Whether nodes/second dropped by more than the threshold.
+    pub fn nps_regressed(&self) -> bool {
+        self.nps_change_pct < -self.threshold_pct
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.nps_regressed() || !self.slower_positions.is_empty()
+    }
+
+    /// A different node count means search behavior changed, so times are not
+    /// directly comparable.
+    pub fn signature_changed(&self) -> bool {
+        self.baseline_nodes != self.current_nodes
+    }
+}
+
+fn percent_change(baseline: f64, current: f64) -> f64 {
+    if baseline > 0.0 {
+        (current - baseline) / baseline * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Compare `current` against `baseline`. Throughput must not drop, and no
+/// position present in both may get slower, by more than `threshold_pct` percent.
+pub fn compare_bench(
+    baseline: &BenchResult,
+    current: &BenchResult,
+    threshold_pct: f64,
+) -> BenchComparison {
+    let slower_positions = current
+        .positions
+        .iter()
+        .filter_map(|position| {
+            let base = baseline.positions.iter().find(|b| b.fen == position.fen)?;
+            if base.time_ms < MIN_COMPARABLE_POSITION_MS {
+                return None;
+            }
+            let change_pct = percent_change(base.time_ms, position.time_ms);
+            (change_pct > threshold_pct).then(|| PositionComparison {
+                fen: position.fen.clone(),
+                baseline_ms: base.time_ms,
+                current_ms: position.time_ms,
+                change_pct,
+            })
+        })
+        .collect();
+
+    BenchComparison {
+        threshold_pct,
+        baseline_nps: baseline.nps,
+        current_nps: current.nps,
+        nps_change_pct: percent_change(baseline.nps as f64, current.nps as f64),
+        baseline_nodes: baseline.nodes,
+        current_nodes: current.nodes,
+        slower_positions,
+    }
+}
+
+/// Print the comparison and a PASS/REGRESSION verdict.
+pub fn print_comparison(comparison: &BenchComparison) {
+    println!("{}", "=".repeat(40));
+    println!(
+        "Nodes/second    : {} -> {} ({:+.1}%)",
+        comparison.baseline_nps, comparison.current_nps, comparison.nps_change_pct
+    );
+    if comparison.signature_changed() {
+        println!(
+            "Bench signature changed: {} -> {} nodes",
+            comparison.baseline_nodes, comparison.current_nodes
+        );
+    }
+    for position in &comparison.slower_positions {
+        println!(
+            "Slower: {:.1}ms -> {:.1}ms ({:+.1}%)  {}",
+            position.baseline_ms, position.current_ms, position.change_pct, position.fen
+        );
+    }
+    if comparison.is_regression() {
+        println!("REGRESSION (threshold {}%)", comparison.threshold_pct);
+    } else {
+        println!("PASS (threshold {}%)", comparison.threshold_pct);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_generator::MoveGenerator;
+
+    #[test]
+    fn test_bench_positions_are_valid_and_playable() {
+        let move_generator = MoveGenerator::default();
+        for fen in BENCH_POSITIONS {
+            let mut board = Board::from_str(fen).expect("bench FEN should parse");
+            let turn = board.turn();
+            assert!(
+                !move_generator.generate_moves(&mut board, turn).is_empty(),
+                "bench position has no legal moves: {}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_bench_signature_is_deterministic() {
+        let first = run_bench(2, |_, _, _| {});
+        let second = run_bench(2, |_, _, _| {});
+        assert!(first.nodes > 0);
+        assert_eq!(first.nodes, second.nodes);
+    }
+
+    fn result(nps: u64, times: &[f64]) -> BenchResult {
+        BenchResult {
+            depth: 5,
+            positions: times
+                .iter()
+                .enumerate()
+                .map(|(i, &time_ms)| BenchPosition {
+                    fen: BENCH_POSITIONS[i].to_string(),
+                    nodes: 1000,
+                    time_ms,
+                })
+                .collect(),
+            nodes: 1000 * times.len(),
+            time_ms: times.iter().sum::<f64>() as u128,
+            nps,
+        }
+    }
+
+    #[test]
+    fn test_compare_bench_flags_regressions_beyond_threshold() {
+        let baseline = result(1_000_000, &[100.0, 60.0, 1.0]);
+
+        let within = compare_bench(&baseline, &result(960_000, &[104.0, 62.0, 1.0]), 5.0);
+        assert!(!within.is_regression());
+        assert!(!within.signature_changed());
+
+        let slower = compare_bench(&baseline, &result(900_000, &[130.0, 60.0, 3.0]), 5.0);
+        assert!(slower.nps_regressed());
+        assert_eq!(
+            slower.slower_positions.len(),
+            1,
+            "tiny positions are ignored"
+        );
+        assert_eq!(slower.slower_positions[0].fen, BENCH_POSITIONS[0]);
+        assert!(slower.is_regression());
+    }
+
+    #[test]
+    fn test_bench_result_round_trips_through_baseline_file() {
+        let path = std::env::temp_dir().join(format!("bench-baseline-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let baseline = result(123_456, &[10.0, 20.0]);
+        baseline.save(path).unwrap();
+
+        let loaded = BenchResult::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.nps, baseline.nps);
+        assert_eq!(loaded.positions.len(), 2);
+        assert_eq!(loaded.positions[1].time_ms, 20.0);
+    }
+}
@@ -0,0 +1,225 @@
+//! Chess clocks for interactive games.
+//!
+//! Each side has its own remaining time and gains the increment after every
+//! completed move. Only one side's clock runs at a time; a side whose time runs
+//! out before completing its move loses on time.
+//!
+//! The sides may start with different times, e.g. for Armageddon games where
+//! White gets more time and Black has draw odds, and may gain different
+//! increments.
+
+use std::time::{Duration, Instant};
+
+use crate::alpha_beta_searcher::TimeLimits;
+use crate::board::color::Color;
+use crate::uci::time_management::time_limits;
+
+/// Remaining time for both sides plus the running clock, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameClock {
+    white_base: Duration,
+    black_base: Duration,
+    white: Duration,
+    black: Duration,
+    white_increment: Duration,
+    black_increment: Duration,
+    running: Option<(Color, Instant)>,
+}
+
+impl GameClock {
+    pub fn new(base: Duration, increment: Duration) -> Self {
+        Self::with_time_odds(base, base, increment)
+    }
+
+    /// A clock where White and Black start with different times.
+    pub fn with_time_odds(white: Duration, black: Duration, increment: Duration) -> Self {
+        Self {
+            white_base: white,
+            black_base: black,
+            white,
+            black,
+            white_increment: increment,
+            black_increment: increment,
+            running: None,
+        }
+    }
+
+    /// This clock with Black gaining `increment` after each move instead.
+    pub fn with_black_increment(mut self, increment: Duration) -> Self {
+        self.black_increment = increment;
+        self
+    }
+
+    /// Whether the sides started with different times or gain different
+    /// increments.
+    pub fn has_time_odds(&self) -> bool {
+        self.white_base != self.black_base || self.white_increment != self.black_increment
+    }
+
+    /// Time `color` gains after each completed move.
+    pub fn increment(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.white_increment,
+            Color::Black => self.black_increment,
+        }
+    }
+
+    /// Starting time for `color`.
+    pub fn base(&self, color: Color) -> Duration {
+        match color {
+            Color::White => self.white_base,
+            Color::Black => self.black_base,
+        }
+    }
+
+    /// The PGN `TimeControl` tag value for `color`, e.g. `300+3`.
+    pub fn time_control_tag(&self, color: Color) -> String {
+        format!(
+            "{}+{}",
+            self.base(color).as_secs_f64(),
+            self.increment(color).as_secs_f64()
+        )
+    }
+
+    /// The side whose clock is running.
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(color, _)| color)
+    }
+
+    /// Time left for `color`, counting the move in progress.
+    pub fn remaining(&self, color: Color) -> Duration {
+        let stored = match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        };
+        match self.running {
+            Some((running, started)) if running == color => {
+                stored.saturating_sub(started.elapsed())
+            }
+            _ => stored,
+        }
+    }
+
+    pub fn is_flagged(&self, color: Color) -> bool {
+        self.remaining(color).is_zero()
+    }
+
+    /// Start `color`'s clock. Does nothing if it is already running.
+    pub fn start(&mut self, color: Color) {
+        if self.running() != Some(color) {
+            self.running = Some((color, Instant::now()));
+        }
+    }
+
+    /// Stop `color`'s clock after it completed a move. Returns false if the
+    /// time ran out, in which case no increment is added.
+    pub fn stop(&mut self, color: Color) -> bool {
+        let Some((running, started)) = self.running.take() else {
+            return !self.is_flagged(color);
+        };
+        debug_assert_eq!(running, color, "stopped the clock of the side not to move");
+        self.charge(color, started.elapsed())
+    }
+
+    /// Deduct `elapsed` from `color`'s time and add the increment. Returns false
+    /// if the time ran out.
+    pub fn charge(&mut self, color: Color, elapsed: Duration) -> bool {
+        let increment = self.increment(color);
+        let stored = match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        };
+        if elapsed >= *stored {
+            *stored = Duration::ZERO;
+            return false;
+        }
+        *stored = *stored - elapsed + increment;
+        true
+    }
+
+    /// Time limits for an engine move by `color`, from the time manager.
+    pub fn time_limits(&self, color: Color) -> TimeLimits {
+        let remaining = self.remaining(color).as_millis() as u64;
+        let increment = self.increment(color).as_millis() as u64;
+        time_limits(remaining, increment, None)
+    }
+}
+
+/// Format a remaining time as `m:ss`, with tenths of a second under 10 seconds.
+pub fn format_clock(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs < 10 {
+        format!("0:{:02}.{}", secs, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge_deducts_time_and_adds_increment() {
+        let mut clock = GameClock::new(Duration::from_secs(60), Duration::from_secs(2));
+        assert!(clock.charge(Color::White, Duration::from_secs(10)));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(52));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+
+        assert!(!clock.charge(Color::Black, Duration::from_secs(61)));
+        assert!(clock.is_flagged(Color::Black));
+        assert_eq!(clock.remaining(Color::Black), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_running_clock_counts_down_only_for_side_to_move() {
+        let mut clock = GameClock::new(Duration::from_secs(60), Duration::ZERO);
+        clock.start(Color::White);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(clock.remaining(Color::White) < Duration::from_secs(60));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+
+        assert!(clock.stop(Color::White));
+        assert_eq!(clock.running(), None);
+    }
+
+    #[test]
+    fn test_time_limits_are_within_remaining_time() {
+        let clock = GameClock::new(Duration::from_secs(60), Duration::from_secs(1));
+        let limits = clock.time_limits(Color::White);
+        assert!(limits.soft > Duration::ZERO);
+        assert!(limits.soft <= limits.hard);
+        assert!(limits.hard < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_time_odds_start_sides_with_different_times() {
+        let clock = GameClock::with_time_odds(
+            Duration::from_secs(300),
+            Duration::from_secs(240),
+            Duration::ZERO,
+        );
+        assert!(clock.has_time_odds());
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(240));
+        assert_eq!(clock.time_control_tag(Color::White), "300+0");
+        assert_eq!(clock.time_control_tag(Color::Black), "240+0");
+    }
+
+    #[test]
+    fn test_sides_gain_their_own_increment() {
+        let mut clock = GameClock::new(Duration::from_secs(60), Duration::from_secs(2))
+            .with_black_increment(Duration::from_secs(5));
+        assert!(clock.has_time_odds());
+        assert!(clock.charge(Color::White, Duration::from_secs(10)));
+        assert!(clock.charge(Color::Black, Duration::from_secs(10)));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(52));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(55));
+        assert_eq!(clock.time_control_tag(Color::Black), "60+5");
+    }
+
+    #[test]
+    fn test_format_clock() {
+        assert_eq!(format_clock(Duration::from_secs(305)), "5:05");
+        assert_eq!(format_clock(Duration::from_millis(9_450)), "0:09.4");
+    }
+}
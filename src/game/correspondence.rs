@@ -0,0 +1,322 @@
+//! Correspondence games that are analyzed over many sessions.
+//!
+//! The game (starting position and moves played) is saved to a JSON file along
+//! with what the engine has learned about it: its transposition table and its
+//! evaluation of each candidate move in the current position. Loading the file
+//! restores the table, so a session that adds a move or a few minutes of
+//! thinking continues the earlier search instead of starting from scratch.
+
+use std::fs;
+use std::str::FromStr;
+use std::time::Duration;
+
+use common::bitboard::Square;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::{BoundType, TTEntry};
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+use crate::chess_move::capture::Capture;
+use crate::chess_move::{
+    CastleChessMove, ChessMove, EnPassantChessMove, PawnPromotionChessMove, StandardChessMove,
+};
+use crate::game::engine::{Engine, EngineConfig, MoveEvaluation};
+use crate::input_handler::fen::FenParseError;
+
+/// Shallow entries are cheap to recompute and make up most of a full table, so
+/// they are not saved.
+const MIN_SAVED_DEPTH: u8 = 2;
+
+#[derive(Error, Debug)]
+pub enum CorrespondenceError {
+    #[error("Failed to access `{path}`: {error}")]
+    Io { path: String, error: std::io::Error },
+    #[error("Invalid correspondence game in `{path}`: {error}")]
+    InvalidFile {
+        path: String,
+        error: serde_json::Error,
+    },
+    #[error("Invalid starting position: {error}")]
+    InvalidStartingPosition { error: FenParseError },
+    #[error("Illegal move `{notation}` at ply {ply}")]
+    IllegalMove { notation: String, ply: usize },
+}
+
+/// The engine's evaluation of one legal move in the current position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CandidateMove {
+    pub san: String,
+    /// Score in centipawns from White's perspective.
+    pub score: i16,
+    /// Depth the position after the move was searched to.
+    pub depth: u8,
+}
+
+impl From<&MoveEvaluation> for CandidateMove {
+    fn from(evaluation: &MoveEvaluation) -> Self {
+        Self {
+            san: evaluation.san.clone(),
+            score: evaluation.score,
+            depth: evaluation.depth,
+        }
+    }
+}
+
+/// A transposition table entry as saved: hash, score, depth, bound and the best
+/// move in the compact form of `encode_move`.
+type SavedEntry = (u64, i16, u8, BoundType, Option<String>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrespondenceGame {
+    /// Starting position in FEN.
+    pub fen: String,
+    /// Moves played from the starting position, in UCI notation.
+    pub moves: Vec<String>,
+    /// Time the engine has spent on the current position, over all sessions.
+    pub analysis_seconds: f64,
+    /// Candidate moves in the current position, best first for the side to move.
+    pub candidates: Vec<CandidateMove>,
+    #[serde(default)]
+    analysis: Vec<SavedEntry>,
+}
+
+impl CorrespondenceGame {
+    pub fn new(starting_position: &Board) -> Self {
+        Self {
+            fen: starting_position.to_fen(),
+            moves: Vec::new(),
+            analysis_seconds: 0.0,
+            candidates: Vec::new(),
+            analysis: Vec::new(),
+        }
+    }
+
+    /// Read a game written by `save`.
+    pub fn load(path: &str) -> Result<Self, CorrespondenceError> {
+        let contents = fs::read_to_string(path).map_err(|error| CorrespondenceError::Io {
+            path: path.to_string(),
+            error,
+        })?;
+        serde_json::from_str(&contents).map_err(|error| CorrespondenceError::InvalidFile {
+            path: path.to_string(),
+            error,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), CorrespondenceError> {
+        let json = serde_json::to_string(self).expect("correspondence game should serialize");
+        fs::write(path, json + "\n").map_err(|error| CorrespondenceError::Io {
+            path: path.to_string(),
+            error,
+        })
+    }
+
+    /// Number of saved transposition table entries.
+    pub fn analysis_len(&self) -> usize {
+        self.analysis.len()
+    }
+
+    /// An engine at the current position of the game, with the saved analysis
+    /// loaded into its transposition table.
+    pub fn engine(&self, search_depth: u8) -> Result<Engine, CorrespondenceError> {
+        let starting_position = Board::from_str(&self.fen)
+            .map_err(|error| CorrespondenceError::InvalidStartingPosition { error })?;
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth,
+            starting_position,
+        });
+        for (index, notation) in self.moves.iter().enumerate() {
+            engine
+                .make_move_uci(notation)
+                .map_err(|_| CorrespondenceError::IllegalMove {
+                    notation: notation.clone(),
+                    ply: index + 1,
+                })?;
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        }
+
+        let entries = self
+            .analysis
+            .iter()
+            .map(|(hash, score, depth, bound_type, best_move)| {
+                let entry = TTEntry {
+                    // Replaced from the hash when the entry is stored
+                    key: 0,
+                    score: *score,
+                    depth: *depth,
+                    bound_type: *bound_type,
+                    best_move: best_move.as_deref().and_then(decode_move),
+                };
+                (*hash, entry)
+            })
+            .collect();
+        engine.restore_analysis(entries);
+        Ok(engine)
+    }
+
+    /// Record the moves and analysis of `engine`, which spent `elapsed` thinking
+    /// about its current position this session and evaluated its `candidates`.
+    /// Candidates saved for the position are kept when none are given.
+    pub fn update(&mut self, engine: &Engine, elapsed: Duration, candidates: &[MoveEvaluation]) {
+        let moves: Vec<String> = engine
+            .move_history()
+            .iter()
+            .map(|entry| entry.chess_move.to_uci())
+            .collect();
+        if moves != self.moves {
+            self.analysis_seconds = 0.0;
+            self.candidates.clear();
+            self.moves = moves;
+        }
+        self.analysis_seconds += elapsed.as_secs_f64();
+        if !candidates.is_empty() {
+            self.candidates = candidates.iter().map(CandidateMove::from).collect();
+        }
+        self.analysis = engine
+            .analysis_snapshot()
+            .into_iter()
+            .filter(|(_, entry)| entry.depth >= MIN_SAVED_DEPTH)
+            .map(|(hash, entry)| {
+                (
+                    hash,
+                    entry.score,
+                    entry.depth,
+                    entry.bound_type,
+                    entry.best_move.as_ref().map(encode_move),
+                )
+            })
+            .collect();
+    }
+}
+
+/// A move as `<from><to>` plus a suffix: the promotion piece, `ep` for en
+/// passant or `c` for castling, then `x` and the captured piece, if any.
+fn encode_move(chess_move: &ChessMove) -> String {
+    let mut encoded = format!(
+        "{}{}",
+        chess_move.from_square().to_algebraic(),
+        chess_move.to_square().to_algebraic()
+    );
+    match chess_move {
+        ChessMove::PawnPromotion(m) => encoded.push(m.promote_to_piece().to_char(Color::Black)),
+        ChessMove::EnPassant(_) => encoded.push_str("ep"),
+        ChessMove::Castle(_) => encoded.push('c'),
+        ChessMove::Standard(_) => {}
+    }
+    if let (ChessMove::Standard(_) | ChessMove::PawnPromotion(_), Some(Capture(piece))) =
+        (chess_move, chess_move.captures())
+    {
+        encoded.push('x');
+        encoded.push(piece.to_char(Color::Black));
+    }
+    encoded
+}
+
+fn decode_move(encoded: &str) -> Option<ChessMove> {
+    if encoded.len() < 4 || !encoded.is_ascii() {
+        return None;
+    }
+    let from = Square::from_algebraic(&encoded[0..2])?;
+    let to = Square::from_algebraic(&encoded[2..4])?;
+    let (suffix, captures) = match encoded[4..].split_once('x') {
+        Some((suffix, captured)) => {
+            let mut chars = captured.chars();
+            let (piece, _) = Piece::from_char(chars.next()?)?;
+            if chars.next().is_some() {
+                return None;
+            }
+            (suffix, Some(Capture(piece)))
+        }
+        None => (&encoded[4..], None),
+    };
+
+    let chess_move = match suffix {
+        "" => ChessMove::Standard(StandardChessMove::new(from, to, captures)),
+        "ep" => ChessMove::EnPassant(EnPassantChessMove::new(from, to)),
+        "c" => {
+            let color = if from.to_algebraic().ends_with('1') {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let castle = if to.to_algebraic().starts_with('g') {
+                CastleChessMove::castle_kingside(color)
+            } else {
+                CastleChessMove::castle_queenside(color)
+            };
+            ChessMove::Castle(castle)
+        }
+        promotion => {
+            let mut chars = promotion.chars();
+            let (piece, _) = Piece::from_char(chars.next()?)?;
+            if chars.next().is_some() {
+                return None;
+            }
+            ChessMove::PawnPromotion(PawnPromotionChessMove::new(from, to, captures, piece))
+        }
+    };
+    Some(chess_move)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoded_moves_round_trip() {
+        let moves = [
+            ChessMove::Standard(StandardChessMove::new(
+                Square::from_algebraic("e4").unwrap(),
+                Square::from_algebraic("d5").unwrap(),
+                Some(Capture(Piece::Pawn)),
+            )),
+            ChessMove::PawnPromotion(PawnPromotionChessMove::new(
+                Square::from_algebraic("b7").unwrap(),
+                Square::from_algebraic("a8").unwrap(),
+                Some(Capture(Piece::Rook)),
+                Piece::Knight,
+            )),
+            ChessMove::EnPassant(EnPassantChessMove::new(
+                Square::from_algebraic("e5").unwrap(),
+                Square::from_algebraic("d6").unwrap(),
+            )),
+            ChessMove::Castle(CastleChessMove::castle_queenside(Color::Black)),
+        ];
+        let encoded: Vec<String> = moves.iter().map(encode_move).collect();
+        assert_eq!(encoded, ["e4d5xp", "b7a8nxr", "e5d6ep", "e8c8c"]);
+        for (chess_move, encoded) in moves.iter().zip(&encoded) {
+            assert_eq!(decode_move(encoded).as_ref(), Some(chess_move));
+        }
+        assert!(decode_move("e2e4xz").is_none());
+    }
+
+    #[test]
+    fn test_saved_game_restores_moves_and_analysis() {
+        let mut game = CorrespondenceGame::new(&Board::default());
+        let mut engine = game.engine(3).unwrap();
+        engine.make_move_san_or_uci("e4").unwrap();
+        engine.board_mut().toggle_turn();
+        engine.record_position_hash();
+        engine.analyze_position().unwrap();
+        let hash = engine.board().current_position_hash();
+        let searched = engine.stored_analysis(hash).unwrap();
+        let candidates = engine.evaluate_moves(Duration::from_secs(1), 2);
+        game.update(&engine, Duration::from_secs(2), &candidates);
+        assert_eq!(game.moves, ["e2e4"]);
+        assert_eq!(game.candidates.len(), 20);
+        assert!(game.analysis_len() > 0);
+
+        let json = serde_json::to_string(&game).unwrap();
+        let restored: CorrespondenceGame = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.candidates, game.candidates);
+        let engine = restored.engine(3).unwrap();
+        assert_eq!(engine.board().turn(), Color::Black);
+        let restored_entry = engine.stored_analysis(hash).unwrap();
+        assert_eq!(restored_entry.score, searched.score);
+        assert_eq!(restored_entry.depth, searched.depth);
+        assert_eq!(restored_entry.best_move, searched.best_move);
+    }
+}
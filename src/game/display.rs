@@ -31,6 +31,7 @@ impl GameDisplay {
         board: &Board,
         current_turn: Color,
         last_move: Option<(&ChessMove, &str)>,
+        move_list: &str,
         stats: Option<&str>,
         opening_name: Option<&str>,
     ) {
@@ -79,6 +80,10 @@ impl GameDisplay {
             self.buffer.push_str(&format!("Last move: {}\n", notation));
         }
 
+        if !move_list.is_empty() {
+            self.buffer.push_str(&format!("Moves: {}\n", move_list));
+        }
+
         if let Some(stats) = stats {
             self.buffer.push_str(&format!("\n{}\n", stats));
         }
@@ -0,0 +1,180 @@
+//! Opening drill: practise the book moves of a named opening.
+//!
+//! The student plays one side and must follow the opening book; the other side
+//! replies with book moves from the same opening, picked at random where the
+//! book branches. Moves that leave the book are counted as deviations and must
+//! be retried. Once no book move remains the drill is complete.
+
+use thiserror::Error;
+
+use crate::board::color::Color;
+use crate::book::{Book, BookMove};
+
+#[derive(Error, Debug)]
+pub enum DrillError {
+    #[error("No opening in the book matches {query:?}")]
+    NoSuchOpening { query: String },
+}
+
+/// Progress through the book lines of one opening.
+#[derive(Debug, Clone)]
+pub struct OpeningDrill {
+    name: String,
+    lines: Vec<Vec<BookMove>>,
+    student: Color,
+    played: Vec<BookMove>,
+    deviations: usize,
+}
+
+impl OpeningDrill {
+    /// Drill every book line named like `query` (see `Book::find_lines`), with the
+    /// student playing `student`.
+    pub fn new(book: &Book, query: &str, student: Color) -> Result<Self, DrillError> {
+        let found = book.find_lines(query);
+        if found.is_empty() {
+            return Err(DrillError::NoSuchOpening {
+                query: query.to_string(),
+            });
+        }
+
+        let first_name = &found[0].0;
+        let name = if found.iter().all(|(name, _)| name == first_name) {
+            first_name.clone()
+        } else {
+            format!("{} ({} lines)", query, found.len())
+        };
+        Ok(Self::from_lines(
+            name,
+            found.into_iter().map(|(_, line)| line).collect(),
+            student,
+        ))
+    }
+
+    pub fn from_lines(name: String, lines: Vec<Vec<BookMove>>, student: Color) -> Self {
+        Self {
+            name,
+            lines,
+            student,
+            played: Vec::new(),
+            deviations: 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn student(&self) -> Color {
+        self.student
+    }
+
+    pub fn deviations(&self) -> usize {
+        self.deviations
+    }
+
+    /// Book moves that continue at least one drilled line from the current position.
+    pub fn expected_moves(&self) -> Vec<BookMove> {
+        let mut expected: Vec<BookMove> = Vec::new();
+        for line in &self.lines {
+            if line.len() > self.played.len() && line.starts_with(&self.played) {
+                let next = line[self.played.len()];
+                if !expected.contains(&next) {
+                    expected.push(next);
+                }
+            }
+        }
+        expected
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.expected_moves().is_empty()
+    }
+
+    /// Record `book_move` if it follows the book. A move that leaves the book is
+    /// counted as a deviation and not recorded.
+    pub fn play(&mut self, book_move: BookMove) -> bool {
+        if self.expected_moves().contains(&book_move) {
+            self.played.push(book_move);
+            true
+        } else {
+            self.deviations += 1;
+            false
+        }
+    }
+
+    /// A random book reply for the side opposite the student.
+    pub fn book_reply(&self) -> Option<BookMove> {
+        let expected = self.expected_moves();
+        if expected.is_empty() {
+            return None;
+        }
+        Some(expected[fastrand::usize(..expected.len())])
+    }
+
+    /// Start over from the initial position, clearing the deviation count.
+    pub fn reset(&mut self) {
+        self.played.clear();
+        self.deviations = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::bitboard::*;
+
+    #[test]
+    fn test_drill_follows_book_and_counts_deviations() {
+        let book = Book::default();
+        let mut drill = OpeningDrill::new(&book, "ruy lopez", Color::White).unwrap();
+        assert_eq!(drill.name(), "Ruy Lopez");
+        assert_eq!(drill.expected_moves(), vec![BookMove::new(E2, E4)]);
+
+        assert!(!drill.play(BookMove::new(D2, D4)));
+        assert_eq!(drill.deviations(), 1);
+        assert!(drill.play(BookMove::new(E2, E4)));
+        assert!(drill.play(drill.book_reply().unwrap()));
+        assert!(drill.play(BookMove::new(G1, F3)));
+        assert!(drill.play(drill.book_reply().unwrap()));
+        assert!(!drill.is_complete());
+        assert!(drill.play(BookMove::new(F1, B5)));
+        assert!(drill.is_complete());
+        assert_eq!(drill.book_reply(), None);
+
+        drill.reset();
+        assert_eq!(drill.deviations(), 0);
+        assert_eq!(drill.expected_moves(), vec![BookMove::new(E2, E4)]);
+    }
+
+    #[test]
+    fn test_drill_over_several_lines_accepts_each_branch() {
+        let lines = vec![
+            vec![BookMove::new(E2, E4), BookMove::new(C7, C5)],
+            vec![
+                BookMove::new(E2, E4),
+                BookMove::new(C7, C5),
+                BookMove::new(C2, C3),
+            ],
+            vec![
+                BookMove::new(E2, E4),
+                BookMove::new(C7, C5),
+                BookMove::new(B1, C3),
+            ],
+        ];
+        let mut drill = OpeningDrill::from_lines("Sicilian".to_string(), lines, Color::White);
+        drill.play(BookMove::new(E2, E4));
+        drill.play(BookMove::new(C7, C5));
+        assert_eq!(drill.expected_moves().len(), 2);
+        assert!(drill.play(BookMove::new(B1, C3)));
+        assert!(drill.is_complete());
+    }
+
+    #[test]
+    fn test_unknown_opening_is_an_error() {
+        let book = Book::default();
+        assert!(matches!(
+            OpeningDrill::new(&book, "Not An Opening", Color::White),
+            Err(DrillError::NoSuchOpening { .. })
+        ));
+    }
+}
@@ -1,18 +1,26 @@
-use std::sync::atomic::Ordering;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
 
-use crate::alpha_beta_searcher::{SearchContext, SearchError};
+use crate::alpha_beta_searcher::{
+    mate_in_moves, CurrentMoveCallback, PonderState, RootMovePenalty, SearchContext, SearchError,
+    SearchInfoCallback, SearchLimits, SearchParams, TTEntry, MAX_LIMITED_SEARCH_DEPTH,
+};
 use crate::board::color::Color;
 use crate::board::error::BoardError;
 use crate::board::piece::Piece;
-use crate::board::Board;
-use crate::book::{Book, BookMove};
+use crate::board::{Board, Variant};
+use crate::book::selection::pick_popular;
+use crate::book::{Book, BookMove, BookPolicy, BookSettings};
 use crate::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
 use crate::chess_move::chess_move::ChessMove;
 use crate::chess_search::search_best_move_with_history;
 use crate::evaluate::{self, GameEnding};
+use crate::game::learning::{position_key, Experience};
+use crate::game::move_list::{move_list_rows, MoveListRow};
+use crate::game::strength::Strength;
 use crate::input_handler::MoveInput;
 use crate::move_generator::MoveGenerator;
 use common::bitboard::Square;
@@ -40,6 +48,15 @@ impl Default for EngineConfig {
     }
 }
 
+/// A square a piece can move to, from `Engine::destinations`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Destination {
+    pub square: Square,
+    pub capture: bool,
+    /// False if the move would leave the king in check.
+    pub legal: bool,
+}
+
 /// Represents a move in the game history with its notation and score
 #[derive(Clone, Debug)]
 pub struct MoveHistoryEntry {
@@ -53,8 +70,11 @@ pub struct MoveHistoryEntry {
 pub struct GameState {
     board: Board,
     move_history: Vec<MoveHistoryEntry>,
+    starting_turn: Color,
+    starting_fullmove: usize,
     position_hashes: Vec<u64>,
     last_score: Option<i16>,
+    last_mate_in: Option<u8>,
     opening_deviation_move: Option<usize>,
     last_known_opening: Option<String>,
 }
@@ -69,10 +89,13 @@ impl GameState {
     fn new(starting_position: Board) -> Self {
         let initial_hash = starting_position.current_position_hash();
         Self {
+            starting_turn: starting_position.turn(),
+            starting_fullmove: starting_position.fullmove_clock().value() as usize,
             board: starting_position,
             move_history: Vec::new(),
             position_hashes: vec![initial_hash],
             last_score: None,
+            last_mate_in: None,
             opening_deviation_move: None,
             last_known_opening: None,
         }
@@ -83,18 +106,35 @@ impl GameState {
 pub enum EngineError {
     #[error("Invalid move")]
     InvalidMove,
+    #[error("No move to undo")]
+    NoMoveToUndo,
     #[error("Board error: {error:?}")]
     BoardError { error: BoardError },
     #[error("Search error: {error:?}")]
     SearchError { error: SearchError },
 }
 
+/// How hard the engine searches, as changed between moves from a renderer's
+/// settings menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineSettings {
+    pub depth: u8,
+    /// Time spent on each move, searching deeper than `depth` until it runs out.
+    pub movetime: Option<Duration>,
+}
+
 /// The main chess engine that manages game state and provides move generation/analysis
 pub struct Engine {
     state: GameState,
     book: Book,
     move_generator: MoveGenerator,
     search_context: SearchContext<ChessMove>,
+    search_limits: SearchLimits,
+    /// Weakened play: occasionally replace the searched move with a mistake.
+    strength: Option<Strength>,
+    book_settings: BookSettings,
+    /// Moves from earlier games, whose losses count against them.
+    experience: Option<Arc<Experience>>,
 }
 
 impl Default for Engine {
@@ -114,6 +154,10 @@ impl Engine {
             book: Book::default(),
             move_generator: MoveGenerator::default(),
             search_context: SearchContext::new(config.search_depth),
+            search_limits: SearchLimits::default(),
+            strength: None,
+            book_settings: BookSettings::default(),
+            experience: None,
         }
     }
 
@@ -131,6 +175,53 @@ impl Engine {
         enumerate_candidate_moves_with_algebraic_notation(board, current_turn, &self.move_generator)
     }
 
+    /// Squares the piece on `from` can move to in the current position, one
+    /// entry per square. Moves ruled out by a pin or check are included and
+    /// marked as not legal.
+    pub fn destinations(&self, from: Square) -> Vec<Destination> {
+        let mut board = self.state.board.clone();
+        let turn = board.turn();
+        let legal = self.move_generator.generate_moves(&mut board, turn);
+        let pseudo_legal = self
+            .move_generator
+            .generate_pseudo_legal_moves(&mut board, turn);
+
+        let mut destinations: Vec<Destination> = Vec::new();
+        for chess_move in pseudo_legal.iter().filter(|m| m.from_square() == from) {
+            let square = chess_move.to_square();
+            if destinations.iter().any(|d| d.square == square) {
+                // The other promotions to the same square
+                continue;
+            }
+            destinations.push(Destination {
+                square,
+                capture: chess_move.captures().is_some(),
+                legal: legal.contains(chess_move),
+            });
+        }
+        destinations
+    }
+
+    /// Whether the side to move in `board` is in check.
+    pub fn is_in_check(&self, board: &Board) -> bool {
+        board.is_in_check(board.turn())
+    }
+
+    /// Whether moving the piece on `from` to `to` is a legal pawn promotion,
+    /// which needs a piece to promote to.
+    pub fn is_promotion(&self, from: Square, to: Square) -> bool {
+        let mut board = self.state.board.clone();
+        let turn = board.turn();
+        self.move_generator
+            .generate_moves(&mut board, turn)
+            .iter()
+            .any(|m| {
+                matches!(m, ChessMove::PawnPromotion(_))
+                    && m.from_square() == from
+                    && m.to_square() == to
+            })
+    }
+
     pub fn check_game_over(&mut self) -> Option<GameEnding> {
         let turn = self.state.board.turn();
         evaluate::game_ending(
@@ -182,10 +273,17 @@ impl Engine {
     }
 
     pub fn make_move_algebraic(&mut self, algebraic: String) -> Result<ChessMove, EngineError> {
+        // Accept SAN with or without the check and checkmate suffixes
+        let without_suffix = |san: &str| san.trim_end_matches(['+', '#']).to_string();
         let valid_moves = self.get_valid_moves();
         let (chess_move, notation) = valid_moves
             .iter()
             .find(|(_, n)| n == &algebraic)
+            .or_else(|| {
+                valid_moves
+                    .iter()
+                    .find(|(_, n)| without_suffix(n) == without_suffix(&algebraic))
+            })
             .ok_or(EngineError::InvalidMove)?
             .clone();
 
@@ -193,6 +291,32 @@ impl Engine {
         Ok(chess_move)
     }
 
+    /// Apply a move in UCI long algebraic notation (e.g. "e2e4", "e7e8q").
+    pub fn make_move_uci(&mut self, uci_move: &str) -> Result<ChessMove, EngineError> {
+        if uci_move.len() < 4 || uci_move.len() > 5 || !uci_move.is_ascii() {
+            return Err(EngineError::InvalidMove);
+        }
+
+        let from = Square::from_algebraic(&uci_move[0..2]).ok_or(EngineError::InvalidMove)?;
+        let to = Square::from_algebraic(&uci_move[2..4]).ok_or(EngineError::InvalidMove)?;
+        let promotion = match uci_move.chars().nth(4) {
+            Some('q') => Some(Piece::Queen),
+            Some('r') => Some(Piece::Rook),
+            Some('b') => Some(Piece::Bishop),
+            Some('n') => Some(Piece::Knight),
+            Some(_) => return Err(EngineError::InvalidMove),
+            None => None,
+        };
+
+        self.make_move_by_squares_with_promotion(from, to, promotion)
+    }
+
+    /// Apply a move given in either SAN (e.g. "Nf3") or UCI notation (e.g. "g1f3").
+    pub fn make_move_san_or_uci(&mut self, notation: &str) -> Result<ChessMove, EngineError> {
+        self.make_move_algebraic(notation.to_string())
+            .or_else(|_| self.make_move_uci(notation))
+    }
+
     pub fn get_best_move(&mut self) -> Result<ChessMove, EngineError> {
         // Try opening book first
         let book_move = self.get_book_move();
@@ -200,6 +324,10 @@ impl Engine {
             return Ok(chess_move);
         }
 
+        if let Some(chess_move) = self.strength_mistake() {
+            return Ok(chess_move);
+        }
+
         // Fall back to search
         self.get_best_move_from_search()
     }
@@ -242,6 +370,49 @@ impl Engine {
         Ok(best_move)
     }
 
+    /// Principal variation beginning with `first_move`, continued with the best
+    /// replies stored in the transposition table by the last search. Returns
+    /// (move, SAN) pairs and stops after `max_length` moves or when the table has
+    /// no legal continuation. The board is left unchanged.
+    pub fn principal_variation(
+        &mut self,
+        first_move: &ChessMove,
+        max_length: usize,
+    ) -> Vec<(ChessMove, String)> {
+        let mut line: Vec<(ChessMove, String)> = Vec::new();
+        let mut next = Some(first_move.clone());
+
+        while let Some(candidate) = next.take() {
+            if line.len() >= max_length {
+                break;
+            }
+            let legal = self
+                .get_valid_moves()
+                .into_iter()
+                .find(|(m, _)| m.to_uci() == candidate.to_uci());
+            let (chess_move, notation) = match legal {
+                Some(entry) => entry,
+                None => break,
+            };
+            if chess_move.apply(&mut self.state.board).is_err() {
+                break;
+            }
+            self.state.board.toggle_turn();
+            line.push((chess_move, notation));
+
+            let hash = self.state.board.current_position_hash();
+            next = self.search_context.tt_best_move(hash);
+        }
+
+        for (chess_move, _) in line.iter().rev() {
+            self.state.board.toggle_turn();
+            chess_move
+                .undo(&mut self.state.board)
+                .expect("undoing a PV move should succeed");
+        }
+        line
+    }
+
     pub fn get_score(&mut self, current_turn: Color) -> i16 {
         evaluate::score(&mut self.state.board, &self.move_generator, current_turn, 0)
     }
@@ -251,13 +422,13 @@ impl Engine {
             positions_searched: self.search_context.searched_position_count(),
             depth: self.search_context.search_depth(),
             last_score: self.state.last_score,
+            mate_in: self.state.last_mate_in,
             last_search_duration: self.search_context.last_search_duration(),
         }
     }
 
     pub fn get_book_line_name(&self) -> Option<String> {
-        let line = self.get_book_line();
-        let current_opening = self.book.get_line(line);
+        let current_opening = self.book.get_line(&self.book_position_keys());
 
         // Return current opening if available, otherwise return last known opening
         current_opening.or_else(|| self.state.last_known_opening.clone())
@@ -271,6 +442,27 @@ impl Engine {
         &self.state.move_history
     }
 
+    /// The position after the first `ply` moves of the game, or None if fewer
+    /// moves have been played.
+    pub fn board_at_ply(&self, ply: usize) -> Option<Board> {
+        let later_moves = self.state.move_history.get(ply..)?;
+        let mut board = self.state.board.clone();
+        for entry in later_moves.iter().rev() {
+            board.toggle_turn();
+            entry.chess_move.undo(&mut board).ok()?;
+        }
+        Some(board)
+    }
+
+    /// The moves played so far, numbered by full move.
+    pub fn move_list(&self) -> Vec<MoveListRow<'_>> {
+        move_list_rows(
+            &self.state.move_history,
+            self.state.starting_turn,
+            self.state.starting_fullmove,
+        )
+    }
+
     pub fn opening_deviation_move(&self) -> Option<usize> {
         self.state.opening_deviation_move
     }
@@ -310,14 +502,12 @@ impl Engine {
         // Check if this move deviates from the opening book
         if self.state.opening_deviation_move.is_none() {
             // Save the current opening name before checking deviation
-            let current_line = self.get_book_line();
-            if let Some(opening_name) = self.book.get_line(current_line) {
+            if let Some(opening_name) = self.book.get_line(&self.book_position_keys()) {
                 self.state.last_known_opening = Some(opening_name);
             }
 
-            let next_moves = self.book.get_next_moves(self.get_book_line());
-            let book_move =
-                crate::book::BookMove::new(chess_move.from_square(), chess_move.to_square());
+            let next_moves = self.book.get_move_popularity(&self.state.board);
+            let book_move = BookMove::new(chess_move.from_square(), chess_move.to_square());
             let is_in_book = next_moves.iter().any(|(mv, _)| *mv == book_move);
 
             if !is_in_book {
@@ -346,33 +536,122 @@ impl Engine {
                 self.make_move_by_squares(from_square, to_square)
             }
             MoveInput::Algebraic { notation } => self.make_move_algebraic(notation),
+            MoveInput::Uci { uci_move } => self.make_move_uci(&uci_move),
             MoveInput::UseEngine => self.make_best_move(),
+            // Takebacks, hints, saving, changes to the view and mouse clicks are
+            // handled by the game loop, not played as moves.
+            MoveInput::Undo
+            | MoveInput::Resign
+            | MoveInput::MainLine
+            | MoveInput::Hint { .. }
+            | MoveInput::SaveGame { .. }
+            | MoveInput::LoadGame { .. }
+            | MoveInput::ChooseGameFile { .. }
+            | MoveInput::Resize
+            | MoveInput::Browse { .. }
+            | MoveInput::FlipBoard
+            | MoveInput::ToggleAnalysis
+            | MoveInput::TogglePieces
+            | MoveInput::OpenSettings
+            | MoveInput::Mouse { .. } => Err(EngineError::InvalidMove),
+        }
+    }
+
+    /// Take back the last move, restoring the board, move history and repetition
+    /// history, and the score to the one the remaining last move was played
+    /// with. Expects the turn to have been toggled and the position hash
+    /// recorded after the move, as the game loop does.
+    pub fn undo_move(&mut self) -> Result<ChessMove, EngineError> {
+        let entry = self
+            .state
+            .move_history
+            .pop()
+            .ok_or(EngineError::NoMoveToUndo)?;
+        self.state.position_hashes.pop();
+        self.state.board.toggle_turn();
+        entry
+            .chess_move
+            .undo(&mut self.state.board)
+            .map_err(|error| EngineError::BoardError { error })?;
+
+        // The evaluation of the position taken back no longer applies
+        self.state.last_score = self.state.move_history.last().and_then(|entry| entry.score);
+        self.state.last_mate_in = None;
+
+        let plies = self.state.move_history.len();
+        if self
+            .state
+            .opening_deviation_move
+            .is_some_and(|deviation| deviation > plies)
+        {
+            self.state.opening_deviation_move = None;
         }
+        Ok(entry.chess_move)
     }
 
     // Private helper methods
 
     fn get_book_move(&mut self) -> Option<ChessMove> {
-        let current_turn = self.state.board.turn();
-        let line = self.get_book_line();
-        let candidate_moves = self.book.get_next_moves(line);
-
-        if candidate_moves.is_empty() {
+        // The book's lines are for standard chess
+        if self.state.board.variant() != Variant::Standard
+            || !self.book_settings.in_book(self.state.move_history.len())
+        {
             return None;
         }
-
-        // Pick random book move
-        let (book_move, _) = &candidate_moves[fastrand::usize(..candidate_moves.len())];
-        let from_square = book_move.from_square();
-        let to_square = book_move.to_square();
+        let current_turn = self.state.board.turn();
+        let candidate_moves = self.book.get_move_popularity(&self.state.board);
 
         let candidates = self
             .move_generator
             .generate_moves(&mut self.state.board, current_turn);
+        let to_chess_move = |book_move: BookMove| {
+            candidates
+                .iter()
+                .find(|m| {
+                    m.from_square() == book_move.from_square()
+                        && m.to_square() == book_move.to_square()
+                })
+                .cloned()
+        };
 
-        candidates
-            .into_iter()
-            .find(|m| m.from_square() == from_square && m.to_square() == to_square)
+        match self.book_settings.policy {
+            BookPolicy::Popular => pick_popular(&candidate_moves).and_then(to_chess_move),
+            BookPolicy::Variety => {
+                if candidate_moves.is_empty() {
+                    return None;
+                }
+                let (book_move, _) = candidate_moves[fastrand::usize(..candidate_moves.len())];
+                to_chess_move(book_move)
+            }
+            BookPolicy::Best => {
+                // Candidates come most popular first, which wins ties
+                let mut best: Option<(i16, ChessMove)> = None;
+                for (book_move, _) in candidate_moves {
+                    let Some(chess_move) = to_chess_move(book_move) else {
+                        continue;
+                    };
+                    let mut board = self.state.board.clone();
+                    if chess_move.apply(&mut board).is_err() {
+                        continue;
+                    }
+                    board.toggle_turn();
+                    let next_turn = board.turn();
+                    let score = evaluate::score(&mut board, &self.move_generator, next_turn, 0);
+                    let score = if current_turn.maximize_score() {
+                        score
+                    } else {
+                        -score
+                    };
+                    if best
+                        .as_ref()
+                        .is_none_or(|(best_score, _)| score > *best_score)
+                    {
+                        best = Some((score, chess_move));
+                    }
+                }
+                best.map(|(_, chess_move)| chess_move)
+            }
+        }
     }
 
     pub fn get_best_move_with_time_limit(
@@ -402,6 +681,166 @@ impl Engine {
         result
     }
 
+    /// Search within `limits`, which replace the configured ones for this
+    /// search, until the `search_stop_flag` is set, e.g. by a UCI `stop` from
+    /// another thread. A stopped search still returns the best move of the
+    /// last depth it completed. Like `ponder`, this does not clear the stop
+    /// flag first, so a stop requested before the search starts is honored.
+    pub fn get_best_move_until_stopped(
+        &mut self,
+        limits: SearchLimits,
+    ) -> Result<ChessMove, EngineError> {
+        if let Some(chess_move) = self.get_book_move() {
+            return Ok(chess_move);
+        }
+        if let Some(chess_move) = self.strength_mistake() {
+            return Ok(chess_move);
+        }
+
+        let saved_depth = self.search_context.search_depth();
+        self.search_context.set_limits(limits);
+        self.search_context
+            .set_root_move_penalty(self.root_move_penalty());
+        let result = self.search_current_position();
+        self.search_context.set_root_move_penalty(None);
+        self.search_context.set_limits(SearchLimits {
+            depth: Some(saved_depth),
+            ..SearchLimits::default()
+        });
+        result
+    }
+
+    /// Search the current position under the configured depth and limits without
+    /// consulting the opening book, so every position gets a score. Used for
+    /// analysis; unlike `get_best_move` it does not poll for Ctrl-C.
+    pub fn analyze_position(&mut self) -> Result<ChessMove, EngineError> {
+        let saved_depth = self.apply_search_limits();
+        let result = self.run_search();
+        self.clear_search_limits(saved_depth);
+        result
+    }
+
+    /// Suggest a move for the side to move from a search of at most `movetime`.
+    /// The board, move history and the score of the last move played are left
+    /// unchanged; the transposition table keeps what the search learned.
+    pub fn hint(&mut self, movetime: Duration, pv_length: usize) -> Result<Hint, EngineError> {
+        let search_limits = self.search_limits;
+        let last_score = self.state.last_score;
+        let last_mate_in = self.state.last_mate_in;
+        self.search_limits = SearchLimits {
+            movetime: Some(movetime),
+            ..search_limits
+        };
+        let result = self.analyze_position();
+        self.search_limits = search_limits;
+        let score = std::mem::replace(&mut self.state.last_score, last_score);
+        self.state.last_mate_in = last_mate_in;
+
+        let best_move = result?;
+        let pv: Vec<String> = self
+            .principal_variation(&best_move, pv_length.max(1))
+            .into_iter()
+            .map(|(_, san)| san)
+            .collect();
+        Ok(Hint {
+            san: pv.first().cloned().unwrap_or_else(|| best_move.to_uci()),
+            pv,
+            score,
+        })
+    }
+
+    /// Search the position after each legal move to at most `max_depth` plies,
+    /// sharing `budget` between the moves, and return them best first for the
+    /// side to move. Moves whose search fails are left out. The game state is
+    /// left unchanged.
+    pub fn evaluate_moves(&mut self, budget: Duration, max_depth: u8) -> Vec<MoveEvaluation> {
+        let maximize = self.state.board.turn().maximize_score();
+        let saved_depth = self.search_context.search_depth();
+        self.search_context.set_depth(max_depth.max(1));
+        let deadline = Instant::now() + budget;
+
+        let valid_moves = self.get_valid_moves();
+        let mut evaluations = Vec::new();
+        for (index, (chess_move, san)) in valid_moves.iter().cloned().enumerate() {
+            let moves_left = (valid_moves.len() - index) as u32;
+            let movetime = deadline.saturating_duration_since(Instant::now()) / moves_left;
+            self.search_context.set_time_limit(Some(movetime));
+
+            let mut board = self.state.board.clone();
+            if chess_move.apply(&mut board).is_err() {
+                continue;
+            }
+            board.toggle_turn();
+            let mut position_hashes = self.state.position_hashes.clone();
+            position_hashes.push(board.current_position_hash());
+            let contempt = Self::contempt_for(board.turn());
+
+            self.search_context.clear_stop();
+            let result = search_best_move_with_history(
+                &mut self.search_context,
+                &mut board,
+                position_hashes,
+                contempt,
+            );
+            let score = self.search_context.last_score();
+            let depth = self.search_context.last_completed_depth();
+            if let (Ok(_), Some(score), Some(depth)) = (result, score, depth) {
+                evaluations.push(MoveEvaluation {
+                    chess_move,
+                    san,
+                    score,
+                    depth,
+                });
+            }
+        }
+
+        self.search_context.set_time_limit(None);
+        self.search_context.set_depth(saved_depth);
+        evaluations.sort_by_key(|evaluation| {
+            let score = if maximize {
+                evaluation.score.saturating_neg()
+            } else {
+                evaluation.score
+            };
+            (score, std::cmp::Reverse(evaluation.depth))
+        });
+        evaluations
+    }
+
+    /// Deepest iteration the last search completed.
+    pub fn last_completed_depth(&self) -> Option<u8> {
+        self.search_context.last_completed_depth()
+    }
+
+    /// Report each completed depth of later searches to `callback`.
+    pub fn set_search_info_callback(&mut self, callback: Option<SearchInfoCallback<ChessMove>>) {
+        self.search_context.set_info_callback(callback);
+    }
+
+    /// Report each root move of later searches to `callback` as its search
+    /// starts.
+    pub fn set_search_current_move_callback(
+        &mut self,
+        callback: Option<CurrentMoveCallback<ChessMove>>,
+    ) {
+        self.search_context.set_current_move_callback(callback);
+    }
+
+    /// Search with tuned heuristic constants instead of the defaults.
+    pub fn set_search_params(&mut self, params: SearchParams) {
+        self.search_context.set_params(params);
+    }
+
+    /// Size the transposition table to at most `size_mb` megabytes, emptying it.
+    pub fn set_hash_size(&mut self, size_mb: usize) {
+        self.search_context.set_hash_size(size_mb);
+    }
+
+    /// The number of positions the transposition table has room for.
+    pub fn hash_capacity(&self) -> usize {
+        self.search_context.tt_capacity()
+    }
+
     pub fn set_search_depth(&mut self, depth: u8) {
         self.search_context.set_depth(depth);
     }
@@ -410,8 +849,194 @@ impl Engine {
         self.search_context.search_depth()
     }
 
+    /// Bound `get_best_move` and `make_best_move` by time and/or nodes instead of depth.
+    pub fn set_search_limits(&mut self, limits: SearchLimits) {
+        self.search_limits = limits;
+    }
+
+    pub fn search_limits(&self) -> SearchLimits {
+        self.search_limits
+    }
+
+    /// The reply the engine expects from the side to move: the best move stored
+    /// in the transposition table for the current position, if it is legal.
+    pub fn predicted_reply(&mut self) -> Option<ChessMove> {
+        let mut board = self.state.board.clone();
+        self.stored_legal_move(&mut board)
+    }
+
+    /// The reply the engine expects to `chess_move` from the current position,
+    /// e.g. to ponder on after playing it.
+    pub fn expected_reply_to(&self, chess_move: &ChessMove) -> Option<ChessMove> {
+        let mut board = self.state.board.clone();
+        chess_move.apply(&mut board).ok()?;
+        board.toggle_turn();
+        self.stored_legal_move(&mut board)
+    }
+
+    /// The best move stored in the transposition table for `board`, if it is
+    /// legal there.
+    fn stored_legal_move(&self, board: &mut Board) -> Option<ChessMove> {
+        let stored = self
+            .search_context
+            .tt_best_move(board.current_position_hash())?;
+        let turn = board.turn();
+        self.move_generator
+            .generate_moves(board, turn)
+            .into_iter()
+            .find(|chess_move| chess_move.to_uci() == stored.to_uci())
+    }
+
+    /// What earlier searches stored in the transposition table for the
+    /// position with `hash`, if it is still there.
+    pub fn stored_analysis(&self, hash: u64) -> Option<TTEntry<ChessMove>> {
+        self.search_context.tt_entry(hash)
+    }
+
+    /// Everything the transposition table has learned so far, for saving with
+    /// the game and passing to `restore_analysis` in a later session.
+    pub fn analysis_snapshot(&self) -> Vec<(u64, TTEntry<ChessMove>)> {
+        self.search_context.tt_entries()
+    }
+
+    /// Load transposition table entries from `analysis_snapshot`.
+    pub fn restore_analysis(&mut self, entries: Vec<(u64, TTEntry<ChessMove>)>) {
+        self.search_context.tt_restore(entries);
+    }
+
+    /// Flag that stops the current search, e.g. `ponder` from another thread.
+    pub fn search_stop_flag(&self) -> Arc<AtomicBool> {
+        self.search_context.stop_flag()
+    }
+
+    /// Suspends the time limit of `get_best_move_until_stopped` while pondering
+    /// on the opponent's time, e.g. for UCI `go ponder` until `ponderhit`.
+    pub fn search_ponder_state(&self) -> Arc<PonderState> {
+        self.search_context.ponder_state()
+    }
+
+    /// Think on the opponent's time: search the position after `predicted` until
+    /// the `search_stop_flag` is set, filling the transposition table for the
+    /// engine's reply. The game state is left unchanged. Unlike other searches
+    /// this does not clear the stop flag, so a stop requested before pondering
+    /// starts is honored.
+    pub fn ponder(&mut self, predicted: &ChessMove) {
+        let mut board = self.state.board.clone();
+        if predicted.apply(&mut board).is_err() {
+            return;
+        }
+        board.toggle_turn();
+        let mut position_hashes = self.state.position_hashes.clone();
+        position_hashes.push(board.current_position_hash());
+        let contempt = Self::contempt_for(board.turn());
+
+        let saved_depth = self.search_context.search_depth();
+        self.search_context.set_depth(MAX_LIMITED_SEARCH_DEPTH);
+        // The result is discarded; stopping mid-search reports an error.
+        let _ = search_best_move_with_history(
+            &mut self.search_context,
+            &mut board,
+            position_hashes,
+            contempt,
+        );
+        self.search_context.set_depth(saved_depth);
+    }
+
+    /// Hold the losses of earlier games in `experience` against the moves that
+    /// lost them when choosing a move; see `game::learning`.
+    pub fn set_experience(&mut self, experience: Option<Arc<Experience>>) {
+        self.experience = experience;
+    }
+
+    /// The penalty `experience` holds against each move in the current
+    /// position, for a search choosing the move to play.
+    fn root_move_penalty(&self) -> Option<RootMovePenalty<ChessMove>> {
+        let experience = Arc::clone(self.experience.as_ref()?);
+        let position = position_key(&self.state.board);
+        Some(Arc::new(move |chess_move: &ChessMove| {
+            experience.penalty(&position, chess_move)
+        }))
+    }
+
+    /// Whether the engine plays from its opening book, how it picks among the
+    /// book moves, and for how many moves.
+    pub fn set_book_settings(&mut self, book_settings: BookSettings) {
+        self.book_settings = book_settings;
+    }
+
+    /// Let `get_best_move` and `get_best_move_until_stopped` make deliberate
+    /// mistakes at the rate set by `strength`. The depth and node budget for
+    /// the strength are set separately, e.g. with `Strength::limit_search`.
+    pub fn set_strength(&mut self, strength: Option<Strength>) {
+        self.strength = strength;
+    }
+
+    /// A deliberate mistake in place of searching, now and then as set by
+    /// `set_strength`. The move has no score.
+    fn strength_mistake(&mut self) -> Option<ChessMove> {
+        let strength = self.strength?;
+        if fastrand::f64() >= strength.mistake_rate() {
+            return None;
+        }
+        let chess_move = self.mistake_move(strength.mistake_noise())?;
+        self.state.last_score = None;
+        self.state.last_mate_in = None;
+        Some(chess_move)
+    }
+
+    /// The move that looks best one ply deep, with up to `noise` centipawns of
+    /// random error on each candidate's evaluation.
+    fn mistake_move(&mut self, noise: i16) -> Option<ChessMove> {
+        let turn = self.state.board.turn();
+        let candidates = self
+            .move_generator
+            .generate_moves(&mut self.state.board, turn);
+
+        let mut best: Option<(i16, ChessMove)> = None;
+        for chess_move in candidates {
+            let mut board = self.state.board.clone();
+            if chess_move.apply(&mut board).is_err() {
+                continue;
+            }
+            board.toggle_turn();
+            let next_turn = board.turn();
+            let score = evaluate::score(&mut board, &self.move_generator, next_turn, 0);
+            let score = if turn.maximize_score() { score } else { -score };
+            let noisy = score.saturating_add(fastrand::i16(-noise..=noise));
+            if best
+                .as_ref()
+                .is_none_or(|(best_score, _)| noisy > *best_score)
+            {
+                best = Some((noisy, chess_move));
+            }
+        }
+        best.map(|(_, chess_move)| chess_move)
+    }
+
+    /// Apply `search_limits` to the search context, returning the depth to restore.
+    fn apply_search_limits(&mut self) -> u8 {
+        let saved_depth = self.search_context.search_depth();
+        if self.search_limits != SearchLimits::default() {
+            self.search_context.set_limits(self.search_limits);
+        }
+        saved_depth
+    }
+
+    fn clear_search_limits(&mut self, saved_depth: u8) {
+        if self.search_limits != SearchLimits::default() {
+            self.search_context.set_limits(SearchLimits {
+                depth: Some(saved_depth),
+                ..SearchLimits::default()
+            });
+        }
+    }
+
     fn contempt(&self) -> i16 {
-        if self.state.board.turn().maximize_score() {
+        Self::contempt_for(self.state.board.turn())
+    }
+
+    fn contempt_for(turn: Color) -> i16 {
+        if turn.maximize_score() {
             -CONTEMPT_VALUE // White searching: draws score slightly negative (bad for White)
         } else {
             CONTEMPT_VALUE // Black searching: draws score slightly positive (bad for Black)
@@ -421,6 +1046,10 @@ impl Engine {
     /// Core search without Ctrl-C polling. Used by time-limited and UCI search paths.
     fn run_search(&mut self) -> Result<ChessMove, EngineError> {
         self.search_context.clear_stop();
+        self.search_current_position()
+    }
+
+    fn search_current_position(&mut self) -> Result<ChessMove, EngineError> {
         let contempt = self.contempt();
         let move_result = search_best_move_with_history(
             &mut self.search_context,
@@ -429,10 +1058,20 @@ impl Engine {
             contempt,
         );
         let best_move = move_result.map_err(|err| EngineError::SearchError { error: err })?;
-        self.state.last_score = self.search_context.last_score();
+        self.record_search_score();
         Ok(best_move)
     }
 
+    /// Keep the score of the last search and, for a forced mate, the number
+    /// of moves to it.
+    fn record_search_score(&mut self) {
+        let score = self.search_context.last_score();
+        self.state.last_score = score;
+        self.state.last_mate_in = score
+            .and_then(mate_in_moves)
+            .map(|moves| moves.unsigned_abs() as u8);
+    }
+
     fn get_best_move_from_search(&mut self) -> Result<ChessMove, EngineError> {
         self.search_context.clear_stop();
         let stop_flag = self.search_context.stop_flag();
@@ -455,12 +1094,17 @@ impl Engine {
         });
 
         let contempt = self.contempt();
+        let saved_depth = self.apply_search_limits();
+        self.search_context
+            .set_root_move_penalty(self.root_move_penalty());
         let move_result = search_best_move_with_history(
             &mut self.search_context,
             &mut self.state.board,
             self.state.position_hashes.clone(),
             contempt,
         );
+        self.search_context.set_root_move_penalty(None);
+        self.clear_search_limits(saved_depth);
 
         // Check if user requested stop before we overwrite the flag for the polling thread
         let was_stopped = self.search_context.should_stop();
@@ -478,45 +1122,226 @@ impl Engine {
         }
 
         let best_move = move_result.map_err(|err| EngineError::SearchError { error: err })?;
-        self.state.last_score = self.search_context.last_score();
+        self.record_search_score();
 
         Ok(best_move)
     }
 
-    fn get_book_line(&self) -> Vec<BookMove> {
-        self.state
-            .move_history
-            .iter()
-            .map(|entry| {
-                BookMove::new(entry.chess_move.from_square(), entry.chess_move.to_square())
-            })
-            .collect()
+    /// The book key of each position of the game so far, in order.
+    fn book_position_keys(&self) -> Vec<u64> {
+        let Some(mut board) = self.board_at_ply(0) else {
+            return vec![];
+        };
+        let mut keys = vec![Book::key(&board)];
+        for entry in &self.state.move_history {
+            if entry.chess_move.apply(&mut board).is_err() {
+                break;
+            }
+            board.toggle_turn();
+            keys.push(Book::key(&board));
+        }
+        keys
     }
 }
 
+/// A suggested move for the side to move, from `Engine::hint`.
+#[derive(Debug, Clone)]
+pub struct Hint {
+    /// The suggested move in SAN.
+    pub san: String,
+    /// Principal variation in SAN, starting with the suggested move.
+    pub pv: Vec<String>,
+    /// Score from White's perspective.
+    pub score: Option<i16>,
+}
+
+/// A legal move scored by `Engine::evaluate_moves`.
+#[derive(Debug, Clone)]
+pub struct MoveEvaluation {
+    pub chess_move: ChessMove,
+    pub san: String,
+    /// Score from White's perspective.
+    pub score: i16,
+    /// Depth the position after the move was searched to.
+    pub depth: u8,
+}
+
 /// Search performance statistics
 #[derive(Debug, Clone)]
 pub struct SearchStats {
     pub positions_searched: usize,
     pub depth: u8,
     pub last_score: Option<i16>,
+    /// Moves to checkmate when `last_score` is a forced mate, if the line to
+    /// it is known.
+    pub mate_in: Option<u8>,
     pub last_search_duration: Option<Duration>,
 }
 
+/// Play `moves` (SAN or UCI, in order) from `starting_position` and return the
+/// resulting position. Fails with a message naming the first illegal move.
+pub fn position_after_moves(starting_position: Board, moves: &[String]) -> Result<Board, String> {
+    let mut engine = Engine::with_config(EngineConfig {
+        starting_position,
+        ..EngineConfig::default()
+    });
+    for (index, notation) in moves.iter().enumerate() {
+        engine
+            .make_move_san_or_uci(notation)
+            .map_err(|_| format!("Illegal move `{}` (move {})", notation, index + 1))?;
+        engine.board_mut().toggle_turn();
+        engine.record_position_hash();
+    }
+    Ok(engine.board().clone())
+}
+
 // Tests
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use super::*;
-    use crate::board::castle_rights::CastleRights;
     use crate::board::piece::Piece;
     use crate::chess_move::standard::StandardChessMove;
     use crate::chess_position;
     use crate::{checkmate_move, std_move};
     use common::bitboard::*;
 
+    #[test]
+    fn test_node_limit_bounds_search() {
+        let starting_position = Board::from_str(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+        )
+        .expect("valid FEN");
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 4,
+            starting_position,
+        });
+        engine.set_search_limits(SearchLimits {
+            movetime: None,
+            nodes: Some(20_000),
+            ..SearchLimits::default()
+        });
+
+        assert!(engine.get_best_move().is_ok());
+        // The budget is checked every 4096 nodes, per thread.
+        let nodes = engine.get_search_stats().positions_searched;
+        assert!(nodes < 20_000 + 4096 * 64, "searched {} nodes", nodes);
+        assert_eq!(engine.search_depth(), 4, "configured depth is restored");
+    }
+
+    #[test]
+    fn test_principal_variation_starts_with_best_move() {
+        let starting_position =
+            Board::from_str("k7/8/2K5/8/8/8/8/7R w - - 0 1").expect("valid FEN");
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 4,
+            starting_position,
+        });
+        let fen_before = engine.board().to_fen();
+
+        let best_move = engine.get_best_move().unwrap();
+        let pv = engine.principal_variation(&best_move, 4);
+
+        assert!(!pv.is_empty());
+        assert_eq!(pv[0].0.to_uci(), best_move.to_uci());
+        assert_eq!(engine.board().to_fen(), fen_before);
+    }
+
+    #[test]
+    fn test_position_after_moves_accepts_san_and_uci() {
+        let moves: Vec<String> = ["e4", "e7e5", "Nf3", "b8c6"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        let board = position_after_moves(Board::default(), &moves).unwrap();
+        assert!(board
+            .to_fen()
+            .starts_with("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq -"));
+
+        let illegal = vec!["e4".to_string(), "e4".to_string()];
+        assert!(position_after_moves(Board::default(), &illegal).is_err());
+    }
+
+    #[test]
+    fn test_position_after_moves_keeps_en_passant_and_castling_state() {
+        let moves: Vec<String> = ["e4", "a6", "e5", "d5", "Ke2", "a5"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        let board = position_after_moves(Board::default(), &moves[..4]).unwrap();
+        let mut engine = Engine::with_config(EngineConfig {
+            starting_position: board,
+            ..EngineConfig::default()
+        });
+        assert!(engine.make_move_san_or_uci("exd6").is_ok());
+
+        let board = position_after_moves(Board::default(), &moves).unwrap();
+        let fen = board.to_fen();
+        assert!(fen.contains(" w kq - "), "got {}", fen);
+        let mut engine = Engine::with_config(EngineConfig {
+            starting_position: Board::from_str(&fen).unwrap(),
+            ..EngineConfig::default()
+        });
+        assert!(engine.make_move_san_or_uci("exd6").is_err());
+    }
+
+    #[test]
+    fn test_ponder_until_stopped_leaves_game_unchanged() {
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 2,
+            starting_position: position_after_moves(
+                Board::default(),
+                &["e4".to_string(), "d5".to_string(), "Nc3".to_string()],
+            )
+            .unwrap(),
+        });
+        engine.analyze_position().unwrap();
+        let predicted = engine
+            .predicted_reply()
+            .expect("searched position has a TT move");
+        let fen = engine.board().to_fen();
+
+        let stop = engine.search_stop_flag();
+        stop.store(false, Ordering::Relaxed);
+        std::thread::scope(|scope| {
+            let ponder = scope.spawn(|| engine.ponder(&predicted));
+            std::thread::sleep(Duration::from_millis(100));
+            stop.store(true, Ordering::Relaxed);
+            ponder.join().unwrap();
+        });
+
+        assert_eq!(engine.board().to_fen(), fen);
+        assert_eq!(engine.search_depth(), 2);
+        assert!(engine.move_history().is_empty());
+    }
+
+    #[test]
+    fn test_mistake_move_without_noise_grabs_material() {
+        let starting_position = chess_position! {
+            turn = Color::White, castling = "-";
+            ....k...
+            ........
+            ........
+            ...q....
+            ........
+            ........
+            ...R....
+            ....K...
+        };
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 1,
+            starting_position,
+        });
+
+        let chess_move = engine.mistake_move(0).unwrap();
+        assert_eq!(chess_move.to_uci(), "d2d5");
+    }
+
     #[test]
     fn test_find_mate_in_1_white() {
-        let mut starting_position = chess_position! {
+        let starting_position = chess_position! {
+            turn = Color::White, castling = "-";
             .Q......
             ........
             ........
@@ -526,8 +1351,6 @@ mod tests {
             k.K.....
             ........
         };
-        starting_position.set_turn(Color::White);
-        starting_position.lose_castle_rights(CastleRights::all());
 
         let mut engine = Engine::with_config(EngineConfig {
             search_depth: 4,
@@ -575,7 +1398,8 @@ mod tests {
 
     #[test]
     fn test_get_best_move_with_time_limit_finds_obvious_mate() {
-        let mut starting_position = chess_position! {
+        let starting_position = chess_position! {
+            turn = Color::White, castling = "-";
             .Q......
             ........
             ........
@@ -585,8 +1409,6 @@ mod tests {
             k.K.....
             ........
         };
-        starting_position.set_turn(Color::White);
-        starting_position.lose_castle_rights(CastleRights::all());
 
         let mut engine = Engine::with_config(EngineConfig {
             search_depth: 10,
@@ -655,7 +1477,8 @@ mod tests {
     #[test]
     fn test_check_game_over_detects_checkmate() {
         // Black king on A1, white queen on B2, white king on C3 -- black is checkmated
-        let mut position = chess_position! {
+        let position = chess_position! {
+            turn = Color::Black, castling = "-";
             ........
             ........
             ........
@@ -665,8 +1488,6 @@ mod tests {
             .Q......
             k.......
         };
-        position.set_turn(Color::Black);
-        position.lose_castle_rights(CastleRights::all());
 
         let mut engine = Engine::with_config(EngineConfig {
             search_depth: 4,
@@ -684,7 +1505,8 @@ mod tests {
     #[test]
     fn test_make_move_by_squares_with_promotion() {
         // White pawn on a7 can promote to queen on a8
-        let mut position = chess_position! {
+        let position = chess_position! {
+            turn = Color::White, castling = "-";
             ....k...
             P.......
             ........
@@ -694,8 +1516,6 @@ mod tests {
             ........
             ....K...
         };
-        position.set_turn(Color::White);
-        position.lose_castle_rights(CastleRights::all());
 
         let mut engine = Engine::with_config(EngineConfig {
             search_depth: 1,
@@ -713,7 +1533,8 @@ mod tests {
 
     #[test]
     fn test_make_move_by_squares_with_promotion_knight() {
-        let mut position = chess_position! {
+        let position = chess_position! {
+            turn = Color::White, castling = "-";
             ....k...
             P.......
             ........
@@ -723,8 +1544,6 @@ mod tests {
             ........
             ....K...
         };
-        position.set_turn(Color::White);
-        position.lose_castle_rights(CastleRights::all());
 
         let mut engine = Engine::with_config(EngineConfig {
             search_depth: 1,
@@ -739,7 +1558,8 @@ mod tests {
     #[test]
     fn test_threefold_repetition_detected() {
         // Simple position where we can repeat moves
-        let mut position = chess_position! {
+        let position = chess_position! {
+            turn = Color::White, castling = "-";
             ....k...
             ........
             ........
@@ -749,8 +1569,6 @@ mod tests {
             ........
             ....K...
         };
-        position.set_turn(Color::White);
-        position.lose_castle_rights(CastleRights::all());
 
         let mut engine = Engine::with_config(EngineConfig {
             search_depth: 1,
@@ -783,6 +1601,170 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hint_suggests_mate_without_moving() {
+        let starting_position = chess_position! {
+            turn = Color::White, castling = "-";
+            .......k
+            ......pp
+            ........
+            ........
+            ........
+            ........
+            ........
+            R.....K.
+        };
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 2,
+            starting_position,
+        });
+        let fen = engine.board().to_fen();
+
+        let hint = engine.hint(Duration::from_millis(200), 4).unwrap();
+        assert!(hint.san.starts_with("Ra8"), "got {}", hint.san);
+        assert_eq!(hint.pv.first(), Some(&hint.san));
+        assert_eq!(engine.board().to_fen(), fen);
+        assert!(engine.move_history().is_empty());
+    }
+
+    #[test]
+    fn test_undo_move_restores_position_and_repetition_history() {
+        let mut engine = Engine::new();
+        let start_fen = engine.board().to_fen();
+        for (from, to) in [(E2, E4), (E7, E5)] {
+            engine.make_move_by_squares(from, to).unwrap();
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        }
+
+        engine.undo_move().unwrap();
+        engine.undo_move().unwrap();
+        assert_eq!(engine.board().to_fen(), start_fen);
+        assert!(engine.move_history().is_empty());
+        assert_eq!(engine.position_hashes().len(), 1);
+        assert!(matches!(engine.undo_move(), Err(EngineError::NoMoveToUndo)));
+    }
+
+    #[test]
+    fn test_undo_move_restores_the_score_of_the_earlier_position() {
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 2,
+            starting_position: Board::from_str("4k3/8/8/8/8/8/3QP3/4K3 w - - 0 1")
+                .expect("valid FEN"),
+        });
+        engine.set_book_settings(BookSettings {
+            enabled: false,
+            ..BookSettings::default()
+        });
+        let play = |engine: &mut Engine, best: bool| {
+            if best {
+                engine.make_best_move().unwrap();
+            } else {
+                let (reply, _) = engine.get_valid_moves().remove(0);
+                engine.make_move_uci(&reply.to_uci()).unwrap();
+            }
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        };
+        play(&mut engine, true);
+        let first_score = engine.get_search_stats().last_score;
+        assert!(first_score.is_some());
+        play(&mut engine, false);
+        play(&mut engine, true);
+
+        engine.undo_move().unwrap();
+        assert_eq!(engine.get_search_stats().last_score, first_score);
+        engine.undo_move().unwrap();
+        engine.undo_move().unwrap();
+        assert_eq!(engine.get_search_stats().last_score, None);
+    }
+
+    #[test]
+    fn test_destinations_mark_moves_ruled_out_by_a_pin() {
+        // The e2 bishop is pinned against the king by the e8 rook
+        let starting_position =
+            Board::from_str("4r1k1/8/8/8/8/8/4B3/3QK3 w - - 0 1").expect("valid FEN");
+        let engine = Engine::with_config(EngineConfig {
+            search_depth: 1,
+            starting_position,
+        });
+
+        let bishop = engine.destinations(E2);
+        assert_eq!(bishop.len(), 8);
+        assert!(bishop.iter().all(|d| !d.legal));
+
+        let queen = engine.destinations(D1);
+        assert!(queen.contains(&Destination {
+            square: D8,
+            capture: false,
+            legal: true,
+        }));
+        assert!(engine.destinations(E4).is_empty());
+    }
+
+    #[test]
+    fn test_search_stats_count_the_moves_to_a_forced_mate() {
+        let starting_position =
+            Board::from_str("1k5r/ppp5/8/8/8/8/3Q4/K2R4 w - - 0 1").expect("valid FEN");
+        let mut engine = Engine::with_config(EngineConfig {
+            search_depth: 4,
+            starting_position,
+        });
+        engine.analyze_position().expect("search should succeed");
+        assert_eq!(engine.get_search_stats().mate_in, Some(2));
+
+        engine.make_move_algebraic("Qd8+".to_string()).unwrap();
+        engine.board_mut().toggle_turn();
+        engine.analyze_position().expect("search should succeed");
+        let stats = engine.get_search_stats();
+        assert!(stats.last_score.unwrap() > 0);
+        assert_eq!(stats.mate_in, Some(1));
+    }
+
+    #[test]
+    fn test_is_in_check_looks_at_the_side_to_move() {
+        let engine = Engine::new();
+        assert!(!engine.is_in_check(engine.board()));
+
+        let checked = Board::from_str("4r1k1/8/8/8/8/8/8/4K3 w - - 0 1").expect("valid FEN");
+        assert!(engine.is_in_check(&checked));
+        // The same position with the checking side to move
+        let mut checking = checked.clone();
+        checking.toggle_turn();
+        assert!(!engine.is_in_check(&checking));
+    }
+
+    #[test]
+    fn test_is_promotion_only_for_legal_moves_to_the_last_rank() {
+        let engine = Engine::with_config(EngineConfig {
+            starting_position: Board::from_str("3r4/4P3/8/8/8/k7/8/K7 w - - 0 1")
+                .expect("valid FEN"),
+            ..EngineConfig::default()
+        });
+        assert!(engine.is_promotion(E7, E8));
+        assert!(engine.is_promotion(E7, D8));
+        assert!(!engine.is_promotion(E7, F8));
+        assert!(!engine.is_promotion(A1, B1));
+    }
+
+    #[test]
+    fn test_board_at_ply_replays_earlier_positions() {
+        let mut engine = Engine::new();
+        let mut fens = vec![engine.board().to_fen()];
+        for (from, to) in [(E2, E4), (E7, E5), (G1, F3)] {
+            engine.make_move_by_squares(from, to).unwrap();
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+            fens.push(engine.board().to_fen());
+        }
+
+        for (ply, fen) in fens.iter().enumerate() {
+            assert_eq!(&engine.board_at_ply(ply).unwrap().to_fen(), fen);
+        }
+        assert!(engine.board_at_ply(4).is_none());
+        assert_eq!(engine.board().to_fen(), fens[3]);
+    }
+
     #[test]
     fn test_position_hashes_tracked() {
         let mut engine = Engine::new();
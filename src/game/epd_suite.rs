@@ -0,0 +1,225 @@
+//! EPD test suite runner for suites such as WAC, STS and Bratko-Kopec. Run
+//! via `chess epd`.
+//!
+//! Each position is searched for a fixed time and the move the search ends on
+//! is checked against the position's `bm` (best move) and `am` (avoid move)
+//! operations: it must be one of the best moves, and none of the moves to
+//! avoid. The time to solution is when the search settled on a solving move
+//! for good, at the first of the completed depths, up to the last, whose best
+//! move solves the position.
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::alpha_beta_searcher::{SearchContext, SearchInfo};
+use crate::board::Board;
+use crate::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use crate::chess_move::chess_move::ChessMove;
+use crate::chess_search::search_best_move;
+use crate::input_handler::epd::EpdRecord;
+use crate::move_generator::MoveGenerator;
+
+/// A suite position with the moves that solve it.
+pub struct EpdPosition {
+    pub name: String,
+    pub board: Board,
+    /// Moves from `bm`, in SAN or UCI notation.
+    pub best_moves: Vec<String>,
+    /// Moves from `am`, in SAN or UCI notation.
+    pub avoid_moves: Vec<String>,
+}
+
+impl EpdPosition {
+    /// The position of `record`, named by its `id` or else `default_name`.
+    pub fn from_record(record: &EpdRecord, default_name: &str) -> Result<Self, String> {
+        let moves = |opcode| record.operands(opcode).map(<[String]>::to_vec);
+        let best_moves = moves("bm").unwrap_or_default();
+        let avoid_moves = moves("am").unwrap_or_default();
+        if best_moves.is_empty() && avoid_moves.is_empty() {
+            return Err("no bm or am operation".to_string());
+        }
+        Ok(Self {
+            name: record.id().unwrap_or(default_name).to_string(),
+            board: record.board().map_err(|e| e.to_string())?,
+            best_moves,
+            avoid_moves,
+        })
+    }
+
+    /// What the position asks for, e.g. `bm Qg6` or `am Nf3`.
+    pub fn expected(&self) -> String {
+        let mut expected = Vec::new();
+        if !self.best_moves.is_empty() {
+            expected.push(format!("bm {}", self.best_moves.join(" ")));
+        }
+        if !self.avoid_moves.is_empty() {
+            expected.push(format!("am {}", self.avoid_moves.join(" ")));
+        }
+        expected.join("; ")
+    }
+
+    /// Whether the move written `san` (or `uci`) solves the position.
+    pub fn is_solved_by(&self, san: &str, uci: &str) -> bool {
+        let matches = |notation: &String| {
+            let notation = normalize_move(notation);
+            notation == normalize_move(san) || notation == uci
+        };
+        (self.best_moves.is_empty() || self.best_moves.iter().any(matches))
+            && !self.avoid_moves.iter().any(matches)
+    }
+}
+
+/// A move without check marks and annotations, and castling with letters,
+/// as EPD files are not consistent about either.
+fn normalize_move(notation: &str) -> String {
+    notation
+        .trim_end_matches(['+', '#', '!', '?'])
+        .replace('0', "O")
+}
+
+/// Read a suite, one EPD record per line. Every position needs a `bm` or an
+/// `am` operation; positions without an `id` are named by their line.
+pub fn load_epd_suite(path: &str) -> Result<Vec<EpdPosition>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    let mut positions = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let location = format!("{}:{}", path, line_number + 1);
+        let position = line
+            .parse()
+            .map_err(|e| format!("{}: {}", location, e))
+            .and_then(|record| {
+                EpdPosition::from_record(&record, &format!("line {}", line_number + 1))
+                    .map_err(|e| format!("{}: {}", location, e))
+            })?;
+        positions.push(position);
+    }
+    Ok(positions)
+}
+
+/// How long each position is searched.
+#[derive(Debug, Clone, Copy)]
+pub struct EpdLimits {
+    pub movetime: Duration,
+    pub max_depth: u8,
+}
+
+/// The outcome of searching one position.
+#[derive(Debug, Clone)]
+pub struct EpdResult {
+    /// The move the search ended on in SAN, or `-` without one.
+    pub found: String,
+    pub solved: bool,
+    /// When the search settled on a solving move, if it did.
+    pub time_to_solution: Option<Duration>,
+    pub elapsed: Duration,
+    pub nodes: usize,
+    pub depth: u8,
+}
+
+/// Search `position` within `limits` and check the move found.
+pub fn solve_epd_position(position: &EpdPosition, limits: &EpdLimits) -> EpdResult {
+    let mut board = position.board.clone();
+    let turn = board.turn();
+    let notations = enumerate_candidate_moves_with_algebraic_notation(
+        &mut board,
+        turn,
+        &MoveGenerator::default(),
+    );
+    let solves = |chess_move: &ChessMove| {
+        notations
+            .iter()
+            .find(|(candidate, _)| candidate == chess_move)
+            .is_some_and(|(candidate, san)| position.is_solved_by(san, &candidate.to_uci()))
+    };
+
+    let iterations: Arc<Mutex<Vec<(Duration, ChessMove)>>> = Arc::default();
+    let mut context = SearchContext::with_time_limit(limits.max_depth, limits.movetime);
+    let recorded = Arc::clone(&iterations);
+    context.set_info_callback(Some(Arc::new(move |info: &SearchInfo<ChessMove>| {
+        if let Some(best) = info.principal_variation.first() {
+            recorded.lock().unwrap().push((info.elapsed, best.clone()));
+        }
+    })));
+
+    let start = Instant::now();
+    let result = search_best_move(&mut context, &mut board);
+    let elapsed = start.elapsed();
+
+    let (found, solved) = match &result {
+        Ok(best) => {
+            let san = notations
+                .iter()
+                .find(|(candidate, _)| candidate == best)
+                .map_or_else(|| best.to_uci(), |(_, san)| san.clone());
+            (san, solves(best))
+        }
+        Err(_) => ("-".to_string(), false),
+    };
+    let time_to_solution = if solved {
+        let iterations = iterations.lock().unwrap();
+        let unsolved_from = iterations
+            .iter()
+            .rposition(|(_, best)| !solves(best))
+            .map_or(0, |last_unsolved| last_unsolved + 1);
+        Some(iterations.get(unsolved_from).map_or(elapsed, |&(at, _)| at))
+    } else {
+        None
+    };
+
+    EpdResult {
+        found,
+        solved,
+        time_to_solution,
+        elapsed,
+        nodes: context.searched_position_count(),
+        depth: context.last_completed_depth().unwrap_or(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(epd: &str) -> EpdPosition {
+        EpdPosition::from_record(&epd.parse().unwrap(), "-").unwrap()
+    }
+
+    #[test]
+    fn test_moves_match_whatever_the_marks() {
+        let mate = position("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - bm Rd8+; id \"mate\";");
+        assert!(mate.is_solved_by("Rd8#", "d1d8"));
+        assert!(!mate.is_solved_by("Rd7", "d1d7"));
+        assert_eq!(mate.expected(), "bm Rd8+");
+
+        let castle = position("4k3/8/8/8/8/8/8/4K2R w K - bm 0-0!;");
+        assert!(castle.is_solved_by("O-O", "e1g1"));
+        let avoid = position("4k3/8/8/8/8/8/8/4K2R w K - am Rh8+;");
+        assert!(avoid.is_solved_by("O-O", "e1g1"));
+        assert!(!avoid.is_solved_by("Rh8+", "h1h8"));
+    }
+
+    #[test]
+    fn test_search_solves_a_mate_and_times_it() {
+        let limits = EpdLimits {
+            movetime: Duration::from_secs(2),
+            max_depth: 3,
+        };
+        let mate = position("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - bm Rd8#;");
+        let result = solve_epd_position(&mate, &limits);
+        assert_eq!(result.found, "Rd8#");
+        assert!(result.solved);
+        assert!(result.time_to_solution.unwrap() <= result.elapsed);
+        assert!(result.nodes > 0);
+
+        let blunder = position("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - am Rd8#;");
+        let result = solve_epd_position(&blunder, &limits);
+        assert!(!result.solved);
+        assert_eq!(result.time_to_solution, None);
+    }
+}
@@ -0,0 +1,88 @@
+//! Events published by the game loop.
+//!
+//! Embedders (the TUI, a GUI, or a network layer) follow a game by registering a
+//! `GameObserver` with `GameLoop::with_observer` instead of scraping the loop's
+//! output. An `mpsc::Sender<GameEvent>` is an observer too, so events can be
+//! consumed on another thread.
+
+use std::sync::mpsc::Sender;
+
+use crate::board::color::Color;
+use crate::board::Variant;
+use crate::game::clock::GameClock;
+use crate::game::pgn::PgnResult;
+
+/// Why a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverReason {
+    Checkmate,
+    Stalemate,
+    Draw,
+    TimeForfeit,
+    /// Ended early by the adjudication rules.
+    Adjudication,
+    /// The side to move gave up.
+    Resignation,
+    /// Won by the variant's own rule, e.g. reaching the hill.
+    VariantWin(Variant),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A new game started, either the first one or after a restart.
+    GameStarted,
+    /// `mover` played `notation` (SAN). `ply` counts moves from the start of
+    /// the game, including opening moves.
+    MovePlayed {
+        mover: Color,
+        notation: String,
+        ply: usize,
+    },
+    /// The engine finished a search with `score` (centipawns, White's view).
+    EvalUpdated { score: i16, depth: u8 },
+    /// A clock started or stopped.
+    ClockUpdated { clock: GameClock },
+    /// Moves were taken back; `plies` is the number of moves removed.
+    MovesUndone { plies: usize },
+    /// Feedback for the player, e.g. a hint or the outcome of a command.
+    Message { text: String },
+    /// Something went wrong that did not end the game, e.g. invalid input.
+    Error { message: String },
+    GameOver {
+        result: PgnResult,
+        reason: GameOverReason,
+    },
+}
+
+/// Receives the events of a game as they happen.
+pub trait GameObserver {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// Forward events over a channel. Events are dropped once the receiver is gone.
+impl GameObserver for Sender<GameEvent> {
+    fn on_event(&mut self, event: &GameEvent) {
+        let _ = self.send(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_sender_forwards_events() {
+        let (mut sender, receiver) = channel();
+        sender.on_event(&GameEvent::Message {
+            text: "hello".to_string(),
+        });
+        drop(sender);
+        assert_eq!(
+            receiver.iter().collect::<Vec<_>>(),
+            vec![GameEvent::Message {
+                text: "hello".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Games saved from and loaded into an interactive session.
+//!
+//! A game is saved as PGN, or in the native format of correspondence games
+//! (see `correspondence`) when the path ends in `.json`. The native format also
+//! keeps what the engine has learned about the game, so loading it restores
+//! the engine's analysis along with the moves.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::board::Board;
+use crate::game::correspondence::{CorrespondenceError, CorrespondenceGame};
+use crate::game::engine::Engine;
+use crate::game::pgn::{parse_pgn, PgnParseError};
+use crate::input_handler::fen::FenParseError;
+
+/// Extension of games saved in the native format.
+pub const NATIVE_EXTENSION: &str = "json";
+
+/// Extension of games saved as PGN.
+pub const PGN_EXTENSION: &str = "pgn";
+
+/// Whether a game file is picked to save the game to or to load one from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameFileAction {
+    Save,
+    Load,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameFileFormat {
+    Pgn,
+    Native,
+}
+
+impl GameFileFormat {
+    /// The format of the file at `path`, by its extension: `.json` is native
+    /// and anything else is PGN.
+    pub fn of(path: &str) -> Self {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str());
+        match extension {
+            Some(extension) if extension.eq_ignore_ascii_case(NATIVE_EXTENSION) => Self::Native,
+            _ => Self::Pgn,
+        }
+    }
+}
+
+/// Whether `path` has the extension of a PGN or native game file.
+pub fn is_game_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case(PGN_EXTENSION)
+                || extension.eq_ignore_ascii_case(NATIVE_EXTENSION)
+        })
+}
+
+#[derive(Error, Debug)]
+pub enum GameFileError {
+    #[error("Failed to read `{path}`: {error}")]
+    Io { path: String, error: std::io::Error },
+    #[error("Invalid PGN in `{path}`: {error}")]
+    InvalidPgn { path: String, error: PgnParseError },
+    #[error("No game in `{path}`")]
+    NoGame { path: String },
+    #[error("Invalid starting position: {error}")]
+    InvalidStartingPosition { error: FenParseError },
+    #[error("{error}")]
+    Correspondence { error: CorrespondenceError },
+}
+
+impl From<CorrespondenceError> for GameFileError {
+    fn from(error: CorrespondenceError) -> Self {
+        Self::Correspondence { error }
+    }
+}
+
+/// A game read from a file, to be replayed from its starting position
+pub struct LoadedGame {
+    pub starting_position: Board,
+    /// Moves played from the starting position, in SAN or UCI.
+    pub moves: Vec<String>,
+    /// The saved game when the file is native, to restore the engine's
+    /// analysis from.
+    pub native: Option<CorrespondenceGame>,
+}
+
+/// Read the game saved at `path`. A PGN file with several games yields the
+/// first.
+pub fn load_game(path: &str) -> Result<LoadedGame, GameFileError> {
+    if GameFileFormat::of(path) == GameFileFormat::Native {
+        let game = CorrespondenceGame::load(path)?;
+        let starting_position = Board::from_str(&game.fen)
+            .map_err(|error| GameFileError::InvalidStartingPosition { error })?;
+        return Ok(LoadedGame {
+            starting_position,
+            moves: game.moves.clone(),
+            native: Some(game),
+        });
+    }
+
+    let text = fs::read_to_string(path).map_err(|error| GameFileError::Io {
+        path: path.to_string(),
+        error,
+    })?;
+    let games = parse_pgn(&text).map_err(|error| GameFileError::InvalidPgn {
+        path: path.to_string(),
+        error,
+    })?;
+    let game = games
+        .into_iter()
+        .next()
+        .ok_or_else(|| GameFileError::NoGame {
+            path: path.to_string(),
+        })?;
+    Ok(LoadedGame {
+        starting_position: game
+            .starting_position()
+            .map_err(|error| GameFileError::InvalidStartingPosition { error })?,
+        moves: game.moves().iter().map(|m| m.san.clone()).collect(),
+        native: None,
+    })
+}
+
+/// Save the moves of `engine`, played from `starting_position`, to `path` in
+/// the native format, along with the engine's analysis.
+pub fn save_native(
+    engine: &Engine,
+    starting_position: &Board,
+    path: &str,
+) -> Result<(), GameFileError> {
+    let mut game = CorrespondenceGame::new(starting_position);
+    game.update(engine, Duration::ZERO, &[]);
+    game.save(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::pgn::PgnGame;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("game_file_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_format_follows_the_extension() {
+        assert_eq!(
+            GameFileFormat::of("games/today.json"),
+            GameFileFormat::Native
+        );
+        assert_eq!(GameFileFormat::of("today.JSON"), GameFileFormat::Native);
+        assert_eq!(GameFileFormat::of("today.pgn"), GameFileFormat::Pgn);
+        assert_eq!(GameFileFormat::of("today"), GameFileFormat::Pgn);
+        assert!(is_game_file(Path::new("a.pgn")));
+        assert!(!is_game_file(Path::new("a.txt")));
+    }
+
+    #[test]
+    fn test_saved_games_load_in_either_format() {
+        let mut engine = Engine::new();
+        for notation in ["e4", "e5", "Nf3"] {
+            engine.make_move_san_or_uci(notation).unwrap();
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        }
+
+        let native = temp_path("game.json");
+        save_native(&engine, &Board::default(), &native).unwrap();
+        let loaded = load_game(&native).unwrap();
+        assert_eq!(loaded.moves, vec!["e2e4", "e7e5", "g1f3"]);
+        assert!(loaded.native.is_some());
+        fs::remove_file(&native).unwrap();
+
+        let pgn_path = temp_path("game.pgn");
+        let mut pgn = PgnGame::new(&Board::default());
+        for entry in engine.move_history() {
+            pgn.push_move(&entry.notation, None);
+        }
+        pgn.write_to_file(&pgn_path).unwrap();
+        let loaded = load_game(&pgn_path).unwrap();
+        assert_eq!(loaded.moves, vec!["e4", "e5", "Nf3"]);
+        assert_eq!(loaded.starting_position.to_fen(), Board::default().to_fen());
+        assert!(loaded.native.is_none());
+        fs::remove_file(&pgn_path).unwrap();
+
+        assert!(matches!(
+            load_game(&temp_path("missing.pgn")),
+            Err(GameFileError::Io { .. })
+        ));
+    }
+}
@@ -1,14 +1,55 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use crate::board::color::Color;
+use crate::board::Board;
+use crate::book::BookMove;
+use crate::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use crate::chess_move::chess_move::ChessMove;
+use crate::game::drill::OpeningDrill;
+use crate::game::uci_engine::{GoLimits, UciEngine};
 use crate::input_handler::{InputError, MoveInput};
+use crate::move_generator::MoveGenerator;
+use common::bitboard::Square;
 
 pub trait InputSource {
-    fn get_move(&self, current_turn: Color) -> Result<Option<MoveInput>, InputError>;
+    fn get_move(&self, current_turn: Color, board: &Board)
+        -> Result<Option<MoveInput>, InputError>;
+
+    /// A status line to show in place of the default prompt, if any.
+    fn status(&self) -> Option<String> {
+        None
+    }
+
+    /// Check a move entered outside `get_move`, e.g. by clicking on the board.
+    /// Returns the input to play, or None if it is rejected.
+    fn review_move(
+        &self,
+        _current_turn: Color,
+        _board: &Board,
+        input: MoveInput,
+    ) -> Option<MoveInput> {
+        Some(input)
+    }
+
+    /// Called when the game restarts from the initial position.
+    fn reset(&self) {}
+
+    /// Whether the built-in engine may think on the opponent's time, i.e. a
+    /// human is to move against it.
+    fn engine_can_ponder(&self, _current_turn: Color) -> bool {
+        false
+    }
 }
 
 pub struct HumanInput;
 
 impl InputSource for HumanInput {
-    fn get_move(&self, _current_turn: Color) -> Result<Option<MoveInput>, InputError> {
+    fn get_move(
+        &self,
+        _current_turn: Color,
+        _board: &Board,
+    ) -> Result<Option<MoveInput>, InputError> {
         match crate::input_handler::parse_move_input() {
             Ok(move_input) => Ok(Some(move_input)),
             Err(InputError::UserExit) => Err(InputError::UserExit),
@@ -20,7 +61,11 @@ impl InputSource for HumanInput {
 pub struct EngineInput;
 
 impl InputSource for EngineInput {
-    fn get_move(&self, _current_turn: Color) -> Result<Option<MoveInput>, InputError> {
+    fn get_move(
+        &self,
+        _current_turn: Color,
+        _board: &Board,
+    ) -> Result<Option<MoveInput>, InputError> {
         Ok(Some(MoveInput::UseEngine))
     }
 }
@@ -30,7 +75,11 @@ pub struct ConditionalInput {
 }
 
 impl InputSource for ConditionalInput {
-    fn get_move(&self, current_turn: Color) -> Result<Option<MoveInput>, InputError> {
+    fn get_move(
+        &self,
+        current_turn: Color,
+        _board: &Board,
+    ) -> Result<Option<MoveInput>, InputError> {
         if current_turn == self.human_color {
             match crate::input_handler::parse_move_input() {
                 Ok(move_input) => Ok(Some(move_input)),
@@ -41,4 +90,209 @@ impl InputSource for ConditionalInput {
             Ok(Some(MoveInput::UseEngine))
         }
     }
+
+    fn engine_can_ponder(&self, current_turn: Color) -> bool {
+        current_turn == self.human_color
+    }
+}
+
+/// An external UCI engine plays `engine_color`; the built-in engine plays the other side,
+/// or a human if `human_opponent` is set. The engine is shared so it survives game
+/// restarts and mode switches.
+#[derive(Clone)]
+pub struct ExternalEngineInput {
+    pub engine: Rc<RefCell<UciEngine>>,
+    pub engine_color: Color,
+    pub limits: GoLimits,
+    pub human_opponent: bool,
+}
+
+impl InputSource for ExternalEngineInput {
+    fn get_move(
+        &self,
+        current_turn: Color,
+        board: &Board,
+    ) -> Result<Option<MoveInput>, InputError> {
+        if current_turn != self.engine_color {
+            if self.human_opponent {
+                return HumanInput.get_move(current_turn, board);
+            }
+            return Ok(Some(MoveInput::UseEngine));
+        }
+        let best_move = self
+            .engine
+            .borrow_mut()
+            .best_move(Some(&board.to_fen()), &[], self.limits)
+            .map_err(|e| InputError::IOError {
+                error: e.to_string(),
+            })?;
+        Ok(Some(MoveInput::Uci {
+            uci_move: best_move.uci_move,
+        }))
+    }
+
+    /// Tell the external engine a new game is starting, clearing its hash.
+    fn reset(&self) {
+        if let Err(e) = self.engine.borrow_mut().new_game() {
+            eprintln!("Failed to reset {}: {}", self.engine.borrow().name(), e);
+        }
+    }
+}
+
+/// Opening drill: the human plays the student's side and must follow the book;
+/// the other side replies with book moves. Once the line is complete the built-in
+/// engine takes over the other side.
+pub struct DrillInput {
+    drill: RefCell<OpeningDrill>,
+    message: RefCell<Option<String>>,
+}
+
+impl DrillInput {
+    pub fn new(drill: OpeningDrill) -> Self {
+        Self {
+            drill: RefCell::new(drill),
+            message: RefCell::new(None),
+        }
+    }
+
+    /// Book move matching `input` if it is a legal move, along with its SAN.
+    fn resolve(input: &MoveInput, board: &Board) -> Option<(BookMove, String)> {
+        let legal = legal_moves(board);
+        let squares = |from: &str, to: &str| {
+            Some((Square::from_algebraic(from)?, Square::from_algebraic(to)?))
+        };
+        let (chess_move, san) = match input {
+            MoveInput::Algebraic { notation } => legal.into_iter().find(|(_, n)| n == notation),
+            MoveInput::Coordinate { from, to } => {
+                let (from, to) = squares(from, to)?;
+                legal
+                    .into_iter()
+                    .find(|(m, _)| m.from_square() == from && m.to_square() == to)
+            }
+            MoveInput::Uci { uci_move } if uci_move.len() >= 4 && uci_move.is_ascii() => {
+                let (from, to) = squares(&uci_move[0..2], &uci_move[2..4])?;
+                legal
+                    .into_iter()
+                    .find(|(m, _)| m.from_square() == from && m.to_square() == to)
+            }
+            _ => None,
+        }?;
+        Some((
+            BookMove::new(chess_move.from_square(), chess_move.to_square()),
+            san,
+        ))
+    }
+
+    /// SAN for each book move, for reporting what was expected.
+    fn describe(book_moves: &[BookMove], board: &Board) -> String {
+        let legal = legal_moves(board);
+        book_moves
+            .iter()
+            .filter_map(|book_move| {
+                legal
+                    .iter()
+                    .find(|(m, _)| {
+                        m.from_square() == book_move.from_square()
+                            && m.to_square() == book_move.to_square()
+                    })
+                    .map(|(_, san)| san.clone())
+            })
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+}
+
+impl InputSource for DrillInput {
+    fn get_move(
+        &self,
+        current_turn: Color,
+        board: &Board,
+    ) -> Result<Option<MoveInput>, InputError> {
+        let mut drill = self.drill.borrow_mut();
+        if drill.is_complete() {
+            if current_turn == drill.student() {
+                return HumanInput.get_move(current_turn, board);
+            }
+            return Ok(Some(MoveInput::UseEngine));
+        }
+
+        if current_turn != drill.student() {
+            let reply = drill
+                .book_reply()
+                .expect("an incomplete drill has a book reply");
+            drill.play(reply);
+            return Ok(Some(MoveInput::Uci {
+                uci_move: reply.to_string(),
+            }));
+        }
+
+        drop(drill);
+
+        match HumanInput.get_move(current_turn, board)? {
+            Some(input) => Ok(self.review_move(current_turn, board, input)),
+            None => Ok(None),
+        }
+    }
+
+    /// Moves the student enters must follow the book until the drill is complete.
+    fn review_move(
+        &self,
+        _current_turn: Color,
+        board: &Board,
+        input: MoveInput,
+    ) -> Option<MoveInput> {
+        let mut drill = self.drill.borrow_mut();
+        if drill.is_complete() {
+            return Some(input);
+        }
+        let Some((book_move, san)) = Self::resolve(&input, board) else {
+            // Not a legal move; let the engine report it.
+            return Some(input);
+        };
+        if drill.play(book_move) {
+            *self.message.borrow_mut() = None;
+            Some(input)
+        } else {
+            let expected = Self::describe(&drill.expected_moves(), board);
+            *self.message.borrow_mut() = Some(format!(
+                "{} is not in the {}; the book plays {}. Try again.",
+                san,
+                drill.name(),
+                expected
+            ));
+            None
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        let drill = self.drill.borrow();
+        if let Some(message) = self.message.borrow().as_ref() {
+            return Some(message.clone());
+        }
+        if drill.is_complete() {
+            Some(format!(
+                "{} complete with {} deviation(s). Play on against the engine.",
+                drill.name(),
+                drill.deviations()
+            ))
+        } else {
+            Some(format!(
+                "Drill: {} - play the book moves for {} ({} deviation(s))",
+                drill.name(),
+                drill.student(),
+                drill.deviations()
+            ))
+        }
+    }
+
+    fn reset(&self) {
+        self.drill.borrow_mut().reset();
+        *self.message.borrow_mut() = None;
+    }
+}
+
+fn legal_moves(board: &Board) -> Vec<(ChessMove, String)> {
+    let mut board = board.clone();
+    let turn = board.turn();
+    enumerate_candidate_moves_with_algebraic_notation(&mut board, turn, &MoveGenerator::default())
 }
@@ -0,0 +1,310 @@
+//! Learning from experience: the engine's moves and how its games ended,
+//! kept in a file from one session to the next.
+//!
+//! Every move the engine chose is recorded under the position it was played
+//! in, with the wins, draws and losses of the games it was chosen in for the
+//! side that played it. When the engine is to move in a recorded position
+//! again, a move that has lost more games than it won is searched with a
+//! penalty, so the engine tries another move where one is about as good
+//! instead of repeating a line that lost. A sound move that lost once is
+//! still played when the alternatives are clearly worse.
+//!
+//! The file is JSON, one entry per position and move. Positions are kept as
+//! FEN without the move counters, as in EPD, rather than as Zobrist hashes,
+//! so a file stays valid when the hash keys change between builds.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::chess_move::chess_move::ChessMove;
+
+/// Penalty in centipawns for a move that has lost every game it was played
+/// in, approached as the games add up.
+const MAX_LOSS_PENALTY: i16 = 100;
+
+#[derive(Error, Debug)]
+pub enum LearningError {
+    #[error("Failed to access `{path}`: {error}")]
+    Io { path: String, error: std::io::Error },
+    #[error("Invalid learning file `{path}`: {error}")]
+    InvalidFile {
+        path: String,
+        error: serde_json::Error,
+    },
+}
+
+/// How the games a move was played in ended, for the side that played it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MoveRecord {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Centipawns held against the move: nothing unless it has lost more
+    /// than it won, growing with the share of games lost.
+    pub fn penalty(&self) -> i16 {
+        let net_losses = self.losses.saturating_sub(self.wins);
+        if net_losses == 0 {
+            return 0;
+        }
+        let share = f64::from(net_losses) / f64::from(self.games() + 1);
+        (f64::from(MAX_LOSS_PENALTY) * share).round() as i16
+    }
+}
+
+/// A move as saved: the position's key, the move in UCI notation, and its
+/// record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedMove {
+    position: String,
+    #[serde(rename = "move")]
+    uci: String,
+    #[serde(flatten)]
+    record: MoveRecord,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SavedExperience {
+    moves: Vec<SavedMove>,
+}
+
+/// The recorded moves, by position key and UCI notation.
+#[derive(Debug, Clone, Default)]
+pub struct Experience {
+    moves: HashMap<(String, String), MoveRecord>,
+}
+
+/// The key a position's moves are recorded under: its FEN without the
+/// halfmove clock and move number.
+pub fn position_key(board: &Board) -> String {
+    let fen = board.to_fen();
+    fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+}
+
+impl Experience {
+    /// The experience saved at `path`, or none yet if there is no file.
+    pub fn load(path: &str) -> Result<Self, LearningError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => {
+                return Err(LearningError::Io {
+                    path: path.to_string(),
+                    error,
+                })
+            }
+        };
+        let saved: SavedExperience =
+            serde_json::from_str(&contents).map_err(|error| LearningError::InvalidFile {
+                path: path.to_string(),
+                error,
+            })?;
+        Ok(Self {
+            moves: saved
+                .moves
+                .into_iter()
+                .map(|saved| ((saved.position, saved.uci), saved.record))
+                .collect(),
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), LearningError> {
+        let mut moves: Vec<SavedMove> = self
+            .moves
+            .iter()
+            .map(|((position, uci), record)| SavedMove {
+                position: position.clone(),
+                uci: uci.clone(),
+                record: *record,
+            })
+            .collect();
+        moves.sort_by(|a, b| (&a.position, &a.uci).cmp(&(&b.position, &b.uci)));
+        let json = serde_json::to_string(&SavedExperience { moves })
+            .expect("learning file should serialize");
+        fs::write(path, json + "\n").map_err(|error| LearningError::Io {
+            path: path.to_string(),
+            error,
+        })
+    }
+
+    /// Number of recorded positions and moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// The record of `chess_move` in the position with key `position`, if it
+    /// has one. See `position_key`.
+    pub fn record(&self, position: &str, chess_move: &ChessMove) -> Option<MoveRecord> {
+        self.moves
+            .get(&(position.to_string(), chess_move.to_uci()))
+            .copied()
+    }
+
+    /// Centipawns held against playing `chess_move` in the position with key
+    /// `position`.
+    pub fn penalty(&self, position: &str, chess_move: &ChessMove) -> i16 {
+        self.record(position, chess_move)
+            .map_or(0, |record| record.penalty())
+    }
+
+    /// Record `chess_move`, played by `mover` in the position with key
+    /// `position`, in a game won by `winner` or drawn without one.
+    pub fn record_move(
+        &mut self,
+        position: String,
+        chess_move: &ChessMove,
+        mover: Color,
+        winner: Option<Color>,
+    ) {
+        let record = self
+            .moves
+            .entry((position, chess_move.to_uci()))
+            .or_default();
+        match winner {
+            None => record.draws += 1,
+            Some(winner) if winner == mover => record.wins += 1,
+            Some(_) => record.losses += 1,
+        }
+    }
+
+    /// Record a game played from `start` with `moves`, won by `winner` or
+    /// drawn without one. Only the moves of the plies for which `learns`
+    /// holds are recorded, e.g. those the engine chose.
+    pub fn record_game(
+        &mut self,
+        start: &Board,
+        moves: &[ChessMove],
+        winner: Option<Color>,
+        learns: impl Fn(usize) -> bool,
+    ) {
+        let mut board = start.clone();
+        for (ply, chess_move) in moves.iter().enumerate() {
+            if learns(ply) {
+                self.record_move(position_key(&board), chess_move, board.turn(), winner);
+            }
+            if chess_move.apply(&mut board).is_err() {
+                return;
+            }
+            board.toggle_turn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::engine::{Engine, EngineConfig};
+
+    fn moves(board: &Board, ucis: &[&str]) -> Vec<ChessMove> {
+        let mut engine = Engine::with_config(EngineConfig {
+            starting_position: board.clone(),
+            ..Default::default()
+        });
+        ucis.iter()
+            .map(|uci| {
+                let chess_move = engine.make_move_uci(uci).unwrap();
+                engine.board_mut().toggle_turn();
+                chess_move
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_losing_moves_are_penalized() {
+        assert_eq!(MoveRecord::default().penalty(), 0);
+        let lost_once = MoveRecord {
+            losses: 1,
+            ..MoveRecord::default()
+        };
+        assert_eq!(lost_once.penalty(), 50);
+        let even = MoveRecord {
+            wins: 1,
+            losses: 1,
+            ..MoveRecord::default()
+        };
+        assert_eq!(even.penalty(), 0);
+        let lost_often = MoveRecord {
+            draws: 1,
+            losses: 8,
+            ..MoveRecord::default()
+        };
+        assert_eq!(lost_often.penalty(), 80);
+    }
+
+    #[test]
+    fn test_positions_are_keyed_without_move_counters() {
+        assert_eq!(
+            position_key(&Board::default()),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+        );
+    }
+
+    #[test]
+    fn test_games_are_recorded_for_the_learning_side() {
+        let start = Board::default();
+        let game = moves(&start, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        let mut experience = Experience::default();
+        // White's moves only, in a game Black won
+        experience.record_game(&start, &game, Some(Color::Black), |ply| ply % 2 == 0);
+
+        assert_eq!(experience.len(), 2);
+        assert_eq!(experience.record(&position_key(&start), &game[1]), None);
+        let lost = experience.record(&position_key(&start), &game[0]);
+        assert_eq!(lost.map(|record| record.losses), Some(1));
+        assert_eq!(experience.penalty(&position_key(&start), &game[0]), 50);
+        experience.record_game(&start, &game[..1], None, |_| true);
+        let record = experience.record(&position_key(&start), &game[0]).unwrap();
+        assert_eq!((record.draws, record.losses), (1, 1));
+    }
+
+    #[test]
+    fn test_experience_survives_saving_and_loading() {
+        let path = std::env::temp_dir().join(format!("learning-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert!(Experience::load(path).unwrap().is_empty());
+
+        let start = Board::default();
+        let game = moves(&start, &["e2e4", "e7e5"]);
+        let mut experience = Experience::default();
+        experience.record_game(&start, &game, Some(Color::White), |_| true);
+        experience.save(path).unwrap();
+
+        let loaded = Experience::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded
+                .record(&position_key(&start), &game[0])
+                .map(|record| record.wins),
+            Some(1)
+        );
+
+        fs::write(path, "not json").unwrap();
+        let error = Experience::load(path);
+        fs::remove_file(path).unwrap();
+        assert!(matches!(error, Err(LearningError::InvalidFile { .. })));
+
+        // Files keyed by Zobrist hash are refused rather than misread
+        let hashed = r#"{"moves":[{"hash":1,"move":"e2e4","wins":1,"draws":0,"losses":0}]}"#;
+        fs::write(path, hashed).unwrap();
+        let error = Experience::load(path);
+        fs::remove_file(path).unwrap();
+        assert!(matches!(error, Err(LearningError::InvalidFile { .. })));
+    }
+}
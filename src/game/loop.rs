@@ -20,11 +20,40 @@
 //! - `Playing` → `GameEnded`: When `engine.check_game_over()` returns a result
 //! - `GameEnded` → `Playing`: When user inputs "start over"
 //!
+//! Starting over builds a fresh engine, so the transposition table and other
+//! search state from the previous game are discarded while the loop's settings
+//! (search limits, strength, clock, takeback limit, PGN output, learning file)
+//! carry over. A
+//! rematch is returned to the caller as `GameAction::Rematch`, which starts a new
+//! loop with the colors swapped.
+//!
 //! ## Command Pattern
 //!
 //! Uses `MoveInput` directly as the command pattern (no redundant wrappers):
 //! - **Game moves**: `Coordinate`, `Algebraic`, `UseEngine` → executed during `Playing` state
-//! - **Control commands**: `StartOver`, `Exit`, `SwitchGameMode` → handled in `GameEnded` state
+//! - **Hints**: `Hint` → shows a suggested move from a short search without playing it
+//! - **Takebacks**: `Undo` (`undo` or the `u` key) → rolls back the last full move pair, up
+//!   to the configured limit
+//! - **Resigning**: `Resign` → ends the game as a loss for the side to move
+//! - **Replays**: `MainLine` → leaves a branch played off a replayed game, returning to
+//!   the game's own moves
+//! - **Saving and loading**: `SaveGame` → writes the game so far to a PGN or native file;
+//!   `LoadGame` → replaces the game with a saved one; `ChooseGameFile` → asks the renderer
+//!   for the file to save to or load from
+//! - **Browsing**: `Browse` → shows an earlier position from the renderer's move list
+//! - **Board orientation**: `FlipBoard` → turns the renderer's board around
+//! - **Live analysis**: `ToggleAnalysis` → shows or hides the renderer's analysis of the
+//!   position on display
+//! - **Blindfold**: `TogglePieces` → shows the pieces the renderer hides in a blindfold
+//!   game until the next move, or hides them again
+//! - **Settings**: `OpenSettings` → opens the renderer's settings menu, redrawing as
+//!   settings change until it is closed, then applies the search depth and time per
+//!   move picked in it
+//! - **Mouse input**: `Mouse` → picks a move on the renderer's board, checked by the
+//!   input source before it is played
+//! - **Resizing**: `Resize` → redraws the screen for the new terminal size
+//! - **Control commands**: `StartOver`, `Rematch`, `Exit`, `SwitchGameMode` → handled in `GameEnded` state
+//! - **Reviewing**: `Analyze` → goes over the finished game with the renderer's live analysis
 //!
 //! Commands are mapped to `GameAction` results which indicate loop-level actions (restart, switch mode, exit).
 //!
@@ -33,18 +62,85 @@
 //! Input handling varies by state:
 //! - **Playing**: Uses the `InputSource` trait (e.g., `ConditionalInput`, `EngineInput`, `HumanInput`)
 //! - **GameEnded**: Always reads from stdin to allow mode switching in all scenarios (including watch mode)
+//!
+//! ## Events
+//!
+//! The loop reports what happens in a game (moves, evaluations, clock changes,
+//! messages, errors, and the result) as `GameEvent`s to the renderer and to any
+//! observers registered with `with_observer`, rather than printing them itself.
+//!
+//! ## Pondering
+//!
+//! With `with_pondering`, the engine searches the reply it predicts from a human
+//! opponent on a background thread while the human thinks. The search fills the
+//! shared transposition table and is stopped as soon as the human's input arrives.
+//!
+//! ## Clocks
+//!
+//! With `with_clock`, the side to move's clock runs while it thinks, engine moves are
+//! budgeted by the time manager, and a side whose time runs out loses the game.
+//! With `with_draw_odds`, a drawn game instead counts as a win for the given side,
+//! as in Armageddon where Black has draw odds and White more time on the clock.
+//!
+//! ## Adjudication
+//!
+//! With `with_adjudication`, the evaluations of the engine's own moves are checked
+//! against resign and draw rules after each move, ending hopeless or dead-drawn
+//! engine games early.
+//!
+//! ## Replays
+//!
+//! With `with_replay`, the opening moves are a game to step through rather than
+//! the start of a new one: the renderer's move list begins at the starting
+//! position, and a move entered while an earlier position is on display branches
+//! off the game there, taking back the moves after it. The game is not reported
+//! as over until its final position is on display.
+//!
+//! ## Playback
+//!
+//! With `with_playback`, key presses polled between moves pause, resume, and single-step
+//! the game, and the configured delay and stop conditions from `PlaybackOptions` apply.
 
-use crate::alpha_beta_searcher::SearchError;
+use crate::alpha_beta_searcher::{SearchError, SearchLimits};
 use crate::board::color::Color;
 use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::GameEnding;
 use crate::game::action::GameAction;
+use crate::game::adjudication::{Adjudication, AdjudicationRules, Adjudicator};
+use crate::game::clock::GameClock;
 use crate::game::display::GameDisplay;
-use crate::game::engine::{Engine, EngineConfig, EngineError};
+use crate::game::engine::{Engine, EngineConfig, EngineError, EngineSettings};
+use crate::game::events::{GameEvent, GameObserver, GameOverReason};
+use crate::game::game_file::{self, GameFileAction, GameFileFormat};
 use crate::game::input_source::InputSource;
+use crate::game::learning::Experience;
+use crate::game::pgn::{
+    format_clock_comment, format_eval_comment, pgn_date, PgnGame, PgnOutput, PgnResult,
+};
+use crate::game::playback::{Playback, PlaybackOptions};
 use crate::game::renderer::GameRenderer;
-use crate::input_handler::{InputError, MenuInput, MoveInput};
+use crate::game::strength::Strength;
+use crate::input_handler::{InputError, MenuInput, MoveInput, Navigation};
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use common::bitboard::Square;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+/// Thinking time for a `hint` search.
+const HINT_MOVETIME: Duration = Duration::from_millis(500);
+
+/// Moves of the principal variation shown by `hint pv`.
+const HINT_PV_LENGTH: usize = 6;
+
+/// How often to poll for key presses while paused or waiting out a delay.
+const PLAYBACK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often a running clock is redrawn while waiting for a move.
+const CLOCK_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
 
 /// Current state of the game loop
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -63,6 +159,44 @@ pub struct GameLoop<I: InputSource, R: GameRenderer> {
     input_source: I,
     renderer: R,
     state: GameLoopState,
+    /// Result of the game once it has ended.
+    result: Option<PgnResult>,
+    pgn_output: Option<PgnOutput>,
+    player_names: (String, String),
+    pgn_written: bool,
+    started_at: SystemTime,
+    playback: Option<Playback>,
+    initial_clock: Option<GameClock>,
+    clock: Option<GameClock>,
+    /// Side that lost on time, ending the game.
+    time_forfeit: Option<Color>,
+    /// Side that resigned, ending the game.
+    resigned: Option<Color>,
+    /// Side that wins drawn games.
+    draw_odds: Option<Color>,
+    /// Set when a drawn game was scored as a win on draw odds.
+    won_on_draw_odds: Option<Color>,
+    adjudicator: Option<Adjudicator>,
+    /// Result reached by adjudication, ending the game.
+    adjudication: Option<Adjudication>,
+    /// Clock after each move played, so takebacks can restore it.
+    clock_history: Vec<GameClock>,
+    max_undos: usize,
+    undos_used: usize,
+    strength: Option<Strength>,
+    /// Moves played from the starting position before the game begins.
+    opening_moves: Vec<String>,
+    pondering: bool,
+    /// Whether the opening moves are a game being replayed.
+    replay: bool,
+    /// Feedback shown in place of the input source's status, e.g. after an undo.
+    message: Option<String>,
+    observers: Vec<Box<dyn GameObserver>>,
+    /// File the engine's games are learned from and recorded to, with what
+    /// it holds.
+    learning: Option<(String, Arc<Experience>)>,
+    /// Plies of the current game whose moves the engine chose.
+    engine_plies: Vec<usize>,
 }
 
 impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
@@ -74,19 +208,166 @@ impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
             input_source,
             renderer,
             state: GameLoopState::Playing,
+            result: None,
+            pgn_output: None,
+            player_names: ("?".to_string(), "?".to_string()),
+            pgn_written: false,
+            started_at: SystemTime::now(),
+            playback: None,
+            initial_clock: None,
+            clock: None,
+            time_forfeit: None,
+            resigned: None,
+            draw_odds: None,
+            won_on_draw_odds: None,
+            adjudicator: None,
+            adjudication: None,
+            clock_history: Vec::new(),
+            max_undos: 0,
+            undos_used: 0,
+            strength: None,
+            opening_moves: Vec::new(),
+            pondering: false,
+            replay: false,
+            message: None,
+            observers: Vec::new(),
+            learning: None,
+            engine_plies: Vec::new(),
+        }
+    }
+
+    /// Append each finished (or abandoned) game to a PGN file, naming the
+    /// White and Black players in the headers.
+    pub fn with_pgn_output(mut self, output: Option<PgnOutput>, white: &str, black: &str) -> Self {
+        self.pgn_output = output;
+        self.player_names = (white.to_string(), black.to_string());
+        self
+    }
+
+    /// Bound the engine's searches by time and/or nodes instead of depth.
+    pub fn with_search_limits(mut self, limits: SearchLimits) -> Self {
+        self.engine.set_search_limits(limits);
+        self
+    }
+
+    /// Enable keyboard playback controls (pause, resume, step) along with the
+    /// delay and stop conditions in `options`.
+    pub fn with_playback(mut self, options: PlaybackOptions) -> Self {
+        self.playback = Some(Playback::new(options));
+        self
+    }
+
+    /// Play with chess clocks starting from `clock`.
+    pub fn with_clock(mut self, clock: GameClock) -> Self {
+        self.initial_clock = Some(clock);
+        self.clock = Some(clock);
+        self.clock_history = vec![clock];
+        self
+    }
+
+    /// Score drawn games as wins for `color`, e.g. Black in Armageddon.
+    pub fn with_draw_odds(mut self, color: Option<Color>) -> Self {
+        self.draw_odds = color;
+        self
+    }
+
+    /// End engine games early by the resign and draw `rules`.
+    pub fn with_adjudication(mut self, rules: AdjudicationRules) -> Self {
+        self.adjudicator = rules.is_enabled().then(|| Adjudicator::new(rules));
+        self
+    }
+
+    /// Weaken the engine's play to `strength`; see `Engine::set_strength`.
+    pub fn with_strength(mut self, strength: Option<Strength>) -> Self {
+        self.strength = strength;
+        self.engine.set_strength(strength);
+        self
+    }
+
+    /// Learn from the engine's games: the moves it chose in games it lost
+    /// count against them, and every finished game is added to `experience`
+    /// and saved to `path`. See `game::learning`.
+    pub fn with_learning(mut self, path: &str, experience: Experience) -> Self {
+        let experience = Arc::new(experience);
+        self.engine.set_experience(Some(Arc::clone(&experience)));
+        self.learning = Some((path.to_string(), experience));
+        self
+    }
+
+    /// Play `moves` (SAN or UCI) from the starting position before the game
+    /// begins and after each restart. They are part of the game record but are
+    /// not timed and cannot be taken back.
+    pub fn with_opening_moves(mut self, moves: Vec<String>) -> Self {
+        self.opening_moves = moves;
+        self.play_opening_moves();
+        self
+    }
+
+    fn play_opening_moves(&mut self) {
+        for notation in &self.opening_moves {
+            if self.engine.make_move_san_or_uci(notation).is_err() {
+                eprintln!("Illegal opening move `{}`", notation);
+                break;
+            }
+            self.engine.board_mut().toggle_turn();
+            self.engine.record_position_hash();
         }
     }
 
+    /// Let the engine think on a human opponent's time.
+    pub fn with_pondering(mut self, pondering: bool) -> Self {
+        self.pondering = pondering;
+        self
+    }
+
+    /// Replay the opening moves as a game to step through and branch off from,
+    /// instead of starting a new game after them.
+    pub fn with_replay(mut self, replay: bool) -> Self {
+        self.replay = replay;
+        self
+    }
+
+    /// Report game events to `observer` as well as the renderer.
+    pub fn with_observer(mut self, observer: impl GameObserver + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    /// Allow up to `max_undos` takebacks per game. Takebacks are disabled by default.
+    pub fn with_undo_limit(mut self, max_undos: usize) -> Self {
+        self.max_undos = max_undos;
+        self
+    }
+
     /// Main game loop following classic update/render pattern
     pub fn run(&mut self) -> GameAction {
+        self.emit(GameEvent::GameStarted);
+        self.show_replay_start();
         loop {
             self.render();
             if let Some(action) = self.update() {
+                if action == GameAction::Exit {
+                    self.write_pgn(PgnResult::Unfinished);
+                }
                 return action;
             }
         }
     }
 
+    /// Send `event` to the renderer and every observer.
+    fn emit(&mut self, event: GameEvent) {
+        self.renderer.on_event(&event);
+        for observer in &mut self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    /// Show `text` in place of the input source's status until the next move.
+    fn set_message(&mut self, text: String) {
+        self.message = Some(text.clone());
+        self.emit(GameEvent::Message { text });
+    }
+
     /// Update phase: processes game logic, input, and state transitions
     /// Returns Some(action) if the game should exit or switch modes
     fn update(&mut self) -> Option<GameAction> {
@@ -103,26 +384,241 @@ impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
 
     /// Update logic when game is actively being played
     fn update_playing(&mut self) -> Option<GameAction> {
-        if self.engine.check_game_over().is_some() {
-            self.state = GameLoopState::GameEnded;
+        let ending = self
+            .engine
+            .check_game_over()
+            .filter(|_| self.replayed_ply().is_none());
+        if let Some(ending) = ending {
+            let (result, reason) = match ending {
+                GameEnding::Checkmate => (
+                    PgnResult::win_for(self.engine.board().turn().opposite()),
+                    GameOverReason::Checkmate,
+                ),
+                GameEnding::Stalemate => (PgnResult::Draw, GameOverReason::Stalemate),
+                GameEnding::Draw => (PgnResult::Draw, GameOverReason::Draw),
+                GameEnding::VariantWin => (
+                    PgnResult::win_for(self.engine.board().turn().opposite()),
+                    GameOverReason::VariantWin(self.engine.board().variant()),
+                ),
+            };
+            self.end_game(result, reason);
             return None;
         }
 
+        if self.playback.as_ref().is_some_and(Playback::should_wait) {
+            return self.wait_while_paused();
+        }
+
         let current_turn = self.engine.board().turn();
-        match self.input_source.get_move(current_turn) {
-            Ok(Some(input)) => self.execute_move_input(input),
+        if let Some(clock) = &mut self.clock {
+            clock.start(current_turn);
+            let clock = *clock;
+            self.emit(GameEvent::ClockUpdated { clock });
+        }
+        match self.with_clock_refresh(|game| game.get_move(current_turn)) {
+            Ok(Some(_)) if self.clock.is_some_and(|c| c.is_flagged(current_turn)) => {
+                self.forfeit_on_time(current_turn);
+                None
+            }
+            Ok(Some(MoveInput::Undo)) => {
+                self.undo_move_pair();
+                None
+            }
+            Ok(Some(MoveInput::Resign)) => {
+                self.resigned = Some(current_turn);
+                self.end_game(
+                    PgnResult::win_for(current_turn.opposite()),
+                    GameOverReason::Resignation,
+                );
+                None
+            }
+            Ok(Some(MoveInput::MainLine)) => {
+                self.return_to_main_line();
+                None
+            }
+            Ok(Some(MoveInput::Hint { show_line })) => {
+                self.show_hint(show_line);
+                None
+            }
+            Ok(Some(MoveInput::SaveGame { path })) => {
+                self.save_game(&path);
+                None
+            }
+            Ok(Some(MoveInput::LoadGame { path })) => {
+                self.load_game(&path);
+                None
+            }
+            Ok(Some(MoveInput::ChooseGameFile { action })) => {
+                self.choose_game_file(action);
+                None
+            }
+            // Redrawn on the next pass through the loop
+            Ok(Some(MoveInput::Resize)) => None,
+            Ok(Some(MoveInput::Browse { navigation })) => {
+                self.renderer.browse_moves(navigation);
+                None
+            }
+            Ok(Some(MoveInput::FlipBoard)) => {
+                self.renderer.flip_board();
+                None
+            }
+            Ok(Some(MoveInput::ToggleAnalysis)) => {
+                self.renderer.toggle_analysis();
+                None
+            }
+            Ok(Some(MoveInput::TogglePieces)) => {
+                self.renderer.toggle_pieces();
+                None
+            }
+            Ok(Some(MoveInput::OpenSettings)) => {
+                self.show_settings();
+                None
+            }
+            Ok(Some(MoveInput::Mouse { event })) => {
+                let input = self.renderer.handle_mouse(event, self.engine.board())?;
+                let input = self.choose_promotion(input)?;
+                match self
+                    .input_source
+                    .review_move(current_turn, self.engine.board(), input)
+                {
+                    Some(input) => self.execute_move_input(input),
+                    None => {
+                        self.emit(GameEvent::Error {
+                            message: "Invalid input".to_string(),
+                        });
+                        None
+                    }
+                }
+            }
+            Ok(Some(input)) => self.play_move_input(input),
             Ok(None) => {
-                eprintln!("Invalid input");
+                self.emit(GameEvent::Error {
+                    message: "Invalid input".to_string(),
+                });
                 None
             }
             Err(InputError::UserExit) => Some(GameAction::Exit),
+            Err(InputError::IOError { error }) => {
+                self.emit(GameEvent::Error {
+                    message: format!("Input error: {}", error),
+                });
+                Some(GameAction::Exit)
+            }
             Err(_) => {
-                eprintln!("Input error");
+                self.emit(GameEvent::Error {
+                    message: "Input error".to_string(),
+                });
                 None
             }
         }
     }
 
+    /// Run `f`, redrawing the running clock meanwhile if the renderer supports it.
+    fn with_clock_refresh<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let refresh = self
+            .clock
+            .filter(|clock| clock.running().is_some())
+            .and_then(|clock| self.renderer.clock_refresh(clock));
+        let Some(refresh) = refresh else {
+            return f(self);
+        };
+
+        let (done, ticks) = mpsc::channel::<()>();
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) =
+                    ticks.recv_timeout(CLOCK_REFRESH_INTERVAL)
+                {
+                    refresh();
+                }
+            });
+            let result = f(self);
+            drop(done);
+            result
+        })
+    }
+
+    /// Show the renderer's settings menu, redrawing after each change until it
+    /// is closed.
+    fn show_settings(&mut self) {
+        let search_limits = self.engine.search_limits();
+        let engine = EngineSettings {
+            depth: self.engine.search_depth(),
+            movetime: search_limits.movetime,
+        };
+        self.renderer.open_settings(engine);
+        self.render();
+        while self.with_clock_refresh(|game| game.renderer.update_settings()) {
+            self.render();
+        }
+        match self.renderer.engine_settings() {
+            Some(settings) if settings != engine => {
+                // Kept by the engines of later games too
+                self.config.search_depth = settings.depth;
+                self.engine.set_search_depth(settings.depth);
+                self.engine.set_search_limits(SearchLimits {
+                    movetime: settings.movetime,
+                    ..search_limits
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Let the player pick the piece for a move given by its squares, such as
+    /// `e7e8` or a pawn dragged to the last rank, when it promotes. Returns the
+    /// move with the piece, or None if the player backs out.
+    fn choose_promotion(&mut self, input: MoveInput) -> Option<MoveInput> {
+        let MoveInput::Coordinate { from, to } = &input else {
+            return Some(input);
+        };
+        let (Some(from_square), Some(to_square)) =
+            (Square::from_algebraic(from), Square::from_algebraic(to))
+        else {
+            return Some(input);
+        };
+        if !self.engine.is_promotion(from_square, to_square) {
+            return Some(input);
+        }
+        let color = self.engine.board().turn();
+        let piece = self.with_clock_refresh(|game| game.renderer.choose_promotion(color))?;
+        Some(MoveInput::Uci {
+            uci_move: format!("{}{}{}", from, to, piece.to_char(Color::Black)),
+        })
+    }
+
+    /// Read the next input from the input source, pondering meanwhile if enabled.
+    fn get_move(&mut self, current_turn: Color) -> Result<Option<MoveInput>, InputError> {
+        let predicted = if self.pondering && self.input_source.engine_can_ponder(current_turn) {
+            self.engine.predicted_reply()
+        } else {
+            None
+        };
+        let Some(predicted) = predicted else {
+            return self
+                .input_source
+                .get_move(current_turn, self.engine.board());
+        };
+
+        let board = self.engine.board().clone();
+        let stop = self.engine.search_stop_flag();
+        stop.store(false, Ordering::Relaxed);
+        let engine = &mut self.engine;
+        let input_source = &self.input_source;
+        let (input, pondered) = std::thread::scope(|scope| {
+            let ponder = scope.spawn(move || engine.ponder(&predicted));
+            let input = input_source.get_move(current_turn, &board);
+            stop.store(true, Ordering::Relaxed);
+            (input, ponder.join().is_ok())
+        });
+        if !pondered {
+            self.emit(GameEvent::Error {
+                message: "Pondering failed".to_string(),
+            });
+        }
+        input
+    }
+
     /// Update logic when game has ended
     fn update_game_ended(&mut self) -> Option<GameAction> {
         match crate::input_handler::parse_menu_input() {
@@ -130,7 +626,41 @@ impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
                 self.restart_game();
                 None
             }
+            Ok(MenuInput::Rematch) => {
+                self.input_source.reset();
+                Some(GameAction::Rematch)
+            }
             Ok(MenuInput::SwitchGameMode { target }) => Some(GameAction::SwitchGameMode { target }),
+            Ok(MenuInput::Browse { navigation }) => {
+                self.renderer.browse_moves(navigation);
+                // A replay can branch off again from an earlier position
+                if self.replayed_ply().is_some() {
+                    self.state = GameLoopState::Playing;
+                    self.result = None;
+                }
+                None
+            }
+            Ok(MenuInput::Analyze) => {
+                self.renderer.analyze_game();
+                None
+            }
+            Ok(MenuInput::FlipBoard) => {
+                self.renderer.flip_board();
+                None
+            }
+            Ok(MenuInput::ToggleAnalysis) => {
+                self.renderer.toggle_analysis();
+                None
+            }
+            Ok(MenuInput::OpenSettings) => {
+                self.show_settings();
+                None
+            }
+            Ok(MenuInput::ChooseGameFile { action }) => {
+                self.choose_game_file(action);
+                None
+            }
+            Ok(MenuInput::Resize) => None,
             Ok(MenuInput::Exit) => Some(GameAction::Exit),
             Err(InputError::UserExit) => Some(GameAction::Exit),
             Err(_) => None, // Invalid input, continue waiting
@@ -138,6 +668,28 @@ impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
     }
 
     fn render(&mut self) {
+        let status = match (&self.playback, self.state) {
+            (Some(playback), GameLoopState::Playing) => Some(playback.status()),
+            (None, GameLoopState::Playing) => {
+                self.message.clone().or_else(|| self.input_source.status())
+            }
+            _ => None,
+        };
+        self.renderer.set_status(status);
+        self.renderer.set_clock(self.clock);
+        self.renderer.set_termination(
+            self.time_forfeit
+                .map(|loser| format!("{} lost on time!", loser))
+                .or_else(|| self.resigned.map(|loser| format!("{} resigned.", loser)))
+                .or_else(|| {
+                    self.adjudication
+                        .map(|adjudication| adjudication.to_string())
+                })
+                .or_else(|| {
+                    self.won_on_draw_odds
+                        .map(|winner| format!("{} wins on draw odds!", winner))
+                }),
+        );
         let view_model = self.build_view_model();
         self.renderer.render(
             &mut self.ui,
@@ -149,7 +701,10 @@ impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
     }
 
     fn build_view_model(&mut self) -> ViewModel {
-        let game_ending = self.engine.check_game_over();
+        let game_ending = self
+            .engine
+            .check_game_over()
+            .filter(|_| self.replayed_ply().is_none());
         let valid_moves = self.engine.get_valid_moves();
         let current_turn = self.engine.board().turn();
         let last_move = self.find_last_move_with_notation(&valid_moves);
@@ -173,56 +728,560 @@ impl<I: InputSource, R: GameRenderer> GameLoop<I, R> {
         })
     }
 
+    /// Play `input`, asking for the promotion piece if it needs one. In a
+    /// replay showing an earlier position, the game branches off there: the
+    /// moves after it are taken back first, and played again if `input` is
+    /// not played after all.
+    fn play_move_input(&mut self, input: MoveInput) -> Option<GameAction> {
+        let Some(ply) = self.replayed_ply() else {
+            let input = self.choose_promotion(input)?;
+            return self.execute_move_input(input);
+        };
+
+        let taken_back: Vec<String> = self.engine.move_history()[ply..]
+            .iter()
+            .map(|entry| entry.notation.clone())
+            .collect();
+        for _ in &taken_back {
+            if let Err(error) = self.engine.undo_move() {
+                self.set_message(format!("Undo failed: {}", error));
+                return None;
+            }
+        }
+        let action = match self.choose_promotion(input) {
+            Some(input) => self.execute_move_input(input),
+            None => None,
+        };
+        if self.engine.move_history().len() == ply {
+            for notation in &taken_back {
+                if self.engine.make_move_san_or_uci(notation).is_err() {
+                    break;
+                }
+                self.engine.board_mut().toggle_turn();
+                self.engine.record_position_hash();
+            }
+            return action;
+        }
+
+        self.renderer.browse_moves(Navigation::Latest);
+        self.set_message("Branched off the game. Enter `mainline` to return to it.".to_string());
+        action
+    }
+
+    /// In a replay, the number of moves played in the earlier position on
+    /// display, if one is.
+    fn replayed_ply(&self) -> Option<usize> {
+        self.renderer.viewed_ply().filter(|_| self.replay)
+    }
+
+    /// Show a replayed game from its starting position.
+    fn show_replay_start(&mut self) {
+        if !self.replay {
+            return;
+        }
+        // The move list learns how many moves there are to browse as it is drawn
+        self.render();
+        self.renderer.browse_moves(Navigation::Start);
+        self.set_message(
+            "Replaying the game: browse it with the arrows, or enter a move to branch off."
+                .to_string(),
+        );
+    }
+
+    /// Leave a branch played off a replayed game, back to the game's own moves.
+    fn return_to_main_line(&mut self) {
+        if !self.replay {
+            self.set_message("Only a replayed game has a main line.".to_string());
+            return;
+        }
+        self.restart_game();
+    }
+
     /// Executes a move input and returns an action if needed
     fn execute_move_input(&mut self, input: MoveInput) -> Option<GameAction> {
-        match self.engine.make_move_from_input(input) {
+        let mover = self.engine.board().turn();
+        let fullmove = self.engine.board().fullmove_clock().value() as usize;
+        let engine_move = matches!(input, MoveInput::UseEngine);
+        let search_limits = self.engine.search_limits();
+        if let (Some(clock), MoveInput::UseEngine) = (&self.clock, &input) {
+            self.engine.set_search_limits(SearchLimits {
+                clock: Some(clock.time_limits(mover)),
+                ..search_limits
+            });
+        }
+        let result = self.with_clock_refresh(|game| game.engine.make_move_from_input(input));
+        self.engine.set_search_limits(search_limits);
+
+        match result {
             Ok(_) => {
+                if engine_move {
+                    self.engine_plies.push(self.engine.move_history().len() - 1);
+                }
                 self.engine.board_mut().toggle_turn();
                 self.engine.record_position_hash();
-                self.apply_frame_delay();
-                None
+                self.message = None;
+                self.emit_move_played(mover);
+                if let Some(clock) = &mut self.clock {
+                    if !clock.stop(mover) {
+                        self.forfeit_on_time(mover);
+                        return None;
+                    }
+                    self.clock_history.push(*clock);
+                    let clock = *clock;
+                    self.emit(GameEvent::ClockUpdated { clock });
+                }
+                if let Some(adjudicator) = &mut self.adjudicator {
+                    // Only the engine's own searches give a current evaluation
+                    let score = self
+                        .engine
+                        .move_history()
+                        .last()
+                        .and_then(|entry| entry.score)
+                        .filter(|_| engine_move);
+                    if let Some(adjudication) = adjudicator.record(fullmove, score) {
+                        self.adjudication = Some(adjudication);
+                        self.end_game(adjudication.result(), GameOverReason::Adjudication);
+                        return None;
+                    }
+                }
+                if let Some(playback) = &mut self.playback {
+                    let history = self.engine.move_history();
+                    let score = history.last().and_then(|entry| entry.score);
+                    playback.after_move(history.len(), score);
+                }
+                self.apply_frame_delay()
             }
             Err(EngineError::SearchError {
                 error: SearchError::Stopped,
             }) => Some(GameAction::Exit),
             Err(error) => {
-                eprintln!("error: {}", error);
+                self.emit(GameEvent::Error {
+                    message: format!("error: {}", error),
+                });
                 None
             }
         }
     }
 
+    /// Report the move just played by `mover`, and the engine's evaluation if
+    /// it searched for it.
+    fn emit_move_played(&mut self, mover: Color) {
+        let history = self.engine.move_history();
+        let Some(entry) = history.last() else {
+            return;
+        };
+        let ply = history.len();
+        let notation = entry.notation.clone();
+        let score = entry.score;
+        self.emit(GameEvent::MovePlayed {
+            mover,
+            notation,
+            ply,
+        });
+        if let Some(score) = score {
+            let depth = self.engine.get_search_stats().depth;
+            self.emit(GameEvent::EvalUpdated { score, depth });
+        }
+    }
+
+    /// Take back the last move of each side, restoring the clocks to when the
+    /// side to move started its previous turn, as the `Undo` input does. Counts
+    /// against the takeback limit; the outcome is reported as a message.
+    fn undo_move_pair(&mut self) {
+        if self.undos_used >= self.max_undos {
+            self.set_message(match self.max_undos {
+                0 => "Takebacks are disabled.".to_string(),
+                _ => "No takebacks left.".to_string(),
+            });
+            return;
+        }
+        if self.engine.move_history().len() < self.opening_moves.len() + 2 {
+            self.set_message("No move to take back.".to_string());
+            return;
+        }
+
+        for _ in 0..2 {
+            if let Err(error) = self.engine.undo_move() {
+                self.set_message(format!("Undo failed: {}", error));
+                return;
+            }
+        }
+        let plies = self.engine.move_history().len();
+        self.engine_plies.retain(|&ply| ply < plies);
+        self.emit(GameEvent::MovesUndone { plies: 2 });
+        if let Some(adjudicator) = &mut self.adjudicator {
+            adjudicator.reset();
+        }
+        if self.clock.is_some() {
+            self.clock_history
+                .truncate(self.clock_history.len().saturating_sub(2));
+            self.clock = self.clock_history.last().copied();
+        }
+        self.undos_used += 1;
+        self.set_message(if self.max_undos == usize::MAX {
+            "Took back the last move pair.".to_string()
+        } else {
+            format!(
+                "Took back the last move pair ({} takeback(s) left).",
+                self.max_undos - self.undos_used
+            )
+        });
+    }
+
+    /// End the game with `loser` out of time.
+    fn forfeit_on_time(&mut self, loser: Color) {
+        if let Some(clock) = &mut self.clock {
+            clock.stop(loser);
+        }
+        self.time_forfeit = Some(loser);
+        self.end_game(
+            PgnResult::win_for(loser.opposite()),
+            GameOverReason::TimeForfeit,
+        );
+    }
+
+    fn end_game(&mut self, result: PgnResult, reason: GameOverReason) {
+        let result = match (result, self.draw_odds) {
+            (PgnResult::Draw, Some(winner)) => {
+                self.won_on_draw_odds = Some(winner);
+                PgnResult::win_for(winner)
+            }
+            (result, _) => result,
+        };
+        self.write_pgn(result);
+        self.learn_from_game(result);
+        self.result = Some(result);
+        self.state = GameLoopState::GameEnded;
+        self.emit(GameEvent::GameOver { result, reason });
+    }
+
+    /// Record the engine's moves of a finished game in the learning file.
+    fn learn_from_game(&mut self, result: PgnResult) {
+        let Some((path, experience)) = &mut self.learning else {
+            return;
+        };
+        let winner = match result {
+            PgnResult::WhiteWins => Some(Color::White),
+            PgnResult::BlackWins => Some(Color::Black),
+            PgnResult::Draw => None,
+            PgnResult::Unfinished => return,
+        };
+        if self.engine_plies.is_empty() {
+            return;
+        }
+        let moves: Vec<ChessMove> = self
+            .engine
+            .move_history()
+            .iter()
+            .map(|entry| entry.chess_move.clone())
+            .collect();
+        let engine_plies = std::mem::take(&mut self.engine_plies);
+        Arc::make_mut(experience).record_game(
+            &self.config.starting_position,
+            &moves,
+            winner,
+            |ply| engine_plies.contains(&ply),
+        );
+        let saved = experience.save(path);
+        self.engine.set_experience(Some(Arc::clone(experience)));
+        if let Err(e) = saved {
+            self.emit(GameEvent::Error {
+                message: e.to_string(),
+            });
+        }
+    }
+
     fn restart_game(&mut self) {
+        let search_limits = self.engine.search_limits();
         self.engine = Engine::with_config(self.config.clone());
+        self.engine.set_search_limits(search_limits);
+        self.engine.set_strength(self.strength);
+        self.engine.set_experience(
+            self.learning
+                .as_ref()
+                .map(|(_, experience)| Arc::clone(experience)),
+        );
+        self.engine_plies.clear();
+        self.play_opening_moves();
         self.state = GameLoopState::Playing;
+        self.result = None;
+        self.pgn_written = false;
+        self.started_at = SystemTime::now();
+        self.clock = self.initial_clock;
+        self.clock_history = self.initial_clock.into_iter().collect();
+        self.time_forfeit = None;
+        self.resigned = None;
+        self.adjudication = None;
+        self.won_on_draw_odds = None;
+        if let Some(adjudicator) = &mut self.adjudicator {
+            adjudicator.reset();
+        }
+        self.undos_used = 0;
+        self.message = None;
+        if let Some(playback) = &mut self.playback {
+            playback.reset();
+        }
+        self.input_source.reset();
+        self.emit(GameEvent::GameStarted);
+        self.show_replay_start();
     }
 
-    fn apply_frame_delay(&self) {
-        if let Some(delay) = self.renderer.frame_delay() {
-            std::thread::sleep(delay);
+    /// Append the current game to the PGN file, once per game. Games without
+    /// any moves are skipped.
+    fn write_pgn(&mut self, result: PgnResult) {
+        let output = match &self.pgn_output {
+            Some(output) => output,
+            None => return,
+        };
+        if self.pgn_written || self.engine.move_history().is_empty() {
+            return;
+        }
+
+        let pgn = self.pgn_record(result, output.include_evals);
+        if let Err(e) = pgn.append_to_file(&output.path) {
+            let message = format!("Failed to write PGN to {}: {}", output.path, e);
+            self.emit(GameEvent::Error { message });
         }
+        self.pgn_written = true;
     }
 
-    /// Check for user interrupt (Ctrl-C) without blocking
-    fn check_for_interrupt(&self) -> bool {
-        use std::time::Duration;
+    /// The game so far as PGN: players, date, time control, starting position
+    /// when custom, and each move with the mover's remaining clock time and,
+    /// with `include_evals`, the engine's evaluation.
+    pub fn pgn_record(&self, result: PgnResult, include_evals: bool) -> PgnGame {
+        let mut pgn = PgnGame::new(&self.config.starting_position);
+        pgn.set_header("Event", "Casual game");
+        pgn.set_header("Date", &pgn_date(self.started_at));
+        pgn.set_header("White", &self.player_names.0);
+        pgn.set_header("Black", &self.player_names.1);
+        match &self.initial_clock {
+            Some(clock) if clock.has_time_odds() => {
+                pgn.set_header("WhiteTimeControl", &clock.time_control_tag(Color::White));
+                pgn.set_header("BlackTimeControl", &clock.time_control_tag(Color::Black));
+            }
+            Some(clock) => pgn.set_header("TimeControl", &clock.time_control_tag(Color::White)),
+            None => {}
+        }
+        if let Some(color) = self.draw_odds {
+            pgn.set_header("DrawOdds", &color.to_string());
+        }
+        if self.time_forfeit.is_some() {
+            pgn.set_header("Termination", "time forfeit");
+        } else if self.adjudication.is_some() {
+            pgn.set_header("Termination", "adjudication");
+        } else if self.won_on_draw_odds.is_some() {
+            pgn.set_header("Termination", "draw odds");
+        }
 
-        if event::poll(Duration::from_millis(0)).unwrap_or(false) {
-            if let Ok(Event::Key(key_event)) = event::read() {
-                if key_event.code == KeyCode::Char('c')
-                    && key_event.modifiers.contains(KeyModifiers::CONTROL)
-                {
-                    return true;
-                }
+        let mut mover = self.config.starting_position.turn();
+        for (ply, entry) in self.engine.move_history().iter().enumerate() {
+            let eval = entry
+                .score
+                .filter(|_| include_evals)
+                .map(format_eval_comment);
+            let clock = ply
+                .checked_sub(self.opening_moves.len())
+                .and_then(|timed_ply| self.clock_history.get(timed_ply + 1))
+                .map(|clock| format_clock_comment(clock.remaining(mover)));
+            let comment = match (eval, clock) {
+                (Some(eval), Some(clock)) => Some(format!("{} {}", eval, clock)),
+                (eval, clock) => eval.or(clock),
+            };
+            pgn.push_move(&entry.notation, comment);
+            mover = mover.opposite();
+        }
+        pgn.set_result(result);
+        pgn
+    }
+
+    /// Suggest a move for the side to move without playing it.
+    fn show_hint(&mut self, show_line: bool) {
+        let text = match self.engine.hint(HINT_MOVETIME, HINT_PV_LENGTH) {
+            Ok(hint) if show_line => format!(
+                "Hint: {} ({}) - {}",
+                hint.san,
+                hint.score.map_or("-".to_string(), format_eval_comment),
+                hint.pv.join(" ")
+            ),
+            Ok(hint) => format!("Hint: {}", hint.san),
+            Err(error) => format!("No hint available: {}", error),
+        };
+        self.set_message(text);
+    }
+
+    /// Save the game so far to `path` on request, replacing the file. Paths
+    /// ending in `.json` get the native format, with the engine's analysis.
+    fn save_game(&mut self, path: &str) {
+        let saved = match GameFileFormat::of(path) {
+            GameFileFormat::Pgn => {
+                let include_evals = self
+                    .pgn_output
+                    .as_ref()
+                    .is_some_and(|output| output.include_evals);
+                let result = self.result.unwrap_or(PgnResult::Unfinished);
+                let pgn = self.pgn_record(result, include_evals);
+                pgn.write_to_file(path).map_err(|e| e.to_string())
+            }
+            GameFileFormat::Native => {
+                game_file::save_native(&self.engine, &self.config.starting_position, path)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        self.set_message(match saved {
+            Ok(()) => format!("Saved the game to {}.", path),
+            Err(e) => format!("Failed to save the game to {}: {}", path, e),
+        });
+    }
+
+    /// Replace the game with the one saved at `path`. Its moves are replayed
+    /// from its starting position like `--fen` and `--moves`, so starting over
+    /// returns to the end of them.
+    fn load_game(&mut self, path: &str) {
+        let game = match game_file::load_game(path) {
+            Ok(game) => game,
+            Err(e) => {
+                self.set_message(format!("Failed to load {}: {}", path, e));
+                return;
+            }
+        };
+        if self.state == GameLoopState::Playing {
+            self.write_pgn(PgnResult::Unfinished);
+        }
+        let moves = game.moves.len();
+        self.config.starting_position = game.starting_position;
+        self.opening_moves = game.moves;
+        self.restart_game();
+        if let Some(native) = game.native {
+            if let Ok(engine) = native.engine(self.config.search_depth) {
+                self.engine.restore_analysis(engine.analysis_snapshot());
+            }
+        }
+
+        let played = self.engine.move_history().len();
+        self.set_message(if played < moves {
+            format!(
+                "Loaded {}, up to an illegal move after {} of its {} moves.",
+                path, played, moves
+            )
+        } else {
+            format!("Loaded the game from {}.", path)
+        });
+    }
+
+    /// Save or load a game through the renderer's file dialog.
+    fn choose_game_file(&mut self, action: GameFileAction) {
+        let Some(path) = self.with_clock_refresh(|game| game.renderer.choose_game_file(action))
+        else {
+            return;
+        };
+        match action {
+            GameFileAction::Save => self.save_game(&path),
+            GameFileAction::Load => self.load_game(&path),
+        }
+    }
+
+    /// Wait between moves. With playback controls the delay comes from the playback
+    /// options and key presses are handled while waiting.
+    fn apply_frame_delay(&mut self) -> Option<GameAction> {
+        let Some(playback) = &self.playback else {
+            if let Some(delay) = self.renderer.frame_delay() {
+                std::thread::sleep(delay);
+            }
+            return None;
+        };
+
+        let deadline = Instant::now() + playback.delay().unwrap_or(Duration::ZERO);
+        while Instant::now() < deadline {
+            if self.check_for_interrupt() {
+                return Some(GameAction::Exit);
+            }
+            if self.playback.as_ref().is_some_and(Playback::is_paused) {
+                break;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            std::thread::sleep(PLAYBACK_POLL_INTERVAL.min(remaining));
+        }
+        None
+    }
+
+    /// Block until the user resumes or steps. Returns to the loop so the new state
+    /// is rendered.
+    fn wait_while_paused(&mut self) -> Option<GameAction> {
+        while self.playback.as_ref().is_some_and(Playback::should_wait) {
+            if event::poll(PLAYBACK_POLL_INTERVAL).unwrap_or(false) && self.handle_key_event() {
+                return Some(GameAction::Exit);
+            }
+        }
+        None
+    }
+
+    /// Check for user interrupt (Ctrl-C) without blocking. Playback keys pressed in
+    /// the meantime are applied.
+    fn check_for_interrupt(&mut self) -> bool {
+        event::poll(Duration::from_millis(0)).unwrap_or(false) && self.handle_key_event()
+    }
+
+    /// Read one pending key event. Returns true on Ctrl-C. Navigation keys
+    /// browse the move list, Tab flips the board, F2 toggles the analysis and
+    /// F3 opens the settings, redrawing right away, as does a resize.
+    fn handle_key_event(&mut self) -> bool {
+        let key_event = match event::read() {
+            Ok(Event::Key(key_event)) => key_event,
+            Ok(Event::Resize(..)) => {
+                self.render();
+                return false;
             }
+            _ => return false,
+        };
+        if key_event.code == KeyCode::Char('c')
+            && key_event.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            return true;
+        }
+        if let Some(navigation) = Navigation::from_key(key_event.code) {
+            self.renderer.browse_moves(navigation);
+            self.render();
+            return false;
+        }
+        if key_event.code == KeyCode::Tab {
+            self.renderer.flip_board();
+            self.render();
+            return false;
+        }
+        if key_event.code == KeyCode::F(2) {
+            self.renderer.toggle_analysis();
+            self.render();
+            return false;
+        }
+        if key_event.code == KeyCode::F(3) {
+            self.show_settings();
+            self.render();
+            return false;
+        }
+        if key_event.code == KeyCode::F(4) {
+            self.renderer.toggle_pieces();
+            self.render();
+            return false;
+        }
+        if let Some(playback) = &mut self.playback {
+            apply_playback_key(playback, key_event);
         }
         false
     }
 }
 
+/// Space toggles pause; `n` or the right arrow steps one move while paused.
+fn apply_playback_key(playback: &mut Playback, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Char(' ') => playback.toggle_pause(),
+        KeyCode::Char('n') | KeyCode::Right => playback.request_step(),
+        _ => {}
+    }
+}
+
 /// View model containing all data needed for rendering
 struct ViewModel {
-    game_ending: Option<crate::evaluate::GameEnding>,
+    game_ending: Option<GameEnding>,
     current_turn: Color,
     last_move: Option<(ChessMove, String)>,
 }
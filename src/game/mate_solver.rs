@@ -0,0 +1,259 @@
+//! Exhaustive forced-mate solver for composing and checking puzzles.
+//!
+//! Unlike the alpha-beta search, which relies on evaluation and pruning, the
+//! solver proves mates: a position is "mate in N" only if every defence is
+//! refuted within N attacking moves. This makes it suitable for verifying that
+//! a puzzle's key move is unique.
+
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::player_is_in_checkmate;
+use crate::move_generator::MoveGenerator;
+
+/// A proven forced mate.
+#[derive(Debug, Clone)]
+pub struct MateSolution {
+    /// Number of attacking moves needed to deliver mate, which can be fewer
+    /// than the solver was asked to prove.
+    pub mate_in: u8,
+    /// Every first move that forces mate within the moves asked for, in SAN.
+    pub key_moves: Vec<String>,
+    /// Main line in SAN: key move, longest defence, and so on until mate.
+    pub line: Vec<String>,
+}
+
+impl MateSolution {
+    /// A puzzle is sound when exactly one key move forces the mate.
+    pub fn is_unique(&self) -> bool {
+        self.key_moves.len() == 1
+    }
+}
+
+/// Find the shortest forced mate for the side to move, trying 1 through
+/// `max_moves` attacking moves.
+pub fn find_forced_mate(board: &mut Board, max_moves: u8) -> Option<MateSolution> {
+    (1..=max_moves).find_map(|n| solve_mate_in(board, n))
+}
+
+/// Prove a forced mate in exactly `n` moves (or fewer) for the side to move.
+pub fn solve_mate_in(board: &mut Board, n: u8) -> Option<MateSolution> {
+    let move_generator = MoveGenerator::default();
+    let attacker = board.turn();
+
+    let keys = key_moves(board, &move_generator, attacker, n);
+    if keys.is_empty() {
+        return None;
+    }
+
+    let notated =
+        enumerate_candidate_moves_with_algebraic_notation(board, attacker, &move_generator);
+    let key_moves = keys.iter().map(|m| san_for(&notated, m)).collect();
+    let line = main_line(board, &move_generator, attacker, n);
+    // The main line mates as fast as possible, so its attacking moves are the
+    // real distance to mate
+    let mate_in = line.len().div_ceil(2) as u8;
+
+    Some(MateSolution {
+        mate_in,
+        key_moves,
+        line,
+    })
+}
+
+/// All moves for `attacker` that force mate within `n` moves.
+fn key_moves(
+    board: &mut Board,
+    move_generator: &MoveGenerator,
+    attacker: Color,
+    n: u8,
+) -> Vec<ChessMove> {
+    move_generator
+        .generate_moves(board, attacker)
+        .into_iter()
+        .filter(|chess_move| move_forces_mate(board, move_generator, chess_move, attacker, n))
+        .collect()
+}
+
+/// Whether `attacker` can force mate within `n` moves from this position.
+fn forces_mate(board: &mut Board, move_generator: &MoveGenerator, attacker: Color, n: u8) -> bool {
+    if n == 0 {
+        return false;
+    }
+    move_generator
+        .generate_moves(board, attacker)
+        .iter()
+        .any(|chess_move| move_forces_mate(board, move_generator, chess_move, attacker, n))
+}
+
+/// Whether playing `chess_move` forces mate within `n` moves (including this one).
+fn move_forces_mate(
+    board: &mut Board,
+    move_generator: &MoveGenerator,
+    chess_move: &ChessMove,
+    attacker: Color,
+    n: u8,
+) -> bool {
+    let defender = attacker.opposite();
+    apply(board, chess_move, defender);
+
+    let replies = move_generator.generate_moves(board, defender);
+    let result = if replies.is_empty() {
+        board.is_in_check(defender)
+    } else if n == 1 {
+        false
+    } else {
+        replies.iter().all(|reply| {
+            apply(board, reply, attacker);
+            let mates = forces_mate(board, move_generator, attacker, n - 1);
+            undo(board, reply, defender);
+            mates
+        })
+    };
+
+    undo(board, chess_move, attacker);
+    result
+}
+
+/// Shortest number of moves (up to `n`) in which `attacker` forces mate.
+fn shortest_mate(
+    board: &mut Board,
+    move_generator: &MoveGenerator,
+    attacker: Color,
+    n: u8,
+) -> Option<u8> {
+    (1..=n).find(|&m| forces_mate(board, move_generator, attacker, m))
+}
+
+/// Build the main line: at each attacking turn play the first move that mates
+/// fastest, and at each defending turn play the reply that delays mate longest.
+fn main_line(
+    board: &mut Board,
+    move_generator: &MoveGenerator,
+    attacker: Color,
+    n: u8,
+) -> Vec<String> {
+    let defender = attacker.opposite();
+    let mut played: Vec<(ChessMove, Color)> = Vec::new();
+    let mut line = Vec::new();
+    let mut remaining = n;
+
+    while let Some(remaining_mate) = shortest_mate(board, move_generator, attacker, remaining) {
+        let notated =
+            enumerate_candidate_moves_with_algebraic_notation(board, attacker, move_generator);
+        let attacking_move = match notated
+            .iter()
+            .find(|(m, _)| move_forces_mate(board, move_generator, m, attacker, remaining_mate))
+        {
+            Some((m, san)) => {
+                line.push(san.clone());
+                m.clone()
+            }
+            None => break,
+        };
+        apply(board, &attacking_move, defender);
+        played.push((attacking_move, attacker));
+
+        if player_is_in_checkmate(board, move_generator, defender) || remaining_mate == 1 {
+            break;
+        }
+
+        let notated =
+            enumerate_candidate_moves_with_algebraic_notation(board, defender, move_generator);
+        let longest_defence = notated.iter().max_by_key(|(reply, _)| {
+            apply(board, reply, attacker);
+            let distance = shortest_mate(board, move_generator, attacker, remaining_mate - 1);
+            undo(board, reply, defender);
+            distance.unwrap_or(u8::MAX)
+        });
+        let (reply, san) = match longest_defence {
+            Some(defence) => defence.clone(),
+            None => break,
+        };
+        line.push(san);
+        apply(board, &reply, attacker);
+        played.push((reply, defender));
+        remaining = remaining_mate - 1;
+    }
+
+    for (chess_move, mover) in played.iter().rev() {
+        undo(board, chess_move, *mover);
+    }
+    line
+}
+
+fn san_for(notated: &[(ChessMove, String)], chess_move: &ChessMove) -> String {
+    notated
+        .iter()
+        .find(|(m, _)| m.to_uci() == chess_move.to_uci())
+        .map(|(_, san)| san.clone())
+        .unwrap_or_else(|| chess_move.to_uci())
+}
+
+/// Apply a move and hand the turn to `next_turn`.
+fn apply(board: &mut Board, chess_move: &ChessMove, next_turn: Color) {
+    chess_move
+        .apply(board)
+        .expect("move application should succeed in mate search");
+    board.set_turn(next_turn);
+}
+
+/// Undo a move and give the turn back to `mover`.
+fn undo(board: &mut Board, chess_move: &ChessMove, mover: Color) {
+    chess_move
+        .undo(board)
+        .expect("move undo should succeed in mate search");
+    board.set_turn(mover);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_mate_in_one_with_multiple_keys() {
+        let mut board = Board::from_str("1Q6/8/8/8/8/k1K5/8/8 w - - 0 1").unwrap();
+        let solution = solve_mate_in(&mut board, 1).expect("position is mate in 1");
+
+        assert_eq!(solution.mate_in, 1);
+        assert!(solution.key_moves.contains(&"Qb3#".to_string()));
+        assert!(!solution.is_unique());
+        assert_eq!(solution.line.len(), 1);
+        assert!(solution.line[0].ends_with('#'));
+    }
+
+    #[test]
+    fn test_shorter_mate_reports_its_real_distance() {
+        let mut board = Board::from_str("1Q6/8/8/8/8/k1K5/8/8 w - - 0 1").unwrap();
+        let solution = solve_mate_in(&mut board, 3).expect("mate in 1 is within 3");
+
+        assert_eq!(solution.mate_in, 1);
+        assert_eq!(solution.line.len(), 1);
+        assert!(solution.line[0].ends_with('#'));
+    }
+
+    #[test]
+    fn test_mate_in_two_line() {
+        let mut board = Board::from_str("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let fen_before = board.to_fen();
+
+        assert!(solve_mate_in(&mut board, 1).is_none());
+        let solution = find_forced_mate(&mut board, 2).expect("position is mate in 2");
+
+        assert_eq!(solution.mate_in, 2);
+        assert!(solution.key_moves.contains(&"Kb6".to_string()));
+        assert_eq!(solution.line.len(), 3);
+        assert!(solution.line[2].ends_with('#'));
+        assert_eq!(board.to_fen(), fen_before, "solver must restore the board");
+    }
+
+    #[test]
+    fn test_stalemate_is_not_mate() {
+        // Black to move has no legal moves but is not in check.
+        let mut board = Board::from_str("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(find_forced_mate(&mut board, 1).is_none());
+    }
+}
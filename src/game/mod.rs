@@ -1,14 +1,33 @@
 //! Game loop and engine coordination.
 
 pub mod action;
+pub mod adjudication;
 pub mod alpha_beta_benchmark;
+pub mod analysis;
+pub mod annotate;
+pub mod bench;
+pub mod clock;
+pub mod correspondence;
 pub mod display;
+pub mod drill;
 pub mod engine;
+pub mod epd_suite;
+pub mod events;
+pub mod game_file;
 pub mod input_source;
+pub mod learning;
 pub mod r#loop; // `loop` is reserved keyword, need to escape with `r#`
+pub mod mate_solver;
 pub mod mode;
+pub mod move_list;
+pub mod odds;
+pub mod pgn;
+pub mod playback;
 pub mod position_counter;
 pub mod puzzle_suite;
 pub mod renderer;
+pub mod spsa;
 pub mod stockfish_elo;
-mod stockfish_interface;
+pub mod strength;
+pub mod tournament;
+pub mod uci_engine;
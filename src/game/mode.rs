@@ -2,6 +2,7 @@ use crate::board::color::Color;
 use crate::chess_move::chess_move::ChessMove;
 use crate::game::display::GameDisplay;
 use crate::game::engine::Engine;
+use crate::game::move_list::format_move_list;
 use crate::input_handler::{parse_move_input, MoveInput};
 use std::time::Duration;
 
@@ -56,10 +57,12 @@ impl GameMode for HumanVsComputer {
                 .map_or("-".to_string(), |d| format!("{:?}", d))
         );
         let opening_name = engine.get_book_line_name();
+        let move_list = format_move_list(&engine.move_list());
         ui.render_game_state(
             engine.board(),
             current_turn,
             last_move,
+            &move_list,
             Some(&stats_display),
             opening_name.as_deref(),
         );
@@ -96,10 +99,12 @@ impl GameMode for ComputerVsComputer {
                 .map_or("-".to_string(), |d| format!("{:?}", d))
         );
         let opening_name = engine.get_book_line_name();
+        let move_list = format_move_list(&engine.move_list());
         ui.render_game_state(
             engine.board(),
             current_turn,
             last_move,
+            &move_list,
             Some(&stats_display),
             opening_name.as_deref(),
         );
@@ -123,10 +128,12 @@ impl GameMode for HumanVsHuman {
         last_move: Option<(&ChessMove, &str)>,
     ) {
         let opening_name = engine.get_book_line_name();
+        let move_list = format_move_list(&engine.move_list());
         ui.render_game_state(
             engine.board(),
             current_turn,
             last_move,
+            &move_list,
             None,
             opening_name.as_deref(),
         );
@@ -0,0 +1,106 @@
+//! Numbered move lists in SAN, e.g. `1. e4 e5 2. Nf3 Nc6`.
+//!
+//! Moves are grouped into rows of one full move. A game starting with Black to
+//! move gets a first row without a White move, written `1... e5`.
+
+use crate::board::color::Color;
+use crate::game::engine::MoveHistoryEntry;
+
+/// One full move: the move number and the White and Black moves played in it.
+#[derive(Debug, Clone, Copy)]
+pub struct MoveListRow<'a> {
+    pub number: usize,
+    pub white: Option<&'a MoveHistoryEntry>,
+    pub black: Option<&'a MoveHistoryEntry>,
+}
+
+/// Group `history` into numbered rows, for a game that started with
+/// `starting_turn` to move on move `starting_fullmove`.
+pub fn move_list_rows(
+    history: &[MoveHistoryEntry],
+    starting_turn: Color,
+    starting_fullmove: usize,
+) -> Vec<MoveListRow<'_>> {
+    let mut rows: Vec<MoveListRow> = Vec::new();
+    let mut turn = starting_turn;
+    let mut number = starting_fullmove.max(1);
+
+    for entry in history {
+        match turn {
+            Color::White => rows.push(MoveListRow {
+                number,
+                white: Some(entry),
+                black: None,
+            }),
+            Color::Black => match rows.last_mut() {
+                Some(row) if row.number == number => row.black = Some(entry),
+                _ => rows.push(MoveListRow {
+                    number,
+                    white: None,
+                    black: Some(entry),
+                }),
+            },
+        }
+        if turn == Color::Black {
+            number += 1;
+        }
+        turn = turn.opposite();
+    }
+    rows
+}
+
+/// Format rows as a single line, e.g. `1. e4 e5 2. Nf3` or `12... Kd7 13. Ra1`.
+pub fn format_move_list(rows: &[MoveListRow]) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    for row in rows {
+        match (row.white, row.black) {
+            (Some(white), black) => {
+                tokens.push(format!("{}. {}", row.number, white.notation));
+                if let Some(black) = black {
+                    tokens.push(black.notation.clone());
+                }
+            }
+            (None, Some(black)) => tokens.push(format!("{}... {}", row.number, black.notation)),
+            (None, None) => {}
+        }
+    }
+    tokens.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::game::engine::position_after_moves;
+    use crate::game::engine::{Engine, EngineConfig};
+
+    fn play(starting_position: Board, moves: &[&str]) -> Engine {
+        let mut engine = Engine::with_config(EngineConfig {
+            starting_position,
+            ..EngineConfig::default()
+        });
+        for notation in moves {
+            engine.make_move_san_or_uci(notation).unwrap();
+            engine.board_mut().toggle_turn();
+            engine.record_position_hash();
+        }
+        engine
+    }
+
+    #[test]
+    fn test_move_list_numbers_full_moves_with_check_suffixes() {
+        let engine = play(Board::default(), &["f3", "e5", "g4", "Qh4"]);
+        let rows = move_list_rows(engine.move_history(), Color::White, 1);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(format_move_list(&rows), "1. f3 e5 2. g4 Qh4#");
+    }
+
+    #[test]
+    fn test_move_list_starting_with_black() {
+        let start = position_after_moves(Board::default(), &["e4".to_string()]).unwrap();
+        let engine = play(start, &["e5", "Nf3"]);
+        let rows = move_list_rows(engine.move_history(), Color::Black, 1);
+        assert!(rows[0].white.is_none());
+        assert_eq!(format_move_list(&rows), "1... e5 2. Nf3");
+    }
+}
@@ -0,0 +1,119 @@
+//! Material odds for handicap games.
+//!
+//! The stronger side starts without a piece: the f-pawn for pawn odds, the
+//! queen's knight or rook, or the queen. Removing the queen's rook also gives up
+//! queenside castling. The result is an ordinary custom starting position, so it
+//! is recorded in the PGN `SetUp`/`FEN` headers like any other.
+
+use std::fmt;
+use std::str::FromStr;
+
+use common::bitboard::Square;
+
+use crate::board::castle_rights::CastleRights;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Odds {
+    Pawn,
+    Knight,
+    Rook,
+    Queen,
+}
+
+impl Odds {
+    /// The piece given up and the square it starts on for `color`.
+    fn removed_piece(self, color: Color) -> (Piece, &'static str) {
+        let (piece, white, black) = match self {
+            Odds::Pawn => (Piece::Pawn, "f2", "f7"),
+            Odds::Knight => (Piece::Knight, "b1", "b8"),
+            Odds::Rook => (Piece::Rook, "a1", "a8"),
+            Odds::Queen => (Piece::Queen, "d1", "d8"),
+        };
+        match color {
+            Color::White => (piece, white),
+            Color::Black => (piece, black),
+        }
+    }
+
+    /// The position `board` with `color` giving these odds. Fails if the piece
+    /// is not on its starting square.
+    pub fn apply(self, board: &Board, color: Color) -> Result<Board, String> {
+        let (piece, square_name) = self.removed_piece(color);
+        let square = Square::from_algebraic(square_name)
+            .ok_or_else(|| format!("invalid square {}", square_name))?;
+        if board.get(square) != Some((piece, color)) {
+            return Err(format!(
+                "{} odds need a {} {:?} on {}",
+                self, color, piece, square_name
+            ));
+        }
+
+        let mut board = board.clone();
+        board.remove(square);
+        if self == Odds::Rook {
+            board.lose_castle_rights(match color {
+                Color::White => CastleRights::white_queenside(),
+                Color::Black => CastleRights::black_queenside(),
+            });
+        }
+        // Round-trip through FEN for a position without any history
+        board
+            .to_fen()
+            .parse()
+            .map_err(|e| format!("invalid odds position: {:?}", e))
+    }
+}
+
+impl FromStr for Odds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pawn" | "p" => Ok(Odds::Pawn),
+            "knight" | "n" => Ok(Odds::Knight),
+            "rook" | "r" => Ok(Odds::Rook),
+            "queen" | "q" => Ok(Odds::Queen),
+            _ => Err(format!(
+                "invalid odds {:?}; options are: pawn, knight, rook, queen",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Odds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Odds::Pawn => "pawn",
+            Odds::Knight => "knight",
+            Odds::Rook => "rook",
+            Odds::Queen => "queen",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rook_odds_removes_rook_and_queenside_castling() {
+        let board = Odds::Rook.apply(&Board::default(), Color::Black).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1"
+        );
+    }
+
+    #[test]
+    fn test_odds_need_the_piece_on_its_starting_square() {
+        let board = Odds::Knight.apply(&Board::default(), Color::White).unwrap();
+        assert!(Odds::Knight.apply(&board, Color::White).is_err());
+        assert_eq!("N".parse::<Odds>(), Ok(Odds::Knight));
+        assert!("bishop".parse::<Odds>().is_err());
+    }
+}
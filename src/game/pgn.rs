@@ -0,0 +1,613 @@
+//! PGN (Portable Game Notation) import and export.
+//!
+//! Builds PGN text from a list of SAN moves plus header tags, and appends
+//! finished games to a file. `parse_pgn` reads games back, keeping the tags,
+//! the mainline moves and their comments; variations and NAGs are skipped.
+//! `input_handler::pgn` keeps them, resolving every move on the board. See
+//! https://www.chessprogramming.org/Portable_Game_Notation
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+
+use crate::board::color::Color;
+use crate::board::{Board, Variant};
+use crate::input_handler::fen::{FenParseError, STARTING_POSITION_FEN};
+
+/// Maximum line length for movetext, per the PGN export format.
+const MAX_LINE_WIDTH: usize = 80;
+
+/// Outcome of a game as written in the `Result` tag and after the movetext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgnResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unfinished,
+}
+
+impl PgnResult {
+    /// The result for a win by `color`.
+    pub fn win_for(color: Color) -> Self {
+        match color {
+            Color::White => PgnResult::WhiteWins,
+            Color::Black => PgnResult::BlackWins,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PgnResult::WhiteWins => "1-0",
+            PgnResult::BlackWins => "0-1",
+            PgnResult::Draw => "1/2-1/2",
+            PgnResult::Unfinished => "*",
+        }
+    }
+
+    fn parse(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(PgnResult::WhiteWins),
+            "0-1" => Some(PgnResult::BlackWins),
+            "1/2-1/2" => Some(PgnResult::Draw),
+            "*" => Some(PgnResult::Unfinished),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PgnResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Settings for recording interactive games to a PGN file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnOutput {
+    pub path: String,
+    /// Attach the engine's evaluation to each move as a comment.
+    pub include_evals: bool,
+}
+
+/// Format a centipawn score (White's perspective) as a PGN comment in pawns, e.g. `+0.35`.
+pub fn format_eval_comment(score: i16) -> String {
+    format!("{:+.2}", score as f32 / 100.0)
+}
+
+/// Format a player's remaining time after a move as a PGN `%clk` command, e.g.
+/// `[%clk 0:04:58]`.
+pub fn format_clock_comment(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    format!(
+        "[%clk {}:{:02}:{:02}]",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Format `time` as a PGN `Date` tag value (`YYYY.MM.DD`, UTC).
+pub fn pgn_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() / 86_400) as i64;
+
+    // Convert days since 1970-01-01 to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// A single move in SAN with an optional comment (e.g. an engine evaluation).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+    pub san: String,
+    pub comment: Option<String>,
+}
+
+/// A complete game ready to be written as PGN.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    headers: Vec<(String, String)>,
+    moves: Vec<PgnMove>,
+    result: PgnResult,
+    starting_turn: Color,
+    starting_fullmove: u16,
+}
+
+impl PgnGame {
+    /// Create a game starting from `starting_position`. The seven-tag roster is
+    /// pre-populated with placeholders; non-standard starting positions also get
+    /// `SetUp` and `FEN` tags, and variants a `Variant` tag.
+    pub fn new(starting_position: &Board) -> Self {
+        let mut game = Self {
+            headers: Vec::new(),
+            moves: Vec::new(),
+            result: PgnResult::Unfinished,
+            starting_turn: starting_position.turn(),
+            starting_fullmove: starting_position.fullmove_clock().value().max(1),
+        };
+        for tag in ["Event", "Site", "Date", "Round", "White", "Black"] {
+            game.set_header(tag, "?");
+        }
+        game.set_header("Result", PgnResult::Unfinished.as_str());
+
+        let fen = starting_position.to_fen();
+        if fen != STARTING_POSITION_FEN {
+            game.set_header("SetUp", "1");
+            game.set_header("FEN", &fen);
+        }
+        if starting_position.variant() != Variant::Standard {
+            game.set_header("Variant", starting_position.variant().name());
+        }
+        game
+    }
+
+    /// Set a header tag, replacing any existing value for the same tag.
+    pub fn set_header(&mut self, tag: &str, value: &str) {
+        match self.headers.iter_mut().find(|(t, _)| t == tag) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.headers.push((tag.to_string(), value.to_string())),
+        }
+    }
+
+    /// All header tags in the order they will be written.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    pub fn header(&self, tag: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn push_move(&mut self, san: &str, comment: Option<String>) {
+        self.moves.push(PgnMove {
+            san: san.to_string(),
+            comment,
+        });
+    }
+
+    pub fn moves(&self) -> &[PgnMove] {
+        &self.moves
+    }
+
+    pub fn set_result(&mut self, result: PgnResult) {
+        self.result = result;
+        self.set_header("Result", result.as_str());
+    }
+
+    pub fn result(&self) -> PgnResult {
+        self.result
+    }
+
+    /// The position the game starts from: the `FEN` tag if present, otherwise
+    /// the standard starting position, played by the `Variant` tag's rules.
+    pub fn starting_position(&self) -> Result<Board, FenParseError> {
+        let mut board = match self.header("FEN") {
+            Some(fen) => Board::from_str(fen)?,
+            None => Board::default(),
+        };
+        if let Some(variant) = self.header("Variant").and_then(|v| v.parse().ok()) {
+            board.set_variant(variant);
+        }
+        Ok(board)
+    }
+
+    /// Write this game alone to `path`, replacing the file's contents.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Append this game to the PGN file at `path`, creating it if needed.
+    pub fn append_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", self)
+    }
+
+    fn movetext_tokens(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut fullmove = self.starting_fullmove as usize;
+        let mut turn = self.starting_turn;
+
+        for (i, pgn_move) in self.moves.iter().enumerate() {
+            match turn {
+                Color::White => tokens.push(format!("{}.", fullmove)),
+                Color::Black if i == 0 => tokens.push(format!("{}...", fullmove)),
+                Color::Black => {}
+            }
+            tokens.push(pgn_move.san.clone());
+            if let Some(comment) = &pgn_move.comment {
+                tokens.push(format!("{{{}}}", comment));
+            }
+            if turn == Color::Black {
+                fullmove += 1;
+            }
+            turn = turn.opposite();
+        }
+
+        tokens.push(self.result.as_str().to_string());
+        tokens
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (tag, value) in &self.headers {
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", tag, escaped)?;
+        }
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for token in self.movetext_tokens() {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PgnParseError {
+    #[error("Malformed tag pair: {text:?}")]
+    MalformedTag { text: String },
+    #[error("Invalid FEN tag {fen:?}: {error}")]
+    InvalidFen { fen: String, error: FenParseError },
+    #[error("Unterminated {what}")]
+    Unterminated { what: &'static str },
+}
+
+/// A game being read: tags and mainline moves seen so far.
+#[derive(Default)]
+struct PartialGame {
+    headers: Vec<(String, String)>,
+    moves: Vec<PgnMove>,
+    result: Option<PgnResult>,
+}
+
+impl PartialGame {
+    fn is_empty(&self) -> bool {
+        self.headers.is_empty() && self.moves.is_empty() && self.result.is_none()
+    }
+
+    fn finish(self) -> Result<PgnGame, PgnParseError> {
+        let fen = self
+            .headers
+            .iter()
+            .find(|(tag, _)| tag == "FEN")
+            .map(|(_, fen)| fen.clone());
+        let starting_position = match fen {
+            Some(fen) => Board::from_str(&fen).map_err(|error| PgnParseError::InvalidFen {
+                fen: fen.clone(),
+                error,
+            })?,
+            None => Board::default(),
+        };
+
+        let mut game = PgnGame::new(&starting_position);
+        for (tag, value) in &self.headers {
+            game.set_header(tag, value);
+        }
+        game.moves = self.moves;
+        let result = self
+            .result
+            .or_else(|| game.header("Result").and_then(PgnResult::parse))
+            .unwrap_or(PgnResult::Unfinished);
+        game.set_result(result);
+        Ok(game)
+    }
+}
+
+/// Parse every game in `text`. Comments following a move are attached to it;
+/// recursive variations, NAGs, move numbers and `;` comments are skipped.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnParseError> {
+    let mut games = Vec::new();
+    let mut current = PartialGame::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let text = read_until(&mut chars, ']', "tag pair")?;
+                if !current.moves.is_empty() || current.result.is_some() {
+                    games.push(std::mem::take(&mut current).finish()?);
+                }
+                current.headers.push(parse_tag_pair(&text)?);
+            }
+            '{' => {
+                chars.next();
+                let comment = read_until(&mut chars, '}', "comment")?;
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                if let Some(last) = current.moves.last_mut() {
+                    last.comment = Some(match last.comment.take() {
+                        Some(existing) => format!("{} {}", existing, comment),
+                        None => comment,
+                    });
+                }
+            }
+            ';' | '%' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => skip_variation(&mut chars)?,
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[]{}();".contains(c) {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                if token.is_empty() {
+                    // A stray closing bracket.
+                    chars.next();
+                    continue;
+                }
+                if let Some(result) = PgnResult::parse(&token) {
+                    current.result = Some(result);
+                    games.push(std::mem::take(&mut current).finish()?);
+                    continue;
+                }
+                let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                if !san.is_empty() && !san.starts_with('$') {
+                    current.moves.push(PgnMove {
+                        san: san.to_string(),
+                        comment: None,
+                    });
+                }
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        games.push(current.finish()?);
+    }
+    Ok(games)
+}
+
+/// Read up to `terminator`, which is consumed, e.g. the rest of a tag pair or
+/// comment. A `]` inside a tag's quoted value does not end it.
+pub(crate) fn read_until(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    terminator: char,
+    what: &'static str,
+) -> Result<String, PgnParseError> {
+    let mut text = String::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for c in chars.by_ref() {
+        if c == terminator && !in_quotes {
+            return Ok(text);
+        }
+        if terminator == ']' {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_quotes = !in_quotes;
+            }
+        }
+        text.push(c);
+    }
+    Err(PgnParseError::Unterminated { what })
+}
+
+fn skip_variation(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+) -> Result<(), PgnParseError> {
+    let mut depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+            '{' => {
+                read_until(chars, '}', "comment")?;
+            }
+            _ => {}
+        }
+    }
+    Err(PgnParseError::Unterminated { what: "variation" })
+}
+
+/// Split the text between a tag pair's brackets into its name and unescaped
+/// value.
+pub(crate) fn parse_tag_pair(text: &str) -> Result<(String, String), PgnParseError> {
+    let malformed = || PgnParseError::MalformedTag {
+        text: text.to_string(),
+    };
+    let (tag, value) = text
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(malformed)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(malformed)?;
+    let value = value.replace("\\\"", "\"").replace("\\\\", "\\");
+    Ok((tag.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_pgn_from_starting_position() {
+        let mut game = PgnGame::new(&Board::default());
+        game.set_header("White", "Alice");
+        game.set_header("Black", "Bob");
+        game.push_move("e4", None);
+        game.push_move("e5", None);
+        game.push_move("Qh5", Some("+0.35".to_string()));
+        game.set_result(PgnResult::WhiteWins);
+
+        let pgn = game.to_string();
+        assert!(pgn.contains("[White \"Alice\"]"));
+        assert!(pgn.contains("[Result \"1-0\"]"));
+        assert!(!pgn.contains("[FEN"));
+        assert!(pgn.contains("1. e4 e5 2. Qh5 {+0.35} 1-0"));
+    }
+
+    #[test]
+    fn test_pgn_from_fen_with_black_to_move() {
+        let board =
+            Board::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let mut game = PgnGame::new(&board);
+        game.push_move("e5", None);
+        game.push_move("Nf3", None);
+        game.set_result(PgnResult::Draw);
+
+        let pgn = game.to_string();
+        assert!(pgn.contains("[SetUp \"1\"]"));
+        assert!(
+            pgn.contains("[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\"]")
+        );
+        assert!(pgn.contains("1... e5 2. Nf3 1/2-1/2"));
+    }
+
+    #[test]
+    fn test_pgn_wraps_long_movetext() {
+        let mut game = PgnGame::new(&Board::default());
+        for _ in 0..40 {
+            game.push_move("Nf3", None);
+            game.push_move("Nf6", None);
+        }
+        let pgn = game.to_string();
+        assert!(pgn.lines().all(|line| line.len() <= MAX_LINE_WIDTH));
+    }
+
+    #[test]
+    fn test_format_eval_comment() {
+        assert_eq!(format_eval_comment(35), "+0.35");
+        assert_eq!(format_eval_comment(-120), "-1.20");
+        assert_eq!(format_eval_comment(0), "+0.00");
+    }
+
+    #[test]
+    fn test_format_clock_comment() {
+        assert_eq!(
+            format_clock_comment(Duration::from_millis(298_700)),
+            "[%clk 0:04:58]"
+        );
+        assert_eq!(
+            format_clock_comment(Duration::from_secs(3 * 3600 + 5)),
+            "[%clk 3:00:05]"
+        );
+    }
+
+    #[test]
+    fn test_pgn_date() {
+        assert_eq!(pgn_date(UNIX_EPOCH), "1970.01.01");
+        assert_eq!(
+            pgn_date(UNIX_EPOCH + Duration::from_secs(1_709_208_000)),
+            "2024.02.29"
+        );
+    }
+
+    #[test]
+    fn test_set_header_replaces_existing_value() {
+        let mut game = PgnGame::new(&Board::default());
+        game.set_header("Event", "Test");
+        assert_eq!(game.header("Event"), Some("Test"));
+        assert_eq!(game.to_string().matches("[Event ").count(), 1);
+    }
+
+    #[test]
+    fn test_parse_pgn_reads_tags_moves_and_comments() {
+        let text = r#"[Event "Casual \"blitz\""]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {Best by test} e5 (1... c5 2. Nf3 {Sicilian}) 2. Qh5 $2 Nc6 ; threat
+3.Bc4 Nf6?? 4. Qxf7# 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"]
+
+1... e5 *
+"#;
+        let games = parse_pgn(text).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let first = &games[0];
+        assert_eq!(first.header("Event"), Some("Casual \"blitz\""));
+        assert_eq!(first.header("White"), Some("Alice"));
+        assert_eq!(first.result(), PgnResult::WhiteWins);
+        let sans: Vec<&str> = first.moves().iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6??", "Qxf7#"]);
+        assert_eq!(first.moves()[0].comment.as_deref(), Some("Best by test"));
+        assert_eq!(first.moves()[1].comment, None);
+
+        let second = &games[1];
+        assert_eq!(second.result(), PgnResult::Unfinished);
+        assert_eq!(second.starting_position().unwrap().turn(), Color::Black);
+        assert_eq!(second.moves().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_pgn_round_trips_exported_game() {
+        let mut game = PgnGame::new(&Board::default());
+        game.set_header("White", "Alice");
+        game.push_move("d4", Some("+0.20".to_string()));
+        game.push_move("d5", None);
+        game.set_result(PgnResult::Draw);
+
+        let parsed = parse_pgn(&game.to_string()).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].to_string(), game.to_string());
+    }
+
+    #[test]
+    fn test_parse_pgn_rejects_unterminated_comment() {
+        assert!(matches!(
+            parse_pgn("1. e4 {never closed"),
+            Err(PgnParseError::Unterminated { .. })
+        ));
+    }
+}
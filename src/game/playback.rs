@@ -0,0 +1,157 @@
+//! Playback controls for engine-vs-engine games.
+//!
+//! Watch mode can slow the game down with a per-move delay, be paused, resumed,
+//! and single-stepped from the keyboard, and pause itself at a given move number
+//! or the first time the evaluation crosses a threshold.
+
+use std::time::Duration;
+
+/// Playback settings chosen on the command line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlaybackOptions {
+    /// Wait this long after each move.
+    pub delay: Option<Duration>,
+    /// Pause once this many full moves have been played.
+    pub pause_at_move: Option<usize>,
+    /// Pause the first time the absolute evaluation reaches this many centipawns.
+    pub pause_at_eval: Option<i16>,
+}
+
+/// Pause/step state for a game in progress.
+#[derive(Debug, Clone)]
+pub struct Playback {
+    options: PlaybackOptions,
+    paused: bool,
+    step_requested: bool,
+    move_pause_done: bool,
+    eval_pause_done: bool,
+}
+
+impl Playback {
+    pub fn new(options: PlaybackOptions) -> Self {
+        Self {
+            options,
+            paused: false,
+            step_requested: false,
+            move_pause_done: false,
+            eval_pause_done: false,
+        }
+    }
+
+    pub fn delay(&self) -> Option<Duration> {
+        self.options.delay
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether the next move must wait for the user.
+    pub fn should_wait(&self) -> bool {
+        self.paused && !self.step_requested
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.step_requested = false;
+    }
+
+    /// Allow exactly one move while paused.
+    pub fn request_step(&mut self) {
+        if self.paused {
+            self.step_requested = true;
+        }
+    }
+
+    /// Record that a move was played. `plies` is the number of half-moves played so
+    /// far and `score` the latest evaluation from White's perspective. Pauses when a
+    /// stop condition is first met.
+    pub fn after_move(&mut self, plies: usize, score: Option<i16>) {
+        self.step_requested = false;
+
+        if let Some(full_moves) = self.options.pause_at_move {
+            if !self.move_pause_done && plies >= full_moves * 2 {
+                self.move_pause_done = true;
+                self.paused = true;
+            }
+        }
+        if let (Some(threshold), Some(score)) = (self.options.pause_at_eval, score) {
+            if !self.eval_pause_done && score.unsigned_abs() >= threshold.unsigned_abs() {
+                self.eval_pause_done = true;
+                self.paused = true;
+            }
+        }
+    }
+
+    /// Reset for a new game, keeping the options.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.options);
+    }
+
+    /// Status line describing the state and the available keys.
+    pub fn status(&self) -> String {
+        if self.paused {
+            "Paused - [space] resume  [n] step  [Ctrl-C] exit".to_string()
+        } else {
+            "Watching - [space] pause  [Ctrl-C] exit".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_allows_one_move_while_paused() {
+        let mut playback = Playback::new(PlaybackOptions::default());
+        assert!(!playback.should_wait());
+
+        playback.toggle_pause();
+        assert!(playback.should_wait());
+
+        playback.request_step();
+        assert!(!playback.should_wait());
+        playback.after_move(1, None);
+        assert!(playback.should_wait());
+
+        playback.toggle_pause();
+        assert!(!playback.should_wait());
+    }
+
+    #[test]
+    fn test_pauses_once_at_move_number() {
+        let mut playback = Playback::new(PlaybackOptions {
+            pause_at_move: Some(2),
+            ..PlaybackOptions::default()
+        });
+        playback.after_move(3, None);
+        assert!(!playback.is_paused());
+        playback.after_move(4, None);
+        assert!(playback.is_paused());
+
+        playback.toggle_pause();
+        playback.after_move(5, None);
+        assert!(!playback.is_paused(), "move pause only triggers once");
+    }
+
+    #[test]
+    fn test_pauses_once_when_eval_crosses_threshold() {
+        let mut playback = Playback::new(PlaybackOptions {
+            pause_at_eval: Some(300),
+            ..PlaybackOptions::default()
+        });
+        playback.after_move(1, Some(120));
+        assert!(!playback.is_paused());
+        playback.after_move(2, Some(-310));
+        assert!(playback.is_paused());
+
+        playback.toggle_pause();
+        playback.after_move(3, Some(-400));
+        assert!(!playback.is_paused(), "eval pause only triggers once");
+
+        playback.reset();
+        playback.after_move(1, Some(500));
+        assert!(playback.is_paused(), "reset re-arms the stop conditions");
+    }
+}
@@ -1,12 +1,14 @@
 use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 
+use serde::Serialize;
+
 use crate::alpha_beta_searcher::SearchContext;
 use crate::board::color::Color;
 use crate::board::Board;
 use crate::chess_search::search_best_move;
 use crate::diagnostics::memory_profiler::MemoryProfiler;
-use crate::move_generator::MoveGenerator;
+use crate::move_generator::{MoveGenerator, PerftHash};
 
 #[derive(Debug)]
 pub enum CountPositionsStrategy {
@@ -25,16 +27,292 @@ impl FromStr for CountPositionsStrategy {
     }
 }
 
-pub fn run_count_positions(depth: u8, strategy: CountPositionsStrategy) {
-    MemoryProfiler::reset();
+/// Reference perft results for standard test positions, from the Chess
+/// Programming Wiki. `counts[i]` is perft(i + 1).
+pub const PERFT_REFERENCE: &[(&str, &str, &[usize])] = &[
+    (
+        "initial",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        &[20, 400, 8_902, 197_281, 4_865_609, 119_060_324],
+    ),
+    (
+        "kiwipete",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2_039, 97_862, 4_085_603, 193_690_690],
+    ),
+    (
+        "position 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2_812, 43_238, 674_624, 11_030_083],
+    ),
+    (
+        "position 4",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9_467, 422_333, 15_833_292],
+    ),
+    (
+        "position 5",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1_486, 62_379, 2_103_487, 89_941_194],
+    ),
+    (
+        "position 6",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2_079, 89_890, 3_894_594, 164_075_551],
+    ),
+];
+
+/// Outcome of checking one position at one depth against `PERFT_REFERENCE`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PerftCheck {
+    pub name: &'static str,
+    pub depth: u8,
+    pub expected: usize,
+    pub actual: usize,
+    pub time_ms: u128,
+}
+
+impl PerftCheck {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// The reference perft count for `fen` at `depth`, if the table has one.
+pub fn reference_perft(fen: &str, depth: u8) -> Option<usize> {
+    fn position(fen: &str) -> Vec<&str> {
+        fen.split_whitespace().take(4).collect()
+    }
+    PERFT_REFERENCE
+        .iter()
+        .find(|(_, reference_fen, _)| position(reference_fen) == position(fen))
+        .and_then(|(_, _, counts)| counts.get(usize::from(depth).checked_sub(1)?).copied())
+}
+
+/// A perft hash of `hash_mb` megabytes, or none for 0.
+fn perft_hash(hash_mb: usize) -> Option<PerftHash> {
+    (hash_mb > 0).then(|| PerftHash::new(hash_mb))
+}
 
-    let depths = 1..=depth;
+/// Run perft on every reference position for each depth up to `max_depth`
+/// (limited to the depths the table covers), calling `on_check` as each completes.
+/// With `hash_mb` above 0, subtree counts are cached in a perft hash that size.
+pub fn verify_perft<F>(max_depth: u8, hash_mb: usize, mut on_check: F) -> Vec<PerftCheck>
+where
+    F: FnMut(&PerftCheck),
+{
     let move_generator = MoveGenerator::default();
+    let hash = perft_hash(hash_mb);
+    let mut checks = Vec::new();
 
-    let mut total_positions = 0;
+    for &(name, fen, counts) in PERFT_REFERENCE {
+        let mut board = Board::from_str(fen).expect("reference FEN should be valid");
+        let turn = board.turn();
+        for (index, &expected) in counts.iter().enumerate().take(max_depth as usize) {
+            let depth = index as u8 + 1;
+            let starting_time = SystemTime::now();
+            let actual = match &hash {
+                Some(hash) => move_generator.perft_hashed(depth, &mut board, turn, hash),
+                None => move_generator.perft(depth, &mut board, turn),
+            };
+            let duration = SystemTime::now().duration_since(starting_time).unwrap();
+
+            let check = PerftCheck {
+                name,
+                depth,
+                expected,
+                actual,
+                time_ms: duration.as_millis(),
+            };
+            on_check(&check);
+            checks.push(check);
+        }
+    }
+
+    checks
+}
+
+/// Verify move generation against the reference table, printing each result.
+/// Returns whether every check passed.
+pub fn run_verify_perft(max_depth: u8, hash_mb: usize, json: bool) -> bool {
+    if json {
+        let checks = verify_perft(max_depth, hash_mb, |_| {});
+        let passed = checks.iter().all(PerftCheck::passed);
+        println!(
+            "{}",
+            serde_json::json!({ "passed": passed, "checks": checks })
+        );
+        return passed;
+    }
+
+    let checks = verify_perft(max_depth, hash_mb, |check| {
+        println!(
+            "{:<12} depth {}: {:>12} expected {:>12} ({}ms) {}",
+            check.name,
+            check.depth,
+            check.actual,
+            check.expected,
+            check.time_ms,
+            if check.passed() { "ok" } else { "MISMATCH" }
+        );
+    });
+
+    let failures = checks.iter().filter(|check| !check.passed()).count();
+    if failures == 0 {
+        println!("All {} perft checks passed.", checks.len());
+    } else {
+        println!("{} of {} perft checks FAILED.", failures, checks.len());
+    }
+    failures == 0
+}
+
+/// Leaf count under one root move.
+#[derive(Debug, Clone, Serialize)]
+pub struct DivideCount {
+    #[serde(rename = "move")]
+    pub uci: String,
+    pub nodes: usize,
+}
+
+/// Perft of one position split by root move, checked against an expected
+/// total when there is one.
+#[derive(Debug, Clone, Serialize)]
+pub struct PerftDivide {
+    pub fen: String,
+    pub depth: u8,
+    pub moves: Vec<DivideCount>,
+    pub nodes: usize,
+    pub expected: Option<usize>,
+    pub time_ms: u128,
+    pub nps: u64,
+}
+
+impl PerftDivide {
+    /// Whether the total matches the expected count, or there is none.
+    pub fn passed(&self) -> bool {
+        self.expected.is_none_or(|expected| expected == self.nodes)
+    }
+}
+
+/// Perft divide of `board` at `depth`, with root moves sorted by their UCI
+/// notation so the output lines up with other engines' divides. `expected`
+/// defaults to the reference count when `board` is a reference position.
+pub fn perft_divide(
+    board: &Board,
+    depth: u8,
+    hash_mb: usize,
+    expected: Option<usize>,
+) -> PerftDivide {
+    let move_generator = MoveGenerator::default();
+    let hash = perft_hash(hash_mb);
+    let fen = board.to_fen();
+    let mut board = board.clone();
+    let turn = board.turn();
+
+    let starting_time = SystemTime::now();
+    let mut moves: Vec<DivideCount> = move_generator
+        .perft_divide(depth, &mut board, turn, hash.as_ref())
+        .into_iter()
+        .map(|(chess_move, nodes)| DivideCount {
+            uci: chess_move.to_uci(),
+            nodes,
+        })
+        .collect();
+    let duration = SystemTime::now().duration_since(starting_time).unwrap();
+    moves.sort_by(|a, b| a.uci.cmp(&b.uci));
+
+    let nodes = moves.iter().map(|count| count.nodes).sum();
+    PerftDivide {
+        expected: expected.or_else(|| reference_perft(&fen, depth)),
+        fen,
+        depth,
+        moves,
+        nodes,
+        time_ms: duration.as_millis(),
+        nps: nodes_per_second(nodes, duration),
+    }
+}
+
+/// Print a perft divide of `board`. Returns whether the total matched the
+/// expected count, if there was one.
+pub fn run_perft_divide(
+    board: &Board,
+    depth: u8,
+    hash_mb: usize,
+    expected: Option<usize>,
+    json: bool,
+) -> bool {
+    let divide = perft_divide(board, depth, hash_mb, expected);
+    if json {
+        let mut value = serde_json::to_value(&divide).expect("divide should serialize");
+        value["passed"] = divide.passed().into();
+        println!("{}", value);
+        return divide.passed();
+    }
+
+    for count in &divide.moves {
+        println!("{}: {}", count.uci, count.nodes);
+    }
+    println!();
+    println!(
+        "Nodes searched: {} ({}ms, {} nodes per second)",
+        divide.nodes, divide.time_ms, divide.nps
+    );
+    match divide.expected {
+        Some(expected) if divide.passed() => println!("Matches the expected {}.", expected),
+        Some(expected) => println!("MISMATCH: expected {}.", expected),
+        None => {}
+    }
+    divide.passed()
+}
+
+/// Position count for a single depth.
+#[derive(Debug, Clone, Serialize)]
+pub struct DepthCount {
+    pub depth: u8,
+    pub positions: usize,
+    pub time_ms: u128,
+    pub nps: u64,
+    #[serde(skip)]
+    pub duration: Duration,
+}
+
+/// Position counts for every depth from 1 to the requested depth.
+#[derive(Debug, Clone, Serialize)]
+pub struct CountPositionsReport {
+    pub depths: Vec<DepthCount>,
+    pub total_positions: usize,
+    pub time_ms: u128,
+    pub nps: u64,
+    #[serde(skip)]
+    pub duration: Duration,
+}
+
+fn nodes_per_second(positions: usize, duration: Duration) -> u64 {
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        (positions as f64 / secs) as u64
+    } else {
+        0
+    }
+}
+
+/// Count positions at each depth from 1 to `depth`, calling `on_depth` as each
+/// depth completes.
+pub fn count_positions<F>(
+    depth: u8,
+    strategy: &CountPositionsStrategy,
+    mut on_depth: F,
+) -> CountPositionsReport
+where
+    F: FnMut(&DepthCount),
+{
+    let move_generator = MoveGenerator::default();
+
+    let mut depths = Vec::new();
     let mut total_duration = Duration::from_secs(0);
 
-    for depth in depths {
+    for depth in 1..=depth {
         let mut board = Board::default();
 
         let starting_time = SystemTime::now();
@@ -49,24 +327,111 @@ pub fn run_count_positions(depth: u8, strategy: CountPositionsStrategy) {
             }
         };
         let duration = SystemTime::now().duration_since(starting_time).unwrap();
-        let positions_per_second = count as f64 / duration.as_secs_f64();
-
-        total_positions += count;
         total_duration += duration;
 
+        let depth_count = DepthCount {
+            depth,
+            positions: count,
+            time_ms: duration.as_millis(),
+            nps: nodes_per_second(count, duration),
+            duration,
+        };
+        on_depth(&depth_count);
+        depths.push(depth_count);
+    }
+
+    let total_positions = depths.iter().map(|d| d.positions).sum();
+    CountPositionsReport {
+        depths,
+        total_positions,
+        time_ms: total_duration.as_millis(),
+        nps: nodes_per_second(total_positions, total_duration),
+        duration: total_duration,
+    }
+}
+
+pub fn run_count_positions(depth: u8, strategy: CountPositionsStrategy, json: bool) {
+    if json {
+        let report = count_positions(depth, &strategy, |_| {});
         println!(
-            "depth: {}, positions: {}, positions per second: {}",
-            depth, count, positions_per_second
+            "{}",
+            serde_json::to_string(&report).expect("report should serialize")
         );
+        return;
     }
 
+    MemoryProfiler::reset();
+
+    let report = count_positions(depth, &strategy, |depth_count| {
+        println!(
+            "depth: {}, positions: {}, positions per second: {}",
+            depth_count.depth,
+            depth_count.positions,
+            depth_count.positions as f64 / depth_count.duration.as_secs_f64()
+        );
+    });
+
     println!(
         "total positions: {}, total duration: {:?}, positions per second: {}",
-        total_positions,
-        total_duration,
-        total_positions as f64 / total_duration.as_secs_f64()
+        report.total_positions,
+        report.duration,
+        report.total_positions as f64 / report.duration.as_secs_f64()
     );
 
     println!();
     MemoryProfiler::print_stats();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_matches_reference_at_shallow_depths() {
+        let checks = verify_perft(2, 0, |_| {});
+        assert_eq!(checks.len(), PERFT_REFERENCE.len() * 2);
+        for check in &checks {
+            assert!(check.passed(), "perft mismatch: {:?}", check);
+        }
+    }
+
+    #[test]
+    fn test_perft_starting_position_depth_3() {
+        let move_generator = MoveGenerator::default();
+        let mut board = Board::default();
+        assert_eq!(move_generator.perft(3, &mut board, Color::White), 8_902);
+    }
+
+    #[test]
+    fn test_hashed_perft_counts_en_passant_out_of_check() {
+        // Depth 5 is the first to reach an en passant capture of a checking pawn
+        let (_, fen, counts) = PERFT_REFERENCE[2];
+        let mut board = Board::from_str(fen).unwrap();
+        let hash = PerftHash::new(1);
+        let move_generator = MoveGenerator::default();
+        assert_eq!(
+            move_generator.perft_hashed(5, &mut board, Color::White, &hash),
+            counts[4]
+        );
+    }
+
+    #[test]
+    fn test_hashed_divide_matches_reference() {
+        let board = Board::from_str(PERFT_REFERENCE[1].1).unwrap();
+        let divide = perft_divide(&board, 3, 1, None);
+        assert_eq!(divide.expected, Some(97_862));
+        assert_eq!(divide.nodes, 97_862);
+        assert!(divide.passed());
+        assert_eq!(divide.moves.len(), 48);
+        assert!(divide
+            .moves
+            .windows(2)
+            .all(|pair| pair[0].uci < pair[1].uci));
+
+        let board = Board::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let divide = perft_divide(&board, 2, 0, Some(26));
+        assert_eq!(divide.expected, Some(26));
+        assert_eq!(divide.nodes, 25);
+        assert!(!divide.passed());
+    }
+}
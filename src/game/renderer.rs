@@ -2,13 +2,26 @@ use std::cell::RefCell;
 use std::io;
 use std::time::Duration;
 
+use crossterm::event::MouseEvent;
+
 use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::Board;
 use crate::chess_move::chess_move::ChessMove;
 use crate::evaluate::GameEnding;
+use crate::game::clock::GameClock;
 use crate::game::display::GameDisplay;
-use crate::game::engine::Engine;
+use crate::game::engine::{Engine, EngineSettings};
+use crate::game::events::GameEvent;
+use crate::game::game_file::GameFileAction;
+use crate::game::move_list::format_move_list;
+use crate::input_handler::{MoveInput, Navigation};
 use crate::tui::TuiApp;
 
+/// Redraws a running clock between frames. Called from another thread while the
+/// game loop waits for a move.
+pub type ClockRefresh = Box<dyn Fn() + Send>;
+
 pub trait GameRenderer {
     fn render(
         &self,
@@ -19,6 +32,76 @@ pub trait GameRenderer {
         game_ending: Option<&GameEnding>,
     );
     fn frame_delay(&self) -> Option<Duration>;
+    /// Show a status line (e.g. playback state) in place of the default prompt.
+    fn set_status(&self, _status: Option<String>) {}
+    /// Report that the game ended off the board (e.g. on time), with the reason.
+    fn set_termination(&self, _termination: Option<String>) {}
+    /// Show the current game clocks, if the game is timed.
+    fn set_clock(&self, _clock: Option<GameClock>) {}
+    /// A callback that redraws `clock` as it runs, if the renderer can update
+    /// the clocks without a full frame.
+    fn clock_refresh(&self, _clock: GameClock) -> Option<ClockRefresh> {
+        None
+    }
+    /// Move through the moves played so far, if the renderer can show earlier
+    /// positions. Takes effect on the next `render`.
+    fn browse_moves(&self, _navigation: Navigation) {}
+    /// Go over the finished game from its start with a live analysis, if the
+    /// renderer can run one.
+    fn analyze_game(&self) {}
+    /// The number of moves played in the earlier position on display while
+    /// browsing, or None when the current position is shown.
+    fn viewed_ply(&self) -> Option<usize> {
+        None
+    }
+    /// Turn the board around, if the renderer can draw it from either side.
+    fn flip_board(&self) {}
+    /// Show or hide a live analysis of the position on display, if the
+    /// renderer can run one.
+    fn toggle_analysis(&self) {}
+    /// Leave the pieces off the board, for playing blindfold, if the renderer
+    /// draws one.
+    fn set_blindfold(&self, _blindfold: bool) {}
+    /// Show the pieces of a blindfold game until the next move, or hide them
+    /// again.
+    fn toggle_pieces(&self) {}
+    /// Open a menu of display settings, if the renderer has one, along with
+    /// the engine's current `engine` settings. It is drawn by the next
+    /// `render`.
+    fn open_settings(&self, _engine: EngineSettings) {}
+    /// Wait for the player to change a setting in the open menu. Returns false
+    /// once the menu is closed.
+    fn update_settings(&self) -> bool {
+        false
+    }
+    /// The engine settings picked in the last settings menu, if the renderer
+    /// lets the player change them.
+    fn engine_settings(&self) -> Option<EngineSettings> {
+        None
+    }
+    /// React to a mouse click on the screen showing `board`. Returns the move
+    /// once a piece and its destination have been picked.
+    fn handle_mouse(&self, _event: MouseEvent, _board: &Board) -> Option<MoveInput> {
+        None
+    }
+    /// Ask which piece a pawn of `color` promotes to. Returns None if the
+    /// player backs out of the move. Renderers that cannot ask promote to a
+    /// queen.
+    fn choose_promotion(&self, _color: Color) -> Option<Piece> {
+        Some(Piece::Queen)
+    }
+    /// Ask for a file to save the game to or load one from. Returns None if
+    /// the player backs out or the renderer cannot ask.
+    fn choose_game_file(&self, _action: GameFileAction) -> Option<String> {
+        None
+    }
+    /// React to a game event. By default errors are printed to stderr and
+    /// everything else is left to `render`.
+    fn on_event(&self, event: &GameEvent) {
+        if let GameEvent::Error { message } = event {
+            eprintln!("{}", message);
+        }
+    }
 }
 
 pub struct SimpleRenderer;
@@ -33,10 +116,12 @@ impl GameRenderer for SimpleRenderer {
         game_ending: Option<&GameEnding>,
     ) {
         let opening_name = engine.get_book_line_name();
+        let move_list = format_move_list(&engine.move_list());
         ui.render_game_state(
             engine.board(),
             current_turn,
             last_move,
+            &move_list,
             None,
             opening_name.as_deref(),
         );
@@ -45,6 +130,7 @@ impl GameRenderer for SimpleRenderer {
                 GameEnding::Checkmate => println!("Checkmate!"),
                 GameEnding::Stalemate => println!("Stalemate!"),
                 GameEnding::Draw => println!("Draw!"),
+                GameEnding::VariantWin => println!("{}", variant_win_message(engine)),
             }
         } else {
             println!("Enter your move:");
@@ -80,10 +166,12 @@ impl GameRenderer for StatsRenderer {
                 .map_or("-".to_string(), |d| format!("{:?}", d))
         );
         let opening_name = engine.get_book_line_name();
+        let move_list = format_move_list(&engine.move_list());
         ui.render_game_state(
             engine.board(),
             current_turn,
             last_move,
+            &move_list,
             Some(&stats_display),
             opening_name.as_deref(),
         );
@@ -92,6 +180,7 @@ impl GameRenderer for StatsRenderer {
                 GameEnding::Checkmate => println!("Checkmate!"),
                 GameEnding::Stalemate => println!("Stalemate!"),
                 GameEnding::Draw => println!("Draw!"),
+                GameEnding::VariantWin => println!("{}", variant_win_message(engine)),
             }
         }
     }
@@ -125,10 +214,12 @@ impl GameRenderer for ConditionalStatsRenderer {
                 .map_or("-".to_string(), |d| format!("{:?}", d))
         );
         let opening_name = engine.get_book_line_name();
+        let move_list = format_move_list(&engine.move_list());
         ui.render_game_state(
             engine.board(),
             current_turn,
             last_move,
+            &move_list,
             Some(&stats_display),
             opening_name.as_deref(),
         );
@@ -137,6 +228,7 @@ impl GameRenderer for ConditionalStatsRenderer {
                 GameEnding::Checkmate => println!("Checkmate!"),
                 GameEnding::Stalemate => println!("Stalemate!"),
                 GameEnding::Draw => println!("Draw!"),
+                GameEnding::VariantWin => println!("{}", variant_win_message(engine)),
             }
         } else if current_turn == self.human_color {
             println!("Enter your move:");
@@ -190,4 +282,118 @@ impl GameRenderer for TuiRenderer {
     fn frame_delay(&self) -> Option<Duration> {
         None
     }
+
+    fn set_status(&self, status: Option<String>) {
+        self.app.borrow_mut().set_status(status);
+    }
+
+    fn set_termination(&self, termination: Option<String>) {
+        self.app.borrow_mut().set_termination(termination);
+    }
+
+    fn set_clock(&self, clock: Option<GameClock>) {
+        self.app.borrow_mut().set_clock(clock);
+    }
+
+    fn browse_moves(&self, navigation: Navigation) {
+        self.app.borrow_mut().browse_moves(navigation);
+    }
+
+    fn viewed_ply(&self) -> Option<usize> {
+        self.app.borrow().viewed_ply()
+    }
+
+    fn analyze_game(&self) {
+        self.app.borrow_mut().analyze_game();
+    }
+
+    fn flip_board(&self) {
+        self.app.borrow_mut().flip_board();
+    }
+
+    fn toggle_analysis(&self) {
+        self.app.borrow_mut().toggle_analysis();
+    }
+
+    fn set_blindfold(&self, blindfold: bool) {
+        self.app.borrow_mut().set_blindfold(blindfold);
+    }
+
+    fn toggle_pieces(&self) {
+        self.app.borrow_mut().toggle_pieces();
+    }
+
+    fn open_settings(&self, engine: EngineSettings) {
+        self.app.borrow_mut().open_settings(engine);
+    }
+
+    fn update_settings(&self) -> bool {
+        self.app.borrow_mut().update_settings().unwrap_or(false)
+    }
+
+    fn engine_settings(&self) -> Option<EngineSettings> {
+        self.app.borrow().engine_settings()
+    }
+
+    fn handle_mouse(&self, event: MouseEvent, board: &Board) -> Option<MoveInput> {
+        let (from, to) = self.app.borrow_mut().handle_mouse(event, board)?;
+        Some(MoveInput::Coordinate {
+            from: from.to_algebraic().to_string(),
+            to: to.to_algebraic().to_string(),
+        })
+    }
+
+    fn choose_promotion(&self, color: Color) -> Option<Piece> {
+        self.app
+            .borrow_mut()
+            .choose_promotion(color)
+            .unwrap_or(Some(Piece::Queen))
+    }
+
+    fn choose_game_file(&self, action: GameFileAction) -> Option<String> {
+        self.app
+            .borrow_mut()
+            .choose_game_file(action)
+            .unwrap_or(None)
+    }
+
+    fn on_event(&self, event: &GameEvent) {
+        match event {
+            GameEvent::GameStarted => self.app.borrow_mut().set_game_over(None),
+            // Only the opponent's moves ring, as the player knows of their own
+            GameEvent::MovePlayed { mover, .. }
+                if self.human_color.is_some_and(|human| human != *mover) =>
+            {
+                self.app.borrow().ring_bell()
+            }
+            GameEvent::GameOver { result, reason } => {
+                let mut app = self.app.borrow_mut();
+                app.set_game_over(Some((*result, *reason)));
+                app.ring_bell();
+            }
+            GameEvent::Error { message } => eprintln!("{}", message),
+            _ => {}
+        }
+    }
+
+    fn clock_refresh(&self, clock: GameClock) -> Option<ClockRefresh> {
+        let ticker = self.app.borrow().clock_ticker(clock)?;
+        Some(Box::new(move || {
+            let _ = ticker.paint();
+        }))
+    }
+}
+
+/// The winner of a game won by its variant's rule, e.g. "White wins by
+/// reaching the hill!".
+fn variant_win_message(engine: &Engine) -> String {
+    let winner = match engine.board().turn() {
+        Color::White => "Black",
+        Color::Black => "White",
+    };
+    format!(
+        "{} wins by {}!",
+        winner,
+        engine.board().variant().win_description()
+    )
 }
@@ -0,0 +1,396 @@
+//! SPSA tuning of the search parameters by self-play.
+//!
+//! Simultaneous perturbation stochastic approximation tunes all parameters at
+//! once from noisy match results. Each iteration pushes every parameter up or
+//! down by its perturbation at random, plays pairs of games (one opening,
+//! colors reversed) between the engine with the parameters pushed one way and
+//! the engine with them pushed the other, and moves the parameters towards the
+//! side that scored better. Perturbations and steps shrink over the run with
+//! the usual SPSA gain sequences, so the values settle as it nears the end.
+//!
+//! The games are played with the tournament runner's game loop. State is
+//! saved as a small TOML file after every iteration so a run can be
+//! interrupted and resumed. Copy the final values into `SearchParams::default`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::alpha_beta_searcher::SearchParams;
+use crate::game::adjudication::AdjudicationRules;
+use crate::game::pgn::PgnResult;
+use crate::game::tournament::{
+    play_game, BuiltinPlayer, TimeControl, TournamentConfig, TournamentFormat,
+};
+use crate::input_handler::fen::STARTING_POSITION_FEN;
+
+/// Exponent of the step size decay.
+const ALPHA: f64 = 0.602;
+
+/// Exponent of the perturbation decay.
+const GAMMA: f64 = 0.101;
+
+/// A tuned search parameter: its name in the state file, its bounds, and how
+/// far it is perturbed at the end of a run.
+pub struct SpsaParam {
+    pub name: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub perturbation: f64,
+}
+
+/// The tuned parameters, in the order of `SpsaState::values`.
+pub const SPSA_PARAMS: &[SpsaParam] = &[
+    SpsaParam {
+        name: "aspiration_window",
+        min: 10.0,
+        max: 300.0,
+        perturbation: 10.0,
+    },
+    SpsaParam {
+        name: "lmr_base",
+        min: 0.0,
+        max: 2.0,
+        perturbation: 0.1,
+    },
+    SpsaParam {
+        name: "lmr_divisor",
+        min: 1.0,
+        max: 4.0,
+        perturbation: 0.2,
+    },
+    SpsaParam {
+        name: "futility_margin_scale",
+        min: 0.5,
+        max: 2.0,
+        perturbation: 0.1,
+    },
+];
+
+fn param_values(params: &SearchParams) -> Vec<f64> {
+    vec![
+        f64::from(params.aspiration_window),
+        params.lmr_base,
+        params.lmr_divisor,
+        params.futility_margin_scale,
+    ]
+}
+
+fn search_params(values: &[f64]) -> SearchParams {
+    SearchParams {
+        aspiration_window: values[0].round() as i16,
+        lmr_base: values[1],
+        lmr_divisor: values[2],
+        futility_margin_scale: values[3],
+    }
+}
+
+/// How the tuning games are played and how fast the parameters move.
+pub struct SpsaConfig {
+    /// Iterations in the whole run, which set the gain sequences.
+    pub iterations: usize,
+    /// Game pairs played per iteration.
+    pub pairs: usize,
+    /// Fixed search depth, or `None` to play on `time_control`.
+    pub depth: Option<u8>,
+    pub time_control: TimeControl,
+    /// Starting positions as FEN. Empty means the standard starting position.
+    pub openings: Vec<String>,
+    /// Adjudicate the game as a draw after this many full moves.
+    pub max_moves: usize,
+    /// Final step of each parameter per game point won, relative to the
+    /// square of its perturbation.
+    pub learning_rate: f64,
+}
+
+impl Default for SpsaConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 100,
+            pairs: 1,
+            depth: Some(4),
+            time_control: TimeControl::MoveTime(Duration::from_millis(100)),
+            openings: Vec::new(),
+            max_moves: 150,
+            learning_rate: 0.05,
+        }
+    }
+}
+
+/// Resumable tuner state: completed iterations and current parameter values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpsaState {
+    pub iteration: usize,
+    /// Values in `SPSA_PARAMS` order.
+    pub values: Vec<f64>,
+}
+
+impl SpsaState {
+    /// Start from the engine's default parameters.
+    pub fn initial() -> Self {
+        Self {
+            iteration: 0,
+            values: param_values(&SearchParams::default()),
+        }
+    }
+
+    /// The search parameters for the current values.
+    pub fn params(&self) -> SearchParams {
+        search_params(&self.values)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "# SPSA tuning state; copy values into SearchParams::default"
+        );
+        let _ = writeln!(out, "iteration = {}", self.iteration);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "[params]");
+        for (param, value) in SPSA_PARAMS.iter().zip(&self.values) {
+            let _ = writeln!(out, "{} = {:.4}", param.name, value);
+        }
+        out
+    }
+
+    /// Parse a state file written by [`SpsaState::to_toml`]. Unknown keys are
+    /// rejected; missing parameters keep their default value.
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        let mut state = Self::initial();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| format!("line {}: expected `key = value`", index + 1))?;
+            let invalid = || format!("line {}: invalid value for `{}`", index + 1, key);
+            if key == "iteration" {
+                state.iteration = value.parse().map_err(|_| invalid())?;
+                continue;
+            }
+            let param = SPSA_PARAMS
+                .iter()
+                .position(|param| param.name == key)
+                .ok_or_else(|| format!("line {}: unknown parameter `{}`", index + 1, key))?;
+            state.values[param] = value.parse().map_err(|_| invalid())?;
+        }
+        Ok(state)
+    }
+
+    /// Load `path` if it exists, otherwise start from the defaults.
+    pub fn load_or_initial(path: &str) -> Result<Self, String> {
+        if !Path::new(path).exists() {
+            return Ok(Self::initial());
+        }
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        Self::from_toml(&contents).map_err(|e| format!("invalid SPSA state in {}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_toml()).map_err(|e| format!("failed to write {}: {}", path, e))
+    }
+}
+
+/// Outcome of one iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpsaIteration {
+    pub plus: SearchParams,
+    pub minus: SearchParams,
+    /// Game points of `plus` minus those of `minus`.
+    pub net_score: f64,
+}
+
+/// Run one iteration: perturb the parameters, let `play_match` report the net
+/// score of the first parameter set against the second, and step towards the
+/// better one.
+pub fn spsa_iteration(
+    state: &mut SpsaState,
+    config: &SpsaConfig,
+    rng: &fastrand::Rng,
+    mut play_match: impl FnMut(SearchParams, SearchParams) -> Result<f64, String>,
+) -> Result<SpsaIteration, String> {
+    let k = (state.iteration + 1) as f64;
+    let n = config.iterations.max(state.iteration + 1) as f64;
+    // Stability constant of the step sequence, as usually chosen
+    let a_offset = n / 10.0;
+
+    let signs: Vec<f64> = SPSA_PARAMS
+        .iter()
+        .map(|_| if rng.bool() { 1.0 } else { -1.0 })
+        .collect();
+    // Perturbation and step per parameter this iteration, decaying to the
+    // parameter's perturbation and `learning_rate * perturbation²` at the end
+    let gains: Vec<(f64, f64)> = SPSA_PARAMS
+        .iter()
+        .map(|param| {
+            let c = param.perturbation * n.powf(GAMMA) / k.powf(GAMMA);
+            let a = config.learning_rate * param.perturbation.powi(2) * (a_offset + n).powf(ALPHA)
+                / (a_offset + k).powf(ALPHA);
+            (c, a)
+        })
+        .collect();
+
+    let perturbed = |direction: f64| -> Vec<f64> {
+        SPSA_PARAMS
+            .iter()
+            .zip(&state.values)
+            .zip(signs.iter().zip(&gains))
+            .map(|((param, value), (sign, (c, _)))| {
+                (value + direction * sign * c).clamp(param.min, param.max)
+            })
+            .collect()
+    };
+    let plus = search_params(&perturbed(1.0));
+    let minus = search_params(&perturbed(-1.0));
+    let net_score = play_match(plus, minus)?;
+
+    for (((value, param), sign), (c, a)) in state
+        .values
+        .iter_mut()
+        .zip(SPSA_PARAMS)
+        .zip(&signs)
+        .zip(&gains)
+    {
+        *value = (*value + a / c * net_score * sign).clamp(param.min, param.max);
+    }
+    state.iteration += 1;
+
+    Ok(SpsaIteration {
+        plus,
+        minus,
+        net_score,
+    })
+}
+
+/// Play `config.pairs` game pairs between the built-in engine with `plus`
+/// and with `minus`, each pair from a random opening with colors reversed,
+/// and return the points `plus` scored more than `minus`.
+pub fn play_self_play_match(
+    plus: SearchParams,
+    minus: SearchParams,
+    config: &SpsaConfig,
+    rng: &fastrand::Rng,
+) -> Result<f64, String> {
+    let game_config = TournamentConfig {
+        engines: Vec::new(),
+        format: TournamentFormat::RoundRobin,
+        time_control: config.time_control,
+        games_per_pairing: 2,
+        openings: Vec::new(),
+        max_moves: config.max_moves,
+        adjudication: AdjudicationRules::default(),
+        pgn_out: None,
+        pgn_evals: false,
+    };
+    let mut plus_player = BuiltinPlayer::new("plus".to_string(), config.depth).with_params(plus);
+    let mut minus_player = BuiltinPlayer::new("minus".to_string(), config.depth).with_params(minus);
+
+    let mut net_score = 0.0;
+    for _ in 0..config.pairs {
+        let opening = if config.openings.is_empty() {
+            STARTING_POSITION_FEN
+        } else {
+            &config.openings[rng.usize(..config.openings.len())]
+        };
+        let first = play_game(
+            &mut plus_player,
+            &mut minus_player,
+            "plus",
+            "minus",
+            opening,
+            &game_config,
+        )?;
+        let second = play_game(
+            &mut minus_player,
+            &mut plus_player,
+            "minus",
+            "plus",
+            opening,
+            &game_config,
+        )?;
+        net_score += white_net_score(first.result()) - white_net_score(second.result());
+    }
+    Ok(net_score)
+}
+
+/// White's points minus Black's.
+fn white_net_score(result: PgnResult) -> f64 {
+    match result {
+        PgnResult::WhiteWins => 1.0,
+        PgnResult::BlackWins => -1.0,
+        PgnResult::Draw | PgnResult::Unfinished => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trips_through_toml() {
+        let mut state = SpsaState::initial();
+        state.iteration = 3;
+        state.values = vec![62.0, 0.75, 2.25, 1.125];
+        let parsed = SpsaState::from_toml(&state.to_toml()).unwrap();
+        assert_eq!(parsed, state);
+        assert_eq!(parsed.params().aspiration_window, 62);
+        assert!(SpsaState::from_toml("null_move_r = 3").is_err());
+    }
+
+    #[test]
+    fn test_iterations_step_towards_the_winning_side() {
+        let config = SpsaConfig {
+            iterations: 10,
+            ..SpsaConfig::default()
+        };
+        let rng = fastrand::Rng::with_seed(7);
+        let mut state = SpsaState::initial();
+
+        // Wider aspiration windows always win
+        for _ in 0..10 {
+            let before = state.values[0];
+            let iteration = spsa_iteration(&mut state, &config, &rng, |plus, minus| {
+                Ok(if plus.aspiration_window > minus.aspiration_window {
+                    2.0
+                } else {
+                    -2.0
+                })
+            })
+            .unwrap();
+            assert_ne!(
+                iteration.plus.aspiration_window,
+                iteration.minus.aspiration_window
+            );
+            assert!(state.values[0] > before);
+        }
+        assert_eq!(state.iteration, 10);
+        for (param, value) in SPSA_PARAMS.iter().zip(&state.values) {
+            assert!((param.min..=param.max).contains(value));
+        }
+    }
+
+    #[test]
+    fn test_self_play_match_plays_game_pairs() {
+        let config = SpsaConfig {
+            pairs: 1,
+            depth: Some(1),
+            max_moves: 3,
+            ..SpsaConfig::default()
+        };
+        let rng = fastrand::Rng::with_seed(1);
+        let net_score = play_self_play_match(
+            SearchParams::default(),
+            SearchParams::default(),
+            &config,
+            &rng,
+        )
+        .unwrap();
+        assert!((-2.0..=2.0).contains(&net_score));
+    }
+}
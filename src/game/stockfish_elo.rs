@@ -1,7 +1,18 @@
+//! Estimate the engine's rating by playing games against a strength-limited UCI engine.
+//!
+//! The opponent is Stockfish by default, but any engine supporting the
+//! `UCI_LimitStrength` and `UCI_Elo` options works. Games are spread over a range of
+//! `UCI_Elo` levels with colors alternating, optionally on several worker threads
+//! (each with its own engine process). The rating is the maximum-likelihood Elo given every game's opponent
+//! level and result, reported with a 95% confidence interval.
+
+use std::f64::consts::LN_10;
 use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 use std::time::{Duration, Instant};
 
-use common::bitboard::*;
 use crossterm::{cursor, execute, terminal};
 use ratatui::{
     backend::CrosstermBackend,
@@ -11,315 +22,622 @@ use ratatui::{
 };
 
 use crate::board::color::Color;
-use crate::board::piece::Piece;
 use crate::board::Board;
 use crate::evaluate::GameEnding;
-use crate::game::engine::{Engine, EngineConfig};
-use crate::game::stockfish_interface::Stockfish;
+use crate::game::engine::{Engine, EngineConfig, SearchStats};
+use crate::game::pgn::{PgnGame, PgnResult};
+use crate::game::tournament::TimeControl;
+use crate::game::uci_engine::{GoLimits, UciEngine, UciEngineError, UciEngineHandle};
 use crate::tui::{board_widget::BoardWidget, Theme};
+use crate::uci::time_management::allocate_time;
+
+/// Two-sided z-score for a 95% confidence interval.
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Settings for an Elo estimation run.
+#[derive(Debug, Clone)]
+pub struct EloRunConfig {
+    /// The strength-limited opponent.
+    pub opponent: UciEngineHandle,
+    pub depth: u8,
+    /// Total number of games, spread evenly over the Elo levels.
+    pub games: usize,
+    pub time_control: TimeControl,
+    /// Opponent `UCI_Elo` levels to play against: `min_elo`, `min_elo + elo_step`,
+    /// ... up to `max_elo`. Levels outside the opponent's supported range are clamped.
+    pub min_elo: u32,
+    pub max_elo: u32,
+    pub elo_step: u32,
+    /// Number of games played in parallel.
+    pub concurrency: usize,
+}
+
+impl EloRunConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.games == 0 {
+            return Err("at least one game is required".to_string());
+        }
+        if self.concurrency == 0 {
+            return Err("concurrency must be at least 1".to_string());
+        }
+        if self.min_elo > self.max_elo {
+            return Err(format!(
+                "minimum Elo {} is above maximum Elo {}",
+                self.min_elo, self.max_elo
+            ));
+        }
+        if self.elo_step == 0 && self.min_elo != self.max_elo {
+            return Err("Elo step must be positive".to_string());
+        }
+        Ok(())
+    }
+}
 
-const GAMES_PER_ELO: usize = 10;
-const ELO_INCREMENT: u32 = 25;
+/// The opponent levels to play against, in ascending order.
+fn elo_levels(min_elo: u32, max_elo: u32, elo_step: u32) -> Vec<u32> {
+    if elo_step == 0 {
+        return vec![min_elo];
+    }
+    (min_elo..=max_elo).step_by(elo_step as usize).collect()
+}
 
-pub fn determine_stockfish_elo(depth: u8, starting_elo: u32, no_tui: bool, time_limit_ms: u64) {
-    let mut stockfish = match Stockfish::new() {
-        Ok(sf) => sf,
-        Err(_) => {
-            eprintln!("Error: Stockfish not found. Please ensure it's installed and in your PATH.");
-            return;
+/// One scheduled game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EloJob {
+    index: usize,
+    opponent_elo: u32,
+    engine_color: Color,
+}
+
+/// Cycle through the levels, switching the engine's color after each full cycle
+/// so every level is played with both colors.
+fn schedule_jobs(levels: &[u32], games: usize) -> Vec<EloJob> {
+    (0..games)
+        .map(|index| EloJob {
+            index,
+            opponent_elo: levels[index % levels.len()],
+            engine_color: if (index / levels.len()).is_multiple_of(2) {
+                Color::White
+            } else {
+                Color::Black
+            },
+        })
+        .collect()
+}
+
+/// Maximum-likelihood rating with a 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    /// Half-width of the 95% confidence interval.
+    pub margin: f64,
+}
+
+/// Expected score against an opponent rated `opponent_elo` for a player rated `elo`.
+fn expected_score(elo: f64, opponent_elo: u32) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_elo as f64 - elo) / 400.0))
+}
+
+/// Estimate a rating from `(opponent_elo, score)` pairs, where score is 1 for a
+/// win, 0.5 for a draw, and 0 for a loss. Returns `None` when there are no games
+/// or every game was won (or every game lost), since the estimate is then unbounded.
+pub fn estimate_elo(games: &[(u32, f64)]) -> Option<EloEstimate> {
+    let total: f64 = games.iter().map(|(_, score)| score).sum();
+    if games.is_empty() || total <= 0.0 || total >= games.len() as f64 {
+        return None;
+    }
+
+    // The expected total score increases monotonically with the rating, so bisect
+    // for the rating at which it matches the actual total.
+    let (mut low, mut high) = (-2000.0, 6000.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        let expected: f64 = games.iter().map(|&(opp, _)| expected_score(mid, opp)).sum();
+        if expected < total {
+            low = mid;
+        } else {
+            high = mid;
         }
-    };
+    }
+    let elo = (low + high) / 2.0;
+
+    // Fisher information of the logistic model gives the standard error.
+    let slope = LN_10 / 400.0;
+    let information: f64 = games
+        .iter()
+        .map(|&(opp, _)| {
+            let p = expected_score(elo, opp);
+            slope * slope * p * (1.0 - p)
+        })
+        .sum();
+    Some(EloEstimate {
+        elo,
+        margin: CONFIDENCE_Z / information.sqrt(),
+    })
+}
+
+/// Result of a game from the engine's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl GameResult {
+    fn score(self) -> f64 {
+        match self {
+            GameResult::Win => 1.0,
+            GameResult::Loss => 0.0,
+            GameResult::Draw => 0.5,
+        }
+    }
+}
+
+/// A finished game against the opponent.
+#[derive(Debug, Clone)]
+pub struct EloGame {
+    /// 1-based game number in scheduling order.
+    pub round: usize,
+    /// The `UCI_Elo` the opponent actually played at.
+    pub opponent_elo: u32,
+    pub engine_color: Color,
+    pub result: GameResult,
+    pub pgn: PgnGame,
+    pub engine_time: Duration,
+    pub opponent_time: Duration,
+}
+
+/// Running totals across finished games.
+#[derive(Debug, Clone, Default)]
+pub struct EloTally {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    pub engine_total_time: Duration,
+    pub opponent_total_time: Duration,
+}
+
+impl EloTally {
+    fn record(&mut self, game: &EloGame) {
+        match game.result {
+            GameResult::Win => self.wins += 1,
+            GameResult::Loss => self.losses += 1,
+            GameResult::Draw => self.draws += 1,
+        }
+        self.engine_total_time += game.engine_time;
+        self.opponent_total_time += game.opponent_time;
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Score percentage (wins count 1, draws count 0.5).
+    pub fn score_rate(&self) -> f32 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f32 + self.draws as f32 * 0.5) / self.games() as f32 * 100.0
+    }
+}
+
+/// Outcome of a full estimation run.
+#[derive(Debug, Clone)]
+pub struct EloReport {
+    pub games: Vec<EloGame>,
+    pub tally: EloTally,
+    /// `None` if the engine won or lost every game.
+    pub estimate: Option<EloEstimate>,
+}
+
+impl EloReport {
+    fn new(mut games: Vec<EloGame>) -> Self {
+        games.sort_by_key(|game| game.round);
+        let mut tally = EloTally::default();
+        for game in &games {
+            tally.record(game);
+        }
+        let estimate = estimate_elo(&game_scores(&games));
+        Self {
+            games,
+            tally,
+            estimate,
+        }
+    }
+
+    /// Games and score percentage per opponent level, in ascending order.
+    pub fn level_summary(&self) -> Vec<(u32, usize, f32)> {
+        let mut levels: Vec<u32> = self.games.iter().map(|g| g.opponent_elo).collect();
+        levels.sort_unstable();
+        levels.dedup();
+        levels
+            .into_iter()
+            .map(|elo| {
+                let level_games: Vec<&EloGame> = self
+                    .games
+                    .iter()
+                    .filter(|g| g.opponent_elo == elo)
+                    .collect();
+                let score: f64 = level_games.iter().map(|g| g.result.score()).sum();
+                let rate = score / level_games.len() as f64 * 100.0;
+                (elo, level_games.len(), rate as f32)
+            })
+            .collect()
+    }
+}
+
+fn game_scores(games: &[EloGame]) -> Vec<(u32, f64)> {
+    games
+        .iter()
+        .map(|game| (game.opponent_elo, game.result.score()))
+        .collect()
+}
+
+/// Live view of a game in progress, sent from worker threads to the renderer.
+struct GameSnapshot {
+    board: Board,
+    engine_color: Color,
+    opponent_elo: u32,
+    engine_time: Duration,
+    opponent_time: Duration,
+    search_stats: SearchStats,
+}
+
+enum WorkerEvent {
+    Position(Box<GameSnapshot>),
+    Finished(Box<EloGame>),
+    Failed(String),
+}
+
+/// A spawned opponent and the `UCI_Elo` range it supports.
+struct Opponent {
+    engine: UciEngine,
+    min_elo: u32,
+    max_elo: u32,
+}
+
+impl Opponent {
+    fn spawn(handle: &UciEngineHandle) -> Result<Self, UciEngineError> {
+        let engine = handle.spawn()?;
+        let (min_elo, max_elo) = engine.spin_option_range("UCI_Elo").ok_or_else(|| {
+            UciEngineError::UnsupportedOption {
+                name: "UCI_Elo".to_string(),
+            }
+        })?;
+        Ok(Self {
+            engine,
+            min_elo: min_elo.max(0) as u32,
+            max_elo: max_elo.max(0) as u32,
+        })
+    }
+
+    /// Limit the opponent to `elo`, clamped to its supported range. Returns the
+    /// Elo actually applied.
+    fn set_elo(&mut self, elo: u32) -> Result<u32, UciEngineError> {
+        self.engine.limit_strength(elo)
+    }
+}
+
+/// Play the configured games against the opponent and estimate the engine's rating.
+/// Progress is shown in a TUI unless `no_tui` is set.
+pub fn determine_stockfish_elo(config: &EloRunConfig, no_tui: bool) -> Result<EloReport, String> {
+    config.validate()?;
+
+    let mut opponents = (0..config.concurrency.min(config.games))
+        .map(|_| Opponent::spawn(&config.opponent))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{} (engine: {})", e, config.opponent))?;
+    let (opp_min, opp_max) = (opponents[0].min_elo, opponents[0].max_elo);
+    if config.min_elo < opp_min || config.max_elo > opp_max {
+        eprintln!(
+            "Note: {} supports UCI_Elo {}-{}; levels outside it are clamped.",
+            opponents[0].engine.name(),
+            opp_min,
+            opp_max
+        );
+    }
 
     let mut renderer: Box<dyn EloRenderer> = if no_tui {
         Box::new(HeadlessRenderer)
     } else {
-        match EloTui::new() {
-            Ok(tui) => Box::new(tui),
-            Err(e) => {
-                eprintln!("Failed to initialize TUI: {}", e);
-                return;
-            }
-        }
+        Box::new(EloTui::new().map_err(|e| format!("Failed to initialize TUI: {}", e))?)
     };
 
-    let min_elo = stockfish.min_elo();
-    let mut current_elo = starting_elo.max(min_elo);
-    let mut engine_total_time = Duration::new(0, 0);
-    let mut stockfish_total_time = Duration::new(0, 0);
-
-    loop {
-        stockfish.set_elo(current_elo).unwrap();
-
-        let mut level_wins = 0;
-        let mut level_losses = 0;
-        let mut level_draws = 0;
-        let mut level_games = 0;
-
-        for _ in 0..GAMES_PER_ELO {
-            let stats = EloStats {
-                current_elo,
-                wins: level_wins,
-                losses: level_losses,
-                draws: level_draws,
-                total_games: level_games,
-                engine_total_time,
-                stockfish_total_time,
-            };
-
-            let (result, engine_time, sf_time) = play_game(
-                &mut stockfish,
-                depth,
-                time_limit_ms,
-                renderer.as_mut(),
-                &stats,
-            );
-            level_games += 1;
-            engine_total_time += engine_time;
-            stockfish_total_time += sf_time;
-
-            match result {
-                GameResult::Win => level_wins += 1,
-                GameResult::Loss => level_losses += 1,
-                GameResult::Draw => level_draws += 1,
-            }
+    let levels = elo_levels(config.min_elo, config.max_elo, config.elo_step);
+    let jobs = schedule_jobs(&levels, config.games);
+    let next_job = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    let mut finished = Vec::with_capacity(jobs.len());
+    let mut failure = None;
+    thread::scope(|scope| {
+        for opponent in opponents.iter_mut() {
+            let sender = sender.clone();
+            let (jobs, next_job) = (&jobs, &next_job);
+            scope.spawn(move || {
+                while let Some(job) = jobs.get(next_job.fetch_add(1, Ordering::Relaxed)) {
+                    let event = match play_game(opponent, job, config, &sender) {
+                        Ok(game) => WorkerEvent::Finished(Box::new(game)),
+                        Err(e) => WorkerEvent::Failed(e),
+                    };
+                    let failed = matches!(event, WorkerEvent::Failed(_));
+                    if sender.send(event).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
 
-            if is_elo_determined(level_wins, level_draws, level_games) {
-                let final_stats = EloStats {
-                    current_elo,
-                    wins: level_wins,
-                    losses: level_losses,
-                    draws: level_draws,
-                    total_games: level_games,
-                    engine_total_time,
-                    stockfish_total_time,
-                };
-                renderer.render_final(&final_stats).ok();
-                return;
+        let mut tally = EloTally::default();
+        for event in receiver {
+            match event {
+                WorkerEvent::Position(snapshot) => {
+                    renderer.render(&snapshot, &tally, jobs.len()).ok();
+                }
+                WorkerEvent::Finished(game) => {
+                    tally.record(&game);
+                    finished.push(*game);
+                    renderer
+                        .game_finished(finished.last().expect("game was just pushed"), &tally)
+                        .ok();
+                }
+                WorkerEvent::Failed(e) => {
+                    // Stop handing out new games; games in progress still finish.
+                    next_job.store(jobs.len(), Ordering::Relaxed);
+                    failure.get_or_insert(e);
+                }
             }
         }
+    });
 
-        // Use chess score rate: wins count 1, draws count 0.5
-        let score = level_wins as f32 + level_draws as f32 * 0.5;
-        if score > level_games as f32 * 0.5 {
-            current_elo += ELO_INCREMENT;
-        } else if current_elo <= min_elo {
-            // Can't go below Stockfish's minimum -- report this as the floor
-            let final_stats = EloStats {
-                current_elo: min_elo,
-                wins: level_wins,
-                losses: level_losses,
-                draws: level_draws,
-                total_games: level_games,
-                engine_total_time,
-                stockfish_total_time,
-            };
-            renderer.render_final(&final_stats).ok();
-            return;
-        } else {
-            current_elo = current_elo.saturating_sub(ELO_INCREMENT).max(min_elo);
-        }
+    if let Some(e) = failure {
+        return Err(e);
+    }
+    let report = EloReport::new(finished);
+    renderer.render_final(&report).ok();
+    Ok(report)
+}
+
+fn go_limits(time_control: TimeControl, white_clock: Duration, black_clock: Duration) -> GoLimits {
+    match time_control {
+        TimeControl::MoveTime(time) => GoLimits::MoveTime(time.as_millis() as u64),
+        TimeControl::Clock { increment, .. } => GoLimits::Clock {
+            wtime: white_clock.as_millis() as u64,
+            btime: black_clock.as_millis() as u64,
+            winc: increment.as_millis() as u64,
+            binc: increment.as_millis() as u64,
+        },
     }
 }
 
 fn play_game(
-    stockfish: &mut Stockfish,
-    depth: u8,
-    time_limit_ms: u64,
-    renderer: &mut dyn EloRenderer,
-    stats: &EloStats,
-) -> (GameResult, Duration, Duration) {
+    opponent: &mut Opponent,
+    job: &EloJob,
+    config: &EloRunConfig,
+    sender: &mpsc::Sender<WorkerEvent>,
+) -> Result<EloGame, String> {
+    let opponent_label = opponent.engine.name().to_string();
+    let opponent_error = |e: UciEngineError| format!("{}: {}", opponent_label, e);
+    let opponent_elo = opponent.set_elo(job.opponent_elo).map_err(opponent_error)?;
+    opponent.engine.new_game().map_err(opponent_error)?;
+
     let mut engine = Engine::with_config(EngineConfig {
-        search_depth: depth,
+        search_depth: config.depth,
         starting_position: Board::default(),
     });
-    let mut moves = Vec::new();
-    let mut engine_time = Duration::new(0, 0);
-    let mut stockfish_time = Duration::new(0, 0);
-
-    let engine_color = Color::random();
-
-    loop {
-        if let Some(result) = engine.check_game_over() {
-            return (
-                match result {
-                    GameEnding::Checkmate => {
-                        if engine.board().turn() == engine_color {
-                            GameResult::Loss
-                        } else {
-                            GameResult::Win
-                        }
-                    }
-                    _ => GameResult::Draw,
-                },
-                engine_time,
-                stockfish_time,
-            );
-        }
-
-        let start_time = Instant::now();
-        let current_turn = engine.board().turn();
+    let engine_name = format!("chess (depth {})", config.depth);
+    let opponent_name = format!("{} (Elo {})", opponent_label, opponent_elo);
+    let mut pgn = PgnGame::new(engine.board());
+    pgn.set_header("Event", "Elo estimation");
+    pgn.set_header("Round", &(job.index + 1).to_string());
+    pgn.set_header("TimeControl", &config.time_control.to_string());
+    let (white_name, black_name) = match job.engine_color {
+        Color::White => (&engine_name, &opponent_name),
+        Color::Black => (&opponent_name, &engine_name),
+    };
+    pgn.set_header("White", white_name);
+    pgn.set_header("Black", black_name);
 
-        if current_turn == engine_color {
-            match engine.make_best_move_with_time_limit(Duration::from_millis(time_limit_ms)) {
-                Ok(chess_move) => {
-                    engine_time += start_time.elapsed();
-                    moves.push(chess_move.to_uci());
+    let (mut white_clock, mut black_clock) = match config.time_control {
+        TimeControl::Clock { base, .. } => (base, base),
+        TimeControl::MoveTime(_) => (Duration::ZERO, Duration::ZERO),
+    };
+    let mut moves: Vec<String> = Vec::new();
+    let mut engine_time = Duration::ZERO;
+    let mut opponent_time = Duration::ZERO;
+
+    let (result, termination) = loop {
+        let turn = engine.board().turn();
+        if let Some(ending) = engine.check_game_over() {
+            break match ending {
+                GameEnding::Checkmate if turn == job.engine_color => {
+                    (GameResult::Loss, "checkmate")
                 }
-                Err(_) => return (GameResult::Draw, engine_time, stockfish_time),
-            }
-        } else {
-            let (sf_move, sf_time) = match stockfish.get_best_move(&moves.join(" "), time_limit_ms)
-            {
-                Ok(result) => result,
-                Err(_) => return (GameResult::Draw, engine_time, stockfish_time),
+                GameEnding::Checkmate => (GameResult::Win, "checkmate"),
+                GameEnding::Stalemate => (GameResult::Draw, "stalemate"),
+                GameEnding::Draw => (GameResult::Draw, "draw by rule"),
+                GameEnding::VariantWin if turn == job.engine_color => {
+                    (GameResult::Loss, "variant win")
+                }
+                GameEnding::VariantWin => (GameResult::Win, "variant win"),
             };
-            stockfish_time += Duration::from_millis(sf_time);
+        }
 
-            let from = match Square::from_algebraic(&sf_move[0..2]) {
-                Some(sq) => sq,
-                None => return (GameResult::Draw, engine_time, stockfish_time),
-            };
-            let to = match Square::from_algebraic(&sf_move[2..4]) {
-                Some(sq) => sq,
-                None => return (GameResult::Draw, engine_time, stockfish_time),
+        let limits = go_limits(config.time_control, white_clock, black_clock);
+        let start_time = Instant::now();
+        let uci_move = if turn == job.engine_color {
+            let time_limit = match limits {
+                GoLimits::MoveTime(ms) => ms,
+                GoLimits::Clock {
+                    wtime,
+                    btime,
+                    winc,
+                    binc,
+                } => match turn {
+                    Color::White => allocate_time(wtime, winc),
+                    Color::Black => allocate_time(btime, binc),
+                },
             };
-            let promotion = sf_move.chars().nth(4).map(|c| match c {
-                'q' => Piece::Queen,
-                'r' => Piece::Rook,
-                'b' => Piece::Bishop,
-                'n' => Piece::Knight,
-                _ => Piece::Queen,
-            });
+            let chess_move = engine
+                .get_best_move_with_time_limit(Duration::from_millis(time_limit))
+                .map_err(|e| format!("Engine failed to find a move: {}", e))?;
+            engine_time += start_time.elapsed();
+            chess_move.to_uci()
+        } else {
+            let best_move = opponent
+                .engine
+                .best_move(None, &moves, limits)
+                .map_err(opponent_error)?;
+            opponent_time += start_time.elapsed();
+            best_move.uci_move
+        };
 
-            match engine.make_move_by_squares_with_promotion(from, to, promotion) {
-                Ok(chess_move) => moves.push(chess_move.to_uci()),
-                Err(e) => {
-                    let valid_moves = engine.get_valid_moves();
-                    let valid_uci: Vec<String> =
-                        valid_moves.iter().map(|(m, _)| m.to_uci()).collect();
-                    eprintln!(
-                        "Board desync with Stockfish: {}\n  \
-                         FEN: {}\n  \
-                         SF move: {}\n  \
-                         Turn: {:?}\n  \
-                         Move list: {}\n  \
-                         Valid moves: {:?}",
-                        e,
-                        engine.board().to_fen(),
-                        sf_move,
-                        engine.board().turn(),
-                        moves.join(" "),
-                        valid_uci
-                    );
-                    return (GameResult::Draw, engine_time, stockfish_time);
-                }
+        if let TimeControl::Clock { increment, .. } = config.time_control {
+            let clock = match turn {
+                Color::White => &mut white_clock,
+                Color::Black => &mut black_clock,
+            };
+            let elapsed = start_time.elapsed();
+            if elapsed > *clock {
+                let result = if turn == job.engine_color {
+                    GameResult::Loss
+                } else {
+                    GameResult::Win
+                };
+                break (result, "time forfeit");
             }
+            *clock = *clock - elapsed + increment;
         }
 
-        // Render the current game state
-        let game_state = GameState {
-            engine_color,
+        if engine.make_move_uci(&uci_move).is_err() {
+            let valid_uci: Vec<String> = engine
+                .get_valid_moves()
+                .iter()
+                .map(|(m, _)| m.to_uci())
+                .collect();
+            return Err(format!(
+                "Board desync with opponent\n  \
+                 FEN: {}\n  \
+                 Move: {}\n  \
+                 Move list: {}\n  \
+                 Valid moves: {:?}",
+                engine.board().to_fen(),
+                uci_move,
+                moves.join(" "),
+                valid_uci
+            ));
+        }
+        let san = engine
+            .move_history()
+            .last()
+            .map(|entry| entry.notation.clone())
+            .unwrap_or_else(|| uci_move.clone());
+        pgn.push_move(&san, None);
+        moves.push(uci_move);
+
+        let snapshot = GameSnapshot {
+            board: engine.board().clone(),
+            engine_color: job.engine_color,
+            opponent_elo,
             engine_time,
-            stockfish_time,
+            opponent_time,
+            search_stats: engine.get_search_stats(),
         };
-        renderer.render(&engine, &game_state, stats).ok();
+        // The receiver only disappears if the run is shutting down.
+        let _ = sender.send(WorkerEvent::Position(Box::new(snapshot)));
 
         engine.board_mut().toggle_turn();
         engine.record_position_hash();
-    }
-}
-
-/// Check if ELO is determined using chess score rate (wins=1, draws=0.5).
-fn is_elo_determined(wins: usize, draws: usize, total_games: usize) -> bool {
-    if total_games < GAMES_PER_ELO {
-        return false;
-    }
-    let score_rate = (wins as f32 + draws as f32 * 0.5) / total_games as f32;
-    (score_rate - 0.5).abs() < 0.1
-}
-
-enum GameResult {
-    Win,
-    Loss,
-    Draw,
-}
+    };
 
-/// Statistics for ELO determination progress
-struct EloStats {
-    current_elo: u32,
-    wins: usize,
-    losses: usize,
-    draws: usize,
-    total_games: usize,
-    engine_total_time: Duration,
-    stockfish_total_time: Duration,
+    pgn.set_result(match result {
+        GameResult::Win => PgnResult::win_for(job.engine_color),
+        GameResult::Loss => PgnResult::win_for(job.engine_color.opposite()),
+        GameResult::Draw => PgnResult::Draw,
+    });
+    pgn.set_header("Termination", termination);
+    Ok(EloGame {
+        round: job.index + 1,
+        opponent_elo,
+        engine_color: job.engine_color,
+        result,
+        pgn,
+        engine_time,
+        opponent_time,
+    })
 }
 
-/// Game state for rendering
-struct GameState {
-    engine_color: Color,
-    engine_time: Duration,
-    stockfish_time: Duration,
+fn format_estimate(estimate: Option<&EloEstimate>) -> String {
+    match estimate {
+        Some(estimate) => format!("{:.0} ± {:.0} (95% CI)", estimate.elo, estimate.margin),
+        None => "-".to_string(),
+    }
 }
 
 /// Rendering abstraction for ELO determination progress
 trait EloRenderer {
     fn render(
         &mut self,
-        engine: &Engine,
-        game_state: &GameState,
-        elo_stats: &EloStats,
+        snapshot: &GameSnapshot,
+        tally: &EloTally,
+        total_games: usize,
     ) -> io::Result<()>;
 
-    fn render_final(&mut self, elo_stats: &EloStats) -> io::Result<()>;
+    fn game_finished(&mut self, game: &EloGame, tally: &EloTally) -> io::Result<()>;
+
+    fn render_final(&mut self, report: &EloReport) -> io::Result<()>;
 }
 
 /// Headless renderer that prints progress to stdout
 struct HeadlessRenderer;
 
 impl EloRenderer for HeadlessRenderer {
-    fn render(
-        &mut self,
-        engine: &Engine,
-        game_state: &GameState,
-        elo_stats: &EloStats,
-    ) -> io::Result<()> {
-        // Print a summary line after each game completes (when move count resets).
-        // We detect "new game" by checking if the board is near starting position.
-        let move_count = engine.move_history().len();
-        if move_count <= 1 && elo_stats.total_games > 0 {
-            let score_rate = (elo_stats.wins as f32 + elo_stats.draws as f32 * 0.5)
-                / elo_stats.total_games as f32
-                * 100.0;
-            println!(
-                "  ELO {} | Game {} | W/L/D: {}/{}/{} | Score: {:.1}% | \
-                 Engine: {:.1}s | SF: {:.1}s",
-                elo_stats.current_elo,
-                elo_stats.total_games,
-                elo_stats.wins,
-                elo_stats.losses,
-                elo_stats.draws,
-                score_rate,
-                game_state.engine_time.as_secs_f64(),
-                game_state.stockfish_time.as_secs_f64(),
-            );
-        }
+    fn render(&mut self, _: &GameSnapshot, _: &EloTally, _: usize) -> io::Result<()> {
         Ok(())
     }
 
-    fn render_final(&mut self, elo_stats: &EloStats) -> io::Result<()> {
-        let score_rate = if elo_stats.total_games > 0 {
-            (elo_stats.wins as f32 + elo_stats.draws as f32 * 0.5) / elo_stats.total_games as f32
-                * 100.0
-        } else {
-            0.0
-        };
+    fn game_finished(&mut self, game: &EloGame, tally: &EloTally) -> io::Result<()> {
+        println!(
+            "  Game {} | Opponent ELO {} | Engine: {} | {:?} ({}) | W/L/D: {}/{}/{} | \
+             Score: {:.1}% | Engine: {:.1}s | Opponent: {:.1}s",
+            tally.games(),
+            game.opponent_elo,
+            game.engine_color,
+            game.result,
+            game.pgn.header("Termination").unwrap_or("-"),
+            tally.wins,
+            tally.losses,
+            tally.draws,
+            tally.score_rate(),
+            game.engine_time.as_secs_f64(),
+            game.opponent_time.as_secs_f64(),
+        );
+        Ok(())
+    }
+
+    fn render_final(&mut self, report: &EloReport) -> io::Result<()> {
+        let tally = &report.tally;
         println!("\nELO DETERMINATION COMPLETE");
-        println!("Final ELO: {}", elo_stats.current_elo);
+        println!(
+            "Estimated ELO: {}",
+            format_estimate(report.estimate.as_ref())
+        );
         println!(
             "Games: {} | W/L/D: {}/{}/{} | Score: {:.1}%",
-            elo_stats.total_games, elo_stats.wins, elo_stats.losses, elo_stats.draws, score_rate
+            tally.games(),
+            tally.wins,
+            tally.losses,
+            tally.draws,
+            tally.score_rate()
         );
+        for (elo, games, rate) in report.level_summary() {
+            println!(
+                "  Opponent ELO {:>4}: {:>3} games, score {:.1}%",
+                elo, games, rate
+            );
+        }
         Ok(())
     }
 }
@@ -346,27 +664,27 @@ impl EloTui {
     fn render_game_info(
         frame: &mut ratatui::Frame,
         area: Rect,
-        engine: &Engine,
-        game_state: &GameState,
+        snapshot: &GameSnapshot,
         theme: &Theme,
     ) {
         let mut info_text = String::new();
 
         // FEN
-        info_text.push_str(&format!("FEN: {}\n\n", engine.board().to_fen()));
+        info_text.push_str(&format!("FEN: {}\n\n", snapshot.board.to_fen()));
 
         // Turn
-        info_text.push_str(&format!("Turn: {}\n\n", engine.board().turn()));
+        info_text.push_str(&format!("Turn: {}\n\n", snapshot.board.turn()));
 
         // Colors
-        info_text.push_str(&format!("Engine: {}\n", game_state.engine_color));
+        info_text.push_str(&format!("Engine: {}\n", snapshot.engine_color));
         info_text.push_str(&format!(
-            "Stockfish: {}\n\n",
-            game_state.engine_color.opposite()
+            "Opponent ({}): {}\n\n",
+            snapshot.opponent_elo,
+            snapshot.engine_color.opposite()
         ));
 
         // Engine stats
-        let stats = engine.get_search_stats();
+        let stats = &snapshot.search_stats;
         info_text.push_str("Engine Stats:\n");
         info_text.push_str(&format!("  Depth: {}\n", stats.depth));
 
@@ -401,45 +719,40 @@ impl EloTui {
     fn render_elo_stats(
         frame: &mut ratatui::Frame,
         area: Rect,
-        game_state: &GameState,
-        elo_stats: &EloStats,
+        snapshot: &GameSnapshot,
+        tally: &EloTally,
+        total_games: usize,
         theme: &Theme,
     ) {
-        let score_rate = if elo_stats.total_games > 0 {
-            (elo_stats.wins as f32 + elo_stats.draws as f32 * 0.5) / elo_stats.total_games as f32
-                * 100.0
+        let games = tally.games();
+        let avg_engine_time = if games > 0 {
+            tally.engine_total_time.as_millis() as f32 / games as f32
         } else {
             0.0
         };
 
-        let avg_engine_time = if elo_stats.total_games > 0 {
-            elo_stats.engine_total_time.as_millis() as f32 / elo_stats.total_games as f32
-        } else {
-            0.0
-        };
-
-        let avg_stockfish_time = if elo_stats.total_games > 0 {
-            elo_stats.stockfish_total_time.as_millis() as f32 / elo_stats.total_games as f32
+        let avg_opponent_time = if games > 0 {
+            tally.opponent_total_time.as_millis() as f32 / games as f32
         } else {
             0.0
         };
 
         let stats_text = format!(
-            "Target ELO: {}  │  Games: {}  │  W/L/D: {}/{}/{}  │  Score: {:.1}%\n\
-             Engine avg: {:.0}ms  │  Stockfish avg: {:.0}ms\n\
+            "Games: {}/{}  │  W/L/D: {}/{}/{}  │  Score: {:.1}%\n\
+             Engine avg: {:.0}ms  │  Opponent avg: {:.0}ms\n\
              \n\
              Current game timings:\n\
-             Engine: {:.0}ms  │  Stockfish: {:.0}ms",
-            elo_stats.current_elo,
-            elo_stats.total_games,
-            elo_stats.wins,
-            elo_stats.losses,
-            elo_stats.draws,
-            score_rate,
+             Engine: {:.0}ms  │  Opponent: {:.0}ms",
+            games,
+            total_games,
+            tally.wins,
+            tally.losses,
+            tally.draws,
+            tally.score_rate(),
             avg_engine_time,
-            avg_stockfish_time,
-            game_state.engine_time.as_millis(),
-            game_state.stockfish_time.as_millis()
+            avg_opponent_time,
+            snapshot.engine_time.as_millis(),
+            snapshot.opponent_time.as_millis()
         );
 
         let paragraph = Paragraph::new(stats_text)
@@ -457,9 +770,9 @@ impl EloTui {
 impl EloRenderer for EloTui {
     fn render(
         &mut self,
-        engine: &Engine,
-        game_state: &GameState,
-        elo_stats: &EloStats,
+        snapshot: &GameSnapshot,
+        tally: &EloTally,
+        total_games: usize,
     ) -> io::Result<()> {
         self.terminal.clear()?;
 
@@ -477,17 +790,21 @@ impl EloRenderer for EloTui {
                 .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
                 .split(main_chunks[0]);
 
-            let board_widget = BoardWidget::new(engine.board(), theme);
+            let board_widget = BoardWidget::new(&snapshot.board, theme);
             f.render_widget(board_widget, board_chunks[0]);
 
-            Self::render_game_info(f, board_chunks[1], engine, game_state, theme);
-            Self::render_elo_stats(f, main_chunks[1], game_state, elo_stats, theme);
+            Self::render_game_info(f, board_chunks[1], snapshot, theme);
+            Self::render_elo_stats(f, main_chunks[1], snapshot, tally, total_games, theme);
         })?;
 
         Ok(())
     }
 
-    fn render_final(&mut self, elo_stats: &EloStats) -> io::Result<()> {
+    fn game_finished(&mut self, _game: &EloGame, _tally: &EloTally) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn render_final(&mut self, report: &EloReport) -> io::Result<()> {
         self.terminal.clear()?;
 
         let theme = &self.theme;
@@ -498,13 +815,21 @@ impl EloRenderer for EloTui {
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
                 .split(size);
 
-            let score_rate = (elo_stats.wins as f32 + elo_stats.draws as f32 * 0.5)
-                / elo_stats.total_games as f32
-                * 100.0;
+            let tally = &report.tally;
+            let levels: String = report
+                .level_summary()
+                .into_iter()
+                .map(|(elo, games, rate)| {
+                    format!(
+                        "  Opponent ELO {:>4}: {:>3} games, score {:.1}%\n",
+                        elo, games, rate
+                    )
+                })
+                .collect();
             let final_text = format!(
                 "ELO DETERMINATION COMPLETE\n\
                  \n\
-                 Final ELO: {}\n\
+                 Estimated ELO: {}\n\
                  \n\
                  Total Games: {}\n\
                  Wins: {}\n\
@@ -512,13 +837,15 @@ impl EloRenderer for EloTui {
                  Draws: {}\n\
                  Score: {:.1}%\n\
                  \n\
+                 {}\n\
                  Press any key to exit...",
-                elo_stats.current_elo,
-                elo_stats.total_games,
-                elo_stats.wins,
-                elo_stats.losses,
-                elo_stats.draws,
-                score_rate
+                format_estimate(report.estimate.as_ref()),
+                tally.games(),
+                tally.wins,
+                tally.losses,
+                tally.draws,
+                tally.score_rate(),
+                levels
             );
 
             let paragraph = Paragraph::new(final_text)
@@ -573,58 +900,88 @@ fn format_number(n: u64) -> String {
 mod tests {
     use super::*;
 
-    // --- is_elo_determined tests (uses score rate: wins=1, draws=0.5) ---
+    #[test]
+    fn test_elo_levels_step_through_range() {
+        assert_eq!(elo_levels(1320, 1720, 200), vec![1320, 1520, 1720]);
+        assert_eq!(elo_levels(1320, 1800, 200), vec![1320, 1520, 1720]);
+        assert_eq!(elo_levels(1500, 1500, 0), vec![1500]);
+    }
 
     #[test]
-    fn test_is_elo_determined_50_percent_score_rate() {
-        // 5 wins, 0 draws, 5 losses -> score = 5/10 = 50%
-        assert!(
-            is_elo_determined(5, 0, 10),
-            "50% score rate at 10 games should be determined"
+    fn test_schedule_alternates_colors_per_cycle() {
+        let jobs = schedule_jobs(&[1400, 1600], 5);
+        let summary: Vec<(u32, Color)> = jobs
+            .iter()
+            .map(|job| (job.opponent_elo, job.engine_color))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1400, Color::White),
+                (1600, Color::White),
+                (1400, Color::Black),
+                (1600, Color::Black),
+                (1400, Color::White),
+            ]
         );
     }
 
     #[test]
-    fn test_is_elo_determined_draws_count_half() {
-        // 4 wins, 2 draws, 4 losses -> score = (4+1)/10 = 50%
-        assert!(
-            is_elo_determined(4, 2, 10),
-            "4W/2D/4L = 50% score rate should be determined"
-        );
+    fn test_estimate_at_fifty_percent_matches_opponent() {
+        let games = vec![(1600, 1.0), (1600, 0.0), (1600, 0.5), (1600, 0.5)];
+        let estimate = estimate_elo(&games).expect("mixed results give an estimate");
+        assert!((estimate.elo - 1600.0).abs() < 0.5, "elo {}", estimate.elo);
+        assert!(estimate.margin > 0.0);
     }
 
     #[test]
-    fn test_is_elo_determined_high_score_rate() {
-        // 8 wins, 0 draws -> score = 8/10 = 80%
-        assert!(
-            !is_elo_determined(8, 0, 10),
-            "80% score rate should not be determined"
-        );
+    fn test_estimate_margin_shrinks_with_more_games() {
+        let few = vec![(1500, 1.0), (1500, 0.0)];
+        let many: Vec<(u32, f64)> = few.iter().cycle().take(40).copied().collect();
+        let few = estimate_elo(&few).unwrap();
+        let many = estimate_elo(&many).unwrap();
+        assert!(many.margin < few.margin);
     }
 
     #[test]
-    fn test_is_elo_determined_low_score_rate() {
-        // 2 wins, 0 draws -> score = 2/10 = 20%
-        assert!(
-            !is_elo_determined(2, 0, 10),
-            "20% score rate should not be determined"
-        );
+    fn test_estimate_reflects_score_against_levels() {
+        // 75% against 1500 corresponds to about +191 Elo.
+        let games = vec![(1500, 1.0), (1500, 1.0), (1500, 1.0), (1500, 0.0)];
+        let estimate = estimate_elo(&games).unwrap();
+        assert!((estimate.elo - 1691.0).abs() < 1.0, "elo {}", estimate.elo);
     }
 
     #[test]
-    fn test_is_elo_determined_insufficient_games() {
-        assert!(
-            !is_elo_determined(3, 0, 5),
-            "Only 5 games should not be determined"
-        );
+    fn test_estimate_is_unbounded_for_perfect_scores() {
+        assert_eq!(estimate_elo(&[]), None);
+        assert_eq!(estimate_elo(&[(1500, 1.0), (1700, 1.0)]), None);
+        assert_eq!(estimate_elo(&[(1500, 0.0)]), None);
     }
 
     #[test]
-    fn test_is_elo_determined_many_draws_is_determined() {
-        // 3 wins, 4 draws, 3 losses -> score = (3+2)/10 = 50%
-        assert!(
-            is_elo_determined(3, 4, 10),
-            "3W/4D/3L = 50% score rate should be determined"
-        );
+    fn test_config_validation() {
+        let config = EloRunConfig {
+            opponent: UciEngineHandle::new("stockfish"),
+            depth: 4,
+            games: 10,
+            time_control: TimeControl::MoveTime(Duration::from_millis(100)),
+            min_elo: 1320,
+            max_elo: 2000,
+            elo_step: 100,
+            concurrency: 1,
+        };
+        assert!(config.validate().is_ok());
+        assert!(EloRunConfig {
+            min_elo: 2100,
+            ..config.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(EloRunConfig {
+            concurrency: 0,
+            ..config
+        }
+        .validate()
+        .is_err());
     }
 }
@@ -0,0 +1,205 @@
+//! Adjustable engine strength for casual games.
+//!
+//! A target Elo between `MIN_STRENGTH_ELO` and `MAX_STRENGTH_ELO` is mapped to a
+//! search depth, a node budget, and a rate of deliberate mistakes. A mistake
+//! replaces the searched move with the move that looks best after a single ply,
+//! judged with some random noise, so weak settings miss tactics and drop
+//! material rather than playing random moves.
+//!
+//! UCI GUIs set a strength with `UCI_LimitStrength` and `UCI_Elo`, or with a
+//! `Skill Level` from 0 to `MAX_SKILL_LEVEL` spread over the same range.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::alpha_beta_searcher::{SearchLimits, MAX_LIMITED_SEARCH_DEPTH};
+
+/// Weakest supported setting.
+pub const MIN_STRENGTH_ELO: u16 = 600;
+
+/// Strongest supported setting; plays at full depth without mistakes.
+pub const MAX_STRENGTH_ELO: u16 = 2400;
+
+/// Highest `Skill Level`, which plays at full strength.
+pub const MAX_SKILL_LEVEL: u8 = 20;
+
+/// Deepest search used by a strength setting.
+const MAX_STRENGTH_DEPTH: u8 = 6;
+
+/// Share of moves replaced by a mistake at the weakest setting.
+const MAX_MISTAKE_RATE: f64 = 0.3;
+
+/// Node budget at the weakest setting; doubles every 180 Elo.
+const MIN_NODE_LIMIT: f64 = 1_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Strength {
+    elo: u16,
+}
+
+impl Strength {
+    /// A strength of `elo`, clamped to the supported range.
+    pub fn new(elo: u16) -> Self {
+        Self {
+            elo: elo.clamp(MIN_STRENGTH_ELO, MAX_STRENGTH_ELO),
+        }
+    }
+
+    /// The strength of a `Skill Level`, or None at the highest level.
+    pub fn from_skill_level(level: u8) -> Option<Self> {
+        if level >= MAX_SKILL_LEVEL {
+            return None;
+        }
+        let range = MAX_STRENGTH_ELO - MIN_STRENGTH_ELO;
+        let elo = MIN_STRENGTH_ELO + range * u16::from(level) / u16::from(MAX_SKILL_LEVEL);
+        Some(Self::new(elo))
+    }
+
+    pub fn elo(&self) -> u16 {
+        self.elo
+    }
+
+    /// Position in the supported range, from 0.0 (weakest) to 1.0 (strongest).
+    fn level(&self) -> f64 {
+        f64::from(self.elo - MIN_STRENGTH_ELO) / f64::from(MAX_STRENGTH_ELO - MIN_STRENGTH_ELO)
+    }
+
+    pub fn search_depth(&self) -> u8 {
+        1 + (self.level() * f64::from(MAX_STRENGTH_DEPTH - 1)).round() as u8
+    }
+
+    /// Nodes searched per move, or None at full strength.
+    pub fn node_limit(&self) -> Option<usize> {
+        if self.elo >= MAX_STRENGTH_ELO {
+            return None;
+        }
+        Some((MIN_NODE_LIMIT * 2f64.powf(self.level() * 10.0)) as usize)
+    }
+
+    /// `limits` held to this strength's depth and node budget.
+    pub fn limit_search(&self, limits: SearchLimits) -> SearchLimits {
+        let depth = limits
+            .depth
+            .unwrap_or(MAX_LIMITED_SEARCH_DEPTH)
+            .min(self.search_depth());
+        let nodes = match (limits.nodes, self.node_limit()) {
+            (Some(nodes), Some(limit)) => Some(nodes.min(limit)),
+            (nodes, limit) => nodes.or(limit),
+        };
+        SearchLimits {
+            depth: Some(depth),
+            nodes,
+            ..limits
+        }
+    }
+
+    /// Probability that a move is replaced by a mistake.
+    pub fn mistake_rate(&self) -> f64 {
+        MAX_MISTAKE_RATE * (1.0 - self.level())
+    }
+
+    /// Random noise, in centipawns, added to each candidate when making a mistake.
+    pub fn mistake_noise(&self) -> i16 {
+        50 + (250.0 * (1.0 - self.level())) as i16
+    }
+
+    /// Effective settings, e.g. for PGN player names.
+    pub fn describe(&self) -> String {
+        match self.node_limit() {
+            Some(nodes) => format!(
+                "strength {}: depth {}, {} nodes, {:.0}% mistakes",
+                self.elo,
+                self.search_depth(),
+                nodes,
+                self.mistake_rate() * 100.0
+            ),
+            None => format!("strength {}: depth {}", self.elo, self.search_depth()),
+        }
+    }
+}
+
+impl FromStr for Strength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elo: u16 = s
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid strength {:?}, expected an Elo rating", s))?;
+        if !(MIN_STRENGTH_ELO..=MAX_STRENGTH_ELO).contains(&elo) {
+            return Err(format!(
+                "strength must be between {} and {}",
+                MIN_STRENGTH_ELO, MAX_STRENGTH_ELO
+            ));
+        }
+        Ok(Self::new(elo))
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.elo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weaker_settings_search_less_and_err_more() {
+        let weak = Strength::new(MIN_STRENGTH_ELO);
+        let club = Strength::new(1500);
+        let full = Strength::new(MAX_STRENGTH_ELO);
+
+        assert_eq!(weak.search_depth(), 1);
+        assert_eq!(full.search_depth(), MAX_STRENGTH_DEPTH);
+        assert!(weak.search_depth() <= club.search_depth());
+        assert!(weak.node_limit() < club.node_limit());
+        assert_eq!(full.node_limit(), None);
+        assert!(weak.mistake_rate() > club.mistake_rate());
+        assert_eq!(full.mistake_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_skill_levels_span_the_elo_range() {
+        assert_eq!(
+            Strength::from_skill_level(0),
+            Some(Strength::new(MIN_STRENGTH_ELO))
+        );
+        assert_eq!(Strength::from_skill_level(10), Some(Strength::new(1500)));
+        assert_eq!(Strength::from_skill_level(MAX_SKILL_LEVEL), None);
+    }
+
+    #[test]
+    fn test_limit_search_keeps_the_tighter_bounds() {
+        let weak = Strength::new(MIN_STRENGTH_ELO);
+        let limits = weak.limit_search(SearchLimits::default());
+        assert_eq!(limits.depth, Some(1));
+        assert_eq!(limits.nodes, weak.node_limit());
+
+        let limits = weak.limit_search(SearchLimits {
+            depth: Some(8),
+            nodes: Some(10),
+            ..SearchLimits::default()
+        });
+        assert_eq!(limits.depth, Some(1));
+        assert_eq!(limits.nodes, Some(10));
+
+        let full = Strength::new(MAX_STRENGTH_ELO);
+        let limits = full.limit_search(SearchLimits {
+            depth: Some(3),
+            ..SearchLimits::default()
+        });
+        assert_eq!(limits.depth, Some(3));
+        assert_eq!(limits.nodes, None);
+    }
+
+    #[test]
+    fn test_parse_rejects_out_of_range_elo() {
+        assert_eq!("1200".parse::<Strength>(), Ok(Strength::new(1200)));
+        assert!("300".parse::<Strength>().is_err());
+        assert!("3000".parse::<Strength>().is_err());
+        assert!("strong".parse::<Strength>().is_err());
+    }
+}
@@ -0,0 +1,867 @@
+//! Tournament runner for matches between this engine and external UCI engines.
+//!
+//! Participants are either the built-in engine or any UCI engine binary. The
+//! runner schedules a round-robin or gauntlet, plays each pairing from a set of
+//! opening positions with colors reversed, adjudicates results (mate, draws,
+//! time forfeits, illegal moves, move limit, and optional resign and draw rules
+//! based on the engines' evaluations), and reports a crosstable. Every
+//! game can optionally be appended to a PGN file.
+
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::alpha_beta_searcher::SearchParams;
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::evaluate::GameEnding;
+use crate::game::adjudication::{Adjudication, AdjudicationRules, Adjudicator};
+use crate::game::engine::{Engine, EngineConfig};
+use crate::game::pgn::{format_eval_comment, PgnGame, PgnResult};
+use crate::game::uci_engine::{GoLimits, UciEngine, UciEngineHandle};
+use crate::input_handler::fen::STARTING_POSITION_FEN;
+use crate::uci::time_management::allocate_time;
+
+/// How a participant is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineSpec {
+    /// The built-in engine. With a depth it searches to that fixed depth,
+    /// otherwise it manages its own time from the clock.
+    Builtin { depth: Option<u8> },
+    /// An external UCI engine.
+    External(UciEngineHandle),
+}
+
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    /// Parses `builtin`, `builtin:<depth>`, or an external engine command line
+    /// such as `stockfish` or `/opt/engines/lc0 --backend=cpu option.Threads=4`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "builtin" {
+            return Ok(EngineSpec::Builtin { depth: None });
+        }
+        if let Some(depth) = s.strip_prefix("builtin:") {
+            let depth = depth
+                .parse::<u8>()
+                .map_err(|_| format!("invalid builtin depth: {}", depth))?;
+            return Ok(EngineSpec::Builtin { depth: Some(depth) });
+        }
+
+        s.parse().map(EngineSpec::External)
+    }
+}
+
+/// Pairing scheme for the event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TournamentFormat {
+    /// Every participant plays every other participant.
+    RoundRobin,
+    /// The first participant plays every other participant.
+    Gauntlet,
+}
+
+impl FromStr for TournamentFormat {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round-robin" => Ok(TournamentFormat::RoundRobin),
+            "gauntlet" => Ok(TournamentFormat::Gauntlet),
+            _ => Err("invalid format; options are: round-robin, gauntlet"),
+        }
+    }
+}
+
+/// Time control applied to every game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// A fixed amount of thinking time per move. No time forfeits.
+    MoveTime(Duration),
+    /// A game clock with a per-move increment. Flagging loses the game.
+    Clock { base: Duration, increment: Duration },
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Parses `<base>+<increment>` in seconds (e.g. `60+0.5`), `<base>` in
+    /// seconds, or `movetime=<ms>` for a fixed time per move.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_secs = |v: &str| {
+            v.parse::<f64>()
+                .ok()
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .map(Duration::from_secs_f64)
+                .ok_or_else(|| format!("invalid time control: {}", s))
+        };
+
+        if let Some(ms) = s.strip_prefix("movetime=") {
+            let ms = ms
+                .parse::<u64>()
+                .map_err(|_| format!("invalid time control: {}", s))?;
+            return Ok(TimeControl::MoveTime(Duration::from_millis(ms)));
+        }
+
+        let (base, increment) = match s.split_once('+') {
+            Some((base, increment)) => (parse_secs(base)?, parse_secs(increment)?),
+            None => (parse_secs(s)?, Duration::ZERO),
+        };
+        if base.is_zero() {
+            return Err(format!("time control base must be positive: {}", s));
+        }
+        Ok(TimeControl::Clock { base, increment })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// Formats as a PGN `TimeControl` tag value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeControl::MoveTime(time) => write!(f, "{}/move", time.as_secs_f64()),
+            TimeControl::Clock { base, increment } => {
+                write!(f, "{}+{}", base.as_secs_f64(), increment.as_secs_f64())
+            }
+        }
+    }
+}
+
+/// Full configuration for a tournament.
+pub struct TournamentConfig {
+    pub engines: Vec<EngineSpec>,
+    pub format: TournamentFormat,
+    pub time_control: TimeControl,
+    /// Games per pairing; colors alternate between consecutive games.
+    pub games_per_pairing: usize,
+    /// Starting positions as FEN. Empty means the standard starting position.
+    pub openings: Vec<String>,
+    /// Adjudicate the game as a draw after this many full moves.
+    pub max_moves: usize,
+    /// Resign and draw rules applied to the engines' evaluations.
+    pub adjudication: AdjudicationRules,
+    pub pgn_out: Option<String>,
+    /// Attach each engine's evaluation to its moves as PGN comments.
+    pub pgn_evals: bool,
+}
+
+/// A scheduled game between two participants (indices into the engine list).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pairing {
+    pub round: usize,
+    pub white: usize,
+    pub black: usize,
+    pub opening: usize,
+}
+
+/// Build the list of games to play. Each pair of opponents plays
+/// `games_per_pairing` games, alternating colors, and each opening is played
+/// from both sides before moving on to the next one.
+pub fn schedule(
+    format: TournamentFormat,
+    participant_count: usize,
+    games_per_pairing: usize,
+    opening_count: usize,
+) -> Vec<Pairing> {
+    let opponents: Vec<(usize, usize)> = match format {
+        TournamentFormat::RoundRobin => (0..participant_count)
+            .flat_map(|a| ((a + 1)..participant_count).map(move |b| (a, b)))
+            .collect(),
+        TournamentFormat::Gauntlet => (1..participant_count).map(|b| (0, b)).collect(),
+    };
+
+    let opening_count = opening_count.max(1);
+    let mut pairings = Vec::new();
+    for game in 0..games_per_pairing {
+        for &(a, b) in &opponents {
+            let (white, black) = if game % 2 == 0 { (a, b) } else { (b, a) };
+            pairings.push(Pairing {
+                round: game + 1,
+                white,
+                black,
+                opening: (game / 2) % opening_count,
+            });
+        }
+    }
+    pairings
+}
+
+/// Read opening positions from a file with one FEN or EPD per line. Blank
+/// lines and lines starting with `#` are ignored. EPD lines (four fields) get
+/// default move counters.
+pub fn load_openings(path: &str) -> Result<Vec<String>, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    let mut openings = Vec::new();
+    for (line_number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let fen = if fields.len() >= 6 {
+            fields[..6].join(" ")
+        } else if fields.len() >= 4 {
+            format!("{} 0 1", fields[..4].join(" "))
+        } else {
+            return Err(format!("{}:{}: not a FEN: {}", path, line_number + 1, line));
+        };
+        Board::from_str(&fen)
+            .map_err(|e| format!("{}:{}: invalid FEN: {:?}", path, line_number + 1, e))?;
+        openings.push(fen);
+    }
+    Ok(openings)
+}
+
+/// Accumulated results between every pair of participants.
+pub struct Crosstable {
+    names: Vec<String>,
+    /// `scores[a][b]` holds (wins, draws, losses) of `a` against `b`.
+    scores: Vec<Vec<(usize, usize, usize)>>,
+}
+
+impl Crosstable {
+    pub fn new(names: Vec<String>) -> Self {
+        let n = names.len();
+        Self {
+            names,
+            scores: vec![vec![(0, 0, 0); n]; n],
+        }
+    }
+
+    pub fn record(&mut self, white: usize, black: usize, result: PgnResult) {
+        match result {
+            PgnResult::WhiteWins => {
+                self.scores[white][black].0 += 1;
+                self.scores[black][white].2 += 1;
+            }
+            PgnResult::BlackWins => {
+                self.scores[black][white].0 += 1;
+                self.scores[white][black].2 += 1;
+            }
+            PgnResult::Draw => {
+                self.scores[white][black].1 += 1;
+                self.scores[black][white].1 += 1;
+            }
+            PgnResult::Unfinished => {}
+        }
+    }
+
+    /// Total score for a participant (win = 1, draw = 0.5).
+    pub fn score(&self, player: usize) -> f32 {
+        self.scores[player]
+            .iter()
+            .map(|&(w, d, _)| w as f32 + d as f32 * 0.5)
+            .sum()
+    }
+
+    pub fn games_played(&self, player: usize) -> usize {
+        self.scores[player].iter().map(|&(w, d, l)| w + d + l).sum()
+    }
+
+    /// Participant indices ordered by descending score.
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.score(b)
+                .partial_cmp(&self.score(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        order
+    }
+}
+
+impl fmt::Display for Crosstable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let standings = self.standings();
+        let name_width = self.names.iter().map(|n| n.len()).max().unwrap_or(0).max(6);
+
+        write!(
+            f,
+            "{:>3}  {:<width$}  {:>6}  {:>5}",
+            "#",
+            "Engine",
+            "Score",
+            "Games",
+            width = name_width
+        )?;
+        for rank in 1..=standings.len() {
+            write!(f, "  {:>6}", rank)?;
+        }
+        writeln!(f)?;
+
+        for (rank, &player) in standings.iter().enumerate() {
+            write!(
+                f,
+                "{:>3}  {:<width$}  {:>6.1}  {:>5}",
+                rank + 1,
+                self.names[player],
+                self.score(player),
+                self.games_played(player),
+                width = name_width
+            )?;
+            for &opponent in &standings {
+                if opponent == player {
+                    write!(f, "  {:>6}", "-")?;
+                } else {
+                    let (w, d, _) = self.scores[player][opponent];
+                    write!(f, "  {:>6.1}", w as f32 + d as f32 * 0.5)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A participant that can be asked for moves.
+pub(crate) trait Player {
+    fn name(&self) -> &str;
+    fn new_game(&mut self, starting_position: &Board) -> Result<(), String>;
+    /// Choose a move in UCI notation given all moves played from the starting
+    /// position, along with the player's evaluation from White's perspective.
+    fn choose_move(
+        &mut self,
+        start_fen: Option<&str>,
+        moves: &[String],
+        limits: GoLimits,
+    ) -> Result<(String, Option<i16>), String>;
+}
+
+/// The built-in engine, kept in sync with the game by replaying new moves.
+pub(crate) struct BuiltinPlayer {
+    name: String,
+    depth: Option<u8>,
+    params: SearchParams,
+    engine: Engine,
+    applied_moves: usize,
+}
+
+impl BuiltinPlayer {
+    pub(crate) fn new(name: String, depth: Option<u8>) -> Self {
+        Self {
+            name,
+            depth,
+            params: SearchParams::default(),
+            engine: Engine::new(),
+            applied_moves: 0,
+        }
+    }
+
+    /// Search with `params` from the next game on.
+    pub(crate) fn with_params(self, params: SearchParams) -> Self {
+        Self { params, ..self }
+    }
+}
+
+impl Player for BuiltinPlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self, starting_position: &Board) -> Result<(), String> {
+        self.engine = Engine::with_config(EngineConfig {
+            search_depth: self.depth.unwrap_or(EngineConfig::default().search_depth),
+            starting_position: starting_position.clone(),
+        });
+        self.engine.set_search_params(self.params);
+        self.applied_moves = 0;
+        Ok(())
+    }
+
+    fn choose_move(
+        &mut self,
+        _start_fen: Option<&str>,
+        moves: &[String],
+        limits: GoLimits,
+    ) -> Result<(String, Option<i16>), String> {
+        for uci_move in &moves[self.applied_moves..] {
+            self.engine
+                .make_move_uci(uci_move)
+                .map_err(|e| format!("failed to apply {}: {}", uci_move, e))?;
+            self.engine.board_mut().toggle_turn();
+            self.engine.record_position_hash();
+        }
+        self.applied_moves = moves.len();
+
+        let result = match (self.depth, limits) {
+            (Some(_), _) => self.engine.get_best_move(),
+            (None, GoLimits::MoveTime(ms)) => self
+                .engine
+                .get_best_move_with_time_limit(Duration::from_millis(ms)),
+            (
+                None,
+                GoLimits::Clock {
+                    wtime,
+                    btime,
+                    winc,
+                    binc,
+                },
+            ) => {
+                let (remaining, increment) = match self.engine.board().turn() {
+                    Color::White => (wtime, winc),
+                    Color::Black => (btime, binc),
+                };
+                let budget = allocate_time(remaining, increment);
+                self.engine
+                    .get_best_move_with_time_limit(Duration::from_millis(budget))
+            }
+        };
+        let score = self.engine.get_search_stats().last_score;
+        result
+            .map(|m| (m.to_uci(), score))
+            .map_err(|e| e.to_string())
+    }
+}
+
+struct ExternalPlayer {
+    engine: UciEngine,
+}
+
+impl Player for ExternalPlayer {
+    fn name(&self) -> &str {
+        self.engine.name()
+    }
+
+    fn new_game(&mut self, _starting_position: &Board) -> Result<(), String> {
+        self.engine.new_game().map_err(|e| e.to_string())
+    }
+
+    fn choose_move(
+        &mut self,
+        start_fen: Option<&str>,
+        moves: &[String],
+        limits: GoLimits,
+    ) -> Result<(String, Option<i16>), String> {
+        // UCI scores are relative to the side to move; convert to White's perspective.
+        let sign = if moves.len().is_multiple_of(2) { 1 } else { -1 };
+        let starts_with_black = start_fen
+            .map(|fen| fen.split_whitespace().nth(1) == Some("b"))
+            .unwrap_or(false);
+        let sign = if starts_with_black { -sign } else { sign };
+
+        self.engine
+            .best_move(start_fen, moves, limits)
+            .map(|best| {
+                let score = best
+                    .score_cp
+                    .map(|cp| (cp * sign).clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+                (best.uci_move, score)
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn create_player(spec: &EngineSpec) -> Result<Box<dyn Player>, String> {
+    match spec {
+        EngineSpec::Builtin { depth } => {
+            let name = match depth {
+                Some(depth) => format!("chess (depth {})", depth),
+                None => "chess".to_string(),
+            };
+            Ok(Box::new(BuiltinPlayer::new(name, *depth)))
+        }
+        EngineSpec::External(handle) => handle
+            .spawn()
+            .map(|engine| Box::new(ExternalPlayer { engine }) as Box<dyn Player>)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Make participant names unique by suffixing duplicates with their index.
+fn unique_names(names: Vec<String>) -> Vec<String> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if names.iter().filter(|n| *n == name).count() > 1 {
+                format!("{} #{}", name, i + 1)
+            } else {
+                name.clone()
+            }
+        })
+        .collect()
+}
+
+/// Result of a single game plus the reason it ended.
+struct GameOutcome {
+    result: PgnResult,
+    termination: String,
+}
+
+/// Play one game between two participants and return the finished PGN.
+pub(crate) fn play_game(
+    white: &mut dyn Player,
+    black: &mut dyn Player,
+    white_name: &str,
+    black_name: &str,
+    opening: &str,
+    config: &TournamentConfig,
+) -> Result<PgnGame, String> {
+    let starting_position =
+        Board::from_str(opening).map_err(|e| format!("invalid opening FEN: {:?}", e))?;
+    let start_fen = (opening != STARTING_POSITION_FEN).then_some(opening);
+
+    white.new_game(&starting_position)?;
+    black.new_game(&starting_position)?;
+
+    let mut referee = Engine::with_config(EngineConfig {
+        search_depth: 1,
+        starting_position: starting_position.clone(),
+    });
+    let mut pgn = PgnGame::new(&starting_position);
+    pgn.set_header("White", white_name);
+    pgn.set_header("Black", black_name);
+    pgn.set_header("TimeControl", &config.time_control.to_string());
+
+    let (mut white_clock, mut black_clock) = match config.time_control {
+        TimeControl::Clock { base, .. } => (base, base),
+        TimeControl::MoveTime(_) => (Duration::ZERO, Duration::ZERO),
+    };
+    let mut moves: Vec<String> = Vec::new();
+    let mut adjudicator = Adjudicator::new(config.adjudication);
+
+    let outcome = loop {
+        let turn = referee.board().turn();
+        if let Some(ending) = referee.check_game_over() {
+            break match ending {
+                GameEnding::Checkmate => GameOutcome {
+                    result: PgnResult::win_for(turn.opposite()),
+                    termination: "checkmate".to_string(),
+                },
+                GameEnding::Stalemate => GameOutcome {
+                    result: PgnResult::Draw,
+                    termination: "stalemate".to_string(),
+                },
+                GameEnding::Draw => GameOutcome {
+                    result: PgnResult::Draw,
+                    termination: "draw by rule".to_string(),
+                },
+                GameEnding::VariantWin => GameOutcome {
+                    result: PgnResult::win_for(turn.opposite()),
+                    termination: referee.board().variant().win_description().to_string(),
+                },
+            };
+        }
+        if moves.len() >= config.max_moves * 2 {
+            break GameOutcome {
+                result: PgnResult::Draw,
+                termination: "adjudication: move limit".to_string(),
+            };
+        }
+
+        let limits = match config.time_control {
+            TimeControl::MoveTime(time) => GoLimits::MoveTime(time.as_millis() as u64),
+            TimeControl::Clock { increment, .. } => GoLimits::Clock {
+                wtime: white_clock.as_millis() as u64,
+                btime: black_clock.as_millis() as u64,
+                winc: increment.as_millis() as u64,
+                binc: increment.as_millis() as u64,
+            },
+        };
+
+        let player: &mut dyn Player = match turn {
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+        let start_time = Instant::now();
+        let chosen = player.choose_move(start_fen, &moves, limits);
+        let elapsed = start_time.elapsed();
+
+        if let TimeControl::Clock { increment, .. } = config.time_control {
+            let clock = match turn {
+                Color::White => &mut white_clock,
+                Color::Black => &mut black_clock,
+            };
+            if elapsed > *clock {
+                break GameOutcome {
+                    result: PgnResult::win_for(turn.opposite()),
+                    termination: "time forfeit".to_string(),
+                };
+            }
+            *clock = *clock - elapsed + increment;
+        }
+
+        let (uci_move, score) = match chosen {
+            Ok(chosen) => chosen,
+            Err(error) => {
+                break GameOutcome {
+                    result: PgnResult::win_for(turn.opposite()),
+                    termination: format!("engine failure: {}", error),
+                }
+            }
+        };
+        let fullmove = referee.board().fullmove_clock().value() as usize;
+        if referee.make_move_uci(&uci_move).is_err() {
+            break GameOutcome {
+                result: PgnResult::win_for(turn.opposite()),
+                termination: format!("illegal move: {}", uci_move),
+            };
+        }
+        let san = referee
+            .move_history()
+            .last()
+            .map(|entry| entry.notation.clone())
+            .unwrap_or_else(|| uci_move.clone());
+        let comment = score.filter(|_| config.pgn_evals).map(format_eval_comment);
+        pgn.push_move(&san, comment);
+        referee.board_mut().toggle_turn();
+        referee.record_position_hash();
+        moves.push(uci_move);
+
+        if let Some(adjudication) = adjudicator.record(fullmove, score) {
+            break GameOutcome {
+                result: adjudication.result(),
+                termination: match adjudication {
+                    Adjudication::Resign { .. } => "adjudication: resign".to_string(),
+                    Adjudication::Draw => "adjudication: draw".to_string(),
+                },
+            };
+        }
+    };
+
+    pgn.set_result(outcome.result);
+    pgn.set_header("Termination", &outcome.termination);
+    Ok(pgn)
+}
+
+/// Run a full tournament, printing progress and the final crosstable.
+pub fn run_tournament(config: TournamentConfig) -> Result<Crosstable, String> {
+    if config.engines.len() < 2 {
+        return Err("a tournament needs at least two engines".to_string());
+    }
+
+    let mut players = config
+        .engines
+        .iter()
+        .map(create_player)
+        .collect::<Result<Vec<_>, _>>()?;
+    let names = unique_names(players.iter().map(|p| p.name().to_string()).collect());
+
+    let openings = if config.openings.is_empty() {
+        vec![STARTING_POSITION_FEN.to_string()]
+    } else {
+        config.openings.clone()
+    };
+    let pairings = schedule(
+        config.format,
+        players.len(),
+        config.games_per_pairing,
+        openings.len(),
+    );
+
+    let mut crosstable = Crosstable::new(names.clone());
+    println!(
+        "Tournament: {} engines, {} games, time control {}",
+        players.len(),
+        pairings.len(),
+        config.time_control
+    );
+
+    for (game_number, pairing) in pairings.iter().enumerate() {
+        let (white, black) = borrow_pair(&mut players, pairing.white, pairing.black);
+        let mut pgn = play_game(
+            white,
+            black,
+            &names[pairing.white],
+            &names[pairing.black],
+            &openings[pairing.opening],
+            &config,
+        )?;
+        pgn.set_header("Event", "chess tournament");
+        pgn.set_header("Round", &format!("{}.{}", pairing.round, game_number + 1));
+
+        crosstable.record(pairing.white, pairing.black, pgn.result());
+        println!(
+            "Game {}/{}: {} vs {}: {} ({})",
+            game_number + 1,
+            pairings.len(),
+            names[pairing.white],
+            names[pairing.black],
+            pgn.result(),
+            pgn.header("Termination").unwrap_or("-")
+        );
+
+        if let Some(path) = &config.pgn_out {
+            pgn.append_to_file(path)
+                .map_err(|e| format!("failed to write PGN to {}: {}", path, e))?;
+        }
+    }
+
+    println!("\n{}", crosstable);
+    Ok(crosstable)
+}
+
+/// Mutably borrow two distinct players at once.
+fn borrow_pair(
+    players: &mut [Box<dyn Player>],
+    a: usize,
+    b: usize,
+) -> (&mut dyn Player, &mut dyn Player) {
+    assert_ne!(a, b, "a player cannot play against itself");
+    if a < b {
+        let (left, right) = players.split_at_mut(b);
+        (left[a].as_mut(), right[0].as_mut())
+    } else {
+        let (left, right) = players.split_at_mut(a);
+        (right[0].as_mut(), left[b].as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_spec_parsing() {
+        assert_eq!(
+            "builtin".parse::<EngineSpec>(),
+            Ok(EngineSpec::Builtin { depth: None })
+        );
+        assert_eq!(
+            "builtin:5".parse::<EngineSpec>(),
+            Ok(EngineSpec::Builtin { depth: Some(5) })
+        );
+        assert_eq!(
+            "/usr/bin/lc0 --threads 2".parse::<EngineSpec>(),
+            Ok(EngineSpec::External(UciEngineHandle {
+                path: "/usr/bin/lc0".to_string(),
+                args: vec!["--threads".to_string(), "2".to_string()],
+                options: Vec::new(),
+            }))
+        );
+        assert!("builtin:deep".parse::<EngineSpec>().is_err());
+    }
+
+    #[test]
+    fn test_time_control_parsing() {
+        assert_eq!(
+            "60+0.5".parse::<TimeControl>(),
+            Ok(TimeControl::Clock {
+                base: Duration::from_secs(60),
+                increment: Duration::from_millis(500),
+            })
+        );
+        assert_eq!(
+            "10".parse::<TimeControl>(),
+            Ok(TimeControl::Clock {
+                base: Duration::from_secs(10),
+                increment: Duration::ZERO,
+            })
+        );
+        assert_eq!(
+            "movetime=250".parse::<TimeControl>(),
+            Ok(TimeControl::MoveTime(Duration::from_millis(250)))
+        );
+        assert!("0+1".parse::<TimeControl>().is_err());
+        assert!("fast".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn test_round_robin_schedule_alternates_colors() {
+        let pairings = schedule(TournamentFormat::RoundRobin, 3, 2, 1);
+        assert_eq!(pairings.len(), 6);
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            assert!(pairings.iter().any(|p| p.white == a && p.black == b));
+            assert!(pairings.iter().any(|p| p.white == b && p.black == a));
+        }
+    }
+
+    #[test]
+    fn test_gauntlet_schedule_only_features_first_engine() {
+        let pairings = schedule(TournamentFormat::Gauntlet, 4, 4, 2);
+        assert_eq!(pairings.len(), 12);
+        assert!(pairings.iter().all(|p| p.white == 0 || p.black == 0));
+        // Each opening is played from both sides before moving to the next.
+        assert!(pairings[..6].iter().all(|p| p.opening == 0));
+        assert!(pairings[6..].iter().all(|p| p.opening == 1));
+    }
+
+    #[test]
+    fn test_crosstable_scores_and_standings() {
+        let names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let mut crosstable = Crosstable::new(names);
+        crosstable.record(0, 1, PgnResult::BlackWins);
+        crosstable.record(1, 2, PgnResult::Draw);
+        crosstable.record(2, 0, PgnResult::WhiteWins);
+
+        assert_eq!(crosstable.score(0), 0.0);
+        assert_eq!(crosstable.score(1), 1.5);
+        assert_eq!(crosstable.score(2), 1.5);
+        assert_eq!(crosstable.games_played(1), 2);
+        assert_eq!(crosstable.standings().last(), Some(&0));
+    }
+
+    #[test]
+    fn test_unique_names_suffixes_duplicates() {
+        let names = unique_names(vec![
+            "sf".to_string(),
+            "chess".to_string(),
+            "sf".to_string(),
+        ]);
+        assert_eq!(names, vec!["sf #1", "chess", "sf #3"]);
+    }
+
+    #[test]
+    fn test_builtin_players_complete_a_game() {
+        let config = TournamentConfig {
+            engines: vec![],
+            format: TournamentFormat::RoundRobin,
+            time_control: TimeControl::MoveTime(Duration::from_millis(10)),
+            games_per_pairing: 1,
+            openings: vec![],
+            max_moves: 5,
+            adjudication: AdjudicationRules::default(),
+            pgn_out: None,
+            pgn_evals: false,
+        };
+        let mut white = BuiltinPlayer::new("white".to_string(), Some(1));
+        let mut black = BuiltinPlayer::new("black".to_string(), Some(1));
+        let pgn = play_game(
+            &mut white,
+            &mut black,
+            "white",
+            "black",
+            STARTING_POSITION_FEN,
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(pgn.moves().len(), 10);
+        assert_eq!(pgn.result(), PgnResult::Draw);
+        assert_eq!(pgn.header("Termination"), Some("adjudication: move limit"));
+    }
+
+    #[test]
+    fn test_resign_adjudication_ends_lopsided_game() {
+        let config = TournamentConfig {
+            engines: vec![],
+            format: TournamentFormat::RoundRobin,
+            time_control: TimeControl::MoveTime(Duration::from_millis(10)),
+            games_per_pairing: 1,
+            openings: vec![],
+            max_moves: 50,
+            adjudication: AdjudicationRules {
+                resign: "movecount=1 score=300".parse().ok(),
+                draw: None,
+            },
+            pgn_out: None,
+            pgn_evals: false,
+        };
+        let mut white = BuiltinPlayer::new("white".to_string(), Some(2));
+        let mut black = BuiltinPlayer::new("black".to_string(), Some(2));
+        let pgn = play_game(
+            &mut white,
+            &mut black,
+            "white",
+            "black",
+            "4k3/8/8/8/8/8/8/Q3K3 w - - 0 1",
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(pgn.moves().len(), 2);
+        assert_eq!(pgn.result(), PgnResult::WhiteWins);
+        assert_eq!(pgn.header("Termination"), Some("adjudication: resign"));
+    }
+}
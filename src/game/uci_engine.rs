@@ -0,0 +1,509 @@
+//! Client for external engines that speak the UCI protocol.
+//!
+//! A `UciEngineHandle` describes how to launch an engine: its executable, command-line
+//! arguments, and UCI options to set after the handshake. Spawning it performs the
+//! `uci`/`isready` handshake and yields a `UciEngine`, which is asked for moves given
+//! a starting FEN, the moves played so far, and the remaining clock time.
+//!
+//! Common settings (threads, hash size, skill level, and an Elo limit) have typed
+//! builder methods on the handle. A missing executable is reported as such, and an
+//! engine that fails the handshake is restarted a few times before giving up.
+
+use std::fmt;
+use std::io::ErrorKind;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// Attempts to start an engine that fails its handshake before giving up.
+const SPAWN_ATTEMPTS: usize = 3;
+
+#[derive(Error, Debug)]
+pub enum UciEngineError {
+    #[error("Engine `{path}` not found; install it or pass the full path to its executable")]
+    NotFound { path: String },
+    #[error("Failed to start engine `{path}`: {error}")]
+    Spawn { path: String, error: std::io::Error },
+    #[error("I/O error communicating with engine: {error}")]
+    Io { error: std::io::Error },
+    #[error("Engine closed its output stream")]
+    Disconnected,
+    #[error("Engine sent a malformed response: {line}")]
+    MalformedResponse { line: String },
+    #[error("Engine does not support the UCI option `{name}`")]
+    UnsupportedOption { name: String },
+}
+
+impl UciEngineError {
+    /// Whether retrying might help, e.g. the engine crashed, as opposed to a
+    /// missing executable.
+    fn is_transient(&self) -> bool {
+        !matches!(
+            self,
+            UciEngineError::NotFound { .. } | UciEngineError::Spawn { .. }
+        )
+    }
+}
+
+impl From<std::io::Error> for UciEngineError {
+    fn from(error: std::io::Error) -> Self {
+        UciEngineError::Io { error }
+    }
+}
+
+/// How to launch an external UCI engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciEngineHandle {
+    pub path: String,
+    pub args: Vec<String>,
+    /// UCI options (name, value) set after the handshake, in order.
+    pub options: Vec<(String, String)>,
+}
+
+impl UciEngineHandle {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            args: Vec::new(),
+            options: Vec::new(),
+        }
+    }
+
+    /// Set the UCI option `name`, replacing an earlier value.
+    pub fn with_option(mut self, name: &str, value: &str) -> Self {
+        match self.options.iter_mut().find(|(n, _)| n == name) {
+            Some(option) => option.1 = value.to_string(),
+            None => self.options.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Search with `threads` threads (`Threads`).
+    pub fn with_threads(self, threads: u32) -> Self {
+        self.with_option("Threads", &threads.to_string())
+    }
+
+    /// Use a transposition table of `megabytes` MB (`Hash`).
+    pub fn with_hash(self, megabytes: u32) -> Self {
+        self.with_option("Hash", &megabytes.to_string())
+    }
+
+    /// Weaken the engine to `level` (Stockfish's `Skill Level`, 0-20).
+    pub fn with_skill_level(self, level: u8) -> Self {
+        self.with_option("Skill Level", &level.to_string())
+    }
+
+    /// Limit the engine to `elo` (`UCI_LimitStrength` and `UCI_Elo`).
+    pub fn with_elo_limit(self, elo: u32) -> Self {
+        self.with_option("UCI_LimitStrength", "true")
+            .with_option("UCI_Elo", &elo.to_string())
+    }
+
+    /// Start the engine, complete the handshake, and apply the configured options.
+    /// An engine that exits or misbehaves during startup is restarted up to
+    /// `SPAWN_ATTEMPTS` times; a missing executable fails immediately.
+    pub fn spawn(&self) -> Result<UciEngine, UciEngineError> {
+        let mut attempt = 1;
+        loop {
+            match self.try_spawn() {
+                Err(error) if attempt < SPAWN_ATTEMPTS && error.is_transient() => {
+                    eprintln!(
+                        "Engine `{}` failed to start ({}); retrying",
+                        self.path, error
+                    );
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn try_spawn(&self) -> Result<UciEngine, UciEngineError> {
+        let mut engine = UciEngine::new(&self.path, &self.args)?;
+        for (name, value) in &self.options {
+            engine.set_option(name, value)?;
+        }
+        if !self.options.is_empty() {
+            engine.wait_ready()?;
+        }
+        Ok(engine)
+    }
+}
+
+impl FromStr for UciEngineHandle {
+    type Err = String;
+
+    /// Parses an engine command line such as `stockfish` or
+    /// `/opt/engines/lc0 --backend=cpu option.Threads=4`. Tokens of the form
+    /// `option.<name>=<value>` become UCI options; the rest are program arguments.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let path = tokens.next().ok_or("engine command cannot be empty")?;
+        let mut handle = Self::new(path);
+        for token in tokens {
+            match token.strip_prefix("option.") {
+                Some(option) => {
+                    let (name, value) = option
+                        .split_once('=')
+                        .filter(|(name, _)| !name.is_empty())
+                        .ok_or_else(|| format!("invalid engine option: {}", token))?;
+                    handle.options.push((name.to_string(), value.to_string()));
+                }
+                None => handle.args.push(token.to_string()),
+            }
+        }
+        Ok(handle)
+    }
+}
+
+impl fmt::Display for UciEngineHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+        for (name, value) in &self.options {
+            write!(f, " option.{}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Search limits passed along with a `go` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoLimits {
+    /// Fixed time per move in milliseconds.
+    MoveTime(u64),
+    /// Remaining clock time and increment for each side, in milliseconds.
+    Clock {
+        wtime: u64,
+        btime: u64,
+        winc: u64,
+        binc: u64,
+    },
+}
+
+impl GoLimits {
+    pub fn to_command(self) -> String {
+        match self {
+            GoLimits::MoveTime(ms) => format!("go movetime {}", ms),
+            GoLimits::Clock {
+                wtime,
+                btime,
+                winc,
+                binc,
+            } => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                wtime, btime, winc, binc
+            ),
+        }
+    }
+}
+
+/// The engine's answer to a `go` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestMove {
+    /// The chosen move in UCI notation.
+    pub uci_move: String,
+    /// Wall-clock time between sending `go` and receiving `bestmove`.
+    pub elapsed: Duration,
+    /// Last centipawn score reported via `info ... score cp`, from the
+    /// perspective of the side to move.
+    pub score_cp: Option<i32>,
+}
+
+/// Extract the centipawn score from a UCI `info` line, if present.
+fn parse_info_score_cp(line: &str) -> Option<i32> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.first() != Some(&"info") {
+        return None;
+    }
+    let score_index = tokens.iter().position(|&t| t == "score")?;
+    match tokens.get(score_index + 1..score_index + 3) {
+        Some(["cp", value]) => value.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Find the `min` and `max` of the `spin` option `name` among `option name ...` lines.
+fn parse_spin_option_range(options: &[String], name: &str) -> Option<(i64, i64)> {
+    let prefix = format!("option name {} type spin ", name);
+    let line = options.iter().find(|line| line.starts_with(&prefix))?;
+    let value = |key: &str| {
+        line.split_whitespace()
+            .skip_while(|&w| w != key)
+            .nth(1)
+            .and_then(|v| v.parse::<i64>().ok())
+    };
+    Some((value("min")?, value("max")?))
+}
+
+/// A running external UCI engine process.
+pub struct UciEngine {
+    process: Child,
+    reader: BufReader<ChildStdout>,
+    name: String,
+    options: Vec<String>,
+}
+
+impl UciEngine {
+    /// Spawn the engine at `path` with the given command-line arguments and
+    /// complete the UCI handshake.
+    pub fn new(path: &str, args: &[String]) -> Result<Self, UciEngineError> {
+        let mut process = Command::new(path)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|error| match error.kind() {
+                ErrorKind::NotFound => UciEngineError::NotFound {
+                    path: path.to_string(),
+                },
+                _ => UciEngineError::Spawn {
+                    path: path.to_string(),
+                    error,
+                },
+            })?;
+
+        let stdout = process.stdout.take().ok_or(UciEngineError::Disconnected)?;
+        let mut engine = Self {
+            process,
+            reader: BufReader::new(stdout),
+            name: path.to_string(),
+            options: Vec::new(),
+        };
+        engine.handshake()?;
+        Ok(engine)
+    }
+
+    /// The engine's self-reported name (`id name ...`), or its path if it did not report one.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Raw `option name ...` lines reported during the handshake.
+    pub fn options(&self) -> &[String] {
+        &self.options
+    }
+
+    /// The `(min, max)` range of a `spin` option reported during the handshake,
+    /// such as `UCI_Elo`.
+    pub fn spin_option_range(&self, name: &str) -> Option<(i64, i64)> {
+        parse_spin_option_range(&self.options, name)
+    }
+
+    fn handshake(&mut self) -> Result<(), UciEngineError> {
+        self.send_command("uci")?;
+        loop {
+            let line = self.read_line()?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                self.name = name.trim().to_string();
+            } else if line.starts_with("option name ") {
+                self.options.push(line);
+            } else if line == "uciok" {
+                break;
+            }
+        }
+        self.wait_ready()
+    }
+
+    pub fn send_command(&mut self, command: &str) -> Result<(), UciEngineError> {
+        let stdin = self
+            .process
+            .stdin
+            .as_mut()
+            .ok_or(UciEngineError::Disconnected)?;
+        writeln!(stdin, "{}", command)?;
+        stdin.flush()?;
+        Ok(())
+    }
+
+    pub fn read_line(&mut self) -> Result<String, UciEngineError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(UciEngineError::Disconnected);
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// Send `isready` and block until the engine answers `readyok`.
+    pub fn wait_ready(&mut self) -> Result<(), UciEngineError> {
+        self.send_command("isready")?;
+        while self.read_line()? != "readyok" {}
+        Ok(())
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciEngineError> {
+        self.send_command(&format!("setoption name {} value {}", name, value))
+    }
+
+    /// Limit the engine to `elo` with `UCI_LimitStrength` and `UCI_Elo`, clamped
+    /// to the range it supports. Returns the Elo actually applied.
+    pub fn limit_strength(&mut self, elo: u32) -> Result<u32, UciEngineError> {
+        let (min_elo, max_elo) =
+            self.spin_option_range("UCI_Elo")
+                .ok_or_else(|| UciEngineError::UnsupportedOption {
+                    name: "UCI_Elo".to_string(),
+                })?;
+        let clamped = i64::from(elo).clamp(min_elo, max_elo).max(0) as u32;
+        self.set_option("UCI_LimitStrength", "true")?;
+        self.set_option("UCI_Elo", &clamped.to_string())?;
+        self.wait_ready()?;
+        Ok(clamped)
+    }
+
+    /// Tell the engine a new game is starting.
+    pub fn new_game(&mut self) -> Result<(), UciEngineError> {
+        self.send_command("ucinewgame")?;
+        self.wait_ready()
+    }
+
+    /// Ask the engine for its move. `start_fen` of `None` means the standard starting
+    /// position.
+    pub fn best_move(
+        &mut self,
+        start_fen: Option<&str>,
+        moves: &[String],
+        limits: GoLimits,
+    ) -> Result<BestMove, UciEngineError> {
+        let mut position = match start_fen {
+            Some(fen) => format!("position fen {}", fen),
+            None => "position startpos".to_string(),
+        };
+        if !moves.is_empty() {
+            position.push_str(" moves ");
+            position.push_str(&moves.join(" "));
+        }
+        self.send_command(&position)?;
+
+        let start_time = Instant::now();
+        self.send_command(&limits.to_command())?;
+
+        let mut score_cp = None;
+        loop {
+            let line = self.read_line()?;
+            if let Some(score) = parse_info_score_cp(&line) {
+                score_cp = Some(score);
+            } else if line.starts_with("bestmove") {
+                let uci_move = line
+                    .split_whitespace()
+                    .nth(1)
+                    .ok_or_else(|| UciEngineError::MalformedResponse { line: line.clone() })?
+                    .to_string();
+                return Ok(BestMove {
+                    uci_move,
+                    elapsed: start_time.elapsed(),
+                    score_cp,
+                });
+            }
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send_command("quit");
+        let _ = self.process.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info_score_cp() {
+        assert_eq!(
+            parse_info_score_cp("info depth 12 seldepth 18 score cp -34 nodes 1000 pv e2e4"),
+            Some(-34)
+        );
+        assert_eq!(
+            parse_info_score_cp("info depth 20 score mate 3 pv h5f7"),
+            None
+        );
+        assert_eq!(parse_info_score_cp("bestmove e2e4 ponder e7e5"), None);
+    }
+
+    #[test]
+    fn test_parse_spin_option_range() {
+        let options = vec![
+            "option name Threads type spin default 1 min 1 max 1024".to_string(),
+            "option name UCI_Elo type spin default 1320 min 1320 max 3190".to_string(),
+            "option name UCI_LimitStrength type check default false".to_string(),
+        ];
+        assert_eq!(
+            parse_spin_option_range(&options, "UCI_Elo"),
+            Some((1320, 3190))
+        );
+        assert_eq!(parse_spin_option_range(&options, "UCI_LimitStrength"), None);
+        assert_eq!(parse_spin_option_range(&options, "Hash"), None);
+    }
+
+    #[test]
+    fn test_engine_handle_parsing() {
+        let handle: UciEngineHandle =
+            "/usr/bin/lc0 --backend=cpu option.Threads=4 option.UCI_Elo=1500"
+                .parse()
+                .unwrap();
+        assert_eq!(handle.path, "/usr/bin/lc0");
+        assert_eq!(handle.args, vec!["--backend=cpu".to_string()]);
+        assert_eq!(
+            handle.options,
+            vec![
+                ("Threads".to_string(), "4".to_string()),
+                ("UCI_Elo".to_string(), "1500".to_string()),
+            ]
+        );
+        assert_eq!(
+            handle.to_string(),
+            "/usr/bin/lc0 --backend=cpu option.Threads=4 option.UCI_Elo=1500"
+        );
+        assert!("".parse::<UciEngineHandle>().is_err());
+        assert!("stockfish option.Threads"
+            .parse::<UciEngineHandle>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_engine_handle_settings_replace_options() {
+        let handle: UciEngineHandle = "stockfish option.Threads=2".parse().unwrap();
+        let handle = handle.with_threads(8).with_hash(256).with_elo_limit(1500);
+        assert_eq!(
+            handle.options,
+            vec![
+                ("Threads".to_string(), "8".to_string()),
+                ("Hash".to_string(), "256".to_string()),
+                ("UCI_LimitStrength".to_string(), "true".to_string()),
+                ("UCI_Elo".to_string(), "1500".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_engine_is_reported_as_not_found() {
+        let handle = UciEngineHandle::new("/nonexistent/uci-engine");
+        assert!(matches!(
+            handle.spawn(),
+            Err(UciEngineError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_go_limits_command() {
+        assert_eq!(GoLimits::MoveTime(500).to_command(), "go movetime 500");
+        assert_eq!(
+            GoLimits::Clock {
+                wtime: 60000,
+                btime: 59000,
+                winc: 1000,
+                binc: 1000,
+            }
+            .to_command(),
+            "go wtime 60000 btime 59000 winc 1000 binc 1000"
+        );
+    }
+}
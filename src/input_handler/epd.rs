@@ -0,0 +1,219 @@
+//! EPD (Extended Position Description) records, as used by test suites.
+//!
+//! An EPD line holds the first four FEN fields followed by operations, each an
+//! opcode and its operands ended by a semicolon:
+//!
+//! ```text
+//! 2rr3k/pp3pp1/1nnrp1p1/3pN3/2pP4/2P3P1/PPB1PP1P/3RR1K1 w - - bm Qg6; id "WAC.001";
+//! ```
+//!
+//! Operands are separated by spaces and may be quoted strings. The move
+//! counters come from the `hmvc` and `fmvn` opcodes when given, or from two
+//! numbers after the four fields as in a full FEN.
+
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::board::Board;
+
+use super::fen::FenParseError;
+
+#[derive(Error, Debug)]
+pub enum EpdParseError {
+    #[error("expected a position of four FEN fields")]
+    MissingFields,
+    #[error("unterminated string in operation `{opcode}`")]
+    UnterminatedString { opcode: String },
+    #[error("invalid position: {0}")]
+    InvalidFen(#[from] FenParseError),
+}
+
+/// An opcode and its operands, with the quotes taken off quoted strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdOperation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdRecord {
+    /// The position as a full six-field FEN.
+    pub fen: String,
+    pub operations: Vec<EpdOperation>,
+}
+
+impl EpdRecord {
+    /// The operands of the first `opcode` operation, if there is one.
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|operation| operation.opcode == opcode)
+            .map(|operation| operation.operands.as_slice())
+    }
+
+    /// The position's name from its `id` operation.
+    pub fn id(&self) -> Option<&str> {
+        self.operands("id")?.first().map(String::as_str)
+    }
+
+    pub fn board(&self) -> Result<Board, EpdParseError> {
+        Ok(Board::from_str(&self.fen)?)
+    }
+}
+
+impl FromStr for EpdRecord {
+    type Err = EpdParseError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut rest = line.trim();
+        let mut fields = Vec::with_capacity(4);
+        while fields.len() < 4 {
+            let (field, after) = next_token(rest).ok_or(EpdParseError::MissingFields)?;
+            fields.push(field);
+            rest = after;
+        }
+
+        // Move counters written as in a FEN rather than as opcodes
+        let mut counters = None;
+        if let Some((halfmove, after_halfmove)) = next_token(rest) {
+            if let Some((fullmove, after_fullmove)) = next_token(after_halfmove) {
+                if halfmove.parse::<u32>().is_ok() && fullmove.parse::<u32>().is_ok() {
+                    counters = Some((halfmove.to_string(), fullmove.to_string()));
+                    rest = after_fullmove;
+                }
+            }
+        }
+
+        let operations = parse_operations(rest)?;
+        let record_counter = |opcode: &str, default: &str| {
+            operations
+                .iter()
+                .find(|operation| operation.opcode == opcode)
+                .and_then(|operation| operation.operands.first().cloned())
+                .unwrap_or_else(|| default.to_string())
+        };
+        let (halfmove, fullmove) =
+            counters.unwrap_or_else(|| (record_counter("hmvc", "0"), record_counter("fmvn", "1")));
+        let fen = format!("{} {} {}", fields.join(" "), halfmove, fullmove);
+        Board::from_str(&fen)?;
+
+        Ok(Self { fen, operations })
+    }
+}
+
+/// The next space-separated token and the text after it.
+fn next_token(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if text.is_empty() {
+        return None;
+    }
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    Some((&text[..end], &text[end..]))
+}
+
+fn parse_operations(text: &str) -> Result<Vec<EpdOperation>, EpdParseError> {
+    let mut operations = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ';' => {
+                chars.next();
+                if let Some(operation) = operation_from_words(&mut words) {
+                    operations.push(operation);
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => string.push(c),
+                        None => {
+                            return Err(EpdParseError::UnterminatedString {
+                                opcode: words.first().cloned().unwrap_or_default(),
+                            })
+                        }
+                    }
+                }
+                words.push(string);
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == ';' || c == '"' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                words.push(word);
+            }
+        }
+    }
+    // The last operation's semicolon is sometimes left off
+    if let Some(operation) = operation_from_words(&mut words) {
+        operations.push(operation);
+    }
+    Ok(operations)
+}
+
+fn operation_from_words(words: &mut Vec<String>) -> Option<EpdOperation> {
+    if words.is_empty() {
+        return None;
+    }
+    let mut words = std::mem::take(words).into_iter();
+    Some(EpdOperation {
+        opcode: words.next()?,
+        operands: words.collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_and_quoted_operands_parse() {
+        let record: EpdRecord = "2rr3k/pp3pp1/1nnrp1p1/3pN3/2pP4/2P3P1/PPB1PP1P/3RR1K1 w - - bm Qg6 Rxe8+; am Nf3; id \"WAC.001; first\"; c0 \"a; b\""
+            .parse()
+            .unwrap();
+        assert_eq!(
+            record.fen,
+            "2rr3k/pp3pp1/1nnrp1p1/3pN3/2pP4/2P3P1/PPB1PP1P/3RR1K1 w - - 0 1"
+        );
+        assert_eq!(
+            record.operands("bm"),
+            Some(&["Qg6".to_string(), "Rxe8+".to_string()][..])
+        );
+        assert_eq!(record.operands("am"), Some(&["Nf3".to_string()][..]));
+        assert_eq!(record.id(), Some("WAC.001; first"));
+        assert_eq!(record.operands("c0"), Some(&["a; b".to_string()][..]));
+        assert_eq!(record.operands("pv"), None);
+    }
+
+    #[test]
+    fn test_move_counters_come_from_opcodes_or_fen_fields() {
+        let record: EpdRecord = "4k3/8/8/8/8/8/8/4K2R w K - hmvc 12; fmvn 40;"
+            .parse()
+            .unwrap();
+        assert_eq!(record.fen, "4k3/8/8/8/8/8/8/4K2R w K - 12 40");
+        let record: EpdRecord = "4k3/8/8/8/8/8/8/4K2R w K - 3 17 bm O-O".parse().unwrap();
+        assert_eq!(record.fen, "4k3/8/8/8/8/8/8/4K2R w K - 3 17");
+        assert_eq!(record.operands("bm"), Some(&["O-O".to_string()][..]));
+
+        assert!(matches!(
+            "4k3/8/8/8 w".parse::<EpdRecord>(),
+            Err(EpdParseError::MissingFields)
+        ));
+        assert!(matches!(
+            "4k3/8/8/8/8/8/8/4K2R w K - id \"open".parse::<EpdRecord>(),
+            Err(EpdParseError::UnterminatedString { .. })
+        ));
+    }
+}
@@ -1,8 +1,8 @@
 use crate::board::{
     castle_rights::CastleRights, color::Color, error::BoardError, fullmove_number::FullmoveNumber,
-    halfmove_clock::HalfmoveClock, piece::Piece, Board,
+    halfmove_clock::HalfmoveClock, piece::Piece, variant::CHECKS_TO_WIN, Board, Variant,
 };
-use common::bitboard::Square;
+use common::bitboard::{Bitboard, Square, A1, A8, E1, E8, H1, H8};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -29,6 +29,22 @@ pub enum FenParseError {
     InvalidHalfmoveClock { invalid_clock: String },
     #[error("Invalid fullmove number: {invalid_number:?}")]
     InvalidFullmoveNumber { invalid_number: String },
+    #[error("Invalid remaining checks: {invalid_checks:?}")]
+    InvalidRemainingChecks { invalid_checks: String },
+    #[error("Invalid piece placement: {color} has {count} kings, 1 expected")]
+    InvalidKingCount { color: Color, count: u32 },
+    #[error("Invalid piece placement: pawn on the back rank at {square}")]
+    PawnOnBackRank { square: String },
+    #[error("Invalid active color: {color} is to move while the other king is in check")]
+    OpponentInCheck { color: Color },
+    #[error(
+        "Invalid castling rights: {right:?} needs the king on {king_square} and a rook on {rook_square}"
+    )]
+    InconsistentCastlingRights {
+        right: char,
+        king_square: String,
+        rook_square: String,
+    },
 }
 
 type FenResult<T> = Result<T, FenParseError>;
@@ -37,7 +53,13 @@ pub const STARTING_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ
 
 /// Parses a FEN (Forsyth–Edwards Notation) string into a Board.
 /// FEN string contains 6 fields: piece placement, active color, castling rights,
-/// en passant target square, halfmove clock, and fullmove number.
+/// en passant target square, halfmove clock, and fullmove number. A three-check
+/// position has a 7th after the en passant square with the checks each side
+/// has left to give, e.g. `3+2`, and is played as three-check.
+///
+/// The position must also be one a game could reach: one king per side, no
+/// pawns on the first or last rank, the side not to move not in check, and
+/// castling rights only where the king and rook are still on their squares.
 pub fn parse_fen(fen: &str) -> FenResult<Board> {
     let fields = split_fen_fields(fen)?;
     let mut board = Board::new();
@@ -48,32 +70,90 @@ pub fn parse_fen(fen: &str) -> FenResult<Board> {
     parse_en_passant(&mut board, fields.en_passant)?;
     parse_halfmove_clock(&mut board, fields.halfmove_clock)?;
     parse_fullmove_number(&mut board, fields.fullmove_number)?;
+    if let Some(remaining_checks) = fields.remaining_checks {
+        parse_remaining_checks(&mut board, remaining_checks)?;
+    }
+    validate_position(&board)?;
 
     Ok(board)
 }
 
-/// Represents the six fields in a FEN string
+/// Checks that the parsed position is structurally legal
+fn validate_position(board: &Board) -> FenResult<()> {
+    for color in [Color::White, Color::Black] {
+        let count = board.pieces(color).locate(Piece::King).count_ones();
+        if count != 1 {
+            return Err(FenParseError::InvalidKingCount { color, count });
+        }
+    }
+
+    let back_ranks = Bitboard::RANK_1 | Bitboard::RANK_8;
+    let pawns = board.pieces(Color::White).locate(Piece::Pawn)
+        | board.pieces(Color::Black).locate(Piece::Pawn);
+    let mut misplaced = pawns & back_ranks;
+    if !misplaced.is_empty() {
+        return Err(FenParseError::PawnOnBackRank {
+            square: misplaced.pop_lsb_as_square().to_algebraic().to_string(),
+        });
+    }
+
+    let castling = [
+        ('K', CastleRights::white_kingside(), Color::White, E1, H1),
+        ('Q', CastleRights::white_queenside(), Color::White, E1, A1),
+        ('k', CastleRights::black_kingside(), Color::Black, E8, H8),
+        ('q', CastleRights::black_queenside(), Color::Black, E8, A8),
+    ];
+    let rights = board.peek_castle_rights();
+    for (right, castle_right, color, king_square, rook_square) in castling {
+        if rights.contains(castle_right)
+            && (board.get(king_square) != Some((Piece::King, color))
+                || board.get(rook_square) != Some((Piece::Rook, color)))
+        {
+            return Err(FenParseError::InconsistentCastlingRights {
+                right,
+                king_square: king_square.to_algebraic().to_string(),
+                rook_square: rook_square.to_algebraic().to_string(),
+            });
+        }
+    }
+
+    let waiting_king = board.pieces(board.turn().opposite()).locate(Piece::King);
+    if waiting_king.overlaps(board.attacks(board.turn())) {
+        return Err(FenParseError::OpponentInCheck {
+            color: board.turn(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Represents the six fields in a FEN string, and the three-check one
 struct FenFields<'a> {
     position: &'a str,
     active_color: &'a str,
     castle_rights: &'a str,
     en_passant: &'a str,
+    remaining_checks: Option<&'a str>,
     halfmove_clock: &'a str,
     fullmove_number: &'a str,
 }
 
-/// Splits a FEN string into its six component fields
+/// Splits a FEN string into its six component fields, or seven for
+/// three-check
 fn split_fen_fields(fen: &str) -> FenResult<FenFields<'_>> {
-    let parts: Vec<&str> = fen.split_whitespace().collect();
-    if parts.len() != 6 {
-        return Err(FenParseError::WrongNumberOfFields);
-    }
+    let mut parts: Vec<&str> = fen.split_whitespace().collect();
+    let remaining_checks = match parts.len() {
+        6 => None,
+        7 => Some(parts.remove(4)),
+        _ => return Err(FenParseError::WrongNumberOfFields),
+    };
 
     Ok(FenFields {
         position: parts[0],
         active_color: parts[1],
         castle_rights: parts[2],
         en_passant: parts[3],
+        remaining_checks,
         halfmove_clock: parts[4],
         fullmove_number: parts[5],
     })
@@ -152,26 +232,11 @@ fn parse_active_color(board: &mut Board, active_color: &str) -> FenResult<()> {
 
 /// Parses the castling rights field
 fn parse_castle_rights(board: &mut Board, castle_rights: &str) -> FenResult<()> {
-    if castle_rights == "-" {
-        board.lose_castle_rights(CastleRights::all());
-        return Ok(());
-    }
-
-    let mut rights = CastleRights::none();
-    for c in castle_rights.chars() {
-        rights = rights
-            | match c {
-                'K' => CastleRights::white_kingside(),
-                'Q' => CastleRights::white_queenside(),
-                'k' => CastleRights::black_kingside(),
-                'q' => CastleRights::black_queenside(),
-                _ => {
-                    return Err(FenParseError::InvalidCastlingRights {
-                        invalid_castling: c,
-                    })
-                }
-            };
-    }
+    let rights = castle_rights.parse::<CastleRights>().map_err(|c| {
+        FenParseError::InvalidCastlingRights {
+            invalid_castling: c,
+        }
+    })?;
     board.lose_castle_rights(!rights);
     Ok(())
 }
@@ -237,7 +302,7 @@ fn parse_halfmove_clock(board: &mut Board, halfmove_clock: &str) -> FenResult<()
 fn parse_fullmove_number(board: &mut Board, fullmove_number: &str) -> FenResult<()> {
     let fullmove =
         fullmove_number
-            .parse::<u8>()
+            .parse::<u16>()
             .map_err(|_| FenParseError::InvalidFullmoveNumber {
                 invalid_number: fullmove_number.to_string(),
             })?;
@@ -245,10 +310,32 @@ fn parse_fullmove_number(board: &mut Board, fullmove_number: &str) -> FenResult<
     Ok(())
 }
 
+/// Parses the three-check field, White's remaining checks then Black's, and
+/// makes the position a three-check one
+fn parse_remaining_checks(board: &mut Board, remaining_checks: &str) -> FenResult<()> {
+    let invalid = || FenParseError::InvalidRemainingChecks {
+        invalid_checks: remaining_checks.to_string(),
+    };
+    let (white, black) = remaining_checks.split_once('+').ok_or_else(invalid)?;
+    let given = |remaining: &str| {
+        remaining
+            .parse::<u8>()
+            .ok()
+            .filter(|&remaining| remaining <= CHECKS_TO_WIN)
+            .map(|remaining| CHECKS_TO_WIN - remaining)
+            .ok_or_else(invalid)
+    };
+    let mut checks = [0; 2];
+    checks[Color::White as usize] = given(white)?;
+    checks[Color::Black as usize] = given(black)?;
+    board.set_variant(Variant::ThreeCheck);
+    board.push_checks_given(checks);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use common::bitboard::bitboard::Bitboard;
 
     #[test]
     fn test_parse_starting_position() {
@@ -293,13 +380,63 @@ mod tests {
 
         // Test invalid castling rights
         assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w XYZx - 0 1").is_err());
+
+        // Test invalid remaining checks
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 4+3 0 1").is_err());
+        assert!(parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 33 0 1").is_err());
     }
 
     #[test]
     fn test_empty_squares() {
-        let fen = "8/8/8/8/8/8/8/8 w - - 0 1";
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
         let board = parse_fen(fen).unwrap();
-        assert_eq!(board.occupied(), Bitboard::EMPTY);
+        assert_eq!(board.occupied().count_ones(), 2);
+    }
+
+    #[test]
+    fn test_illegal_positions_are_rejected() {
+        assert!(matches!(
+            parse_fen("8/8/8/8/8/8/8/8 w - - 0 1"),
+            Err(FenParseError::InvalidKingCount {
+                color: Color::White,
+                count: 0
+            })
+        ));
+        assert!(matches!(
+            parse_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+            Err(FenParseError::InvalidKingCount {
+                color: Color::White,
+                count: 2
+            })
+        ));
+        assert!(matches!(
+            parse_fen("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Err(FenParseError::PawnOnBackRank { square }) if square == "h8"
+        ));
+        // The king to move may be in check, the other one may not, nor may
+        // the kings stand side by side
+        assert!(parse_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").is_ok());
+        assert!(matches!(
+            parse_fen("8/8/8/8/8/8/k7/K7 w - - 0 1"),
+            Err(FenParseError::OpponentInCheck { .. })
+        ));
+        assert!(matches!(
+            parse_fen("4k3/8/8/8/8/8/8/4K2r b - - 0 1"),
+            Err(FenParseError::OpponentInCheck {
+                color: Color::Black
+            })
+        ));
+        let Err(error) = parse_fen("r3k2r/8/8/8/8/8/8/R3K1R1 w KQkq - 0 1") else {
+            panic!("castling without a rook on h1 was accepted");
+        };
+        assert!(matches!(
+            error,
+            FenParseError::InconsistentCastlingRights { right: 'K', .. }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Invalid castling rights: 'K' needs the king on e1 and a rook on h1"
+        );
     }
 
     #[test]
@@ -1,6 +1,6 @@
 //! FEN serialization - converts Board to FEN string.
 
-use crate::board::Board;
+use crate::board::{Board, Color, Variant};
 use common::bitboard::Square;
 
 /// Converts a Board to FEN (Forsyth–Edwards Notation) string.
@@ -33,8 +33,8 @@ pub fn to_fen(board: &Board) -> String {
     // 2. Active color
     fen.push(' ');
     fen.push(match board.turn() {
-        crate::board::color::Color::White => 'w',
-        crate::board::color::Color::Black => 'b',
+        Color::White => 'w',
+        Color::Black => 'b',
     });
 
     // 3. Castling rights
@@ -66,6 +66,17 @@ pub fn to_fen(board: &Board) -> String {
         fen.push('-');
     }
 
+    // Three-check: the checks each side has left to give
+    if board.variant() == Variant::ThreeCheck {
+        use crate::board::variant::CHECKS_TO_WIN;
+        fen.push(' ');
+        fen.push_str(&format!(
+            "{}+{}",
+            CHECKS_TO_WIN.saturating_sub(board.checks_given(Color::White)),
+            CHECKS_TO_WIN.saturating_sub(board.checks_given(Color::Black))
+        ));
+    }
+
     // 5. Halfmove clock
     fen.push(' ');
     fen.push_str(&board.halfmove_clock().value().to_string());
@@ -80,6 +91,7 @@ pub fn to_fen(board: &Board) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::engine::{Engine, EngineConfig};
     use crate::input_handler::fen::STARTING_POSITION_FEN;
 
     #[test]
@@ -105,6 +117,46 @@ mod tests {
         assert_eq!(serialized, fen);
     }
 
+    #[test]
+    fn test_three_check_roundtrip() {
+        let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5Q2/PPPP1PPP/RNB1KBNR b KQkq - 2+3 0 3";
+        let board: Board = fen.parse().unwrap();
+        assert_eq!(board.variant(), Variant::ThreeCheck);
+        assert_eq!(board.checks_given(Color::White), 1);
+        assert_eq!(to_fen(&board), fen);
+
+        // The checks given are part of the position's hash
+        let unchecked: Board = fen.replace("2+3", "3+3").parse().unwrap();
+        assert_ne!(
+            board.current_position_hash(),
+            unchecked.current_position_hash()
+        );
+    }
+
+    #[test]
+    fn test_fen_follows_the_moves_played() {
+        let mut engine = Engine::with_config(EngineConfig::default());
+        let mut fens = Vec::new();
+        for uci in ["e2e4", "c7c5", "g1f3", "d8c7", "e1e2"] {
+            engine.make_move_uci(uci).unwrap();
+            engine.board_mut().toggle_turn();
+            fens.push(to_fen(engine.board()));
+        }
+        assert_eq!(
+            fens,
+            [
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+                "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+                "rnb1kbnr/ppqppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+                "rnb1kbnr/ppqppppp/8/2p5/4P3/5N2/PPPPKPPP/RNBQ1B1R b kq - 3 3",
+            ]
+        );
+        for fen in fens {
+            assert_eq!(to_fen(&fen.parse().unwrap()), fen);
+        }
+    }
+
     #[test]
     fn test_no_castle_rights() {
         let fen = "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1";
@@ -2,15 +2,19 @@
 
 use std::str::FromStr;
 
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseEvent, MouseEventKind};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use thiserror::Error;
 
 use crate::game::action::GameMode;
+use crate::game::game_file::GameFileAction;
 
 static COORD_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new("^([a-h][1-8])([a-h][1-8])$").expect("COORD_RE regex should be valid"));
+static PROMOTION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new("^[a-h][1-8][a-h][1-8][nbrq]$").expect("PROMOTION_RE regex should be valid")
+});
 static ALG_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new("^([NBRQK]?[a-h]?[1-8]?x?[a-h][1-8](=[NBRQ])?[+#]?|O-O(-O)?)$")
         .expect("ALG_RE regex should be valid")
@@ -28,16 +32,118 @@ pub enum InputError {
 
 #[derive(Debug)]
 pub enum MoveInput {
-    Coordinate { from: String, to: String },
-    Algebraic { notation: String },
+    Coordinate {
+        from: String,
+        to: String,
+    },
+    Algebraic {
+        notation: String,
+    },
+    /// A move in UCI notation, e.g. from an external engine.
+    Uci {
+        uci_move: String,
+    },
     UseEngine,
+    /// Take back the last full move pair.
+    Undo,
+    /// Give up the game for the side to move.
+    Resign,
+    /// Leave the line branched off a replayed game and return to the game's
+    /// own moves.
+    MainLine,
+    /// Suggest a move, with the principal variation and score if `show_line` is set.
+    Hint {
+        show_line: bool,
+    },
+    /// Save the game so far to a file: PGN, or the native format for a
+    /// `.json` path.
+    SaveGame {
+        path: String,
+    },
+    /// Replace the game with one saved to a file.
+    LoadGame {
+        path: String,
+    },
+    /// Pick a file to save the game to or load one from in the renderer.
+    ChooseGameFile {
+        action: GameFileAction,
+    },
+    /// Browse the moves played so far without making a move.
+    Browse {
+        navigation: Navigation,
+    },
+    /// Turn the board around.
+    FlipBoard,
+    /// Show or hide the live analysis of the position on the board.
+    ToggleAnalysis,
+    /// Show the hidden pieces of a blindfold game until the next move, or
+    /// hide them again.
+    TogglePieces,
+    /// Open the display settings, such as the theme.
+    OpenSettings,
+    /// The terminal was resized, so the screen has to be redrawn.
+    Resize,
+    /// A mouse button pressed or released, for the renderer to map onto the
+    /// board.
+    Mouse {
+        event: MouseEvent,
+    },
+}
+
+/// Keys that move through the move list: the arrows step one move, PgUp and
+/// PgDn a page, Home jumps to the starting position and End back to the
+/// current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    PreviousMove,
+    NextMove,
+    PageUp,
+    PageDown,
+    Start,
+    Latest,
+}
+
+impl Navigation {
+    pub fn from_key(code: KeyCode) -> Option<Self> {
+        match code {
+            KeyCode::Up => Some(Self::PreviousMove),
+            KeyCode::Down => Some(Self::NextMove),
+            KeyCode::PageUp => Some(Self::PageUp),
+            KeyCode::PageDown => Some(Self::PageDown),
+            KeyCode::Home => Some(Self::Start),
+            KeyCode::End => Some(Self::Latest),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum MenuInput {
     StartOver,
+    /// Play again with the colors swapped.
+    Rematch,
     Exit,
-    SwitchGameMode { target: GameMode },
+    SwitchGameMode {
+        target: GameMode,
+    },
+    /// Browse the moves of the finished game.
+    Browse {
+        navigation: Navigation,
+    },
+    /// Go over the finished game from its start with a live analysis.
+    Analyze,
+    /// Turn the board around.
+    FlipBoard,
+    /// Show or hide the live analysis of the position on the board.
+    ToggleAnalysis,
+    /// Open the display settings, such as the theme.
+    OpenSettings,
+    /// Pick a file to save the game to or load one from in the renderer.
+    ChooseGameFile {
+        action: GameFileAction,
+    },
+    /// The terminal was resized, so the screen has to be redrawn.
+    Resize,
 }
 
 impl MenuInput {
@@ -68,6 +174,8 @@ impl FromStr for MenuInput {
 
         match trimmed.as_str() {
             "1" => Ok(MenuInput::StartOver),
+            "r" => Ok(MenuInput::Rematch),
+            "a" => Ok(MenuInput::Analyze),
             "q" => Ok(MenuInput::Exit),
             "2" => Ok(MenuInput::switch_to_play()),
             "3" => Ok(MenuInput::switch_to_watch()),
@@ -83,6 +191,42 @@ impl FromStr for MoveInput {
     type Err = InputError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.eq_ignore_ascii_case("undo") {
+            return Ok(MoveInput::Undo);
+        }
+        if input.eq_ignore_ascii_case("resign") {
+            return Ok(MoveInput::Resign);
+        }
+        if input.eq_ignore_ascii_case("mainline") {
+            return Ok(MoveInput::MainLine);
+        }
+
+        match input
+            .to_ascii_lowercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()[..]
+        {
+            ["hint"] => return Ok(MoveInput::Hint { show_line: false }),
+            ["hint", "pv"] => return Ok(MoveInput::Hint { show_line: true }),
+            _ => {}
+        }
+
+        if let Some(path) = input.strip_prefix("save ").map(str::trim) {
+            if !path.is_empty() {
+                return Ok(MoveInput::SaveGame {
+                    path: path.to_string(),
+                });
+            }
+        }
+
+        if let Some(path) = input.strip_prefix("load ").map(str::trim) {
+            if !path.is_empty() {
+                return Ok(MoveInput::LoadGame {
+                    path: path.to_string(),
+                });
+            }
+        }
+
         if let Some(caps) = COORD_RE.captures(input) {
             return Ok(MoveInput::Coordinate {
                 from: caps[1].to_string(),
@@ -90,6 +234,13 @@ impl FromStr for MoveInput {
             });
         }
 
+        // Coordinates naming the piece a pawn promotes to, e.g. e7e8n
+        if PROMOTION_RE.is_match(input) {
+            return Ok(MoveInput::Uci {
+                uci_move: input.to_string(),
+            });
+        }
+
         if let Some(caps) = ALG_RE.captures(input) {
             return Ok(MoveInput::Algebraic {
                 notation: caps[1].to_string(),
@@ -102,8 +253,12 @@ impl FromStr for MoveInput {
     }
 }
 
-/// Parse chess move input (coordinates, algebraic notation, or "use engine")
-/// Used during gameplay when entering moves
+/// Parse chess move input (coordinates, algebraic notation, or the "undo", "resign",
+/// "mainline", "hint", "save <path>" and "load <path>" commands)
+/// Used during gameplay when entering moves. Navigation keys pressed before
+/// anything is typed browse the move list, Tab flips the board, `u` takes
+/// back the last move pair as `undo` does, and mouse clicks are passed on to
+/// pick moves on the board.
 pub fn parse_move_input() -> Result<MoveInput, InputError> {
     use std::io::Write;
 
@@ -113,9 +268,19 @@ pub fn parse_move_input() -> Result<MoveInput, InputError> {
         if event::poll(std::time::Duration::from_millis(100)).map_err(|e| InputError::IOError {
             error: format!("Failed to poll event: {}", e),
         })? {
-            if let Event::Key(key_event) = event::read().map_err(|e| InputError::IOError {
+            let event = event::read().map_err(|e| InputError::IOError {
                 error: format!("Failed to read event: {}", e),
-            })? {
+            })?;
+            // Anything typed so far is dropped with the redrawn prompt
+            if let Event::Resize(..) = event {
+                return Ok(MoveInput::Resize);
+            }
+            if let Event::Mouse(mouse_event) = event {
+                if is_button_event(&mouse_event) && input.is_empty() {
+                    return Ok(MoveInput::Mouse { event: mouse_event });
+                }
+            }
+            if let Event::Key(key_event) = event {
                 // Handle Ctrl-C
                 if key_event.code == KeyCode::Char('c')
                     && key_event.modifiers.contains(KeyModifiers::CONTROL)
@@ -123,12 +288,35 @@ pub fn parse_move_input() -> Result<MoveInput, InputError> {
                     return Err(InputError::UserExit);
                 }
 
+                if let Some(navigation) = Navigation::from_key(key_event.code) {
+                    if input.is_empty() {
+                        return Ok(MoveInput::Browse { navigation });
+                    }
+                }
+                if key_event.code == KeyCode::Tab && input.is_empty() {
+                    return Ok(MoveInput::FlipBoard);
+                }
+                // No move or other command starts with `u`
+                if key_event.code == KeyCode::Char('u') && input.is_empty() {
+                    return Ok(MoveInput::Undo);
+                }
+                if key_event.code == KeyCode::F(2) {
+                    return Ok(MoveInput::ToggleAnalysis);
+                }
+                if key_event.code == KeyCode::F(3) {
+                    return Ok(MoveInput::OpenSettings);
+                }
+                if key_event.code == KeyCode::F(4) {
+                    return Ok(MoveInput::TogglePieces);
+                }
+                if let Some(action) = game_file_action(key_event.code) {
+                    return Ok(MoveInput::ChooseGameFile { action });
+                }
+
                 match key_event.code {
-                    KeyCode::Enter => {
-                        if !input.is_empty() {
-                            println!(); // Move to next line after input
-                            break;
-                        }
+                    KeyCode::Enter if !input.is_empty() => {
+                        println!(); // Move to next line after input
+                        break;
                     }
                     KeyCode::Char(c) => {
                         input.push(c);
@@ -137,14 +325,12 @@ pub fn parse_move_input() -> Result<MoveInput, InputError> {
                             error: format!("Failed to flush stdout: {}", e),
                         })?;
                     }
-                    KeyCode::Backspace => {
-                        if !input.is_empty() {
-                            input.pop();
-                            print!("\x08 \x08"); // Erase character: backspace, space, backspace
-                            std::io::stdout().flush().map_err(|e| InputError::IOError {
-                                error: format!("Failed to flush stdout: {}", e),
-                            })?;
-                        }
+                    KeyCode::Backspace if !input.is_empty() => {
+                        input.pop();
+                        print!("\x08 \x08"); // Erase character: backspace, space, backspace
+                        std::io::stdout().flush().map_err(|e| InputError::IOError {
+                            error: format!("Failed to flush stdout: {}", e),
+                        })?;
                     }
                     _ => {}
                 }
@@ -155,6 +341,24 @@ pub fn parse_move_input() -> Result<MoveInput, InputError> {
     input.trim().parse()
 }
 
+/// F5 saves the game and F6 loads one, through the renderer's file dialog.
+fn game_file_action(code: KeyCode) -> Option<GameFileAction> {
+    match code {
+        KeyCode::F(5) => Some(GameFileAction::Save),
+        KeyCode::F(6) => Some(GameFileAction::Load),
+        _ => None,
+    }
+}
+
+/// Presses and releases; motion is left out so that dragging a piece does not
+/// flood the game loop.
+fn is_button_event(mouse_event: &MouseEvent) -> bool {
+    matches!(
+        mouse_event.kind,
+        MouseEventKind::Down(_) | MouseEventKind::Up(_)
+    )
+}
+
 /// Parse menu input commands during game end
 /// Handles immediate key presses for game control commands
 pub fn parse_menu_input() -> Result<MenuInput, InputError> {
@@ -162,9 +366,13 @@ pub fn parse_menu_input() -> Result<MenuInput, InputError> {
         if event::poll(std::time::Duration::from_millis(100)).map_err(|e| InputError::IOError {
             error: format!("Failed to poll event: {}", e),
         })? {
-            if let Event::Key(key_event) = event::read().map_err(|e| InputError::IOError {
+            let event = event::read().map_err(|e| InputError::IOError {
                 error: format!("Failed to read event: {}", e),
-            })? {
+            })?;
+            if let Event::Resize(..) = event {
+                return Ok(MenuInput::Resize);
+            }
+            if let Event::Key(key_event) = event {
                 // Handle Ctrl-C
                 if key_event.code == KeyCode::Char('c')
                     && key_event.modifiers.contains(KeyModifiers::CONTROL)
@@ -174,11 +382,28 @@ pub fn parse_menu_input() -> Result<MenuInput, InputError> {
 
                 match key_event.code {
                     KeyCode::Char('1') => return Ok(MenuInput::StartOver),
+                    KeyCode::Char('r') => return Ok(MenuInput::Rematch),
+                    KeyCode::Char('a') => return Ok(MenuInput::Analyze),
+                    KeyCode::Char('s') => {
+                        return Ok(MenuInput::ChooseGameFile {
+                            action: GameFileAction::Save,
+                        })
+                    }
                     KeyCode::Char('q') => return Ok(MenuInput::Exit),
                     KeyCode::Char('2') => return Ok(MenuInput::switch_to_play()),
                     KeyCode::Char('3') => return Ok(MenuInput::switch_to_watch()),
                     KeyCode::Char('4') => return Ok(MenuInput::switch_to_pvp()),
-                    _ => {} // Ignore other keys
+                    KeyCode::Tab => return Ok(MenuInput::FlipBoard),
+                    KeyCode::F(2) => return Ok(MenuInput::ToggleAnalysis),
+                    KeyCode::F(3) => return Ok(MenuInput::OpenSettings),
+                    code => {
+                        if let Some(action) = game_file_action(code) {
+                            return Ok(MenuInput::ChooseGameFile { action });
+                        }
+                        if let Some(navigation) = Navigation::from_key(code) {
+                            return Ok(MenuInput::Browse { navigation });
+                        }
+                    }
                 }
             }
         }
@@ -1,7 +1,12 @@
 //! Input parsing and handling for chess moves and positions.
 
+pub mod epd;
 pub mod fen;
 pub mod fen_serialize;
+#[cfg(feature = "native")]
 pub mod input;
+#[cfg(feature = "native")]
+pub mod pgn;
 
-pub use input::{parse_menu_input, parse_move_input, InputError, MenuInput, MoveInput};
+#[cfg(feature = "native")]
+pub use input::{parse_menu_input, parse_move_input, InputError, MenuInput, MoveInput, Navigation};
@@ -0,0 +1,509 @@
+//! PGN import: whole games, variations included, replayed on a board.
+//!
+//! `Game::from_pgn` reads one game's tag pairs and movetext and resolves each
+//! SAN move against the legal moves of the position it is played in, so every
+//! move of the mainline and of its variations is a `ChessMove` that can be
+//! applied. Comments, NAGs (`$1`, or the `!` and `?` suffixes they stand for)
+//! and recursive variations are kept with the move they follow. Files holding
+//! several games are read with `parse_games`.
+//! See https://www.chessprogramming.org/Portable_Game_Notation
+
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use thiserror::Error;
+
+use crate::board::error::BoardError;
+use crate::board::{Board, Variant};
+use crate::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use crate::chess_move::chess_move::ChessMove;
+use crate::game::pgn::{parse_tag_pair, read_until, PgnParseError};
+use crate::input_handler::fen::{parse_fen, FenParseError};
+use crate::input_handler::fen_serialize::to_fen;
+use crate::move_generator::MoveGenerator;
+
+/// Move suffix annotations and the NAGs they abbreviate.
+const SUFFIX_ANNOTATIONS: [(&str, u8); 6] = [
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+    ("!", 1),
+    ("?", 2),
+];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+#[derive(Error, Debug)]
+pub enum PgnImportError {
+    #[error("No game found")]
+    NoGame,
+    #[error("Malformed tag pair: {text:?}")]
+    MalformedTag { text: String },
+    #[error("Invalid FEN tag {fen:?}: {error}")]
+    InvalidFen { fen: String, error: FenParseError },
+    #[error("Unterminated {what}")]
+    Unterminated { what: &'static str },
+    #[error("Unexpected {token:?} in movetext")]
+    UnexpectedToken { token: String },
+    #[error("Illegal move {san:?} in position {fen}")]
+    IllegalMove { san: String, fen: String },
+    #[error("Error applying {san:?}: {error}")]
+    ApplyMove { san: String, error: BoardError },
+}
+
+/// Tags and comments are read with `game::pgn`'s tokenizer, whose errors
+/// become the import's own.
+impl From<PgnParseError> for PgnImportError {
+    fn from(error: PgnParseError) -> Self {
+        match error {
+            PgnParseError::MalformedTag { text } => Self::MalformedTag { text },
+            PgnParseError::InvalidFen { fen, error } => Self::InvalidFen { fen, error },
+            PgnParseError::Unterminated { what } => Self::Unterminated { what },
+        }
+    }
+}
+
+/// A move of a game, with the annotations that follow it.
+#[derive(Debug, Clone)]
+pub struct GameMove {
+    pub chess_move: ChessMove,
+    /// The move in SAN, as this engine writes it.
+    pub san: String,
+    pub comment: Option<String>,
+    /// Numeric annotation glyphs, e.g. 1 for "good move" (`!`).
+    pub nags: Vec<u8>,
+    /// Lines played instead of this move, from the position before it.
+    pub variations: Vec<Vec<GameMove>>,
+}
+
+/// A game read from PGN, which can be replayed move by move.
+#[derive(Clone)]
+pub struct Game {
+    tags: Vec<(String, String)>,
+    starting_position: Board,
+    moves: Vec<GameMove>,
+    result: Option<String>,
+}
+
+impl Game {
+    /// Read the first game in `pgn`.
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnImportError> {
+        let mut tokens = tokenize(pgn)?.into_iter().peekable();
+        if tokens.peek().is_none() {
+            return Err(PgnImportError::NoGame);
+        }
+        parse_game(&mut tokens)
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The position the game starts from: the `FEN` tag if present, otherwise
+    /// the standard starting position, played by the `Variant` tag's rules.
+    pub fn starting_position(&self) -> &Board {
+        &self.starting_position
+    }
+
+    /// The mainline.
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// The result ending the movetext, or else the `Result` tag.
+    pub fn result(&self) -> Option<&str> {
+        self.result.as_deref().or_else(|| self.tag("Result"))
+    }
+
+    /// The position after the first `plies` moves of the mainline, or `None`
+    /// past its end.
+    pub fn position_after(&self, plies: usize) -> Option<Board> {
+        let moves = self.moves.get(..plies)?;
+        let mut board = self.starting_position.clone();
+        for game_move in moves {
+            game_move
+                .chess_move
+                .apply(&mut board)
+                .expect("imported moves are legal");
+            board.toggle_turn();
+        }
+        Some(board)
+    }
+
+    /// The position at the end of the mainline.
+    pub fn final_position(&self) -> Board {
+        self.position_after(self.moves.len())
+            .expect("the mainline ends after all of its moves")
+    }
+}
+
+/// Read every game in `pgn`.
+pub fn parse_games(pgn: &str) -> Result<Vec<Game>, PgnImportError> {
+    let mut tokens = tokenize(pgn)?.into_iter().peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        games.push(parse_game(&mut tokens)?);
+    }
+    Ok(games)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    StartVariation,
+    EndVariation,
+    Move(String),
+    Result(String),
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+fn parse_game(tokens: &mut Tokens) -> Result<Game, PgnImportError> {
+    let mut tags = Vec::new();
+    while let Some(Token::Tag(..)) = tokens.peek() {
+        if let Some(Token::Tag(tag, value)) = tokens.next() {
+            tags.push((tag, value));
+        }
+    }
+
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value): &(String, String)| value.clone())
+    };
+    let mut starting_position = match tag("FEN") {
+        Some(fen) => parse_fen(&fen).map_err(|error| PgnImportError::InvalidFen { fen, error })?,
+        None => Board::default(),
+    };
+    if let Some(variant) = tag("Variant").and_then(|v| v.parse::<Variant>().ok()) {
+        starting_position.set_variant(variant);
+    }
+
+    let move_generator = MoveGenerator::default();
+    let line = parse_line(tokens, starting_position.clone(), &move_generator, false)?;
+    Ok(Game {
+        tags,
+        starting_position,
+        moves: line.moves,
+        result: line.result,
+    })
+}
+
+struct Line {
+    moves: Vec<GameMove>,
+    result: Option<String>,
+}
+
+/// Read the moves of a line played from `board`, up to the end of its
+/// variation, or for the mainline up to the result or the next game's tags.
+fn parse_line(
+    tokens: &mut Tokens,
+    mut board: Board,
+    move_generator: &MoveGenerator,
+    is_variation: bool,
+) -> Result<Line, PgnImportError> {
+    let mut moves: Vec<GameMove> = Vec::new();
+    let mut position_before_last_move = None;
+    // A comment before the first move goes with it
+    let mut leading_comment: Option<String> = None;
+
+    loop {
+        let token = match tokens.peek() {
+            None if is_variation => return Err(PgnImportError::Unterminated { what: "variation" }),
+            None | Some(Token::Tag(..)) if !is_variation => break,
+            _ => tokens.next().expect("peeked"),
+        };
+
+        match token {
+            Token::Tag(tag, _) => {
+                return Err(PgnImportError::UnexpectedToken {
+                    token: format!("[{}", tag),
+                })
+            }
+            Token::Result(result) => {
+                if !is_variation {
+                    return Ok(Line {
+                        moves,
+                        result: Some(result),
+                    });
+                }
+            }
+            Token::Comment(comment) => match moves.last_mut() {
+                Some(last) => append_comment(&mut last.comment, comment),
+                None => append_comment(&mut leading_comment, comment),
+            },
+            Token::Nag(nag) => {
+                if let Some(last) = moves.last_mut() {
+                    last.nags.push(nag);
+                }
+            }
+            Token::StartVariation => {
+                let (Some(last), Some(position)) = (moves.last_mut(), &position_before_last_move)
+                else {
+                    return Err(PgnImportError::UnexpectedToken {
+                        token: "(".to_string(),
+                    });
+                };
+                let variation = parse_line(tokens, Board::clone(position), move_generator, true)?;
+                last.variations.push(variation.moves);
+            }
+            Token::EndVariation if is_variation => break,
+            Token::EndVariation => {
+                return Err(PgnImportError::UnexpectedToken {
+                    token: ")".to_string(),
+                })
+            }
+            Token::Move(san) => {
+                let (chess_move, san) = resolve_san(&mut board, &san, move_generator)?;
+                position_before_last_move = Some(board.clone());
+                chess_move
+                    .apply(&mut board)
+                    .map_err(|error| PgnImportError::ApplyMove {
+                        san: san.clone(),
+                        error,
+                    })?;
+                board.toggle_turn();
+                moves.push(GameMove {
+                    chess_move,
+                    san,
+                    comment: leading_comment.take(),
+                    nags: Vec::new(),
+                    variations: Vec::new(),
+                });
+            }
+        }
+    }
+
+    Ok(Line {
+        moves,
+        result: None,
+    })
+}
+
+fn append_comment(comment: &mut Option<String>, text: String) {
+    *comment = Some(match comment.take() {
+        Some(existing) => format!("{} {}", existing, text),
+        None => text,
+    });
+}
+
+/// The legal move `san` stands for, and its SAN as this engine writes it.
+/// Check suffixes are optional, castling may be written with zeros and
+/// promotions without `=`.
+fn resolve_san(
+    board: &mut Board,
+    san: &str,
+    move_generator: &MoveGenerator,
+) -> Result<(ChessMove, String), PgnImportError> {
+    let normalize = |san: &str| {
+        san.trim_end_matches(['+', '#'])
+            .replace('0', "O")
+            .replace('=', "")
+    };
+    let wanted = normalize(san);
+    let turn = board.turn();
+    enumerate_candidate_moves_with_algebraic_notation(board, turn, move_generator)
+        .into_iter()
+        .find(|(_, candidate)| normalize(candidate) == wanted)
+        .ok_or_else(|| PgnImportError::IllegalMove {
+            san: san.to_string(),
+            fen: to_fen(board),
+        })
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnImportError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let text = read_until(&mut chars, ']', "tag pair")?;
+                let (tag, value) = parse_tag_pair(&text)?;
+                tokens.push(Token::Tag(tag, value));
+            }
+            '{' => {
+                chars.next();
+                let comment = read_until(&mut chars, '}', "comment")?;
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                tokens.push(Token::Comment(comment));
+            }
+            ';' | '%' => {
+                // Comments and escaped lines run to the end of the line
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::StartVariation);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::EndVariation);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[]{}();".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                push_word_tokens(&mut tokens, &word)?;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Split a movetext word into its move number, move, NAGs or result.
+fn push_word_tokens(tokens: &mut Vec<Token>, word: &str) -> Result<(), PgnImportError> {
+    if RESULTS.contains(&word) {
+        tokens.push(Token::Result(word.to_string()));
+        return Ok(());
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        let nag = nag.parse().map_err(|_| PgnImportError::UnexpectedToken {
+            token: word.to_string(),
+        })?;
+        tokens.push(Token::Nag(nag));
+        return Ok(());
+    }
+
+    // Move numbers, e.g. "12." or "12...", may run into the move
+    let rest = if word.contains('.') {
+        word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+    } else if word.chars().all(|c| c.is_ascii_digit()) {
+        ""
+    } else {
+        word
+    };
+    let san = rest.trim_end_matches(['!', '?']);
+    if !san.is_empty() {
+        tokens.push(Token::Move(san.to_string()));
+    }
+    let suffix = &rest[san.len()..];
+    if !suffix.is_empty() {
+        let nag = SUFFIX_ANNOTATIONS
+            .iter()
+            .find(|(annotation, _)| *annotation == suffix)
+            .map(|&(_, nag)| nag)
+            .ok_or_else(|| PgnImportError::UnexpectedToken {
+                token: word.to_string(),
+            })?;
+        tokens.push(Token::Nag(nag));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::bitboard::Square;
+
+    use super::*;
+    use crate::board::color::Color;
+    use crate::board::piece::Piece;
+
+    const ANNOTATED_GAME: &str = r#"[Event "Casual"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+{A short one} 1. e4 e5 2. Nf3 $1 Nc6 (2... d6 {Philidor} 3. d4 (3. Bc4) 3... exd4) 3. Bc4!?
+Nf6?? ; Nd4 was better
+4. Ng5 d5 5. exd5 Nxd5? 6. Nxf7 Kxf7 7. Qf3+ Ke6 8. Nc3 1-0
+"#;
+
+    #[test]
+    fn test_game_with_variations_comments_and_nags() {
+        let game = Game::from_pgn(ANNOTATED_GAME).unwrap();
+        assert_eq!(game.tag("White"), Some("Alice"));
+        assert_eq!(game.result(), Some("1-0"));
+        assert_eq!(game.moves().len(), 15);
+
+        let moves = game.moves();
+        assert_eq!(moves[0].comment.as_deref(), Some("A short one"));
+        assert_eq!(moves[2].nags, vec![1]);
+        assert_eq!(moves[4].san, "Bc4");
+        assert_eq!(moves[4].nags, vec![5]);
+        assert_eq!(moves[5].nags, vec![4]);
+        assert_eq!(moves[12].san, "Qf3+");
+
+        let variation = &moves[3].variations[0];
+        let sans: Vec<_> = variation.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["d6", "d4", "exd4"]);
+        assert_eq!(variation[0].comment.as_deref(), Some("Philidor"));
+        assert_eq!(variation[1].variations[0][0].san, "Bc4");
+    }
+
+    #[test]
+    fn test_replays_positions_along_the_mainline() {
+        let game = Game::from_pgn(ANNOTATED_GAME).unwrap();
+        assert_eq!(game.position_after(0).unwrap().turn(), Color::White);
+
+        let board = game.position_after(1).unwrap();
+        assert_eq!(board.get(Square::E4), Some((Piece::Pawn, Color::White)));
+        assert_eq!(board.turn(), Color::Black);
+        assert!(game.position_after(16).is_none());
+
+        let board = game.final_position();
+        assert_eq!(board.get(Square::E6), Some((Piece::King, Color::Black)));
+        assert_eq!(board.get(Square::F7), None);
+    }
+
+    #[test]
+    fn test_games_from_a_position_and_in_sequence() {
+        let pgn = r#"[FEN "4k3/P7/8/8/8/8/8/4K2R w K - 0 1"]
+[SetUp "1"]
+
+1. a8Q+ Kd7 2. 0-0 *
+
+[White "Next"]
+
+1. d4 d5 1/2-1/2
+"#;
+        let games = parse_games(pgn).unwrap();
+        assert_eq!(games.len(), 2);
+        let sans: Vec<_> = games[0].moves().iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["a8=Q+", "Kd7", "O-O"]);
+        assert_eq!(games[0].result(), Some("*"));
+        assert_eq!(games[1].tag("White"), Some("Next"));
+        assert_eq!(games[1].moves().len(), 2);
+    }
+
+    #[test]
+    fn test_illegal_and_malformed_movetext_is_rejected() {
+        assert!(matches!(
+            Game::from_pgn("1. e4 e5 2. Ke3"),
+            Err(PgnImportError::IllegalMove { san, .. }) if san == "Ke3"
+        ));
+        assert!(matches!(
+            Game::from_pgn("1. e4 (1. d4 d5"),
+            Err(PgnImportError::Unterminated { what: "variation" })
+        ));
+        assert!(matches!(
+            Game::from_pgn("(1. d4) 1. e4"),
+            Err(PgnImportError::UnexpectedToken { .. })
+        ));
+        assert!(matches!(Game::from_pgn("  "), Err(PgnImportError::NoGame)));
+    }
+}
@@ -5,9 +5,18 @@ pub mod chess_move;
 pub mod chess_search;
 pub mod diagnostics;
 pub mod evaluate;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "native")]
 pub mod game;
 pub mod input_handler;
+#[cfg(feature = "native")]
+pub mod lichess_bot;
 pub mod move_generator;
 pub mod prelude;
+#[cfg(feature = "native")]
+pub mod server;
+#[cfg(feature = "native")]
 pub mod tui;
+#[cfg(feature = "native")]
 pub mod uci;
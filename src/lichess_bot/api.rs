@@ -0,0 +1,361 @@
+//! Client for the Lichess Bot API and the messages it streams.
+//!
+//! The event and game streams are newline-delimited JSON, with an empty line
+//! every few seconds to keep the connection open. Only the fields the bot
+//! uses are read; anything else in a message is ignored.
+
+use std::io::{BufRead, BufReader};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::bot::LichessBotError;
+use super::challenge::DeclineReason;
+
+/// Time allowed to connect before a request fails. Streams are read without
+/// a timeout, since they are quiet between keep-alive lines and moves.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub username: String,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl Account {
+    pub fn is_bot(&self) -> bool {
+        self.title.as_deref() == Some("BOT")
+    }
+}
+
+/// A player in a challenge or game. Lichess AI opponents have no id.
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VariantInfo {
+    pub key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChallengeTimeControl {
+    /// Initial time and increment, in seconds.
+    Clock {
+        limit: u64,
+        increment: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Correspondence {
+        days_per_turn: u32,
+    },
+    Unlimited,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Challenge {
+    pub id: String,
+    pub challenger: User,
+    pub variant: VariantInfo,
+    pub rated: bool,
+    pub time_control: ChallengeTimeControl,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameStartInfo {
+    #[serde(alias = "id")]
+    pub game_id: String,
+}
+
+/// A message on the account's event stream.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    Challenge {
+        challenge: Challenge,
+    },
+    GameStart {
+        game: GameStartInfo,
+    },
+    GameFinish {
+        game: GameStartInfo,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// The moves and clocks of a game, in milliseconds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameState {
+    /// Moves played from the initial position in UCI notation, space separated.
+    pub moves: String,
+    pub wtime: u64,
+    pub btime: u64,
+    pub winc: u64,
+    pub binc: u64,
+    /// `started` while the game is on.
+    pub status: String,
+}
+
+impl GameState {
+    pub fn is_ongoing(&self) -> bool {
+        matches!(self.status.as_str(), "created" | "started")
+    }
+}
+
+/// Initial clock of a game, in milliseconds.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameClock {
+    pub initial: u64,
+    pub increment: u64,
+}
+
+/// The first message on a game stream.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameFull {
+    pub id: String,
+    pub variant: VariantInfo,
+    /// Absent for correspondence and unlimited games.
+    #[serde(default)]
+    pub clock: Option<GameClock>,
+    pub white: User,
+    pub black: User,
+    /// `startpos` or a FEN.
+    pub initial_fen: String,
+    pub state: GameState,
+}
+
+/// A message on a game stream.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GameEvent {
+    GameFull(Box<GameFull>),
+    GameState(GameState),
+    #[serde(other)]
+    Other,
+}
+
+/// Messages read off a newline-delimited JSON stream, skipping keep-alive
+/// lines. Ends when the server closes the stream.
+pub struct NdjsonStream<T> {
+    reader: Box<dyn BufRead + Send>,
+    message: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> NdjsonStream<T> {
+    pub fn new(reader: impl BufRead + Send + 'static) -> Self {
+        Self {
+            reader: Box::new(reader),
+            message: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for NdjsonStream<T> {
+    type Item = Result<T, LichessBotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) if line.trim().is_empty() => continue,
+                Ok(_) => {
+                    return Some(serde_json::from_str(line.trim()).map_err(|error| {
+                        LichessBotError::Parse(format!("{}: {}", error, line.trim()))
+                    }))
+                }
+                Err(error) => {
+                    return Some(Err(LichessBotError::Request {
+                        path: "stream".to_string(),
+                        message: error.to_string(),
+                    }))
+                }
+            }
+        }
+    }
+}
+
+/// An authenticated connection to a Lichess server. Clones share the
+/// underlying connection pool.
+#[derive(Clone)]
+pub struct LichessClient {
+    agent: ureq::Agent,
+    base_url: String,
+    token: String,
+}
+
+impl LichessClient {
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(CONNECT_TIMEOUT)
+                .user_agent(concat!("chess/", env!("CARGO_PKG_VERSION")))
+                .build(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    pub fn account(&self) -> Result<Account, LichessBotError> {
+        let path = "/api/account";
+        let response = self.request("GET", path).call();
+        let body = read_response(path, response)?;
+        serde_json::from_str(&body).map_err(|error| LichessBotError::Parse(error.to_string()))
+    }
+
+    /// Challenges and game starts and ends for the account, as they happen.
+    /// Games already in progress are announced when the stream opens.
+    pub fn stream_events(&self) -> Result<NdjsonStream<Event>, LichessBotError> {
+        self.stream("/api/stream/event")
+    }
+
+    pub fn stream_game(&self, game_id: &str) -> Result<NdjsonStream<GameEvent>, LichessBotError> {
+        self.stream(&format!("/api/bot/game/stream/{}", game_id))
+    }
+
+    pub fn accept_challenge(&self, challenge_id: &str) -> Result<(), LichessBotError> {
+        let path = format!("/api/challenge/{}/accept", challenge_id);
+        read_response(&path, self.request("POST", &path).call()).map(drop)
+    }
+
+    pub fn decline_challenge(
+        &self,
+        challenge_id: &str,
+        reason: DeclineReason,
+    ) -> Result<(), LichessBotError> {
+        let path = format!("/api/challenge/{}/decline", challenge_id);
+        let response = self
+            .request("POST", &path)
+            .send_form(&[("reason", reason.as_str())]);
+        read_response(&path, response).map(drop)
+    }
+
+    pub fn make_move(&self, game_id: &str, uci_move: &str) -> Result<(), LichessBotError> {
+        let path = format!("/api/bot/game/{}/move/{}", game_id, uci_move);
+        read_response(&path, self.request("POST", &path).call()).map(drop)
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.agent
+            .request(method, &format!("{}{}", self.base_url, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+    }
+
+    fn stream<T: DeserializeOwned>(&self, path: &str) -> Result<NdjsonStream<T>, LichessBotError> {
+        let response = self
+            .request("GET", path)
+            .set("Accept", "application/x-ndjson")
+            .call()
+            .map_err(|error| request_error(path, error))?;
+        Ok(NdjsonStream::new(BufReader::new(response.into_reader())))
+    }
+}
+
+fn read_response(
+    path: &str,
+    response: Result<ureq::Response, ureq::Error>,
+) -> Result<String, LichessBotError> {
+    response
+        .map_err(|error| request_error(path, error))?
+        .into_string()
+        .map_err(|error| LichessBotError::Request {
+            path: path.to_string(),
+            message: error.to_string(),
+        })
+}
+
+fn request_error(path: &str, error: ureq::Error) -> LichessBotError {
+    match error {
+        ureq::Error::Status(status, response) => LichessBotError::Status {
+            path: path.to_string(),
+            status,
+            body: response.into_string().unwrap_or_default(),
+        },
+        ureq::Error::Transport(transport) => LichessBotError::Request {
+            path: path.to_string(),
+            message: transport.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_event_stream_skips_keep_alive_lines() {
+        let stream = concat!(
+            "\n",
+            r#"{"type":"challenge","challenge":{"id":"c1","status":"created","challenger":{"id":"alice","name":"Alice","rating":1500},"destUser":{"id":"bot"},"variant":{"key":"standard","name":"Standard"},"rated":true,"speed":"blitz","timeControl":{"type":"clock","limit":180,"increment":2,"show":"3+2"},"color":"random"}}"#,
+            "\n\n",
+            r#"{"type":"gameStart","game":{"gameId":"g1","fullId":"g1abcd","color":"white"}}"#,
+            "\n",
+            r#"{"type":"challengeCanceled","challenge":{"id":"c2"}}"#,
+            "\n",
+        );
+        let events: Vec<Event> = NdjsonStream::new(Cursor::new(stream))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 3);
+        match &events[0] {
+            Event::Challenge { challenge } => {
+                assert_eq!(challenge.id, "c1");
+                assert_eq!(
+                    challenge.time_control,
+                    ChallengeTimeControl::Clock {
+                        limit: 180,
+                        increment: 2
+                    }
+                );
+            }
+            other => panic!("expected a challenge, got {:?}", other),
+        }
+        assert!(matches!(&events[1], Event::GameStart { game } if game.game_id == "g1"));
+        assert!(matches!(events[2], Event::Other));
+    }
+
+    #[test]
+    fn test_game_stream_messages_parse() {
+        let stream = concat!(
+            r#"{"type":"gameFull","id":"g1","rated":false,"variant":{"key":"standard"},"clock":{"initial":180000,"increment":2000},"speed":"blitz","white":{"id":"alice","name":"Alice"},"black":{"id":"bot","name":"Bot","title":"BOT"},"initialFen":"startpos","state":{"type":"gameState","moves":"e2e4","wtime":179000,"btime":180000,"winc":2000,"binc":2000,"status":"started"}}"#,
+            "\n",
+            r#"{"type":"chatLine","username":"alice","text":"hi","room":"player"}"#,
+            "\n",
+            r#"{"type":"gameState","moves":"e2e4 e7e5","wtime":179000,"btime":178000,"winc":2000,"binc":2000,"status":"resign","winner":"white"}"#,
+            "\n",
+        );
+        let events: Vec<GameEvent> = NdjsonStream::new(Cursor::new(stream))
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        match &events[0] {
+            GameEvent::GameFull(full) => {
+                assert_eq!(full.black.id.as_deref(), Some("bot"));
+                assert_eq!(full.state.moves, "e2e4");
+                assert!(full.state.is_ongoing());
+            }
+            other => panic!("expected the full game, got {:?}", other),
+        }
+        assert!(matches!(events[1], GameEvent::Other));
+        assert!(matches!(&events[2], GameEvent::GameState(state) if !state.is_ongoing()));
+    }
+}
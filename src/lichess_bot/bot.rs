@@ -0,0 +1,126 @@
+//! The bot's main loop: answers challenges and starts a thread per game.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+use thiserror::Error;
+
+use super::api::{Account, Event, LichessClient};
+use super::challenge::{ChallengeFilter, DeclineReason};
+use super::game::{self, TimeSettings};
+
+/// Lichess's own server.
+pub const LICHESS_URL: &str = "https://lichess.org";
+
+/// Wait before reconnecting when the event stream drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug)]
+pub enum LichessBotError {
+    #[error("Request to {path} failed: {message}")]
+    Request { path: String, message: String },
+    #[error("Lichess answered {path} with status {status}: {body}")]
+    Status {
+        path: String,
+        status: u16,
+        body: String,
+    },
+    #[error("Unexpected message from Lichess: {0}")]
+    Parse(String),
+    #[error(
+        "{0} is not a bot account; see https://lichess.org/api#tag/Bot/operation/botAccountUpgrade"
+    )]
+    NotABot(String),
+    #[error("{0}")]
+    Game(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    /// Server to connect to, such as `LICHESS_URL`.
+    pub server: String,
+    pub token: String,
+    pub filter: ChallengeFilter,
+    pub time: TimeSettings,
+    /// Games played at the same time.
+    pub max_games: usize,
+}
+
+/// Connect as the token's bot account and play until the process is stopped.
+/// Fails only if the account can't be used; a dropped event stream is
+/// reconnected.
+pub fn run_bot(config: BotConfig) -> Result<(), LichessBotError> {
+    let client = LichessClient::new(&config.server, &config.token);
+    let account = client.account()?;
+    if !account.is_bot() {
+        return Err(LichessBotError::NotABot(account.username));
+    }
+    println!("Playing on {} as {}", config.server, account.username);
+
+    let games = Arc::new(Mutex::new(HashSet::new()));
+    loop {
+        if let Err(error) = handle_events(&client, &account, &config, &games) {
+            warn!("Event stream failed: {}", error);
+        }
+        info!("Event stream closed; reconnecting");
+        thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Answer challenges and start games until the event stream ends.
+fn handle_events(
+    client: &LichessClient,
+    account: &Account,
+    config: &BotConfig,
+    games: &Arc<Mutex<HashSet<String>>>,
+) -> Result<(), LichessBotError> {
+    for event in client.stream_events()? {
+        match event? {
+            Event::Challenge { challenge } => {
+                // Our own challenges to others show up here too
+                if challenge.challenger.id.as_deref() == Some(account.id.as_str()) {
+                    continue;
+                }
+                let busy = games.lock().unwrap().len() >= config.max_games;
+                let reason = config
+                    .filter
+                    .decline_reason(&challenge)
+                    .or_else(|| busy.then_some(DeclineReason::Later));
+                let answer = match reason {
+                    None => client.accept_challenge(&challenge.id),
+                    Some(reason) => client.decline_challenge(&challenge.id, reason),
+                };
+                match (answer, reason) {
+                    (Ok(()), None) => println!("Accepted challenge {}", challenge.id),
+                    (Ok(()), Some(reason)) => {
+                        println!("Declined challenge {} ({})", challenge.id, reason.as_str())
+                    }
+                    (Err(error), _) => warn!("Challenge {}: {}", challenge.id, error),
+                }
+            }
+            Event::GameStart { game } => {
+                // Games in progress are announced again on reconnecting
+                if !games.lock().unwrap().insert(game.game_id.clone()) {
+                    continue;
+                }
+                println!("Game {} started", game.game_id);
+                let client = client.clone();
+                let bot_id = account.id.clone();
+                let time = config.time;
+                let games = Arc::clone(games);
+                thread::spawn(move || {
+                    if let Err(error) = game::play_game(&client, &game.game_id, &bot_id, &time) {
+                        warn!("Game {} failed: {}", game.game_id, error);
+                    }
+                    games.lock().unwrap().remove(&game.game_id);
+                    println!("Game {} finished", game.game_id);
+                });
+            }
+            Event::GameFinish { .. } | Event::Other => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Which challenges the bot accepts.
+
+use crate::board::Variant;
+
+use super::api::{Challenge, ChallengeTimeControl};
+
+/// Why a challenge is declined, as the Bot API names the reasons. Lichess
+/// shows the challenger a message for each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeclineReason {
+    Generic,
+    /// The bot is busy with as many games as it plays at once.
+    Later,
+    TooFast,
+    TooSlow,
+    TimeControl,
+    Rated,
+    Casual,
+    Variant,
+    NoBot,
+}
+
+impl DeclineReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeclineReason::Generic => "generic",
+            DeclineReason::Later => "later",
+            DeclineReason::TooFast => "tooFast",
+            DeclineReason::TooSlow => "tooSlow",
+            DeclineReason::TimeControl => "timeControl",
+            DeclineReason::Rated => "rated",
+            DeclineReason::Casual => "casual",
+            DeclineReason::Variant => "variant",
+            DeclineReason::NoBot => "noBot",
+        }
+    }
+}
+
+/// The variant a Lichess variant key stands for, if the engine plays it.
+/// Games from a custom position are standard chess.
+pub fn lichess_variant(key: &str) -> Option<Variant> {
+    match key {
+        "standard" | "fromPosition" => Some(Variant::Standard),
+        "kingOfTheHill" => Some(Variant::KingOfTheHill),
+        "threeCheck" => Some(Variant::ThreeCheck),
+        _ => None,
+    }
+}
+
+/// The challenges to accept. Clock limits are in seconds.
+#[derive(Debug, Clone)]
+pub struct ChallengeFilter {
+    pub variants: Vec<Variant>,
+    pub min_initial: u64,
+    pub max_initial: Option<u64>,
+    pub min_increment: u64,
+    pub max_increment: Option<u64>,
+    /// Accept correspondence and unlimited games, which have no clock.
+    pub allow_untimed: bool,
+    pub allow_rated: bool,
+    pub allow_casual: bool,
+    /// Accept challenges from other bots.
+    pub allow_bots: bool,
+}
+
+impl Default for ChallengeFilter {
+    fn default() -> Self {
+        Self {
+            variants: vec![Variant::Standard],
+            min_initial: 0,
+            max_initial: None,
+            min_increment: 0,
+            max_increment: None,
+            allow_untimed: false,
+            allow_rated: true,
+            allow_casual: true,
+            allow_bots: true,
+        }
+    }
+}
+
+impl ChallengeFilter {
+    /// Why `challenge` should be declined, or `None` to accept it.
+    pub fn decline_reason(&self, challenge: &Challenge) -> Option<DeclineReason> {
+        let variant = lichess_variant(&challenge.variant.key);
+        if !variant.is_some_and(|variant| self.variants.contains(&variant)) {
+            return Some(DeclineReason::Variant);
+        }
+        if challenge.challenger.title.as_deref() == Some("BOT") && !self.allow_bots {
+            return Some(DeclineReason::NoBot);
+        }
+        if challenge.rated && !self.allow_rated {
+            return Some(DeclineReason::Casual);
+        }
+        if !challenge.rated && !self.allow_casual {
+            return Some(DeclineReason::Rated);
+        }
+        match challenge.time_control {
+            ChallengeTimeControl::Clock { limit, increment } => {
+                if limit < self.min_initial || increment < self.min_increment {
+                    Some(DeclineReason::TooFast)
+                } else if self.max_initial.is_some_and(|max| limit > max)
+                    || self.max_increment.is_some_and(|max| increment > max)
+                {
+                    Some(DeclineReason::TooSlow)
+                } else {
+                    None
+                }
+            }
+            ChallengeTimeControl::Correspondence { .. } | ChallengeTimeControl::Unlimited => {
+                (!self.allow_untimed).then_some(DeclineReason::TimeControl)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(variant: &str, rated: bool, time_control: ChallengeTimeControl) -> Challenge {
+        serde_json::from_value(serde_json::json!({
+            "id": "c1",
+            "challenger": { "id": "alice", "name": "Alice" },
+            "variant": { "key": variant },
+            "rated": rated,
+            "timeControl": { "type": "unlimited" },
+        }))
+        .map(|challenge: Challenge| Challenge {
+            time_control,
+            ..challenge
+        })
+        .unwrap()
+    }
+
+    fn clock(limit: u64, increment: u64) -> ChallengeTimeControl {
+        ChallengeTimeControl::Clock { limit, increment }
+    }
+
+    #[test]
+    fn test_filter_declines_by_variant_and_rating() {
+        let filter = ChallengeFilter {
+            allow_rated: false,
+            ..ChallengeFilter::default()
+        };
+        assert_eq!(
+            filter.decline_reason(&challenge("standard", false, clock(180, 2))),
+            None
+        );
+        assert_eq!(
+            filter.decline_reason(&challenge("chess960", false, clock(180, 2))),
+            Some(DeclineReason::Variant)
+        );
+        assert_eq!(
+            filter.decline_reason(&challenge("kingOfTheHill", false, clock(180, 2))),
+            Some(DeclineReason::Variant)
+        );
+        assert_eq!(
+            filter.decline_reason(&challenge("standard", true, clock(180, 2))),
+            Some(DeclineReason::Casual)
+        );
+    }
+
+    #[test]
+    fn test_filter_declines_by_time_control() {
+        let filter = ChallengeFilter {
+            min_initial: 60,
+            max_initial: Some(600),
+            max_increment: Some(5),
+            ..ChallengeFilter::default()
+        };
+        let reason =
+            |time_control| filter.decline_reason(&challenge("standard", true, time_control));
+        assert_eq!(reason(clock(300, 3)), None);
+        assert_eq!(reason(clock(30, 0)), Some(DeclineReason::TooFast));
+        assert_eq!(reason(clock(900, 0)), Some(DeclineReason::TooSlow));
+        assert_eq!(reason(clock(300, 10)), Some(DeclineReason::TooSlow));
+        assert_eq!(
+            reason(ChallengeTimeControl::Correspondence { days_per_turn: 3 }),
+            Some(DeclineReason::TimeControl)
+        );
+    }
+}
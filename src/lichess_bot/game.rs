@@ -0,0 +1,277 @@
+//! Playing one game: following its stream and answering with moves.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::board::color::Color;
+use crate::board::Board;
+use crate::game::engine::{Engine, EngineConfig};
+use crate::uci::time_management::ClockState;
+
+use super::api::{GameEvent, GameFull, GameState, LichessClient};
+use super::bot::LichessBotError;
+use super::challenge::lichess_variant;
+
+/// Shortest search for a move, however low the clock.
+const MIN_MOVE_TIME: Duration = Duration::from_millis(10);
+
+/// How long the bot thinks about its moves.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSettings {
+    /// Time kept back on every move for the move to reach Lichess.
+    pub move_overhead: Duration,
+    /// Longest time spent on any move, whatever the clock allows.
+    pub max_movetime: Option<Duration>,
+    /// Time per move in correspondence and unlimited games.
+    pub untimed_movetime: Duration,
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        Self {
+            move_overhead: Duration::from_millis(100),
+            max_movetime: None,
+            untimed_movetime: Duration::from_secs(5),
+        }
+    }
+}
+
+impl TimeSettings {
+    /// Thinking time for `color`'s move in a game with `state`'s clocks, or
+    /// without a clock when `timed` is false.
+    pub fn move_time(&self, state: &GameState, color: Color, timed: bool) -> Duration {
+        let clock = ClockState {
+            wtime: Some(state.wtime),
+            btime: Some(state.btime),
+            winc: Some(state.winc),
+            binc: Some(state.binc),
+            movestogo: None,
+        };
+        let budget = if timed {
+            clock
+                .move_budget(color)
+                .map(|budget| budget.saturating_sub(self.move_overhead))
+                .unwrap_or(MIN_MOVE_TIME)
+        } else {
+            self.untimed_movetime
+        };
+        self.max_movetime
+            .map_or(budget, |max| budget.min(max))
+            .max(MIN_MOVE_TIME)
+    }
+}
+
+/// The bot's side of a game, with an engine following its moves.
+pub(crate) struct BotGame {
+    id: String,
+    color: Color,
+    timed: bool,
+    starting_position: Board,
+    engine: Engine,
+    applied_moves: Vec<String>,
+}
+
+impl BotGame {
+    /// Set up from the first message of a game stream, for the account `bot_id`.
+    pub(crate) fn new(full: &GameFull, bot_id: &str) -> Result<Self, String> {
+        let is_bot = |id: &Option<String>| {
+            id.as_deref()
+                .is_some_and(|id| id.eq_ignore_ascii_case(bot_id))
+        };
+        let color = if is_bot(&full.white.id) {
+            Color::White
+        } else if is_bot(&full.black.id) {
+            Color::Black
+        } else {
+            return Err(format!("{} is not playing in game {}", bot_id, full.id));
+        };
+        let variant = lichess_variant(&full.variant.key)
+            .ok_or_else(|| format!("unsupported variant {}", full.variant.key))?;
+        let mut starting_position = if full.initial_fen == "startpos" {
+            Board::default()
+        } else {
+            Board::from_str(&full.initial_fen)
+                .map_err(|e| format!("invalid initial FEN {}: {:?}", full.initial_fen, e))?
+        };
+        starting_position.set_variant(variant);
+
+        Ok(Self {
+            id: full.id.clone(),
+            color,
+            timed: full.clock.is_some(),
+            engine: new_engine(&starting_position),
+            starting_position,
+            applied_moves: Vec::new(),
+        })
+    }
+
+    pub(crate) fn color(&self) -> Color {
+        self.color
+    }
+
+    /// The bot's reply to `state`, or `None` when the game is over or it's
+    /// the opponent's turn.
+    pub(crate) fn choose_move(
+        &mut self,
+        state: &GameState,
+        time: &TimeSettings,
+    ) -> Result<Option<String>, String> {
+        if !state.is_ongoing() {
+            return Ok(None);
+        }
+        let moves: Vec<String> = state.moves.split_whitespace().map(String::from).collect();
+        self.follow_moves(&moves)?;
+        if self.engine.board().turn() != self.color || self.engine.check_game_over().is_some() {
+            return Ok(None);
+        }
+
+        let move_time = time.move_time(state, self.color, self.timed);
+        self.engine
+            .get_best_move_with_time_limit(move_time)
+            .map(|chess_move| Some(chess_move.to_uci()))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Catch the engine up with the game, starting over if moves were taken
+    /// back.
+    fn follow_moves(&mut self, moves: &[String]) -> Result<(), String> {
+        if !moves.starts_with(&self.applied_moves) {
+            self.engine = new_engine(&self.starting_position);
+            self.applied_moves.clear();
+        }
+        for uci_move in &moves[self.applied_moves.len()..] {
+            self.engine
+                .make_move_uci(uci_move)
+                .map_err(|e| format!("failed to apply {}: {}", uci_move, e))?;
+            self.engine.board_mut().toggle_turn();
+            self.engine.record_position_hash();
+            self.applied_moves.push(uci_move.clone());
+        }
+        Ok(())
+    }
+}
+
+fn new_engine(starting_position: &Board) -> Engine {
+    Engine::with_config(EngineConfig {
+        starting_position: starting_position.clone(),
+        ..EngineConfig::default()
+    })
+}
+
+/// Play game `game_id` on its stream until it ends.
+pub(crate) fn play_game(
+    client: &LichessClient,
+    game_id: &str,
+    bot_id: &str,
+    time: &TimeSettings,
+) -> Result<(), LichessBotError> {
+    let mut game: Option<BotGame> = None;
+    for event in client.stream_game(game_id)? {
+        let state = match event? {
+            GameEvent::GameFull(full) => {
+                let new_game = BotGame::new(&full, bot_id).map_err(LichessBotError::Game)?;
+                info!("Game {}: playing {:?}", full.id, new_game.color());
+                game = Some(new_game);
+                full.state
+            }
+            GameEvent::GameState(state) => state,
+            GameEvent::Other => continue,
+        };
+        let Some(game) = game.as_mut() else {
+            continue;
+        };
+        if !state.is_ongoing() {
+            info!("Game {} ended: {}", game.id, state.status);
+            break;
+        }
+        match game.choose_move(&state, time) {
+            Ok(Some(uci_move)) => {
+                if let Err(error) = client.make_move(&game.id, &uci_move) {
+                    // The game may have ended while searching; the stream says
+                    warn!(
+                        "Game {}: move {} was not accepted: {}",
+                        game.id, uci_move, error
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(message) => return Err(LichessBotError::Game(message)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_full(clock: bool, moves: &str) -> GameFull {
+        let mut full = serde_json::json!({
+            "id": "g1",
+            "variant": { "key": "standard" },
+            "white": { "id": "alice", "name": "Alice" },
+            "black": { "id": "Bot", "name": "Bot", "title": "BOT" },
+            "initialFen": "startpos",
+            "state": {
+                "moves": moves,
+                "wtime": 60000, "btime": 60000, "winc": 0, "binc": 0,
+                "status": "started",
+            },
+        });
+        if clock {
+            full["clock"] = serde_json::json!({ "initial": 60000, "increment": 0 });
+        }
+        serde_json::from_value(full).unwrap()
+    }
+
+    #[test]
+    fn test_move_time_follows_the_clock_within_the_settings() {
+        let state = game_full(true, "").state;
+        let time = TimeSettings::default();
+        // 60s / 30 moves, less the overhead
+        assert_eq!(
+            time.move_time(&state, Color::White, true),
+            Duration::from_millis(1900)
+        );
+        assert_eq!(
+            time.move_time(&state, Color::White, false),
+            Duration::from_secs(5)
+        );
+        let capped = TimeSettings {
+            max_movetime: Some(Duration::from_millis(500)),
+            ..time
+        };
+        assert_eq!(
+            capped.move_time(&state, Color::Black, true),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn test_bot_moves_only_on_its_turn() {
+        let full = game_full(true, "e2e4");
+        let mut game = BotGame::new(&full, "bot").unwrap();
+        assert_eq!(game.color(), Color::Black);
+        let time = TimeSettings {
+            max_movetime: Some(Duration::from_millis(50)),
+            ..TimeSettings::default()
+        };
+
+        let reply = game.choose_move(&full.state, &time).unwrap();
+        assert!(reply.is_some());
+
+        let next = GameState {
+            moves: format!("e2e4 {} g1f3", reply.unwrap()),
+            ..full.state.clone()
+        };
+        assert!(game.choose_move(&next, &time).unwrap().is_some());
+        let theirs = GameState {
+            moves: "d2d4 d7d5".to_string(),
+            ..full.state.clone()
+        };
+        assert_eq!(game.choose_move(&theirs, &time).unwrap(), None);
+        assert!(BotGame::new(&full, "carol").is_err());
+    }
+}
@@ -0,0 +1,24 @@
+//! Lichess bot: plays games on lichess.org through the Bot API.
+//!
+//! The bot listens on the account's event stream. Incoming challenges are
+//! accepted or declined by a `ChallengeFilter`, and challenges beyond the
+//! number of games played at once are declined for later. Every game that
+//! starts gets its own thread and engine, which follow the game's stream and
+//! post a move whenever it's the bot's turn. How long a move is thought about
+//! follows the game's clock within the limits of `TimeSettings`.
+//!
+//! The account must be a bot account and the API token needs the `bot:play`
+//! scope; see <https://lichess.org/api#tag/Bot>.
+
+mod api;
+mod bot;
+mod challenge;
+mod game;
+
+pub use api::{
+    Account, Challenge, ChallengeTimeControl, Event, GameEvent, GameFull, GameState, LichessClient,
+    NdjsonStream,
+};
+pub use bot::{run_bot, BotConfig, LichessBotError, LICHESS_URL};
+pub use challenge::{lichess_variant, ChallengeFilter, DeclineReason};
+pub use game::TimeSettings;
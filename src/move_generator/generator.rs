@@ -1,5 +1,12 @@
 //! Move generation implementation.
 //!
+//! Moves are generated legal, without applying them to see whether they leave
+//! the king in check: pinned pieces only move along their pin rays, a king in
+//! check only answers it, the king never steps onto a square the opponent
+//! attacks (or one behind it on a checking ray), and castling needs the king's
+//! path to be safe. En passant, which takes two pawns off a rank, is the one
+//! move whose legality is looked at separately.
+//!
 //! **Performance optimizations:**
 //! - Pre-allocate move lists with capacity to avoid reallocations
 //! - Pre-compute constants (promotion rank) before hot loops
@@ -12,6 +19,7 @@
 //!   with conditional parallelization based on move count (>= 10 moves). This improves CPU
 //!   utilization across all threads and reduces thread synchronization overhead.
 
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use smallvec::{smallvec, SmallVec};
 #[cfg(feature = "instrumentation")]
@@ -25,7 +33,9 @@ use crate::chess_move::{
     chess_move_effect::ChessMoveEffect, en_passant::EnPassantChessMove,
     pawn_promotion::PawnPromotionChessMove, standard::StandardChessMove,
 };
-use crate::evaluate::{player_is_in_check, player_is_in_checkmate};
+use crate::evaluate::player_is_in_checkmate;
+
+use super::perft_hash::PerftHash;
 
 use super::targets::{
     generate_pawn_attack_targets, generate_pawn_move_targets, PieceTargetList, PinInfo, Targets,
@@ -43,6 +53,18 @@ pub const PAWN_PROMOTIONS: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bisho
 /// determine when to use parallel iteration vs sequential apply/undo pattern.
 const PARALLEL_MOVE_THRESHOLD: usize = 10;
 
+/// Iterate over `moves` on all cores, or one after another without the
+/// `parallel` feature.
+#[cfg(feature = "parallel")]
+fn parallel_iter(moves: &[ChessMove]) -> rayon::slice::Iter<'_, ChessMove> {
+    moves.par_iter()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn parallel_iter(moves: &[ChessMove]) -> std::slice::Iter<'_, ChessMove> {
+    moves.iter()
+}
+
 /// A list of chess moves that is optimized for small sizes.
 pub type ChessMoveList = SmallVec<[ChessMove; 32]>;
 
@@ -88,6 +110,20 @@ impl MoveGenerator {
         generate_valid_moves(board, player, &self.targets)
     }
 
+    /// Generates moves without regard to pins or check, so some may leave the
+    /// king in check. Used to show moves that are ruled out; castling is only
+    /// generated when it is legal.
+    pub fn generate_pseudo_legal_moves(&self, board: &mut Board, player: Color) -> ChessMoveList {
+        let no_pins = PinInfo::empty();
+        let mut moves = ChessMoveList::new();
+        generate_knight_moves(&mut moves, board, player, &self.targets, &no_pins);
+        generate_sliding_moves(&mut moves, board, player, &self.targets, &no_pins);
+        generate_king_moves(&mut moves, board, player, &self.targets, Bitboard::ALL);
+        generate_pawn_moves(&mut moves, board, player, &no_pins);
+        generate_castle_moves(&mut moves, board, player);
+        moves
+    }
+
     /// Generates only tactical moves (captures, en passant, and promotions).
     /// More efficient than generate_moves() followed by filtering, as it avoids
     /// generating quiet moves entirely.
@@ -96,6 +132,23 @@ impl MoveGenerator {
         generate_tactical_valid_moves(board, player, &self.targets)
     }
 
+    /// Generates only quiet moves: those that neither capture nor promote,
+    /// castling included. With `generate_tactical_moves` they make up every
+    /// legal move, so the search can generate the quiet ones only when its
+    /// tactical moves haven't already cut the node off.
+    #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
+    pub fn generate_quiet_moves(&self, board: &mut Board, player: Color) -> ChessMoveList {
+        generate_quiet_valid_moves(board, player, &self.targets)
+    }
+
+    /// Generates the quiet moves that give check, directly or by uncovering a
+    /// sliding piece, for quiescence search to try along with the tactical
+    /// moves. Castling is left out.
+    #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
+    pub fn generate_quiet_checks(&self, board: &mut Board, player: Color) -> ChessMoveList {
+        generate_quiet_checking_moves(board, player, &self.targets)
+    }
+
     fn lazily_update_chess_move_effect_for_checks_and_checkmates(
         &self,
         moves: &mut ChessMoveList,
@@ -119,7 +172,7 @@ impl MoveGenerator {
             .expect("move application should succeed when checking effects");
         let chess_move_effect = if player_is_in_checkmate(board, self, player) {
             ChessMoveEffect::Checkmate
-        } else if player_is_in_check(board, self, player) {
+        } else if board.is_in_check(player) {
             ChessMoveEffect::Check
         } else {
             ChessMoveEffect::None
@@ -146,7 +199,7 @@ impl MoveGenerator {
         // Use parallel iteration for larger move lists, sequential for small ones to avoid cloning overhead
         let inner_count = if candidates.len() >= PARALLEL_MOVE_THRESHOLD {
             // Parallel path: clone board for each task
-            let inner_counts = candidates.par_iter().map(|chess_move| {
+            let inner_counts = parallel_iter(&candidates).map(|chess_move| {
                 let mut local_board = board.clone();
 
                 chess_move
@@ -183,8 +236,75 @@ impl MoveGenerator {
         initial_count + inner_count
     }
 
+    /// Standard perft: the number of leaf positions exactly `depth` plies from
+    /// `board` with `player` to move. Unlike `count_positions`, interior nodes
+    /// are not counted, so results compare directly against published tables.
+    pub fn perft(&self, depth: u8, board: &mut Board, player: Color) -> usize {
+        self.perft_with_hash(depth, board, player, None)
+    }
+
+    /// Perft that looks up and stores subtree counts in `hash`, so positions
+    /// reached by transposition are only counted once.
+    pub fn perft_hashed(
+        &self,
+        depth: u8,
+        board: &mut Board,
+        player: Color,
+        hash: &PerftHash,
+    ) -> usize {
+        self.perft_with_hash(depth, board, player, Some(hash))
+    }
+
+    fn perft_with_hash(
+        &self,
+        depth: u8,
+        board: &mut Board,
+        player: Color,
+        hash: Option<&PerftHash>,
+    ) -> usize {
+        if depth == 0 {
+            return 1;
+        }
+        self.perft_divide(depth, board, player, hash)
+            .iter()
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Perft split by the first move: each legal move in `board` with the
+    /// number of leaves `depth - 1` plies after it, in generation order.
+    /// Comparing against another engine's divide narrows a miscount down to
+    /// the moves it is under.
+    pub fn perft_divide(
+        &self,
+        depth: u8,
+        board: &mut Board,
+        player: Color,
+        hash: Option<&PerftHash>,
+    ) -> Vec<(ChessMove, usize)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+        let candidates = self.generate_moves(board, player);
+        let next_player = player.opposite();
+        parallel_iter(&candidates)
+            .map(|chess_move| {
+                if depth == 1 {
+                    return (chess_move.clone(), 1);
+                }
+                let mut local_board = board.clone();
+                chess_move
+                    .apply(&mut local_board)
+                    .expect("move application should succeed in perft");
+                local_board.set_turn(next_player);
+                let count = perft_inner(depth - 1, &mut local_board, next_player, self, hash);
+                (chess_move.clone(), count)
+            })
+            .collect()
+    }
+
     pub fn get_attack_targets(&self, board: &Board, player: Color) -> Bitboard {
-        self.targets.generate_attack_targets(board, player)
+        board.attacks(player)
     }
 }
 
@@ -206,7 +326,7 @@ fn count_positions_inner(
 
     // Parallelize if depth is above threshold and we have enough moves to justify overhead
     if depth >= parallel_threshold && candidates.len() >= PARALLEL_MOVE_THRESHOLD {
-        let inner_counts = candidates.par_iter().map(|chess_move| {
+        let inner_counts = parallel_iter(&candidates).map(|chess_move| {
             let mut local_board = board.clone();
             chess_move
                 .apply(&mut local_board)
@@ -246,20 +366,56 @@ fn count_positions_inner(
     count
 }
 
+fn perft_inner(
+    depth: u8,
+    board: &mut Board,
+    color: Color,
+    move_generator: &MoveGenerator,
+    hash: Option<&PerftHash>,
+) -> usize {
+    // Leaf counts at depth 1 are cheaper to generate than to look up
+    let probe = hash.filter(|_| depth > 1);
+    if let Some(count) = probe.and_then(|hash| hash.probe(board.current_position_hash(), depth)) {
+        return count;
+    }
+
+    let candidates = move_generator.generate_moves(board, color);
+    if depth == 1 {
+        return candidates.len();
+    }
+
+    let next_color = color.opposite();
+    let mut count = 0;
+    for chess_move in candidates.iter() {
+        chess_move
+            .apply(board)
+            .expect("move application should succeed in perft");
+        board.set_turn(next_color);
+        count += perft_inner(depth - 1, board, next_color, move_generator, hash);
+        board.set_turn(color);
+        chess_move
+            .undo(board)
+            .expect("move undo should succeed in perft");
+    }
+    if let Some(hash) = probe {
+        hash.store(board.current_position_hash(), depth, count);
+    }
+    count
+}
+
 /// Generates all valid moves for the given board state and color.
 #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
 fn generate_valid_moves(board: &mut Board, color: Color, targets: &Targets) -> ChessMoveList {
     // Calculate pin and check information once at the start
     let pin_info = targets.calculate_pins(board, color);
     let check_info = targets.calculate_checks(board, color);
+    let king_safe_squares = !targets.king_danger_squares(board, color, check_info.checkers);
 
     let mut moves = ChessMoveList::new();
 
     // Handle double check: only king moves are legal (major optimization!)
     if check_info.in_double_check() {
-        generate_king_moves(&mut moves, board, color, targets);
-        // Still need to filter king moves to ensure they don't move into check
-        remove_invalid_king_moves(&mut moves, board, color, targets);
+        generate_king_moves(&mut moves, board, color, targets, king_safe_squares);
         return moves;
     }
 
@@ -268,8 +424,7 @@ fn generate_valid_moves(board: &mut Board, color: Color, targets: &Targets) -> C
         // Legal target squares: capture the checker or block the check ray
         let legal_targets = check_info.checkers | check_info.check_ray;
 
-        // Generate king moves (still need validation - king might move into check)
-        generate_king_moves(&mut moves, board, color, targets);
+        generate_king_moves(&mut moves, board, color, targets, king_safe_squares);
 
         // Generate non-king moves restricted to legal targets
         generate_knight_moves(&mut moves, board, color, targets, &pin_info);
@@ -278,9 +433,7 @@ fn generate_valid_moves(board: &mut Board, color: Color, targets: &Targets) -> C
 
         // Filter non-king moves to only those that address the check
         filter_moves_by_target(&mut moves, board, color, legal_targets);
-
-        // Validate king moves only (non-king moves are guaranteed legal)
-        remove_invalid_king_moves(&mut moves, board, color, targets);
+        remove_illegal_en_passant_moves(&mut moves, board, color, targets);
 
         return moves;
     }
@@ -288,12 +441,10 @@ fn generate_valid_moves(board: &mut Board, color: Color, targets: &Targets) -> C
     // Not in check: Generate all moves respecting pins
     generate_knight_moves(&mut moves, board, color, targets, &pin_info);
     generate_sliding_moves(&mut moves, board, color, targets, &pin_info);
-    generate_king_moves(&mut moves, board, color, targets);
+    generate_king_moves(&mut moves, board, color, targets, king_safe_squares);
     generate_pawn_moves(&mut moves, board, color, &pin_info);
-    generate_castle_moves(&mut moves, board, color, targets);
-
-    // Selective validation: only king moves, castle moves, and en passant need validation
-    remove_invalid_moves(&mut moves, board, color, targets, false);
+    generate_castle_moves(&mut moves, board, color);
+    remove_illegal_en_passant_moves(&mut moves, board, color, targets);
 
     moves
 }
@@ -308,14 +459,20 @@ fn generate_tactical_valid_moves(
 ) -> ChessMoveList {
     let pin_info = targets.calculate_pins(board, color);
     let check_info = targets.calculate_checks(board, color);
+    let king_safe_squares = !targets.king_danger_squares(board, color, check_info.checkers);
 
     let mut moves = ChessMoveList::new();
     let opponent_pieces = board.pieces(color.opposite()).occupied();
 
     // In double check, only king captures are legal
     if check_info.in_double_check() {
-        generate_king_captures(&mut moves, board, color, targets, opponent_pieces);
-        remove_invalid_king_moves(&mut moves, board, color, targets);
+        generate_king_captures(
+            &mut moves,
+            board,
+            color,
+            targets,
+            opponent_pieces & king_safe_squares,
+        );
         return moves;
     }
 
@@ -324,7 +481,13 @@ fn generate_tactical_valid_moves(
         let legal_targets = check_info.checkers | check_info.check_ray;
 
         // King can capture (not just on legal_targets)
-        generate_king_captures(&mut moves, board, color, targets, opponent_pieces);
+        generate_king_captures(
+            &mut moves,
+            board,
+            color,
+            targets,
+            opponent_pieces & king_safe_squares,
+        );
 
         // Non-king pieces can only capture the checker (not block -- blocks are quiet)
         // Actually, non-king captures on legal_targets are valid
@@ -350,8 +513,7 @@ fn generate_tactical_valid_moves(
         filter_moves_by_target(&mut moves, board, color, legal_targets);
         // But also keep pawn promotions that block the check ray
         // (handled by filter_moves_by_target since promotions have a target square)
-
-        remove_invalid_king_moves(&mut moves, board, color, targets);
+        remove_illegal_en_passant_moves(&mut moves, board, color, targets);
         return moves;
     }
 
@@ -372,15 +534,167 @@ fn generate_tactical_valid_moves(
         &pin_info,
         opponent_pieces,
     );
-    generate_king_captures(&mut moves, board, color, targets, opponent_pieces);
+    generate_king_captures(
+        &mut moves,
+        board,
+        color,
+        targets,
+        opponent_pieces & king_safe_squares,
+    );
     generate_pawn_tactical_moves(&mut moves, board, color, &pin_info);
+    remove_illegal_en_passant_moves(&mut moves, board, color, targets);
 
-    // Validate king moves and en passant only
-    remove_invalid_moves(&mut moves, board, color, targets, false);
+    moves
+}
+
+/// Generates only quiet moves (no captures, en passant or promotions) for the
+/// given position.
+#[cfg_attr(feature = "instrumentation", instrument(skip_all))]
+fn generate_quiet_valid_moves(board: &mut Board, color: Color, targets: &Targets) -> ChessMoveList {
+    let pin_info = targets.calculate_pins(board, color);
+    let check_info = targets.calculate_checks(board, color);
+    let king_safe_squares = !targets.king_danger_squares(board, color, check_info.checkers);
+    let empty = !board.occupied();
+
+    let mut moves = ChessMoveList::new();
+    generate_king_moves(&mut moves, board, color, targets, king_safe_squares & empty);
+    if check_info.in_double_check() {
+        return moves;
+    }
+
+    generate_knight_moves(&mut moves, board, color, targets, &pin_info);
+    generate_sliding_moves(&mut moves, board, color, targets, &pin_info);
+    moves.retain(|chess_move| chess_move.captures().is_none());
+    generate_quiet_pawn_moves(&mut moves, board, color, &pin_info);
+
+    if check_info.in_check() {
+        // Quiet moves can only answer a check by blocking it
+        filter_moves_by_target(&mut moves, board, color, check_info.check_ray);
+    } else {
+        generate_castle_moves(&mut moves, board, color);
+    }
+
+    moves
+}
+
+/// Generates the quiet moves that give check, from the targets of each piece
+/// rather than by filtering the quiet moves: a piece checks directly from the
+/// squares the same piece of the enemy king's color attacks from the king, and
+/// a piece standing alone between one of `color`'s sliding pieces and the
+/// enemy king checks by moving anywhere off that line.
+fn generate_quiet_checking_moves(
+    board: &mut Board,
+    color: Color,
+    targets: &Targets,
+) -> ChessMoveList {
+    let mut moves = ChessMoveList::new();
+    let enemy_king = board.pieces(color.opposite()).locate(Piece::King);
+    if enemy_king.is_empty() {
+        return moves;
+    }
+    let enemy_king = enemy_king.to_square();
+    let occupied = board.occupied();
+    let empty = !occupied;
+    let pin_info = targets.calculate_pins(board, color);
+    let check_info = targets.calculate_checks(board, color);
+    let discovered = targets.calculate_discovered_checkers(board, color);
+    let discovering = |square: Square| {
+        if discovered.is_pinned(square) {
+            !discovered.pin_ray(square)
+        } else {
+            Bitboard::EMPTY
+        }
+    };
+    let checking = |square: Square, piece: Piece| {
+        targets.attacks_from(enemy_king, piece, color.opposite(), occupied) | discovering(square)
+    };
+
+    // The king can only uncover a check
+    let king_safe_squares = !targets.king_danger_squares(board, color, check_info.checkers);
+    let mut piece_targets: PieceTargetList = smallvec![];
+    targets.generate_targets_from_precomputed_tables(&mut piece_targets, board, color, Piece::King);
+    for (square, target_bitboard) in piece_targets.iter_mut() {
+        *target_bitboard &= king_safe_squares & empty & discovering(*square);
+    }
+    expand_piece_targets(&mut moves, board, color, piece_targets);
+    if check_info.in_double_check() {
+        return moves;
+    }
+
+    // Quiet moves of the other pieces can only answer a check by blocking it
+    let allowed = if check_info.in_check() {
+        empty & check_info.check_ray
+    } else {
+        empty
+    };
+
+    let mut piece_targets: PieceTargetList = smallvec![];
+    targets.generate_targets_from_precomputed_tables(
+        &mut piece_targets,
+        board,
+        color,
+        Piece::Knight,
+    );
+    piece_targets.retain(|(square, _)| !pin_info.is_pinned(*square));
+    targets.generate_sliding_targets(&mut piece_targets, board, color);
+    for (square, target_bitboard) in piece_targets.iter_mut() {
+        if pin_info.is_pinned(*square) {
+            *target_bitboard &= pin_info.pin_ray(*square);
+        }
+        *target_bitboard &= match board.get(*square) {
+            Some((piece, _)) => allowed & checking(*square, piece),
+            None => Bitboard::EMPTY,
+        };
+    }
+    expand_piece_targets(&mut moves, board, color, piece_targets);
+
+    let promotion_rank = match color {
+        Color::White => Bitboard::RANK_8,
+        Color::Black => Bitboard::RANK_1,
+    };
+    for (piece_sq, mut target_squares) in generate_pawn_move_targets(board, color) {
+        if pin_info.is_pinned(piece_sq) {
+            target_squares &= pin_info.pin_ray(piece_sq);
+        }
+        let mut targets =
+            target_squares & !promotion_rank & allowed & checking(piece_sq, Piece::Pawn);
+        while !targets.is_empty() {
+            let target_sq = targets.pop_lsb_as_square();
+            moves.push(ChessMove::Standard(StandardChessMove::new(
+                piece_sq, target_sq, None,
+            )));
+        }
+    }
 
     moves
 }
 
+/// Generates pawn pushes that don't promote.
+fn generate_quiet_pawn_moves(
+    moves: &mut ChessMoveList,
+    board: &Board,
+    color: Color,
+    pin_info: &PinInfo,
+) {
+    let promotion_rank = match color {
+        Color::White => Bitboard::RANK_8,
+        Color::Black => Bitboard::RANK_1,
+    };
+
+    for (piece_sq, mut target_squares) in generate_pawn_move_targets(board, color) {
+        if pin_info.is_pinned(piece_sq) {
+            target_squares &= pin_info.pin_ray(piece_sq);
+        }
+        let mut targets = target_squares & !promotion_rank;
+        while !targets.is_empty() {
+            let target_sq = targets.pop_lsb_as_square();
+            moves.push(ChessMove::Standard(StandardChessMove::new(
+                piece_sq, target_sq, None,
+            )));
+        }
+    }
+}
+
 /// Generates only capture moves for knights.
 #[inline]
 fn generate_knight_captures(
@@ -434,20 +748,20 @@ fn generate_sliding_captures(
     expand_piece_targets(moves, board, color, piece_targets)
 }
 
-/// Generates only capture moves for the king.
+/// Generates only capture moves for the king, of the pieces on `captures`.
 #[inline]
 fn generate_king_captures(
     moves: &mut ChessMoveList,
     board: &Board,
     color: Color,
     targets: &Targets,
-    opponent_pieces: Bitboard,
+    captures: Bitboard,
 ) {
     let mut piece_targets: PieceTargetList = smallvec![];
     targets.generate_targets_from_precomputed_tables(&mut piece_targets, board, color, Piece::King);
 
     for (_, target_bitboard) in piece_targets.iter_mut() {
-        *target_bitboard &= opponent_pieces;
+        *target_bitboard &= captures;
     }
 
     expand_piece_targets(moves, board, color, piece_targets)
@@ -704,20 +1018,25 @@ fn expand_piece_targets(
     }
 }
 
+/// Generates the king's moves to `safe_squares`.
 #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
-fn generate_king_moves(moves: &mut ChessMoveList, board: &Board, color: Color, targets: &Targets) {
+fn generate_king_moves(
+    moves: &mut ChessMoveList,
+    board: &Board,
+    color: Color,
+    targets: &Targets,
+    safe_squares: Bitboard,
+) {
     let mut piece_targets: PieceTargetList = smallvec![];
     targets.generate_targets_from_precomputed_tables(&mut piece_targets, board, color, Piece::King);
+    for (_, target_bitboard) in piece_targets.iter_mut() {
+        *target_bitboard &= safe_squares;
+    }
     expand_piece_targets(moves, board, color, piece_targets)
 }
 
 #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
-fn generate_castle_moves(
-    moves: &mut ChessMoveList,
-    board: &Board,
-    color: Color,
-    targets: &Targets,
-) {
+fn generate_castle_moves(moves: &mut ChessMoveList, board: &Board, color: Color) {
     // Verify king is on its starting square before checking anything else
     let king = board.pieces(color).locate(Piece::King);
     let expected_king_square = match color {
@@ -728,7 +1047,7 @@ fn generate_castle_moves(
         return;
     }
 
-    // Early exit if player has no castle rights - avoids expensive attack target generation
+    // Early exit if player has no castle rights
     let castle_rights = board.peek_castle_rights();
     let player_rights = match color {
         Color::White => {
@@ -742,7 +1061,7 @@ fn generate_castle_moves(
         return;
     }
 
-    let attacked_squares = targets.generate_attack_targets(board, color.opposite());
+    let attacked_squares = board.attacks(color.opposite());
 
     // Reuse the cached king bitboard instead of looking it up again
     if king.overlaps(attacked_squares) {
@@ -783,6 +1102,7 @@ fn generate_castle_moves(
         && !kingside_transit_square.overlaps(attacked_squares)
         && !kingside_transit_square.overlaps(occupied)
         && !kingside_target_square.overlaps(occupied)
+        && !kingside_target_square.overlaps(attacked_squares)
     {
         let castle_move = CastleChessMove::castle_kingside(color);
         moves.push(ChessMove::Castle(castle_move));
@@ -794,6 +1114,7 @@ fn generate_castle_moves(
         && !queenside_transit_square.overlaps(occupied)
         && !queenside_rook_transit_square.overlaps(occupied)
         && !queenside_target_square.overlaps(occupied)
+        && !queenside_target_square.overlaps(attacked_squares)
     {
         let castle_move = CastleChessMove::castle_queenside(color);
         moves.push(ChessMove::Castle(castle_move));
@@ -813,94 +1134,69 @@ fn filter_moves_by_target(
 
     // Keep only moves that either:
     // 1. Are king moves (handled separately), OR
-    // 2. Land on legal target squares (capture checker or block check ray)
+    // 2. Land on legal target squares (capture checker or block check ray), OR
+    // 3. Capture en passant the pawn that just gave check by its double push
     candidates.retain(|chess_move| {
         let is_king_move = chess_move.from_square().overlaps(king_square);
-        is_king_move || chess_move.to_square().overlaps(legal_targets)
+        let captures_checker_en_passant = matches!(chess_move, ChessMove::EnPassant(_)) && {
+            let target = chess_move.to_square().to_bitboard();
+            let captured = match color {
+                Color::White => target >> 8,
+                Color::Black => target << 8,
+            };
+            captured.overlaps(legal_targets)
+        };
+        is_king_move
+            || chess_move.to_square().overlaps(legal_targets)
+            || captures_checker_en_passant
     });
 }
 
-/// Validates only king moves to ensure they don't move into check.
-/// Used when in check, where non-king moves are already guaranteed legal by check-aware generation.
+/// Removes the en passant captures that would leave the king in check.
+///
+/// Pins only cover pieces between the king and a slider, but en passant
+/// takes two pawns off a rank at once and the captured pawn may be the one
+/// shielding the king, so the king's rays are looked at again with both
+/// pawns gone. Captures that leave a knight or pawn check unanswered are
+/// already filtered out by `filter_moves_by_target`.
 #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
-fn remove_invalid_king_moves(
+fn remove_illegal_en_passant_moves(
     candidates: &mut ChessMoveList,
-    board: &mut Board,
+    board: &Board,
     color: Color,
     targets: &Targets,
 ) {
-    let mut valid_moves = ChessMoveList::with_capacity(candidates.len());
-    let king_square = board.pieces(color).locate(Piece::King);
-
-    for chess_move in candidates.drain(..) {
-        let is_king_move = chess_move.from_square().overlaps(king_square);
-
-        if is_king_move {
-            // Validate king moves by applying and checking if king is attacked
-            chess_move
-                .apply(board)
-                .expect("move application should succeed when validating moves");
-            let king = board.pieces(color).locate(Piece::King);
-            let attacked_squares = targets.generate_attack_targets(board, color.opposite());
-            chess_move
-                .undo(board)
-                .expect("move undo should succeed when validating moves");
-
-            if !king.overlaps(attacked_squares) {
-                valid_moves.push(chess_move);
-            }
-        } else {
-            // Non-king moves in check are guaranteed legal by check-aware generation
-            valid_moves.push(chess_move);
-        }
+    if board.peek_en_passant_target().is_none() {
+        return;
     }
-
-    candidates.append(&mut valid_moves);
-}
-
-#[cfg_attr(feature = "instrumentation", instrument(skip_all))]
-fn remove_invalid_moves(
-    candidates: &mut ChessMoveList,
-    board: &mut Board,
-    color: Color,
-    targets: &Targets,
-    in_check: bool,
-) {
-    let mut valid_moves = ChessMoveList::with_capacity(candidates.len());
-
-    let king_square = board.pieces(color).locate(Piece::King);
-
-    for chess_move in candidates.drain(..) {
-        // Determine if this move needs validation:
-        // When IN CHECK: All moves need validation (must address the check)
-        // When NOT in check: Only king moves and en passant need validation
-        //   - Castle moves are fully validated during generation (square occupation, attacks)
-        //   - Non-king moves are guaranteed legal by pin-aware generation
-        let is_king_move = chess_move.from_square().overlaps(king_square);
-        let is_en_passant = matches!(chess_move, ChessMove::EnPassant(_));
-        let needs_validation = in_check || is_king_move || is_en_passant;
-
-        if needs_validation {
-            // Try to apply the move - if it fails, the move is invalid (skip it)
-            if let Ok(()) = chess_move.apply(board) {
-                let king = board.pieces(color).locate(Piece::King);
-                let attacked_squares = targets.generate_attack_targets(board, color.opposite());
-                chess_move
-                    .undo(board)
-                    .expect("move undo should succeed when validating moves");
-
-                if !king.overlaps(attacked_squares) {
-                    valid_moves.push(chess_move);
-                }
-            }
-            // If apply failed, move is invalid - don't add it to valid_moves
-        } else {
-            // Pin-aware generation guarantees this move is legal
-            valid_moves.push(chess_move);
-        }
+    let king = board.pieces(color).locate(Piece::King);
+    if king.is_empty() {
+        return;
     }
+    let king_square = king.to_square();
+    let opponent = board.pieces(color.opposite());
+    let queens = opponent.locate(Piece::Queen);
+    let diagonal = opponent.locate(Piece::Bishop) | queens;
+    let orthogonal = opponent.locate(Piece::Rook) | queens;
 
-    candidates.append(&mut valid_moves);
+    candidates.retain(|chess_move| {
+        if !matches!(chess_move, ChessMove::EnPassant(_)) {
+            return true;
+        }
+        let to = chess_move.to_square().to_bitboard();
+        let captured = match color {
+            Color::White => to >> 8,
+            Color::Black => to << 8,
+        };
+        let occupied =
+            (board.occupied() & !chess_move.from_square().to_bitboard() & !captured) | to;
+        !targets
+            .bishop_attacks(king_square, occupied)
+            .overlaps(diagonal)
+            && !targets
+                .rook_attacks(king_square, occupied)
+                .overlaps(orthogonal)
+    });
 }
 
 #[cfg(test)]
@@ -1235,7 +1531,13 @@ mod tests {
         expected_moves.sort();
 
         let mut moves = smallvec![];
-        generate_king_moves(&mut moves, &board, Color::White, &Targets::default());
+        generate_king_moves(
+            &mut moves,
+            &board,
+            Color::White,
+            &Targets::default(),
+            Bitboard::ALL,
+        );
         chess_move_list_with_effect_set_to_none(&mut moves);
         moves.sort();
 
@@ -1265,7 +1567,13 @@ mod tests {
         expected_moves.sort();
 
         let mut moves = smallvec![];
-        generate_king_moves(&mut moves, &board, Color::White, &Targets::default());
+        generate_king_moves(
+            &mut moves,
+            &board,
+            Color::White,
+            &Targets::default(),
+            Bitboard::ALL,
+        );
         chess_move_list_with_effect_set_to_none(&mut moves);
         moves.sort();
 
@@ -1297,7 +1605,13 @@ mod tests {
         expected_moves.sort();
 
         let mut moves = smallvec![];
-        generate_king_moves(&mut moves, &board, Color::White, &Targets::default());
+        generate_king_moves(
+            &mut moves,
+            &board,
+            Color::White,
+            &Targets::default(),
+            Bitboard::ALL,
+        );
         chess_move_list_with_effect_set_to_none(&mut moves);
         moves.sort();
 
@@ -1360,15 +1674,13 @@ mod tests {
         ];
         expected_black_moves.sort();
 
-        let mut targets = Targets::default();
-
         let mut white_moves = smallvec![];
-        generate_castle_moves(&mut white_moves, &board, Color::White, &mut targets);
+        generate_castle_moves(&mut white_moves, &board, Color::White);
         chess_move_list_with_effect_set_to_none(&mut white_moves);
         white_moves.sort();
 
         let mut black_moves = smallvec![];
-        generate_castle_moves(&mut black_moves, &board, Color::Black, &mut targets);
+        generate_castle_moves(&mut black_moves, &board, Color::Black);
         chess_move_list_with_effect_set_to_none(&mut black_moves);
         black_moves.sort();
 
@@ -1398,21 +1710,39 @@ mod tests {
         let expected_white_moves: ChessMoveList = smallvec![castle_kingside!(Color::White)];
         let expected_black_moves: ChessMoveList = smallvec![castle_queenside!(Color::Black)];
 
-        let mut targets = Targets::default();
-        targets.generate_attack_targets(&board, Color::Black);
-
         let mut white_moves = smallvec![];
-        generate_castle_moves(&mut white_moves, &board, Color::White, &mut targets);
+        generate_castle_moves(&mut white_moves, &board, Color::White);
         chess_move_list_with_effect_set_to_none(&mut white_moves);
 
         let mut black_moves = smallvec![];
-        generate_castle_moves(&mut black_moves, &board, Color::Black, &mut targets);
+        generate_castle_moves(&mut black_moves, &board, Color::Black);
         chess_move_list_with_effect_set_to_none(&mut black_moves);
 
         assert_eq!(expected_white_moves, white_moves);
         assert_eq!(expected_black_moves, black_moves);
     }
 
+    #[test]
+    fn test_no_castling_onto_an_attacked_square() {
+        let board = chess_position! {
+            ....k...
+            ........
+            ........
+            ........
+            ........
+            ........
+            .......b
+            R...K..R
+        };
+
+        let expected_white_moves: ChessMoveList = smallvec![castle_queenside!(Color::White)];
+        let mut white_moves = smallvec![];
+        generate_castle_moves(&mut white_moves, &board, Color::White);
+        chess_move_list_with_effect_set_to_none(&mut white_moves);
+
+        assert_eq!(expected_white_moves, white_moves);
+    }
+
     #[test]
     pub fn test_generate_castle_moves_blocked() {
         let board = chess_position! {
@@ -1428,7 +1758,7 @@ mod tests {
 
         let expected_white_moves: ChessMoveList = smallvec![];
         let mut white_moves = smallvec![];
-        generate_castle_moves(&mut white_moves, &board, Color::White, &Targets::default());
+        generate_castle_moves(&mut white_moves, &board, Color::White);
         chess_move_list_with_effect_set_to_none(&mut white_moves);
 
         assert_eq!(expected_white_moves, white_moves);
@@ -1528,6 +1858,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pseudo_legal_moves_include_pinned_pieces() {
+        let mut board = chess_position! {
+            castling = "-";
+            ........
+            ........
+            ........
+            ........
+            r.N.K...
+            ........
+            ........
+            ........
+        };
+
+        let move_gen = MoveGenerator::default();
+        let legal = move_gen.generate_moves(&mut board, Color::White);
+        let pseudo_legal = move_gen.generate_pseudo_legal_moves(&mut board, Color::White);
+
+        assert!(legal.iter().all(|m| m.from_square() != C4));
+        assert_eq!(
+            pseudo_legal
+                .iter()
+                .filter(|m| m.from_square() == C4)
+                .count(),
+            8
+        );
+        assert!(pseudo_legal.contains(&std_move!(E4, D4)));
+        assert!(legal.iter().all(|m| pseudo_legal.contains(m)));
+    }
+
     #[test]
     fn test_tactical_moves_match_filtered_all_moves_starting_position() {
         let mut board = Board::default();
@@ -1554,6 +1914,7 @@ mod tests {
     #[test]
     fn test_tactical_moves_match_filtered_all_moves_complex() {
         let mut board = chess_position! {
+            castling = "-";
             r...k..r
             pp..ppbp
             ..n..np.
@@ -1563,7 +1924,6 @@ mod tests {
             PP..BPPP
             RN.QK..R
         };
-        board.lose_castle_rights(CastleRights::all());
 
         for color in [Color::White, Color::Black] {
             board.set_turn(color);
@@ -1591,6 +1951,7 @@ mod tests {
     #[test]
     fn test_tactical_moves_includes_en_passant() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-", en_passant = B6;
             ........
             ........
             ........
@@ -1600,9 +1961,6 @@ mod tests {
             ........
             K......k
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
-        board.push_en_passant_target(Some(B6));
 
         let move_gen = MoveGenerator::default();
         let tactical = move_gen.generate_tactical_moves(&mut board, Color::White);
@@ -1613,9 +1971,33 @@ mod tests {
         assert!(has_en_passant, "Tactical moves should include en passant");
     }
 
+    #[test]
+    fn test_en_passant_captures_checking_pawn() {
+        // c7-c5 checks the king on b4; taking the pawn en passant answers it
+        let mut board = chess_position! {
+            turn = Color::White, castling = "-", en_passant = C6;
+            ........
+            ........
+            ...p....
+            .Ppr....
+            .K...p.k
+            ........
+            ....P.P.
+            .R......
+        };
+
+        let move_gen = MoveGenerator::default();
+        let en_passant = ChessMove::EnPassant(EnPassantChessMove::new(B5, C6));
+        let moves = move_gen.generate_moves(&mut board, Color::White);
+        assert!(moves.contains(&en_passant), "got {:?}", moves);
+        let tactical = move_gen.generate_tactical_moves(&mut board, Color::White);
+        assert!(tactical.contains(&en_passant), "got {:?}", tactical);
+    }
+
     #[test]
     fn test_tactical_moves_includes_promotions() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             ........
             P.......
             ........
@@ -1625,8 +2007,6 @@ mod tests {
             ........
             K......k
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         let move_gen = MoveGenerator::default();
         let tactical = move_gen.generate_tactical_moves(&mut board, Color::White);
@@ -1645,6 +2025,7 @@ mod tests {
     #[test]
     fn test_tactical_moves_empty_in_quiet_position() {
         let mut board = chess_position! {
+            turn = Color::White, castling = "-";
             ........
             ........
             ........
@@ -1654,8 +2035,6 @@ mod tests {
             .P......
             K......k
         };
-        board.set_turn(Color::White);
-        board.lose_castle_rights(CastleRights::all());
 
         let move_gen = MoveGenerator::default();
         let tactical = move_gen.generate_tactical_moves(&mut board, Color::White);
@@ -1709,4 +2088,130 @@ mod tests {
             uci_moves
         );
     }
+
+    /// Stepping back along the rook's ray leaves the king in check, though
+    /// the square is behind the king and not attacked before it moves.
+    #[test]
+    fn test_king_cannot_retreat_along_the_checking_ray() {
+        let mut board = chess_position! {
+            turn = Color::White, castling = "-";
+            ....r..k
+            ........
+            ........
+            ........
+            ....K...
+            ........
+            ........
+            ........
+        };
+        let moves = MoveGenerator::default().generate_moves(&mut board, Color::White);
+        let uci_moves: Vec<String> = moves.iter().map(|m| m.to_uci()).collect();
+        assert!(!uci_moves.contains(&"e4e3".to_string()), "{:?}", uci_moves);
+        assert!(uci_moves.contains(&"e4d3".to_string()), "{:?}", uci_moves);
+    }
+
+    /// Taking en passant would empty the rank between the king and the rook.
+    #[test]
+    fn test_en_passant_cannot_expose_the_king_along_the_rank() {
+        let mut board = chess_position! {
+            turn = Color::White, castling = "-", en_passant = D6;
+            .......k
+            ........
+            ........
+            K..pP..r
+            ........
+            ........
+            ........
+            ........
+        };
+        let move_gen = MoveGenerator::default();
+        let en_passant = ChessMove::EnPassant(EnPassantChessMove::new(E5, D6));
+        assert!(move_gen
+            .generate_pseudo_legal_moves(&mut board, Color::White)
+            .contains(&en_passant));
+        assert!(!move_gen
+            .generate_moves(&mut board, Color::White)
+            .contains(&en_passant));
+        assert!(!move_gen
+            .generate_tactical_moves(&mut board, Color::White)
+            .contains(&en_passant));
+    }
+
+    /// Tactical and quiet moves split the legal moves between them, in and
+    /// out of check.
+    #[test]
+    fn test_tactical_and_quiet_moves_make_up_the_legal_moves() {
+        use std::str::FromStr;
+
+        use crate::alpha_beta_searcher::GameMove;
+        use crate::game::position_counter::PERFT_REFERENCE;
+
+        let move_gen = MoveGenerator::default();
+        let checks = [
+            "4k3/8/8/8/1b6/8/5P2/4K2R w K - 0 1",
+            "4k3/8/8/8/8/5n2/8/R3K2R w KQ - 0 1",
+            "rnbqkbnr/ppp1pppp/8/1B1p4/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 2",
+        ];
+        let fens = PERFT_REFERENCE.iter().map(|(_, fen, _)| *fen).chain(checks);
+        for fen in fens {
+            let mut board = Board::from_str(fen).unwrap();
+            let turn = board.turn();
+            let mut legal: Vec<String> = move_gen
+                .generate_moves(&mut board, turn)
+                .iter()
+                .map(|m| m.to_uci())
+                .collect();
+            let tactical = move_gen.generate_tactical_moves(&mut board, turn);
+            let quiet = move_gen.generate_quiet_moves(&mut board, turn);
+            assert!(tactical.iter().all(|m| m.is_tactical(&board)), "{}", fen);
+            assert!(quiet.iter().all(|m| !m.is_tactical(&board)), "{}", fen);
+
+            let mut split: Vec<String> =
+                tactical.iter().chain(&quiet).map(|m| m.to_uci()).collect();
+            legal.sort();
+            split.sort();
+            assert_eq!(legal, split, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_quiet_checks_are_the_quiet_moves_that_give_check() {
+        use std::str::FromStr;
+
+        use crate::game::position_counter::PERFT_REFERENCE;
+
+        let move_gen = MoveGenerator::default();
+        let checks = [
+            // Discovered checks by the knight and the king, direct ones by the rest
+            "1k6/8/8/8/8/1N6/1R6/1K5Q w - - 0 1",
+            "7k/8/8/8/3K4/8/8/B7 w - - 0 1",
+            "4k3/8/5P2/8/8/8/8/R3K3 w - - 0 1",
+            // A check blocked by a move that gives check
+            "3k4/8/8/b7/8/8/7R/4K3 w - - 0 1",
+        ];
+        let fens = PERFT_REFERENCE.iter().map(|(_, fen, _)| *fen).chain(checks);
+        for fen in fens {
+            let mut board = Board::from_str(fen).unwrap();
+            let turn = board.turn();
+            let mut expected = Vec::new();
+            for chess_move in move_gen.generate_quiet_moves(&mut board, turn) {
+                if matches!(chess_move, ChessMove::Castle(_)) {
+                    continue;
+                }
+                chess_move.apply(&mut board).unwrap();
+                if board.is_in_check(turn.opposite()) {
+                    expected.push(chess_move.to_uci());
+                }
+                chess_move.undo(&mut board).unwrap();
+            }
+            let mut checks: Vec<String> = move_gen
+                .generate_quiet_checks(&mut board, turn)
+                .iter()
+                .map(|m| m.to_uci())
+                .collect();
+            expected.sort();
+            checks.sort();
+            assert_eq!(checks, expected, "{}", fen);
+        }
+    }
 }
@@ -3,6 +3,9 @@
 //! **Performance optimizations:**
 //! - `#[inline]` on `get_rook_targets` and `get_bishop_targets`: 0.9% improvement
 //! - `#[inline(always)]` on `magic_index` for guaranteed inlining in hot paths
+//!
+//! With the `pext` feature, tables are indexed by extracting the blockers'
+//! bits under the mask (BMI2's `PEXT`) instead of with magic numbers.
 
 use common::bitboard::{
     bitboard::Bitboard,
@@ -13,6 +16,7 @@ use tracing::instrument;
 
 include!(concat!(env!("OUT_DIR"), "/magic_table.rs"));
 
+#[cfg(not(feature = "pext"))]
 pub struct MagicEntry {
     mask: u64,
     magic: u64,
@@ -20,29 +24,30 @@ pub struct MagicEntry {
     offset: u32,
 }
 
+#[cfg(feature = "pext")]
+pub struct MagicEntry {
+    mask: u64,
+    offset: u32,
+}
+
+/// Rook and bishop targets for every square and set of blockers. The tables
+/// of all squares share one, packed so that they overlap where their entries
+/// agree (see the precompile step's magic search). PEXT tables don't overlap.
 #[derive(Clone)]
 pub struct MagicTable {
-    rook_table: Vec<Bitboard>,
-    bishop_table: Vec<Bitboard>,
+    table: Vec<Bitboard>,
 }
 
 impl Default for MagicTable {
     fn default() -> Self {
-        let rook_table = make_table(
-            ROOK_TABLE_SIZE,
-            &[(1, 0), (0, -1), (-1, 0), (0, 1)],
-            ROOK_MAGICS,
-        );
-        let bishop_table = make_table(
-            BISHOP_TABLE_SIZE,
+        let mut table = vec![Bitboard::EMPTY; MAGIC_TABLE_SIZE];
+        fill_table(&mut table, &[(1, 0), (0, -1), (-1, 0), (0, 1)], ROOK_MAGICS);
+        fill_table(
+            &mut table,
             &[(1, 1), (1, -1), (-1, -1), (-1, 1)],
             BISHOP_MAGICS,
         );
-
-        Self {
-            rook_table,
-            bishop_table,
-        }
+        Self { table }
     }
 }
 
@@ -55,23 +60,18 @@ impl MagicTable {
     #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
     pub fn get_rook_targets(&self, square: Square, blockers: Bitboard) -> Bitboard {
         let magic = &ROOK_MAGICS[square.index() as usize];
-        self.rook_table[magic_index(magic, blockers)]
+        self.table[magic_index(magic, blockers)]
     }
 
     #[inline]
     #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
     pub fn get_bishop_targets(&self, square: Square, blockers: Bitboard) -> Bitboard {
         let magic = &BISHOP_MAGICS[square.index() as usize];
-        self.bishop_table[magic_index(magic, blockers)]
+        self.table[magic_index(magic, blockers)]
     }
 }
 
-fn make_table(
-    table_size: usize,
-    slider_deltas: &[(i8, i8)],
-    magics: &[MagicEntry; 64],
-) -> Vec<Bitboard> {
-    let mut table = vec![Bitboard::EMPTY; table_size];
+fn fill_table(table: &mut [Bitboard], slider_deltas: &[(i8, i8)], magics: &[MagicEntry; 64]) {
     for &square in &ORDERED_SQUARES {
         let square_bitboard = Bitboard(1 << square.index());
         let magic_entry = &magics[square.index() as usize];
@@ -90,7 +90,6 @@ fn make_table(
             }
         }
     }
-    table
 }
 
 fn slider_moves(slider_deltas: &[(i8, i8)], square: Bitboard, blockers: Bitboard) -> Bitboard {
@@ -122,6 +121,7 @@ fn try_offset(square: Bitboard, d_rank: i8, d_file: i8) -> Option<Bitboard> {
     }
 }
 
+#[cfg(not(feature = "pext"))]
 #[inline(always)]
 fn magic_index(entry: &MagicEntry, blockers: Bitboard) -> usize {
     let blockers = blockers.0 & entry.mask;
@@ -130,6 +130,40 @@ fn magic_index(entry: &MagicEntry, blockers: Bitboard) -> usize {
     entry.offset as usize + index
 }
 
+#[cfg(feature = "pext")]
+#[inline(always)]
+fn magic_index(entry: &MagicEntry, blockers: Bitboard) -> usize {
+    entry.offset as usize + pext(blockers.0, entry.mask) as usize
+}
+
+/// The bits of `value` under `mask`, packed into the low bits.
+#[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+#[inline(always)]
+fn pext(value: u64, mask: u64) -> u64 {
+    // SAFETY: the target has BMI2
+    unsafe { std::arch::x86_64::_pext_u64(value, mask) }
+}
+
+/// The bits of `value` under `mask`, packed into the low bits, without BMI2.
+#[cfg(all(
+    feature = "pext",
+    not(all(target_arch = "x86_64", target_feature = "bmi2"))
+))]
+#[inline(always)]
+fn pext(value: u64, mut mask: u64) -> u64 {
+    let mut result = 0;
+    let mut bit = 1;
+    while mask != 0 {
+        let lowest = mask & mask.wrapping_neg();
+        if value & lowest != 0 {
+            result |= bit;
+        }
+        bit <<= 1;
+        mask &= mask - 1;
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use common::bitboard::*;
@@ -183,6 +217,14 @@ mod tests {
         assert_eq!(targets, expected_targets);
     }
 
+    #[cfg(feature = "pext")]
+    #[test]
+    fn test_pext_packs_the_bits_under_the_mask() {
+        assert_eq!(pext(0b1011_0110, 0b1111_0000), 0b1011);
+        assert_eq!(pext(0b1011_0110, 0b0101_0101), 0b0110);
+        assert_eq!(pext(u64::MAX, 0), 0);
+    }
+
     #[test]
     fn test_get_queen_targets() {
         let magic_table = MagicTable::new();
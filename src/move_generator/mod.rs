@@ -2,7 +2,9 @@
 
 pub mod generator;
 mod magic_table;
+pub mod perft_hash;
 pub mod targets;
 
 pub use generator::{ChessMoveList, MoveGenerator, PAWN_PROMOTIONS};
+pub use perft_hash::PerftHash;
 pub use targets::{PieceTarget, PieceTargetList, Targets};
@@ -0,0 +1,80 @@
+//! Hash table of perft subtree counts, so that transpositions are counted
+//! once. Perft runs on all cores, so entries are written without locks: each
+//! slot stores its count alongside the key XORed with that count, and a torn
+//! write from two threads fails the key check instead of returning a wrong
+//! count.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Default)]
+struct PerftSlot {
+    checked_key: AtomicU64,
+    count: AtomicU64,
+}
+
+pub struct PerftHash {
+    slots: Vec<PerftSlot>,
+}
+
+impl PerftHash {
+    /// A table of at most `size_mb` megabytes, rounded down to a power of two
+    /// number of slots.
+    pub fn new(size_mb: usize) -> Self {
+        let slot_size = std::mem::size_of::<PerftSlot>();
+        let wanted = (size_mb.max(1) << 20) / slot_size;
+        let capacity = if wanted.is_power_of_two() {
+            wanted
+        } else {
+            wanted.next_power_of_two() >> 1
+        };
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, PerftSlot::default);
+        Self { slots }
+    }
+
+    /// The number of leaves `depth` plies below the position with `hash`, if
+    /// it has been stored. The side to move is part of the board's hash.
+    #[inline]
+    pub fn probe(&self, hash: u64, depth: u8) -> Option<usize> {
+        let key = Self::key(hash, depth);
+        let slot = &self.slots[self.index(key)];
+        let count = slot.count.load(Ordering::Relaxed);
+        (slot.checked_key.load(Ordering::Relaxed) ^ count == key).then_some(count as usize)
+    }
+
+    #[inline]
+    pub fn store(&self, hash: u64, depth: u8, count: usize) {
+        let key = Self::key(hash, depth);
+        let slot = &self.slots[self.index(key)];
+        slot.checked_key
+            .store(key ^ count as u64, Ordering::Relaxed);
+        slot.count.store(count as u64, Ordering::Relaxed);
+    }
+
+    /// Counts at different depths of one position are different entries.
+    /// The position hash already covers the side to move.
+    #[inline]
+    fn key(hash: u64, depth: u8) -> u64 {
+        hash ^ (depth as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    #[inline]
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.slots.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_are_stored_per_depth() {
+        let hash = PerftHash::new(1);
+        assert_eq!(hash.slots.len(), (1 << 20) / 16);
+        assert_eq!(hash.probe(0xdead_beef, 3), None);
+        hash.store(0xdead_beef, 3, 8_902);
+        assert_eq!(hash.probe(0xdead_beef, 3), Some(8_902));
+        assert_eq!(hash.probe(0xdead_beef, 4), None);
+    }
+}
@@ -144,6 +144,28 @@ impl Targets {
         self.magic_table.get_rook_targets(square, occupied)
     }
 
+    /// Returns the squares attacked by `piece` of `color` on the given square,
+    /// with `occupied` blocking sliding pieces.
+    #[inline]
+    pub fn attacks_from(
+        &self,
+        square: Square,
+        piece: Piece,
+        color: Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        match piece {
+            Piece::Pawn => pawn_attacks(square.to_bitboard(), color),
+            Piece::Knight => self.knights[square.index() as usize],
+            Piece::Bishop => self.bishop_attacks(square, occupied),
+            Piece::Rook => self.rook_attacks(square, occupied),
+            Piece::Queen => {
+                self.bishop_attacks(square, occupied) | self.rook_attacks(square, occupied)
+            }
+            Piece::King => self.kings[square.index() as usize],
+        }
+    }
+
     /// Calculate pinned pieces for the given color.
     ///
     /// A piece is pinned if it's on a line between the king and an opponent sliding piece,
@@ -152,7 +174,21 @@ impl Targets {
     ///
     /// Uses magic bitboards to efficiently find all pins in a single pass.
     pub fn calculate_pins(&self, board: &Board, color: Color) -> PinInfo {
-        let king = board.pieces(color).locate(Piece::King);
+        self.lone_blockers(board, color, color)
+    }
+
+    /// Calculate the pieces of `color` that give check by moving off the line
+    /// between one of `color`'s sliding pieces and the opponent's king, as
+    /// `PinInfo`: a move from a square in `pinned_pieces` uncovers check unless
+    /// its target is on that square's ray.
+    pub fn calculate_discovered_checkers(&self, board: &Board, color: Color) -> PinInfo {
+        self.lone_blockers(board, color.opposite(), color)
+    }
+
+    /// The pieces of `blocker_color` that stand alone between the king of
+    /// `king_color` and a sliding piece of the other side, with their rays.
+    fn lone_blockers(&self, board: &Board, king_color: Color, blocker_color: Color) -> PinInfo {
+        let king = board.pieces(king_color).locate(Piece::King);
 
         // If no king exists (invalid position), return empty pin info
         if king.is_empty() {
@@ -160,73 +196,67 @@ impl Targets {
         }
 
         let occupied = board.occupied();
-        let own_pieces = board.pieces(color).occupied();
-        let opponent_pieces = board.pieces(color.opposite()).occupied();
+        let blockers = board.pieces(blocker_color).occupied();
+        let attacker = board.pieces(king_color.opposite());
 
         let mut pin_info = PinInfo::empty();
 
-        // Check for pins from opponent rooks and queens (orthogonal)
-        let mut rook_attackers = (board.pieces(color.opposite()).locate(Piece::Rook)
-            | board.pieces(color.opposite()).locate(Piece::Queen))
+        // Rooks and queens of the other side that see the king through the blockers
+        let mut rook_attackers = (attacker.locate(Piece::Rook) | attacker.locate(Piece::Queen))
             & self
                 .magic_table
-                .get_rook_targets(king.to_square(), opponent_pieces);
+                .get_rook_targets(king.to_square(), occupied & !blockers);
 
         while !rook_attackers.is_empty() {
-            let attacker = rook_attackers.pop_lsb();
+            let slider = rook_attackers.pop_lsb();
 
             // Get the ray BETWEEN attacker and king by using each as a
             // blocker for the other. This prevents the ray from extending
             // beyond both pieces and counting unrelated squares.
-            let ray = self
-                .magic_table
-                .get_rook_targets(attacker.to_square(), king)
-                & self
-                    .magic_table
-                    .get_rook_targets(king.to_square(), attacker);
+            let ray = self.magic_table.get_rook_targets(slider.to_square(), king)
+                & self.magic_table.get_rook_targets(king.to_square(), slider);
 
             // Count pieces between attacker and king
             let pieces_on_ray = ray & occupied;
 
-            // Exactly one piece means it's pinned
+            // Exactly one piece means it blocks alone
             if pieces_on_ray.count_ones() == 1 {
-                // Only pin our own pieces
-                if pieces_on_ray.overlaps(own_pieces) {
+                // Only the blocking side's pieces
+                if pieces_on_ray.overlaps(blockers) {
                     pin_info.pinned_pieces |= pieces_on_ray;
                     // Pin ray includes the attacker (can capture) and squares between
-                    pin_info.pin_rays[pieces_on_ray.to_square().index() as usize] = ray | attacker;
+                    pin_info.pin_rays[pieces_on_ray.to_square().index() as usize] = ray | slider;
                 }
             }
         }
 
-        // Check for pins from opponent bishops and queens (diagonal)
-        let mut bishop_attackers = (board.pieces(color.opposite()).locate(Piece::Bishop)
-            | board.pieces(color.opposite()).locate(Piece::Queen))
+        // Bishops and queens likewise, along the diagonals
+        let mut bishop_attackers = (attacker.locate(Piece::Bishop) | attacker.locate(Piece::Queen))
             & self
                 .magic_table
-                .get_bishop_targets(king.to_square(), opponent_pieces);
+                .get_bishop_targets(king.to_square(), occupied & !blockers);
 
         while !bishop_attackers.is_empty() {
-            let attacker = bishop_attackers.pop_lsb();
+            let slider = bishop_attackers.pop_lsb();
 
             // Get the ray BETWEEN attacker and king (same blocker technique)
             let ray = self
                 .magic_table
-                .get_bishop_targets(attacker.to_square(), king)
+                .get_bishop_targets(slider.to_square(), king)
                 & self
                     .magic_table
-                    .get_bishop_targets(king.to_square(), attacker);
+                    .get_bishop_targets(king.to_square(), slider);
 
             // Count pieces between attacker and king
             let pieces_on_ray = ray & occupied;
 
-            // Exactly one piece means it's pinned
+            // Exactly one piece means it blocks alone
             if pieces_on_ray.count_ones() == 1 {
-                // Only pin our own pieces
-                if pieces_on_ray.overlaps(own_pieces) {
+                // Only the blocking side's pieces
+                if pieces_on_ray.overlaps(blockers) {
                     pin_info.pinned_pieces |= pieces_on_ray;
                     // Pin ray includes the attacker (can capture) and squares between
-                    pin_info.pin_rays[pieces_on_ray.to_square().index() as usize] = ray | attacker;
+                    pin_info.pin_rays[pieces_on_ray.to_square().index() as usize] = ray | slider;
                 }
             }
         }
@@ -247,46 +277,17 @@ impl Targets {
             return CheckInfo::empty();
         }
 
-        let opponent_attacks = self.generate_attack_targets(board, color.opposite());
-
-        // If king not attacked, no check
-        if !opponent_attacks.overlaps(king) {
-            return CheckInfo::empty();
+        let mut check_info = CheckInfo {
+            checkers: board.checkers(color),
+            ..CheckInfo::empty()
+        };
+        if !check_info.in_check() {
+            return check_info;
         }
 
-        let mut check_info = CheckInfo::empty();
-        let occupied = board.occupied();
-
-        // Find checking pieces
-        // Check for pawn attacks
-        let opponent_pawns = board.pieces(color.opposite()).locate(Piece::Pawn);
-        let pawn_attack_squares = match color {
-            // White king attacked by black pawns from above
-            Color::White => (king << 7 & !Bitboard::H_FILE) | (king << 9 & !Bitboard::A_FILE),
-            // Black king attacked by white pawns from below
-            Color::Black => (king >> 7 & !Bitboard::A_FILE) | (king >> 9 & !Bitboard::H_FILE),
-        };
-        check_info.checkers |= opponent_pawns & pawn_attack_squares;
-
-        // Check for knight attacks
-        let opponent_knights = board.pieces(color.opposite()).locate(Piece::Knight);
-        let knight_attacks = self.knights[king.to_square().index() as usize];
-        check_info.checkers |= opponent_knights & knight_attacks;
-
-        // Check for sliding piece attacks (rooks, bishops, queens)
-        let rook_attacks = self
-            .magic_table
-            .get_rook_targets(king.to_square(), occupied);
-        let opponent_rooks = board.pieces(color.opposite()).locate(Piece::Rook)
-            | board.pieces(color.opposite()).locate(Piece::Queen);
-        check_info.checkers |= opponent_rooks & rook_attacks;
-
-        let bishop_attacks = self
-            .magic_table
-            .get_bishop_targets(king.to_square(), occupied);
-        let opponent_bishops = board.pieces(color.opposite()).locate(Piece::Bishop)
-            | board.pieces(color.opposite()).locate(Piece::Queen);
-        check_info.checkers |= opponent_bishops & bishop_attacks;
+        let opponent = board.pieces(color.opposite());
+        let opponent_rooks = opponent.locate(Piece::Rook) | opponent.locate(Piece::Queen);
+        let opponent_bishops = opponent.locate(Piece::Bishop) | opponent.locate(Piece::Queen);
 
         // Calculate check ray for blocking (only for single sliding checks)
         if check_info.checkers.count_ones() == 1 {
@@ -328,6 +329,31 @@ impl Targets {
         check_info
     }
 
+    /// Squares the king of `color` may not move to: those the opponent
+    /// attacks, including its defended pieces, and the squares behind the
+    /// king on the rays of the sliding `checkers`, which the king no longer
+    /// blocks once it steps away along them.
+    pub fn king_danger_squares(&self, board: &Board, color: Color, checkers: Bitboard) -> Bitboard {
+        let mut danger = board.attacks(color.opposite());
+        let opponent = board.pieces(color.opposite());
+        let queens = opponent.locate(Piece::Queen);
+        let diagonal = opponent.locate(Piece::Bishop) | queens;
+        let orthogonal = opponent.locate(Piece::Rook) | queens;
+
+        let occupied = board.occupied() & !board.pieces(color).locate(Piece::King);
+        let mut sliders = checkers & (diagonal | orthogonal);
+        while !sliders.is_empty() {
+            let slider = sliders.pop_lsb_as_square();
+            if slider.overlaps(diagonal) {
+                danger |= self.bishop_attacks(slider, occupied);
+            }
+            if slider.overlaps(orthogonal) {
+                danger |= self.rook_attacks(slider, occupied);
+            }
+        }
+        danger
+    }
+
     #[cfg_attr(feature = "instrumentation", instrument(skip_all))]
     pub fn generate_attack_targets(&self, board: &Board, color: Color) -> Bitboard {
         let mut attack_targets = Bitboard::EMPTY;
@@ -558,8 +584,12 @@ pub fn generate_pawn_attack_targets(
 }
 
 pub fn generate_pawn_attack_targets_bitboard(board: &Board, color: Color) -> Bitboard {
-    let pawns = board.pieces(color).locate(Piece::Pawn);
+    pawn_attacks(board.pieces(color).locate(Piece::Pawn), color)
+}
 
+/// The squares attacked by `color`'s pawns on `pawns`.
+#[inline]
+fn pawn_attacks(pawns: Bitboard, color: Color) -> Bitboard {
     match color {
         Color::White => {
             let attacks_west = (pawns << 9) & !Bitboard::A_FILE;
@@ -625,7 +655,6 @@ pub fn generate_king_targets_table() -> [Bitboard; 64] {
 
 #[cfg(test)]
 mod tests {
-    use crate::chess_move::chess_move_effect::ChessMoveEffect;
     use common::bitboard::*;
 
     use super::*;
@@ -0,0 +1,212 @@
+//! Requests and responses of the server's analysis endpoint.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::alpha_beta_searcher::SearchLimits;
+use crate::board::Board;
+use crate::game::engine::{position_after_moves, Engine, EngineConfig, EngineError};
+use crate::input_handler::fen::{parse_fen, FenParseError};
+
+/// Longest principal variation in a response.
+const MAX_PV_LENGTH: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum AnalysisError {
+    #[error("Invalid request: {error}")]
+    InvalidRequest { error: String },
+    #[error("Invalid FEN: {error}")]
+    InvalidFen { error: FenParseError },
+    #[error("{error}")]
+    IllegalMove { error: String },
+    #[error("There are no legal moves in the given position")]
+    NoLegalMoves,
+    #[error("Search failed: {error}")]
+    Search { error: EngineError },
+}
+
+impl AnalysisError {
+    /// HTTP status of the error's response: the client's mistake, or the
+    /// server's.
+    pub fn status(&self) -> u16 {
+        match self {
+            AnalysisError::Search { .. } => 500,
+            _ => 400,
+        }
+    }
+}
+
+/// Body of a `POST /analyze`
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct AnalysisRequest {
+    pub fen: String,
+    /// Moves (SAN or UCI) to play from `fen` before searching.
+    #[serde(default)]
+    pub moves: Vec<String>,
+    /// Plies to search, up to the server's maximum.
+    pub depth: Option<u8>,
+    /// Milliseconds to search, up to the server's maximum. Takes precedence
+    /// over `depth`.
+    pub movetime: Option<u64>,
+}
+
+impl AnalysisRequest {
+    pub fn from_json(body: &str) -> Result<Self, AnalysisError> {
+        serde_json::from_str(body).map_err(|error| AnalysisError::InvalidRequest {
+            error: error.to_string(),
+        })
+    }
+}
+
+/// How far the server lets one request search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalysisLimits {
+    /// Depth of requests that don't give one.
+    pub default_depth: u8,
+    pub max_depth: u8,
+    pub max_movetime: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct MoveReport {
+    pub uci: String,
+    pub san: String,
+}
+
+/// Body of a successful `POST /analyze`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AnalysisResponse {
+    /// The position searched, after any requested moves.
+    pub fen: String,
+    pub best_move: MoveReport,
+    /// Centipawns from White's perspective.
+    pub score_cp: Option<i16>,
+    /// Moves to checkmate, when the best line ends in one.
+    pub mate_in: Option<u8>,
+    pub pv: Vec<MoveReport>,
+    /// Deepest iteration the search completed.
+    pub depth: Option<u8>,
+    pub nodes: usize,
+    pub time_ms: u128,
+}
+
+/// The position after playing `moves` (SAN or UCI) from `fen`.
+pub fn requested_position(fen: &str, moves: &[String]) -> Result<Board, AnalysisError> {
+    let board = parse_fen(fen).map_err(|error| AnalysisError::InvalidFen { error })?;
+    position_after_moves(board, moves).map_err(|error| AnalysisError::IllegalMove { error })
+}
+
+/// Search the requested position within `limits`. The opening book is not
+/// consulted, so every answer comes with a score.
+pub fn analyze(
+    request: &AnalysisRequest,
+    limits: &AnalysisLimits,
+) -> Result<AnalysisResponse, AnalysisError> {
+    let board = requested_position(&request.fen, &request.moves)?;
+
+    let depth = request
+        .depth
+        .unwrap_or(limits.default_depth)
+        .clamp(1, limits.max_depth);
+    let mut engine = Engine::with_config(EngineConfig {
+        search_depth: depth,
+        starting_position: board,
+    });
+    engine.set_search_limits(SearchLimits {
+        movetime: request
+            .movetime
+            .map(|ms| Duration::from_millis(ms).min(limits.max_movetime)),
+        nodes: None,
+        ..SearchLimits::default()
+    });
+
+    let valid_moves = engine.get_valid_moves();
+    if valid_moves.is_empty() {
+        return Err(AnalysisError::NoLegalMoves);
+    }
+
+    let start = Instant::now();
+    let best_move = engine
+        .analyze_position()
+        .map_err(|error| AnalysisError::Search { error })?;
+    let time_ms = start.elapsed().as_millis();
+
+    let pv: Vec<MoveReport> = engine
+        .principal_variation(&best_move, MAX_PV_LENGTH)
+        .into_iter()
+        .map(|(chess_move, san)| MoveReport {
+            uci: chess_move.to_uci(),
+            san,
+        })
+        .collect();
+    let best_move = pv.first().cloned().ok_or(AnalysisError::NoLegalMoves)?;
+    let stats = engine.get_search_stats();
+    Ok(AnalysisResponse {
+        fen: engine.board().to_fen(),
+        best_move,
+        score_cp: stats.last_score,
+        mate_in: stats.mate_in,
+        pv,
+        depth: engine.last_completed_depth(),
+        nodes: stats.positions_searched,
+        time_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: AnalysisLimits = AnalysisLimits {
+        default_depth: 3,
+        max_depth: 4,
+        max_movetime: Duration::from_millis(100),
+    };
+
+    #[test]
+    fn test_analysis_finds_a_mate_after_the_requested_moves() {
+        let request = AnalysisRequest::from_json(
+            r#"{"fen": "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "moves": ["f3", "e5", "g4"], "depth": 9}"#,
+        )
+        .unwrap();
+        let response = analyze(&request, &LIMITS).unwrap();
+        assert_eq!(response.best_move.san, "Qh4#");
+        assert_eq!(response.best_move.uci, "d8h4");
+        assert_eq!(response.mate_in, Some(1));
+        assert!(response.score_cp.unwrap() < 0);
+        assert!(response.depth.unwrap() <= LIMITS.max_depth);
+    }
+
+    #[test]
+    fn test_bad_requests_are_the_clients_errors() {
+        let unknown_field =
+            AnalysisRequest::from_json(r#"{"fen": "8/8/8/8/8/8/8/8 w - - 0 1", "dept": 3}"#);
+        assert!(matches!(
+            unknown_field,
+            Err(AnalysisError::InvalidRequest { .. })
+        ));
+
+        let request = AnalysisRequest {
+            fen: "not a position".to_string(),
+            moves: Vec::new(),
+            depth: None,
+            movetime: None,
+        };
+        let error = analyze(&request, &LIMITS).unwrap_err();
+        assert!(matches!(error, AnalysisError::InvalidFen { .. }));
+        assert_eq!(error.status(), 400);
+
+        let checkmated = AnalysisRequest {
+            fen: "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3".to_string(),
+            ..request
+        };
+        assert!(matches!(
+            analyze(&checkmated, &LIMITS),
+            Err(AnalysisError::NoLegalMoves)
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+//! The HTTP front end: routes requests, queues analyses for the worker pool
+//! and starts the WebSocket listener when one is configured.
+
+use std::io::Read;
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use log::warn;
+use thiserror::Error;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::analysis::{analyze, AnalysisLimits, AnalysisRequest};
+use super::stream;
+
+/// Largest request body read, which is plenty for a FEN and a game's moves.
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("Could not listen on {address}: {error}")]
+    Bind { address: String, error: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Address to listen on, such as `127.0.0.1:8080`.
+    pub address: String,
+    /// Searches run at the same time.
+    pub workers: usize,
+    /// Requests waiting for a worker before more are turned away.
+    pub queue_size: usize,
+    pub limits: AnalysisLimits,
+    /// Address to stream analysis on over WebSocket, if any.
+    pub stream_address: Option<String>,
+    /// WebSocket connections open at the same time, each with its own search.
+    pub max_streams: usize,
+}
+
+/// Listen on the configured address and answer requests until the process
+/// is stopped.
+pub fn serve(config: ServerConfig) -> Result<(), ServerError> {
+    let server = Server::http(&config.address).map_err(|error| ServerError::Bind {
+        address: config.address.clone(),
+        error: error.to_string(),
+    })?;
+    println!(
+        "Serving analysis on http://{} with {} workers",
+        config.address, config.workers
+    );
+    if let Some(address) = &config.stream_address {
+        let listener = TcpListener::bind(address).map_err(|error| ServerError::Bind {
+            address: address.clone(),
+            error: error.to_string(),
+        })?;
+        println!("Streaming analysis on ws://{}", address);
+        let max_streams = config.max_streams;
+        thread::spawn(move || stream::accept_streams(listener, max_streams));
+    }
+
+    let (jobs, queue) = mpsc::sync_channel::<Request>(config.queue_size);
+    let queue = Arc::new(Mutex::new(queue));
+    for _ in 0..config.workers.max(1) {
+        let queue = queue.clone();
+        let limits = config.limits;
+        thread::spawn(move || run_worker(&queue, &limits));
+    }
+
+    for request in server.incoming_requests() {
+        match (request.method(), request.url()) {
+            (Method::Post, "/analyze") => match jobs.try_send(request) {
+                Ok(()) => {}
+                Err(TrySendError::Full(request)) | Err(TrySendError::Disconnected(request)) => {
+                    respond_error(request, 503, "The server is busy, try again later");
+                }
+            },
+            (Method::Get, "/health") => {
+                respond_json(request, 200, &serde_json::json!({ "status": "ok" }));
+            }
+            (_, "/analyze") | (_, "/health") => {
+                respond_error(request, 405, "Method not allowed");
+            }
+            _ => respond_error(request, 404, "Not found"),
+        }
+    }
+    Ok(())
+}
+
+/// Answer queued analysis requests, one at a time, until the server stops.
+fn run_worker(queue: &Mutex<Receiver<Request>>, limits: &AnalysisLimits) {
+    loop {
+        // The lock is held only while waiting, so idle workers queue up for
+        // the next request and busy ones don't block them
+        let next = queue.lock().unwrap().recv();
+        let Ok(mut request) = next else {
+            return;
+        };
+
+        let mut body = String::new();
+        let read = request
+            .as_reader()
+            .take(MAX_BODY_BYTES)
+            .read_to_string(&mut body);
+        if read.is_err() {
+            respond_error(request, 400, "The request body is not UTF-8 text");
+            continue;
+        }
+
+        match AnalysisRequest::from_json(&body).and_then(|analysis| analyze(&analysis, limits)) {
+            Ok(response) => respond_json(request, 200, &response),
+            Err(error) => {
+                let status = error.status();
+                respond_error(request, status, &error.to_string());
+            }
+        }
+    }
+}
+
+fn respond_error(request: Request, status: u16, message: &str) {
+    respond_json(request, status, &serde_json::json!({ "error": message }));
+}
+
+fn respond_json(request: Request, status: u16, body: &impl serde::Serialize) {
+    let body = serde_json::to_string(body).expect("response should serialize");
+    let header = Header::from_bytes("Content-Type", "application/json")
+        .expect("content type header should be valid");
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(error) = request.respond(response) {
+        warn!("Failed to send a response: {}", error);
+    }
+}
@@ -0,0 +1,24 @@
+//! HTTP analysis server
+//!
+//! A small REST API over the engine, for tools that would rather send a
+//! request than drive a UCI process:
+//!
+//! * `POST /analyze` with a JSON body `{"fen": ..., "moves": [...], "depth": N,
+//!   "movetime": ms}` (only `fen` is required) answers with the best move,
+//!   score and principal variation as JSON. See `AnalysisRequest` and
+//!   `AnalysisResponse`.
+//! * `GET /health` answers `{"status": "ok"}`.
+//!
+//! With a stream address, live analysis is also served over WebSocket there;
+//! see `stream`.
+//!
+//! Searches run on a fixed pool of worker threads, each taking one request at
+//! a time from a bounded queue. When the queue is full, requests are turned
+//! away with `503 Service Unavailable` rather than piling up.
+
+pub mod analysis;
+mod http;
+pub mod stream;
+
+pub use analysis::{analyze, AnalysisError, AnalysisLimits, AnalysisRequest, AnalysisResponse};
+pub use http::{serve, ServerConfig, ServerError};
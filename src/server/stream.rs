@@ -0,0 +1,310 @@
+//! Streaming analysis over WebSocket
+//!
+//! Each connection analyzes one position at a time, deepening until it is
+//! stopped or given another position, and reports every completed depth the
+//! way a UCI engine streams `info` lines. Messages are JSON objects tagged
+//! with a `type`.
+//!
+//! From the client:
+//! * `{"type": "position", "fen": ..., "moves": [...]}` starts analyzing a
+//!   position (`moves` is optional), replacing any analysis in progress.
+//! * `{"type": "stop"}` stops the analysis.
+//!
+//! From the server:
+//! * `{"type": "info", "depth": 12, "score_cp": 31, "mate_in": null, "nps": ..,
+//!   "pv": ["e4", "e5", ...]}` after each completed depth.
+//! * `{"type": "stopped", "best_move": "e4", "depth": 12}` once stopped.
+//! * `{"type": "error", "error": ...}` for a message it could not act on.
+
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tungstenite::{Message, WebSocket};
+
+use super::analysis::{requested_position, AnalysisError};
+use crate::game::analysis::{Analysis, AnalysisLine};
+use crate::move_generator::MoveGenerator;
+
+/// How long a connection waits for a message before sending the analysis
+/// updates that came in meanwhile.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Error, Debug)]
+enum StreamError {
+    #[error("WebSocket error: {error}")]
+    Socket { error: Box<tungstenite::Error> },
+}
+
+impl From<tungstenite::Error> for StreamError {
+    fn from(error: tungstenite::Error) -> Self {
+        StreamError::Socket {
+            error: Box::new(error),
+        }
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        tungstenite::Error::Io(error).into()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ClientMessage {
+    Position {
+        fen: String,
+        #[serde(default)]
+        moves: Vec<String>,
+    },
+    Stop,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServerMessage {
+    Info {
+        depth: u8,
+        /// Centipawns from White's perspective.
+        score_cp: i16,
+        mate_in: Option<u8>,
+        nps: u64,
+        /// The expected continuation in SAN, best move first.
+        pv: Vec<String>,
+    },
+    Stopped {
+        best_move: Option<String>,
+        depth: Option<u8>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl From<&AnalysisLine> for ServerMessage {
+    fn from(line: &AnalysisLine) -> Self {
+        ServerMessage::Info {
+            depth: line.depth,
+            score_cp: line.score,
+            mate_in: line.mate_in,
+            nps: line.nodes_per_second,
+            pv: line.principal_variation.clone(),
+        }
+    }
+}
+
+/// Accept WebSocket connections from `listener`, each on its own thread,
+/// until the process is stopped. Connections past `max_streams` are turned
+/// away.
+pub fn accept_streams(listener: TcpListener, max_streams: usize) {
+    let open_streams = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if open_streams.fetch_add(1, Ordering::SeqCst) >= max_streams {
+            open_streams.fetch_sub(1, Ordering::SeqCst);
+            // Nothing to say without a handshake; closing is the refusal
+            drop(stream);
+            continue;
+        }
+        let open_streams = open_streams.clone();
+        thread::spawn(move || {
+            if let Err(error) = run_connection(stream) {
+                warn!("Analysis stream closed: {}", error);
+            }
+            open_streams.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+/// What a connection is analyzing: the search, and the updates it sends
+/// tagged with the hash of their position
+struct Session {
+    analysis: Option<Analysis>,
+    sender: Sender<(u64, AnalysisLine)>,
+    updates: Receiver<(u64, AnalysisLine)>,
+    move_generator: MoveGenerator,
+}
+
+impl Session {
+    fn new() -> Self {
+        let (sender, updates) = mpsc::channel();
+        Self {
+            analysis: None,
+            sender,
+            updates,
+            move_generator: MoveGenerator::default(),
+        }
+    }
+
+    /// Act on one client message, returning the replies to send at once.
+    fn handle(&mut self, text: &str) -> Vec<ServerMessage> {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(error) => {
+                return vec![error_message(AnalysisError::InvalidRequest {
+                    error: error.to_string(),
+                })]
+            }
+        };
+        match message {
+            ClientMessage::Position { fen, moves } => {
+                // Stop the old search first and drop what it sent, so none of
+                // it is taken for the new position's
+                self.analysis = None;
+                self.updates.try_iter().for_each(drop);
+                match self.start(&fen, &moves) {
+                    Ok(analysis) => {
+                        self.analysis = Some(analysis);
+                        Vec::new()
+                    }
+                    Err(error) => vec![error_message(error)],
+                }
+            }
+            ClientMessage::Stop => {
+                let latest = self.analysis.take().and_then(|analysis| analysis.latest());
+                vec![ServerMessage::Stopped {
+                    best_move: latest
+                        .as_ref()
+                        .and_then(|line| line.principal_variation.first().cloned()),
+                    depth: latest.map(|line| line.depth),
+                }]
+            }
+        }
+    }
+
+    fn start(&self, fen: &str, moves: &[String]) -> Result<Analysis, AnalysisError> {
+        let mut board = requested_position(fen, moves)?;
+        let turn = board.turn();
+        if self
+            .move_generator
+            .generate_moves(&mut board, turn)
+            .is_empty()
+        {
+            return Err(AnalysisError::NoLegalMoves);
+        }
+        let hash = board.current_position_hash();
+        let sender = self.sender.clone();
+        Ok(Analysis::start(board, vec![hash], move |line| {
+            let _ = sender.send((hash, line.clone()));
+        }))
+    }
+
+    /// The updates received since the last call that belong to the current
+    /// analysis.
+    fn pending_updates(&self) -> Vec<ServerMessage> {
+        let current = self.analysis.as_ref().map(Analysis::position_hash);
+        self.updates
+            .try_iter()
+            .filter(|(hash, _)| Some(*hash) == current)
+            .map(|(_, line)| ServerMessage::from(&line))
+            .collect()
+    }
+}
+
+fn error_message(error: AnalysisError) -> ServerMessage {
+    ServerMessage::Error {
+        error: error.to_string(),
+    }
+}
+
+fn run_connection(stream: TcpStream) -> Result<(), StreamError> {
+    let mut socket = tungstenite::accept(stream).map_err(|error| match error {
+        tungstenite::HandshakeError::Failure(error) => error,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(io::ErrorKind::WouldBlock.into())
+        }
+    })?;
+    // Reads time out so analysis updates are sent while the client is quiet
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    let mut session = Session::new();
+    loop {
+        let replies = match socket.read() {
+            Ok(Message::Text(text)) => session.handle(&text),
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => Vec::new(),
+            Err(tungstenite::Error::Io(error))
+                if matches!(
+                    error.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Vec::new()
+            }
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        for message in replies.into_iter().chain(session.pending_updates()) {
+            send(&mut socket, &message)?;
+        }
+    }
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &ServerMessage) -> Result<(), StreamError> {
+    let text = serde_json::to_string(message).expect("message should serialize");
+    socket.send(Message::Text(text))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_position_streams_updates_until_stopped() {
+        let mut session = Session::new();
+        let replies =
+            session.handle(r#"{"type": "position", "fen": "1Q6/8/8/8/8/8/k1K5/8 w - - 0 1"}"#);
+        assert!(replies.is_empty());
+
+        let mut streamed = Vec::new();
+        while streamed.len() < 2 {
+            thread::sleep(Duration::from_millis(10));
+            streamed.extend(session.pending_updates());
+        }
+        let ServerMessage::Info { depth, pv, .. } = &streamed[0] else {
+            panic!("expected an info message, got {:?}", streamed[0]);
+        };
+        assert_eq!(*depth, 1);
+        assert!(pv[0].ends_with('#'));
+
+        let replies = session.handle(r#"{"type": "stop"}"#);
+        assert!(matches!(
+            &replies[..],
+            [ServerMessage::Stopped {
+                best_move: Some(_),
+                depth: Some(_)
+            }]
+        ));
+        assert!(session.pending_updates().is_empty());
+    }
+
+    #[test]
+    fn test_bad_messages_are_answered_with_errors() {
+        let mut session = Session::new();
+        for text in [
+            "not json",
+            r#"{"type": "go"}"#,
+            r#"{"type": "position", "fen": "not a position"}"#,
+            r#"{"type": "position", "fen": "7k/8/8/8/8/8/8/K6q w - - 0 1", "moves": ["Kb1"]}"#,
+        ] {
+            let replies = session.handle(text);
+            assert!(
+                matches!(&replies[..], [ServerMessage::Error { .. }]),
+                "{} got {:?}",
+                text,
+                replies
+            );
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Live analysis panel for TUI rendering
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use crossterm::{cursor, queue};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::Rect,
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::game::analysis::AnalysisLine;
+use crate::tui::eval_bar_widget::score_label;
+use crate::tui::Theme;
+
+/// Number of best moves the panel follows, one line each.
+pub const ANALYSIS_LINES: usize = 3;
+
+/// Height of the panel, including its border: the depth and speed, then a row
+/// per line.
+pub const ANALYSIS_PANEL_HEIGHT: u16 = 3 + ANALYSIS_LINES as u16;
+
+/// Search speed in thousands or millions of nodes per second.
+fn format_speed(nodes_per_second: u64) -> String {
+    if nodes_per_second >= 1_000_000 {
+        format!("{:.1} MN/s", nodes_per_second as f64 / 1_000_000.0)
+    } else {
+        format!("{} kN/s", nodes_per_second / 1000)
+    }
+}
+
+/// Widget that renders the latest lines of a background analysis: the depth
+/// and speed, then the score and expected continuation of each line
+pub struct AnalysisWidget<'a> {
+    lines: &'a [AnalysisLine],
+    finished: bool,
+    theme: &'a Theme,
+}
+
+impl<'a> AnalysisWidget<'a> {
+    pub fn new(lines: &'a [AnalysisLine], theme: &'a Theme) -> Self {
+        Self {
+            lines,
+            finished: false,
+            theme,
+        }
+    }
+
+    /// Mark the search as over, so missing lines mean there was nothing to
+    /// search.
+    pub fn finished(mut self, finished: bool) -> Self {
+        self.finished = finished;
+        self
+    }
+}
+
+impl Widget for AnalysisWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Analysis (F2 to hide)")
+            .border_style(self.theme.border_style());
+
+        let lines = match self.lines.first() {
+            Some(best) => {
+                let mut lines = vec![Line::from(format!(
+                    "Depth: {}  {}",
+                    best.depth,
+                    format_speed(best.nodes_per_second)
+                ))];
+                lines.extend(self.lines.iter().map(|line| {
+                    Line::from(format!(
+                        "{:>5}  {}",
+                        score_label(line.score, line.mate_in),
+                        line.principal_variation.join(" ")
+                    ))
+                }));
+                lines
+            }
+            None if self.finished => vec![Line::from("No legal moves")],
+            None => vec![Line::from("Thinking...")],
+        };
+        // One row per line, cut off at the border rather than wrapped
+        Paragraph::new(lines)
+            .block(block)
+            .style(self.theme.text_style())
+            .render(area, buf);
+    }
+}
+
+/// Repaints the analysis panel drawn in the last frame as new lines arrive
+/// from the search thread, without waiting for the next frame.
+pub struct AnalysisPainter {
+    /// Where the panel was drawn, or None while it is hidden.
+    area: Arc<Mutex<Option<Rect>>>,
+    theme: Theme,
+}
+
+impl AnalysisPainter {
+    pub fn new(area: Arc<Mutex<Option<Rect>>>, theme: Theme) -> Self {
+        Self { area, theme }
+    }
+
+    /// Draw `lines` in the panel, leaving the cursor where it was.
+    pub fn paint(&self, lines: &[AnalysisLine]) -> io::Result<()> {
+        let Some(area) = *self.area.lock().unwrap() else {
+            return Ok(());
+        };
+        let mut buf = Buffer::empty(area);
+        AnalysisWidget::new(lines, &self.theme).render(area, &mut buf);
+
+        let mut stdout = io::stdout().lock();
+        queue!(stdout, cursor::SavePosition)?;
+        let mut backend = CrosstermBackend::new(&mut stdout);
+        let cells = area
+            .positions()
+            .map(|position| (position.x, position.y, &buf[position]));
+        backend.draw(cells)?;
+        Backend::flush(&mut backend)?;
+        queue!(stdout, cursor::RestorePosition)?;
+        stdout.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_line_is_drawn_below_the_depth() {
+        let line = |score, moves: &[&str]| AnalysisLine {
+            depth: 9,
+            score,
+            nodes: 1000,
+            nodes_per_second: 850_400,
+            elapsed: std::time::Duration::from_secs(1),
+            mate_in: None,
+            principal_variation: moves.iter().map(|m| m.to_string()).collect(),
+            principal_variation_uci: Vec::new(),
+        };
+        let lines = [line(30, &["e4", "e5"]), line(-20, &["d4", "d5"])];
+        let theme = Theme::default();
+        let area = Rect::new(0, 0, 30, ANALYSIS_PANEL_HEIGHT);
+        let mut buf = Buffer::empty(area);
+        AnalysisWidget::new(&lines, &theme).render(area, &mut buf);
+
+        let row = |y| {
+            (1..area.width - 1)
+                .map(|x| buf[(x, y)].symbol())
+                .collect::<String>()
+        };
+        assert_eq!(row(1).trim_end(), "Depth: 9  850 kN/s");
+        assert_eq!(row(2).trim_end(), " +0.3  e4 e5");
+        assert_eq!(row(3).trim_end(), " -0.2  d4 d5");
+    }
+
+    #[test]
+    fn test_format_speed() {
+        assert_eq!(format_speed(850_400), "850 kN/s");
+        assert_eq!(format_speed(2_345_000), "2.3 MN/s");
+    }
+}
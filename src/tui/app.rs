@@ -1,32 +1,170 @@
 //! Main TUI application state and rendering
 
 use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
+use common::bitboard::Square;
 use crossterm::{
     cursor,
-    event::{self, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute, terminal,
 };
 use ratatui::{
-    backend::CrosstermBackend,
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
     Terminal,
 };
 
 use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::{Board, Variant};
+use crate::chess_move::chess_move_effect::ChessMoveEffect;
 use crate::chess_move::ChessMove;
 use crate::evaluate::GameEnding;
-use crate::game::engine::Engine;
-use crate::tui::{board_widget::BoardWidget, Theme};
+use crate::game::analysis::Analysis;
+use crate::game::clock::GameClock;
+use crate::game::engine::{Engine, EngineSettings};
+use crate::game::events::GameOverReason;
+use crate::game::game_file::GameFileAction;
+use crate::game::pgn::PgnResult;
+use crate::input_handler::Navigation;
+use crate::tui::{
+    analysis_widget::{AnalysisPainter, AnalysisWidget, ANALYSIS_LINES, ANALYSIS_PANEL_HEIGHT},
+    board_widget::{BoardGeometry, BoardWidget},
+    clock_widget::{ClockTicker, ClockWidget},
+    config::{self, Preferences, CONFIG_FILE},
+    eval_bar_widget::{EvalBarWidget, EVAL_BAR_WIDTH},
+    file_dialog_widget::{dialog_area, DialogResult, FileDialog, FileDialogWidget},
+    game_over_widget::{overlay_area, GameOverWidget},
+    move_list_widget::{MoveListState, MoveListWidget},
+    promotion_widget::{piece_for_key, PromotionWidget},
+    settings_widget::{settings_area, Setting, SettingsWidget, MAX_DEPTH, MOVETIMES},
+    theme::{Coordinates, PieceSet},
+    Theme,
+};
 
 /// Game state information for rendering
+#[derive(Clone, Copy)]
 struct GameState<'a> {
     current_turn: Color,
     last_move: Option<(&'a ChessMove, &'a str)>,
     opening_name: Option<&'a str>,
     human_color: Option<Color>,
     game_ending: Option<&'a GameEnding>,
+    /// Rules of the game, which name a variant win.
+    variant: Variant,
+    /// Replaces the default prompt while the game is in progress.
+    status: Option<&'a str>,
+    /// Why the game ended when it was not decided on the board, e.g. on time.
+    termination: Option<&'a str>,
+    /// Result of the game and why it ended, once it is over.
+    game_over: Option<(PgnResult, GameOverReason)>,
+    clock: Option<&'a GameClock>,
+    /// Side whose pieces are at the bottom of the board.
+    bottom: Color,
+    /// Piece picked up with the mouse.
+    selected_square: Option<Square>,
+    /// Search of the position on the board, when the analysis panel is shown.
+    analysis: Option<&'a Analysis>,
+    /// Setting marked in the settings menu, while it is open.
+    settings: Option<Setting>,
+    preferences: &'a Preferences,
+    /// Engine settings shown in the settings menu.
+    engine_settings: Option<EngineSettings>,
+    /// Outcome of saving the last change made in the settings menu.
+    settings_note: Option<&'a str>,
+    /// Position drawn instead of the one on display, while the last move
+    /// slides into place.
+    animation: Option<&'a Board>,
+    /// Leave the pieces off the board, in a blindfold game.
+    hide_pieces: bool,
+}
+
+impl GameState<'_> {
+    fn is_human_turn(&self) -> bool {
+        self.human_color == Some(self.current_turn)
+    }
+
+    fn is_over(&self) -> bool {
+        self.game_ending.is_some() || self.termination.is_some()
+    }
+}
+
+/// Where the parts of a frame that are updated or clicked on were drawn. All
+/// empty when the terminal is too small to draw the game.
+#[derive(Default)]
+struct FrameLayout {
+    clock_faces: Vec<(Color, Rect)>,
+    board: Option<BoardGeometry>,
+    analysis: Option<Rect>,
+    input: Option<Rect>,
+}
+
+/// Terminals narrower than this show only the board, without the game info,
+/// move list and analysis beside it.
+const COMPACT_WIDTH: u16 = 80;
+
+/// Smallest board: border, rank labels and eight squares two columns wide,
+/// and border, file labels and eight squares one row high.
+const MIN_BOARD_WIDTH: u16 = 19;
+const MIN_BOARD_HEIGHT: u16 = 12;
+
+/// Height of the input panel, and of each clock face.
+const PANEL_HEIGHT: u16 = 3;
+
+/// Options offered once a game is over.
+const GAME_OVER_MENU: &str =
+    "[1] New game  [r] Rematch  [s] Save  [a] Analyze  [2] Play  [3] Watch  [4] PvP  [q] Exit";
+
+/// Squares a piece passes through when a move is animated, and how long it
+/// is shown on each.
+const ANIMATION_STEPS: u8 = 4;
+const ANIMATION_FRAME: Duration = Duration::from_millis(40);
+
+/// The positions that slide the last move's piece from its square toward its
+/// destination, from the board before the move. A piece in the way is not
+/// covered up: the frame that would do so is left out.
+fn slide_frames(engine: &Engine) -> Vec<Board> {
+    let history = engine.move_history();
+    let Some(entry) = history.last() else {
+        return Vec::new();
+    };
+    let Some(before) = engine.board_at_ply(history.len() - 1) else {
+        return Vec::new();
+    };
+    let (from, to) = (entry.chess_move.from_square(), entry.chess_move.to_square());
+    let Some((piece, color)) = before.get(from) else {
+        return Vec::new();
+    };
+    let toward = |start: u8, end: u8, step: u8| {
+        let distance = f32::from(end) - f32::from(start);
+        (f32::from(start) + distance * f32::from(step) / f32::from(ANIMATION_STEPS)).round() as u8
+    };
+    let mut frames = Vec::new();
+    let mut last_square = from;
+    for step in 1..ANIMATION_STEPS {
+        let square = Square::from_rank_file(
+            toward(from.rank(), to.rank(), step),
+            toward(from.file(), to.file(), step),
+        );
+        if square == last_square || square == to || before.get(square).is_some() {
+            continue;
+        }
+        last_square = square;
+        let mut board = before.clone();
+        board.remove(from);
+        if board.put(square, piece, color).is_ok() {
+            frames.push(board);
+        }
+    }
+    frames
 }
 
 /// Format large numbers with thousand separators
@@ -46,7 +184,54 @@ fn format_number(n: u64) -> String {
 pub struct TuiApp {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
     theme: Theme,
+    /// Color schemes the settings menu switches between.
+    themes: Vec<Theme>,
+    /// Setting marked in the settings menu, while it is open.
+    settings: Option<Setting>,
+    /// Settings kept in the config file besides the theme.
+    preferences: Preferences,
+    /// The engine's settings, as changed in the last settings menu.
+    engine_settings: Option<EngineSettings>,
+    /// Outcome of saving the last change made in the settings menu.
+    settings_note: Option<String>,
+    /// Number of moves in the position drawn last, to tell when a single move
+    /// was played since.
+    drawn_plies: Option<usize>,
+    /// Whether the pieces are left off the board.
+    blindfold: bool,
+    /// Number of moves in the position whose pieces are shown in a blindfold
+    /// game, until the next move.
+    peeking_at: Option<usize>,
     should_quit: bool,
+    status: Option<String>,
+    termination: Option<String>,
+    /// Result of the game and why it ended, once it is over.
+    game_over: Option<(PgnResult, GameOverReason)>,
+    clock: Option<GameClock>,
+    /// Where each side's clock was drawn in the last frame.
+    clock_faces: Vec<(Color, Rect)>,
+    move_list: MoveListState,
+    /// Turned around from the default orientation, which puts the human
+    /// player's pieces at the bottom.
+    flipped: bool,
+    /// Whether the terminal was asked to report mouse events.
+    mouse_capture: bool,
+    /// Where the board was drawn in the last frame.
+    board_geometry: Option<BoardGeometry>,
+    /// Piece picked up with the mouse, to be moved by the next click.
+    selected_square: Option<Square>,
+    /// The picked up piece was clicked again, and goes back unless it is
+    /// dragged away before the button is released.
+    put_back_on_release: bool,
+    /// Where the input panel was drawn in the last frame.
+    input_area: Option<Rect>,
+    /// Whether the live analysis panel is shown.
+    show_analysis: bool,
+    /// Search of the position on the board, while the analysis panel is shown.
+    analysis: Option<Analysis>,
+    /// Where the analysis panel was drawn in the last frame, shared with the
+    /// search thread that repaints it. Held locked while a frame is drawn.
+    analysis_area: Arc<Mutex<Option<Rect>>>,
 }
 
 impl TuiApp {
@@ -60,13 +245,412 @@ impl TuiApp {
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
 
+        // Mouse input is optional: without it, moves are still typed in
+        let mouse_capture = execute!(io::stdout(), EnableMouseCapture).is_ok();
+
+        // The theme from the config file comes first when it is not a preset
+        let theme = Theme::default();
+        let mut themes = Theme::presets();
+        if !themes.iter().any(|preset| preset.name == theme.name) {
+            themes.insert(0, theme.clone());
+        }
+        let preferences = Preferences::load();
+
         Ok(Self {
             terminal,
-            theme: Theme::default(),
+            theme,
+            themes,
+            settings: None,
+            preferences,
+            engine_settings: None,
+            settings_note: None,
+            drawn_plies: None,
+            blindfold: false,
+            peeking_at: None,
             should_quit: false,
+            status: None,
+            termination: None,
+            game_over: None,
+            clock: None,
+            clock_faces: Vec::new(),
+            move_list: MoveListState::default(),
+            flipped: preferences.flipped,
+            mouse_capture,
+            board_geometry: None,
+            selected_square: None,
+            put_back_on_release: false,
+            input_area: None,
+            show_analysis: false,
+            analysis: None,
+            analysis_area: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Show `status` in the input panel instead of the default prompt while the
+    /// game is in progress.
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    /// Mark the game as over for a reason other than the board position, such as
+    /// a time forfeit.
+    pub fn set_termination(&mut self, termination: Option<String>) {
+        self.termination = termination;
+    }
+
+    /// Announce the result of the game over the board, or take the
+    /// announcement down when a new game starts.
+    pub fn set_game_over(&mut self, game_over: Option<(PgnResult, GameOverReason)>) {
+        self.game_over = game_over;
+    }
+
+    /// Show the game clocks next to the board.
+    pub fn set_clock(&mut self, clock: Option<GameClock>) {
+        self.clock = clock;
+    }
+
+    /// Select an earlier move in the move list, showing its position on the
+    /// board, or return to the current position.
+    pub fn browse_moves(&mut self, navigation: Navigation) {
+        self.move_list.browse(navigation);
+    }
+
+    /// The number of moves played in the position on the board while browsing
+    /// an earlier one.
+    pub fn viewed_ply(&self) -> Option<usize> {
+        self.move_list
+            .is_browsing()
+            .then(|| self.move_list.viewed_ply())
+    }
+
+    /// Go over the finished game from its first move with the analysis panel
+    /// open.
+    pub fn analyze_game(&mut self) {
+        if !self.show_analysis {
+            self.toggle_analysis();
+        }
+        self.move_list.browse(Navigation::Start);
+    }
+
+    /// Turn the board around.
+    pub fn flip_board(&mut self) {
+        self.flipped = !self.flipped;
+    }
+
+    /// Show or hide the live analysis panel. The analysis starts with the next
+    /// frame.
+    pub fn toggle_analysis(&mut self) {
+        self.show_analysis = !self.show_analysis;
+        if !self.show_analysis {
+            self.analysis = None;
+            *self.analysis_area.lock().unwrap() = None;
+        }
+    }
+
+    /// Leave the pieces off the board, for playing blindfold. They are shown
+    /// again once the game is over.
+    pub fn set_blindfold(&mut self, blindfold: bool) {
+        self.blindfold = blindfold;
+        self.peeking_at = None;
+    }
+
+    /// Show the pieces of a blindfold game until the next move, or hide them
+    /// again.
+    pub fn toggle_pieces(&mut self) {
+        if !self.blindfold {
+            return;
+        }
+        self.peeking_at = match self.peeking_at {
+            Some(_) => None,
+            None => self.drawn_plies,
+        };
+    }
+
+    /// Open the settings menu, which is drawn from the next frame, with the
+    /// engine's current settings.
+    pub fn open_settings(&mut self, engine: EngineSettings) {
+        self.settings = Some(Setting::Theme);
+        self.engine_settings = Some(engine);
+        self.settings_note = None;
+    }
+
+    /// The engine settings picked in the last settings menu.
+    pub fn engine_settings(&self) -> Option<EngineSettings> {
+        self.engine_settings
+    }
+
+    /// Ring the terminal bell, if it is turned on.
+    pub fn ring_bell(&self) {
+        if self.preferences.bell {
+            print!("\x07");
+            let _ = io::stdout().flush();
+        }
+    }
+
+    /// Wait for a key in the open settings menu and act on it: the up and
+    /// down arrows pick a setting, left and right change it, and F3, Esc or
+    /// Enter close the menu. Returns false once the menu is closed.
+    pub fn update_settings(&mut self) -> io::Result<bool> {
+        let Some(selected) = self.settings else {
+            return Ok(false);
+        };
+        loop {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                // Redrawn for the new size
+                Event::Resize(..) => return Ok(true),
+                _ => continue,
+            };
+            match key.code {
+                KeyCode::F(3) | KeyCode::Esc | KeyCode::Enter => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Up => self.settings = Some(selected.previous()),
+                KeyCode::Down => self.settings = Some(selected.next()),
+                KeyCode::Left => self.change_setting(selected, -1),
+                KeyCode::Right => self.change_setting(selected, 1),
+                _ => continue,
+            }
+            return Ok(true);
+        }
+        self.settings = None;
+        Ok(false)
+    }
+
+    /// Step `setting` to the previous or next of its values, wrapping around.
+    fn change_setting(&mut self, setting: Setting, step: isize) {
+        let cycle = |index: usize, len: usize| (index as isize + step).rem_euclid(len as isize);
+        match setting {
+            Setting::Theme => {
+                let index = self
+                    .themes
+                    .iter()
+                    .position(|theme| theme.name == self.theme.name)
+                    .unwrap_or(0);
+                let index = cycle(index, self.themes.len()) as usize;
+                self.theme = Theme {
+                    pieces: self.theme.pieces,
+                    coordinates: self.theme.coordinates,
+                    square_names: self.theme.square_names,
+                    ..self.themes[index].clone()
+                };
+            }
+            Setting::Pieces => {
+                let index = PieceSet::ALL
+                    .iter()
+                    .position(|&pieces| pieces == self.theme.pieces)
+                    .unwrap_or(0);
+                self.theme.pieces = PieceSet::ALL[cycle(index, PieceSet::ALL.len()) as usize];
+            }
+            Setting::Coordinates => {
+                let index = Coordinates::ALL
+                    .iter()
+                    .position(|&coordinates| coordinates == self.theme.coordinates)
+                    .unwrap_or(0);
+                self.theme.coordinates =
+                    Coordinates::ALL[cycle(index, Coordinates::ALL.len()) as usize];
+            }
+            Setting::SquareNames => self.theme.square_names = !self.theme.square_names,
+            Setting::Flipped => {
+                // Turn the board as it is shown, even if Tab already has
+                self.preferences.flipped = !self.preferences.flipped;
+                self.flip_board();
+            }
+            Setting::Bell => self.preferences.bell = !self.preferences.bell,
+            Setting::Animation => self.preferences.animation = !self.preferences.animation,
+            Setting::Depth => {
+                if let Some(engine) = &mut self.engine_settings {
+                    engine.depth =
+                        cycle(usize::from(engine.depth) - 1, usize::from(MAX_DEPTH)) as u8 + 1;
+                    self.preferences.depth = Some(engine.depth);
+                }
+            }
+            Setting::MoveTime => {
+                if let Some(engine) = &mut self.engine_settings {
+                    let index = MOVETIMES
+                        .iter()
+                        .position(|&movetime| movetime == engine.movetime)
+                        .unwrap_or(0);
+                    engine.movetime = MOVETIMES[cycle(index, MOVETIMES.len()) as usize];
+                    self.preferences.movetime = engine.movetime;
+                }
+            }
+        }
+        // Restarted with the next frame, so the search thread paints in the new colors
+        self.analysis = None;
+
+        self.settings_note = Some(match config::save(&self.theme, &self.preferences) {
+            Ok(()) => format!("Saved to {}", CONFIG_FILE),
+            Err(e) => format!("Not saved: {}", e),
+        });
+    }
+
+    /// Keep the analysis on the position shown on the board, restarting it
+    /// when that position changes.
+    fn update_analysis(&mut self, engine: &Engine) {
+        let ply = self.move_list.viewed_ply();
+        let Some(board) = engine.board_at_ply(ply) else {
+            return;
+        };
+        let hash = board.current_position_hash();
+        if self
+            .analysis
+            .as_ref()
+            .is_some_and(|analysis| analysis.position_hash() == hash)
+        {
+            return;
+        }
+
+        // Stop the previous search before its thread can repaint the panel
+        self.analysis = None;
+        let position_hashes = engine
+            .position_hashes()
+            .get(..=ply)
+            .map_or_else(|| vec![hash], <[u64]>::to_vec);
+        let painter = AnalysisPainter::new(self.analysis_area.clone(), self.theme.clone());
+        self.analysis = Some(Analysis::start_with_lines(
+            board,
+            position_hashes,
+            ANALYSIS_LINES,
+            move |lines| {
+                let _ = painter.paint(lines);
+            },
+        ));
+    }
+
+    /// Pick a move with the mouse: click a piece and then its destination, or
+    /// drag the piece there. The right button, or clicking the piece again,
+    /// puts the piece back. Returns the move once both squares are picked.
+    pub fn handle_mouse(&mut self, event: MouseEvent, board: &Board) -> Option<(Square, Square)> {
+        if self.move_list.is_browsing() {
+            return None;
+        }
+        let square = self
+            .board_geometry
+            .and_then(|geometry| geometry.square_at(event.column, event.row));
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Right) => {
+                self.selected_square = None;
+                None
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                let square = square?;
+                if board
+                    .get(square)
+                    .is_some_and(|(_, color)| color == board.turn())
+                {
+                    self.put_back_on_release = self.selected_square == Some(square);
+                    self.selected_square = Some(square);
+                    return None;
+                }
+                let from = self.selected_square.take()?;
+                Some((from, square))
+            }
+            // Dropping a dragged piece on another square
+            MouseEventKind::Up(MouseButton::Left) => {
+                let from = self.selected_square?;
+                let put_back = std::mem::take(&mut self.put_back_on_release);
+                let Some(to) = square.filter(|&to| to != from) else {
+                    if put_back {
+                        self.selected_square = None;
+                    }
+                    return None;
+                };
+                self.selected_square = None;
+                Some((from, to))
+            }
+            _ => None,
+        }
+    }
+
+    /// Ask which piece a pawn of `color` promotes to, offering the choices in
+    /// place of the input panel. A piece is picked by its letter or with a
+    /// click; Esc or the right button backs out of the move, returning None.
+    pub fn choose_promotion(&mut self, color: Color) -> io::Result<Option<Piece>> {
+        let Some(area) = self.input_area else {
+            return Ok(Some(Piece::Queen));
+        };
+        let mut buf = Buffer::empty(area);
+        PromotionWidget::new(color, &self.theme).render(area, &mut buf);
+        Self::paint_over_frame(&buf)?;
+
+        let widget = PromotionWidget::new(color, &self.theme);
+        loop {
+            match event::read()? {
+                Event::Key(key) => match key.code {
+                    KeyCode::Esc => return Ok(None),
+                    KeyCode::Char(c) => {
+                        if let Some(piece) = piece_for_key(c) {
+                            return Ok(Some(piece));
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        if let Some(piece) = widget.piece_at(area, mouse.column, mouse.row) {
+                            return Ok(Some(piece));
+                        }
+                    }
+                    MouseEventKind::Down(MouseButton::Right) => return Ok(None),
+                    _ => {}
+                },
+                // The selector is no longer where it was drawn; the move is
+                // entered again on the redrawn screen
+                Event::Resize(..) => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// Ask for a file to save the game to or load one from, in a dialog over
+    /// the last frame. Returns None if the player closes the dialog.
+    pub fn choose_game_file(&mut self, action: GameFileAction) -> io::Result<Option<String>> {
+        let directory = std::env::current_dir()?;
+        let mut dialog = FileDialog::new(action, directory);
+        loop {
+            let size = self.terminal.size()?;
+            let area = dialog_area(Rect::new(0, 0, size.width, size.height));
+            let mut buf = Buffer::empty(area);
+            FileDialogWidget::new(&dialog, &self.theme).render(area, &mut buf);
+            Self::paint_over_frame(&buf)?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            match dialog.handle_key(key) {
+                DialogResult::Open => {}
+                DialogResult::Chosen(path) => return Ok(Some(path.display().to_string())),
+                DialogResult::Cancelled => return Ok(None),
+            }
+        }
+    }
+
+    /// Draw `buf` over the last frame, holding stdout so that a clock repainted
+    /// meanwhile cannot interleave with it.
+    fn paint_over_frame(buf: &Buffer) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        let mut backend = CrosstermBackend::new(&mut stdout);
+        let cells = buf
+            .area
+            .positions()
+            .map(|position| (position.x, position.y, &buf[position]));
+        backend.draw(cells)?;
+        Backend::flush(&mut backend)
+    }
+
+    /// A ticker that repaints the clocks of the last frame from `clock`, or
+    /// None if no clocks are shown.
+    pub fn clock_ticker(&self, clock: GameClock) -> Option<ClockTicker> {
+        if self.clock_faces.is_empty() {
+            return None;
+        }
+        Some(ClockTicker::new(
+            clock,
+            self.clock_faces.clone(),
+            self.theme.clone(),
+        ))
+    }
+
     /// Run the TUI application
     pub fn run(
         &mut self,
@@ -77,23 +661,92 @@ impl TuiApp {
         human_color: Option<Color>,
         game_ending: Option<&GameEnding>,
     ) -> io::Result<()> {
+        let plies = engine.move_history().len();
+        let animate = self.preferences.animation
+            && self.drawn_plies.is_some_and(|drawn| drawn + 1 == plies)
+            && !self.move_list.is_browsing()
+            && self.settings.is_none();
+        self.drawn_plies = Some(plies);
+        self.move_list.sync(plies);
+        let hide_pieces =
+            self.blindfold && self.game_over.is_none() && self.peeking_at != Some(plies);
+        if self.show_analysis {
+            self.update_analysis(engine);
+        }
+        // The search thread waits to repaint the panel until the frame is drawn
+        let analysis_area = self.analysis_area.clone();
+        let mut analysis_area = analysis_area.lock().unwrap();
+
         // Clear terminal backend state to force full redraw after manual screen clear
         self.terminal.clear()?;
 
+        let human_plays_black = human_color == Some(Color::Black);
+        let bottom = if human_plays_black != self.flipped {
+            Color::Black
+        } else {
+            Color::White
+        };
+
+        // A piece picked up before a move was played some other way is dropped
+        let board = engine.board();
+        self.selected_square = self.selected_square.filter(|&square| {
+            board
+                .get(square)
+                .is_some_and(|(_, color)| color == board.turn())
+        });
+
         let theme = &self.theme;
+        let move_list = &mut self.move_list;
         let game_state = GameState {
             current_turn,
             last_move,
             opening_name,
             human_color,
             game_ending,
+            variant: board.variant(),
+            status: self.status.as_deref(),
+            termination: self.termination.as_deref(),
+            game_over: self.game_over,
+            clock: self.clock.as_ref(),
+            bottom,
+            selected_square: self.selected_square,
+            analysis: self.analysis.as_ref(),
+            settings: self.settings,
+            preferences: &self.preferences,
+            engine_settings: self.engine_settings,
+            settings_note: self.settings_note.as_deref(),
+            animation: None,
+            hide_pieces,
+        };
+        let frames = if animate {
+            slide_frames(engine)
+        } else {
+            Vec::new()
         };
+        for board in &frames {
+            let game_state = GameState {
+                animation: Some(board),
+                ..game_state
+            };
+            self.terminal.draw(|f| {
+                Self::render_frame(f, engine, &game_state, move_list, theme);
+            })?;
+            thread::sleep(ANIMATION_FRAME);
+        }
+        let mut layout = None;
         self.terminal.draw(|f| {
-            Self::render_frame(f, engine, &game_state, theme);
+            layout = Some(Self::render_frame(f, engine, &game_state, move_list, theme));
         })?;
+        if let Some(layout) = layout {
+            self.clock_faces = layout.clock_faces;
+            self.board_geometry = layout.board;
+            *analysis_area = layout.analysis;
+            self.input_area = layout.input;
+        }
 
         // Position cursor in the input box when it's a human's turn and game hasn't ended
-        let should_show_cursor = game_state.game_ending.is_none()
+        let should_show_cursor = !game_state.is_over()
+            && self.input_area.is_some()
             && match game_state.human_color {
                 None => true, // PvP - always show cursor
                 Some(color) => game_state.current_turn == color,
@@ -102,56 +755,218 @@ impl TuiApp {
         if should_show_cursor {
             let height = self.terminal.size()?.height;
             // Input panel is at height - 3, cursor goes after prompt text
-            print!("\x1B[{};19H", height - 1); // Row: height-1, Column: 19 (after prompt)
+            print!("\x1B[{};19H", height.saturating_sub(1)); // Row: height-1, Column: 19 (after prompt)
             io::stdout().flush()?;
         }
 
         Ok(())
     }
 
-    /// Render a single frame
+    /// Render a single frame, returning where the clocks and the board were drawn
     fn render_frame(
         frame: &mut ratatui::Frame,
         engine: &Engine,
         game_state: &GameState,
+        move_list: &mut MoveListState,
         theme: &Theme,
-    ) {
+    ) -> FrameLayout {
         let size = frame.area();
+        if size.width < MIN_BOARD_WIDTH || size.height < MIN_BOARD_HEIGHT + PANEL_HEIGHT {
+            let message = format!(
+                "Terminal too small ({}x{}). Resize it to at least {}x{}.",
+                size.width,
+                size.height,
+                MIN_BOARD_WIDTH,
+                MIN_BOARD_HEIGHT + PANEL_HEIGHT
+            );
+            frame.render_widget(
+                Paragraph::new(message)
+                    .style(theme.text_style())
+                    .wrap(Wrap { trim: true }),
+                size,
+            );
+            return FrameLayout::default();
+        }
 
         // Create main layout: board area + input panel at bottom
         let main_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(10), Constraint::Length(3)])
+            .constraints([Constraint::Min(10), Constraint::Length(PANEL_HEIGHT)])
             .split(size);
 
-        // Split board area: board on left, info panel on right
-        let board_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-            .split(main_chunks[0]);
+        // Split board area: board on left, info panel on right, unless the
+        // terminal is too narrow for both
+        let (mut board_area, side_area) = if size.width < COMPACT_WIDTH {
+            (main_chunks[0], None)
+        } else {
+            let board_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(main_chunks[0]);
+            (board_chunks[0], Some(board_chunks[1]))
+        };
+
+        // Clocks above and below the board, each on its side's edge, when the
+        // board still fits between them
+        let mut clock_faces = Vec::new();
+        let clock = game_state
+            .clock
+            .filter(|_| board_area.height >= MIN_BOARD_HEIGHT + 2 * PANEL_HEIGHT);
+        if let Some(clock) = clock {
+            let board_column = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(PANEL_HEIGHT),
+                    Constraint::Min(MIN_BOARD_HEIGHT),
+                    Constraint::Length(PANEL_HEIGHT),
+                ])
+                .split(board_area);
+            board_area = board_column[1];
+            clock_faces = vec![
+                (game_state.bottom.opposite(), board_column[0]),
+                (game_state.bottom, board_column[2]),
+            ];
+            for &(color, area) in &clock_faces {
+                frame.render_widget(ClockWidget::new(clock, color, theme), area);
+            }
+        }
+
+        // Evaluation bar beside the board, when there is room for both
+        let flipped = game_state.bottom == Color::Black;
+        if board_area.width >= MIN_BOARD_WIDTH + EVAL_BAR_WIDTH {
+            let board_row = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(EVAL_BAR_WIDTH), Constraint::Min(0)])
+                .split(board_area);
+            board_area = board_row[1];
+            let eval_bar_area = Rect {
+                height: BoardGeometry::new(board_area, flipped)
+                    .height()
+                    .min(board_row[0].height),
+                ..board_row[0]
+            };
+            let stats = engine.get_search_stats();
+            frame.render_widget(
+                EvalBarWidget::new(stats.last_score, stats.mate_in, game_state.bottom, theme),
+                eval_bar_area,
+            );
+        }
+        let geometry = BoardGeometry::new(board_area, flipped);
+
+        // Render board, at the move selected in the move list
+        let viewed_board = engine.board_at_ply(move_list.viewed_ply());
+        let board = game_state
+            .animation
+            .or(viewed_board.as_ref())
+            .unwrap_or(engine.board());
+        // A piece picked up with the mouse belongs to the current position,
+        // and is not given away in a blindfold game
+        let selected_square = game_state
+            .selected_square
+            .filter(|_| !move_list.is_browsing() && !game_state.hide_pieces);
+        let destinations = selected_square
+            .map(|square| engine.destinations(square))
+            .unwrap_or_default();
+        let last_move = move_list
+            .viewed_ply()
+            .checked_sub(1)
+            .and_then(|index| engine.move_history().get(index))
+            .map(|entry| &entry.chess_move);
+        // Moves carry their check effect once annotated; fall back to the board
+        let in_check = match last_move.and_then(ChessMove::effect) {
+            Some(effect) => effect != ChessMoveEffect::None,
+            None => engine.is_in_check(board),
+        };
+        let king = board.pieces(board.turn()).locate(Piece::King);
+        let checked_king = Some(king)
+            .filter(|king| in_check && !king.is_empty() && !game_state.hide_pieces)
+            .map(|king| king.to_square());
+        let board_widget = BoardWidget::new(board, theme)
+            .flipped(flipped)
+            .last_move(last_move.map(|m| (m.from_square(), m.to_square())))
+            .checked_king(checked_king)
+            .selected(selected_square)
+            .destinations(&destinations)
+            .hide_pieces(game_state.hide_pieces);
+        frame.render_widget(board_widget, board_area);
 
-        // Render board
-        let board_widget = BoardWidget::new(engine.board(), theme);
-        frame.render_widget(board_widget, board_chunks[0]);
+        // Analysis below the info panel, when shown
+        let mut analysis_area = None;
+        if let Some(mut info_area) = side_area {
+            if let Some(analysis) = game_state.analysis {
+                let info_column = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Min(0),
+                        Constraint::Length(ANALYSIS_PANEL_HEIGHT),
+                    ])
+                    .split(info_area);
+                info_area = info_column[0];
+                let lines = analysis.latest_lines();
+                let widget = AnalysisWidget::new(&lines, theme).finished(analysis.is_finished());
+                frame.render_widget(widget, info_column[1]);
+                analysis_area = Some(info_column[1]);
+            }
 
-        // Render info panel
-        Self::render_info_panel(frame, board_chunks[1], engine, game_state, theme);
+            // Render info panel
+            Self::render_info_panel(frame, info_area, engine, game_state, move_list, theme);
+        }
 
         // Render input panel at bottom
-        Self::render_input_panel(frame, main_chunks[1], game_state, theme);
+        Self::render_input_panel(
+            frame,
+            main_chunks[1],
+            game_state,
+            theme,
+            side_area.is_some(),
+        );
+
+        // The result goes over the board, out of the way while browsing the game
+        let game_over = game_state
+            .game_over
+            .filter(|_| !move_list.is_browsing() && game_state.settings.is_none());
+        if let Some((result, reason)) = game_over {
+            frame.render_widget(
+                GameOverWidget::new(result, reason, theme).termination(game_state.termination),
+                overlay_area(size),
+            );
+        }
+
+        // The settings menu goes over everything else while it is open
+        if let (Some(selected), Some(engine_settings)) =
+            (game_state.settings, game_state.engine_settings)
+        {
+            let widget =
+                SettingsWidget::new(selected, theme, game_state.preferences, engine_settings)
+                    .note(game_state.settings_note);
+            frame.render_widget(widget, settings_area(size));
+        }
+
+        FrameLayout {
+            clock_faces,
+            board: Some(geometry),
+            analysis: analysis_area,
+            input: Some(main_chunks[1]),
+        }
     }
 
-    /// Render the info panel with game details
+    /// Render the info panel with game details, and the move list below it
     fn render_info_panel(
         frame: &mut ratatui::Frame,
         area: Rect,
         engine: &Engine,
         game_state: &GameState,
+        move_list: &mut MoveListState,
         theme: &Theme,
     ) {
         let mut info_text = String::new();
         let is_watch_mode = game_state.human_color.is_none();
 
+        // The input panel holds the prompt on a human's turn, so show the status here
+        if let Some(status) = game_state.status.filter(|_| game_state.is_human_turn()) {
+            info_text.push_str(&format!("{}\n\n", status));
+        }
+
         // Opening name with deviation info
         if let Some(opening) = game_state.opening_name {
             info_text.push_str(&format!("Opening: {}", opening));
@@ -201,89 +1016,53 @@ impl TuiApp {
             info_text.push_str("  Score: -\n\n");
         }
 
-        // Move history table (at bottom so it grows downward)
-        let move_history = engine.move_history();
-        if !move_history.is_empty() {
-            info_text.push_str("Move History:\n");
-
-            // Table header
-            if is_watch_mode {
-                info_text.push_str("  # │ White      │ Black      │ Score\n");
-                info_text.push_str("  ──┼────────────┼────────────┼────────\n");
-            } else {
-                info_text.push_str("  # │ White      │ Black\n");
-                info_text.push_str("  ──┼────────────┼────────────\n");
-            }
-
-            // Process moves in pairs
-            for i in (0..move_history.len()).step_by(2) {
-                let move_number = (i / 2) + 1;
-                let white_move = &move_history[i];
-                let black_move = move_history.get(i + 1);
-
-                if is_watch_mode {
-                    let white_score = white_move
-                        .score
-                        .map(|s| format!("{:>6}", s))
-                        .unwrap_or_else(|| "     -".to_string());
-                    let black_score = black_move
-                        .and_then(|m| m.score)
-                        .map(|s| format!("{:>6}", s))
-                        .unwrap_or_else(|| "     -".to_string());
-
-                    if let Some(black) = black_move {
-                        info_text.push_str(&format!(
-                            " {:>2} │ {:<10} │ {:<10} │ {}/{}\n",
-                            move_number,
-                            white_move.notation,
-                            black.notation,
-                            white_score,
-                            black_score
-                        ));
-                    } else {
-                        info_text.push_str(&format!(
-                            " {:>2} │ {:<10} │            │ {}\n",
-                            move_number, white_move.notation, white_score
-                        ));
-                    }
-                } else if let Some(black) = black_move {
-                    info_text.push_str(&format!(
-                        " {:>2} │ {:<10} │ {:<10}\n",
-                        move_number, white_move.notation, black.notation
-                    ));
-                } else {
-                    info_text.push_str(&format!(
-                        " {:>2} │ {:<10} │\n",
-                        move_number, white_move.notation
-                    ));
-                }
-            }
-        }
+        // Move list below the details, taking the rest of the panel
+        let info_height = info_text.lines().count() as u16 + 2;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(info_height), Constraint::Min(0)])
+            .split(area);
 
         let paragraph = Paragraph::new(info_text)
             .block(Block::default().borders(Borders::ALL).title("Game Info"))
             .style(theme.text_style());
+        frame.render_widget(paragraph, chunks[0]);
 
-        frame.render_widget(paragraph, area);
+        if !engine.move_history().is_empty() {
+            let rows = engine.move_list();
+            let move_list_widget = MoveListWidget::new(&rows, is_watch_mode, theme);
+            frame.render_stateful_widget(move_list_widget, chunks[1], move_list);
+        }
     }
 
-    /// Render the input panel at the bottom
+    /// Render the input panel at the bottom. Without the info panel, the status
+    /// shown there on a human's turn goes in the input panel's title.
     fn render_input_panel(
         frame: &mut ratatui::Frame,
         area: Rect,
         game_state: &GameState,
         theme: &Theme,
+        has_info_panel: bool,
     ) {
         let prompt_text = if let Some(ending) = game_state.game_ending {
             let ending_msg = match ending {
                 GameEnding::Checkmate => "Checkmate!",
                 GameEnding::Stalemate => "Stalemate!",
                 GameEnding::Draw => "Draw!",
+                GameEnding::VariantWin => match game_state.variant {
+                    Variant::KingOfTheHill => "King of the Hill!",
+                    Variant::ThreeCheck => "Third check!",
+                    Variant::Standard => "Game over!",
+                },
             };
-            format!(
-                "{} [1] Restart  [2] Play  [3] Watch  [4] PvP  [q] Exit",
-                ending_msg
-            )
+            match game_state.termination {
+                Some(termination) => format!("{} {} {}", ending_msg, termination, GAME_OVER_MENU),
+                None => format!("{} {}", ending_msg, GAME_OVER_MENU),
+            }
+        } else if let Some(termination) = game_state.termination {
+            format!("{} {}", termination, GAME_OVER_MENU)
+        } else if let Some(status) = game_state.status.filter(|_| !game_state.is_human_turn()) {
+            status.to_string()
         } else {
             match game_state.human_color {
                 None => "Watch mode - engines playing...".to_string(),
@@ -292,8 +1071,12 @@ impl TuiApp {
             }
         };
 
+        let title = game_state
+            .status
+            .filter(|_| !has_info_panel && game_state.is_human_turn())
+            .unwrap_or("Input");
         let paragraph = Paragraph::new(prompt_text)
-            .block(Block::default().borders(Borders::ALL).title("Input"))
+            .block(Block::default().borders(Borders::ALL).title(title))
             .style(theme.text_style());
 
         frame.render_widget(paragraph, area);
@@ -321,6 +1104,13 @@ impl TuiApp {
 
 impl Drop for TuiApp {
     fn drop(&mut self) {
+        // Stop the analysis before its thread can repaint a cleared screen
+        self.analysis = None;
+
+        if self.mouse_capture {
+            let _ = execute!(std::io::stdout(), DisableMouseCapture);
+        }
+
         // Clear screen and reset cursor position
         let _ = execute!(
             std::io::stdout(),
@@ -332,3 +1122,19 @@ impl Drop for TuiApp {
         let _ = crossterm::terminal::disable_raw_mode();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slide_frames_stop_short_of_the_destination() {
+        let mut engine = Engine::new();
+        engine.make_move_san_or_uci("e4").expect("legal move");
+        let frames = slide_frames(&engine);
+        assert_eq!(frames.len(), 1);
+        let e3 = Square::from_algebraic("e3").unwrap();
+        assert_eq!(frames[0].get(e3), Some((Piece::Pawn, Color::White)));
+        assert_eq!(frames[0].get(Square::from_algebraic("e2").unwrap()), None);
+    }
+}
@@ -8,93 +8,268 @@ use ratatui::{
 
 use common::bitboard::Square;
 
-use crate::board::{color::Color as PieceColor, piece::Piece, Board};
+use crate::board::Board;
+use crate::game::engine::Destination;
+use crate::tui::theme::Coordinates;
 use crate::tui::Theme;
 
+/// Where the squares of a board widget are on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardGeometry {
+    /// Area inside the border: rank labels, file labels and squares.
+    inner: Rect,
+    square_width: u16,
+    square_height: u16,
+    flipped: bool,
+}
+
+impl BoardGeometry {
+    /// Layout of a board drawn in `area`.
+    pub fn new(area: Rect, flipped: bool) -> Self {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+
+        // Calculate square dimensions based on available space
+        // We need: 1 char for rank labels + 8 squares + file labels (top/bottom)
+        // Minimum 2 chars per square for piece visibility
+        let available_width = inner.width.saturating_sub(1); // Reserve 1 for rank labels
+        let available_height = inner.height.saturating_sub(2); // Reserve 2 for file labels (top/bottom)
+
+        // Calculate square size (min 2, max 6 for readability)
+        Self {
+            inner,
+            square_width: (available_width / 8).clamp(2, 6),
+            square_height: (available_height / 8).clamp(1, 3),
+            flipped,
+        }
+    }
+
+    /// Rows the board takes up on screen, including its border and labels.
+    pub fn height(&self) -> u16 {
+        // Border and file label above and below the squares
+        8 * self.square_height + 4
+    }
+
+    /// The square drawn at terminal cell (`x`, `y`), if any.
+    pub fn square_at(&self, x: u16, y: u16) -> Option<Square> {
+        let column = x.checked_sub(self.inner.x + 1)? / self.square_width;
+        let row = y.checked_sub(self.inner.y + 1)? / self.square_height;
+        if column >= 8 || row >= 8 || x >= self.inner.right() || y >= self.inner.bottom() {
+            return None;
+        }
+        Some(Square::from_rank_file(
+            self.rank_at(row as u8),
+            self.file_at(column as u8),
+        ))
+    }
+
+    /// File shown in display column `column`, counting from the left.
+    fn file_at(&self, column: u8) -> u8 {
+        if self.flipped {
+            7 - column
+        } else {
+            column
+        }
+    }
+
+    /// Rank shown in display row `row`, counting from the top.
+    fn rank_at(&self, row: u8) -> u8 {
+        if self.flipped {
+            row
+        } else {
+            7 - row
+        }
+    }
+}
+
 /// Widget that renders a chess board
 pub struct BoardWidget<'a> {
     board: &'a Board,
     theme: &'a Theme,
+    flipped: bool,
+    selected: Option<Square>,
+    destinations: &'a [Destination],
+    last_move: Option<(Square, Square)>,
+    checked_king: Option<Square>,
+    hide_pieces: bool,
 }
 
 impl<'a> BoardWidget<'a> {
     pub fn new(board: &'a Board, theme: &'a Theme) -> Self {
-        Self { board, theme }
+        Self {
+            board,
+            theme,
+            flipped: false,
+            selected: None,
+            destinations: &[],
+            last_move: None,
+            checked_king: None,
+            hide_pieces: false,
+        }
+    }
+
+    /// Draw the board from Black's side, with rank 1 at the top and the h-file
+    /// on the left.
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// Highlight the square of the piece picked up to move.
+    pub fn selected(mut self, selected: Option<Square>) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    /// Mark the squares the selected piece can move to: a dot on empty
+    /// squares, a colored background under pieces it can capture, and a cross
+    /// or gray background where a pin or check rules the move out.
+    pub fn destinations(mut self, destinations: &'a [Destination]) -> Self {
+        self.destinations = destinations;
+        self
     }
 
-    fn get_piece_char(piece: Piece, color: PieceColor) -> char {
-        piece.to_unicode_piece_char(color)
+    /// Highlight the squares the last move was played from and to.
+    pub fn last_move(mut self, last_move: Option<(Square, Square)>) -> Self {
+        self.last_move = last_move;
+        self
+    }
+
+    /// Outline the square of a king in check.
+    pub fn checked_king(mut self, checked_king: Option<Square>) -> Self {
+        self.checked_king = checked_king;
+        self
+    }
+
+    /// Draw the squares without the pieces on them, for playing blindfold.
+    pub fn hide_pieces(mut self, hide_pieces: bool) -> Self {
+        self.hide_pieces = hide_pieces;
+        self
+    }
+
+    /// Character at (`dx`, `dy`) of an outline around a square of the given
+    /// size, if that cell is on the edge.
+    fn outline_char(dx: u16, dy: u16, width: u16, height: u16) -> Option<char> {
+        let (left, right) = (dx == 0, dx == width - 1);
+        let (top, bottom) = (dy == 0, dy == height - 1);
+        match (left, right, top, bottom) {
+            (true, _, true, _) => Some('┌'),
+            (_, true, true, _) => Some('┐'),
+            (true, _, _, true) => Some('└'),
+            (_, true, _, true) => Some('┘'),
+            (_, _, true, _) | (_, _, _, true) => Some('─'),
+            (true, _, _, _) | (_, true, _, _) => Some('│'),
+            _ => None,
+        }
     }
 }
 
 impl Widget for BoardWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        // Create a bordered block for the board
+        // Create a bordered block for the board, naming the picked up piece's
+        // square if asked to
+        let title = match self.selected.filter(|_| self.theme.square_names) {
+            Some(square) => format!("Chess Board - {}", square.to_algebraic()),
+            None if self.hide_pieces => "Chess Board - blindfold (F4 shows the pieces)".to_string(),
+            None => "Chess Board".to_string(),
+        };
         let block = Block::default()
             .borders(Borders::ALL)
-            .title("Chess Board")
+            .title(title)
             .border_style(self.theme.border_style());
 
-        let inner = block.inner(area);
         block.render(area, buf);
 
-        // Calculate square dimensions based on available space
-        // We need: 1 char for rank labels + 8 squares + file labels (top/bottom)
-        // Minimum 2 chars per square for piece visibility
-        let available_width = inner.width.saturating_sub(1); // Reserve 1 for rank labels
-        let available_height = inner.height.saturating_sub(2); // Reserve 2 for file labels (top/bottom)
-
-        // Calculate square size (min 2, max 6 for readability)
-        let square_width = (available_width / 8).clamp(2, 6);
-        let square_height = (available_height / 8).clamp(1, 3);
+        let geometry = BoardGeometry::new(area, self.flipped);
+        let BoardGeometry {
+            inner,
+            square_width,
+            square_height,
+            ..
+        } = geometry;
 
         // Check if we have enough space
         if square_width < 2 || square_height < 1 {
             return; // Not enough space to render
         }
 
+        let outside_labels = self.theme.coordinates == Coordinates::Outside;
+        let inside_labels = self.theme.coordinates == Coordinates::Inside;
+
         // Render file labels (a-h) at top, center-aligned
-        for file in 0u8..8 {
-            let x = inner.x + 1 + (u16::from(file) * square_width) + square_width / 2;
+        for column in (0u8..8).filter(|_| outside_labels) {
+            let x = inner.x + 1 + (u16::from(column) * square_width) + square_width / 2;
             let y = inner.y;
             if x < inner.x + inner.width && y < inner.y + inner.height {
                 buf.cell_mut((x, y))
                     .unwrap()
-                    .set_char((b'a' + file) as char)
+                    .set_char((b'a' + geometry.file_at(column)) as char)
                     .set_style(self.theme.text_style());
             }
         }
 
-        // Render board squares (from rank 7 down to 0)
-        for rank in 0u8..8 {
-            let display_rank = 7 - rank; // Display from top (rank 8) to bottom (rank 1)
-            let y = inner.y + 1 + (u16::from(rank) * square_height);
+        // Render board squares, top row first (rank 8, or rank 1 when flipped)
+        for row in 0u8..8 {
+            let display_rank = geometry.rank_at(row);
+            let y = inner.y + 1 + (u16::from(row) * square_height);
 
-            // Render rank label (8 down to 1), center-aligned
+            // Render rank label, center-aligned
             let label_y = y + square_height / 2;
-            if label_y < inner.y + inner.height {
+            if outside_labels && label_y < inner.y + inner.height {
                 buf.cell_mut((inner.x, label_y))
                     .unwrap()
-                    .set_char((b'8' - rank) as char)
+                    .set_char((b'1' + display_rank) as char)
                     .set_style(self.theme.text_style());
             }
 
             // Render squares for this rank
-            for file in 0u8..8 {
+            for column in 0u8..8 {
+                let file = geometry.file_at(column);
                 let square = Square::from_rank_file(display_rank, file);
-                let x = inner.x + 1 + (u16::from(file) * square_width);
+                let x = inner.x + 1 + (u16::from(column) * square_width);
 
                 if x + square_width <= inner.x + inner.width && y < inner.y + inner.height {
                     // Determine square color (alternating pattern)
-                    let is_light = (display_rank + file) % 2 == 0;
+                    let is_light = (display_rank + file).is_multiple_of(2);
 
                     // Get piece on this square
-                    let (piece_char, piece_color) = match self.board.get(square) {
-                        Some((piece, color)) => (Self::get_piece_char(piece, color), Some(color)),
+                    let piece = self.board.get(square).filter(|_| !self.hide_pieces);
+                    let (mut piece_char, piece_color) = match piece {
+                        Some((piece, color)) => {
+                            (self.theme.pieces.glyph(piece, color), Some(color))
+                        }
                         None => (' ', None),
                     };
 
-                    let square_style = self.theme.square_style(is_light, piece_color);
+                    let mut square_style = self.theme.square_style(is_light, piece_color);
+                    if self
+                        .last_move
+                        .is_some_and(|(from, to)| square == from || square == to)
+                    {
+                        square_style = square_style.bg(self.theme.last_move);
+                    }
+                    if self.selected == Some(square) {
+                        square_style = square_style.bg(self.theme.highlight);
+                    }
+                    if let Some(destination) = self.destinations.iter().find(|d| d.square == square)
+                    {
+                        let color = self
+                            .theme
+                            .destination_color(destination.capture, destination.legal);
+                        if piece_color.is_some() {
+                            square_style = square_style.bg(color);
+                        } else {
+                            piece_char = if destination.legal { '•' } else { '×' };
+                            square_style = square_style.fg(color);
+                        }
+                    }
+
+                    // A king in check gets a red outline, or a red square when
+                    // squares are too small to draw one
+                    let in_check = self.checked_king == Some(square);
+                    let outlined = in_check && square_height >= 3;
+                    if in_check && !outlined {
+                        square_style = square_style.bg(self.theme.check);
+                    }
 
                     // Render the square with dynamic width and height
                     for dy in 0..square_height {
@@ -104,12 +279,40 @@ impl Widget for BoardWidget<'_> {
                             if cell_x < inner.x + inner.width && cell_y < inner.y + inner.height {
                                 // Place piece character in center of square
                                 let is_center = dx == square_width / 2 && dy == square_height / 2;
-                                let ch = if is_center { piece_char } else { ' ' };
+                                let outline = outlined
+                                    .then(|| {
+                                        Self::outline_char(dx, dy, square_width, square_height)
+                                    })
+                                    .flatten();
+                                // Inside coordinates: the rank in the top left
+                                // corner of the left column, the file in the
+                                // bottom right corner of the bottom row
+                                let label = if !inside_labels {
+                                    None
+                                } else if column == 0 && dx == 0 && dy == 0 {
+                                    Some((b'1' + display_rank) as char)
+                                } else if row == 7
+                                    && dx == square_width - 1
+                                    && dy == square_height - 1
+                                {
+                                    Some((b'a' + file) as char)
+                                } else {
+                                    None
+                                };
+                                let (ch, style) = match (outline, label) {
+                                    (Some(ch), _) => (ch, square_style.fg(self.theme.check)),
+                                    (None, _) if is_center => (piece_char, square_style),
+                                    (None, Some(label)) => (
+                                        label,
+                                        square_style.fg(self.theme.inside_label_color(is_light)),
+                                    ),
+                                    (None, None) => (' ', square_style),
+                                };
 
                                 buf.cell_mut((cell_x, cell_y))
                                     .unwrap()
                                     .set_char(ch)
-                                    .set_style(square_style);
+                                    .set_style(style);
                             }
                         }
                     }
@@ -118,15 +321,76 @@ impl Widget for BoardWidget<'_> {
         }
 
         // Render file labels (a-h) at bottom, center-aligned
-        for file in 0u8..8 {
-            let x = inner.x + 1 + (u16::from(file) * square_width) + square_width / 2;
+        for column in (0u8..8).filter(|_| outside_labels) {
+            let x = inner.x + 1 + (u16::from(column) * square_width) + square_width / 2;
             let y = inner.y + 1 + (8 * square_height);
             if x < inner.x + inner.width && y < inner.y + inner.height {
                 buf.cell_mut((x, y))
                     .unwrap()
-                    .set_char((b'a' + file) as char)
+                    .set_char((b'a' + geometry.file_at(column)) as char)
                     .set_style(self.theme.text_style());
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::bitboard::{A1, A8, E2, H1, H8};
+
+    #[test]
+    fn test_square_at_maps_cells_to_squares() {
+        // Squares are 6 cells wide and 3 tall, starting inside the border and
+        // the rank labels at (2, 2)
+        let area = Rect::new(0, 0, 72, 37);
+        let geometry = BoardGeometry::new(area, false);
+        assert_eq!(geometry.square_at(2, 2), Some(A8));
+        assert_eq!(geometry.square_at(7, 4), Some(A8));
+        assert_eq!(geometry.square_at(49, 25), Some(H1));
+        assert_eq!(geometry.square_at(29, 21), Some(E2));
+        assert_eq!(geometry.square_at(1, 2), None);
+        assert_eq!(geometry.square_at(50, 2), None);
+        assert_eq!(geometry.square_at(2, 26), None);
+
+        let flipped = BoardGeometry::new(area, true);
+        assert_eq!(flipped.square_at(2, 2), Some(H1));
+        assert_eq!(flipped.square_at(49, 25), Some(A8));
+        assert_eq!(flipped.square_at(7, 25), Some(H8));
+        assert_eq!(flipped.square_at(49, 2), Some(A1));
+    }
+
+    #[test]
+    fn test_inside_coordinates_replace_the_outside_labels() {
+        let mut theme = Theme::presets().remove(0);
+        theme.coordinates = Coordinates::Inside;
+        let board = Board::default();
+        let area = Rect::new(0, 0, 72, 37);
+        let mut buf = Buffer::empty(area);
+        BoardWidget::new(&board, &theme).render(area, &mut buf);
+
+        let symbol = |x: u16, y: u16| buf.cell((x, y)).unwrap().symbol().to_string();
+        // The top left corner of a1 and the bottom right corner of h1
+        assert_eq!(symbol(2, 23), "1");
+        assert_eq!(symbol(49, 25), "h");
+        // No file label above a8
+        assert_eq!(symbol(5, 1), " ");
+    }
+
+    #[test]
+    fn test_hidden_pieces_leave_the_squares_empty() {
+        let theme = Theme::presets().remove(0);
+        let board = Board::default();
+        let area = Rect::new(0, 0, 72, 37);
+        let render = |hide_pieces: bool| {
+            let mut buf = Buffer::empty(area);
+            BoardWidget::new(&board, &theme)
+                .hide_pieces(hide_pieces)
+                .render(area, &mut buf);
+            // The center of e2
+            buf.cell((29, 21)).unwrap().symbol().to_string()
+        };
+        assert_ne!(render(false), " ");
+        assert_eq!(render(true), " ");
+    }
+}
@@ -0,0 +1,102 @@
+//! Chess clock widget for TUI rendering
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crossterm::{cursor, queue};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::Rect,
+    text::Line,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::board::color::Color;
+use crate::game::clock::{format_clock, GameClock};
+use crate::tui::Theme;
+
+/// Time left at which a clock is highlighted: a tenth of the starting time, but
+/// at least 10 seconds.
+fn low_time_threshold(base: Duration) -> Duration {
+    (base / 10).max(Duration::from_secs(10))
+}
+
+/// Widget that renders one side's clock: remaining time, increment, and an
+/// arrow while it is running
+pub struct ClockWidget<'a> {
+    clock: &'a GameClock,
+    color: Color,
+    theme: &'a Theme,
+}
+
+impl<'a> ClockWidget<'a> {
+    pub fn new(clock: &'a GameClock, color: Color, theme: &'a Theme) -> Self {
+        Self {
+            clock,
+            color,
+            theme,
+        }
+    }
+}
+
+impl Widget for ClockWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let remaining = self.clock.remaining(self.color);
+        let running = self.clock.running() == Some(self.color);
+        let low_time = remaining < low_time_threshold(self.clock.base(self.color));
+
+        let marker = if running { "▶" } else { " " };
+        let mut text = format!("{} {}", marker, format_clock(remaining));
+        let increment = self.clock.increment(self.color);
+        if !increment.is_zero() {
+            text.push_str(&format!("  +{}s", increment.as_secs_f64()));
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.color.to_string())
+            .border_style(self.theme.border_style());
+        Paragraph::new(Line::from(text))
+            .block(block)
+            .style(self.theme.clock_style(running, low_time))
+            .render(area, buf);
+    }
+}
+
+/// Repaints the clock widgets drawn in the last frame, so a running clock
+/// counts down between frames. Safe to use from another thread while the
+/// main thread waits for a move.
+pub struct ClockTicker {
+    clock: GameClock,
+    faces: Vec<(Color, Rect)>,
+    theme: Theme,
+}
+
+impl ClockTicker {
+    pub fn new(clock: GameClock, faces: Vec<(Color, Rect)>, theme: Theme) -> Self {
+        Self {
+            clock,
+            faces,
+            theme,
+        }
+    }
+
+    /// Draw the clocks at their current time, leaving the cursor where it was.
+    pub fn paint(&self) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        queue!(stdout, cursor::SavePosition)?;
+        let mut backend = CrosstermBackend::new(&mut stdout);
+        for &(color, area) in &self.faces {
+            let mut buf = Buffer::empty(area);
+            ClockWidget::new(&self.clock, color, &self.theme).render(area, &mut buf);
+            let cells = area
+                .positions()
+                .map(|position| (position.x, position.y, &buf[position]));
+            backend.draw(cells)?;
+        }
+        Backend::flush(&mut backend)?;
+        queue!(stdout, cursor::RestorePosition)?;
+        stdout.flush()
+    }
+}
@@ -0,0 +1,217 @@
+//! The TUI's config file
+//!
+//! `tui_colors.toml` in the current working directory holds the theme (see
+//! `Theme`) and the preferences that are not about colors: the board's
+//! orientation, the bell, move animation and the engine's search depth and
+//! time per move. Everything
+//! changed in the settings menu (F3) is written back to it, so the next launch
+//! starts where the last one left off.
+//!
+//! The format is a simple `key = value` per line, with `#` starting a comment:
+//! ```toml
+//! flipped = off                 # on to draw the board from the other side
+//! bell = on                      # on or off
+//! animation = off                # on or off
+//! depth = 6                      # 1 to 12
+//! movetime = 500                 # milliseconds per engine move, or off
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+use crate::tui::Theme;
+
+/// Name of the config file, in the current working directory.
+pub const CONFIG_FILE: &str = "tui_colors.toml";
+
+/// Color keys of the theme, which a preset's colors replace.
+pub(crate) const COLOR_KEYS: [&str; 13] = [
+    "light_square",
+    "dark_square",
+    "piece_white",
+    "piece_black",
+    "highlight",
+    "last_move",
+    "check",
+    "low_time",
+    "legal_move",
+    "capture",
+    "illegal_move",
+    "border",
+    "text",
+];
+
+/// Parse the `key = value` lines of a config file, skipping comments.
+pub fn parse(contents: &str) -> HashMap<&str, &str> {
+    let mut settings = HashMap::new();
+    for line in contents.lines() {
+        // Skip comments, whole-line or trailing
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if let Some((key, value)) = line.split_once('=') {
+            settings.insert(key.trim(), value.trim());
+        }
+    }
+    settings
+}
+
+/// Parse an on/off switch.
+pub(crate) fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "on" | "true" => Some(true),
+        "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn switch_name(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Settings kept in the config file besides the theme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Preferences {
+    /// Draw the board turned around from its usual side: from Black's when
+    /// playing White, and from White's when playing Black.
+    pub flipped: bool,
+    /// Ring the terminal bell when the opponent moves and when the game ends.
+    pub bell: bool,
+    /// Slide pieces to their new square instead of moving them at once.
+    pub animation: bool,
+    /// Search depth of the engine, when saved. The command line takes
+    /// precedence.
+    pub depth: Option<u8>,
+    /// Time the engine searches each move, when saved. The command line takes
+    /// precedence.
+    pub movetime: Option<Duration>,
+}
+
+impl Preferences {
+    /// Load the preferences from the config file, or the defaults if it is
+    /// missing. Values that can't be parsed are left at their defaults.
+    pub fn load() -> Self {
+        fs::read_to_string(CONFIG_FILE)
+            .map(|contents| Self::from_config(&contents))
+            .unwrap_or_default()
+    }
+
+    fn from_config(contents: &str) -> Self {
+        let settings = parse(contents);
+        let switch = |key| settings.get(key).and_then(|value| parse_switch(value));
+        Self {
+            flipped: switch("flipped").unwrap_or(false),
+            bell: switch("bell").unwrap_or(false),
+            animation: switch("animation").unwrap_or(false),
+            depth: settings
+                .get("depth")
+                .and_then(|value| value.parse().ok())
+                .filter(|depth| *depth > 0),
+            movetime: settings
+                .get("movetime")
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_millis),
+        }
+    }
+}
+
+/// Write `theme` and `preferences` to the config file, keeping its comments
+/// and any keys it has that aren't settings.
+pub fn save(theme: &Theme, preferences: &Preferences) -> io::Result<()> {
+    let contents = match fs::read_to_string(CONFIG_FILE) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    fs::write(CONFIG_FILE, updated(&contents, theme, preferences))
+}
+
+/// `contents` with the settings' lines replaced by their new values, and the
+/// settings it didn't have added at the end. When the theme is a preset, the
+/// colors in the file are commented out so the preset's own are used.
+fn updated(contents: &str, theme: &Theme, preferences: &Preferences) -> String {
+    let movetime = preferences
+        .movetime
+        .map_or("off".to_string(), |time| time.as_millis().to_string());
+    let mut values = vec![
+        ("pieces", theme.pieces.name().to_string()),
+        ("coordinates", theme.coordinates.name().to_string()),
+        ("square_names", switch_name(theme.square_names).to_string()),
+        ("flipped", switch_name(preferences.flipped).to_string()),
+        ("bell", switch_name(preferences.bell).to_string()),
+        ("animation", switch_name(preferences.animation).to_string()),
+        ("movetime", movetime),
+    ];
+    if let Some(depth) = preferences.depth {
+        values.push(("depth", depth.to_string()));
+    }
+    let preset = theme.is_preset();
+    if preset {
+        values.insert(0, ("preset", theme.name.to_string()));
+    }
+
+    let mut lines = Vec::new();
+    for line in contents.lines() {
+        let key = line
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .split_once('=')
+            .map(|(key, _)| key.trim());
+        match key {
+            Some(key) if preset && COLOR_KEYS.contains(&key) => lines.push(format!("# {}", line)),
+            Some(key) => match values.iter().position(|(name, _)| *name == key) {
+                Some(index) => {
+                    let (name, value) = values.remove(index);
+                    lines.push(format!("{} = {}", name, value));
+                }
+                None => lines.push(line.to_string()),
+            },
+            None => lines.push(line.to_string()),
+        }
+    }
+    lines.extend(
+        values
+            .into_iter()
+            .map(|(name, value)| format!("{} = {}", name, value)),
+    );
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saving_replaces_settings_and_keeps_the_rest() {
+        let theme = Theme::presets().remove(1);
+        let preferences = Preferences {
+            flipped: true,
+            bell: true,
+            animation: false,
+            depth: Some(8),
+            movetime: None,
+        };
+        let contents = "# My config\npreset = classic\nbell = off  # quiet\nlight_square = 1, 2, 3\nfoo = bar\n";
+        let saved = updated(contents, &theme, &preferences);
+        assert_eq!(
+            saved,
+            "# My config\npreset = green\nbell = on\n# light_square = 1, 2, 3\nfoo = bar\n\
+             pieces = figurines\ncoordinates = outside\nsquare_names = off\nflipped = on\nanimation = off\n\
+             movetime = off\ndepth = 8\n"
+        );
+        assert_eq!(Preferences::from_config(&saved), preferences);
+    }
+
+    #[test]
+    fn test_unreadable_preferences_fall_back_to_the_defaults() {
+        let preferences = Preferences::from_config("bell = loud\ndepth = 0\nmovetime = 250");
+        assert!(!preferences.bell);
+        assert_eq!(preferences.depth, None);
+        assert_eq!(preferences.movetime, Some(Duration::from_millis(250)));
+    }
+}
@@ -0,0 +1,157 @@
+//! Evaluation bar widget for TUI rendering
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    widgets::{Block, Borders, Widget},
+};
+
+use crate::board::color::Color;
+use crate::tui::Theme;
+
+/// Scores beyond this are treated as forced mates when displayed.
+const MATE_THRESHOLD: i16 = i16::MAX / 4;
+
+/// Width of the bar, including its border.
+pub const EVAL_BAR_WIDTH: u16 = 6;
+
+/// Share of the bar filled for White, from 0.0 to 1.0. Centipawns are mapped
+/// to an expected score so small edges are visible and large ones level off.
+fn white_share(score: i16) -> f64 {
+    if score >= MATE_THRESHOLD {
+        return 1.0;
+    }
+    if score <= -MATE_THRESHOLD {
+        return 0.0;
+    }
+    1.0 / (1.0 + 10f64.powf(-f64::from(score) / 400.0))
+}
+
+/// Short label for a score from White's perspective: pawns, e.g. "+1.4", or
+/// moves to mate, e.g. "M5" when White mates and "-M5" when Black does.
+pub fn score_label(score: i16, mate_in: Option<u8>) -> String {
+    if score.unsigned_abs() >= MATE_THRESHOLD.unsigned_abs() {
+        let sign = if score < 0 { "-" } else { "" };
+        return match mate_in {
+            Some(moves) => format!("{}M{}", sign, moves),
+            None => format!("{}M", sign),
+        };
+    }
+    let pawns = f64::from(score) / 100.0;
+    if pawns.abs() >= 10.0 {
+        format!("{:+.0}", pawns)
+    } else {
+        format!("{:+.1}", pawns)
+    }
+}
+
+/// Widget that renders the engine's score as a vertical bar, filled from each
+/// side's edge of the board in proportion to its advantage
+pub struct EvalBarWidget<'a> {
+    /// Score from White's perspective, or None before the engine has searched.
+    score: Option<i16>,
+    mate_in: Option<u8>,
+    /// The side drawn at the bottom of the board.
+    bottom: Color,
+    theme: &'a Theme,
+}
+
+impl<'a> EvalBarWidget<'a> {
+    pub fn new(score: Option<i16>, mate_in: Option<u8>, bottom: Color, theme: &'a Theme) -> Self {
+        Self {
+            score,
+            mate_in,
+            bottom,
+            theme,
+        }
+    }
+}
+
+impl Widget for EvalBarWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.border_style());
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.height == 0 || inner.width == 0 {
+            return;
+        }
+
+        // An even bar until there is a score
+        let share = self.score.map_or(0.5, white_share);
+        let bottom_share = match self.bottom {
+            Color::White => share,
+            Color::Black => 1.0 - share,
+        };
+        let bottom_rows = (bottom_share * f64::from(inner.height)).round() as u16;
+        let split = inner.bottom() - bottom_rows;
+
+        for y in inner.top()..inner.bottom() {
+            let color = if y >= split {
+                self.bottom
+            } else {
+                self.bottom.opposite()
+            };
+            for x in inner.left()..inner.right() {
+                buf.cell_mut((x, y))
+                    .unwrap()
+                    .set_char(' ')
+                    .set_style(self.theme.eval_bar_style(color));
+            }
+        }
+
+        // The label sits at the end of the bar belonging to the side ahead
+        let Some(score) = self.score else {
+            return;
+        };
+        let label = score_label(score, self.mate_in);
+        let leader = if score < 0 {
+            Color::Black
+        } else {
+            Color::White
+        };
+        let y = if leader == self.bottom {
+            inner.bottom() - 1
+        } else {
+            inner.top()
+        };
+        let fill = if y >= split {
+            self.bottom
+        } else {
+            self.bottom.opposite()
+        };
+        buf.set_stringn(
+            inner.x,
+            y,
+            format!("{:^width$}", label, width = usize::from(inner.width)),
+            usize::from(inner.width),
+            self.theme.eval_bar_style(fill),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_label_shows_pawns_or_moves_to_mate() {
+        assert_eq!(score_label(0, None), "+0.0");
+        assert_eq!(score_label(136, None), "+1.4");
+        assert_eq!(score_label(-250, None), "-2.5");
+        assert_eq!(score_label(1234, None), "+12");
+        assert_eq!(score_label(i16::MAX / 2 + 3, Some(5)), "M5");
+        assert_eq!(score_label(i16::MIN / 2 - 3, Some(2)), "-M2");
+        assert_eq!(score_label(i16::MAX / 2, None), "M");
+    }
+
+    #[test]
+    fn test_white_share_fills_the_bar_for_mates() {
+        assert_eq!(white_share(0), 0.5);
+        assert!(white_share(200) > 0.5 && white_share(200) < 1.0);
+        assert!(white_share(-200) < 0.5 && white_share(-200) > 0.0);
+        assert_eq!(white_share(i16::MAX / 2), 1.0);
+        assert_eq!(white_share(i16::MIN / 2), 0.0);
+    }
+}
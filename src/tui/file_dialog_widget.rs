@@ -0,0 +1,287 @@
+//! Save and load dialog for TUI rendering
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::game::game_file::{is_game_file, GameFileAction, PGN_EXTENSION};
+use crate::tui::Theme;
+
+/// Size of the dialog, including its border.
+const DIALOG_WIDTH: u16 = 64;
+const DIALOG_HEIGHT: u16 = 18;
+
+/// Lines inside the border taken by the directory, the file name and hints.
+const DIALOG_CHROME: u16 = 4;
+
+/// Name shown for the parent directory.
+const PARENT: &str = "../";
+
+/// What a key did to the dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogResult {
+    Open,
+    Chosen(PathBuf),
+    Cancelled,
+}
+
+/// A file picker over the game files in a directory: a file is picked from the
+/// list to load, or named (or picked to overwrite) to save to
+pub struct FileDialog {
+    action: GameFileAction,
+    directory: PathBuf,
+    /// The parent directory, subdirectories and game files, in that order.
+    entries: Vec<String>,
+    /// Entry marked with the arrows, if any.
+    selected: Option<usize>,
+    /// Name of the file to save to.
+    name: String,
+}
+
+impl FileDialog {
+    pub fn new(action: GameFileAction, directory: PathBuf) -> Self {
+        let mut dialog = Self {
+            action,
+            directory,
+            entries: Vec::new(),
+            selected: None,
+            name: match action {
+                GameFileAction::Save => format!("game.{}", PGN_EXTENSION),
+                GameFileAction::Load => String::new(),
+            },
+        };
+        dialog.read_directory();
+        dialog
+    }
+
+    fn read_directory(&mut self) {
+        let mut directories = Vec::new();
+        let mut files = Vec::new();
+        if let Ok(entries) = fs::read_dir(&self.directory) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.starts_with('.') {
+                    continue;
+                }
+                let path = entry.path();
+                if path.is_dir() {
+                    directories.push(format!("{}/", name));
+                } else if is_game_file(&path) {
+                    files.push(name);
+                }
+            }
+        }
+        directories.sort();
+        files.sort();
+
+        self.entries = self
+            .directory
+            .parent()
+            .map(|_| PARENT.to_string())
+            .into_iter()
+            .chain(directories)
+            .chain(files)
+            .collect();
+        // Loading starts on the first game file, if there is one
+        self.selected = match self.action {
+            GameFileAction::Load => self.entries.iter().position(|e| !e.ends_with('/')),
+            GameFileAction::Save => None,
+        };
+    }
+
+    /// Move into the directory `entry`, or up for the parent.
+    fn change_directory(&mut self, entry: &str) {
+        let directory = if entry == PARENT {
+            self.directory.parent().map(Path::to_path_buf)
+        } else {
+            Some(self.directory.join(entry.trim_end_matches('/')))
+        };
+        if let Some(directory) = directory {
+            self.directory = directory;
+            self.read_directory();
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = Some(index);
+        let entry = &self.entries[index];
+        if self.action == GameFileAction::Save && !entry.ends_with('/') {
+            self.name = entry.clone();
+        }
+    }
+
+    /// Act on `key`: the arrows move through the list, Enter opens a directory
+    /// or picks a file, and Esc closes the dialog. When saving, typing edits
+    /// the file name, which gets the PGN extension if it has none.
+    pub fn handle_key(&mut self, key: KeyEvent) -> DialogResult {
+        let last = self.entries.len().checked_sub(1);
+        match key.code {
+            KeyCode::Esc => return DialogResult::Cancelled,
+            KeyCode::Up => {
+                if let Some(last) = last {
+                    self.select(self.selected.map_or(last, |i| i.saturating_sub(1)));
+                }
+            }
+            KeyCode::Down => {
+                if let Some(last) = last {
+                    self.select(self.selected.map_or(0, |i| (i + 1).min(last)));
+                }
+            }
+            KeyCode::Enter => {
+                let entry = self.selected.map(|i| self.entries[i].clone());
+                match entry {
+                    Some(entry) if entry.ends_with('/') => self.change_directory(&entry),
+                    Some(entry) if self.action == GameFileAction::Load => {
+                        return DialogResult::Chosen(self.directory.join(entry));
+                    }
+                    _ if self.action == GameFileAction::Save && !self.name.is_empty() => {
+                        let mut path = self.directory.join(&self.name);
+                        if path.extension().is_none() {
+                            path.set_extension(PGN_EXTENSION);
+                        }
+                        return DialogResult::Chosen(path);
+                    }
+                    _ => {}
+                }
+            }
+            KeyCode::Char(c) if self.action == GameFileAction::Save => {
+                self.name.push(c);
+                self.selected = None;
+            }
+            KeyCode::Backspace if self.action == GameFileAction::Save => {
+                self.name.pop();
+                self.selected = None;
+            }
+            _ => {}
+        }
+        DialogResult::Open
+    }
+}
+
+/// Where the dialog is drawn: centered in `area`, and no larger than it.
+pub fn dialog_area(area: Rect) -> Rect {
+    let width = DIALOG_WIDTH.min(area.width);
+    let height = DIALOG_HEIGHT.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Widget that renders a file dialog over the rest of the frame
+pub struct FileDialogWidget<'a> {
+    dialog: &'a FileDialog,
+    theme: &'a Theme,
+}
+
+impl<'a> FileDialogWidget<'a> {
+    pub fn new(dialog: &'a FileDialog, theme: &'a Theme) -> Self {
+        Self { dialog, theme }
+    }
+}
+
+impl Widget for FileDialogWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let dialog = self.dialog;
+        let title = match dialog.action {
+            GameFileAction::Save => "Save game (Enter to save, Esc to cancel)",
+            GameFileAction::Load => "Load game (Enter to load, Esc to cancel)",
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(self.theme.border_style());
+
+        // Scroll the list so the marked entry stays in view
+        let rows = usize::from(area.height.saturating_sub(2 + DIALOG_CHROME));
+        let first = dialog.selected.map_or(0, |i| (i + 1).saturating_sub(rows));
+        let mut lines = vec![Line::from(dialog.directory.display().to_string())];
+        for (index, entry) in dialog.entries.iter().enumerate().skip(first).take(rows) {
+            if dialog.selected == Some(index) {
+                lines.push(Line::styled(
+                    format!("> {}", entry),
+                    self.theme.text_style().add_modifier(Modifier::BOLD),
+                ));
+            } else {
+                lines.push(Line::from(format!("  {}", entry)));
+            }
+        }
+        if dialog.entries.is_empty() {
+            lines.push(Line::from("  (no games here)"));
+        }
+        while lines.len() < rows + 1 {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(""));
+        match dialog.action {
+            GameFileAction::Save => {
+                lines.push(Line::from(format!("File name: {}_", dialog.name)));
+                lines.push(Line::from(
+                    "PGN, or .json for the native format with the analysis",
+                ));
+            }
+            GameFileAction::Load => {
+                lines.push(Line::from("↑↓ select  Enter open"));
+            }
+        }
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .style(self.theme.text_style())
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn press(dialog: &mut FileDialog, code: KeyCode) -> DialogResult {
+        dialog.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_dialog_lists_directories_and_game_files() {
+        let directory = std::env::temp_dir().join(format!("file_dialog_{}", std::process::id()));
+        fs::create_dir_all(directory.join("archive")).unwrap();
+        for name in ["b.pgn", "a.json", "notes.txt"] {
+            fs::write(directory.join(name), "").unwrap();
+        }
+
+        let mut dialog = FileDialog::new(GameFileAction::Load, directory.clone());
+        assert_eq!(dialog.entries, vec![PARENT, "archive/", "a.json", "b.pgn"]);
+        assert_eq!(dialog.selected, Some(2));
+        press(&mut dialog, KeyCode::Down);
+        assert_eq!(
+            press(&mut dialog, KeyCode::Enter),
+            DialogResult::Chosen(directory.join("b.pgn"))
+        );
+
+        let mut dialog = FileDialog::new(GameFileAction::Save, directory.clone());
+        for _ in "game.pgn".chars() {
+            press(&mut dialog, KeyCode::Backspace);
+        }
+        for c in "today".chars() {
+            press(&mut dialog, KeyCode::Char(c));
+        }
+        assert_eq!(
+            press(&mut dialog, KeyCode::Enter),
+            DialogResult::Chosen(directory.join("today.pgn"))
+        );
+        assert_eq!(press(&mut dialog, KeyCode::Esc), DialogResult::Cancelled);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+//! Game-end overlay for TUI rendering
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::game::events::GameOverReason;
+use crate::game::pgn::PgnResult;
+use crate::tui::Theme;
+
+/// Size of the overlay, including its border.
+const OVERLAY_WIDTH: u16 = 46;
+const OVERLAY_HEIGHT: u16 = 9;
+
+/// The outcome in words, e.g. "White wins by checkmate".
+pub fn summary(result: PgnResult, reason: GameOverReason) -> String {
+    let outcome = match result {
+        PgnResult::WhiteWins => "White wins",
+        PgnResult::BlackWins => "Black wins",
+        PgnResult::Draw => "Draw",
+        PgnResult::Unfinished => "Game over",
+    };
+    let how = match reason {
+        GameOverReason::Checkmate => " by checkmate",
+        GameOverReason::Stalemate => " by stalemate",
+        // A draw rule, or a drawn game won on draw odds, told apart by the
+        // termination shown below
+        GameOverReason::Draw => "",
+        GameOverReason::TimeForfeit => " on time",
+        GameOverReason::Adjudication => " by adjudication",
+        GameOverReason::Resignation => " by resignation",
+        GameOverReason::VariantWin(variant) => {
+            return format!("{} by {}", outcome, variant.win_description())
+        }
+    };
+    format!("{}{}", outcome, how)
+}
+
+/// Where the overlay is drawn: centered in `area`, and no larger than it.
+pub fn overlay_area(area: Rect) -> Rect {
+    let width = OVERLAY_WIDTH.min(area.width);
+    let height = OVERLAY_HEIGHT.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Widget that announces the result over the board, with what can be done
+/// next
+pub struct GameOverWidget<'a> {
+    result: PgnResult,
+    reason: GameOverReason,
+    /// Why the game ended off the board, e.g. "White lost on time!".
+    termination: Option<&'a str>,
+    theme: &'a Theme,
+}
+
+impl<'a> GameOverWidget<'a> {
+    pub fn new(result: PgnResult, reason: GameOverReason, theme: &'a Theme) -> Self {
+        Self {
+            result,
+            reason,
+            termination: None,
+            theme,
+        }
+    }
+
+    pub fn termination(mut self, termination: Option<&'a str>) -> Self {
+        self.termination = termination;
+        self
+    }
+}
+
+impl Widget for GameOverWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Game over")
+            .border_style(self.theme.border_style());
+
+        let lines = vec![
+            Line::styled(
+                format!("{}  {}", self.result, summary(self.result, self.reason)),
+                self.theme.text_style().add_modifier(Modifier::BOLD),
+            ),
+            Line::from(self.termination.unwrap_or("")),
+            Line::from(""),
+            Line::from("[r] Rematch       [s] Save PGN"),
+            Line::from("[a] Analyze game  [q] Quit"),
+            Line::from(""),
+            Line::from("[1] New game  [2] Play  [3] Watch  [4] PvP"),
+        ];
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .style(self.theme.text_style())
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_names_the_winner_and_how() {
+        assert_eq!(
+            summary(PgnResult::BlackWins, GameOverReason::Checkmate),
+            "Black wins by checkmate"
+        );
+        assert_eq!(
+            summary(PgnResult::WhiteWins, GameOverReason::TimeForfeit),
+            "White wins on time"
+        );
+        assert_eq!(
+            summary(PgnResult::Draw, GameOverReason::Stalemate),
+            "Draw by stalemate"
+        );
+        assert_eq!(summary(PgnResult::Draw, GameOverReason::Draw), "Draw");
+    }
+}
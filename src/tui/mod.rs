@@ -1,7 +1,16 @@
 //! Terminal User Interface (TUI) for rich interactive chess gameplay
 
+pub mod analysis_widget;
 pub mod app;
 pub mod board_widget;
+pub mod clock_widget;
+pub mod config;
+pub mod eval_bar_widget;
+pub mod file_dialog_widget;
+pub mod game_over_widget;
+pub mod move_list_widget;
+pub mod promotion_widget;
+pub mod settings_widget;
 pub mod theme;
 
 pub use app::TuiApp;
@@ -0,0 +1,249 @@
+//! Scrollable move list widget for TUI rendering
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget},
+};
+
+use crate::game::engine::MoveHistoryEntry;
+use crate::game::move_list::MoveListRow;
+use crate::input_handler::Navigation;
+use crate::tui::Theme;
+
+/// Lines taken by the table header.
+const HEADER_HEIGHT: u16 = 2;
+
+/// Which move of the game is shown and how far the list is scrolled.
+#[derive(Debug, Clone, Default)]
+pub struct MoveListState {
+    /// Number of moves shown on the board, or None to follow the game.
+    selected: Option<usize>,
+    /// First row drawn.
+    offset: usize,
+    /// Moves played, as of the last frame.
+    plies: usize,
+    /// Rows that fit in the list, as of the last frame.
+    page: usize,
+}
+
+impl MoveListState {
+    /// The number of moves played in the position to show.
+    pub fn viewed_ply(&self) -> usize {
+        self.selected.unwrap_or(self.plies)
+    }
+
+    /// Whether an earlier position is shown instead of the current one.
+    pub fn is_browsing(&self) -> bool {
+        self.selected.is_some()
+    }
+
+    /// Note the number of moves played. A selection past the end of the game,
+    /// e.g. after a takeback, returns to following the game.
+    pub fn sync(&mut self, plies: usize) {
+        self.plies = plies;
+        if self.selected.is_some_and(|selected| selected >= plies) {
+            self.selected = None;
+        }
+    }
+
+    pub fn browse(&mut self, navigation: Navigation) {
+        let current = self.viewed_ply();
+        let page = self.page.max(1) * 2;
+        let target = match navigation {
+            Navigation::PreviousMove => current.saturating_sub(1),
+            Navigation::NextMove => current + 1,
+            Navigation::PageUp => current.saturating_sub(page),
+            Navigation::PageDown => current + page,
+            Navigation::Start => 0,
+            Navigation::Latest => self.plies,
+        };
+        self.selected = Some(target).filter(|&target| target < self.plies);
+    }
+
+    /// Scroll so `row` is visible.
+    fn scroll_to(&mut self, row: usize, height: usize) {
+        if row < self.offset {
+            self.offset = row;
+        } else if row >= self.offset + height {
+            self.offset = row + 1 - height;
+        }
+    }
+}
+
+/// Widget that renders the numbered moves of the game, highlighting the move
+/// shown on the board
+pub struct MoveListWidget<'a> {
+    rows: &'a [MoveListRow<'a>],
+    show_scores: bool,
+    theme: &'a Theme,
+}
+
+impl<'a> MoveListWidget<'a> {
+    pub fn new(rows: &'a [MoveListRow<'a>], show_scores: bool, theme: &'a Theme) -> Self {
+        Self {
+            rows,
+            show_scores,
+            theme,
+        }
+    }
+
+    /// Row and column (0 for White, 1 for Black) of the move that brought the
+    /// game to `ply` moves.
+    fn locate(&self, ply: usize) -> Option<(usize, usize)> {
+        let mut played = 0;
+        for (index, row) in self.rows.iter().enumerate() {
+            for (column, entry) in [row.white, row.black].iter().enumerate() {
+                if entry.is_some() {
+                    played += 1;
+                    if played == ply {
+                        return Some((index, column));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn score(entry: Option<&MoveHistoryEntry>) -> String {
+        entry
+            .and_then(|m| m.score)
+            .map(|s| format!("{:>6}", s))
+            .unwrap_or_else(|| "     -".to_string())
+    }
+
+    fn row_line(&self, row: &MoveListRow, highlighted: Option<usize>) -> Line<'static> {
+        let highlight = Style::default()
+            .fg(self.theme.highlight)
+            .add_modifier(Modifier::REVERSED);
+        let cell = |column: usize, text: String| {
+            if highlighted == Some(column) {
+                Span::styled(text, highlight)
+            } else {
+                Span::raw(text)
+            }
+        };
+        let white = row.white.map_or("...", |m| m.notation.as_str());
+        let black = row.black.map_or("", |m| m.notation.as_str());
+
+        let mut spans = vec![
+            Span::raw(format!(" {:>2} │ ", row.number)),
+            cell(0, format!("{:<10}", white)),
+            Span::raw(" │ "),
+            cell(1, format!("{:<10}", black)),
+        ];
+        if self.show_scores {
+            let scores = match row.black {
+                Some(_) => format!("{}/{}", Self::score(row.white), Self::score(row.black)),
+                None => Self::score(row.white),
+            };
+            spans.push(Span::raw(format!(" │ {}", scores)));
+        }
+        Line::from(spans)
+    }
+}
+
+impl StatefulWidget for MoveListWidget<'_> {
+    type State = MoveListState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut MoveListState) {
+        let title = if state.is_browsing() {
+            "Move History (End to return)"
+        } else {
+            "Move History"
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(self.theme.border_style());
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let height = usize::from(inner.height.saturating_sub(HEADER_HEIGHT));
+        state.page = height;
+        if height == 0 {
+            return;
+        }
+
+        let highlighted = self.locate(state.viewed_ply());
+        match highlighted {
+            Some((row, _)) => state.scroll_to(row, height),
+            // The starting position: show the first moves
+            None => state.offset = 0,
+        }
+        state.offset = state.offset.min(self.rows.len().saturating_sub(height));
+
+        let mut lines = if self.show_scores {
+            vec![
+                Line::from("  # │ White      │ Black      │ Score"),
+                Line::from("  ──┼────────────┼────────────┼────────"),
+            ]
+        } else {
+            vec![
+                Line::from("  # │ White      │ Black"),
+                Line::from("  ──┼────────────┼────────────"),
+            ]
+        };
+        for (index, row) in self.rows.iter().enumerate().skip(state.offset).take(height) {
+            let column = highlighted
+                .filter(|(highlighted_row, _)| *highlighted_row == index)
+                .map(|(_, column)| column);
+            lines.push(self.row_line(row, column));
+        }
+
+        Paragraph::new(lines)
+            .style(self.theme.text_style())
+            .render(inner, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(plies: usize, page: usize) -> MoveListState {
+        let mut state = MoveListState {
+            page,
+            ..MoveListState::default()
+        };
+        state.sync(plies);
+        state
+    }
+
+    #[test]
+    fn test_browsing_steps_through_earlier_positions() {
+        let mut state = state(10, 3);
+        assert_eq!(state.viewed_ply(), 10);
+
+        state.browse(Navigation::PreviousMove);
+        assert_eq!(state.viewed_ply(), 9);
+        state.browse(Navigation::PageUp);
+        assert_eq!(state.viewed_ply(), 3);
+        state.browse(Navigation::Start);
+        assert_eq!(state.viewed_ply(), 0);
+        state.browse(Navigation::PreviousMove);
+        assert_eq!(state.viewed_ply(), 0);
+        state.browse(Navigation::PageDown);
+        assert_eq!(state.viewed_ply(), 6);
+        assert!(state.is_browsing());
+
+        state.browse(Navigation::PageDown);
+        assert!(!state.is_browsing());
+        assert_eq!(state.viewed_ply(), 10);
+    }
+
+    #[test]
+    fn test_new_moves_keep_the_selection_until_it_is_taken_back() {
+        let mut state = state(10, 3);
+        state.browse(Navigation::Start);
+        state.browse(Navigation::NextMove);
+        state.sync(12);
+        assert_eq!(state.viewed_ply(), 1);
+
+        state.sync(1);
+        assert!(!state.is_browsing());
+        assert_eq!(state.viewed_ply(), 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Promotion piece selector for TUI rendering
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Modifier,
+    widgets::{Block, Borders, Widget},
+};
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::tui::Theme;
+
+/// Pieces a pawn can promote to, in the order they are offered.
+const CHOICES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+/// Space between two choices.
+const GAP: u16 = 2;
+
+const CANCEL_HINT: &str = "Esc to cancel";
+
+/// The piece chosen by `key`, either case of its letter, e.g. `n` for a knight.
+pub fn piece_for_key(key: char) -> Option<Piece> {
+    CHOICES
+        .iter()
+        .copied()
+        .find(|piece| piece.to_char(Color::White) == key.to_ascii_uppercase())
+}
+
+/// Label of one choice, e.g. "[N] ♞ knight".
+fn choice_label(piece: Piece, color: Color, theme: &Theme) -> String {
+    format!(
+        "[{}] {} {}",
+        piece.to_char(Color::White),
+        theme.pieces.glyph(piece, color),
+        piece
+    )
+}
+
+/// Widget that offers the pieces a pawn of `color` can promote to, in place of
+/// the input panel
+pub struct PromotionWidget<'a> {
+    color: Color,
+    theme: &'a Theme,
+}
+
+impl<'a> PromotionWidget<'a> {
+    pub fn new(color: Color, theme: &'a Theme) -> Self {
+        Self { color, theme }
+    }
+
+    /// Where each choice is drawn on the row inside the border of `area`, as
+    /// its first column and width.
+    fn choice_spans(&self, area: Rect) -> Vec<(Piece, u16, u16)> {
+        let mut x = area.x + 1;
+        CHOICES
+            .iter()
+            .map(|&piece| {
+                let width = choice_label(piece, self.color, self.theme).chars().count() as u16;
+                let span = (piece, x, width);
+                x += width + GAP;
+                span
+            })
+            .collect()
+    }
+
+    /// The choice drawn at `column`, `row` when the widget fills `area`.
+    pub fn piece_at(&self, area: Rect, column: u16, row: u16) -> Option<Piece> {
+        if row != area.y + 1 {
+            return None;
+        }
+        self.choice_spans(area)
+            .into_iter()
+            .find(|&(_, x, width)| column >= x && column < x + width)
+            .map(|(piece, _, _)| piece)
+    }
+}
+
+impl Widget for PromotionWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Promote to")
+            .border_style(self.theme.border_style());
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if inner.height == 0 {
+            return;
+        }
+
+        let style = self.theme.text_style();
+        let mut end = inner.x;
+        for (piece, x, width) in self.choice_spans(area) {
+            if x >= inner.right() {
+                break;
+            }
+            let max_width = usize::from(width.min(inner.right() - x));
+            buf.set_stringn(
+                x,
+                inner.y,
+                choice_label(piece, self.color, self.theme),
+                max_width,
+                style.add_modifier(Modifier::BOLD),
+            );
+            end = x + width;
+        }
+        let hint_x = end + GAP * 2;
+        if hint_x < inner.right() {
+            buf.set_stringn(
+                hint_x,
+                inner.y,
+                CANCEL_HINT,
+                usize::from(inner.right() - hint_x),
+                style,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_for_key_accepts_either_case() {
+        assert_eq!(piece_for_key('q'), Some(Piece::Queen));
+        assert_eq!(piece_for_key('N'), Some(Piece::Knight));
+        assert_eq!(piece_for_key('k'), None);
+        assert_eq!(piece_for_key('p'), None);
+    }
+
+    #[test]
+    fn test_piece_at_finds_the_clicked_choice() {
+        let theme = Theme::default();
+        let widget = PromotionWidget::new(Color::White, &theme);
+        let area = Rect::new(0, 20, 80, 3);
+        // "[Q] ♛ queen" starts just inside the border
+        assert_eq!(widget.piece_at(area, 1, 21), Some(Piece::Queen));
+        assert_eq!(widget.piece_at(area, 11, 21), Some(Piece::Queen));
+        assert_eq!(widget.piece_at(area, 12, 21), None);
+        assert_eq!(widget.piece_at(area, 14, 21), Some(Piece::Rook));
+        assert_eq!(widget.piece_at(area, 1, 20), None);
+    }
+}
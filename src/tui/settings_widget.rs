@@ -0,0 +1,214 @@
+//! Settings menu for TUI rendering
+
+use std::time::Duration;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::game::engine::EngineSettings;
+use crate::tui::config::Preferences;
+use crate::tui::Theme;
+
+/// Size of the menu, including its border.
+const MENU_WIDTH: u16 = 40;
+const MENU_HEIGHT: u16 = 14;
+
+/// Deepest search the menu offers.
+pub const MAX_DEPTH: u8 = 12;
+
+/// Times per engine move the menu steps through.
+pub const MOVETIMES: [Option<Duration>; 7] = [
+    None,
+    Some(Duration::from_millis(100)),
+    Some(Duration::from_millis(250)),
+    Some(Duration::from_millis(500)),
+    Some(Duration::from_secs(1)),
+    Some(Duration::from_secs(2)),
+    Some(Duration::from_secs(5)),
+];
+
+/// A setting that can be changed from the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Theme,
+    Pieces,
+    Coordinates,
+    SquareNames,
+    Flipped,
+    Bell,
+    Animation,
+    Depth,
+    MoveTime,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 9] = [
+        Setting::Theme,
+        Setting::Pieces,
+        Setting::Coordinates,
+        Setting::SquareNames,
+        Setting::Flipped,
+        Setting::Bell,
+        Setting::Animation,
+        Setting::Depth,
+        Setting::MoveTime,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Setting::Theme => "Theme",
+            Setting::Pieces => "Pieces",
+            Setting::Coordinates => "Coordinates",
+            Setting::SquareNames => "Square names",
+            Setting::Flipped => "Flipped",
+            Setting::Bell => "Bell",
+            Setting::Animation => "Animation",
+            Setting::Depth => "Engine depth",
+            Setting::MoveTime => "Move time",
+        }
+    }
+
+    /// The setting on the row below this one, wrapping around to the first.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// The setting on the row above this one, wrapping around to the last.
+    pub fn previous(self) -> Self {
+        let index = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Where the menu is drawn: centered in `area`, and no larger than it.
+pub fn settings_area(area: Rect) -> Rect {
+    let width = MENU_WIDTH.min(area.width);
+    let height = MENU_HEIGHT.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn switch_name(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// A time per move, e.g. "250 ms" or "2 s".
+fn movetime_name(movetime: Option<Duration>) -> String {
+    match movetime.map(|time| time.as_millis()) {
+        None => "off".to_string(),
+        Some(ms) if ms < 1000 || ms % 1000 != 0 => format!("{} ms", ms),
+        Some(ms) => format!("{} s", ms / 1000),
+    }
+}
+
+/// Widget that shows the current settings, with the one being changed marked,
+/// over the rest of the frame
+pub struct SettingsWidget<'a> {
+    selected: Setting,
+    theme: &'a Theme,
+    preferences: &'a Preferences,
+    engine: EngineSettings,
+    /// Outcome of saving the last change, shown below the settings.
+    note: Option<&'a str>,
+}
+
+impl<'a> SettingsWidget<'a> {
+    pub fn new(
+        selected: Setting,
+        theme: &'a Theme,
+        preferences: &'a Preferences,
+        engine: EngineSettings,
+    ) -> Self {
+        Self {
+            selected,
+            theme,
+            preferences,
+            engine,
+            note: None,
+        }
+    }
+
+    pub fn note(mut self, note: Option<&'a str>) -> Self {
+        self.note = note;
+        self
+    }
+}
+
+impl Widget for SettingsWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Settings (F3 to close)")
+            .border_style(self.theme.border_style());
+
+        let mut lines: Vec<Line> = Setting::ALL
+            .iter()
+            .map(|&setting| {
+                let value = match setting {
+                    Setting::Theme => self.theme.name.to_string(),
+                    Setting::Pieces => self.theme.pieces.name().to_string(),
+                    Setting::Coordinates => self.theme.coordinates.name().to_string(),
+                    Setting::SquareNames => switch_name(self.theme.square_names).to_string(),
+                    Setting::Flipped => switch_name(self.preferences.flipped).to_string(),
+                    Setting::Bell => switch_name(self.preferences.bell).to_string(),
+                    Setting::Animation => switch_name(self.preferences.animation).to_string(),
+                    Setting::Depth => self.engine.depth.to_string(),
+                    Setting::MoveTime => movetime_name(self.engine.movetime),
+                };
+                let text = format!("{:<14}< {} >", format!("{}:", setting.label()), value);
+                if setting == self.selected {
+                    Line::styled(
+                        format!("> {}", text),
+                        self.theme.text_style().add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Line::from(format!("  {}", text))
+                }
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from("↑↓ select  ←→ change"));
+        lines.push(Line::from(self.note.unwrap_or("")));
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(block)
+            .style(self.theme.text_style())
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_area_is_centered_and_fits() {
+        let area = settings_area(Rect::new(0, 0, 100, 40));
+        assert_eq!(area, Rect::new(30, 13, MENU_WIDTH, MENU_HEIGHT));
+        let small = settings_area(Rect::new(0, 0, 20, 4));
+        assert_eq!(small, Rect::new(0, 0, 20, 4));
+    }
+
+    #[test]
+    fn test_movetimes_are_named_in_the_largest_whole_unit() {
+        assert_eq!(movetime_name(None), "off");
+        assert_eq!(movetime_name(MOVETIMES[2]), "250 ms");
+        assert_eq!(movetime_name(MOVETIMES[5]), "2 s");
+        assert_eq!(movetime_name(Some(Duration::from_millis(1500))), "1500 ms");
+    }
+}
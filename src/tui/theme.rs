@@ -1,29 +1,118 @@
 //! Color theme for the TUI
 //!
-//! Colors can be configured via a `tui_colors.toml` file in the current working directory.
-//! If the file doesn't exist or is invalid, default colors are used.
+//! A few color schemes are bundled as presets, and pieces can be drawn as
+//! unicode figurines or as letters. The board's coordinates go around it,
+//! inside its edge squares or nowhere, and the square of a piece picked up with
+//! the mouse can be named. All of these can be switched while playing from the
+//! settings menu (F3), which saves them to the config file.
+//!
+//! The starting theme can be configured via a `tui_colors.toml` file in the
+//! current working directory: pick a `preset`, a `pieces` set and the
+//! `coordinates`, turn `square_names` on, and override any of the preset's
+//! colors. If the file doesn't exist or is invalid, the default theme is used.
 //!
 //! Example `tui_colors.toml`:
 //! ```toml
+//! preset = green                 # classic, green, blue or gray
+//! pieces = letters               # figurines or letters
+//! coordinates = inside           # outside, inside or off
+//! square_names = on              # on or off
 //! light_square = 200, 180, 150  # Medium-light beige
 //! dark_square = 120, 90, 60      # Medium-dark brown
 //! piece_white = 255, 255, 255    # Very light - visible on dark squares
 //! piece_black = 30, 30, 30       # Very dark - visible on light squares
 //! ```
 
-use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
 
 use ratatui::style::{Color, Modifier, Style};
 
+use crate::board::piece::Piece;
+use crate::tui::config;
+
+/// Name of a theme whose colors come from the config file rather than a preset.
+const CUSTOM_THEME: &str = "custom";
+
+/// How pieces are drawn on the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PieceSet {
+    /// Unicode chess figurines, e.g. ♞
+    #[default]
+    Figurines,
+    /// Piece letters, uppercase for White, e.g. N and n
+    Letters,
+}
+
+impl PieceSet {
+    pub const ALL: [PieceSet; 2] = [PieceSet::Figurines, PieceSet::Letters];
+
+    /// The character drawn for `piece` of `color`.
+    pub fn glyph(self, piece: Piece, color: crate::board::color::Color) -> char {
+        match self {
+            PieceSet::Figurines => piece.to_unicode_piece_char(color),
+            PieceSet::Letters => piece.to_char(color),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceSet::Figurines => "figurines",
+            PieceSet::Letters => "letters",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|set| set.name() == name)
+    }
+}
+
+/// Where the board's file letters and rank numbers are drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coordinates {
+    /// Around the board, beside the squares.
+    #[default]
+    Outside,
+    /// In the corners of the squares on the board's bottom and left edges.
+    Inside,
+    Off,
+}
+
+impl Coordinates {
+    pub const ALL: [Coordinates; 3] = [Coordinates::Outside, Coordinates::Inside, Coordinates::Off];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Coordinates::Outside => "outside",
+            Coordinates::Inside => "inside",
+            Coordinates::Off => "off",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.name() == name)
+    }
+}
+
 /// Color theme for the chess TUI
+#[derive(Clone, PartialEq)]
 pub struct Theme {
+    /// Name of the preset, or "custom" for colors from the config file.
+    pub name: &'static str,
+    pub pieces: PieceSet,
+    pub coordinates: Coordinates,
+    /// Name the square of a piece picked up with the mouse.
+    pub square_names: bool,
     pub light_square: Color,
     pub dark_square: Color,
     pub piece_white: Color,
     pub piece_black: Color,
     pub highlight: Color,
+    pub last_move: Color,
+    pub check: Color,
+    pub low_time: Color,
+    pub legal_move: Color,
+    pub capture: Color,
+    pub illegal_move: Color,
     pub border: Color,
     pub text: Color,
 }
@@ -31,68 +120,113 @@ pub struct Theme {
 impl Default for Theme {
     fn default() -> Self {
         // Try to load from config file, fall back to defaults
-        Self::from_config_file().unwrap_or(Self {
+        Self::from_config_file().unwrap_or_else(Self::classic)
+    }
+}
+
+impl Theme {
+    /// Brown squares in medium tones, which keep both piece colors visible.
+    fn classic() -> Self {
+        Self {
+            name: "classic",
+            pieces: PieceSet::default(),
+            coordinates: Coordinates::default(),
+            square_names: false,
             light_square: Color::Rgb(200, 180, 150), // Medium-light beige - provides contrast for both piece colors
             dark_square: Color::Rgb(120, 90, 60), // Medium-dark brown - provides contrast for both piece colors
             piece_white: Color::Rgb(255, 255, 255), // Very light - clearly visible on dark squares
             piece_black: Color::Rgb(30, 30, 30),  // Very dark - clearly visible on light squares
             highlight: Color::Yellow,
+            last_move: Color::Rgb(170, 162, 58),
+            check: Color::Red,
+            low_time: Color::Red,
+            legal_move: Color::Green,
+            capture: Color::LightRed,
+            illegal_move: Color::DarkGray,
             border: Color::Gray,
             text: Color::White,
-        })
+        }
+    }
+
+    /// The classic theme with other square colors.
+    fn with_squares(name: &'static str, light_square: Color, dark_square: Color) -> Self {
+        Self {
+            name,
+            light_square,
+            dark_square,
+            ..Self::classic()
+        }
+    }
+
+    /// The bundled color schemes, the default first.
+    pub fn presets() -> Vec<Self> {
+        vec![
+            Self::classic(),
+            Self::with_squares("green", Color::Rgb(170, 190, 130), Color::Rgb(100, 135, 70)),
+            Self::with_squares("blue", Color::Rgb(160, 180, 200), Color::Rgb(85, 110, 140)),
+            Self::with_squares("gray", Color::Rgb(170, 170, 170), Color::Rgb(100, 100, 100)),
+        ]
+    }
+
+    /// Whether the colors are a bundled preset's.
+    pub fn is_preset(&self) -> bool {
+        self.name != CUSTOM_THEME
     }
-}
 
-impl Theme {
     /// Load theme from `tui_colors.toml` file in the current working directory.
     /// Returns None if the file doesn't exist or can't be parsed.
     fn from_config_file() -> Option<Self> {
-        let config_path = Path::new("tui_colors.toml");
-        if !config_path.exists() {
-            return None;
-        }
+        let contents = fs::read_to_string(config::CONFIG_FILE).ok()?;
+        Self::from_config(&contents)
+    }
 
-        let contents = fs::read_to_string(config_path).ok()?;
-        let mut colors = HashMap::new();
+    /// Parse the contents of a config file. Returns None if the preset, the
+    /// piece set, the coordinates, the square names switch or a color can't be
+    /// parsed.
+    fn from_config(contents: &str) -> Option<Self> {
+        let mut settings = config::parse(contents);
 
-        // Parse simple key = value format
-        for line in contents.lines() {
-            let line = line.trim();
-            // Skip empty lines and comments
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+        let mut theme = match settings.remove("preset") {
+            Some(name) => Self::presets()
+                .into_iter()
+                .find(|preset| preset.name == name)?,
+            None => Self::classic(),
+        };
+        if let Some(name) = settings.remove("pieces") {
+            theme.pieces = PieceSet::from_name(name)?;
+        }
+        if let Some(name) = settings.remove("coordinates") {
+            theme.coordinates = Coordinates::from_name(name)?;
+        }
+        if let Some(value) = settings.remove("square_names") {
+            theme.square_names = config::parse_switch(value)?;
+        }
 
-            // Parse "key = r, g, b" format
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim();
-                let value = value.trim();
-
-                // Parse RGB values: "r, g, b" or "[r, g, b]"
-                let rgb_str = value.trim_start_matches('[').trim_end_matches(']');
-                let rgb_parts: Vec<&str> = rgb_str.split(',').map(|s| s.trim()).collect();
-                if rgb_parts.len() == 3 {
-                    if let (Ok(r), Ok(g), Ok(b)) = (
-                        rgb_parts[0].parse::<u8>(),
-                        rgb_parts[1].parse::<u8>(),
-                        rgb_parts[2].parse::<u8>(),
-                    ) {
-                        colors.insert(key.to_string(), Color::Rgb(r, g, b));
-                    }
-                }
+        for (key, value) in settings {
+            let slot = match key {
+                "light_square" => &mut theme.light_square,
+                "dark_square" => &mut theme.dark_square,
+                "piece_white" => &mut theme.piece_white,
+                "piece_black" => &mut theme.piece_black,
+                "highlight" => &mut theme.highlight,
+                "last_move" => &mut theme.last_move,
+                "check" => &mut theme.check,
+                "low_time" => &mut theme.low_time,
+                "legal_move" => &mut theme.legal_move,
+                "capture" => &mut theme.capture,
+                "illegal_move" => &mut theme.illegal_move,
+                "border" => &mut theme.border,
+                "text" => &mut theme.text,
+                _ => continue, // Unknown keys are ignored
+            };
+            let color = parse_rgb(value)?;
+            // Colors that only repeat the preset's keep its name
+            if *slot != color {
+                *slot = color;
+                theme.name = CUSTOM_THEME;
             }
         }
-
-        // All four colors must be present
-        Some(Self {
-            light_square: *colors.get("light_square")?,
-            dark_square: *colors.get("dark_square")?,
-            piece_white: *colors.get("piece_white")?,
-            piece_black: *colors.get("piece_black")?,
-            highlight: Color::Yellow, // Not configurable for now
-            border: Color::Gray,      // Not configurable for now
-            text: Color::White,       // Not configurable for now
-        })
+        Some(theme)
     }
 
     /// Get style for a square with a specific piece color
@@ -126,13 +260,125 @@ impl Theme {
         }
     }
 
+    /// Get the color of a coordinate drawn inside a square: the other square
+    /// color
+    pub fn inside_label_color(&self, is_light_square: bool) -> Color {
+        if is_light_square {
+            self.dark_square
+        } else {
+            self.light_square
+        }
+    }
+
+    /// Get the color marking a square the selected piece can move to
+    pub fn destination_color(&self, capture: bool, legal: bool) -> Color {
+        match (legal, capture) {
+            (false, _) => self.illegal_move,
+            (true, true) => self.capture,
+            (true, false) => self.legal_move,
+        }
+    }
+
+    /// Get style for one side's part of the evaluation bar, filled in its
+    /// piece color with text in the other
+    pub fn eval_bar_style(&self, color: crate::board::color::Color) -> Style {
+        match color {
+            crate::board::color::Color::White => {
+                Style::default().bg(self.piece_white).fg(self.piece_black)
+            }
+            crate::board::color::Color::Black => {
+                Style::default().bg(self.piece_black).fg(self.piece_white)
+            }
+        }
+    }
+
     /// Get style for text
     pub fn text_style(&self) -> Style {
         Style::default().fg(self.text)
     }
 
+    /// Get style for a clock: highlighted while running, red when low on time
+    pub fn clock_style(&self, running: bool, low_time: bool) -> Style {
+        let style = match (running, low_time) {
+            (_, true) => Style::default().fg(self.low_time),
+            (true, false) => Style::default().fg(self.highlight),
+            (false, false) => self.text_style(),
+        };
+        if running {
+            style.add_modifier(Modifier::BOLD)
+        } else {
+            style
+        }
+    }
+
     /// Get style for borders
     pub fn border_style(&self) -> Style {
         Style::default().fg(self.border)
     }
 }
+
+/// Parse RGB values: "r, g, b" or "[r, g, b]"
+fn parse_rgb(value: &str) -> Option<Color> {
+    let rgb_str = value.trim_start_matches('[').trim_end_matches(']');
+    let rgb_parts: Vec<&str> = rgb_str.split(',').map(|s| s.trim()).collect();
+    match rgb_parts[..] {
+        [r, g, b] => Some(Color::Rgb(
+            r.parse().ok()?,
+            g.parse().ok()?,
+            b.parse().ok()?,
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_overrides_a_preset() {
+        let theme = Theme::from_config(
+            "# My colors\npreset = blue\npieces = letters\nhighlight = 1, 2, 3  # bright\n",
+        )
+        .expect("valid config");
+        assert_eq!(theme.name, CUSTOM_THEME);
+        assert_eq!(theme.pieces, PieceSet::Letters);
+        assert_eq!(theme.highlight, Color::Rgb(1, 2, 3));
+        assert_eq!(theme.light_square, Color::Rgb(160, 180, 200));
+
+        let theme = Theme::from_config("preset = gray\nlight_square = 170, 170, 170")
+            .expect("valid config");
+        assert_eq!(theme.name, "gray");
+        assert_eq!(theme.pieces, PieceSet::Figurines);
+        assert_eq!(theme.coordinates, Coordinates::Outside);
+        assert!(!theme.square_names);
+
+        let theme =
+            Theme::from_config("coordinates = inside\nsquare_names = on").expect("valid config");
+        assert_eq!(theme.name, "classic");
+        assert_eq!(theme.coordinates, Coordinates::Inside);
+        assert!(theme.square_names);
+    }
+
+    #[test]
+    fn test_config_with_an_unknown_preset_or_bad_color_is_invalid() {
+        assert!(Theme::from_config("preset = neon").is_none());
+        assert!(Theme::from_config("pieces = emoji").is_none());
+        assert!(Theme::from_config("coordinates = everywhere").is_none());
+        assert!(Theme::from_config("square_names = maybe").is_none());
+        assert!(Theme::from_config("light_square = 200, 180").is_none());
+    }
+
+    #[test]
+    fn test_letters_follow_the_piece_color() {
+        use crate::board::color::Color as PieceColor;
+        assert_eq!(
+            PieceSet::Letters.glyph(Piece::Knight, PieceColor::White),
+            'N'
+        );
+        assert_eq!(
+            PieceSet::Letters.glyph(Piece::Knight, PieceColor::Black),
+            'n'
+        );
+    }
+}
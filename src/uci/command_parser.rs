@@ -9,6 +9,8 @@ pub enum UciCommand {
     Uci,
     /// Check if engine is ready
     IsReady,
+    /// The next position is from a different game
+    UciNewGame,
     /// Set position from FEN or startpos with optional moves
     Position {
         fen: Option<String>,
@@ -17,15 +19,22 @@ pub enum UciCommand {
     /// Start searching with optional parameters
     Go {
         depth: Option<u8>,
+        nodes: Option<u64>,
         movetime: Option<u64>,
         wtime: Option<u64>,
         btime: Option<u64>,
         winc: Option<u64>,
         binc: Option<u64>,
+        movestogo: Option<u32>,
         infinite: bool,
+        /// Search the position, which ends in the expected reply, on the
+        /// opponent's time until `ponderhit` or `stop`
+        ponder: bool,
     },
     /// Stop searching
     Stop,
+    /// The opponent played the move being pondered; search on for real
+    PonderHit,
     /// Quit the engine
     Quit,
     /// Set an option (UCI protocol feature, currently not implemented)
@@ -49,8 +58,10 @@ impl FromStr for UciCommand {
         match command.as_str() {
             "uci" => Ok(UciCommand::Uci),
             "isready" => Ok(UciCommand::IsReady),
+            "ucinewgame" => Ok(UciCommand::UciNewGame),
             "quit" => Ok(UciCommand::Quit),
             "stop" => Ok(UciCommand::Stop),
+            "ponderhit" => Ok(UciCommand::PonderHit),
 
             "position" => parse_position_command(&parts[1..]),
 
@@ -93,12 +104,11 @@ fn parse_position_command(parts: &[&str]) -> Result<UciCommand, String> {
     }
 
     // Parse moves if present
-    if i < parts.len() && parts[i] == "moves" {
-        i += 1;
-        while i < parts.len() {
-            moves.push(parts[i].to_string());
-            i += 1;
+    if i < parts.len() {
+        if parts[i] != "moves" {
+            return Err(format!("expected moves, got: {}", parts[i]));
         }
+        moves.extend(parts[i + 1..].iter().map(|part| part.to_string()));
     }
 
     Ok(UciCommand::Position { fen, moves })
@@ -121,23 +131,29 @@ fn parse_next_value<T: std::str::FromStr>(
 
 fn parse_go_command(parts: &[&str]) -> Result<UciCommand, String> {
     let mut depth = None;
+    let mut nodes = None;
     let mut movetime = None;
     let mut wtime = None;
     let mut btime = None;
     let mut winc = None;
     let mut binc = None;
+    let mut movestogo = None;
     let mut infinite = false;
+    let mut ponder = false;
     let mut i = 0;
 
     while i < parts.len() {
         match parts[i] {
             "depth" => depth = Some(parse_next_value(parts, &mut i, "depth")?),
+            "nodes" => nodes = Some(parse_next_value(parts, &mut i, "nodes")?),
             "movetime" => movetime = Some(parse_next_value(parts, &mut i, "movetime")?),
             "wtime" => wtime = Some(parse_next_value(parts, &mut i, "wtime")?),
             "btime" => btime = Some(parse_next_value(parts, &mut i, "btime")?),
             "winc" => winc = Some(parse_next_value(parts, &mut i, "winc")?),
             "binc" => binc = Some(parse_next_value(parts, &mut i, "binc")?),
+            "movestogo" => movestogo = Some(parse_next_value(parts, &mut i, "movestogo")?),
             "infinite" => infinite = true,
+            "ponder" => ponder = true,
             _ => {}
         }
         i += 1;
@@ -145,12 +161,15 @@ fn parse_go_command(parts: &[&str]) -> Result<UciCommand, String> {
 
     Ok(UciCommand::Go {
         depth,
+        nodes,
         movetime,
         wtime,
         btime,
         winc,
         binc,
+        movestogo,
         infinite,
+        ponder,
     })
 }
 
@@ -257,6 +276,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_position_fen_with_moves_and_junk() {
+        let cmd = "position   fen 4k3/8/8/8/8/8/8/4K2R w K -   moves e1g1  e8d8 "
+            .parse::<UciCommand>()
+            .unwrap();
+        assert_eq!(
+            cmd,
+            UciCommand::Position {
+                fen: Some("4k3/8/8/8/8/8/8/4K2R w K -".to_string()),
+                moves: vec!["e1g1".to_string(), "e8d8".to_string()]
+            }
+        );
+        assert_eq!(
+            "ucinewgame".parse::<UciCommand>().unwrap(),
+            UciCommand::UciNewGame
+        );
+        assert!("position startpos e2e4".parse::<UciCommand>().is_err());
+        assert!("position fen moves e2e4".parse::<UciCommand>().is_err());
+    }
+
     #[test]
     fn test_parse_go_depth() {
         let cmd = "go depth 6".parse::<UciCommand>().unwrap();
@@ -264,12 +303,15 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: Some(6),
+                nodes: None,
                 movetime: None,
                 wtime: None,
                 btime: None,
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
@@ -281,12 +323,15 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: Some(1000),
                 wtime: None,
                 btime: None,
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
@@ -298,12 +343,15 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: None,
                 wtime: None,
                 btime: None,
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: true,
+                ponder: false,
             }
         );
     }
@@ -315,12 +363,15 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: None,
                 wtime: Some(60000),
                 btime: Some(60000),
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
@@ -334,16 +385,85 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: None,
                 wtime: Some(60000),
                 btime: Some(60000),
                 winc: Some(1000),
                 binc: Some(1000),
+                movestogo: None,
+                infinite: false,
+                ponder: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_go_nodes() {
+        let cmd = "go nodes 50000 depth 8".parse::<UciCommand>().unwrap();
+        assert_eq!(
+            cmd,
+            UciCommand::Go {
+                depth: Some(8),
+                nodes: Some(50000),
+                movetime: None,
+                wtime: None,
+                btime: None,
+                winc: None,
+                binc: None,
+                movestogo: None,
+                infinite: false,
+                ponder: false,
+            }
+        );
+        assert!("go nodes many".parse::<UciCommand>().is_err());
+    }
+
+    #[test]
+    fn test_parse_go_movestogo() {
+        let cmd = "go wtime 60000 btime 55000 movestogo 12"
+            .parse::<UciCommand>()
+            .unwrap();
+        assert_eq!(
+            cmd,
+            UciCommand::Go {
+                depth: None,
+                nodes: None,
+                movetime: None,
+                wtime: Some(60000),
+                btime: Some(55000),
+                winc: None,
+                binc: None,
+                movestogo: Some(12),
                 infinite: false,
+                ponder: false,
             }
         );
     }
 
+    #[test]
+    fn test_parse_go_ponder_and_ponderhit() {
+        let cmd = "go ponder wtime 60000 btime 55000"
+            .parse::<UciCommand>()
+            .unwrap();
+        assert_eq!(
+            cmd,
+            UciCommand::Go {
+                depth: None,
+                nodes: None,
+                movetime: None,
+                wtime: Some(60000),
+                btime: Some(55000),
+                winc: None,
+                binc: None,
+                movestogo: None,
+                infinite: false,
+                ponder: true,
+            }
+        );
+        assert_eq!("ponderhit".parse::<UciCommand>(), Ok(UciCommand::PonderHit));
+    }
+
     #[test]
     fn test_parse_go_combined_depth_and_time() {
         let cmd = "go depth 6 wtime 60000 btime 60000"
@@ -353,12 +473,15 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: Some(6),
+                nodes: None,
                 movetime: None,
                 wtime: Some(60000),
                 btime: Some(60000),
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
@@ -372,12 +495,15 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: Some(5000),
                 wtime: Some(60000),
                 btime: Some(60000),
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
@@ -389,31 +515,35 @@ mod tests {
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: None,
                 wtime: None,
                 btime: None,
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
 
     #[test]
     fn test_parse_go_ignores_unknown_params() {
-        let cmd = "go movestogo 30 nodes 100000"
-            .parse::<UciCommand>()
-            .unwrap();
+        let cmd = "go mate 3 searchmoves e2e4".parse::<UciCommand>().unwrap();
         assert_eq!(
             cmd,
             UciCommand::Go {
                 depth: None,
+                nodes: None,
                 movetime: None,
                 wtime: None,
                 btime: None,
                 winc: None,
                 binc: None,
+                movestogo: None,
                 infinite: false,
+                ponder: false,
             }
         );
     }
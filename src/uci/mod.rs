@@ -3,7 +3,8 @@
 pub mod command_parser;
 pub mod protocol;
 pub mod response_formatter;
+pub mod time_management;
 
 pub use command_parser::UciCommand;
 pub use protocol::UciProtocol;
-pub use response_formatter::UciResponseFormatter;
+pub use response_formatter::{UciResponseFormatter, UciScore};
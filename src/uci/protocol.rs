@@ -1,16 +1,29 @@
 //! UCI protocol state machine and command execution
 
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
 use std::str::FromStr;
-
-use common::bitboard::Square;
-
-use crate::board::piece::Piece;
-use crate::board::Board;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::alpha_beta_searcher::{
+    CurrentMove, SearchInfo, SearchLimits, DEFAULT_TT_SIZE_MB, MAX_LIMITED_SEARCH_DEPTH,
+};
+use crate::board::color::Color;
+use crate::board::{Board, Variant};
+use crate::book::BookSettings;
+use crate::chess_move::chess_move::ChessMove;
+use crate::evaluate::{self, GameEnding};
 use crate::game::engine::{Engine, EngineConfig};
+use crate::game::learning::{position_key, Experience};
+use crate::game::strength::{Strength, MAX_SKILL_LEVEL};
+use crate::move_generator::MoveGenerator;
 
 use super::command_parser::UciCommand;
-use super::response_formatter::UciResponseFormatter;
+use super::response_formatter::{UciResponseFormatter, UciScore};
+use super::time_management::ClockState;
 
 /// Current state of the UCI protocol
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -23,24 +36,49 @@ pub enum UciState {
     Searching,
 }
 
-/// Calculate time allocation for a single move.
-///
-/// Uses `time_remaining / 30 + increment * 80%`, clamped to not exceed
-/// `time_remaining - 50ms` safety margin.
-fn allocate_time(time_remaining_ms: u64, increment_ms: u64) -> u64 {
-    let base = time_remaining_ms / 30;
-    let inc_bonus = increment_ms * 4 / 5;
-    let budget = base + inc_bonus;
-    let safety_margin = 50;
-    let max_allowed = time_remaining_ms.saturating_sub(safety_margin);
-    budget.min(max_allowed).max(1) // always at least 1ms
-}
+/// How often a running search checks for `stop`, `quit` and `isready`.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long a search runs before reporting the root moves it searches, which
+/// would only flood the GUI while depths complete quickly.
+const CURRMOVE_DELAY: Duration = Duration::from_secs(1);
+
+/// How often a long search reports its node count between completed depths.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `UCI_Elo` until a GUI sets it.
+pub(crate) const DEFAULT_UCI_ELO: u16 = 1500;
+
+/// Highest `BookDepth`, in moves per side.
+pub(crate) const MAX_BOOK_DEPTH: u8 = 100;
+
+/// Largest transposition table `Hash` allows, in MB.
+pub(crate) const MAX_HASH_MB: u16 = 4096;
 
 /// UCI protocol handler
 pub struct UciProtocol {
     state: UciState,
     engine: Engine,
     should_quit: bool,
+    /// The transposition table's size in MB, from `Hash`.
+    hash_mb: usize,
+    /// Rules for the positions set from now on, from `UCI_Variant`.
+    variant: Variant,
+    /// Whether to play at `elo`, from `UCI_LimitStrength`.
+    limit_strength: bool,
+    /// From `UCI_Elo`.
+    elo: u16,
+    /// From `Skill Level`; weakens play below `MAX_SKILL_LEVEL`.
+    skill_level: u8,
+    /// From `OwnBook`, `BookPolicy` and `BookDepth`.
+    book: BookSettings,
+    /// The learning file and what it holds.
+    learning: Option<(String, Arc<Experience>)>,
+    /// From `Learning`.
+    learning_enabled: bool,
+    /// The engine's moves of the current game, to learn from once its
+    /// result is seen: the position's learning key, the move and its color.
+    engine_moves: Vec<(String, ChessMove, Color)>,
 }
 
 impl Default for UciProtocol {
@@ -56,9 +94,26 @@ impl UciProtocol {
             state: UciState::WaitingForUci,
             engine: Engine::with_config(EngineConfig::default()),
             should_quit: false,
+            hash_mb: DEFAULT_TT_SIZE_MB,
+            variant: Variant::Standard,
+            limit_strength: false,
+            elo: DEFAULT_UCI_ELO,
+            skill_level: MAX_SKILL_LEVEL,
+            book: BookSettings::default(),
+            learning: None,
+            learning_enabled: true,
+            engine_moves: Vec::new(),
         }
     }
 
+    /// Learn from the engine's games in the file at `path`, which holds
+    /// `experience`; see `game::learning`. The `Learning` option turns this
+    /// off and on.
+    pub fn with_learning(mut self, path: &str, experience: Experience) -> Self {
+        self.learning = Some((path.to_string(), Arc::new(experience)));
+        self
+    }
+
     /// Check if the protocol should quit
     pub fn should_quit(&self) -> bool {
         self.should_quit
@@ -74,88 +129,116 @@ impl UciProtocol {
 
             UciCommand::IsReady => Some(UciResponseFormatter::format_ready_response()),
 
+            // Positions always start a fresh engine, so there is nothing
+            // left over from the last game to clear but the moves of a game
+            // whose result was never seen
+            UciCommand::UciNewGame => {
+                self.engine_moves.clear();
+                None
+            }
+
             UciCommand::Position { fen, moves } => {
                 if let Err(e) = self.set_position(fen, moves) {
                     Some(UciResponseFormatter::format_error(&e))
                 } else {
+                    let board = self.engine.board().clone();
+                    let position_hashes = self.engine.position_hashes().to_vec();
+                    self.learn_if_game_over(board, &position_hashes);
                     None
                 }
             }
 
             UciCommand::Go {
                 depth,
+                nodes,
                 movetime,
                 wtime,
                 btime,
                 winc,
                 binc,
+                movestogo,
                 infinite,
+                ponder,
             } => {
                 self.state = UciState::Searching;
 
-                // Determine time budget and depth
-                let time_budget_ms = if let Some(mt) = movetime {
-                    // movetime takes precedence
-                    Some(mt)
-                } else if !infinite {
-                    // Calculate time budget from clock
-                    let turn = self.engine.board().turn();
-                    let (time_for_side, increment) = match turn {
-                        crate::board::color::Color::White => {
-                            (wtime.unwrap_or(0), winc.unwrap_or(0))
-                        }
-                        crate::board::color::Color::Black => {
-                            (btime.unwrap_or(0), binc.unwrap_or(0))
-                        }
-                    };
-                    if time_for_side > 0 {
-                        Some(allocate_time(time_for_side, increment))
-                    } else {
-                        None
-                    }
-                } else {
-                    None // infinite
+                // movetime takes precedence over the clock; `infinite`
+                // searches until `stop`, and a bare `go` to a fixed depth. A
+                // pondering search's budget applies from `ponderhit`.
+                let clock = ClockState {
+                    wtime,
+                    btime,
+                    winc,
+                    binc,
+                    movestogo,
+                };
+                let limits = SearchLimits {
+                    depth: depth.or(ponder.then_some(MAX_LIMITED_SEARCH_DEPTH)),
+                    nodes: nodes.map(|nodes| nodes as usize),
+                    movetime: movetime.map(Duration::from_millis),
+                    clock: match movetime {
+                        Some(_) => None,
+                        None => clock.time_limits(self.engine.board().turn()),
+                    },
+                    infinite,
+                };
+
+                self.engine.set_book_settings(self.book);
+                self.engine.set_experience(self.experience());
+
+                // A weakened engine searches no further than its strength allows
+                let strength = self.strength();
+                self.engine.set_strength(strength);
+                let limits = match strength {
+                    Some(strength) => strength.limit_search(limits),
+                    None => limits,
                 };
 
                 // Reset depth to default before each search -- depth is per-command,
                 // not persistent across go commands.
                 const DEFAULT_DEPTH: u8 = 4;
-                self.engine.set_search_depth(depth.unwrap_or(DEFAULT_DEPTH));
+                self.engine.set_search_depth(DEFAULT_DEPTH);
 
-                let result = if let Some(budget_ms) = time_budget_ms {
-                    let budget = std::time::Duration::from_millis(budget_ms);
-                    self.engine.get_best_move_with_time_limit(budget)
-                } else {
-                    self.engine.get_best_move()
-                };
+                // `run` stops the search through the engine's stop flag
+                let result = self.engine.get_best_move_until_stopped(limits);
 
                 self.state = UciState::Ready;
 
                 match result {
                     Ok(best_move) => {
-                        let uci_move = best_move.to_uci();
-                        Some(UciResponseFormatter::format_bestmove_response(&uci_move))
+                        self.record_engine_move(&best_move);
+                        let ponder_move = self
+                            .engine
+                            .expected_reply_to(&best_move)
+                            .map(|reply| reply.to_uci());
+                        Some(UciResponseFormatter::format_bestmove_response(
+                            &best_move.to_uci(),
+                            ponder_move.as_deref(),
+                        ))
                     }
                     Err(e) => Some(UciResponseFormatter::format_error(&format!("{:?}", e))),
                 }
             }
 
             UciCommand::Stop => {
-                // For now, we don't support stopping mid-search
-                // Since our search is synchronous
+                // A running search is stopped by `run`; with none there is
+                // nothing to stop
                 self.state = UciState::Ready;
                 None
             }
 
+            // Only meaningful while pondering, which `run` handles
+            UciCommand::PonderHit => None,
+
             UciCommand::Quit => {
                 self.should_quit = true;
                 None
             }
 
-            UciCommand::SetOption { name: _, value: _ } => {
-                // Options not yet implemented
-                None
-            }
+            UciCommand::SetOption { name, value } => self
+                .set_option(&name, value.as_deref().unwrap_or_default())
+                .err()
+                .map(|e| UciResponseFormatter::format_error(&e)),
 
             UciCommand::Unknown(cmd) => {
                 if !cmd.is_empty() {
@@ -170,14 +253,118 @@ impl UciProtocol {
         }
     }
 
-    /// Set the board position from FEN or startpos, optionally applying moves
-    fn set_position(&mut self, fen: Option<String>, moves: Vec<String>) -> Result<(), String> {
-        // Create board from FEN or use starting position
-        let board = if let Some(fen_string) = fen {
-            Board::from_str(&fen_string).map_err(|e| format!("Invalid FEN: {:?}", e))?
+    /// Apply a `setoption`. Options the engine doesn't have, such as
+    /// `Ponder`, are ignored.
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let spin = |value: &str| {
+            value
+                .parse::<u16>()
+                .map_err(|_| format!("invalid value {:?} for option {}", value, name))
+        };
+        if name.eq_ignore_ascii_case("Hash") {
+            self.hash_mb = usize::from(spin(value)?.clamp(1, MAX_HASH_MB));
+            self.engine.set_hash_size(self.hash_mb);
+        } else if name.eq_ignore_ascii_case("UCI_Variant") {
+            self.variant = value.parse()?;
+        } else if name.eq_ignore_ascii_case("UCI_LimitStrength") {
+            self.limit_strength = value.eq_ignore_ascii_case("true");
+        } else if name.eq_ignore_ascii_case("UCI_Elo") {
+            self.elo = Strength::new(spin(value)?).elo();
+        } else if name.eq_ignore_ascii_case("Skill Level") {
+            self.skill_level = spin(value)?.min(u16::from(MAX_SKILL_LEVEL)) as u8;
+        } else if name.eq_ignore_ascii_case("Learning") {
+            self.learning_enabled = value.eq_ignore_ascii_case("true");
+        } else if name.eq_ignore_ascii_case("OwnBook") {
+            self.book.enabled = value.eq_ignore_ascii_case("true");
+        } else if name.eq_ignore_ascii_case("BookPolicy") {
+            self.book.policy = value.parse()?;
+        } else if name.eq_ignore_ascii_case("BookDepth") {
+            // 0 keeps to the book for as long as the game stays in it
+            let depth = spin(value)?.min(u16::from(MAX_BOOK_DEPTH)) as u8;
+            self.book.depth = (depth > 0).then_some(depth);
+        }
+        Ok(())
+    }
+
+    /// The strength to play at: `UCI_Elo` while `UCI_LimitStrength` is on,
+    /// else the `Skill Level`.
+    fn strength(&self) -> Option<Strength> {
+        if self.limit_strength {
+            Some(Strength::new(self.elo))
         } else {
-            Board::default()
+            Strength::from_skill_level(self.skill_level)
+        }
+    }
+
+    /// The experience to choose moves with, while `Learning` is on.
+    fn experience(&self) -> Option<Arc<Experience>> {
+        self.learning
+            .as_ref()
+            .filter(|_| self.learning_enabled)
+            .map(|(_, experience)| Arc::clone(experience))
+    }
+
+    /// Keep `best_move`, about to be played in the current position, for the
+    /// learning file, and record the game if the move ends it.
+    fn record_engine_move(&mut self, best_move: &ChessMove) {
+        if self.experience().is_none() {
+            return;
+        }
+        let mut board = self.engine.board().clone();
+        self.engine_moves
+            .push((position_key(&board), best_move.clone(), board.turn()));
+        if best_move.apply(&mut board).is_ok() {
+            board.toggle_turn();
+            let mut position_hashes = self.engine.position_hashes().to_vec();
+            position_hashes.push(board.current_position_hash());
+            self.learn_if_game_over(board, &position_hashes);
+        }
+    }
+
+    /// If the game is over in `board`, reached through `position_hashes`,
+    /// record how it ended for the engine's moves of it in the learning file.
+    /// Games that end otherwise, e.g. by resignation, are not recorded.
+    fn learn_if_game_over(&mut self, mut board: Board, position_hashes: &[u64]) {
+        if self.engine_moves.is_empty() || !self.learning_enabled {
+            return;
+        }
+        let Some((path, experience)) = &mut self.learning else {
+            return;
+        };
+        let turn = board.turn();
+        let winner = match evaluate::game_ending(
+            &mut board,
+            &MoveGenerator::default(),
+            turn,
+            position_hashes,
+        ) {
+            Some(GameEnding::Checkmate | GameEnding::VariantWin) => Some(turn.opposite()),
+            Some(GameEnding::Stalemate | GameEnding::Draw) => None,
+            None => return,
         };
+        let experience = Arc::make_mut(experience);
+        for (position, chess_move, mover) in self.engine_moves.drain(..) {
+            experience.record_move(position, &chess_move, mover, winner);
+        }
+        if let Err(e) = experience.save(path) {
+            respond(&UciResponseFormatter::format_error(&e.to_string()));
+        }
+    }
+
+    /// Set the board position from FEN or startpos and replay `moves` from
+    /// it, so that the halfmove clock and repetition history are those of the
+    /// game. A bad FEN leaves the position as it was; an illegal move leaves
+    /// it at the move before, and is reported with its place in the list.
+    fn set_position(&mut self, fen: Option<String>, moves: Vec<String>) -> Result<(), String> {
+        let mut board = match fen {
+            Some(fen) => parse_uci_fen(&fen)?,
+            None => Board::default(),
+        };
+        // A three-check FEN is one whatever the option says
+        if self.variant != Variant::Standard {
+            board.set_variant(self.variant);
+        }
 
         // Create new engine with this position
         let config = EngineConfig {
@@ -185,38 +372,29 @@ impl UciProtocol {
             starting_position: board,
         };
         self.engine = Engine::with_config(config);
+        if self.hash_mb != DEFAULT_TT_SIZE_MB {
+            self.engine.set_hash_size(self.hash_mb);
+        }
 
-        // Apply moves if any
-        for move_str in moves {
-            self.apply_uci_move(&move_str)?;
+        for (index, move_str) in moves.iter().enumerate() {
+            self.apply_uci_move(move_str).map_err(|e| {
+                format!(
+                    "{} (move {} of {}); position left before it",
+                    e,
+                    index + 1,
+                    moves.len()
+                )
+            })?;
         }
 
         Ok(())
     }
 
-    /// Apply a single UCI move to the engine
+    /// Apply a single UCI move, e.g. "e2e4" or "e7e8q", to the engine
     fn apply_uci_move(&mut self, uci_move: &str) -> Result<(), String> {
-        // UCI moves are in format "e2e4" or "e7e8q" (with promotion)
-        if uci_move.len() < 4 || uci_move.len() > 5 {
-            return Err(format!("Invalid UCI move format: {}", uci_move));
-        }
-
-        let from_square = Square::from_algebraic(&uci_move[0..2])
-            .ok_or_else(|| format!("Invalid from square: {}", &uci_move[0..2]))?;
-        let to_square = Square::from_algebraic(&uci_move[2..4])
-            .ok_or_else(|| format!("Invalid to square: {}", &uci_move[2..4]))?;
-
-        let promotion = uci_move.chars().nth(4).map(|c| match c {
-            'q' => Piece::Queen,
-            'r' => Piece::Rook,
-            'b' => Piece::Bishop,
-            'n' => Piece::Knight,
-            _ => Piece::Queen,
-        });
-
         self.engine
-            .make_move_by_squares_with_promotion(from_square, to_square, promotion)
-            .map_err(|e| format!("Invalid move: {:?}", e))?;
+            .make_move_uci(uci_move)
+            .map_err(|_| format!("Illegal move: {}", uci_move))?;
 
         // Toggle turn after successful move
         self.engine.board_mut().toggle_turn();
@@ -227,31 +405,26 @@ impl UciProtocol {
 
     /// Run the UCI protocol loop, reading from stdin and writing to stdout
     pub fn run(&mut self) {
-        let stdin = io::stdin();
-        let mut stdout = io::stdout();
-
-        loop {
-            // Read command from stdin
-            let mut input = String::new();
-            if stdin.read_line(&mut input).is_err() {
-                break;
+        // Commands are read on their own thread, so a `stop` reaches a
+        // search while it runs
+        let (sender, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
             }
+        });
 
-            // Parse command
-            let command = match input.parse::<UciCommand>() {
-                Ok(cmd) => cmd,
-                Err(e) => {
-                    let error_response = UciResponseFormatter::format_error(&e);
-                    writeln!(stdout, "{}", error_response).ok();
-                    stdout.flush().ok();
-                    continue;
-                }
+        while let Ok(input) = commands.recv() {
+            let response = match input.parse::<UciCommand>() {
+                Ok(go @ UciCommand::Go { .. }) => self.go_until_stopped(go, &commands),
+                Ok(command) => self.execute_command(command),
+                Err(e) => Some(UciResponseFormatter::format_error(&e)),
             };
-
-            // Execute command
-            if let Some(response) = self.execute_command(command) {
-                writeln!(stdout, "{}", response).ok();
-                stdout.flush().ok();
+            if let Some(response) = response {
+                respond(&response);
             }
 
             // Check if we should quit
@@ -260,11 +433,134 @@ impl UciProtocol {
             }
         }
     }
+
+    /// Run `go` on its own thread, answering `isready` and acting on `stop`,
+    /// `ponderhit` and `quit` until it finishes. Anything else sent meanwhile
+    /// is ignored, as GUIs wait for `bestmove` before changing the position.
+    ///
+    /// With `go ponder` the search has no time limit until `ponderhit`, from
+    /// when the clock's budget applies, and its `bestmove` waits for
+    /// `ponderhit` or `stop` even if the search ends before. Likewise the
+    /// `bestmove` of `go infinite` waits for `stop`.
+    ///
+    /// Each completed depth is reported with an `info` line as it finishes.
+    /// Once the search has run for `CURRMOVE_DELAY`, each root move is
+    /// reported as it is searched, and the node count every
+    /// `PROGRESS_INTERVAL`.
+    fn go_until_stopped(&mut self, go: UciCommand, commands: &Receiver<String>) -> Option<String> {
+        let turn = self.engine.board().turn();
+        self.engine.set_search_info_callback(Some(Arc::new(
+            move |info: &SearchInfo<ChessMove>| {
+                respond(&format_search_info(info, turn));
+            },
+        )));
+        let last_progress = Mutex::new(Duration::ZERO);
+        self.engine.set_search_current_move_callback(Some(Arc::new(
+            move |current: &CurrentMove<ChessMove>| {
+                if current.elapsed < CURRMOVE_DELAY {
+                    return;
+                }
+                respond(&UciResponseFormatter::format_currmove(
+                    current.depth,
+                    &current.current_move.to_uci(),
+                    current.number,
+                ));
+                let mut last_progress = last_progress.lock().unwrap();
+                if current.elapsed >= *last_progress + PROGRESS_INTERVAL {
+                    *last_progress = current.elapsed;
+                    respond(&UciResponseFormatter::format_progress(
+                        current.nodes,
+                        current.elapsed.as_millis() as u64,
+                    ));
+                }
+            },
+        )));
+        let stop = self.engine.search_stop_flag();
+        stop.store(false, Ordering::Relaxed);
+        let ponder = self.engine.search_ponder_state();
+        if matches!(go, UciCommand::Go { ponder: true, .. }) {
+            ponder.start();
+        }
+        let infinite = matches!(go, UciCommand::Go { infinite: true, .. });
+        let mut quit = false;
+        let response = thread::scope(|scope| {
+            let search = scope.spawn(|| self.execute_command(go));
+            let stopped = || stop.load(Ordering::Relaxed);
+            while !search.is_finished() || ponder.is_pondering() || (infinite && !stopped()) {
+                match commands.recv_timeout(COMMAND_POLL_INTERVAL) {
+                    Ok(input) => match input.parse::<UciCommand>() {
+                        Ok(UciCommand::Stop) => {
+                            stop.store(true, Ordering::Relaxed);
+                            ponder.finish();
+                        }
+                        Ok(UciCommand::PonderHit) => ponder.finish(),
+                        Ok(UciCommand::Quit) => {
+                            quit = true;
+                            stop.store(true, Ordering::Relaxed);
+                            ponder.finish();
+                        }
+                        Ok(UciCommand::IsReady) => {
+                            respond(&UciResponseFormatter::format_ready_response())
+                        }
+                        _ => {}
+                    },
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        // No one is left to send `stop`
+                        quit = true;
+                        stop.store(true, Ordering::Relaxed);
+                        ponder.finish();
+                        break;
+                    }
+                }
+            }
+            search.join().expect("search thread panicked")
+        });
+        self.should_quit |= quit;
+        response
+    }
+}
+
+/// Parse a `position fen` FEN. GUIs sometimes leave off the move counters,
+/// which then start from 0 and 1. A position without one king a side can't
+/// be searched; `parse_fen` refuses it here rather than when searching.
+fn parse_uci_fen(fen: &str) -> Result<Board, String> {
+    let fen = if fen.split_whitespace().count() == 4 {
+        format!("{} 0 1", fen)
+    } else {
+        fen.to_string()
+    };
+    Board::from_str(&fen).map_err(|e| format!("Invalid FEN: {}", e))
+}
+
+/// The `info` line for a completed depth of a search with `turn` to move.
+fn format_search_info(info: &SearchInfo<ChessMove>, turn: Color) -> String {
+    let pv = info
+        .principal_variation
+        .iter()
+        .map(ChessMove::to_uci)
+        .collect::<Vec<_>>()
+        .join(" ");
+    UciResponseFormatter::format_info(
+        info.depth,
+        info.seldepth,
+        info.nodes,
+        info.elapsed.as_millis() as u64,
+        Some(UciScore::from_search_score(info.score, turn)),
+        (!pv.is_empty()).then_some(pv.as_str()),
+    )
+}
+
+fn respond(response: &str) {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", response).ok();
+    stdout.flush().ok();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::game::strength::MIN_STRENGTH_ELO;
 
     #[test]
     fn test_initial_state() {
@@ -329,12 +625,15 @@ mod tests {
     ) -> UciCommand {
         UciCommand::Go {
             depth,
+            nodes: None,
             movetime,
             wtime,
             btime,
             winc,
             binc,
+            movestogo: None,
             infinite,
+            ponder: false,
         }
     }
 
@@ -445,29 +744,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_time_allocation_formula() {
-        // Standard game: 60s remaining, no increment
-        let budget = allocate_time(60000, 0);
-        assert_eq!(budget, 2000, "60s / 30 = 2s");
-
-        // With increment: 10s remaining, 1s increment
-        let budget = allocate_time(10000, 1000);
-        assert_eq!(budget, 10000 / 30 + 800, "10s/30 + 1s*0.8");
-
-        // Very low time: 100ms, no increment
-        let budget = allocate_time(100, 0);
-        assert!(
-            budget <= 50,
-            "100ms should budget at most 50ms (safety margin)"
-        );
-        assert!(budget >= 1, "Always at least 1ms");
-
-        // Zero time remaining
-        let budget = allocate_time(0, 0);
-        assert_eq!(budget, 1, "Zero time should still give 1ms minimum");
-    }
-
     #[test]
     fn test_position_fen_then_go() {
         let mut protocol = UciProtocol::new();
@@ -522,4 +798,380 @@ mod tests {
             "Search depth should reset to default (4) after go without explicit depth"
         );
     }
+
+    #[test]
+    fn test_go_nodes_bounds_the_search() {
+        let mut protocol = UciProtocol::new();
+        protocol.execute_command(UciCommand::Uci);
+        protocol.execute_command(UciCommand::Position {
+            fen: Some(
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"
+                    .to_string(),
+            ),
+            moves: vec![],
+        });
+
+        let response = protocol
+            .execute_command("go nodes 20000".parse().unwrap())
+            .unwrap();
+        assert!(response.starts_with("bestmove "), "got {}", response);
+        // The budget is checked every 4096 nodes, per thread.
+        let nodes = protocol.engine.get_search_stats().positions_searched;
+        assert!(nodes < 20_000 + 4096 * 64, "searched {} nodes", nodes);
+        assert_eq!(protocol.engine.search_depth(), 4);
+    }
+
+    #[test]
+    fn test_stop_ends_an_infinite_search_with_a_move() {
+        let mut protocol = UciProtocol::new();
+        protocol.execute_command(UciCommand::Uci);
+        protocol.execute_command(UciCommand::Position {
+            fen: Some(
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"
+                    .to_string(),
+            ),
+            moves: vec![],
+        });
+
+        let stop = protocol.engine.search_stop_flag();
+        let stopper = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            stop.store(true, Ordering::Relaxed);
+        });
+        let response = protocol
+            .execute_command(go_cmd(None, None, None, None, None, None, true))
+            .unwrap();
+        stopper.join().unwrap();
+        assert!(response.starts_with("bestmove "), "got {}", response);
+    }
+
+    #[test]
+    fn test_ponder_search_answers_after_ponderhit() {
+        let mut protocol = UciProtocol::new();
+        protocol.execute_command(UciCommand::Uci);
+        protocol.execute_command(UciCommand::Position {
+            fen: Some(
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"
+                    .to_string(),
+            ),
+            moves: vec![],
+        });
+
+        // The first search of a process sets up tables, which a short budget
+        // would have to wait for
+        protocol.execute_command(go_cmd(Some(1), None, None, None, None, None, false));
+
+        let (sender, commands) = mpsc::channel();
+        let go = "go ponder wtime 3000 btime 3000".parse().unwrap();
+        let start = std::time::Instant::now();
+        // Closing the channel would stop the search, and quit, so the test
+        // keeps `sender` open until the search has answered
+        let ponderhit_sender = sender.clone();
+        let ponderhit = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            ponderhit_sender.send("isready".to_string()).unwrap();
+            ponderhit_sender.send("ponderhit".to_string()).unwrap();
+        });
+        let response = protocol.go_until_stopped(go, &commands).unwrap();
+        let elapsed = start.elapsed();
+        ponderhit.join().unwrap();
+        drop(sender);
+
+        assert!(response.starts_with("bestmove "), "got {}", response);
+        // The budget of 100ms only counts from `ponderhit`
+        assert!(elapsed >= Duration::from_millis(300), "took {:?}", elapsed);
+        assert!(!protocol.should_quit());
+    }
+
+    /// Feed each line of a GUI's log to `protocol` as `run` would, other than
+    /// `go`, returning the responses.
+    fn replay_log(protocol: &mut UciProtocol, log: &str) -> Vec<String> {
+        log.lines()
+            .filter_map(|line| match line.parse::<UciCommand>() {
+                Ok(command) => protocol.execute_command(command),
+                Err(e) => Some(UciResponseFormatter::format_error(&e)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gui_logs_replay_positions_exactly() {
+        // cutechess-cli, into the Najdorf
+        let mut protocol = UciProtocol::new();
+        let responses = replay_log(
+            &mut protocol,
+            "uci\n\
+             setoption name Ponder value false\n\
+             isready\n\
+             ucinewgame\n\
+             position startpos moves e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6 b1c3 a7a6\n\
+             isready",
+        );
+        assert!(responses[0].ends_with("uciok"));
+        assert_eq!(responses[1..], ["readyok", "readyok"]);
+        assert_eq!(
+            protocol.engine.board().to_fen(),
+            "rnbqkb1r/1p2pppp/p2p1n2/8/3NP3/2N5/PPP2PPP/R1BQKB1R w KQkq - 0 6"
+        );
+
+        // Arena, from a FEN through castling on both sides
+        let responses = replay_log(
+            &mut protocol,
+            "position fen r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 \
+             moves e1g1 a6e2 c3e2 e8c8",
+        );
+        assert!(responses.is_empty(), "got {:?}", responses);
+        assert_eq!(
+            protocol.engine.board().to_fen(),
+            "2kr3r/p1ppqpb1/1n2pnp1/3PN3/1p2P3/5Q1p/PPPBNPPP/R4RK1 w - - 1 3"
+        );
+
+        // A FEN without move counters, then en passant and an underpromotion
+        replay_log(
+            &mut protocol,
+            "position fen 4k3/1P6/8/8/5p2/8/4P3/4K3 w - - moves e2e4 f4e3 b7b8n",
+        );
+        assert_eq!(
+            protocol.engine.board().to_fen(),
+            "1N2k3/8/8/8/8/4p3/8/4K3 b - - 0 2"
+        );
+    }
+
+    #[test]
+    fn test_position_moves_keep_clocks_and_repetitions() {
+        let mut protocol = UciProtocol::new();
+        replay_log(
+            &mut protocol,
+            "position fen 4k3/8/8/8/8/8/8/4K2R w K - 45 80 moves h1h2 e8d8 h2h1 d8e8",
+        );
+        assert_eq!(
+            protocol.engine.board().to_fen(),
+            "4k3/8/8/8/8/8/8/4K2R w - - 49 82"
+        );
+
+        replay_log(
+            &mut protocol,
+            "position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1",
+        );
+        assert!(protocol.engine.check_game_over().is_none());
+        replay_log(
+            &mut protocol,
+            "position startpos moves g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8",
+        );
+        assert!(matches!(
+            protocol.engine.check_game_over(),
+            Some(GameEnding::Draw)
+        ));
+    }
+
+    #[test]
+    fn test_bad_positions_are_reported_not_applied() {
+        let mut protocol = UciProtocol::new();
+        let responses = replay_log(
+            &mut protocol,
+            "position startpos moves e2e4 e7e5 e1e8 g1f3\n\
+             position startpos moves e2e4 a\u{e9}4e\n\
+             position startpos moves e7e8x",
+        );
+        assert_eq!(
+            responses,
+            [
+                "info string Error: Illegal move: e1e8 (move 3 of 4); position left before it",
+                "info string Error: Illegal move: a\u{e9}4e (move 2 of 2); position left before it",
+                "info string Error: Illegal move: e7e8x (move 1 of 1); position left before it",
+            ]
+        );
+
+        let before = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        replay_log(&mut protocol, "position startpos moves e2e4");
+        assert_eq!(protocol.engine.board().to_fen(), before);
+        let responses = replay_log(
+            &mut protocol,
+            "position fen 8/8/8/8/8/8/8/8 w - - 0 1\n\
+             position fen 9/8/8/8/8/8/8/8 w - - 0 1 moves e2e4",
+        );
+        assert_eq!(responses.len(), 2);
+        assert!(responses
+            .iter()
+            .all(|response| response.starts_with("info string Error: Invalid FEN")));
+        assert_eq!(protocol.engine.board().to_fen(), before);
+    }
+
+    #[test]
+    fn test_hash_option_sizes_the_transposition_table() {
+        let mut protocol = UciProtocol::new();
+        let default_capacity = protocol.engine.hash_capacity();
+        let response = protocol.execute_command(UciCommand::SetOption {
+            name: "Hash".to_string(),
+            value: Some("1".to_string()),
+        });
+        assert_eq!(response, None);
+        let capacity = protocol.engine.hash_capacity();
+        assert_eq!(capacity * 64, default_capacity);
+
+        // Each position starts a fresh engine, which keeps the size
+        protocol.execute_command(UciCommand::Position {
+            fen: None,
+            moves: vec!["e2e4".to_string()],
+        });
+        assert_eq!(protocol.engine.hash_capacity(), capacity);
+        let response = protocol
+            .execute_command(go_cmd(Some(3), None, None, None, None, None, false))
+            .unwrap();
+        assert!(response.starts_with("bestmove "), "{}", response);
+
+        let response = protocol.execute_command(UciCommand::SetOption {
+            name: "Hash".to_string(),
+            value: Some("lots".to_string()),
+        });
+        assert!(response.is_some());
+    }
+
+    #[test]
+    fn test_uci_variant_option_applies_to_positions() {
+        let mut protocol = UciProtocol::new();
+        protocol.execute_command(UciCommand::Uci);
+        let response = protocol.execute_command(UciCommand::SetOption {
+            name: "UCI_Variant".to_string(),
+            value: Some("kingofthehill".to_string()),
+        });
+        assert_eq!(response, None);
+        protocol.execute_command(UciCommand::Position {
+            fen: Some("4k3/8/8/8/8/4K3/8/r7 w - - 0 1".to_string()),
+            moves: vec![],
+        });
+        assert_eq!(protocol.engine.board().variant(), Variant::KingOfTheHill);
+
+        let response = protocol
+            .execute_command(go_cmd(Some(2), None, None, None, None, None, false))
+            .unwrap();
+        assert!(
+            response == "bestmove e3e4" || response == "bestmove e3d4",
+            "expected the king to take the hill, got {}",
+            response
+        );
+
+        let response = protocol.execute_command(UciCommand::SetOption {
+            name: "UCI_Variant".to_string(),
+            value: Some("crazyhouse".to_string()),
+        });
+        assert!(response.is_some());
+    }
+
+    #[test]
+    fn test_strength_options_weaken_the_search() {
+        let mut protocol = UciProtocol::new();
+        let mut set_option = |name: &str, value: &str| {
+            protocol.execute_command(UciCommand::SetOption {
+                name: name.to_string(),
+                value: Some(value.to_string()),
+            })
+        };
+        assert_eq!(set_option("UCI_Elo", "600"), None);
+        assert_eq!(set_option("Skill Level", "10"), None);
+        assert!(set_option("Skill Level", "strong").is_some());
+        assert_eq!(protocol.strength(), Some(Strength::new(1500)));
+
+        protocol.execute_command(UciCommand::SetOption {
+            name: "UCI_LimitStrength".to_string(),
+            value: Some("true".to_string()),
+        });
+        assert_eq!(protocol.strength(), Some(Strength::new(MIN_STRENGTH_ELO)));
+
+        protocol.execute_command(UciCommand::Position {
+            fen: None,
+            moves: vec![],
+        });
+        let response = protocol
+            .execute_command(go_cmd(Some(8), None, None, None, None, None, false))
+            .unwrap();
+        assert!(response.starts_with("bestmove "), "got {}", response);
+        // Unless it played a mistake without searching at all
+        assert!(protocol.engine.last_completed_depth().unwrap_or(0) <= 1);
+    }
+
+    #[test]
+    fn test_book_options_limit_book_moves() {
+        let mut protocol = UciProtocol::new();
+        let go_from = |protocol: &mut UciProtocol, moves: &[&str]| {
+            protocol.execute_command(UciCommand::Position {
+                fen: None,
+                moves: moves.iter().map(|m| m.to_string()).collect(),
+            });
+            protocol.execute_command(go_cmd(Some(1), None, None, None, None, None, false));
+            // A book move is played without searching
+            protocol.engine.last_completed_depth()
+        };
+        assert_eq!(go_from(&mut protocol, &[]), None);
+
+        for (name, value) in [("BookPolicy", "best"), ("BookDepth", "1")] {
+            protocol.execute_command(UciCommand::SetOption {
+                name: name.to_string(),
+                value: Some(value.to_string()),
+            });
+        }
+        assert_eq!(go_from(&mut protocol, &[]), None);
+        assert_eq!(go_from(&mut protocol, &["e2e4", "e7e5"]), Some(1));
+
+        protocol.execute_command(UciCommand::SetOption {
+            name: "OwnBook".to_string(),
+            value: Some("false".to_string()),
+        });
+        assert_eq!(go_from(&mut protocol, &[]), Some(1));
+
+        let response = protocol.execute_command(UciCommand::SetOption {
+            name: "BookPolicy".to_string(),
+            value: Some("random".to_string()),
+        });
+        assert!(response.is_some());
+    }
+
+    #[test]
+    fn test_games_that_end_are_learned_from() {
+        let path = std::env::temp_dir().join(format!("uci-learning-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut protocol = UciProtocol::new().with_learning(path, Experience::default());
+
+        // The engine mates with its move; the game is recorded as soon as it ends
+        let mate = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1";
+        protocol.execute_command(UciCommand::Position {
+            fen: Some(mate.to_string()),
+            moves: vec![],
+        });
+        let response = protocol
+            .execute_command(go_cmd(Some(2), None, None, None, None, None, false))
+            .unwrap();
+        assert_eq!(response, "bestmove d1d8");
+        assert!(protocol.engine_moves.is_empty());
+        let experience = Experience::load(path).unwrap();
+        let board = parse_uci_fen(mate).unwrap();
+        let mate_move = protocol
+            .engine
+            .get_valid_moves()
+            .into_iter()
+            .find(|(m, _)| m.to_uci() == "d1d8")
+            .unwrap()
+            .0;
+        assert_eq!(
+            experience
+                .record(&position_key(&board), &mate_move)
+                .map(|record| record.wins),
+            Some(1)
+        );
+
+        // A game whose result is never seen is not
+        protocol.execute_command(UciCommand::Position {
+            fen: None,
+            moves: vec![],
+        });
+        protocol.execute_command(UciCommand::SetOption {
+            name: "OwnBook".to_string(),
+            value: Some("false".to_string()),
+        });
+        protocol.execute_command(go_cmd(Some(1), None, None, None, None, None, false));
+        assert_eq!(protocol.engine_moves.len(), 1);
+        protocol.execute_command(UciCommand::UciNewGame);
+        assert!(protocol.engine_moves.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
 }
@@ -1,15 +1,76 @@
 //! UCI response formatting for stdout
 
+use crate::alpha_beta_searcher::{mate_in_moves, DEFAULT_TT_SIZE_MB};
+use crate::board::color::Color;
+use crate::board::variant::ALL_VARIANTS;
+use crate::book::{BookPolicy, ALL_BOOK_POLICIES};
+use crate::game::strength::{MAX_SKILL_LEVEL, MAX_STRENGTH_ELO, MIN_STRENGTH_ELO};
+
+use super::protocol::{DEFAULT_UCI_ELO, MAX_BOOK_DEPTH, MAX_HASH_MB};
+
+/// A search score as UCI reports it, from the side to move's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UciScore {
+    Centipawns(i16),
+    /// Moves to a forced mate; negative when the side to move is mated.
+    Mate(i16),
+}
+
+impl UciScore {
+    /// The UCI score for a search `score` (positive favoring White) of a
+    /// position with `turn` to move.
+    pub fn from_search_score(score: i16, turn: Color) -> Self {
+        let sign = match turn {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        match mate_in_moves(score) {
+            Some(moves) => UciScore::Mate(sign * moves),
+            None => UciScore::Centipawns(sign * score),
+        }
+    }
+}
+
 /// Format UCI responses to send to stdout
 pub struct UciResponseFormatter;
 
 impl UciResponseFormatter {
     /// Format the 'uci' command response
     pub fn format_uci_response() -> String {
-        "id name RustChess\n\
-         id author CJK\n\
-         uciok"
-            .to_string()
+        let variants: String = ALL_VARIANTS
+            .iter()
+            .map(|variant| format!(" var {}", variant.uci_name()))
+            .collect();
+        let book_policies: String = ALL_BOOK_POLICIES
+            .iter()
+            .map(|policy| format!(" var {}", policy))
+            .collect();
+        format!(
+            "id name RustChess\n\
+             id author CJK\n\
+             option name Hash type spin default {} min 1 max {}\n\
+             option name Ponder type check default false\n\
+             option name UCI_Variant type combo default chess{}\n\
+             option name UCI_LimitStrength type check default false\n\
+             option name UCI_Elo type spin default {} min {} max {}\n\
+             option name Skill Level type spin default {} min 0 max {}\n\
+             option name OwnBook type check default true\n\
+             option name BookPolicy type combo default {}{}\n\
+             option name BookDepth type spin default 0 min 0 max {}\n\
+             option name Learning type check default true\n\
+             uciok",
+            DEFAULT_TT_SIZE_MB,
+            MAX_HASH_MB,
+            variants,
+            DEFAULT_UCI_ELO,
+            MIN_STRENGTH_ELO,
+            MAX_STRENGTH_ELO,
+            MAX_SKILL_LEVEL,
+            MAX_SKILL_LEVEL,
+            BookPolicy::default(),
+            book_policies,
+            MAX_BOOK_DEPTH
+        )
     }
 
     /// Format the 'isready' command response
@@ -17,23 +78,36 @@ impl UciResponseFormatter {
         "readyok".to_string()
     }
 
-    /// Format the 'bestmove' response
-    pub fn format_bestmove_response(best_move: &str) -> String {
-        format!("bestmove {}", best_move)
+    /// Format the 'bestmove' response, with the reply to ponder on if known
+    pub fn format_bestmove_response(best_move: &str, ponder_move: Option<&str>) -> String {
+        match ponder_move {
+            Some(ponder_move) => format!("bestmove {} ponder {}", best_move, ponder_move),
+            None => format!("bestmove {}", best_move),
+        }
     }
 
     /// Format search info message
     pub fn format_info(
         depth: u8,
+        seldepth: u8,
         nodes: usize,
         time_ms: u64,
-        score_cp: Option<i16>,
+        score: Option<UciScore>,
         pv: Option<&str>,
     ) -> String {
-        let mut info = format!("info depth {} nodes {} time {}", depth, nodes, time_ms);
+        let mut info = format!(
+            "info depth {} seldepth {} nodes {} nps {} time {}",
+            depth,
+            seldepth,
+            nodes,
+            nodes_per_second(nodes, time_ms),
+            time_ms
+        );
 
-        if let Some(cp) = score_cp {
-            info.push_str(&format!(" score cp {}", cp));
+        match score {
+            Some(UciScore::Centipawns(cp)) => info.push_str(&format!(" score cp {}", cp)),
+            Some(UciScore::Mate(moves)) => info.push_str(&format!(" score mate {}", moves)),
+            None => {}
         }
 
         if let Some(principal_variation) = pv {
@@ -43,15 +117,38 @@ impl UciResponseFormatter {
         info
     }
 
+    /// Format the root move being searched, counting from 1 in search order
+    pub fn format_currmove(depth: u8, current_move: &str, number: usize) -> String {
+        format!(
+            "info depth {} currmove {} currmovenumber {}",
+            depth, current_move, number
+        )
+    }
+
+    /// Format the progress of a search between completed depths
+    pub fn format_progress(nodes: usize, time_ms: u64) -> String {
+        format!(
+            "info nodes {} nps {} time {}",
+            nodes,
+            nodes_per_second(nodes, time_ms),
+            time_ms
+        )
+    }
+
     /// Format error message (not standard UCI, but useful for debugging)
     pub fn format_error(message: &str) -> String {
         format!("info string Error: {}", message)
     }
 }
 
+fn nodes_per_second(nodes: usize, time_ms: u64) -> u64 {
+    nodes as u64 * 1000 / time_ms.max(1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::alpha_beta_searcher::MATE_SCORE;
 
     #[test]
     fn test_format_uci_response() {
@@ -59,6 +156,20 @@ mod tests {
         assert!(response.contains("id name RustChess"));
         assert!(response.contains("id author"));
         assert!(response.contains("uciok"));
+        assert!(response.contains("option name Hash type spin default 64 min 1 max 4096"));
+        assert!(response.contains("option name Ponder type check default false"));
+        assert!(response.contains(
+            "option name UCI_Variant type combo default chess var chess var kingofthehill var 3check"
+        ));
+        assert!(response.contains("option name UCI_LimitStrength type check default false"));
+        assert!(response.contains("option name UCI_Elo type spin default 1500 min 600 max 2400"));
+        assert!(response.contains("option name Skill Level type spin default 20 min 0 max 20"));
+        assert!(response.contains("option name OwnBook type check default true"));
+        assert!(response.contains(
+            "option name BookPolicy type combo default popular var popular var best var variety"
+        ));
+        assert!(response.contains("option name BookDepth type spin default 0 min 0 max 100"));
+        assert!(response.contains("option name Learning type check default true"));
     }
 
     #[test]
@@ -69,16 +180,29 @@ mod tests {
     #[test]
     fn test_format_bestmove_response() {
         assert_eq!(
-            UciResponseFormatter::format_bestmove_response("e2e4"),
+            UciResponseFormatter::format_bestmove_response("e2e4", None),
             "bestmove e2e4"
         );
+        assert_eq!(
+            UciResponseFormatter::format_bestmove_response("e2e4", Some("e7e5")),
+            "bestmove e2e4 ponder e7e5"
+        );
     }
 
     #[test]
     fn test_format_info() {
-        let info = UciResponseFormatter::format_info(6, 123456, 1523, Some(32), Some("e2e4 e7e5"));
+        let info = UciResponseFormatter::format_info(
+            6,
+            11,
+            123456,
+            1523,
+            Some(UciScore::Centipawns(32)),
+            Some("e2e4 e7e5"),
+        );
         assert!(info.contains("depth 6"));
+        assert!(info.contains("seldepth 11"));
         assert!(info.contains("nodes 123456"));
+        assert!(info.contains("nps 81061"));
         assert!(info.contains("time 1523"));
         assert!(info.contains("score cp 32"));
         assert!(info.contains("pv e2e4 e7e5"));
@@ -86,7 +210,58 @@ mod tests {
 
     #[test]
     fn test_format_info_without_score_and_pv() {
-        let info = UciResponseFormatter::format_info(4, 1000, 500, None, None);
-        assert_eq!(info, "info depth 4 nodes 1000 time 500");
+        let info = UciResponseFormatter::format_info(4, 4, 1000, 500, None, None);
+        assert_eq!(info, "info depth 4 seldepth 4 nodes 1000 nps 2000 time 500");
+    }
+
+    #[test]
+    fn test_format_info_with_mate_score() {
+        let info = UciResponseFormatter::format_info(
+            5,
+            7,
+            800,
+            20,
+            Some(UciScore::Mate(-2)),
+            Some("h5f7"),
+        );
+        assert_eq!(
+            info,
+            "info depth 5 seldepth 7 nodes 800 nps 40000 time 20 score mate -2 pv h5f7"
+        );
+    }
+
+    #[test]
+    fn test_format_currmove_and_progress() {
+        assert_eq!(
+            UciResponseFormatter::format_currmove(9, "g1f3", 3),
+            "info depth 9 currmove g1f3 currmovenumber 3"
+        );
+        assert_eq!(
+            UciResponseFormatter::format_progress(2_500_000, 2000),
+            "info nodes 2500000 nps 1250000 time 2000"
+        );
+        // No time has passed yet
+        assert_eq!(
+            UciResponseFormatter::format_progress(300, 0),
+            "info nodes 300 nps 300000 time 0"
+        );
+    }
+
+    #[test]
+    fn test_scores_are_from_the_side_to_move() {
+        assert_eq!(
+            UciScore::from_search_score(-40, Color::Black),
+            UciScore::Centipawns(40)
+        );
+        // White mates with its second move: 3 plies from the root
+        let white_mates = MATE_SCORE - 3;
+        assert_eq!(
+            UciScore::from_search_score(white_mates, Color::White),
+            UciScore::Mate(2)
+        );
+        assert_eq!(
+            UciScore::from_search_score(white_mates, Color::Black),
+            UciScore::Mate(-2)
+        );
     }
 }
@@ -0,0 +1,161 @@
+//! Time management: the thinking time for one move, from the clock.
+//!
+//! A `go` command reports each side's remaining time and increment, and
+//! optionally how many moves are left until the next time control. The budget
+//! for the side to move spreads its remaining time over the moves still to
+//! play, plus most of the increment. The search's time manager aims for half
+//! of the budget, and may run to twice the budget, but never past a quarter of
+//! the time left, while the search is unsettled.
+
+use std::time::Duration;
+
+use crate::alpha_beta_searcher::TimeLimits;
+use crate::board::color::Color;
+
+/// Moves assumed to be left in the game when the clock doesn't say.
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+/// Time kept back for the GUI and the move to get to it, in milliseconds.
+const SAFETY_MARGIN_MS: u64 = 50;
+
+/// The clock as reported by `go`, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClockState {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    /// Moves until the next time control, for classical controls.
+    pub movestogo: Option<u32>,
+}
+
+impl ClockState {
+    /// The thinking time for `turn`'s move, or `None` when the clock gives
+    /// that side no time.
+    pub fn move_budget(&self, turn: Color) -> Option<Duration> {
+        let (time, increment) = self.time_and_increment(turn)?;
+        let budget = allocate_time_with_moves_to_go(time, increment, self.movestogo.map(u64::from));
+        Some(Duration::from_millis(budget))
+    }
+
+    /// The search's time limits for `turn`'s move, or `None` when the clock
+    /// gives that side no time.
+    pub fn time_limits(&self, turn: Color) -> Option<TimeLimits> {
+        let (time, increment) = self.time_and_increment(turn)?;
+        Some(time_limits(time, increment, self.movestogo.map(u64::from)))
+    }
+
+    fn time_and_increment(&self, turn: Color) -> Option<(u64, u64)> {
+        let (time, increment) = match turn {
+            Color::White => (self.wtime, self.winc),
+            Color::Black => (self.btime, self.binc),
+        };
+        let time = time.filter(|&time| time > 0)?;
+        Some((time, increment.unwrap_or(0)))
+    }
+}
+
+/// Time limits around the budget of `allocate_time_with_moves_to_go`. The
+/// hard limit takes no more than a quarter of the time left, unless the
+/// budget is so large that its four fifths would.
+pub(crate) fn time_limits(
+    time_remaining_ms: u64,
+    increment_ms: u64,
+    moves_to_go: Option<u64>,
+) -> TimeLimits {
+    let budget = allocate_time_with_moves_to_go(time_remaining_ms, increment_ms, moves_to_go);
+    let max = time_remaining_ms.saturating_sub(SAFETY_MARGIN_MS) / 4;
+    TimeLimits::for_budget(Duration::from_millis(budget), Duration::from_millis(max))
+}
+
+/// Calculate time allocation for a single move.
+///
+/// Uses `time_remaining / 30 + increment * 80%`, clamped to not exceed
+/// `time_remaining - 50ms` safety margin.
+pub(crate) fn allocate_time(time_remaining_ms: u64, increment_ms: u64) -> u64 {
+    allocate_time_with_moves_to_go(time_remaining_ms, increment_ms, None)
+}
+
+/// Like `allocate_time`, dividing the remaining time over `moves_to_go + 1`
+/// moves when the time control says how many are left, so the last move
+/// before it still keeps some time back.
+pub(crate) fn allocate_time_with_moves_to_go(
+    time_remaining_ms: u64,
+    increment_ms: u64,
+    moves_to_go: Option<u64>,
+) -> u64 {
+    let moves = moves_to_go.map_or(DEFAULT_MOVES_TO_GO, |moves| moves + 1);
+    let base = time_remaining_ms / moves;
+    let inc_bonus = increment_ms * 4 / 5;
+    let budget = base + inc_bonus;
+    let max_allowed = time_remaining_ms.saturating_sub(SAFETY_MARGIN_MS);
+    budget.min(max_allowed).max(1) // always at least 1ms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_allocation_formula() {
+        // Standard game: 60s remaining, no increment
+        let budget = allocate_time(60000, 0);
+        assert_eq!(budget, 2000, "60s / 30 = 2s");
+
+        // With increment: 10s remaining, 1s increment
+        let budget = allocate_time(10000, 1000);
+        assert_eq!(budget, 10000 / 30 + 800, "10s/30 + 1s*0.8");
+
+        // Very low time: 100ms, no increment
+        let budget = allocate_time(100, 0);
+        assert!(
+            budget <= 50,
+            "100ms should budget at most 50ms (safety margin)"
+        );
+        assert!(budget >= 1, "Always at least 1ms");
+
+        // Zero time remaining
+        let budget = allocate_time(0, 0);
+        assert_eq!(budget, 1, "Zero time should still give 1ms minimum");
+    }
+
+    #[test]
+    fn test_moves_to_go_spreads_the_time_over_the_moves_left() {
+        assert_eq!(allocate_time_with_moves_to_go(60000, 0, Some(9)), 6000);
+        // The last move before the time control keeps half back
+        assert_eq!(allocate_time_with_moves_to_go(60000, 0, Some(1)), 30000);
+        assert_eq!(allocate_time_with_moves_to_go(60000, 0, Some(0)), 59950);
+    }
+
+    #[test]
+    fn test_time_limits_allow_overrunning_the_budget_within_the_clock() {
+        let limits = time_limits(60000, 0, None);
+        assert_eq!(limits.soft, Duration::from_millis(1000));
+        assert_eq!(limits.hard, Duration::from_millis(4000));
+
+        // The last move before the time control can't take more
+        let limits = time_limits(60000, 0, Some(0));
+        assert_eq!(limits.hard, Duration::from_millis(59950 * 4 / 5));
+        assert_eq!(ClockState::default().time_limits(Color::White), None);
+    }
+
+    #[test]
+    fn test_move_budget_uses_the_side_to_move() {
+        let clock = ClockState {
+            wtime: Some(60000),
+            btime: Some(30000),
+            winc: Some(1000),
+            binc: None,
+            movestogo: None,
+        };
+        assert_eq!(
+            clock.move_budget(Color::White),
+            Some(Duration::from_millis(2800))
+        );
+        assert_eq!(
+            clock.move_budget(Color::Black),
+            Some(Duration::from_millis(1000))
+        );
+        assert_eq!(ClockState::default().move_budget(Color::White), None);
+    }
+}
@@ -0,0 +1,230 @@
+//! WebAssembly bindings for the chess engine
+//!
+//! A `Game` holds a position and the moves that led to it, and offers what a
+//! web front-end needs: the legal moves, playing a move, the engine's best move,
+//! and FEN import and export. Moves are taken in SAN (`Nf3`) or UCI (`g1f3`)
+//! and returned in SAN. Errors are thrown to JavaScript as `Error`s.
+//!
+//! Build with `wasm-pack build wasm --target web`, then from JavaScript:
+//! ```js
+//! import init, { new_game } from "./pkg/chess_wasm.js";
+//!
+//! await init();
+//! const game = new_game();
+//! game.make_move("e4");
+//! const reply = game.best_move(8, 500); // 8 plies deep, or 500 ms
+//! game.make_move(reply);
+//! console.log(game.fen(), game.legal_moves());
+//! ```
+
+use std::time::Duration;
+
+use chess::alpha_beta_searcher::{SearchContext, SearchError};
+use chess::board::color::Color;
+use chess::board::Board;
+use chess::chess_move::algebraic_notation::enumerate_candidate_moves_with_algebraic_notation;
+use chess::chess_move::chess_move::ChessMove;
+use chess::chess_search::search_best_move_with_history;
+use chess::evaluate::{game_ending, GameEnding};
+use chess::input_handler::fen::{parse_fen, FenParseError};
+use chess::input_handler::fen_serialize::to_fen;
+use chess::move_generator::MoveGenerator;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+#[derive(Error, Debug)]
+pub enum GameError {
+    #[error("Invalid FEN: {error}")]
+    InvalidFen { error: FenParseError },
+    #[error("Illegal move: {notation}")]
+    IllegalMove { notation: String },
+    #[error("Search failed: {error}")]
+    Search { error: SearchError },
+}
+
+impl From<GameError> for JsValue {
+    fn from(error: GameError) -> Self {
+        JsError::new(&error.to_string()).into()
+    }
+}
+
+/// A game in progress, from a starting position
+#[wasm_bindgen]
+pub struct Game {
+    board: Board,
+    move_generator: MoveGenerator,
+    /// Hashes of the positions reached so far, for repetition draws.
+    position_history: Vec<u64>,
+    /// Kept between searches, so later ones reuse the transposition table.
+    search_context: SearchContext<ChessMove>,
+}
+
+/// Start a game from the standard starting position.
+#[wasm_bindgen]
+pub fn new_game() -> Game {
+    Game::new(Board::default())
+}
+
+#[wasm_bindgen]
+impl Game {
+    fn new(board: Board) -> Self {
+        Self {
+            position_history: vec![board.current_position_hash()],
+            board,
+            move_generator: MoveGenerator::default(),
+            search_context: SearchContext::new(1),
+        }
+    }
+
+    /// Start a game from a position in FEN.
+    pub fn from_fen(fen: &str) -> Result<Game, GameError> {
+        parse_fen(fen)
+            .map(Self::new)
+            .map_err(|error| GameError::InvalidFen { error })
+    }
+
+    /// The current position in FEN.
+    pub fn fen(&self) -> String {
+        to_fen(&self.board)
+    }
+
+    /// The side to move, "white" or "black".
+    pub fn turn(&self) -> String {
+        match self.board.turn() {
+            Color::White => "white",
+            Color::Black => "black",
+        }
+        .to_string()
+    }
+
+    /// The moves the side to move can play, in SAN.
+    pub fn legal_moves(&mut self) -> Vec<String> {
+        self.candidates()
+            .into_iter()
+            .map(|(_, notation)| notation)
+            .collect()
+    }
+
+    /// Play a move given in SAN, with or without its check suffix, or in UCI.
+    /// Returns the move in SAN.
+    pub fn make_move(&mut self, notation: &str) -> Result<String, GameError> {
+        let without_suffix = |san: &str| san.trim_end_matches(['+', '#']).to_string();
+        let candidates = self.candidates();
+        let (chess_move, san) = candidates
+            .iter()
+            .find(|(_, san)| san == notation)
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|(_, san)| without_suffix(san) == without_suffix(notation))
+            })
+            .or_else(|| candidates.iter().find(|(m, _)| m.to_uci() == notation))
+            .ok_or_else(|| GameError::IllegalMove {
+                notation: notation.to_string(),
+            })?;
+
+        chess_move
+            .apply(&mut self.board)
+            .map_err(|_| GameError::IllegalMove {
+                notation: notation.to_string(),
+            })?;
+        self.board.toggle_turn();
+        self.position_history
+            .push(self.board.current_position_hash());
+        Ok(san.clone())
+    }
+
+    /// The engine's choice for the side to move, in SAN, without playing it.
+    /// The search goes `depth` plies deep, or stops after `ms` milliseconds
+    /// when given.
+    pub fn best_move(&mut self, depth: u8, ms: Option<u32>) -> Result<String, GameError> {
+        self.search_context.set_depth(depth);
+        self.search_context
+            .set_time_limit(ms.map(|ms| Duration::from_millis(ms.into())));
+        let mut board = self.board.clone();
+        let best_move = search_best_move_with_history(
+            &mut self.search_context,
+            &mut board,
+            self.position_history.clone(),
+            0,
+        )
+        .map_err(|error| GameError::Search { error })?;
+
+        let uci = best_move.to_uci();
+        self.candidates()
+            .into_iter()
+            .find(|(chess_move, _)| chess_move.to_uci() == uci)
+            .map(|(_, san)| san)
+            .ok_or(GameError::IllegalMove { notation: uci })
+    }
+
+    /// How the game ended: "checkmate", "stalemate", "draw" or, in a
+    /// three-check position, "variant win"; or undefined while it goes on.
+    pub fn outcome(&mut self) -> Option<String> {
+        let turn = self.board.turn();
+        let ending = game_ending(
+            &mut self.board,
+            &self.move_generator,
+            turn,
+            &self.position_history,
+        )?;
+        let outcome = match ending {
+            GameEnding::Checkmate => "checkmate",
+            GameEnding::Stalemate => "stalemate",
+            GameEnding::Draw => "draw",
+            GameEnding::VariantWin => "variant win",
+        };
+        Some(outcome.to_string())
+    }
+
+    /// The legal moves with their SAN.
+    fn candidates(&mut self) -> Vec<(ChessMove, String)> {
+        let turn = self.board.turn();
+        enumerate_candidate_moves_with_algebraic_notation(
+            &mut self.board,
+            turn,
+            &self.move_generator,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_are_taken_in_san_or_uci_and_returned_in_san() {
+        let mut game = new_game();
+        assert_eq!(game.legal_moves().len(), 20);
+        assert_eq!(game.make_move("e4").unwrap(), "e4");
+        assert_eq!(game.make_move("e7e5").unwrap(), "e5");
+        assert_eq!(game.turn(), "white");
+        assert!(matches!(
+            game.make_move("Ke3"),
+            Err(GameError::IllegalMove { .. })
+        ));
+        assert!(game
+            .fen()
+            .starts_with("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 "));
+    }
+
+    #[test]
+    fn test_best_move_finds_a_mate_in_one() {
+        // Scholar's mate: Qxf7#
+        let mut game =
+            Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4")
+                .unwrap();
+        let best_move = game.best_move(2, None).unwrap();
+        assert_eq!(best_move, "Qxf7#");
+        game.make_move(&best_move).unwrap();
+        assert_eq!(game.outcome().as_deref(), Some("checkmate"));
+    }
+
+    #[test]
+    fn test_invalid_fen_is_an_error() {
+        assert!(matches!(
+            Game::from_fen("not a position"),
+            Err(GameError::InvalidFen { .. })
+        ));
+    }
+}